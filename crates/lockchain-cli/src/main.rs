@@ -1,103 +1,251 @@
 //! Lockchain command-line interface: provisioning, maintenance, and unlock tooling.
 
 use anyhow::{bail, ensure, Context, Result};
-use clap::{Parser, Subcommand};
+use chrono::{DateTime, Utc};
+use clap::{ArgGroup, Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use crossterm::{
+    cursor::MoveTo,
+    execute,
+    style::Stylize,
+    terminal::{Clear, ClearType},
+};
+use indicatif::{ProgressBar, ProgressStyle};
 use lockchain_core::{
-    config::Policy,
-    keyfile::write_raw_key_file,
+    audit::{self, AuditEvent, AuditFilter, AuditRecord, AuditResult},
+    config::{dataset_pattern_matches, is_dataset_pattern, Policy},
+    control::{
+        self, ControlCall, ControlClient, DaemonEvent, DaemonEventKind, KeepAliveReport,
+        ReloadReport,
+    },
+    kdf,
+    keyfile::{self, write_raw_key_file},
     logging,
-    provider::{DatasetKeyDescriptor, KeyState},
-    workflow::{self, ForgeMode, ProvisionOptions, WorkflowLevel, WorkflowReport},
-    LockchainConfig, LockchainService, UnlockOptions,
+    provider::{DatasetKeyDescriptor, KeyState, ZfsProvider},
+    seal, units,
+    workflow::{
+        self, DoctorMode, ForgeMode, ProvisionOptions, RotateOptions, SelfTestOptions,
+        WorkflowEvent, WorkflowLevel, WorkflowReport,
+    },
+    DatasetStatus, LockOptions, LockReport, LockchainConfig, LockchainError, LockchainResult,
+    LockchainService, SecretBuffer, UnlockOptions, UnlockReport,
 };
 use lockchain_zfs::SystemZfsProvider;
 use log::warn;
-use rpassword::prompt_password;
+use prompt::Prompter;
 use schemars::schema_for;
-use serde_json::to_string_pretty;
+use serde::Serialize;
+use serde_json::{to_string_pretty, Value};
+use std::collections::HashMap;
 use std::fs;
-use std::io::{self, Write};
-use std::path::PathBuf;
+use std::io::{self, IsTerminal, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
 
+mod prompt;
 mod tui;
+mod wizard;
+
+/// Exit statuses documented in `--help`; see `LockchainError::exit_code`.
+const EXIT_CODES: &str = "\
+Exit codes:
+  0  success
+  1  any other failure
+  2  configuration unreadable or invalid (LC1001-LC1100), or bad command-line usage
+  3  dataset not declared in policy (LC1200)
+  4  no key source configured for the dataset (LC1201)
+  5  ZFS provider error (LC2000)
+  6  unlock retries exhausted (LC3000)";
 
 /// Top-level command-line options shared by every subcommand.
 #[derive(Parser, Debug)]
 #[command(
     name = "lockchain",
     version,
-    about = "Key management utilities for Lockchain ZFS deployments."
+    about = "Key management utilities for Lockchain ZFS deployments.",
+    after_help = EXIT_CODES
 )]
 struct Cli {
     /// Path to the Lockchain configuration file.
     #[arg(short, long, default_value = "/etc/lockchain-zfs.toml")]
     config: PathBuf,
 
+    /// Apply the `[profile.<name>]` overrides from the configuration.
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Answer confirmations with yes and never prompt; fail instead of waiting for input.
+    #[arg(short = 'y', long, visible_alias = "non-interactive", global = true)]
+    yes: bool,
+
+    /// Print results as text, or as JSON/YAML for scripts and monitoring.
+    #[arg(
+        long = "output",
+        id = "format",
+        value_enum,
+        global = true,
+        default_value_t = OutputFormat::Text
+    )]
+    output: OutputFormat,
+
+    /// Include debug detail (commands run, timings) in workflow reports.
+    #[arg(short, long, global = true, conflicts_with = "quiet")]
+    verbose: bool,
+
+    /// Only show warnings, errors, and security notices in workflow reports.
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Rendering used for command results.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+    Yaml,
+}
+
+/// Which workflow events `-v` / `-q` let through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+impl Verbosity {
+    fn shows(self, level: WorkflowLevel) -> bool {
+        match self {
+            Verbosity::Quiet => matches!(
+                level,
+                WorkflowLevel::Warn | WorkflowLevel::Error | WorkflowLevel::Security
+            ),
+            Verbosity::Normal => level != WorkflowLevel::Debug,
+            Verbosity::Verbose => true,
+        }
+    }
+}
+
 /// Subcommands covering the full lifecycle of a Lockchain deployment.
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Provision a USB token with raw key material and refresh initramfs assets.
-    Init {
+    Init(InitArgs),
+
+    /// Replace the key on the inserted token and re-wrap its encryption roots.
+    RotateKey {
         /// Target dataset; defaults to the first entry in policy.datasets.
         dataset: Option<String>,
 
-        /// USB block device (e.g. /dev/sdb1). When omitted, autodetect via label/UUID.
+        /// Keep the retired key on the token as `<file>.old`.
         #[arg(long)]
-        device: Option<String>,
+        keep_old_token: bool,
 
-        /// Mountpoint used during provisioning.
+        /// Skip initramfs rebuild after rotating.
         #[arg(long)]
-        mount: Option<PathBuf>,
+        no_rebuild: bool,
 
-        /// Filename to write inside the mounted token (default: key.hex).
-        #[arg(long)]
-        filename: Option<String>,
+        /// Encrypt a copy of the new key to this age recipient.
+        #[arg(long, value_name = "RECIPIENT")]
+        escrow: Option<String>,
+    },
+
+    /// Install key material from a file, hex string, or stdin at the runtime key path.
+    #[command(group(ArgGroup::new("source").required(true).args(["file", "hex", "stdin"])))]
+    ImportKey {
+        /// Read the key from a file holding 32 raw bytes or 64 hex digits.
+        #[arg(long, value_name = "PATH")]
+        file: Option<PathBuf>,
+
+        /// Take the key as 64 hex digits (visible in the process list; prefer --stdin).
+        #[arg(long, value_name = "HEX")]
+        hex: Option<String>,
 
-        /// Optional fallback passphrase material to configure immediately.
+        /// Read the key from standard input.
         #[arg(long)]
-        passphrase: Option<String>,
+        stdin: bool,
 
-        /// Perform a non-destructive safety check instead of wiping the token.
+        /// Record the key's SHA-256 as usb.expected_sha256 and save the config.
         #[arg(long)]
-        safe: bool,
+        update_checksum: bool,
+    },
 
-        /// Force a wipe even in safe mode.
+    /// Measure PBKDF2 speed here and recommend fallback.passphrase_iters.
+    BenchmarkKdf {
+        /// Unlock time to aim for with the fallback passphrase.
+        #[arg(long, default_value = "1s", value_parser = parse_interval)]
+        target: Duration,
+
+        /// Re-derive the fallback mask with the recommended iterations and save.
         #[arg(long)]
-        force_wipe: bool,
+        write: bool,
+
+        /// Passphrase to re-derive with (prompted when omitted).
+        #[arg(long, requires = "write")]
+        passphrase: Option<String>,
+    },
 
-        /// Skip initramfs rebuild after provisioning.
+    /// Show the audit trail of unlocks, locks, break-glass, key rotations, and escrow.
+    History {
+        /// Only records for this dataset and its descendants.
         #[arg(long)]
-        no_rebuild: bool,
+        dataset: Option<String>,
+
+        /// Only records at or after an RFC 3339 time, or within an age such as `24h` or `7d`.
+        #[arg(long, value_name = "WHEN", value_parser = parse_since)]
+        since: Option<DateTime<Utc>>,
+
+        /// Only records of this kind: unlock, lock, breakglass, rotation, provision, or escrow.
+        #[arg(long, value_name = "EVENT")]
+        event: Option<AuditEvent>,
+
+        /// Check the tamper-evidence hash chain instead of listing records.
+        #[arg(long, conflicts_with_all = ["dataset", "since", "event"])]
+        verify: bool,
     },
 
     /// Run diagnostics and remediation to keep the environment healthy.
-    Doctor,
+    Doctor {
+        /// Only report problems and the repairs `--fix` would make (the default).
+        #[arg(long, conflicts_with = "fix")]
+        check_only: bool,
+
+        /// Apply permission fixes, checksum updates, and unit installs.
+        #[arg(long)]
+        fix: bool,
+
+        /// Also write a JSON report (events, unit states, journal excerpts, redacted config) here.
+        #[arg(long, value_name = "PATH")]
+        report: Option<PathBuf>,
+    },
 
     /// Unlock an encrypted dataset (and its descendants).
-    Unlock {
-        /// Target dataset; defaults to the first entry in policy.datasets.
-        dataset: Option<String>,
+    Unlock(UnlockArgs),
 
-        /// Require USB key material and skip fallback handling.
-        #[arg(long)]
-        strict_usb: bool,
+    /// Unload the key for a dataset's encryption root (or every managed dataset).
+    Lock {
+        /// Target dataset or pattern; asks which one when several are configured.
+        #[arg(conflicts_with = "all")]
+        dataset: Option<String>,
 
-        /// Provide a fallback passphrase directly on the command line.
+        /// Lock every dataset listed in policy.datasets.
         #[arg(long)]
-        passphrase: Option<String>,
+        all: bool,
 
-        /// Prompt interactively for the fallback passphrase.
-        #[arg(long)]
-        prompt_passphrase: bool,
+        /// Without a dataset, use the first policy entry instead of asking which one.
+        #[arg(long, conflicts_with_all = ["dataset", "all"])]
+        first: bool,
 
-        /// Provide raw key material via file (32-byte binary).
+        /// Unmount the datasets before unloading their keys.
         #[arg(long)]
-        key_file: Option<PathBuf>,
+        unmount: bool,
     },
 
     /// Perform a self-test using an ephemeral ZFS pool.
@@ -108,22 +256,54 @@ enum Commands {
         /// Require the USB token and skip fallback handling during the drill.
         #[arg(long)]
         strict_usb: bool,
+
+        /// Size of the file backing the scratch pool (e.g. `512MiB`, at least 64MiB).
+        #[arg(long, default_value = "256MiB", value_parser = parse_size)]
+        pool_size: u64,
+
+        /// Leave the scratch pool and its backing file in place if a step fails.
+        #[arg(long)]
+        keep_on_failure: bool,
+
+        /// Shorthand for `--output json`.
+        #[arg(long)]
+        json: bool,
     },
 
     /// Reinstall mount/unlock systemd units and ensure services are enabled.
     Repair,
 
-    /// Show keystatus information for a dataset (or all managed datasets).
+    /// Show keystatus and mount state for a dataset (or all managed datasets).
     Status {
-        /// Dataset to inspect; defaults to all configured datasets.
+        /// Dataset or pattern to inspect; defaults to all configured datasets.
         dataset: Option<String>,
+
+        /// Keep polling and redraw the table, highlighting lock/unlock transitions.
+        #[arg(long)]
+        watch: bool,
+
+        /// Delay between polls in watch mode (e.g. `2s`, `500ms`).
+        #[arg(long, default_value = "2s", value_parser = parse_interval, requires = "watch")]
+        interval: Duration,
+
+        /// Query the zfs/zpool commands on `user@host` over SSH.
+        #[arg(long, value_name = "USER@HOST")]
+        remote: Option<String>,
     },
 
-    /// List the managed datasets and their current key status.
+    /// List the managed datasets with key status, mount state, and key properties.
     ListKeys,
 
     /// Launch the interactive TUI unlocker.
-    Tui,
+    Tui {
+        /// How often the dataset list re-queries keystatus in the background.
+        #[arg(long, default_value = "5s", value_parser = parse_interval)]
+        refresh: Duration,
+
+        /// Only refresh the dataset list on `r` or after an action.
+        #[arg(long, conflicts_with = "refresh")]
+        no_auto_refresh: bool,
+    },
 
     /// Validate a configuration file or emit the config schema.
     Validate {
@@ -134,32 +314,393 @@ enum Commands {
         /// Output the JSON schema instead of validating a file.
         #[arg(long)]
         schema: bool,
+
+        /// Emit the validation issues as JSON (same as `--output json`).
+        #[arg(long)]
+        json: bool,
     },
 
     /// Derive the fallback key and write it to disk (emergency only).
-    Breakglass {
-        /// Dataset to target; defaults to the first entry in policy.datasets.
+    Breakglass(BreakglassArgs),
+
+    /// Inspect the USB token.
+    Token {
+        #[command(subcommand)]
+        action: TokenCommand,
+    },
+
+    /// Erase a token and reformat it as an empty LOCKCHAINKEY filesystem.
+    WipeToken {
+        /// Block device to wipe, e.g. /dev/sdb.
+        device: String,
+
+        /// Overwrite the whole device with random data this many times first.
+        #[arg(long, default_value_t = 0)]
+        passes: u32,
+
+        /// Wipe a disk that is neither removable nor hotplugged.
+        #[arg(long)]
+        allow_fixed_disk: bool,
+    },
+
+    /// Keep age-encrypted copies of the token key and restore from them.
+    Escrow {
+        #[command(subcommand)]
+        action: EscrowCommand,
+    },
+
+    /// Drive the running lockchain-daemon through its control socket.
+    Daemon {
+        /// Control socket to connect to (default: /run/lockchain/daemon.sock).
+        #[arg(long, value_name = "PATH")]
+        socket: Option<PathBuf>,
+
+        #[command(subcommand)]
+        action: DaemonCommand,
+    },
+
+    /// Print a shell completion script generated from this CLI.
+    Completions {
+        /// Shell to generate completions for.
+        shell: Shell,
+    },
+
+    /// Render the manual page (or one page per subcommand with --out-dir).
+    Man {
+        /// Write `lockchain.1` and `lockchain-<subcommand>.1` pages into this directory.
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+
+    /// Maintain the configuration file itself.
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+}
+
+/// Arguments of `lockchain init`.
+#[derive(Args, Debug)]
+struct InitArgs {
+    /// Target dataset; defaults to the first entry in policy.datasets.
+    dataset: Option<String>,
+
+    /// USB block device (e.g. /dev/sdb1). When omitted, autodetect via label/UUID.
+    #[arg(long)]
+    device: Option<String>,
+
+    /// Mountpoint used during provisioning.
+    #[arg(long)]
+    mount: Option<PathBuf>,
+
+    /// Filename to write inside the mounted token (default: lockchain.key).
+    #[arg(long)]
+    filename: Option<String>,
+
+    /// Optional fallback passphrase material to configure immediately.
+    #[arg(long)]
+    passphrase: Option<String>,
+
+    /// Read the fallback passphrase from the first line of standard input.
+    #[arg(long, conflicts_with = "passphrase")]
+    passphrase_stdin: bool,
+
+    /// Perform a non-destructive safety check instead of wiping the token.
+    #[arg(long)]
+    safe: bool,
+
+    /// Force a wipe even in safe mode.
+    #[arg(long)]
+    force_wipe: bool,
+
+    /// Skip initramfs rebuild after provisioning.
+    #[arg(long)]
+    no_rebuild: bool,
+
+    /// Print the device, partition, key path, and initramfs steps that
+    /// would run, without changing anything.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// Arguments of `lockchain unlock`.
+#[derive(Args, Debug)]
+struct UnlockArgs {
+    /// Target datasets or patterns (`tank/*`, `tank/vms/%`); asks which one when several are configured.
+    #[arg(conflicts_with = "all")]
+    datasets: Vec<String>,
+
+    /// Unlock every dataset listed in policy.datasets, once per encryption root.
+    #[arg(long)]
+    all: bool,
+
+    /// Require USB key material and skip fallback handling.
+    #[arg(long)]
+    strict_usb: bool,
+
+    /// Provide a fallback passphrase directly on the command line.
+    #[arg(long)]
+    passphrase: Option<String>,
+
+    /// Prompt interactively for the fallback passphrase.
+    #[arg(long)]
+    prompt_passphrase: bool,
+
+    /// Provide raw key material via file (32-byte binary).
+    #[arg(long)]
+    key_file: Option<PathBuf>,
+
+    /// Without a dataset, use the first policy entry instead of asking which one.
+    #[arg(long, conflicts_with_all = ["datasets", "all"])]
+    first: bool,
+
+    /// Run the zfs/zpool commands on `user@host` over SSH; the key travels inside the SSH channel.
+    #[arg(long, value_name = "USER@HOST")]
+    remote: Option<String>,
+}
+
+/// Arguments of `lockchain breakglass`.
+#[derive(Args, Debug)]
+struct BreakglassArgs {
+    /// Dataset to target; defaults to the first entry in policy.datasets.
+    dataset: Option<String>,
+
+    /// File path to write the derived key material to.
+    #[arg(short, long = "output-file", required_unless_present_any = ["stdout", "fifo"])]
+    output: Option<PathBuf>,
+
+    /// Write the raw key to standard output instead of a file; refused on a terminal.
+    #[arg(long, conflicts_with_all = ["output", "fifo"])]
+    stdout: bool,
+
+    /// Hand the key to the first reader of a named pipe at this path, creating it if needed; an existing pipe must be yours and closed to group and others.
+    #[arg(long, conflicts_with = "output")]
+    fifo: Option<PathBuf>,
+
+    /// With --output-file, shred the key file as soon as you press Enter.
+    #[arg(long, requires = "output")]
+    ephemeral: bool,
+
+    /// Provide the emergency passphrase directly.
+    #[arg(long)]
+    passphrase: Option<String>,
+
+    /// Read the emergency passphrase from the first line of standard input.
+    #[arg(long, conflicts_with = "passphrase")]
+    passphrase_stdin: bool,
+
+    /// Skip interactive confirmations (same as --yes).
+    #[arg(long)]
+    force: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum TokenCommand {
+    /// Mount the token read-only and check its key files against the config
+    /// and ZFS without writing anything.
+    Verify,
+}
+
+#[derive(Subcommand, Debug)]
+enum EscrowCommand {
+    /// Encrypt the dataset's key to one or more age recipients.
+    Export {
+        /// Dataset whose key to escrow; defaults to the first entry in policy.datasets.
+        #[arg(long)]
         dataset: Option<String>,
 
-        /// File path to write the derived key material to.
-        #[arg(short, long)]
-        output: PathBuf,
+        /// age recipient (`age1…` or an SSH public key); repeat for several.
+        #[arg(long, required = true)]
+        recipient: Vec<String>,
+
+        /// Escrow file to write (default: a timestamped file under /var/lib/lockchain/escrow).
+        #[arg(short, long = "output-file")]
+        output: Option<PathBuf>,
+    },
+
+    /// Decrypt an escrow file and install the key at the runtime key path.
+    Restore {
+        /// Escrow file written by `escrow export` or `rotate-key --escrow`.
+        #[arg(long, value_name = "PATH")]
+        input: PathBuf,
+
+        /// age identity file that can decrypt it.
+        #[arg(short, long, value_name = "PATH")]
+        identity: PathBuf,
+
+        /// Write the plaintext key here instead of installing it.
+        #[arg(short, long = "output-file")]
+        output: Option<PathBuf>,
 
-        /// Provide the emergency passphrase directly.
+        /// Dataset the key belongs to, for the audit trail; defaults to the
+        /// first entry in policy.datasets.
         #[arg(long)]
-        passphrase: Option<String>,
+        dataset: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum DaemonCommand {
+    /// Keystatus as the daemon sees it.
+    Status {
+        /// Dataset to inspect; defaults to all datasets in the daemon's config.
+        dataset: Option<String>,
+    },
+
+    /// Have the daemon unlock a dataset with its own key sources.
+    Unlock {
+        /// Target dataset; defaults to the first entry in the daemon's policy.datasets.
+        dataset: Option<String>,
+
+        /// Require the USB key; never fall back to the passphrase.
+        #[arg(long)]
+        strict_usb: bool,
+    },
+
+    /// Have the daemon unload a dataset's key.
+    Lock {
+        /// Target dataset; defaults to the first entry in the daemon's policy.datasets.
+        dataset: Option<String>,
+
+        /// Unmount the datasets before unloading their keys.
+        #[arg(long)]
+        unmount: bool,
+    },
+
+    /// Make the daemon re-read its configuration file.
+    Reload,
+
+    /// Tell the daemon someone is still around, restarting its
+    /// `[daemon.idle_lock]` clock and lifting an idle lock already in place.
+    KeepAlive,
+
+    /// What the daemon did recently: unlock attempts, locks, USB key changes,
+    /// health changes, and reloads.
+    Events {
+        /// Only this dataset and its descendants.
+        dataset: Option<String>,
+
+        /// Only entries at or after an RFC 3339 time, or within an age such as `1h`.
+        #[arg(long, value_name = "WHEN", value_parser = parse_since)]
+        since: Option<DateTime<Utc>>,
+
+        /// Only entries of this kind: unlock, lock, usb, health, or reload.
+        #[arg(long, value_name = "KIND")]
+        kind: Option<DaemonEventKind>,
+
+        /// How many entries to show (default 50, or every one with `--since`).
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// List the newest records of the daemon's audit trail instead.
+        #[arg(long, conflicts_with_all = ["since", "kind"])]
+        audit: bool,
+    },
+}
 
-        /// Skip interactive confirmations.
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Encrypt fallback secrets in place with the host's seal key.
+    Seal,
+
+    /// Interactively detect datasets and write a starting configuration.
+    Init {
+        /// Overwrite an existing configuration file.
         #[arg(long)]
         force: bool,
     },
+
+    /// Show the effective settings and which layer (file, drop-in, profile,
+    /// env, or default) each one came from.
+    Diff {
+        /// Only list settings that differ from the base file.
+        #[arg(long)]
+        changed: bool,
+    },
+
+    /// Print the effective value of one setting, e.g. `usb.device_label`.
+    Get {
+        /// Dotted key.
+        key: String,
+    },
+
+    /// Change one setting in the base file after validating the result.
+    Set {
+        /// Dotted key, e.g. `usb.device_label`.
+        key: String,
+
+        /// New value as a TOML literal (`3`, `true`, `["tank/a"]`); anything
+        /// else is taken as a string.
+        value: String,
+    },
 }
 
 /// Entry point: parse arguments and surface errors with an exit code.
 fn main() {
     if let Err(err) = run() {
         eprintln!("error: {err}");
-        std::process::exit(1);
+        std::process::exit(exit_code(&err));
+    }
+}
+
+/// Exit status for `err`, taken from the first `LockchainError` in its chain.
+///
+/// Any failure to load the configuration counts as a configuration error,
+/// including an unreadable file.
+fn exit_code(err: &anyhow::Error) -> i32 {
+    if err.downcast_ref::<ConfigLoadFailed>().is_some() {
+        return 2;
+    }
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<LockchainError>())
+        .map_or(1, LockchainError::exit_code)
+}
+
+/// Context attached to configuration load failures.
+#[derive(Debug)]
+struct ConfigLoadFailed(PathBuf);
+
+impl std::fmt::Display for ConfigLoadFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "failed to load configuration from {}", self.0.display())
+    }
+}
+
+/// Load the configuration with the selected profile applied.
+fn load_config(path: &Path, profile: Option<&str>) -> Result<LockchainConfig> {
+    LockchainConfig::load_profile(path, profile).context(ConfigLoadFailed(path.to_path_buf()))
+}
+
+/// Global options every subcommand runs with.
+struct Globals {
+    config_path: PathBuf,
+    profile: Option<String>,
+    output: OutputFormat,
+    verbosity: Verbosity,
+    prompter: Prompter,
+}
+
+impl Globals {
+    fn from_cli(cli: &Cli) -> Self {
+        let verbosity = if cli.verbose {
+            Verbosity::Verbose
+        } else if cli.quiet {
+            Verbosity::Quiet
+        } else {
+            Verbosity::Normal
+        };
+        Self {
+            config_path: cli.config.clone(),
+            profile: cli.profile.clone(),
+            output: cli.output,
+            verbosity,
+            prompter: Prompter::new(cli.yes),
+        }
+    }
+
+    /// Load the configuration with the selected profile applied.
+    fn config(&self) -> Result<LockchainConfig> {
+        load_config(&self.config_path, self.profile.as_deref())
     }
 }
 
@@ -167,310 +708,1557 @@ fn main() {
 fn run() -> Result<()> {
     logging::init("info");
     let cli = Cli::parse();
-    let config_path = cli.config.clone();
+    let globals = Globals::from_cli(&cli);
 
     match cli.command {
-        Commands::Init {
+        Commands::Token {
+            action: TokenCommand::Verify,
+        } => verify_token(&globals),
+        Commands::Daemon { socket, action } => daemon(&globals, socket, action),
+        Commands::Completions { shell } => completions(shell),
+        Commands::Man { out_dir } => man(out_dir),
+        Commands::Init(args) => init(&globals, args),
+        Commands::RotateKey {
             dataset,
-            device,
-            mount,
-            filename,
-            passphrase,
-            safe,
-            force_wipe,
+            keep_old_token,
             no_rebuild,
-        } => {
-            let mut config = LockchainConfig::load(&config_path).with_context(|| {
-                format!(
-                    "failed to load configuration from {}",
-                    config_path.display()
-                )
-            })?;
-            let provider = SystemZfsProvider::from_config(&config)?;
-            let target = resolve_dataset(dataset, &config.policy)?;
-            let mut options = ProvisionOptions::default();
-            options.usb_device = device;
-            options.mountpoint = mount;
-            options.key_filename = filename;
-            options.passphrase = passphrase;
-            options.force_wipe = force_wipe;
-            options.rebuild_initramfs = !no_rebuild;
-            let mode = if safe {
-                ForgeMode::Safe
-            } else {
-                ForgeMode::Standard
-            };
-            let report = workflow::forge_key(&mut config, &provider, &target, mode, options)
-                .map_err(anyhow::Error::new)?;
-            print_report(report);
-            return Ok(());
-        }
-        Commands::Doctor => {
-            let config = LockchainConfig::load(&config_path).with_context(|| {
-                format!(
-                    "failed to load configuration from {}",
-                    config_path.display()
-                )
-            })?;
-            let provider = SystemZfsProvider::from_config(&config)?;
-            let report = workflow::doctor(&config, provider).map_err(anyhow::Error::new)?;
-            print_report(report);
-            return Ok(());
-        }
-        Commands::Validate { file, schema } => {
-            if schema {
-                let schema = schema_for!(LockchainConfig);
-                println!("{}", to_string_pretty(&schema)?);
-                return Ok(());
-            }
+            escrow,
+        } => rotate_key(&globals, dataset, keep_old_token, no_rebuild, escrow),
+        Commands::ImportKey {
+            file,
+            hex,
+            stdin: _,
+            update_checksum,
+        } => import_key(&globals, file, hex, update_checksum),
+        Commands::BenchmarkKdf {
+            target,
+            write,
+            passphrase,
+        } => benchmark_kdf(&globals, target, write, passphrase),
+        Commands::WipeToken {
+            device,
+            passes,
+            allow_fixed_disk,
+        } => wipe_token(&globals, device, passes, allow_fixed_disk),
+        Commands::Escrow { action } => match action {
+            EscrowCommand::Export {
+                dataset,
+                recipient,
+                output,
+            } => escrow_export(&globals, dataset, recipient, output),
+            EscrowCommand::Restore {
+                input,
+                identity,
+                output,
+                dataset,
+            } => escrow_restore(&globals, input, identity, output, dataset),
+        },
+        Commands::History {
+            dataset,
+            since,
+            event,
+            verify,
+        } => history(&globals, dataset, since, event, verify),
+        Commands::Doctor {
+            check_only,
+            fix,
+            report,
+        } => doctor(&globals, check_only, fix, report),
+        Commands::Validate { file, schema, json } => validate(&globals, file, schema, json),
+        Commands::Breakglass(args) => breakglass(&globals, args),
+        Commands::Config { action } => match action {
+            ConfigCommand::Init { force } => wizard::run(&globals.config_path, force),
+            ConfigCommand::Seal => config_seal(&globals),
+            ConfigCommand::Diff { changed } => config_diff(&globals, changed),
+            ConfigCommand::Get { key } => config_get(&globals, key),
+            ConfigCommand::Set { key, value } => config_set(&globals, key, value),
+        },
+        Commands::SelfTest {
+            dataset,
+            strict_usb,
+            pool_size,
+            keep_on_failure,
+            json,
+        } => self_test(
+            &globals,
+            dataset,
+            strict_usb,
+            pool_size,
+            keep_on_failure,
+            json,
+        ),
+        Commands::Repair => repair(&globals),
+        Commands::Unlock(args) => unlock(&globals, args),
+        Commands::Lock {
+            dataset,
+            all,
+            first,
+            unmount,
+        } => lock(&globals, dataset, all, first, unmount),
+        Commands::Status {
+            dataset,
+            watch,
+            interval,
+            remote,
+        } => status(&globals, dataset, watch, interval, remote),
+        Commands::ListKeys => list_keys(&globals),
+        Commands::Tui {
+            refresh,
+            no_auto_refresh,
+        } => launch_tui(&globals, refresh, no_auto_refresh),
+    }
+}
 
-            let cfg = LockchainConfig::load(&file)
-                .with_context(|| format!("failed to load configuration from {}", file.display()))?;
+/// Check the inserted token against the configured key.
+fn verify_token(globals: &Globals) -> Result<()> {
+    let config = globals.config()?;
+    let provider = SystemZfsProvider::from_config(&config)?;
+    let report = workflow::verify_token(&config, &provider).map_err(anyhow::Error::new)?;
+    let failed = report
+        .events
+        .iter()
+        .any(|event| event.level == WorkflowLevel::Error);
+    print_report(report, globals.output, globals.verbosity)?;
+    if failed {
+        bail!("token verification failed");
+    }
+    Ok(())
+}
 
-            let issues = cfg.validate();
-            if issues.is_empty() {
-                println!(
-                    "Configuration valid ({} datasets).",
-                    cfg.policy.datasets.len()
-                );
-            } else {
-                eprintln!("Configuration validation failed:");
-                for issue in issues {
-                    eprintln!("  - {issue}");
-                }
-                std::process::exit(1);
+/// Send one request to the running daemon and print its answer.
+fn daemon(globals: &Globals, socket: Option<PathBuf>, action: DaemonCommand) -> Result<()> {
+    let socket = socket.unwrap_or_else(control::socket_path);
+    let mut client = ControlClient::connect(&socket)?;
+    match action {
+        DaemonCommand::Status { dataset } => {
+            let statuses: Vec<DatasetStatus> = client.call(ControlCall::Status { dataset })?;
+            if !emit_structured(globals.output, &statuses)? {
+                print_statuses(statuses);
             }
-            return Ok(());
         }
-        Commands::Breakglass {
+        DaemonCommand::Unlock {
             dataset,
-            output,
-            passphrase,
-            force,
+            strict_usb,
         } => {
-            let config = Arc::new(LockchainConfig::load(&config_path).with_context(|| {
-                format!(
-                    "failed to load configuration from {}",
-                    config_path.display()
-                )
-            })?);
-            let provider = SystemZfsProvider::from_config(&config)?;
-            let service = LockchainService::new(config.clone(), provider);
-
-            let target = resolve_dataset(dataset, &config.policy)?;
-            if !config.fallback.enabled {
-                bail!("fallback recovery is not enabled in this configuration");
+            let report: UnlockReport = client.call(ControlCall::Unlock {
+                dataset,
+                strict_usb,
+            })?;
+            if !emit_structured(globals.output, &report)? {
+                print_unlock_report(&report);
             }
-            if config.fallback.passphrase_salt.is_none() || config.fallback.passphrase_xor.is_none()
-            {
-                bail!("fallback configuration is incomplete (salt/xor missing)");
+        }
+        DaemonCommand::Lock { dataset, unmount } => {
+            let shown = dataset.as_deref().unwrap_or("the daemon's default dataset");
+            let action = if unmount { "Unmount and lock" } else { "Lock" };
+            let confirmed = globals.prompter.confirm(
+                &[
+                    format!("{action} {shown} through the daemon?"),
+                    "Data stays unreadable until the next unlock.".to_string(),
+                ],
+                "yes",
+            )?;
+            if !confirmed {
+                eprintln!("Lock aborted.");
+                return Ok(());
             }
-
-            if !force {
-                println!("*** BREAK-GLASS RECOVERY ***");
+            let report: LockReport = client.call(ControlCall::Lock { dataset, unmount })?;
+            if !emit_structured(globals.output, &report)? {
+                print_lock_report(&report);
+            }
+        }
+        DaemonCommand::Reload => {
+            let report: ReloadReport = client.call(ControlCall::Reload)?;
+            if !emit_structured(globals.output, &report)? {
                 println!(
-                    "This will derive the raw key for dataset `{}` and write it to {}.",
-                    target,
-                    output.display()
+                    "Daemon reloaded {} ({} settings changed).",
+                    report.config_path.display(),
+                    report.changes.len()
                 );
-                println!("Type the dataset name to continue or press Enter to abort:");
-                print!("> ");
-                io::stdout().flush().ok();
-                let mut confirm_dataset = String::new();
-                io::stdin().read_line(&mut confirm_dataset)?;
-                if confirm_dataset.trim() != target {
-                    println!("Break-glass aborted.");
-                    return Ok(());
+                for change in &report.changes {
+                    println!("  {change}");
                 }
-
-                println!("Type BREAKGLASS to confirm this emergency action:");
-                print!("> ");
-                io::stdout().flush().ok();
-                let mut confirm_phrase = String::new();
-                io::stdin().read_line(&mut confirm_phrase)?;
-                if confirm_phrase.trim() != "BREAKGLASS" {
-                    println!("Break-glass aborted.");
-                    return Ok(());
+            }
+        }
+        DaemonCommand::KeepAlive => {
+            let report: KeepAliveReport = client.call(ControlCall::KeepAlive)?;
+            if !emit_structured(globals.output, &report)? {
+                if report.resumed {
+                    println!("Idle lock lifted; the daemon unlocks datasets again.");
+                }
+                match &report.lock_at {
+                    Some(lock_at) => {
+                        println!("Datasets idle-lock at {lock_at} unless kept alive again.")
+                    }
+                    None => println!("The daemon has no idle lock configured."),
                 }
             }
-
-            let passphrase = match passphrase {
-                Some(p) => p,
-                None => prompt_password(format!("Emergency passphrase for {target}: "))?,
-            };
-
-            let key = service.derive_fallback_key(passphrase.as_bytes())?;
-            write_raw_key_file(&output, &key)?;
-
-            warn!(
-                "[LC4000] break-glass recovery invoked for dataset {target}, output {}",
-                output.display()
-            );
-            println!(
-                "Emergency key material written to {} (permissions set to 0400). Remember to securely delete this file when finished.",
-                output.display()
-            );
-            return Ok(());
         }
-        Commands::SelfTest {
+        DaemonCommand::Events {
             dataset,
-            strict_usb,
+            audit: true,
+            limit,
+            ..
         } => {
-            let config = LockchainConfig::load(&config_path).with_context(|| {
-                format!(
-                    "failed to load configuration from {}",
-                    config_path.display()
-                )
-            })?;
-            let provider = SystemZfsProvider::from_config(&config)?;
-            let target = resolve_dataset(dataset, &config.policy)?;
-            let report = workflow::self_test(&config, provider, &target, strict_usb)
-                .map_err(anyhow::Error::new)?;
-            print_report(report);
-            return Ok(());
-        }
-        Commands::Repair => {
-            let config = LockchainConfig::load(&config_path).with_context(|| {
-                format!(
-                    "failed to load configuration from {}",
-                    config_path.display()
-                )
-            })?;
-            let report = workflow::repair_environment(&config).map_err(anyhow::Error::new)?;
-            print_report(report);
-            return Ok(());
+            let limit = limit.unwrap_or(control::DEFAULT_EVENT_LIMIT);
+            let records: Vec<AuditRecord> =
+                client.call(ControlCall::RecentEvents { dataset, limit })?;
+            if !emit_structured(globals.output, &records)? {
+                if records.is_empty() {
+                    println!("The daemon's audit trail has no matching records.");
+                } else {
+                    print_history_table(&records.iter().collect::<Vec<_>>());
+                }
+            }
         }
-        Commands::Unlock {
+        DaemonCommand::Events {
             dataset,
-            strict_usb,
-            passphrase,
-            prompt_passphrase,
-            key_file,
+            since,
+            kind,
+            limit,
+            audit: false,
         } => {
-            let config = Arc::new(LockchainConfig::load(&config_path).with_context(|| {
-                format!(
-                    "failed to load configuration from {}",
-                    config_path.display()
-                )
-            })?);
-            let provider = SystemZfsProvider::from_config(&config)?;
-            let service = LockchainService::new(config.clone(), provider);
-            let target = resolve_dataset(dataset, &config.policy)?;
-            let mut options = UnlockOptions::default();
-            options.strict_usb = strict_usb;
-
-            if let Some(path) = key_file {
-                let key_bytes =
-                    fs::read(&path).with_context(|| format!("read key file {}", path.display()))?;
-                ensure!(
-                    key_bytes.len() == 32,
-                    "expected a 32-byte raw key in {}, found {} bytes",
-                    path.display(),
-                    key_bytes.len()
-                );
-                options.key_override = Some(key_bytes);
-            }
-
-            if let Some(pass) = passphrase {
-                options.fallback_passphrase = Some(pass);
-            } else if prompt_passphrase {
-                let prompt = format!("Fallback passphrase for {}", target);
-                let value = prompt_password(prompt)?;
-                options.fallback_passphrase = Some(value);
+            let limit = limit.or(since.is_none().then_some(control::DEFAULT_JOURNAL_LIMIT));
+            let events: Vec<DaemonEvent> = client.call(ControlCall::Events {
+                since: since.map(|since| since.to_rfc3339()),
+                dataset,
+                kind,
+                limit,
+            })?;
+            if !emit_structured(globals.output, &events)? {
+                if events.is_empty() {
+                    println!("The daemon's event journal has no matching entries.");
+                } else {
+                    print_daemon_events(&events);
+                }
             }
+        }
+    }
+    Ok(())
+}
 
-            let report = service.unlock_with_retry(&target, options)?;
-            if report.already_unlocked {
-                println!(
-                    "Dataset {} (root {}) already has an available key.",
-                    target, report.encryption_root
-                );
+/// Print the completion script for `shell`.
+fn completions(shell: Shell) -> Result<()> {
+    clap_complete::generate(shell, &mut Cli::command(), "lockchain", &mut io::stdout());
+    Ok(())
+}
+
+/// Render the manual page, or one page per subcommand into `out_dir`.
+fn man(out_dir: Option<PathBuf>) -> Result<()> {
+    let command = Cli::command();
+    match out_dir {
+        Some(dir) => {
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+            clap_mangen::generate_to(command, &dir)
+                .with_context(|| format!("failed to write pages to {}", dir.display()))?;
+            println!("Wrote manual pages to {}.", dir.display());
+        }
+        None => clap_mangen::Man::new(command).render(&mut io::stdout())?,
+    }
+    Ok(())
+}
+
+/// Provision a token for a dataset, or print the plan with `--dry-run`.
+fn init(globals: &Globals, args: InitArgs) -> Result<()> {
+    let InitArgs {
+        dataset,
+        device,
+        mount,
+        filename,
+        passphrase,
+        passphrase_stdin,
+        safe,
+        force_wipe,
+        no_rebuild,
+        dry_run,
+    } = args;
+    let passphrase = if passphrase_stdin {
+        Some(read_stdin_secret()?)
+    } else {
+        passphrase
+    };
+    let mut config = globals.config()?;
+    let provider = SystemZfsProvider::from_config(&config)?;
+    let target = resolve_dataset(dataset, &config.policy)?;
+    let options = ProvisionOptions {
+        usb_device: device,
+        mountpoint: mount,
+        key_filename: filename,
+        passphrase,
+        force_wipe,
+        rebuild_initramfs: !no_rebuild,
+    };
+    let mode = if safe {
+        ForgeMode::Safe
+    } else {
+        ForgeMode::Standard
+    };
+    if dry_run {
+        let plan = workflow::plan_forge(&config, &provider, &target, mode, &options)?;
+        if emit_structured(globals.output, &plan)? {
+            return Ok(());
+        }
+        println!("Provisioning plan for {target} (dry run, nothing changed):");
+        for (index, step) in plan.steps().iter().enumerate() {
+            println!("  {}. {step}", index + 1);
+        }
+        return Ok(());
+    }
+    if !safe || force_wipe {
+        let token = options
+            .usb_device
+            .as_deref()
+            .unwrap_or("the configured USB token");
+        let confirmed = globals.prompter.confirm(
+            &[format!(
+                "Wipe {token} and write new key material for {target}? Existing key files on it are destroyed."
+            )],
+            "yes",
+        )?;
+        if !confirmed {
+            eprintln!("Init aborted.");
+            return Ok(());
+        }
+    }
+    let label = format!("Forging key for {target}");
+    run_with_progress(&label, globals.output, globals.verbosity, |on_event| {
+        workflow::forge_key_observed(&mut config, &provider, &target, mode, options, on_event)
+    })
+}
+
+/// Replace the token key and re-wrap the roots that share it.
+fn rotate_key(
+    globals: &Globals,
+    dataset: Option<String>,
+    keep_old_token: bool,
+    no_rebuild: bool,
+    escrow: Option<String>,
+) -> Result<()> {
+    let mut config = globals.config()?;
+    let provider = SystemZfsProvider::from_config(&config)?;
+    let target = resolve_dataset(dataset, &config.policy)?;
+    let confirmed = globals.prompter.confirm(
+        &[format!(
+            "Replace the key for {target} and re-wrap every encryption root sharing it?"
+        )],
+        "yes",
+    )?;
+    if !confirmed {
+        eprintln!("Key rotation aborted.");
+        return Ok(());
+    }
+    let options = RotateOptions {
+        keep_old_token,
+        rebuild_initramfs: !no_rebuild,
+        escrow,
+        ..RotateOptions::default()
+    };
+    let report = workflow::rotate_key(&mut config, &provider, &target, options)
+        .map_err(anyhow::Error::new)?;
+    let failed = report
+        .events
+        .iter()
+        .any(|event| event.level == WorkflowLevel::Error);
+    print_report(report, globals.output, globals.verbosity)?;
+    if failed {
+        bail!("key rotation for {target} finished with errors");
+    }
+    Ok(())
+}
+
+/// Install key material from a file, hex string, or stdin.
+fn import_key(
+    globals: &Globals,
+    file: Option<PathBuf>,
+    hex: Option<String>,
+    update_checksum: bool,
+) -> Result<()> {
+    let mut config = globals.config()?;
+    let target = config.key_hex_path();
+    if target.exists() {
+        let confirmed = globals.prompter.confirm(
+            &[format!("Replace the existing key at {}?", target.display())],
+            "yes",
+        )?;
+        if !confirmed {
+            eprintln!("Key import aborted.");
+            return Ok(());
+        }
+    }
+    let (material, origin) = if let Some(path) = file {
+        let bytes =
+            fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        (bytes, path)
+    } else if let Some(text) = hex {
+        (text.into_bytes(), PathBuf::from("<hex>"))
+    } else {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)?;
+        (bytes, PathBuf::from("<stdin>"))
+    };
+    let report = workflow::import_key(&mut config, &material, &origin, update_checksum)
+        .map_err(anyhow::Error::new)?;
+    print_report(report, globals.output, globals.verbosity)?;
+    Ok(())
+}
+
+/// Benchmark PBKDF2 and, with `--write`, re-derive the fallback mask.
+fn benchmark_kdf(
+    globals: &Globals,
+    target: Duration,
+    write: bool,
+    passphrase: Option<String>,
+) -> Result<()> {
+    let mut config = globals.config()?;
+    let bench = kdf::benchmark_pbkdf2(target, config.fallback.passphrase_iters);
+    if !write {
+        if emit_structured(globals.output, &bench)? {
+            return Ok(());
+        }
+        print_benchmark(&bench);
+        return Ok(());
+    }
+    let passphrase = match passphrase {
+        Some(value) => value,
+        None => globals.prompter.secret("Fallback passphrase")?,
+    };
+    kdf::retune_fallback(&mut config, passphrase.as_bytes(), bench.recommended_iters)?;
+    config.save()?;
+    if emit_structured(globals.output, &bench)? {
+        return Ok(());
+    }
+    print_benchmark(&bench);
+    println!(
+        "Re-derived the fallback mask with {} iterations in {}.",
+        bench.recommended_iters,
+        globals.config_path.display()
+    );
+    Ok(())
+}
+
+/// Erase `device` and reformat it as an empty token.
+fn wipe_token(
+    globals: &Globals,
+    device: String,
+    passes: u32,
+    allow_fixed_disk: bool,
+) -> Result<()> {
+    let mut warning = vec![format!(
+        "Everything on {device} will be destroyed and replaced by an empty LOCKCHAINKEY filesystem."
+    )];
+    if passes > 0 {
+        warning.push(format!(
+            "The whole device is overwritten {passes} time(s) first; large devices take a while."
+        ));
+    }
+    if !globals.prompter.confirm(&warning, &device)? {
+        eprintln!("Token wipe aborted.");
+        return Ok(());
+    }
+    run_with_progress(
+        &format!("Wiping {device}"),
+        globals.output,
+        globals.verbosity,
+        |on_event| workflow::wipe_token_observed(&device, passes, allow_fixed_disk, on_event),
+    )
+}
+
+/// Encrypt the dataset's key to age recipients.
+fn escrow_export(
+    globals: &Globals,
+    dataset: Option<String>,
+    recipient: Vec<String>,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let config = globals.config()?;
+    let provider = SystemZfsProvider::from_config(&config)?;
+    let target = resolve_dataset(dataset, &config.policy)?;
+    let confirmed = globals.prompter.confirm(
+        &[format!(
+            "Export the key for {target} encrypted to {}?",
+            recipient.join(", ")
+        )],
+        &target,
+    )?;
+    if !confirmed {
+        eprintln!("Escrow export aborted.");
+        return Ok(());
+    }
+    let report = workflow::export_escrow(
+        &config,
+        &provider,
+        &target,
+        &recipient,
+        output.as_deref(),
+        Path::new(workflow::ESCROW_DIR),
+    )
+    .map_err(anyhow::Error::new)?;
+    print_report(report, globals.output, globals.verbosity)?;
+    Ok(())
+}
+
+/// Decrypt an escrowed key back to the runtime key path or `output`.
+fn escrow_restore(
+    globals: &Globals,
+    input: PathBuf,
+    identity: PathBuf,
+    output: Option<PathBuf>,
+    dataset: Option<String>,
+) -> Result<()> {
+    let mut config = globals.config()?;
+    let target = resolve_dataset(dataset, &config.policy)?;
+    let destination = output.clone().unwrap_or_else(|| config.key_hex_path());
+    let mut warning = vec![format!(
+        "Decrypt {} and write the plaintext key to {}?",
+        input.display(),
+        destination.display()
+    )];
+    if destination.exists() {
+        warning.push(format!(
+            "{} already exists and will be replaced.",
+            destination.display()
+        ));
+    }
+    if !globals.prompter.confirm(&warning, "yes")? {
+        eprintln!("Escrow restore aborted.");
+        return Ok(());
+    }
+    let report =
+        workflow::restore_escrow(&mut config, &target, &input, &identity, output.as_deref())
+            .map_err(anyhow::Error::new)?;
+    print_report(report, globals.output, globals.verbosity)?;
+    Ok(())
+}
+
+/// List the audit trail, or check its hash chain with `--verify`.
+fn history(
+    globals: &Globals,
+    dataset: Option<String>,
+    since: Option<DateTime<Utc>>,
+    event: Option<AuditEvent>,
+    verify: bool,
+) -> Result<()> {
+    let config = globals.config()?;
+    let path = config.audit.log_path();
+    let records = audit::read_records(&path)?;
+    if verify {
+        return verify_history(&path, &records, globals.output);
+    }
+    let filter = AuditFilter {
+        dataset,
+        event,
+        since,
+    };
+    let selected: Vec<&AuditRecord> = records
+        .iter()
+        .filter(|record| filter.matches(record))
+        .collect();
+    if !emit_structured(globals.output, &selected)? {
+        if selected.is_empty() {
+            println!("No matching audit records in {}.", path.display());
+            if !config.audit.enabled {
+                println!("Auditing is disabled; set audit.enabled = true to record operations.");
+            }
+        } else {
+            print_history_table(&selected);
+        }
+    }
+    Ok(())
+}
+
+/// Run diagnostics, repairing what they find only with `--fix`.
+fn doctor(
+    globals: &Globals,
+    check_only: bool,
+    fix: bool,
+    report_path: Option<PathBuf>,
+) -> Result<()> {
+    let config = globals.config()?;
+    let provider = SystemZfsProvider::from_config(&config)?;
+    // The flags conflict; `--check-only` only spells out the default.
+    let mode = if fix && !check_only {
+        DoctorMode::Fix
+    } else {
+        DoctorMode::CheckOnly
+    };
+    let full = workflow::doctor_report(&config, provider, mode).map_err(anyhow::Error::new)?;
+    if let Some(path) = report_path {
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&path)
+            .with_context(|| format!("write doctor report to {}", path.display()))?;
+        serde_json::to_writer_pretty(&mut file, &full)?;
+        file.write_all(b"\n")?;
+        eprintln!("Doctor report written to {}.", path.display());
+    }
+    print_report(full.report, globals.output, globals.verbosity)?;
+    Ok(())
+}
+
+/// Validate a configuration file, or print the config schema.
+fn validate(globals: &Globals, file: PathBuf, schema: bool, json: bool) -> Result<()> {
+    let format = if json {
+        OutputFormat::Json
+    } else {
+        globals.output
+    };
+    if schema {
+        let schema = schema_for!(LockchainConfig);
+        println!("{}", to_string_pretty(&schema)?);
+        return Ok(());
+    }
+
+    let cfg = load_config(&file, globals.profile.as_deref())?;
+
+    let issues = cfg.validate();
+    let failed = issues.iter().any(|issue| issue.is_error());
+    if !emit_structured(format, &issues)? {
+        for fragment in &cfg.fragments {
+            println!("Merged drop-in {}", fragment.display());
+        }
+        for issue in issues.iter().filter(|issue| !issue.is_error()) {
+            eprintln!("warning: {issue}");
+            if let Some(hint) = &issue.suggestion {
+                eprintln!("    hint: {hint}");
+            }
+        }
+        if failed {
+            eprintln!("Configuration validation failed:");
+            for issue in issues.iter().filter(|issue| issue.is_error()) {
+                eprintln!("  - {issue}");
+                if let Some(hint) = &issue.suggestion {
+                    eprintln!("    hint: {hint}");
+                }
+            }
+        } else {
+            println!(
+                "Configuration valid ({} datasets).",
+                cfg.policy.datasets.len()
+            );
+        }
+    }
+    ensure!(!failed, "{} is not a valid configuration", file.display());
+    Ok(())
+}
+
+/// Derive the fallback key and hand it out (emergency only).
+fn breakglass(globals: &Globals, args: BreakglassArgs) -> Result<()> {
+    let BreakglassArgs {
+        dataset,
+        output,
+        stdout,
+        fifo,
+        ephemeral,
+        passphrase,
+        passphrase_stdin,
+        force,
+    } = args;
+    let config = Arc::new(globals.config()?);
+    let provider = SystemZfsProvider::from_config(&config)?;
+    let service = LockchainService::new(config.clone(), provider);
+
+    let target = resolve_dataset(dataset, &config.policy)?;
+    if !config.fallback.enabled {
+        bail!("fallback recovery is not enabled in this configuration");
+    }
+    if config.fallback.passphrase_salt.is_none() || config.fallback.passphrase_xor.is_none() {
+        bail!("fallback configuration is incomplete (salt/xor missing)");
+    }
+    ensure!(
+        !stdout || !io::stdout().is_terminal(),
+        "refusing to print raw key material to a terminal; pipe --stdout into the consumer"
+    );
+    let destination = match (&output, &fifo) {
+        (Some(path), _) => path.display().to_string(),
+        (None, Some(path)) => format!("the named pipe {}", path.display()),
+        (None, None) => "standard output".to_string(),
+    };
+
+    let confirmer = if force {
+        Prompter::new(true)
+    } else {
+        globals.prompter
+    };
+    let confirmed = confirmer.confirm(
+        &[
+            "*** BREAK-GLASS RECOVERY ***".to_string(),
+            format!(
+                "This will derive the raw key for dataset `{}` and write it to {}.",
+                target, destination
+            ),
+            "Enter the dataset name to continue, or anything else to abort.".to_string(),
+        ],
+        &target,
+    )? && confirmer.confirm(
+        &["Confirm this emergency action.".to_string()],
+        "BREAKGLASS",
+    )?;
+    if !confirmed {
+        eprintln!("Break-glass aborted.");
+        return Ok(());
+    }
+
+    let passphrase = match passphrase {
+        Some(p) => p,
+        None if passphrase_stdin => read_stdin_secret()?,
+        None => globals
+            .prompter
+            .secret(&format!("Emergency passphrase for {target}"))?,
+    };
+
+    let key = service.break_glass(&target, passphrase.as_bytes())?;
+    warn!("[LC4000] break-glass recovery invoked for dataset {target}, output {destination}");
+    match (output, fifo) {
+        (Some(output), _) => {
+            write_raw_key_file(&output, &key)?;
+            if ephemeral {
+                let waited = globals.prompter.pause(&format!(
+                    "Emergency key material written to {} (permissions 0400). Press Enter once it has been used to shred it.",
+                    output.display()
+                ));
+                keyfile::shred_key_file(&output)?;
+                waited?;
+                eprintln!("Shredded {}.", output.display());
             } else {
                 println!(
-                    "Unlocked encryption root {} via dataset {}.",
-                    report.encryption_root, target
+                    "Emergency key material written to {} (permissions set to 0400). Remember to securely delete this file when finished.",
+                    output.display()
                 );
-                for ds in report.unlocked {
-                    println!("  - {ds}");
-                }
             }
         }
-        Commands::Status { dataset } => {
-            let config = Arc::new(LockchainConfig::load(&config_path).with_context(|| {
-                format!(
-                    "failed to load configuration from {}",
-                    config_path.display()
-                )
-            })?);
-            let provider = SystemZfsProvider::from_config(&config)?;
-            let service = LockchainService::new(config.clone(), provider);
-            let datasets = match dataset {
-                Some(ds) => vec![ds],
-                None => config.policy.datasets.clone(),
-            };
+        (None, Some(fifo)) => {
+            eprintln!("Waiting for a reader on {}…", fifo.display());
+            keyfile::write_key_to_fifo(&fifo, &key)?;
+            eprintln!(
+                "Emergency key material handed to the reader of {}.",
+                fifo.display()
+            );
+        }
+        (None, None) => {
+            let mut out = io::stdout().lock();
+            out.write_all(&key)?;
+            out.flush()?;
+            eprintln!("Emergency key material written to standard output.");
+        }
+    }
+    Ok(())
+}
 
-            for ds in datasets {
-                let status = service.status(&ds)?;
-                if status.root_locked {
-                    println!(
-                        "{} (root {}) is LOCKED.",
-                        status.dataset, status.encryption_root
-                    );
-                    if status.locked_descendants.is_empty() {
-                        println!("  No locked descendants reported.");
+/// Seal the fallback secrets in the config file.
+fn config_seal(globals: &Globals) -> Result<()> {
+    let mut config = globals.config()?;
+    if config.fallback.passphrase_salt.is_none() && config.fallback.passphrase_xor.is_none() {
+        println!(
+            "{} has no fallback secrets to seal.",
+            globals.config_path.display()
+        );
+        return Ok(());
+    }
+    if config.fallback.sealed {
+        println!(
+            "Fallback secrets in {} are already sealed.",
+            globals.config_path.display()
+        );
+        return Ok(());
+    }
+    config.seal_secrets()?;
+    config.save()?;
+    println!(
+        "Sealed fallback.passphrase_salt and fallback.passphrase_xor in {} with {}; copies of the config cannot be opened without that key, which only root and the config's group can read.",
+        globals.config_path.display(),
+        seal::seal_key_path().display()
+    );
+    Ok(())
+}
+
+/// Print the effective settings and where each came from.
+fn config_diff(globals: &Globals, changed: bool) -> Result<()> {
+    let config = globals.config()?;
+    let settings: Vec<_> = config
+        .effective_settings()?
+        .into_iter()
+        .filter(|setting| !changed || setting.overridden())
+        .collect();
+    if emit_structured(globals.output, &settings)? {
+        return Ok(());
+    }
+    match config.profile.as_ref() {
+        Some(active) => println!(
+            "Effective configuration for {} (profile {}):",
+            globals.config_path.display(),
+            active.name()
+        ),
+        None => println!(
+            "Effective configuration for {}:",
+            globals.config_path.display()
+        ),
+    }
+    let rows: Vec<String> = settings
+        .iter()
+        .map(|setting| format!("{} = {}", setting.field, setting.value))
+        .collect();
+    let width = rows.iter().map(String::len).max().unwrap_or(0);
+    for (row, setting) in rows.iter().zip(&settings) {
+        let marker = if setting.overridden() { '*' } else { ' ' };
+        let mut origin = setting.origin.to_string();
+        if let Some(on_disk) = &setting.on_disk {
+            origin.push_str(&format!(" (file: {on_disk})"));
+        }
+        println!("{marker} {row:<width$}  {origin}");
+    }
+    if settings.is_empty() {
+        println!("  Every setting comes from the file.");
+    }
+    Ok(())
+}
+
+/// Print the effective value of one setting.
+fn config_get(globals: &Globals, key: String) -> Result<()> {
+    let config = globals.config()?;
+    let value = config.setting(&key)?;
+    if emit_structured(globals.output, &value)? {
+        return Ok(());
+    }
+    match value {
+        Value::Null => {}
+        Value::String(text) => println!("{text}"),
+        other => println!("{other}"),
+    }
+    Ok(())
+}
+
+/// Change one setting in the base file.
+fn config_set(globals: &Globals, key: String, value: String) -> Result<()> {
+    let config = globals.config()?;
+    let updated = config.set_setting(&key, &value)?;
+    let setting = updated
+        .effective_settings()?
+        .into_iter()
+        .find(|setting| setting.field == key);
+    let written = setting
+        .as_ref()
+        .and_then(|setting| setting.on_disk.clone().or(Some(setting.value.clone())))
+        .unwrap_or(Value::String(value));
+    println!("Set {key} = {written} in {}", globals.config_path.display());
+    if let Some(setting) = setting.filter(|setting| setting.overridden()) {
+        eprintln!(
+            "warning: {} overrides {key}; the file value applies once it is removed",
+            setting.origin
+        );
+    }
+    Ok(())
+}
+
+/// Run the self-test drill on a scratch pool.
+fn self_test(
+    globals: &Globals,
+    dataset: Option<String>,
+    strict_usb: bool,
+    pool_size: u64,
+    keep_on_failure: bool,
+    json: bool,
+) -> Result<()> {
+    let config = globals.config()?;
+    let provider = SystemZfsProvider::from_config(&config)?;
+    let target = resolve_dataset(dataset, &config.policy)?;
+    let format = if json {
+        OutputFormat::Json
+    } else {
+        globals.output
+    };
+    let options = SelfTestOptions {
+        strict_usb,
+        pool_size,
+        keep_on_failure,
+    };
+    run_with_progress("Running self-test", format, globals.verbosity, |on_event| {
+        workflow::self_test_observed(&config, provider, &target, options, on_event)
+    })
+}
+
+/// Reinstall the systemd units and re-enable the services.
+fn repair(globals: &Globals) -> Result<()> {
+    let config = globals.config()?;
+    let report = workflow::repair_environment(&config).map_err(anyhow::Error::new)?;
+    print_report(report, globals.output, globals.verbosity)?;
+    Ok(())
+}
+
+/// Unlock the selected datasets, once per encryption root.
+fn unlock(globals: &Globals, args: UnlockArgs) -> Result<()> {
+    let UnlockArgs {
+        datasets,
+        all,
+        strict_usb,
+        passphrase,
+        prompt_passphrase,
+        key_file,
+        first,
+        remote,
+    } = args;
+    let config = Arc::new(globals.config()?);
+    let provider = zfs_provider(&config, remote.as_deref())?;
+    let datasets = expand_datasets(&config.policy, &provider, datasets)?;
+    let service = LockchainService::new(config.clone(), provider);
+    let single = !all && datasets.len() <= 1;
+    let groups = if all {
+        service
+            .status_all()?
+            .roots
+            .into_iter()
+            .map(|root| (root.encryption_root, root.datasets))
+            .collect()
+    } else if single {
+        let target = pick_dataset(
+            datasets.into_iter().next(),
+            &config.policy,
+            first,
+            globals.prompter,
+        )?;
+        vec![(String::new(), vec![target])]
+    } else {
+        let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+        for dataset in datasets {
+            let root = service.status(&dataset)?.encryption_root;
+            match groups.iter_mut().find(|(name, _)| *name == root) {
+                Some((_, members)) => members.push(dataset),
+                None => groups.push((root, vec![dataset])),
+            }
+        }
+        groups
+    };
+    let mut options = UnlockOptions {
+        strict_usb,
+        ..UnlockOptions::default()
+    };
+
+    if let Some(path) = key_file {
+        let key_bytes = Zeroizing::new(
+            fs::read(&path).with_context(|| format!("read key file {}", path.display()))?,
+        );
+        ensure!(
+            key_bytes.len() == 32,
+            "expected a 32-byte raw key in {}, found {} bytes",
+            path.display(),
+            key_bytes.len()
+        );
+        options.key_override = Some(SecretBuffer::from_slice(&key_bytes));
+    }
+
+    if let Some(pass) = passphrase {
+        options.fallback_passphrase = Some(pass);
+    } else if prompt_passphrase {
+        let prompt = if single {
+            format!("Fallback passphrase for {}", groups[0].1[0])
+        } else {
+            "Fallback passphrase".to_string()
+        };
+        let value = globals.prompter.secret(&prompt)?;
+        options.fallback_passphrase = Some(value);
+    }
+
+    // One unlock per encryption root; the other datasets share its key.
+    let mut reports = Vec::new();
+    let mut failures = Vec::new();
+    for (_, members) in &groups {
+        let target = &members[0];
+        let result = service.unlock_with_retry_observed(target, options.clone(), |retry| {
+            warn!(
+                "{target}: attempt {}/{} failed: {}; retrying in {:?}",
+                retry.attempt, retry.max_attempts, retry.error, retry.delay
+            );
+        });
+        match result {
+            Ok(report) => reports.push(report),
+            Err(err) if single => return Err(err.into()),
+            Err(err) => failures.push(format!("{target}: {err}")),
+        }
+    }
+
+    let structured = if single {
+        emit_structured(globals.output, &reports[0])?
+    } else {
+        emit_structured(globals.output, &reports)?
+    };
+    if !structured {
+        for report in &reports {
+            print_unlock_report(report);
+        }
+        if !single {
+            println!(
+                "Unlocked {} of {} encryption roots.",
+                reports.len(),
+                groups.len()
+            );
+        }
+    }
+    if !failures.is_empty() {
+        bail!("failed to unlock {}", failures.join("; "));
+    }
+    Ok(())
+}
+
+/// Unload the keys of the selected datasets.
+fn lock(
+    globals: &Globals,
+    dataset: Option<String>,
+    all: bool,
+    first: bool,
+    unmount: bool,
+) -> Result<()> {
+    let config = Arc::new(globals.config()?);
+    let provider = SystemZfsProvider::from_config(&config)?;
+    let multiple = all || dataset.as_deref().is_some_and(is_dataset_pattern);
+    let targets = match dataset {
+        _ if all => config.policy.datasets.clone(),
+        Some(arg) => expand_datasets(&config.policy, &provider, vec![arg])?,
+        None => vec![pick_dataset(None, &config.policy, first, globals.prompter)?],
+    };
+
+    let action = if unmount { "Unmount and lock" } else { "Lock" };
+    let confirmed = globals.prompter.confirm(
+        &[
+            format!("{action} {}?", targets.join(", ")),
+            "Data stays unreadable until the next unlock.".to_string(),
+        ],
+        "yes",
+    )?;
+    if !confirmed {
+        eprintln!("Lock aborted.");
+        return Ok(());
+    }
+
+    let service = LockchainService::new(config.clone(), provider);
+    let mut reports = Vec::new();
+    let mut failures = Vec::new();
+    for target in &targets {
+        match service.lock(target, LockOptions { unmount }) {
+            Ok(report) => reports.push(report),
+            Err(err) => failures.push(format!("{target}: {err}")),
+        }
+    }
+
+    let structured = if multiple {
+        emit_structured(globals.output, &reports)?
+    } else {
+        match reports.first() {
+            Some(report) => emit_structured(globals.output, report)?,
+            None => false,
+        }
+    };
+    if !structured {
+        for report in &reports {
+            print_lock_report(report);
+        }
+    }
+    if !failures.is_empty() {
+        bail!("failed to lock {}", failures.join("; "));
+    }
+    Ok(())
+}
+
+/// Print keystatus for the selected datasets, or keep redrawing it.
+fn status(
+    globals: &Globals,
+    dataset: Option<String>,
+    watch: bool,
+    interval: Duration,
+    remote: Option<String>,
+) -> Result<()> {
+    let config = Arc::new(globals.config()?);
+    let provider = zfs_provider(&config, remote.as_deref())?;
+    let datasets = match dataset {
+        Some(arg) => expand_datasets(&config.policy, &provider, vec![arg])?,
+        None => Vec::new(),
+    };
+    let service = LockchainService::new(config.clone(), provider);
+    if watch {
+        ensure!(
+            globals.output == OutputFormat::Text,
+            "--watch only supports text output"
+        );
+        return watch_status(&service, &config.policy, &datasets, interval);
+    }
+    let statuses = collect_statuses(&service, &config.policy, &datasets)?;
+    if !emit_structured(globals.output, &statuses)? {
+        print_statuses(statuses);
+    }
+    Ok(())
+}
+
+/// Print the managed datasets with their key properties.
+fn list_keys(globals: &Globals) -> Result<()> {
+    let config = Arc::new(globals.config()?);
+    let provider = SystemZfsProvider::from_config(&config)?;
+    let service = LockchainService::new(config.clone(), provider);
+    let snapshot = service.list_keys()?;
+    if !emit_structured(globals.output, &snapshot)? {
+        print_key_table(snapshot);
+    }
+    Ok(())
+}
+
+/// Start the TUI.
+fn launch_tui(globals: &Globals, refresh: Duration, no_auto_refresh: bool) -> Result<()> {
+    let config = Arc::new(globals.config()?);
+    let provider = SystemZfsProvider::from_config(&config)?;
+    tui::launch(config, provider, (!no_auto_refresh).then_some(refresh))?;
+    Ok(())
+}
+
+/// Provider for this host, or for `remote` (`user@host`) over SSH.
+fn zfs_provider(config: &LockchainConfig, remote: Option<&str>) -> Result<SystemZfsProvider> {
+    Ok(match remote {
+        Some(destination) => SystemZfsProvider::remote(config, destination)?,
+        None => SystemZfsProvider::from_config(config)?,
+    })
+}
+
+/// Keystatus for the selected datasets, or for every dataset in policy order.
+fn collect_statuses(
+    service: &LockchainService<SystemZfsProvider>,
+    policy: &Policy,
+    datasets: &[String],
+) -> Result<Vec<DatasetStatus>> {
+    if let [ds] = datasets {
+        return Ok(vec![service.status(ds)?]);
+    }
+    let estate = service.status_all()?;
+    let selected = if datasets.is_empty() {
+        policy.datasets.as_slice()
+    } else {
+        datasets
+    };
+    Ok(selected
+        .iter()
+        .filter_map(|ds| estate.dataset_status(ds))
+        .collect())
+}
+
+/// Resolve dataset arguments, expanding patterns against policy.datasets.
+///
+/// Literal names pass through unchanged. A pattern that selects nothing is an
+/// error, which also names matching encryption roots the policy does not list.
+fn expand_datasets<P: ZfsProvider>(
+    policy: &Policy,
+    provider: &P,
+    args: Vec<String>,
+) -> Result<Vec<String>> {
+    let mut resolved: Vec<String> = Vec::new();
+    for arg in args {
+        let matched = if is_dataset_pattern(&arg) {
+            policy.datasets_matching(&arg)
+        } else {
+            vec![arg.clone()]
+        };
+        if matched.is_empty() {
+            let unmanaged: Vec<String> = provider
+                .encryption_roots()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|root| dataset_pattern_matches(&arg, root))
+                .collect();
+            let mut message = format!("pattern `{arg}` matches no dataset in policy.datasets");
+            if !unmanaged.is_empty() {
+                message.push_str(&format!(
+                    "; {} exist on this system but are not configured",
+                    unmanaged.join(", ")
+                ));
+            }
+            return Err(
+                anyhow::Error::new(LockchainError::DatasetNotConfigured(arg)).context(message),
+            );
+        }
+        for dataset in matched {
+            if !resolved.contains(&dataset) {
+                resolved.push(dataset);
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+/// Redraw the status table every `interval` until interrupted.
+///
+/// Rows whose root changed state since the previous poll are highlighted,
+/// and every transition seen so far is listed under the table.
+fn watch_status(
+    service: &LockchainService<SystemZfsProvider>,
+    policy: &Policy,
+    datasets: &[String],
+    interval: Duration,
+) -> Result<()> {
+    let started = Instant::now();
+    let mut previous: HashMap<String, bool> = HashMap::new();
+    let mut transitions: Vec<String> = Vec::new();
+    let mut stdout = io::stdout();
+    loop {
+        let polled = collect_statuses(service, policy, datasets);
+        execute!(stdout, Clear(ClearType::All), MoveTo(0, 0))?;
+        println!(
+            "Every {}: lockchain status (Ctrl-C to quit, {}s elapsed)\n",
+            units::format_duration(interval),
+            started.elapsed().as_secs()
+        );
+        match polled {
+            Ok(statuses) => {
+                let width = statuses
+                    .iter()
+                    .map(|status| status.dataset.len())
+                    .chain(["DATASET".len()])
+                    .max()
+                    .unwrap_or_default();
+                let root_width = statuses
+                    .iter()
+                    .map(|status| status.encryption_root.len())
+                    .chain(["ROOT".len()])
+                    .max()
+                    .unwrap_or_default();
+                println!(
+                    "{:<width$}  {:<root_width$}  {:<9}  {:<7}  LOCKED DESCENDANTS",
+                    "DATASET", "ROOT", "STATE", "MOUNTED"
+                );
+                for status in &statuses {
+                    let state = if status.root_locked {
+                        "LOCKED"
                     } else {
-                        println!("  Locked descendants:");
-                        for child in status.locked_descendants {
-                            println!("    - {child}");
-                        }
+                        "available"
+                    };
+                    let changed = previous
+                        .insert(status.dataset.clone(), status.root_locked)
+                        .is_some_and(|was_locked| was_locked != status.root_locked);
+                    let cell = format!("{state:<9}");
+                    let cell = match (changed, status.root_locked) {
+                        (false, _) => cell.stylize(),
+                        (true, false) => cell.green().bold(),
+                        (true, true) => cell.red().bold(),
+                    };
+                    if changed {
+                        let (from, to) = if status.root_locked {
+                            ("available", "LOCKED")
+                        } else {
+                            ("LOCKED", "available")
+                        };
+                        transitions.push(format!(
+                            "+{}s {}: {from} -> {to}",
+                            started.elapsed().as_secs(),
+                            status.dataset
+                        ));
                     }
-                } else {
+                    let descendants = if status.locked_descendants.is_empty() {
+                        "-".to_string()
+                    } else {
+                        status.locked_descendants.join(", ")
+                    };
                     println!(
-                        "{} (root {}) is unlocked.",
-                        status.dataset, status.encryption_root
+                        "{:<width$}  {:<root_width$}  {cell}  {:<7}  {descendants}",
+                        status.dataset,
+                        status.encryption_root,
+                        mounted_cell(status.mounted)
                     );
                 }
             }
+            Err(err) => println!("{}", format!("poll failed: {err:#}").red()),
         }
-        Commands::ListKeys => {
-            let config = Arc::new(LockchainConfig::load(&config_path).with_context(|| {
-                format!(
-                    "failed to load configuration from {}",
-                    config_path.display()
-                )
-            })?);
-            let provider = SystemZfsProvider::from_config(&config)?;
-            let service = LockchainService::new(config.clone(), provider);
-            let snapshot = service.list_keys()?;
-            print_key_table(snapshot);
-        }
-        Commands::Tui => {
-            let config = Arc::new(LockchainConfig::load(&config_path).with_context(|| {
-                format!(
-                    "failed to load configuration from {}",
-                    config_path.display()
-                )
-            })?);
-            let provider = SystemZfsProvider::from_config(&config)?;
-            let service = LockchainService::new(config.clone(), provider);
-            tui::launch(config, service)?;
+        if !transitions.is_empty() {
+            println!("\nTransitions:");
+            for line in &transitions {
+                println!("  {line}");
+            }
         }
+        stdout.flush()?;
+        thread::sleep(interval);
+    }
+}
+
+/// Parse `--pool-size` with the same size syntax as the config file.
+fn parse_size(value: &str) -> Result<u64, String> {
+    units::parse_size(value).map_err(|err| err.to_string())
+}
+
+/// Parse `--interval` with the same duration syntax as the config file.
+fn parse_interval(value: &str) -> Result<Duration, String> {
+    let interval = units::parse_duration(value).map_err(|err| err.to_string())?;
+    if interval.is_zero() {
+        return Err("interval must be greater than zero".to_string());
+    }
+    Ok(interval)
+}
+
+/// Human-readable summary of a PBKDF2 benchmark.
+fn print_benchmark(bench: &kdf::KdfBenchmark) {
+    println!(
+        "PBKDF2-HMAC-SHA256: about {} iterations per second on this machine.",
+        bench.iterations_per_second
+    );
+    println!(
+        "Recommended fallback.passphrase_iters for ~{}: {}",
+        units::format_duration(bench.target),
+        bench.recommended_iters
+    );
+    println!(
+        "Current setting: {} (~{} per unlock attempt)",
+        bench.current_iters,
+        units::format_duration(Duration::from_millis(bench.current_time.as_millis() as u64))
+    );
+}
+
+/// Parse `--since` as an RFC 3339 time or an age relative to now.
+fn parse_since(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
     }
+    let age = units::parse_duration(value)
+        .map_err(|_| format!("expected an RFC 3339 time or an age such as `24h`, got `{value}`"))?;
+    let age = chrono::Duration::from_std(age).map_err(|err| err.to_string())?;
+    Ok(Utc::now() - age)
+}
 
+/// Result of `history --verify`.
+#[derive(Serialize)]
+struct ChainVerification {
+    path: PathBuf,
+    records: usize,
+    intact: bool,
+    /// 1-based position of the first record that breaks the chain.
+    broken_at: Option<usize>,
+    reason: Option<String>,
+}
+
+/// Check the audit hash chain and fail when any record was altered or removed.
+fn verify_history(path: &Path, records: &[AuditRecord], format: OutputFormat) -> Result<()> {
+    let broken = audit::verify_chain(records).err();
+    let verification = ChainVerification {
+        path: path.to_path_buf(),
+        records: records.len(),
+        intact: broken.is_none(),
+        broken_at: broken.as_ref().map(|(idx, _)| idx + 1),
+        reason: broken.as_ref().map(|(_, reason)| reason.clone()),
+    };
+    let structured = emit_structured(format, &verification)?;
+    if let Some((idx, reason)) = broken {
+        bail!(
+            "audit chain in {} broken at record {} ({}): {reason}",
+            path.display(),
+            idx + 1,
+            records[idx].timestamp
+        );
+    }
+    if !structured {
+        println!(
+            "Audit chain intact: {} records in {}.",
+            records.len(),
+            path.display()
+        );
+    }
     Ok(())
 }
 
-/// Pretty-print a workflow report so humans can follow along.
-fn print_report(report: WorkflowReport) {
+/// Render audit records as a table, oldest first.
+fn print_history_table(records: &[&AuditRecord]) {
+    println!(
+        "{:<24} {:<10} {:<32} {:<7} {:<20} DETAIL",
+        "TIMESTAMP", "EVENT", "DATASET", "RESULT", "ACTOR"
+    );
+    for record in records {
+        let entry = &record.entry;
+        let result = match entry.result {
+            AuditResult::Success => "ok",
+            AuditResult::Failure => "failed",
+        };
+        let detail = entry
+            .detail
+            .as_deref()
+            .or(entry.key_source.as_deref())
+            .unwrap_or("");
+        println!(
+            "{:<24} {:<10} {:<32} {:<7} {:<20} {}",
+            record.timestamp,
+            entry.event.as_str(),
+            entry.dataset,
+            result,
+            record.actor,
+            detail
+        );
+    }
+}
+
+fn print_daemon_events(events: &[DaemonEvent]) {
+    println!(
+        "{:<24} {:<7} {:<32} MESSAGE",
+        "TIMESTAMP", "KIND", "DATASET"
+    );
+    for event in events {
+        println!(
+            "{:<24} {:<7} {:<32} {}",
+            event.timestamp,
+            event.kind.as_str(),
+            event.dataset.as_deref().unwrap_or("-"),
+            event.message
+        );
+    }
+}
+
+/// Run a long workflow, printing its events as each step finishes.
+///
+/// On a terminal a spinner tracks the step in progress; otherwise events are
+/// plain lines. Structured output still waits for the complete report.
+fn run_with_progress<F>(
+    label: &str,
+    format: OutputFormat,
+    verbosity: Verbosity,
+    work: F,
+) -> Result<()>
+where
+    F: FnOnce(&mut dyn FnMut(&WorkflowEvent)) -> LockchainResult<WorkflowReport>,
+{
+    if format != OutputFormat::Text {
+        let report = work(&mut |_| {})?;
+        return print_report(report, format, verbosity);
+    }
+
+    let spinner = io::stderr().is_terminal().then(|| {
+        let bar = ProgressBar::new_spinner();
+        bar.set_style(
+            ProgressStyle::with_template("{spinner} {msg} [{elapsed}]")
+                .expect("static progress template"),
+        );
+        bar.set_message(format!("{label}: step 1"));
+        bar.enable_steady_tick(Duration::from_millis(120));
+        bar
+    });
+    let mut finished = 0;
+    let result = work(&mut |event| {
+        finished += 1;
+        if let Some(bar) = &spinner {
+            bar.set_message(format!("{label}: step {}", finished + 1));
+        }
+        if !verbosity.shows(event.level) {
+            return;
+        }
+        let line = format!("  [{}] {}", level_tag(event.level), event.message);
+        match &spinner {
+            Some(bar) => bar.suspend(|| println!("{line}")),
+            None => println!("{line}"),
+        }
+    });
+    if let Some(bar) = spinner {
+        bar.finish_and_clear();
+    }
+    println!("{}", result?.title);
+    Ok(())
+}
+
+/// Pretty-print a workflow report so humans can follow along, keeping only
+/// the events `verbosity` allows (structured output is filtered the same way).
+fn print_report(
+    mut report: WorkflowReport,
+    format: OutputFormat,
+    verbosity: Verbosity,
+) -> Result<()> {
+    report.events.retain(|event| verbosity.shows(event.level));
+    if emit_structured(format, &report)? {
+        return Ok(());
+    }
     println!("{}", report.title);
     for event in report.events {
         println!("  [{}] {}", level_tag(event.level), event.message);
     }
+    Ok(())
+}
+
+/// Print `value` as JSON or YAML; returns false for text output so the
+/// caller renders its own prose.
+fn emit_structured<T: Serialize>(format: OutputFormat, value: &T) -> Result<bool> {
+    match format {
+        OutputFormat::Text => return Ok(false),
+        OutputFormat::Json => println!("{}", to_string_pretty(value)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(value)?),
+    }
+    Ok(true)
+}
+
+/// Describe the outcome of an unlock for humans.
+fn print_unlock_report(report: &UnlockReport) {
+    if report.already_unlocked {
+        println!(
+            "Dataset {} (root {}) already has an available key.",
+            report.dataset, report.encryption_root
+        );
+    } else {
+        println!(
+            "Unlocked encryption root {} via dataset {}.",
+            report.encryption_root, report.dataset
+        );
+        for ds in &report.unlocked {
+            println!("  - {ds}");
+        }
+    }
+}
+
+/// Describe the keystatus of each dataset for humans.
+fn print_statuses(statuses: Vec<DatasetStatus>) {
+    for status in statuses {
+        if status.root_locked {
+            println!(
+                "{} (root {}) is LOCKED.",
+                status.dataset, status.encryption_root
+            );
+            if status.locked_descendants.is_empty() {
+                println!("  No locked descendants reported.");
+            } else {
+                println!("  Locked descendants:");
+                for child in status.locked_descendants {
+                    println!("    - {child}");
+                }
+            }
+        } else {
+            let mount = match status.mounted {
+                Some(true) => " and mounted",
+                Some(false) => " but not mounted",
+                None => "",
+            };
+            println!(
+                "{} (root {}) is unlocked{mount}.",
+                status.dataset, status.encryption_root
+            );
+        }
+        if status.keyformat.is_some() || status.keylocation.is_some() {
+            println!(
+                "  keyformat {}, keylocation {}",
+                status.keyformat.as_deref().unwrap_or("-"),
+                status.keylocation.as_deref().unwrap_or("-")
+            );
+        }
+    }
+}
+
+/// Describe the outcome of a lock for humans.
+fn print_lock_report(report: &LockReport) {
+    if report.already_locked {
+        println!(
+            "Dataset {} (root {}) is already locked.",
+            report.dataset, report.encryption_root
+        );
+    } else {
+        println!(
+            "Locked encryption root {} via dataset {}.",
+            report.encryption_root, report.dataset
+        );
+        for ds in &report.locked {
+            println!("  - {ds}");
+        }
+    }
 }
 
 /// Short tag used when printing workflow severity levels.
 fn level_tag(level: WorkflowLevel) -> &'static str {
     match level {
+        WorkflowLevel::Debug => "DBG",
         WorkflowLevel::Info => "INFO",
         WorkflowLevel::Success => "OK",
         WorkflowLevel::Warn => "WARN",
@@ -479,6 +2267,33 @@ fn level_tag(level: WorkflowLevel) -> &'static str {
     }
 }
 
+/// Like [`resolve_dataset`], but ask which dataset to use when several are
+/// configured and the operator can answer; `first` keeps the old default.
+fn pick_dataset(
+    dataset: Option<String>,
+    policy: &Policy,
+    first: bool,
+    prompter: Prompter,
+) -> Result<String> {
+    if dataset.is_some() || first || policy.datasets.len() < 2 || !prompter.interactive() {
+        return resolve_dataset(dataset, policy);
+    }
+    match prompter.choose("Select a dataset", &policy.datasets)? {
+        Some(index) => Ok(policy.datasets[index].clone()),
+        None => bail!("no dataset selected"),
+    }
+}
+
+/// First line of standard input, for passphrases handed over by another
+/// process (such as the Control Deck through pkexec) instead of argv.
+fn read_stdin_secret() -> Result<String> {
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    let secret = line.trim_end_matches(['\r', '\n']).to_string();
+    ensure!(!secret.is_empty(), "no passphrase on standard input");
+    Ok(secret)
+}
+
 /// Pick a dataset from CLI input or fall back to the first policy entry.
 fn resolve_dataset(dataset: Option<String>, policy: &Policy) -> Result<String> {
     if let Some(ds) = dataset {
@@ -493,7 +2308,10 @@ fn resolve_dataset(dataset: Option<String>, policy: &Policy) -> Result<String> {
 
 /// Render a simple table describing current key status across datasets.
 fn print_key_table(snapshot: Vec<DatasetKeyDescriptor>) {
-    println!("{:<32} {:<32} {}", "DATASET", "ENCRYPTION ROOT", "STATUS");
+    println!(
+        "{:<32} {:<32} {:<11} {:<7} {:<9} KEYLOCATION",
+        "DATASET", "ENCRYPTION ROOT", "STATUS", "MOUNTED", "KEYFORMAT"
+    );
     for entry in snapshot {
         let status = match entry.state {
             KeyState::Available => "available".to_string(),
@@ -501,8 +2319,100 @@ fn print_key_table(snapshot: Vec<DatasetKeyDescriptor>) {
             KeyState::Unknown(value) => value,
         };
         println!(
-            "{:<32} {:<32} {}",
-            entry.dataset, entry.encryption_root, status
+            "{:<32} {:<32} {:<11} {:<7} {:<9} {}",
+            entry.dataset,
+            entry.encryption_root,
+            status,
+            mounted_cell(entry.mounted),
+            entry.keyformat.as_deref().unwrap_or("-"),
+            entry.keylocation.as_deref().unwrap_or("-")
         );
     }
 }
+
+/// `yes`/`no` for a mount state, `-` when it does not apply or is unknown.
+fn mounted_cell(mounted: Option<bool>) -> &'static str {
+    match mounted {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "-",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ArgAction;
+    use lockchain_core::workflow::{ParamKind, BREAKGLASS_PARAMS, FORGE_PARAMS, SELF_TEST_PARAMS};
+
+    #[test]
+    fn doctor_repairs_only_when_asked() {
+        let cli = Cli::try_parse_from(["lockchain", "doctor"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Doctor {
+                check_only: false,
+                fix: false,
+                ..
+            }
+        ));
+        let cli = Cli::try_parse_from(["lockchain", "doctor", "--fix"]).unwrap();
+        assert!(matches!(cli.command, Commands::Doctor { fix: true, .. }));
+        assert!(Cli::try_parse_from(["lockchain", "doctor", "--check-only", "--fix"]).is_err());
+    }
+
+    #[test]
+    fn output_format_is_accepted_after_the_subcommand() {
+        let cli = Cli::try_parse_from(["lockchain", "status", "--output", "json"]).unwrap();
+        assert_eq!(cli.output, OutputFormat::Json);
+        assert!(matches!(cli.command, Commands::Status { .. }));
+
+        let cli = Cli::try_parse_from([
+            "lockchain",
+            "breakglass",
+            "tank/secure",
+            "-o",
+            "/root/key",
+            "--output",
+            "yaml",
+        ])
+        .unwrap();
+        assert_eq!(cli.output, OutputFormat::Yaml);
+        match cli.command {
+            Commands::Breakglass(args) => {
+                assert_eq!(args.output, Some(PathBuf::from("/root/key")))
+            }
+            other => panic!("parsed {other:?}"),
+        }
+    }
+
+    #[test]
+    fn workflow_params_match_cli_arguments() {
+        let cli = Cli::command();
+        for (name, params) in [
+            ("init", FORGE_PARAMS),
+            ("self-test", SELF_TEST_PARAMS),
+            ("breakglass", BREAKGLASS_PARAMS),
+        ] {
+            let subcommand = cli.find_subcommand(name).unwrap();
+            for spec in params {
+                let arg = subcommand
+                    .get_arguments()
+                    .find(|arg| arg.get_id() == spec.name)
+                    .unwrap_or_else(|| panic!("`{name}` has no `{}` argument", spec.name));
+                assert_eq!(
+                    arg.get_help().map(ToString::to_string).as_deref(),
+                    Some(spec.help.trim_end_matches('.')),
+                    "`{name} {}` help differs from the shared schema",
+                    spec.name
+                );
+                assert_eq!(
+                    matches!(arg.get_action(), ArgAction::SetTrue),
+                    spec.kind == ParamKind::Flag,
+                    "`{name} {}` flag-ness differs from the shared schema",
+                    spec.name
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Confirmation and passphrase prompts shared by the destructive commands.
+//!
+//! `--yes` / `--non-interactive` answers confirmations up front and forbids
+//! every other prompt; without it, prompting on a non-terminal stdin fails
+//! immediately rather than waiting on input that will never arrive.
+
+use anyhow::{bail, ensure, Result};
+use crossterm::cursor::{MoveToColumn, MoveUp};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::Stylize;
+use crossterm::terminal::{self, Clear, ClearType};
+use crossterm::{execute, queue};
+use rpassword::prompt_password;
+use std::io::{self, IsTerminal, Write};
+
+/// How the CLI may interact with the operator for this invocation.
+#[derive(Debug, Clone, Copy)]
+pub struct Prompter {
+    assume_yes: bool,
+}
+
+impl Prompter {
+    pub fn new(assume_yes: bool) -> Self {
+        Self { assume_yes }
+    }
+
+    /// Print `lines` on stderr, then require the operator to type `answer`.
+    ///
+    /// Returns `false` when they typed anything else; `--yes` skips the prompt.
+    pub fn confirm(&self, lines: &[String], answer: &str) -> Result<bool> {
+        if self.assume_yes {
+            return Ok(true);
+        }
+        self.require_terminal("confirmation")?;
+        // Prompt on stderr so `--output json` stays parseable.
+        for line in lines {
+            eprintln!("{line}");
+        }
+        eprintln!("Type `{answer}` to continue:");
+        eprint!("> ");
+        io::stderr().flush().ok();
+        let mut typed = String::new();
+        io::stdin().read_line(&mut typed)?;
+        Ok(typed.trim() == answer)
+    }
+
+    /// Read a secret without echo; fails when prompting is not allowed.
+    pub fn secret(&self, prompt: &str) -> Result<String> {
+        if self.assume_yes {
+            bail!("{prompt}: cannot prompt with --yes/--non-interactive; pass it as an option");
+        }
+        self.require_terminal("passphrase")?;
+        Ok(prompt_password(format!("{prompt}: "))?)
+    }
+
+    /// Print `message` on stderr and wait until the operator presses Enter.
+    pub fn pause(&self, message: &str) -> Result<()> {
+        if self.assume_yes {
+            bail!("cannot wait for Enter with --yes/--non-interactive");
+        }
+        self.require_terminal("acknowledgement")?;
+        eprintln!("{message}");
+        let mut typed = String::new();
+        io::stdin().read_line(&mut typed)?;
+        Ok(())
+    }
+
+    /// Whether the operator can be asked anything at all in this invocation.
+    pub fn interactive(&self) -> bool {
+        !self.assume_yes && io::stdin().is_terminal() && io::stderr().is_terminal()
+    }
+
+    /// Let the operator pick one of `options` with the arrow keys (or `j`/`k`)
+    /// and Enter; `None` when they press Esc, `q`, or Ctrl-C.
+    pub fn choose(&self, title: &str, options: &[String]) -> Result<Option<usize>> {
+        ensure!(
+            self.interactive(),
+            "{title}: cannot prompt here; name one explicitly"
+        );
+        let mut stderr = io::stderr();
+        terminal::enable_raw_mode()?;
+        let picked = pick(&mut stderr, title, options);
+        terminal::disable_raw_mode()?;
+        eprintln!();
+        Ok(picked?)
+    }
+
+    fn require_terminal(&self, what: &str) -> Result<()> {
+        ensure!(
+            io::stdin().is_terminal(),
+            "{what} required but stdin is not a terminal; pass --yes to run non-interactively"
+        );
+        Ok(())
+    }
+}
+
+/// Draw the list on `out` and track the highlighted row until a choice is made.
+fn pick(out: &mut impl Write, title: &str, options: &[String]) -> io::Result<Option<usize>> {
+    let mut selected = 0;
+    let mut drawn = false;
+    loop {
+        if drawn {
+            queue!(out, MoveUp(options.len() as u16), MoveToColumn(0))?;
+        } else {
+            write!(out, "{title} (↑/↓, Enter to choose, Esc to cancel):\r\n")?;
+        }
+        queue!(out, Clear(ClearType::FromCursorDown))?;
+        for (index, option) in options.iter().enumerate() {
+            if index == selected {
+                write!(out, "{}\r\n", format!("> {option}").bold())?;
+            } else {
+                write!(out, "  {option}\r\n")?;
+            }
+        }
+        out.flush()?;
+        drawn = true;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => selected = selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => selected = (selected + 1).min(options.len() - 1),
+            KeyCode::Enter => return Ok(Some(selected)),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                execute!(out, MoveToColumn(0))?;
+                return Ok(None);
+            }
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(None),
+            _ => {}
+        }
+    }
+}
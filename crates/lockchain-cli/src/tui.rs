@@ -1,67 +1,321 @@
 //! Minimal terminal UI for unlocking datasets when you prefer arrow keys over shells.
 
+mod export;
+mod keys;
+mod mouse;
+mod options;
+mod report;
+mod theme;
+
 use anyhow::Result;
+use chrono::{DateTime, Local};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use export::{ExportEvent, StatusExport, EXPORT_EVENTS};
+use keys::{Action, KeyMap};
 use lockchain_core::{
-    error::LockchainError,
+    error::{LockchainError, LockchainResult},
+    logging::{LogCapture, LogLine},
     provider::{DatasetKeyDescriptor, KeyState},
-    service::{LockchainService, UnlockOptions},
+    service::{LockOptions, LockchainService, UnlockOptions, UnlockReport},
+    workflow::{self, DoctorMode, WorkflowEvent, WorkflowLevel},
     LockchainConfig,
 };
 use lockchain_zfs::SystemZfsProvider;
+use mouse::{MouseMap, Target};
+use options::{DatasetOptions, FormOutcome, OptionsForm};
 use ratatui::{
-    prelude::{Alignment, Constraint, Direction, Frame, Layout},
-    style::{Color, Modifier, Style},
+    prelude::{Alignment, Constraint, Direction, Frame, Layout, Rect},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap,
+    },
     Terminal,
 };
-use rpassword::prompt_password;
+use report::{Report, ReportView};
 use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
     io::{self, Stdout},
-    sync::Arc,
+    path::PathBuf,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread,
     time::{Duration, Instant},
 };
+use theme::Theme;
+use zeroize::Zeroizing;
 
-/// Fire up the TUI with shared config/service references.
+/// Fire up the TUI; with `auto_refresh` keystatus is re-queried that often
+/// on a background thread.
 pub fn launch(
     config: Arc<LockchainConfig>,
-    service: LockchainService<SystemZfsProvider>,
+    provider: SystemZfsProvider,
+    auto_refresh: Option<Duration>,
 ) -> Result<()> {
-    let mut app = App::new(config, service);
+    let refresher = auto_refresh.map(|interval| {
+        Refresher::spawn(
+            LockchainService::new(config.clone(), provider.clone()),
+            interval,
+        )
+    });
+    let keys = KeyMap::from_config(&config.tui)?;
+    let theme = Theme::from_config(&config.tui.theme)?;
+    let mut app = App::new(config, provider, keys, theme, refresher);
     app.run()
 }
 
+/// Outcome of one background keystatus query.
+type RefreshResult = Result<Vec<DatasetKeyDescriptor>, String>;
+
+/// Worker thread that re-queries `list_keys` on a fixed interval so a slow
+/// `zfs` never blocks the event loop. It stops when the `Refresher` is dropped.
+struct Refresher {
+    interval: Duration,
+    updates: Receiver<RefreshResult>,
+    _stop: Sender<()>,
+}
+
+impl Refresher {
+    fn spawn(service: LockchainService<SystemZfsProvider>, interval: Duration) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let (publish, updates) = mpsc::channel();
+        thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                let result = service.list_keys().map_err(|err| err.to_string());
+                if publish.send(result).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            interval,
+            updates,
+            _stop: stop,
+        }
+    }
+}
+
+/// How often the key watcher looks for the configured key files.
+const KEY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Worker thread that reports how many of the configured key files exist,
+/// sending only when the count changes. It stops when dropped.
+struct KeyWatcher {
+    total: usize,
+    updates: Receiver<usize>,
+    _stop: Sender<()>,
+}
+
+impl KeyWatcher {
+    fn spawn(paths: Vec<PathBuf>) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let (publish, updates) = mpsc::channel();
+        let total = paths.len();
+        thread::spawn(move || {
+            let mut last = None;
+            loop {
+                let present = paths.iter().filter(|path| path.exists()).count();
+                if last != Some(present) {
+                    if publish.send(present).is_err() {
+                        break;
+                    }
+                    last = Some(present);
+                }
+                if !matches!(
+                    stopped.recv_timeout(KEY_POLL_INTERVAL),
+                    Err(RecvTimeoutError::Timeout)
+                ) {
+                    break;
+                }
+            }
+        });
+        Self {
+            total,
+            updates,
+            _stop: stop,
+        }
+    }
+}
+
+/// Progress from a diagnostics run on the worker thread.
+enum JobUpdate {
+    Event(WorkflowEvent),
+    /// Warning and error counts, or why the workflow could not run.
+    Done(Result<(usize, usize), String>),
+}
+
+/// Doctor or self-heal running on a worker thread so the screen stays live.
+struct Job {
+    name: &'static str,
+    updates: Receiver<JobUpdate>,
+    /// Events received so far, kept for the report view.
+    entries: Vec<LogEntry>,
+}
+
+impl Job {
+    fn spawn<W>(name: &'static str, workflow: W) -> Self
+    where
+        W: FnOnce(&mut dyn FnMut(&WorkflowEvent)) -> LockchainResult<(usize, usize)>
+            + Send
+            + 'static,
+    {
+        let (publish, updates) = mpsc::channel();
+        thread::spawn(move || {
+            let events = publish.clone();
+            let result = workflow(&mut |event| {
+                let _ = events.send(JobUpdate::Event(event.clone()));
+            });
+            let _ = publish.send(JobUpdate::Done(result.map_err(|err| err.to_string())));
+        });
+        Self {
+            name,
+            updates,
+            entries: Vec::new(),
+        }
+    }
+}
+
+/// Dialog drawn over the dataset list that takes every key until it closes.
+enum Modal {
+    /// Ask before unloading the key shared by `encryption_root`.
+    ConfirmLock {
+        dataset: String,
+        encryption_root: String,
+    },
+    /// Every action and the keys bound to it.
+    Help,
+    /// Unlock options for one dataset.
+    Options(OptionsForm),
+    /// Masked fallback passphrase entry for `dataset`.
+    Passphrase {
+        dataset: String,
+        input: Zeroizing<String>,
+    },
+}
+
+/// Lines kept in the log panel before the oldest are dropped.
+const LOG_CAPACITY: usize = 500;
+
+/// One line in the log panel: a workflow event, an outcome, or a log record.
+struct LogEntry {
+    at: DateTime<Local>,
+    level: WorkflowLevel,
+    message: String,
+}
+
 /// Encapsulates TUI state, list data, and last operation outcome.
 struct App {
+    config: Arc<LockchainConfig>,
+    provider: SystemZfsProvider,
     service: LockchainService<SystemZfsProvider>,
+    keys: KeyMap,
+    theme: Theme,
     datasets: Vec<DatasetKeyDescriptor>,
     selected: usize,
     last_error: Option<String>,
     status_message: Option<String>,
     status_timestamp: Instant,
     strict_usb: bool,
+    /// Unlock options set with the options form, by dataset.
+    dataset_options: HashMap<String, DatasetOptions>,
+    profile: Option<String>,
+    refresher: Option<Refresher>,
+    modal: Option<Modal>,
+    /// Case-insensitive `/` filter over dataset names and encryption roots.
+    filter: String,
+    /// Whether key presses currently edit `filter`.
+    editing_filter: bool,
+    /// When keystatus was last read successfully.
+    refreshed_at: Instant,
+    /// Why the most recent background refresh failed, until one succeeds.
+    refresh_error: Option<String>,
+    /// Log records diverted from stderr while the TUI owns the screen.
+    capture: LogCapture,
+    log: VecDeque<LogEntry>,
+    /// Lines scrolled back from the newest entry; 0 follows the tail.
+    log_scroll: usize,
+    job: Option<Job>,
+    key_watcher: KeyWatcher,
+    /// Key files found by the last watcher poll; `None` until it reports.
+    keys_present: Option<usize>,
+    /// Dataset whose unlock failed for want of a key, retried when it appears.
+    pending_unlock: Option<DatasetKeyDescriptor>,
+    /// Outcome of the last doctor or self-heal run, until cleared.
+    banner: Option<(WorkflowLevel, String)>,
+    /// Every event of the last finished doctor or self-heal run.
+    report: Option<Report>,
+    /// Open when the report is shown full-screen.
+    report_view: Option<ReportView>,
+    /// Clickable areas from the last frame.
+    mouse: RefCell<MouseMap>,
 }
 
 impl App {
     /// Hydrate the dataset list and stash service handles for later use.
-    fn new(config: Arc<LockchainConfig>, service: LockchainService<SystemZfsProvider>) -> Self {
+    fn new(
+        config: Arc<LockchainConfig>,
+        provider: SystemZfsProvider,
+        keys: KeyMap,
+        theme: Theme,
+        refresher: Option<Refresher>,
+    ) -> Self {
+        let service = LockchainService::new(config.clone(), provider.clone());
+        let key_watcher = KeyWatcher::spawn(
+            config
+                .key_sources()
+                .into_iter()
+                .map(|source| source.path)
+                .collect(),
+        );
         let datasets = service.list_keys().unwrap_or_default();
 
-        let _ = config; // config retained by caller; service owns needed state
+        let profile = config
+            .profile
+            .as_ref()
+            .map(|active| active.name().to_string());
 
         Self {
+            config,
+            provider,
             service,
+            keys,
+            theme,
             datasets,
             selected: 0,
             last_error: None,
             status_message: None,
             status_timestamp: Instant::now(),
             strict_usb: false,
+            dataset_options: HashMap::new(),
+            profile,
+            refresher,
+            modal: None,
+            filter: String::new(),
+            editing_filter: false,
+            refreshed_at: Instant::now(),
+            refresh_error: None,
+            capture: LogCapture::start(),
+            log: VecDeque::new(),
+            log_scroll: 0,
+            job: None,
+            key_watcher,
+            keys_present: None,
+            pending_unlock: None,
+            banner: None,
+            report: None,
+            report_view: None,
+            mouse: RefCell::default(),
         }
     }
 
@@ -96,47 +350,37 @@ impl App {
 
             if crossterm::event::poll(Duration::from_millis(200))? {
                 match event::read()? {
-                    Event::Key(key) => match key.code {
-                        KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
-                        KeyCode::Up | KeyCode::Char('k') => {
-                            if self.selected > 0 {
-                                self.selected -= 1;
+                    Event::Key(key) if self.modal.is_some() => self.handle_modal_key(key)?,
+                    Event::Key(key) if self.report_view.is_some() => self.handle_report_key(key),
+                    Event::Key(key) if self.editing_filter => self.handle_filter_key(key),
+                    Event::Key(key) if key.code == KeyCode::Esc && !self.filter.is_empty() => {
+                        self.filter.clear();
+                        self.keep_selection_visible();
+                    }
+                    Event::Key(key) => {
+                        if let Some(action) = self.keys.action(key.code) {
+                            if !self.perform(action)? {
+                                return Ok(());
                             }
                         }
-                        KeyCode::Down | KeyCode::Char('j') => {
-                            if self.selected + 1 < self.datasets.len() {
-                                self.selected += 1;
+                    }
+                    Event::Mouse(mouse) => {
+                        if let Some(action) = self.handle_mouse(mouse) {
+                            if !self.perform(action)? {
+                                return Ok(());
                             }
                         }
-                        KeyCode::Char('r') => {
-                            self.refresh_status()?;
-                        }
-                        KeyCode::Char('s') => {
-                            self.strict_usb = !self.strict_usb;
-                            self.set_status(if self.strict_usb {
-                                "Strict USB mode enabled"
-                            } else {
-                                "Strict USB mode disabled"
-                            });
-                        }
-                        KeyCode::Char('p') => {
-                            if let Err(err) = self.prompt_and_unlock() {
-                                self.last_error = Some(err.to_string());
-                            }
-                        }
-                        KeyCode::Enter => {
-                            self.attempt_unlock()?;
-                        }
-                        KeyCode::Char('c') => {
-                            self.last_error = None;
-                        }
-                        _ => {}
-                    },
+                    }
                     Event::Resize(_, _) => {}
                     _ => {}
                 }
             }
 
+            self.apply_background_refresh();
+            self.drain_log_capture();
+            self.poll_job()?;
+            self.poll_key_watcher()?;
+
             if self.status_message.is_some()
                 && self.status_timestamp.elapsed() > Duration::from_secs(5)
             {
@@ -145,29 +389,567 @@ impl App {
         }
     }
 
+    /// Carry out `action` from a key or a header button; returns false when
+    /// the user asked to quit.
+    fn perform(&mut self, action: Action) -> Result<bool> {
+        match action {
+            Action::Quit => return Ok(false),
+            Action::Up => self.move_selection(-1),
+            Action::Down => self.move_selection(1),
+            Action::Filter => self.editing_filter = true,
+            Action::Refresh => self.refresh_status()?,
+            Action::StrictUsb => {
+                self.strict_usb = !self.strict_usb;
+                self.set_status(if self.strict_usb {
+                    "Strict USB mode enabled"
+                } else {
+                    "Strict USB mode disabled"
+                });
+            }
+            Action::Passphrase => self.prompt_passphrase(),
+            Action::Unlock => self.attempt_unlock()?,
+            Action::Lock => self.confirm_lock(),
+            Action::Options => self.edit_options(),
+            Action::Clear => {
+                self.last_error = None;
+                self.banner = None;
+            }
+            Action::Doctor => self.start_doctor(),
+            Action::SelfHeal => self.start_self_heal(),
+            Action::Report => self.open_report(),
+            Action::Export => self.export_status(),
+            Action::LogUp => self.scroll_log(5),
+            Action::LogDown => self.scroll_log(-5),
+            Action::LogFollow => self.log_scroll = 0,
+            Action::Help => self.modal = Some(Modal::Help),
+        }
+        Ok(true)
+    }
+
+    /// Select clicked rows and scroll whatever is under the wheel; a click on
+    /// a header button returns its action.
+    fn handle_mouse(&mut self, mouse: MouseEvent) -> Option<Action> {
+        if self.modal.is_some() {
+            return None;
+        }
+        let step = match mouse.kind {
+            MouseEventKind::ScrollUp => -1,
+            MouseEventKind::ScrollDown => 1,
+            MouseEventKind::Down(MouseButton::Left) => 0,
+            _ => return None,
+        };
+        if let (Some(view), Some(report)) = (&mut self.report_view, &self.report) {
+            let action = if step < 0 { Action::Up } else { Action::Down };
+            if step != 0 {
+                view.handle(Some(action), report);
+            }
+            return None;
+        }
+        let target = self.mouse.borrow().target(mouse.column, mouse.row);
+        match target {
+            Target::Button(action) if step == 0 => return Some(action),
+            Target::Row(_) | Target::List if step != 0 => self.move_selection(step),
+            Target::Row(row) if step == 0 => {
+                if let Some(&index) = self.visible().get(row) {
+                    self.selected = index;
+                }
+            }
+            Target::Log if step != 0 => self.scroll_log(-3 * step),
+            _ => {}
+        }
+        None
+    }
+
+    /// Scroll the log panel back (positive) or towards the newest line.
+    fn scroll_log(&mut self, lines: isize) {
+        self.log_scroll = self
+            .log_scroll
+            .saturating_add_signed(lines)
+            .min(self.log.len().saturating_sub(1));
+    }
+
     /// Reload keystatus from the service and keep selection stable.
     fn refresh_status(&mut self) -> Result<()> {
-        self.datasets = self.service.list_keys()?;
-        if !self.datasets.is_empty() {
-            self.selected = self.selected.min(self.datasets.len() - 1);
+        let datasets = self.service.list_keys()?;
+        self.replace_datasets(datasets);
+        Ok(())
+    }
+
+    /// Take the newest result from the background refresher, if any arrived.
+    fn apply_background_refresh(&mut self) {
+        let Some(refresher) = &self.refresher else {
+            return;
+        };
+        let Some(latest) = refresher.updates.try_iter().last() else {
+            return;
+        };
+        match latest {
+            Ok(datasets) => self.replace_datasets(datasets),
+            Err(err) => {
+                if self.refresh_error.as_ref() != Some(&err) {
+                    self.record(
+                        WorkflowLevel::Warn,
+                        format!("background refresh failed: {err}"),
+                    );
+                }
+                self.refresh_error = Some(err);
+            }
+        }
+    }
+
+    /// Run a check-only doctor pass in the background.
+    fn start_doctor(&mut self) {
+        let (config, provider) = (self.config.clone(), self.provider.clone());
+        self.start_job("doctor", move |on_event| {
+            let report = workflow::doctor_report_observed(
+                &config,
+                provider,
+                DoctorMode::CheckOnly,
+                on_event,
+            )?;
+            Ok((report.warnings, report.errors))
+        });
+    }
+
+    /// Run self-heal, which applies repairs, in the background.
+    fn start_self_heal(&mut self) {
+        let (config, provider) = (self.config.clone(), self.provider.clone());
+        self.start_job("self-heal", move |on_event| {
+            let report = workflow::self_heal_observed(&config, provider, on_event)?;
+            let count = |level| {
+                report
+                    .events
+                    .iter()
+                    .filter(|event| event.level == level)
+                    .count()
+            };
+            Ok((count(WorkflowLevel::Warn), count(WorkflowLevel::Error)))
+        });
+    }
+
+    fn start_job<W>(&mut self, name: &'static str, workflow: W)
+    where
+        W: FnOnce(&mut dyn FnMut(&WorkflowEvent)) -> LockchainResult<(usize, usize)>
+            + Send
+            + 'static,
+    {
+        if let Some(job) = &self.job {
+            self.set_status(format!("{} is still running", job.name));
+            return;
+        }
+        self.banner = None;
+        self.record(WorkflowLevel::Info, format!("Starting {name}"));
+        self.job = Some(Job::spawn(name, workflow));
+    }
+
+    /// Stream events from a running job into the log and raise the summary
+    /// banner once it finishes, keeping its events as the latest report.
+    fn poll_job(&mut self) -> Result<()> {
+        let Some(job) = &mut self.job else {
+            return Ok(());
+        };
+        let updates: Vec<JobUpdate> = job.updates.try_iter().collect();
+        let mut events = Vec::new();
+        let mut finished = None;
+        for update in updates {
+            match update {
+                JobUpdate::Event(event) => {
+                    job.entries.push(LogEntry {
+                        at: Local::now(),
+                        level: event.level,
+                        message: event.message.clone(),
+                    });
+                    events.push(event);
+                }
+                JobUpdate::Done(result) => finished = Some(result),
+            }
+        }
+        self.record_events(&events);
+        let Some(result) = finished else {
+            return Ok(());
+        };
+        let Some(job) = self.job.take() else {
+            return Ok(());
+        };
+        let name = job.name;
+        let report = self.keys.label(Action::Report);
+        let banner = match result {
+            Ok((0, 0)) => (WorkflowLevel::Success, format!("{name}: all checks passed")),
+            Ok((warnings, 0)) => (
+                WorkflowLevel::Warn,
+                format!("{name}: {warnings} warnings; {report} for the report"),
+            ),
+            Ok((warnings, errors)) => (
+                WorkflowLevel::Error,
+                format!("{name}: {errors} errors, {warnings} warnings; {report} for the report"),
+            ),
+            Err(err) => (WorkflowLevel::Error, format!("{name} failed: {err}")),
+        };
+        self.record(banner.0, banner.1.clone());
+        self.report = Some(Report {
+            name,
+            finished_at: Local::now(),
+            summary: banner.clone(),
+            entries: job.entries,
+        });
+        self.banner = Some(banner);
+        self.refresh_status()
+    }
+
+    /// Show the last doctor or self-heal report full-screen.
+    fn open_report(&mut self) {
+        if self.report.is_some() {
+            self.report_view = Some(ReportView::new());
+        } else if let Some(job) = &self.job {
+            self.set_status(format!("{} is still running", job.name));
         } else {
-            self.selected = 0;
+            self.set_status(format!(
+                "No report yet; {} runs doctor, {} runs self-heal",
+                self.keys.label(Action::Doctor),
+                self.keys.label(Action::SelfHeal)
+            ));
         }
-        Ok(())
+    }
+
+    /// Write the dataset list and recent log lines to timestamped JSON and
+    /// Markdown files under `[tui] export_dir`.
+    fn export_status(&mut self) {
+        let dir = PathBuf::from(self.config.tui.export_dir.as_deref().unwrap_or("."));
+        let skip = self.log.len().saturating_sub(EXPORT_EVENTS);
+        let export = StatusExport {
+            generated_at: Local::now(),
+            config: &self.config.path,
+            profile: self.profile.as_deref(),
+            strict_usb: self.strict_usb,
+            keys_present: self.keys_present,
+            key_files: self.key_watcher.total,
+            refreshed_secs_ago: self.refreshed_at.elapsed().as_secs(),
+            refresh_error: self.refresh_error.as_deref(),
+            datasets: &self.datasets,
+            banner: self.banner.as_ref().map(|(level, message)| ExportEvent {
+                at: None,
+                level: *level,
+                message,
+            }),
+            events: self.log.iter().skip(skip).map(ExportEvent::from).collect(),
+        };
+        match export.write(&dir) {
+            Ok((json, markdown)) => {
+                let message = format!(
+                    "Exported status to {} and {}",
+                    json.display(),
+                    markdown.display()
+                );
+                self.record(WorkflowLevel::Success, message.clone());
+                self.set_status(message);
+            }
+            Err(err) => {
+                let message = format!("export to {} failed: {err}", dir.display());
+                self.record(WorkflowLevel::Error, message.clone());
+                self.last_error = Some(message);
+            }
+        }
+    }
+
+    /// Scroll, filter, or close the report view.
+    fn handle_report_key(&mut self, key: KeyEvent) {
+        let (Some(view), Some(report)) = (&mut self.report_view, &self.report) else {
+            return;
+        };
+        if !view.handle(self.keys.action(key.code), report) {
+            self.report_view = None;
+        }
+    }
+
+    /// Note key files coming and going, and retry a pending unlock once its
+    /// key is back.
+    fn poll_key_watcher(&mut self) -> Result<()> {
+        let Some(present) = self.key_watcher.updates.try_iter().last() else {
+            return Ok(());
+        };
+        let total = self.key_watcher.total;
+        if self.keys_present.is_some() {
+            let (level, message) = if present == 0 {
+                (WorkflowLevel::Warn, "Key token removed".to_string())
+            } else {
+                (
+                    WorkflowLevel::Info,
+                    format!("Key token present ({present} of {total} key files)"),
+                )
+            };
+            self.record(level, message);
+        }
+        self.keys_present = Some(present);
+
+        let Some(pending) = self.pending_unlock.clone() else {
+            return Ok(());
+        };
+        let source = self
+            .config
+            .key_source(&pending.dataset, &pending.encryption_root);
+        if !source.path.exists() {
+            return Ok(());
+        }
+        self.pending_unlock = None;
+        self.record(
+            WorkflowLevel::Info,
+            format!(
+                "{} appeared; retrying unlock of {}",
+                source.path.display(),
+                pending.dataset
+            ),
+        );
+        self.last_error = None;
+        self.unlock_from_token(pending)
+    }
+
+    /// Header chip showing whether the configured key files are present.
+    fn key_chip(&self) -> Span<'static> {
+        let total = self.key_watcher.total;
+        let (text, style) = match self.keys_present {
+            None => (" [key: checking] ".to_string(), self.theme.muted),
+            Some(0) => (" [key: missing] ".to_string(), self.theme.error),
+            Some(present) if present == total => (" [key: present] ".to_string(), self.theme.ok),
+            Some(present) => (format!(" [keys: {present}/{total}] "), self.theme.warning),
+        };
+        let text = if self.pending_unlock.is_some() {
+            format!("{}(unlock waiting) ", text.trim_end())
+        } else {
+            text
+        };
+        Span::styled(text, style)
+    }
+
+    /// Move log records captured since the last frame into the panel.
+    fn drain_log_capture(&mut self) {
+        let lines: Vec<LogLine> = self.capture.drain().collect();
+        for line in lines {
+            let level = match line.level {
+                log::Level::Error => WorkflowLevel::Error,
+                log::Level::Warn => WorkflowLevel::Warn,
+                log::Level::Info => WorkflowLevel::Info,
+                log::Level::Debug | log::Level::Trace => WorkflowLevel::Debug,
+            };
+            self.push_log(LogEntry {
+                at: line.at.into(),
+                level,
+                message: format!("{}: {}", line.target, line.message),
+            });
+        }
+    }
+
+    /// Append a line to the log panel.
+    fn record(&mut self, level: WorkflowLevel, message: impl Into<String>) {
+        self.push_log(LogEntry {
+            at: Local::now(),
+            level,
+            message: message.into(),
+        });
+    }
+
+    /// Append every event of a workflow to the log panel.
+    fn record_events(&mut self, events: &[WorkflowEvent]) {
+        for event in events {
+            self.record(event.level, event.message.clone());
+        }
+    }
+
+    fn push_log(&mut self, entry: LogEntry) {
+        if self.log.len() == LOG_CAPACITY {
+            self.log.pop_front();
+        }
+        self.log.push_back(entry);
+        if self.log_scroll > 0 {
+            // Keep the scrolled-back view on the same lines.
+            self.log_scroll = (self.log_scroll + 1).min(self.log.len() - 1);
+        }
+    }
+
+    /// Unlock with retries, recording each failed attempt and the outcome.
+    fn unlock_logged(
+        &mut self,
+        dataset: &str,
+        options: UnlockOptions,
+    ) -> LockchainResult<UnlockReport> {
+        let mut retries = Vec::new();
+        let result = self
+            .service
+            .unlock_with_retry_observed(dataset, options, |retry| {
+                retries.push(WorkflowEvent {
+                    level: WorkflowLevel::Warn,
+                    message: format!(
+                        "unlock {} attempt {}/{} failed: {}; retrying in {:.1}s",
+                        retry.dataset,
+                        retry.attempt,
+                        retry.max_attempts,
+                        retry.error,
+                        retry.delay.as_secs_f64()
+                    ),
+                });
+            });
+        self.record_events(&retries);
+        if result.is_ok()
+            && self
+                .pending_unlock
+                .as_ref()
+                .is_some_and(|pending| pending.dataset == dataset)
+        {
+            self.pending_unlock = None;
+        }
+        match &result {
+            Ok(report) if report.already_unlocked => self.record(
+                WorkflowLevel::Info,
+                format!("{} was already unlocked", report.encryption_root),
+            ),
+            Ok(report) => self.record(
+                WorkflowLevel::Success,
+                format!(
+                    "Unlocked {} ({} datasets)",
+                    report.encryption_root,
+                    report.unlocked.len()
+                ),
+            ),
+            Err(err) => self.record(WorkflowLevel::Error, format!("unlock {dataset}: {err}")),
+        }
+        if let Ok(report) = &result {
+            if !report.mounted.is_empty() {
+                self.record(
+                    WorkflowLevel::Info,
+                    format!("Mounted {}", report.mounted.join(", ")),
+                );
+            }
+        }
+        result
+    }
+
+    /// Swap in a fresh dataset list, keeping the same dataset selected when
+    /// it is still listed.
+    fn replace_datasets(&mut self, datasets: Vec<DatasetKeyDescriptor>) {
+        let previous = self
+            .datasets
+            .get(self.selected)
+            .map(|entry| entry.dataset.clone());
+        self.datasets = datasets;
+        self.selected = previous
+            .and_then(|name| self.datasets.iter().position(|entry| entry.dataset == name))
+            .unwrap_or_else(|| self.selected.min(self.datasets.len().saturating_sub(1)));
+        self.keep_selection_visible();
+        self.refreshed_at = Instant::now();
+        self.refresh_error = None;
+    }
+
+    /// Footer note on how old the list is; stale after two missed intervals
+    /// or a failed refresh.
+    fn freshness(&self) -> Span<'static> {
+        let age = self.refreshed_at.elapsed().as_secs();
+        let interval = self.refresher.as_ref().map(|refresher| refresher.interval);
+        let stale = self.refresh_error.is_some()
+            || interval.is_some_and(|interval| self.refreshed_at.elapsed() > interval * 2);
+        if stale {
+            let reason = self
+                .refresh_error
+                .as_deref()
+                .map(|err| format!(": {err}"))
+                .unwrap_or_default();
+            Span::styled(
+                format!(" STALE, refreshed {age}s ago{reason} "),
+                self.theme.warning,
+            )
+        } else {
+            let mode = if interval.is_some() { "auto" } else { "manual" };
+            Span::styled(format!(" refreshed {age}s ago ({mode}) "), self.theme.muted)
+        }
+    }
+
+    /// Whether `entry` passes the `/` filter on dataset name or encryption root.
+    fn matches_filter(&self, entry: &DatasetKeyDescriptor) -> bool {
+        let needle = self.filter.to_lowercase();
+        entry.dataset.to_lowercase().contains(&needle)
+            || entry.encryption_root.to_lowercase().contains(&needle)
+    }
+
+    /// Indices into `datasets` of the rows the filter lets through.
+    fn visible(&self) -> Vec<usize> {
+        (0..self.datasets.len())
+            .filter(|&index| self.matches_filter(&self.datasets[index]))
+            .collect()
+    }
+
+    /// The highlighted dataset, unless the filter hides every row.
+    fn selected_entry(&self) -> Option<&DatasetKeyDescriptor> {
+        self.datasets
+            .get(self.selected)
+            .filter(|entry| self.matches_filter(entry))
+    }
+
+    /// Like [`Self::selected_entry`], reporting why nothing is selected.
+    fn require_selection(&mut self) -> Option<DatasetKeyDescriptor> {
+        let entry = self.selected_entry().cloned();
+        if entry.is_none() {
+            self.last_error = Some(if self.datasets.is_empty() {
+                "No datasets configured".into()
+            } else {
+                format!("No dataset matches /{}", self.filter)
+            });
+        }
+        entry
+    }
+
+    /// Step through the visible rows.
+    fn move_selection(&mut self, step: isize) {
+        let visible = self.visible();
+        let Some(at) = visible.iter().position(|&index| index == self.selected) else {
+            self.keep_selection_visible();
+            return;
+        };
+        let next = at.saturating_add_signed(step).min(visible.len() - 1);
+        self.selected = visible[next];
+    }
+
+    /// Move the selection to the first visible row if the filter hid it.
+    fn keep_selection_visible(&mut self) {
+        if self.selected_entry().is_none() {
+            if let Some(&first) = self.visible().first() {
+                self.selected = first;
+            }
+        }
+    }
+
+    /// Edit the filter; every key press re-filters the list.
+    fn handle_filter_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => self.editing_filter = false,
+            KeyCode::Esc => {
+                self.editing_filter = false;
+                self.filter.clear();
+            }
+            KeyCode::Backspace => {
+                self.filter.pop();
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.filter.push(c);
+            }
+            _ => return,
+        }
+        self.keep_selection_visible();
     }
 
     /// Kick off an unlock using the current selection and strict flag.
     fn attempt_unlock(&mut self) -> Result<()> {
-        if self.datasets.is_empty() {
-            self.last_error = Some("No datasets configured".into());
+        let Some(entry) = self.require_selection() else {
             return Ok(());
-        }
+        };
+        self.unlock_from_token(entry)
+    }
 
-        let dataset = self.datasets[self.selected].dataset.clone();
-        let mut options = UnlockOptions::default();
-        options.strict_usb = self.strict_usb;
+    /// Unlock `entry` with its key file; if the key is missing, remember it
+    /// so the unlock is retried once the token shows up.
+    fn unlock_from_token(&mut self, entry: DatasetKeyDescriptor) -> Result<()> {
+        let Some(options) = self.unlock_options(&entry.dataset) else {
+            return Ok(());
+        };
 
-        match self.service.unlock_with_retry(&dataset, options) {
+        match self.unlock_logged(&entry.dataset, options) {
             Ok(report) => {
                 if report.already_unlocked {
                     self.set_status("Dataset already unlocked");
@@ -179,8 +961,9 @@ impl App {
             Err(err) => match err {
                 LockchainError::MissingKeySource(_) => {
                     self.last_error = Some(
-                        "Key source missing. Insert USB or press 'p' to supply passphrase.".into(),
+                        "Key source missing. Insert USB to retry automatically, or press 'p' to supply passphrase.".into(),
                     );
+                    self.pending_unlock = Some(entry);
                 }
                 other => {
                     self.last_error = Some(other.to_string());
@@ -191,32 +974,170 @@ impl App {
         Ok(())
     }
 
-    /// Temporarily drop raw mode, prompt for a passphrase, and retry the unlock.
-    fn prompt_and_unlock(&mut self) -> Result<()> {
-        if self.datasets.is_empty() {
-            self.last_error = Some("No datasets configured".into());
-            return Ok(());
+    /// Open the lock confirmation for the selected dataset.
+    fn confirm_lock(&mut self) {
+        let Some(entry) = self.require_selection() else {
+            return;
+        };
+        self.modal = Some(Modal::ConfirmLock {
+            dataset: entry.dataset,
+            encryption_root: entry.encryption_root,
+        });
+    }
+
+    /// Route a key press to the open dialog.
+    fn handle_modal_key(&mut self, key: KeyEvent) -> Result<()> {
+        match self.modal.as_mut() {
+            Some(Modal::ConfirmLock { dataset, .. }) => {
+                let unmount = match key.code {
+                    KeyCode::Char('y') => false,
+                    KeyCode::Char('u') => true,
+                    KeyCode::Char('n') | KeyCode::Esc => {
+                        self.modal = None;
+                        self.set_status("Lock cancelled");
+                        return Ok(());
+                    }
+                    _ => return Ok(()),
+                };
+                let dataset = dataset.clone();
+                self.modal = None;
+                self.lock(&dataset, unmount)
+            }
+            Some(Modal::Passphrase { dataset, input }) => {
+                let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                match key.code {
+                    KeyCode::Enter => {
+                        let dataset = dataset.clone();
+                        let passphrase = input.as_str().to_owned();
+                        self.modal = None;
+                        return self.unlock_with_passphrase(&dataset, passphrase);
+                    }
+                    KeyCode::Esc => {}
+                    KeyCode::Char('c') if ctrl => {}
+                    KeyCode::Char('u') if ctrl => {
+                        input.clear();
+                        return Ok(());
+                    }
+                    KeyCode::Backspace => {
+                        input.pop();
+                        return Ok(());
+                    }
+                    KeyCode::Char(c) if !ctrl => {
+                        input.push(c);
+                        return Ok(());
+                    }
+                    _ => return Ok(()),
+                }
+                self.modal = None;
+                self.set_status("Passphrase entry cancelled");
+                Ok(())
+            }
+            Some(Modal::Help) => {
+                self.modal = None;
+                Ok(())
+            }
+            Some(Modal::Options(form)) => {
+                match form.handle(key) {
+                    FormOutcome::Editing => return Ok(()),
+                    FormOutcome::Cancelled => self.set_status("Options unchanged"),
+                    FormOutcome::Saved(options) => {
+                        let dataset = form.dataset.clone();
+                        self.set_status(format!("Saved unlock options for {dataset}"));
+                        if options == DatasetOptions::default() {
+                            self.dataset_options.remove(&dataset);
+                        } else {
+                            self.dataset_options.insert(dataset, options);
+                        }
+                    }
+                }
+                self.modal = None;
+                Ok(())
+            }
+            None => Ok(()),
         }
+    }
 
-        disable_raw_mode()?;
-        let dataset = self.datasets[self.selected].dataset.clone();
-        let prompt = format!("Fallback passphrase for {}", dataset);
-        let result = prompt_password(prompt);
-        enable_raw_mode()?;
+    /// Open the unlock options form for the selected dataset.
+    fn edit_options(&mut self) {
+        let Some(entry) = self.require_selection() else {
+            return;
+        };
+        let current = self
+            .dataset_options
+            .get(&entry.dataset)
+            .cloned()
+            .unwrap_or_default();
+        self.modal = Some(Modal::Options(OptionsForm::new(entry.dataset, &current)));
+    }
 
-        let passphrase = match result {
-            Ok(p) => p,
+    /// Unlock options for `dataset` from its form and the strict USB toggle;
+    /// a key file that cannot be read is reported and yields `None`.
+    fn unlock_options(&mut self, dataset: &str) -> Option<UnlockOptions> {
+        let options = self
+            .dataset_options
+            .get(dataset)
+            .cloned()
+            .unwrap_or_default()
+            .unlock_options(self.strict_usb);
+        match options {
+            Ok(options) => Some(options),
             Err(err) => {
-                self.last_error = Some(format!("passphrase prompt failed: {err}"));
-                return Ok(());
+                self.record(WorkflowLevel::Error, format!("unlock {dataset}: {err}"));
+                self.last_error = Some(err);
+                None
             }
+        }
+    }
+
+    /// Unload the key for `dataset`'s encryption root and refresh the list.
+    fn lock(&mut self, dataset: &str, unmount: bool) -> Result<()> {
+        match self.service.lock(dataset, LockOptions { unmount }) {
+            Ok(report) => {
+                if report.already_locked {
+                    let message = format!("{} is already locked", report.encryption_root);
+                    self.record(WorkflowLevel::Info, message.clone());
+                    self.set_status(message);
+                } else {
+                    let message = format!(
+                        "Locked {} ({} datasets)",
+                        report.encryption_root,
+                        report.locked.len()
+                    );
+                    self.record(WorkflowLevel::Success, message.clone());
+                    self.set_status(message);
+                }
+                self.refresh_status()?;
+            }
+            Err(err) => {
+                self.record(WorkflowLevel::Error, format!("lock {dataset}: {err}"));
+                self.last_error = Some(err.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Open the masked passphrase dialog for the selected dataset.
+    fn prompt_passphrase(&mut self) {
+        let Some(entry) = self.require_selection() else {
+            return;
         };
+        self.modal = Some(Modal::Passphrase {
+            dataset: entry.dataset,
+            input: Zeroizing::new(String::new()),
+        });
+    }
 
-        let mut options = UnlockOptions::default();
-        options.strict_usb = self.strict_usb;
-        options.fallback_passphrase = Some(passphrase);
+    /// Retry the unlock for `dataset` with a fallback passphrase.
+    fn unlock_with_passphrase(&mut self, dataset: &str, passphrase: String) -> Result<()> {
+        let Some(options) = self.unlock_options(dataset) else {
+            return Ok(());
+        };
+        let options = UnlockOptions {
+            fallback_passphrase: Some(passphrase),
+            ..options
+        };
 
-        match self.service.unlock_with_retry(&dataset, options) {
+        match self.unlock_logged(dataset, options) {
             Ok(report) => {
                 if report.already_unlocked {
                     self.set_status("Dataset already unlocked");
@@ -249,80 +1170,262 @@ impl App {
                 [
                     Constraint::Length(3),
                     Constraint::Min(5),
+                    Constraint::Length(8),
                     Constraint::Length(3),
                 ]
                 .as_ref(),
             )
             .split(size);
 
-        let header = Paragraph::new(vec![Line::from(vec![
-            Span::styled(
-                "LockChain :: TUI",
-                Style::default()
-                    .fg(Color::Cyan)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::raw(
-                "  q:quit  ↑/↓:select  enter:unlock  r:refresh  s:strictUSB  p:passphrase  c:clear",
-            ),
-        ])])
-        .alignment(Alignment::Left)
-        .block(Block::default().borders(Borders::ALL));
+        let mut title = vec![Span::styled("LockChain :: TUI", self.theme.title)];
+        if let Some(profile) = &self.profile {
+            title.push(Span::styled(format!(" [{profile}]"), self.theme.profile));
+        }
+        title.push(self.key_chip());
+        let hints = [
+            (Action::Unlock, "unlock"),
+            (Action::Passphrase, "passphrase"),
+            (Action::Lock, "lock"),
+            (Action::Filter, "filter"),
+            (Action::Help, "help"),
+            (Action::Quit, "quit"),
+        ];
+        for (action, what) in hints {
+            title.push(Span::raw(format!(" {}:{what} ", self.keys.label(action))));
+        }
+        let header = Block::default().borders(Borders::ALL);
+        let header_row = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Min(0),
+                Constraint::Length(mouse::buttons_width()),
+            ])
+            .split(header.inner(chunks[0]));
         f.render_widget(header, chunks[0]);
+        f.render_widget(
+            Paragraph::new(vec![Line::from(title)]).alignment(Alignment::Left),
+            header_row[0],
+        );
+        let buttons = mouse::render_buttons(f, &self.theme, header_row[1]);
 
+        let visible = self.visible();
         let items: Vec<ListItem> = if self.datasets.is_empty() {
             vec![ListItem::new("No datasets configured")]
+        } else if visible.is_empty() {
+            vec![ListItem::new(format!(
+                "No dataset matches /{}",
+                self.filter
+            ))]
         } else {
-            self.datasets
+            visible
                 .iter()
-                .map(|entry| {
+                .map(|&index| {
+                    let entry = &self.datasets[index];
                     let status = match entry.state {
-                        KeyState::Available => {
-                            Span::styled("available", Style::default().fg(Color::Green))
-                        }
-                        KeyState::Unavailable => {
-                            Span::styled("locked", Style::default().fg(Color::Red))
-                        }
-                        KeyState::Unknown(ref v) => {
-                            Span::styled(v, Style::default().fg(Color::Yellow))
-                        }
+                        KeyState::Available => Span::styled("available", self.theme.ok),
+                        KeyState::Unavailable => Span::styled("locked", self.theme.error),
+                        KeyState::Unknown(ref v) => Span::styled(v, self.theme.warning),
                     };
-                    let line = vec![
-                        Span::styled(&entry.dataset, Style::default().fg(Color::White)),
+                    let mut line = vec![
+                        Span::styled(&entry.dataset, self.theme.dataset),
                         Span::raw("  →  "),
-                        Span::styled(&entry.encryption_root, Style::default().fg(Color::Magenta)),
+                        Span::styled(&entry.encryption_root, self.theme.encryption_root),
                         Span::raw("  ::  "),
                         status,
                     ];
+                    if let Some(summary) = self
+                        .dataset_options
+                        .get(&entry.dataset)
+                        .and_then(DatasetOptions::summary)
+                    {
+                        line.push(Span::styled(format!("  [{summary}]"), self.theme.muted));
+                    }
                     ListItem::new(Line::from(line))
                 })
                 .collect()
         };
 
+        let title = if self.editing_filter {
+            format!("Datasets  /{}▏", self.filter)
+        } else if !self.filter.is_empty() {
+            format!(
+                "Datasets  /{} ({} of {}, esc to clear)",
+                self.filter,
+                visible.len(),
+                self.datasets.len()
+            )
+        } else {
+            "Datasets".to_string()
+        };
         let list = List::new(items)
-            .block(Block::default().borders(Borders::ALL).title("Datasets"))
-            .highlight_style(Style::default().bg(Color::Blue).fg(Color::Black))
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(self.theme.selection)
             .highlight_symbol("▶ ");
         let mut state = ListState::default();
-        state.select(if self.datasets.is_empty() {
-            None
-        } else {
-            Some(self.selected)
-        });
+        state.select(visible.iter().position(|&index| index == self.selected));
         f.render_stateful_widget(list, chunks[1], &mut state);
 
-        let footer = if let Some(ref msg) = self.status_message {
-            Paragraph::new(msg.as_str()).style(Style::default().fg(Color::Cyan))
+        self.render_log(f, chunks[2]);
+        *self.mouse.borrow_mut() = MouseMap {
+            list: Block::default().borders(Borders::ALL).inner(chunks[1]),
+            list_offset: state.offset(),
+            log: chunks[2],
+            buttons,
+        };
+
+        let footer = if let Some((level, text)) = &self.banner {
+            Paragraph::new(text.as_str()).style(self.theme.banner(*level))
+        } else if let Some(job) = &self.job {
+            Paragraph::new(format!("Running {}…", job.name)).style(self.theme.accent)
+        } else if let Some(ref msg) = self.status_message {
+            Paragraph::new(msg.as_str()).style(self.theme.accent)
         } else if let Some(ref err) = self.last_error {
-            Paragraph::new(err.as_str()).style(Style::default().fg(Color::Red))
+            Paragraph::new(err.as_str()).style(self.theme.error)
         } else if self.strict_usb {
-            Paragraph::new("Strict USB mode enabled").style(Style::default().fg(Color::Yellow))
+            Paragraph::new("Strict USB mode enabled").style(self.theme.warning)
         } else {
-            Paragraph::new("Ready").style(Style::default().fg(Color::Green))
+            Paragraph::new("Ready").style(self.theme.ok)
         };
+        let freshness = Title::from(self.freshness())
+            .alignment(Alignment::Right)
+            .position(Position::Bottom);
         f.render_widget(
-            footer.block(Block::default().borders(Borders::ALL)),
-            chunks[2],
+            footer.block(Block::default().borders(Borders::ALL).title(freshness)),
+            chunks[3],
         );
+
+        if let (Some(view), Some(report)) = (&self.report_view, &self.report) {
+            view.render(f, report, &self.keys, &self.theme, size);
+        }
+        if let Some(modal) = &self.modal {
+            render_modal(f, modal, &self.keys, &self.theme, size);
+        }
+    }
+
+    /// Draw the newest log lines that fit, or the scrolled-back window.
+    fn render_log(&self, f: &mut Frame<'_>, area: Rect) {
+        let height = area.height.saturating_sub(2) as usize;
+        let end = self.log.len().saturating_sub(self.log_scroll);
+        let start = end.saturating_sub(height);
+        let lines: Vec<Line> = self
+            .log
+            .range(start..end)
+            .map(|entry| {
+                Line::from(vec![
+                    Span::styled(entry.at.format("%H:%M:%S ").to_string(), self.theme.muted),
+                    Span::styled(
+                        format!("{:<4} ", level_tag(entry.level)),
+                        self.theme.level(entry.level),
+                    ),
+                    Span::raw(entry.message.as_str()),
+                ])
+            })
+            .collect();
+        let title = if self.log_scroll > 0 {
+            format!(
+                "Log (-{} lines, {} to follow)",
+                self.log_scroll,
+                self.keys.label(Action::LogFollow)
+            )
+        } else {
+            format!(
+                "Log ({}/{} to scroll)",
+                self.keys.label(Action::LogUp),
+                self.keys.label(Action::LogDown)
+            )
+        };
+        f.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)),
+            area,
+        );
+    }
+}
+
+/// Short tag for a log panel line.
+fn level_tag(level: WorkflowLevel) -> &'static str {
+    match level {
+        WorkflowLevel::Debug => "DBG",
+        WorkflowLevel::Info => "INFO",
+        WorkflowLevel::Success => "OK",
+        WorkflowLevel::Warn => "WARN",
+        WorkflowLevel::Error => "ERR",
+        WorkflowLevel::Security => "SEC",
+    }
+}
+
+/// Draw `modal` centred over whatever is on screen.
+fn render_modal(f: &mut Frame<'_>, modal: &Modal, keys: &KeyMap, theme: &Theme, size: Rect) {
+    let (title, lines) = match modal {
+        Modal::ConfirmLock {
+            dataset,
+            encryption_root,
+        } => (
+            "Lock dataset",
+            vec![
+                Line::from(format!("Unload the key for {encryption_root}?")),
+                Line::from(format!(
+                    "{dataset} and every dataset sharing its key become unreadable."
+                )),
+                Line::from(""),
+                Line::from("y: lock   u: unmount, then lock   n/esc: cancel"),
+            ],
+        ),
+        Modal::Help => {
+            let help = keys.help();
+            let width = help.iter().map(|(label, _)| label.chars().count()).max();
+            let mut lines: Vec<Line> = help
+                .into_iter()
+                .map(|(label, what)| {
+                    Line::from(vec![
+                        Span::styled(
+                            format!("{label:<width$}  ", width = width.unwrap_or(0)),
+                            theme.accent,
+                        ),
+                        Span::raw(what),
+                    ])
+                })
+                .collect();
+            lines.push(Line::from(""));
+            lines.push(Line::from(
+                "Remap keys under [tui.keys]. Press any key to close.",
+            ));
+            ("Keys", lines)
+        }
+        Modal::Options(form) => ("Unlock options", form.lines(theme)),
+        Modal::Passphrase { dataset, input } => (
+            "Fallback passphrase",
+            vec![
+                Line::from(format!("Passphrase for {dataset}:")),
+                Line::from(Span::styled(
+                    format!("{}▏", "•".repeat(input.chars().count())),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )),
+                Line::from(""),
+                Line::from("enter: unlock   ctrl-u: clear   esc: cancel"),
+            ],
+        ),
+    };
+    let area = centered(size, 64, lines.len() as u16 + 2);
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_style(theme.warning),
+        ),
+        area,
+    );
+}
+
+/// A `width` × `height` rectangle centred in `outer`, clamped to fit.
+fn centered(outer: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(outer.width);
+    let height = height.min(outer.height);
+    Rect {
+        x: outer.x + (outer.width - width) / 2,
+        y: outer.y + (outer.height - height) / 2,
+        width,
+        height,
     }
 }
@@ -0,0 +1,222 @@
+//! Status exports: the dataset list and recent events as JSON and Markdown,
+//! for pasting into incident channels.
+
+use super::{level_tag, LogEntry};
+use chrono::{DateTime, Local};
+use lockchain_core::{
+    provider::{DatasetKeyDescriptor, KeyState},
+    workflow::WorkflowLevel,
+};
+use serde::Serialize;
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+};
+
+/// Log lines included in an export, newest last.
+pub(super) const EXPORT_EVENTS: usize = 100;
+
+/// Everything an export records about the estate.
+#[derive(Serialize)]
+pub(super) struct StatusExport<'a> {
+    #[serde(serialize_with = "rfc3339")]
+    pub(super) generated_at: DateTime<Local>,
+    pub(super) config: &'a Path,
+    pub(super) profile: Option<&'a str>,
+    pub(super) strict_usb: bool,
+    /// Key files found by the watcher, out of `key_files`.
+    pub(super) keys_present: Option<usize>,
+    pub(super) key_files: usize,
+    /// Seconds since keystatus was last read.
+    pub(super) refreshed_secs_ago: u64,
+    pub(super) refresh_error: Option<&'a str>,
+    pub(super) datasets: &'a [DatasetKeyDescriptor],
+    pub(super) banner: Option<ExportEvent<'a>>,
+    pub(super) events: Vec<ExportEvent<'a>>,
+}
+
+#[derive(Serialize)]
+pub(super) struct ExportEvent<'a> {
+    #[serde(serialize_with = "rfc3339", skip_serializing_if = "Option::is_none")]
+    pub(super) at: Option<DateTime<Local>>,
+    pub(super) level: WorkflowLevel,
+    pub(super) message: &'a str,
+}
+
+impl<'a> From<&'a LogEntry> for ExportEvent<'a> {
+    fn from(entry: &'a LogEntry) -> Self {
+        Self {
+            at: Some(entry.at),
+            level: entry.level,
+            message: &entry.message,
+        }
+    }
+}
+
+fn rfc3339<S, T>(at: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    T: Clone + Into<Option<DateTime<Local>>>,
+{
+    match at.clone().into() {
+        Some(at) => serializer.serialize_str(&at.to_rfc3339()),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl StatusExport<'_> {
+    /// Write `lockchain-status-<timestamp>.json` and `.md` into `dir`,
+    /// readable by the owner only; returns both paths.
+    pub(super) fn write(&self, dir: &Path) -> io::Result<(PathBuf, PathBuf)> {
+        let stem = format!(
+            "lockchain-status-{}",
+            self.generated_at.format("%Y%m%d-%H%M%S")
+        );
+        let json = dir.join(format!("{stem}.json"));
+        let markdown = dir.join(format!("{stem}.md"));
+        let mut body = serde_json::to_vec_pretty(self)?;
+        body.push(b'\n');
+        create_private(&json)?.write_all(&body)?;
+        create_private(&markdown)?.write_all(self.markdown().as_bytes())?;
+        Ok((json, markdown))
+    }
+
+    fn markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# LockChain status, {}\n",
+            self.generated_at.format("%Y-%m-%d %H:%M:%S %:z")
+        );
+        let _ = writeln!(out, "- Config: `{}`", self.config.display());
+        if let Some(profile) = self.profile {
+            let _ = writeln!(out, "- Profile: {profile}");
+        }
+        let keys = match self.keys_present {
+            Some(present) => format!("{present} of {} present", self.key_files),
+            None => "not checked yet".to_string(),
+        };
+        let _ = writeln!(out, "- Key files: {keys}");
+        let _ = writeln!(
+            out,
+            "- Strict USB: {}",
+            if self.strict_usb { "on" } else { "off" }
+        );
+        let _ = write!(out, "- Keystatus read {}s ago", self.refreshed_secs_ago);
+        match self.refresh_error {
+            Some(err) => {
+                let _ = writeln!(out, " (stale: {err})");
+            }
+            None => out.push('\n'),
+        }
+        if let Some(banner) = &self.banner {
+            let _ = writeln!(
+                out,
+                "- Last diagnostics: {} {}",
+                level_tag(banner.level),
+                banner.message
+            );
+        }
+
+        out.push_str("\n## Datasets\n\n");
+        if self.datasets.is_empty() {
+            out.push_str("No datasets configured.\n");
+        } else {
+            out.push_str("| Dataset | Encryption root | Key | Mounted |\n");
+            out.push_str("|---|---|---|---|\n");
+            for entry in self.datasets {
+                let state = match &entry.state {
+                    KeyState::Available => "available",
+                    KeyState::Unavailable => "locked",
+                    KeyState::Unknown(value) => value.as_str(),
+                };
+                let mounted = match entry.mounted {
+                    Some(true) => "yes",
+                    Some(false) => "no",
+                    None => "-",
+                };
+                let _ = writeln!(
+                    out,
+                    "| `{}` | `{}` | {state} | {mounted} |",
+                    entry.dataset, entry.encryption_root
+                );
+            }
+        }
+
+        out.push_str("\n## Recent events\n\n");
+        if self.events.is_empty() {
+            out.push_str("None.\n");
+        } else {
+            out.push_str("```\n");
+            for event in &self.events {
+                let at = event
+                    .at
+                    .map(|at| at.format("%H:%M:%S ").to_string())
+                    .unwrap_or_default();
+                let _ = writeln!(out, "{at}{:<4} {}", level_tag(event.level), event.message);
+            }
+            out.push_str("```\n");
+        }
+        out
+    }
+}
+
+fn create_private(path: &Path) -> io::Result<fs::File> {
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_lists_datasets_and_events() {
+        let entry = LogEntry {
+            at: Local::now(),
+            level: WorkflowLevel::Warn,
+            message: "key token removed".into(),
+        };
+        let datasets = [DatasetKeyDescriptor {
+            dataset: "tank/secure".into(),
+            encryption_root: "tank/secure".into(),
+            state: KeyState::Unavailable,
+            mounted: Some(false),
+            keyformat: Some("raw".into()),
+            keylocation: None,
+        }];
+        let export = StatusExport {
+            generated_at: Local::now(),
+            config: Path::new("/etc/lockchain-zfs.toml"),
+            profile: Some("site-a"),
+            strict_usb: false,
+            keys_present: Some(0),
+            key_files: 1,
+            refreshed_secs_ago: 4,
+            refresh_error: None,
+            datasets: &datasets,
+            banner: None,
+            events: vec![ExportEvent::from(&entry)],
+        };
+        let markdown = export.markdown();
+        assert!(
+            markdown.contains("- Key files: 0 of 1 present"),
+            "{markdown}"
+        );
+        assert!(
+            markdown.contains("| `tank/secure` | `tank/secure` | locked | no |"),
+            "{markdown}"
+        );
+        assert!(markdown.contains("WARN key token removed"), "{markdown}");
+
+        let json: serde_json::Value = serde_json::to_value(&export).unwrap();
+        assert_eq!(json["datasets"][0]["state"], "unavailable");
+        assert_eq!(json["events"][0]["level"], "warn");
+    }
+}
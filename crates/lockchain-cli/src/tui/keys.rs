@@ -0,0 +1,290 @@
+//! Key bindings for the TUI: built-in defaults, replaced per action by `[tui.keys]`.
+
+use crossterm::event::KeyCode;
+use lockchain_core::{config::TuiCfg, LockchainError, LockchainResult};
+
+/// Something the dataset screen can do in response to a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Action {
+    Quit,
+    Up,
+    Down,
+    Filter,
+    Unlock,
+    Passphrase,
+    Lock,
+    Options,
+    Refresh,
+    StrictUsb,
+    Doctor,
+    SelfHeal,
+    Report,
+    Export,
+    Clear,
+    LogUp,
+    LogDown,
+    LogFollow,
+    Help,
+}
+
+impl Action {
+    const ALL: [Action; 19] = [
+        Action::Quit,
+        Action::Up,
+        Action::Down,
+        Action::Filter,
+        Action::Unlock,
+        Action::Passphrase,
+        Action::Lock,
+        Action::Options,
+        Action::Refresh,
+        Action::StrictUsb,
+        Action::Doctor,
+        Action::SelfHeal,
+        Action::Report,
+        Action::Export,
+        Action::Clear,
+        Action::LogUp,
+        Action::LogDown,
+        Action::LogFollow,
+        Action::Help,
+    ];
+
+    /// Name used under `[tui.keys]`.
+    fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Filter => "filter",
+            Action::Unlock => "unlock",
+            Action::Passphrase => "passphrase",
+            Action::Lock => "lock",
+            Action::Options => "options",
+            Action::Refresh => "refresh",
+            Action::StrictUsb => "strict_usb",
+            Action::Doctor => "doctor",
+            Action::SelfHeal => "self_heal",
+            Action::Report => "report",
+            Action::Export => "export",
+            Action::Clear => "clear",
+            Action::LogUp => "log_up",
+            Action::LogDown => "log_down",
+            Action::LogFollow => "log_follow",
+            Action::Help => "help",
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Action::Quit => "quit (esc clears an active filter first)",
+            Action::Up => "select previous dataset",
+            Action::Down => "select next dataset",
+            Action::Filter => "filter datasets by name or encryption root",
+            Action::Unlock => "unlock with the key file",
+            Action::Passphrase => "unlock with the fallback passphrase",
+            Action::Lock => "lock (unload key) after confirmation",
+            Action::Options => "edit unlock options for the selected dataset",
+            Action::Refresh => "refresh keystatus now",
+            Action::StrictUsb => "toggle strict USB mode",
+            Action::Doctor => "run a check-only doctor",
+            Action::SelfHeal => "run self-heal",
+            Action::Report => "browse the last doctor or self-heal report",
+            Action::Export => "write status and recent events to JSON and Markdown",
+            Action::Clear => "clear errors and banners",
+            Action::LogUp => "scroll the log back",
+            Action::LogDown => "scroll the log forward",
+            Action::LogFollow => "follow the newest log line",
+            Action::Help => "show this help",
+        }
+    }
+
+    fn defaults(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q", "Esc"],
+            Action::Up => &["Up", "k"],
+            Action::Down => &["Down", "j"],
+            Action::Filter => &["/"],
+            Action::Unlock => &["Enter"],
+            Action::Passphrase => &["p"],
+            Action::Lock => &["l"],
+            Action::Options => &["o"],
+            Action::Refresh => &["r"],
+            Action::StrictUsb => &["s"],
+            Action::Doctor => &["d"],
+            Action::SelfHeal => &["h"],
+            Action::Report => &["v"],
+            Action::Export => &["e"],
+            Action::Clear => &["c"],
+            Action::LogUp => &["PageUp"],
+            Action::LogDown => &["PageDown"],
+            Action::LogFollow => &["End"],
+            Action::Help => &["?"],
+        }
+    }
+}
+
+/// Keys bound to every [`Action`].
+pub(super) struct KeyMap {
+    bindings: Vec<(Action, Vec<KeyCode>)>,
+}
+
+impl KeyMap {
+    /// Defaults with `[tui.keys]` applied; unknown actions or keys, and a key
+    /// bound to two actions, are configuration errors.
+    pub(super) fn from_config(cfg: &TuiCfg) -> LockchainResult<Self> {
+        for action in cfg.keys.keys() {
+            if !Action::ALL.iter().any(|known| known.name() == action) {
+                let names: Vec<&str> = Action::ALL.iter().map(|known| known.name()).collect();
+                return Err(LockchainError::InvalidConfig(format!(
+                    "tui.keys.{action} is not a TUI action; expected one of {}",
+                    names.join(", ")
+                )));
+            }
+        }
+
+        let mut bindings: Vec<(Action, Vec<KeyCode>)> = Vec::new();
+        for action in Action::ALL {
+            let specs: Vec<&str> = match cfg.keys.get(action.name()) {
+                Some(specs) => specs.iter().map(String::as_str).collect(),
+                None => action.defaults().to_vec(),
+            };
+            let mut codes = Vec::new();
+            for spec in specs {
+                let code = parse_key(spec).ok_or_else(|| {
+                    LockchainError::InvalidConfig(format!(
+                        "tui.keys.{}: `{spec}` is not a key; use a single character or a name such as Enter, Esc, PageUp, or F1",
+                        action.name()
+                    ))
+                })?;
+                if let Some((other, _)) = bindings.iter().find(|(_, bound)| bound.contains(&code)) {
+                    return Err(LockchainError::InvalidConfig(format!(
+                        "tui.keys: `{spec}` is bound to both {} and {}; rebind one of them",
+                        other.name(),
+                        action.name()
+                    )));
+                }
+                codes.push(code);
+            }
+            bindings.push((action, codes));
+        }
+        Ok(Self { bindings })
+    }
+
+    /// Action bound to `code`, if any.
+    pub(super) fn action(&self, code: KeyCode) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, codes)| codes.contains(&code))
+            .map(|(action, _)| *action)
+    }
+
+    /// Keys for `action` as shown on screen, e.g. `↑/k`.
+    pub(super) fn label(&self, action: Action) -> String {
+        let codes = self
+            .bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map(|(_, codes)| codes.as_slice())
+            .unwrap_or_default();
+        if codes.is_empty() {
+            return "unbound".into();
+        }
+        codes
+            .iter()
+            .map(|code| key_label(*code))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Every action with its keys and description, for the help overlay.
+    pub(super) fn help(&self) -> Vec<(String, &'static str)> {
+        Action::ALL
+            .iter()
+            .map(|action| (self.label(*action), action.describe()))
+            .collect()
+    }
+}
+
+fn parse_key(spec: &str) -> Option<KeyCode> {
+    let mut chars = spec.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    let lower = spec.to_ascii_lowercase();
+    let code = match lower.as_str() {
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "space" => KeyCode::Char(' '),
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "pageup" | "pgup" => KeyCode::PageUp,
+        "pagedown" | "pgdn" => KeyCode::PageDown,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        other => {
+            let number = other.strip_prefix('f')?.parse::<u8>().ok()?;
+            if !(1..=12).contains(&number) {
+                return None;
+            }
+            KeyCode::F(number)
+        }
+    };
+    Some(code)
+}
+
+fn key_label(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "space".into(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Enter => "enter".into(),
+        KeyCode::Esc => "esc".into(),
+        KeyCode::Tab => "tab".into(),
+        KeyCode::Backspace => "backspace".into(),
+        KeyCode::Delete => "del".into(),
+        KeyCode::Up => "↑".into(),
+        KeyCode::Down => "↓".into(),
+        KeyCode::Left => "←".into(),
+        KeyCode::Right => "→".into(),
+        KeyCode::PageUp => "pgup".into(),
+        KeyCode::PageDown => "pgdn".into(),
+        KeyCode::Home => "home".into(),
+        KeyCode::End => "end".into(),
+        KeyCode::F(number) => format!("F{number}"),
+        other => format!("{other:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_replace_defaults_and_conflicts_are_rejected() {
+        let mut cfg = TuiCfg::default();
+        cfg.keys
+            .insert("down".into(), vec!["Down".into(), "n".into()]);
+        cfg.keys.insert("up".into(), vec!["Up".into(), "i".into()]);
+        let keys = KeyMap::from_config(&cfg).unwrap();
+        assert_eq!(keys.action(KeyCode::Char('n')), Some(Action::Down));
+        assert_eq!(keys.action(KeyCode::Char('j')), None);
+        assert_eq!(keys.action(KeyCode::Esc), Some(Action::Quit));
+        assert_eq!(keys.label(Action::Up), "↑/i");
+
+        cfg.keys.insert("up".into(), vec!["l".into()]);
+        let err = KeyMap::from_config(&cfg).err().unwrap().to_string();
+        assert!(err.contains("both up and lock"), "{err}");
+
+        cfg.keys.clear();
+        cfg.keys.insert("jump".into(), vec!["g".into()]);
+        assert!(KeyMap::from_config(&cfg).is_err());
+        cfg.keys.clear();
+        cfg.keys.insert("help".into(), vec!["F13".into()]);
+        assert!(KeyMap::from_config(&cfg).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+//! Where the last frame drew clickable things, so mouse events can find them.
+
+use super::{keys::Action, theme::Theme};
+use ratatui::{
+    prelude::{Frame, Rect},
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+
+/// Header buttons, left to right.
+const BUTTONS: [(Action, &str); 3] = [
+    (Action::Unlock, "Unlock"),
+    (Action::Refresh, "Refresh"),
+    (Action::Quit, "Quit"),
+];
+
+/// What sits under the pointer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Target {
+    Button(Action),
+    /// Position among the visible dataset rows.
+    Row(usize),
+    /// The dataset list below its last row.
+    List,
+    Log,
+    Nothing,
+}
+
+/// Hit areas recorded while rendering.
+#[derive(Debug, Default)]
+pub(super) struct MouseMap {
+    /// Rows of the dataset list, inside its border.
+    pub(super) list: Rect,
+    /// Visible rows scrolled off the top of the list.
+    pub(super) list_offset: usize,
+    pub(super) log: Rect,
+    pub(super) buttons: Vec<(Rect, Action)>,
+}
+
+impl MouseMap {
+    pub(super) fn target(&self, column: u16, row: u16) -> Target {
+        let at = |area: Rect| {
+            column >= area.x
+                && column < area.x + area.width
+                && row >= area.y
+                && row < area.y + area.height
+        };
+        if let Some((_, action)) = self.buttons.iter().find(|(area, _)| at(*area)) {
+            return Target::Button(*action);
+        }
+        if at(self.list) {
+            return Target::Row(self.list_offset + (row - self.list.y) as usize);
+        }
+        if at(Rect {
+            x: self.list.x.saturating_sub(1),
+            y: self.list.y.saturating_sub(1),
+            width: self.list.width + 2,
+            height: self.list.height + 2,
+        }) {
+            return Target::List;
+        }
+        if at(self.log) {
+            return Target::Log;
+        }
+        Target::Nothing
+    }
+}
+
+/// Width of the header buttons, spaces included.
+pub(super) fn buttons_width() -> u16 {
+    BUTTONS
+        .iter()
+        .map(|(_, label)| label.len() as u16 + 3)
+        .sum()
+}
+
+/// Draw the header buttons left-aligned in `area` and return where each landed.
+pub(super) fn render_buttons(f: &mut Frame<'_>, theme: &Theme, area: Rect) -> Vec<(Rect, Action)> {
+    let mut spans = Vec::new();
+    let mut hits = Vec::new();
+    let mut x = area.x;
+    for (action, label) in BUTTONS {
+        let width = label.len() as u16 + 2;
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(format!("[{label}]"), theme.selection));
+        hits.push((
+            Rect {
+                x: x + 1,
+                y: area.y,
+                width,
+                height: 1,
+            },
+            action,
+        ));
+        x += width + 1;
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clicks_land_on_buttons_rows_and_panels() {
+        let map = MouseMap {
+            list: Rect::new(2, 5, 40, 4),
+            list_offset: 3,
+            log: Rect::new(1, 11, 42, 8),
+            buttons: vec![(Rect::new(30, 2, 8, 1), Action::Unlock)],
+        };
+        assert_eq!(map.target(33, 2), Target::Button(Action::Unlock));
+        assert_eq!(map.target(2, 5), Target::Row(3));
+        assert_eq!(map.target(41, 8), Target::Row(6));
+        assert_eq!(map.target(1, 4), Target::List);
+        assert_eq!(map.target(10, 15), Target::Log);
+        assert_eq!(map.target(60, 15), Target::Nothing);
+    }
+}
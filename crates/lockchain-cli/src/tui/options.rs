@@ -0,0 +1,225 @@
+//! Per-dataset unlock options, edited in a small form attached to the selection.
+
+use super::theme::Theme;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use lockchain_core::service::UnlockOptions;
+use lockchain_core::SecretBuffer;
+use ratatui::text::{Line, Span};
+use std::{fs, path::PathBuf};
+use zeroize::Zeroizing;
+
+/// Unlock tweaks for one dataset, kept until the TUI exits.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(super) struct DatasetOptions {
+    pub(super) strict_usb: bool,
+    /// Raw 32-byte key to use instead of the configured key source.
+    pub(super) key_file: Option<PathBuf>,
+    pub(super) mount: bool,
+}
+
+impl DatasetOptions {
+    /// Options for an unlock of this dataset; the screen-wide strict USB
+    /// toggle still applies on top.
+    pub(super) fn unlock_options(&self, strict_usb: bool) -> Result<UnlockOptions, String> {
+        let mut options = UnlockOptions {
+            strict_usb: strict_usb || self.strict_usb,
+            mount: self.mount,
+            ..UnlockOptions::default()
+        };
+        if let Some(path) = &self.key_file {
+            let key = Zeroizing::new(
+                fs::read(path).map_err(|err| format!("read key file {}: {err}", path.display()))?,
+            );
+            if key.len() != 32 {
+                return Err(format!(
+                    "expected a 32-byte raw key in {}, found {} bytes",
+                    path.display(),
+                    key.len()
+                ));
+            }
+            options.key_override = Some(SecretBuffer::from_slice(&key));
+        }
+        Ok(options)
+    }
+
+    /// Note for the dataset row, e.g. `strict usb, mount`; `None` when
+    /// nothing differs from the defaults.
+    pub(super) fn summary(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.strict_usb {
+            parts.push("strict usb");
+        }
+        if self.key_file.is_some() {
+            parts.push("key file");
+        }
+        if self.mount {
+            parts.push("mount");
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    StrictUsb,
+    KeyFile,
+    Mount,
+}
+
+impl Field {
+    const ALL: [Field; 3] = [Field::StrictUsb, Field::KeyFile, Field::Mount];
+
+    fn step(self, by: isize) -> Self {
+        let at = Self::ALL
+            .iter()
+            .position(|field| *field == self)
+            .unwrap_or(0);
+        let len = Self::ALL.len() as isize;
+        Self::ALL[(at as isize + by).rem_euclid(len) as usize]
+    }
+}
+
+/// What a key press did to the form.
+pub(super) enum FormOutcome {
+    Editing,
+    Cancelled,
+    Saved(DatasetOptions),
+}
+
+/// The open options form for `dataset`.
+pub(super) struct OptionsForm {
+    pub(super) dataset: String,
+    field: Field,
+    strict_usb: bool,
+    key_file: String,
+    mount: bool,
+}
+
+impl OptionsForm {
+    pub(super) fn new(dataset: String, current: &DatasetOptions) -> Self {
+        Self {
+            dataset,
+            field: Field::StrictUsb,
+            strict_usb: current.strict_usb,
+            key_file: current
+                .key_file
+                .as_ref()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+            mount: current.mount,
+        }
+    }
+
+    /// Move between fields, toggle, or edit the key file path.
+    pub(super) fn handle(&mut self, key: KeyEvent) -> FormOutcome {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let editing_path = self.field == Field::KeyFile;
+        match key.code {
+            KeyCode::Esc => return FormOutcome::Cancelled,
+            KeyCode::Enter => return FormOutcome::Saved(self.options()),
+            KeyCode::Tab | KeyCode::Down => self.field = self.field.step(1),
+            KeyCode::BackTab | KeyCode::Up => self.field = self.field.step(-1),
+            KeyCode::Char(' ') if !editing_path => match self.field {
+                Field::StrictUsb => self.strict_usb = !self.strict_usb,
+                Field::Mount => self.mount = !self.mount,
+                Field::KeyFile => {}
+            },
+            KeyCode::Char('u') if ctrl && editing_path => self.key_file.clear(),
+            KeyCode::Backspace if editing_path => {
+                self.key_file.pop();
+            }
+            KeyCode::Char(c) if !ctrl && editing_path => self.key_file.push(c),
+            _ => {}
+        }
+        FormOutcome::Editing
+    }
+
+    fn options(&self) -> DatasetOptions {
+        let key_file = self.key_file.trim();
+        DatasetOptions {
+            strict_usb: self.strict_usb,
+            key_file: (!key_file.is_empty()).then(|| PathBuf::from(key_file)),
+            mount: self.mount,
+        }
+    }
+
+    /// Form body for the modal.
+    pub(super) fn lines(&self, theme: &Theme) -> Vec<Line<'static>> {
+        let marker = |field: Field| {
+            if self.field == field {
+                Span::styled("▶ ", theme.accent)
+            } else {
+                Span::raw("  ")
+            }
+        };
+        let check = |on: bool| if on { "[x]" } else { "[ ]" };
+        let path = if self.field == Field::KeyFile {
+            format!("{}▏", self.key_file)
+        } else if self.key_file.is_empty() {
+            "(configured key source)".to_string()
+        } else {
+            self.key_file.clone()
+        };
+        vec![
+            Line::from(format!("Unlock options for {}", self.dataset)),
+            Line::from(""),
+            Line::from(vec![
+                marker(Field::StrictUsb),
+                Span::raw(format!("{} strict USB", check(self.strict_usb))),
+            ]),
+            Line::from(vec![
+                marker(Field::KeyFile),
+                Span::raw("key file: "),
+                Span::styled(path, theme.dataset),
+            ]),
+            Line::from(vec![
+                marker(Field::Mount),
+                Span::raw(format!("{} mount after unlock", check(self.mount))),
+            ]),
+            Line::from(""),
+            Line::from("tab/↑↓: move   space: toggle   enter: save   esc: cancel"),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(form: &mut OptionsForm, code: KeyCode) -> FormOutcome {
+        form.handle(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn form_toggles_fields_and_reads_the_key_file() {
+        let mut form = OptionsForm::new("tank/secure".into(), &DatasetOptions::default());
+        press(&mut form, KeyCode::Char(' '));
+        press(&mut form, KeyCode::Tab);
+        for c in "/nonexistent/key.bin".chars() {
+            press(&mut form, KeyCode::Char(c));
+        }
+        press(&mut form, KeyCode::Up);
+        press(&mut form, KeyCode::Up);
+        press(&mut form, KeyCode::Char(' '));
+        let FormOutcome::Saved(options) = press(&mut form, KeyCode::Enter) else {
+            panic!("enter should save the form");
+        };
+        assert_eq!(
+            options,
+            DatasetOptions {
+                strict_usb: true,
+                key_file: Some(PathBuf::from("/nonexistent/key.bin")),
+                mount: true,
+            }
+        );
+        assert_eq!(
+            options.summary().as_deref(),
+            Some("strict usb, key file, mount")
+        );
+        let err = options.unlock_options(false).unwrap_err();
+        assert!(err.contains("/nonexistent/key.bin"), "{err}");
+
+        let plain = DatasetOptions::default().unlock_options(true).unwrap();
+        assert!(plain.strict_usb && !plain.mount && plain.key_override.is_none());
+    }
+}
@@ -0,0 +1,239 @@
+//! Full-screen browser for the events of the last doctor or self-heal run.
+
+use super::{
+    keys::{Action, KeyMap},
+    level_tag,
+    theme::Theme,
+    LogEntry,
+};
+use chrono::{DateTime, Local};
+use lockchain_core::workflow::WorkflowLevel;
+use ratatui::{
+    prelude::{Alignment, Frame, Rect},
+    text::{Line, Span},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Clear, Paragraph,
+    },
+};
+use std::cell::Cell;
+
+/// Every event a finished job emitted, with the banner it ended on.
+pub(super) struct Report {
+    pub(super) name: &'static str,
+    pub(super) finished_at: DateTime<Local>,
+    pub(super) summary: (WorkflowLevel, String),
+    pub(super) entries: Vec<LogEntry>,
+}
+
+/// Which events the report view lists; the filter key cycles through them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum LevelFilter {
+    All,
+    /// Everything but debug detail.
+    Info,
+    Warnings,
+    Errors,
+}
+
+impl LevelFilter {
+    fn shows(self, level: WorkflowLevel) -> bool {
+        match self {
+            LevelFilter::All => true,
+            LevelFilter::Info => level != WorkflowLevel::Debug,
+            LevelFilter::Warnings => matches!(
+                level,
+                WorkflowLevel::Warn | WorkflowLevel::Error | WorkflowLevel::Security
+            ),
+            LevelFilter::Errors => {
+                matches!(level, WorkflowLevel::Error | WorkflowLevel::Security)
+            }
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            LevelFilter::All => LevelFilter::Info,
+            LevelFilter::Info => LevelFilter::Warnings,
+            LevelFilter::Warnings => LevelFilter::Errors,
+            LevelFilter::Errors => LevelFilter::All,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            LevelFilter::All => "all levels",
+            LevelFilter::Info => "info and above",
+            LevelFilter::Warnings => "warnings and errors",
+            LevelFilter::Errors => "errors only",
+        }
+    }
+}
+
+/// Scroll position and filter while the report is open.
+pub(super) struct ReportView {
+    filter: LevelFilter,
+    /// First listed event shown at the top of the screen.
+    scroll: usize,
+    /// Rows drawn last frame, so paging and clamping match the screen.
+    page: Cell<usize>,
+}
+
+impl ReportView {
+    pub(super) fn new() -> Self {
+        Self {
+            filter: LevelFilter::Info,
+            scroll: 0,
+            page: Cell::new(1),
+        }
+    }
+
+    /// Apply a key press; returns false once the view should close.
+    pub(super) fn handle(&mut self, action: Option<Action>, report: &Report) -> bool {
+        let page = self.page.get().max(1);
+        match action {
+            Some(Action::Quit | Action::Report) => return false,
+            Some(Action::Up) => self.scroll = self.scroll.saturating_sub(1),
+            Some(Action::Down) => self.scroll += 1,
+            Some(Action::LogUp) => self.scroll = self.scroll.saturating_sub(page),
+            Some(Action::LogDown) => self.scroll += page,
+            Some(Action::LogFollow) => self.scroll = usize::MAX,
+            Some(Action::Filter) => {
+                self.filter = self.filter.next();
+                self.scroll = 0;
+            }
+            _ => {}
+        }
+        self.scroll = self.scroll.min(self.max_scroll(report));
+        true
+    }
+
+    fn max_scroll(&self, report: &Report) -> usize {
+        self.listed(report).len().saturating_sub(self.page.get())
+    }
+
+    fn listed<'a>(&self, report: &'a Report) -> Vec<&'a LogEntry> {
+        report
+            .entries
+            .iter()
+            .filter(|entry| self.filter.shows(entry.level))
+            .collect()
+    }
+
+    /// Draw `report` over the whole of `area`.
+    pub(super) fn render(
+        &self,
+        f: &mut Frame<'_>,
+        report: &Report,
+        keys: &KeyMap,
+        theme: &Theme,
+        area: Rect,
+    ) {
+        let page = area.height.saturating_sub(2) as usize;
+        self.page.set(page);
+        let listed = self.listed(report);
+        let start = self.scroll.min(listed.len().saturating_sub(page));
+        let lines: Vec<Line> = if listed.is_empty() {
+            vec![Line::from(Span::styled(
+                format!("No events at {}", self.filter.label()),
+                theme.muted,
+            ))]
+        } else {
+            listed
+                .iter()
+                .skip(start)
+                .take(page)
+                .map(|entry| {
+                    Line::from(vec![
+                        Span::styled(entry.at.format("%H:%M:%S ").to_string(), theme.muted),
+                        Span::styled(
+                            format!("{:<4} ", level_tag(entry.level)),
+                            theme.level(entry.level),
+                        ),
+                        Span::raw(entry.message.as_str()),
+                    ])
+                })
+                .collect()
+        };
+
+        let title = format!(
+            "{} report, finished {} :: {} ({} of {}, {} to change)",
+            report.name,
+            report.finished_at.format("%H:%M:%S"),
+            self.filter.label(),
+            listed.len(),
+            report.entries.len(),
+            keys.label(Action::Filter)
+        );
+        let position = if listed.len() > page {
+            format!(
+                " lines {}-{} of {} ({}/{} to scroll, {} to close) ",
+                start + 1,
+                (start + page).min(listed.len()),
+                listed.len(),
+                keys.label(Action::LogUp),
+                keys.label(Action::LogDown),
+                keys.label(Action::Quit)
+            )
+        } else {
+            format!(" {} to close ", keys.label(Action::Quit))
+        };
+        let (level, text) = &report.summary;
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title(
+                Title::from(Span::styled(format!(" {text} "), theme.banner(*level)))
+                    .position(Position::Bottom),
+            )
+            .title(
+                Title::from(Span::styled(position, theme.muted))
+                    .alignment(Alignment::Right)
+                    .position(Position::Bottom),
+            );
+        f.render_widget(Clear, area);
+        f.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(level: WorkflowLevel) -> LogEntry {
+        LogEntry {
+            at: Local::now(),
+            level,
+            message: String::new(),
+        }
+    }
+
+    #[test]
+    fn filter_cycles_levels_and_scroll_stays_on_screen() {
+        let report = Report {
+            name: "doctor",
+            finished_at: Local::now(),
+            summary: (WorkflowLevel::Warn, "doctor: 1 warnings".into()),
+            entries: vec![
+                entry(WorkflowLevel::Debug),
+                entry(WorkflowLevel::Info),
+                entry(WorkflowLevel::Success),
+                entry(WorkflowLevel::Warn),
+                entry(WorkflowLevel::Security),
+            ],
+        };
+        let mut view = ReportView::new();
+        assert_eq!(view.listed(&report).len(), 4);
+        view.page.set(2);
+        assert!(view.handle(Some(Action::LogFollow), &report));
+        assert_eq!(view.scroll, 2);
+        assert!(view.handle(Some(Action::Filter), &report));
+        assert_eq!(view.listed(&report).len(), 2);
+        assert_eq!(view.scroll, 0);
+        view.handle(Some(Action::Filter), &report);
+        assert_eq!(view.listed(&report).len(), 1);
+        view.handle(Some(Action::Filter), &report);
+        assert_eq!(view.listed(&report).len(), 5);
+        assert!(!view.handle(Some(Action::Quit), &report));
+    }
+}
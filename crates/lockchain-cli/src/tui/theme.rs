@@ -0,0 +1,237 @@
+//! Colour schemes for the TUI: a `[tui.theme]` preset plus per-element overrides.
+
+use lockchain_core::{
+    config::{ThemePreset, TuiTheme},
+    workflow::WorkflowLevel,
+    LockchainError, LockchainResult,
+};
+use ratatui::style::{Color, Modifier, Style};
+use std::env;
+
+/// Styles for every element the TUI draws.
+pub(super) struct Theme {
+    /// False in mono mode; banners then use reverse video instead of a fill.
+    color: bool,
+    pub(super) title: Style,
+    pub(super) profile: Style,
+    /// Key hints, running jobs, and transient status messages.
+    pub(super) accent: Style,
+    pub(super) dataset: Style,
+    pub(super) encryption_root: Style,
+    pub(super) ok: Style,
+    pub(super) warning: Style,
+    pub(super) error: Style,
+    pub(super) muted: Style,
+    pub(super) text: Style,
+    pub(super) security: Style,
+    pub(super) selection: Style,
+}
+
+impl Theme {
+    /// Build the configured theme; `NO_COLOR` forces [`ThemePreset::Mono`]
+    /// and drops colour overrides.
+    pub(super) fn from_config(cfg: &TuiTheme) -> LockchainResult<Self> {
+        let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        Self::build(cfg, no_color)
+    }
+
+    fn build(cfg: &TuiTheme, no_color: bool) -> LockchainResult<Self> {
+        let preset = if no_color {
+            ThemePreset::Mono
+        } else {
+            cfg.preset
+        };
+        let mut theme = Self::preset(preset);
+        if no_color {
+            return Ok(theme);
+        }
+        for (element, spec) in &cfg.colors {
+            let color = parse_color(spec).ok_or_else(|| {
+                LockchainError::InvalidConfig(format!(
+                    "tui.theme.colors.{element}: `{spec}` is not a colour; use a name such as lightblue, #rrggbb, or 0-255"
+                ))
+            })?;
+            let slot = theme.element(element).ok_or_else(|| {
+                LockchainError::InvalidConfig(format!(
+                    "tui.theme.colors.{element} is not a themed element; expected one of {}",
+                    ELEMENTS.join(", ")
+                ))
+            })?;
+            *slot = if element == "selection" {
+                slot.bg(color)
+            } else {
+                slot.fg(color)
+            };
+            theme.color = true;
+        }
+        Ok(theme)
+    }
+
+    fn preset(preset: ThemePreset) -> Self {
+        let fg = |color| Style::default().fg(color);
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        match preset {
+            ThemePreset::Default => Self {
+                color: true,
+                title: fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                profile: fg(Color::Yellow),
+                accent: fg(Color::Cyan),
+                dataset: fg(Color::White),
+                encryption_root: fg(Color::Magenta),
+                ok: fg(Color::Green),
+                warning: fg(Color::Yellow),
+                error: fg(Color::Red),
+                muted: fg(Color::DarkGray),
+                text: fg(Color::White),
+                security: fg(Color::Magenta),
+                selection: Style::default().bg(Color::Blue).fg(Color::Black),
+            },
+            ThemePreset::Light => Self {
+                color: true,
+                title: fg(Color::Blue).add_modifier(Modifier::BOLD),
+                profile: fg(Color::Magenta),
+                accent: fg(Color::Blue),
+                dataset: fg(Color::Black),
+                encryption_root: fg(Color::Magenta),
+                ok: fg(Color::Green),
+                warning: fg(Color::Rgb(0xb5, 0x89, 0x00)),
+                error: fg(Color::Red),
+                muted: fg(Color::Gray),
+                text: fg(Color::Black),
+                security: fg(Color::Magenta),
+                selection: Style::default().bg(Color::LightBlue).fg(Color::Black),
+            },
+            ThemePreset::HighContrast => Self {
+                color: true,
+                title: fg(Color::White).add_modifier(Modifier::BOLD),
+                profile: fg(Color::LightYellow).add_modifier(Modifier::BOLD),
+                accent: fg(Color::LightCyan).add_modifier(Modifier::BOLD),
+                dataset: fg(Color::White).add_modifier(Modifier::BOLD),
+                encryption_root: fg(Color::LightMagenta),
+                ok: fg(Color::LightGreen).add_modifier(Modifier::BOLD),
+                warning: fg(Color::LightYellow).add_modifier(Modifier::BOLD),
+                error: fg(Color::LightRed).add_modifier(Modifier::BOLD),
+                muted: fg(Color::Gray),
+                text: fg(Color::White),
+                security: fg(Color::LightMagenta).add_modifier(Modifier::BOLD),
+                selection: Style::default()
+                    .bg(Color::White)
+                    .fg(Color::Black)
+                    .add_modifier(Modifier::BOLD),
+            },
+            ThemePreset::Mono => Self {
+                color: false,
+                title: bold,
+                profile: bold,
+                accent: Style::default(),
+                dataset: Style::default(),
+                encryption_root: Style::default(),
+                ok: Style::default(),
+                warning: bold,
+                error: bold.add_modifier(Modifier::UNDERLINED),
+                muted: Style::default().add_modifier(Modifier::DIM),
+                text: Style::default(),
+                security: bold,
+                selection: Style::default().add_modifier(Modifier::REVERSED),
+            },
+        }
+    }
+
+    fn element(&mut self, name: &str) -> Option<&mut Style> {
+        Some(match name {
+            "title" => &mut self.title,
+            "profile" => &mut self.profile,
+            "accent" => &mut self.accent,
+            "dataset" => &mut self.dataset,
+            "encryption_root" => &mut self.encryption_root,
+            "ok" => &mut self.ok,
+            "warning" => &mut self.warning,
+            "error" => &mut self.error,
+            "muted" => &mut self.muted,
+            "text" => &mut self.text,
+            "security" => &mut self.security,
+            "selection" => &mut self.selection,
+            _ => return None,
+        })
+    }
+
+    /// Style for a log line or banner of `level`.
+    pub(super) fn level(&self, level: WorkflowLevel) -> Style {
+        match level {
+            WorkflowLevel::Debug => self.muted,
+            WorkflowLevel::Info => self.text,
+            WorkflowLevel::Success => self.ok,
+            WorkflowLevel::Warn => self.warning,
+            WorkflowLevel::Error => self.error,
+            WorkflowLevel::Security => self.security,
+        }
+    }
+
+    /// Filled bar for a summary banner of `level`.
+    pub(super) fn banner(&self, level: WorkflowLevel) -> Style {
+        let style = self.level(level);
+        match style.fg {
+            Some(color) if self.color => Style::default().fg(Color::Black).bg(color),
+            _ => style.add_modifier(Modifier::REVERSED),
+        }
+        .add_modifier(Modifier::BOLD)
+    }
+}
+
+const ELEMENTS: &[&str] = &[
+    "title",
+    "profile",
+    "accent",
+    "dataset",
+    "encryption_root",
+    "ok",
+    "warning",
+    "error",
+    "muted",
+    "text",
+    "security",
+    "selection",
+];
+
+fn parse_color(spec: &str) -> Option<Color> {
+    match spec.to_ascii_lowercase().as_str() {
+        "none" | "default" => Some(Color::Reset),
+        other => other.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_apply_over_presets() {
+        let mut cfg = TuiTheme {
+            preset: ThemePreset::Mono,
+            ..TuiTheme::default()
+        };
+        let mono = Theme::build(&cfg, false).unwrap();
+        assert_eq!(mono.error.fg, None);
+        assert!(mono
+            .banner(WorkflowLevel::Warn)
+            .add_modifier
+            .contains(Modifier::REVERSED));
+
+        cfg.colors
+            .insert("encryption_root".into(), "#336699".into());
+        cfg.colors.insert("selection".into(), "22".into());
+        let themed = Theme::build(&cfg, false).unwrap();
+        assert_eq!(
+            themed.encryption_root.fg,
+            Some(Color::Rgb(0x33, 0x66, 0x99))
+        );
+        assert_eq!(themed.selection.bg, Some(Color::Indexed(22)));
+        assert_eq!(Theme::build(&cfg, true).unwrap().encryption_root.fg, None);
+
+        cfg.colors.insert("sidebar".into(), "red".into());
+        assert!(Theme::build(&cfg, false).is_err());
+        cfg.colors.clear();
+        cfg.colors.insert("title".into(), "chartreuse-ish".into());
+        assert!(Theme::build(&cfg, false).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+//! Interactive `lockchain config init`: detect encryption roots and write a starting config.
+
+use anyhow::{bail, Context, Result};
+use lockchain_core::{config::RetryStrategy, LockchainConfig, ZfsProvider};
+use lockchain_zfs::SystemZfsProvider;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Label `lockchain init` writes to freshly forged tokens.
+const DEFAULT_USB_LABEL: &str = "LOCKCHAIN";
+
+/// Walk the operator through the essentials and save a validated config to `path`.
+pub fn run(path: &Path, force: bool) -> Result<()> {
+    if path.exists() && !force {
+        bail!(
+            "{} already exists; pass --force to overwrite it",
+            path.display()
+        );
+    }
+
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+
+    println!("Lockchain configuration wizard — press Enter to accept [defaults].");
+    let detected = detect_roots();
+    let datasets = loop {
+        let answer = ask(
+            &mut input,
+            "Datasets to manage (comma-separated)",
+            &detected.join(", "),
+        )?;
+        let datasets: Vec<String> = answer
+            .split(',')
+            .map(str::trim)
+            .filter(|ds| !ds.is_empty())
+            .map(str::to_string)
+            .collect();
+        if !datasets.is_empty() {
+            break datasets;
+        }
+        println!("  At least one dataset is required.");
+    };
+
+    let mut config = LockchainConfig::starter(path, datasets);
+    let label = ask(&mut input, "USB token label", DEFAULT_USB_LABEL)?;
+    config.usb.device_label = Some(label);
+
+    // Fallback material is derived from the key, so `lockchain init` writes it.
+    let fallback = ask_yes_no(
+        &mut input,
+        "Set a fallback passphrase when the token is provisioned?",
+        true,
+    )?;
+    config.fallback.enabled = false;
+
+    config.retry.max_attempts = loop {
+        let answer = ask(
+            &mut input,
+            "Unlock attempts before giving up",
+            &config.retry.max_attempts.to_string(),
+        )?;
+        match answer.parse::<u32>() {
+            Ok(attempts) if attempts > 0 => break attempts,
+            _ => println!("  Enter a whole number greater than zero."),
+        }
+    };
+    config.retry.strategy = loop {
+        let answer = ask(
+            &mut input,
+            "Retry strategy (fixed, exponential, exponential_full_jitter)",
+            "exponential",
+        )?;
+        match answer.parse::<RetryStrategy>() {
+            Ok(strategy) => break strategy,
+            Err(err) => println!("  {err}"),
+        }
+    };
+
+    let issues = config.validate();
+    for issue in issues.iter().filter(|issue| !issue.is_error()) {
+        println!("warning: {issue}");
+    }
+    let errors: Vec<_> = issues.iter().filter(|issue| issue.is_error()).collect();
+    if !errors.is_empty() {
+        for issue in errors {
+            eprintln!("  - {issue}");
+        }
+        bail!("configuration not written; fix the answers above and re-run");
+    }
+
+    if let Some(parent) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    config
+        .save()
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    println!("Wrote {}.", path.display());
+    let dataset = &config.policy.datasets[0];
+    if fallback {
+        println!(
+            "Next: lockchain -c {} init {dataset} --passphrase <secret>",
+            path.display()
+        );
+    } else {
+        println!("Next: lockchain -c {} init {dataset}", path.display());
+    }
+    Ok(())
+}
+
+/// Encryption roots visible to the local `zfs`, or none when detection fails.
+fn detect_roots() -> Vec<String> {
+    let roots = SystemZfsProvider::discover(Duration::from_secs(10))
+        .and_then(|provider| provider.encryption_roots());
+    match roots {
+        Ok(roots) if !roots.is_empty() => {
+            println!("Detected encryption roots: {}", roots.join(", "));
+            roots
+        }
+        Ok(_) => {
+            println!("No encrypted datasets detected; enter them manually.");
+            Vec::new()
+        }
+        Err(err) => {
+            println!("Could not query ZFS ({err}); enter datasets manually.");
+            Vec::new()
+        }
+    }
+}
+
+/// Prompt for a value, returning `default` on an empty answer.
+fn ask(input: &mut impl BufRead, question: &str, default: &str) -> Result<String> {
+    if default.is_empty() {
+        print!("{question}: ");
+    } else {
+        print!("{question} [{default}]: ");
+    }
+    io::stdout().flush().ok();
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        bail!("input closed before the wizard finished");
+    }
+    let answer = line.trim();
+    Ok(if answer.is_empty() {
+        default.to_string()
+    } else {
+        answer.to_string()
+    })
+}
+
+fn ask_yes_no(input: &mut impl BufRead, question: &str, default: bool) -> Result<bool> {
+    let hint = if default { "Y/n" } else { "y/N" };
+    loop {
+        let answer = ask(input, &format!("{question} ({hint})"), "")?;
+        match answer.to_ascii_lowercase().as_str() {
+            "" => return Ok(default),
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => println!("  Please answer y or n."),
+        }
+    }
+}
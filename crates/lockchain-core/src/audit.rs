@@ -0,0 +1,758 @@
+//! Append-only, hash-chained audit trail of key operations (JSON lines on disk).
+
+use crate::config::AuditCfg;
+use crate::error::{LockchainError, LockchainResult};
+use chrono::{DateTime, SecondsFormat, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// `prev_hash` of the very first record in a chain.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Bytes read from the end of the log when looking for the previous hash.
+const TAIL_WINDOW: u64 = 64 * 1024;
+
+/// Operation captured by an audit record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEvent {
+    Unlock,
+    Lock,
+    Breakglass,
+    Rotation,
+    Provision,
+    Escrow,
+}
+
+impl AuditEvent {
+    /// Stable identifier used in the JSON payload and CLI filters.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditEvent::Unlock => "unlock",
+            AuditEvent::Lock => "lock",
+            AuditEvent::Breakglass => "breakglass",
+            AuditEvent::Rotation => "rotation",
+            AuditEvent::Provision => "provision",
+            AuditEvent::Escrow => "escrow",
+        }
+    }
+}
+
+impl FromStr for AuditEvent {
+    type Err = LockchainError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        [
+            AuditEvent::Unlock,
+            AuditEvent::Lock,
+            AuditEvent::Breakglass,
+            AuditEvent::Rotation,
+            AuditEvent::Provision,
+            AuditEvent::Escrow,
+        ]
+        .into_iter()
+        .find(|event| event.as_str() == value)
+        .ok_or_else(|| {
+            LockchainError::InvalidConfig(format!(
+                "unknown audit event `{value}` (expected unlock, lock, breakglass, rotation, provision, or escrow)"
+            ))
+        })
+    }
+}
+
+/// Whether the audited operation succeeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditResult {
+    Success,
+    Failure,
+}
+
+/// Operation details supplied by callers; timestamps and hashes are added on write.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub event: AuditEvent,
+    pub dataset: String,
+    pub key_source: Option<String>,
+    pub result: AuditResult,
+    pub error_code: Option<String>,
+    pub detail: Option<String>,
+    /// How long the operation took, in milliseconds; absent from records
+    /// written before it was tracked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+impl AuditEntry {
+    /// Successful `event` against `dataset`.
+    pub fn success(event: AuditEvent, dataset: &str) -> Self {
+        Self {
+            event,
+            dataset: dataset.to_string(),
+            key_source: None,
+            result: AuditResult::Success,
+            error_code: None,
+            detail: None,
+            duration_ms: None,
+        }
+    }
+
+    /// Failed `event` against `dataset`, carrying the error's LC code.
+    pub fn failure(event: AuditEvent, dataset: &str, err: &LockchainError) -> Self {
+        Self {
+            result: AuditResult::Failure,
+            error_code: Some(err.code().to_string()),
+            detail: Some(err.to_string()),
+            ..Self::success(event, dataset)
+        }
+    }
+
+    /// Build a success or failure entry from an operation result.
+    pub fn from_result<T>(event: AuditEvent, dataset: &str, result: &LockchainResult<T>) -> Self {
+        match result {
+            Ok(_) => Self::success(event, dataset),
+            Err(err) => Self::failure(event, dataset, err),
+        }
+    }
+
+    /// Attach the key source that was used (or attempted).
+    pub fn with_key_source(mut self, source: Option<String>) -> Self {
+        self.key_source = source;
+        self
+    }
+
+    /// Attach free-form context.
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Attach how long the operation took.
+    pub fn with_duration(mut self, elapsed: Duration) -> Self {
+        self.duration_ms = Some(u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX));
+        self
+    }
+}
+
+/// A persisted line of the audit trail.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp: String,
+    pub actor: String,
+    #[serde(flatten)]
+    pub entry: AuditEntry,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditRecord {
+    /// When the record was written, if its timestamp parses.
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.timestamp)
+            .ok()
+            .map(|time| time.with_timezone(&Utc))
+    }
+
+    /// Hash binding this record's content to the previous record.
+    fn compute_hash(&self) -> String {
+        let body = serde_json::json!({
+            "timestamp": self.timestamp,
+            "actor": self.actor,
+            "entry": self.entry,
+        });
+        let mut hasher = Sha256::new();
+        hasher.update(self.prev_hash.as_bytes());
+        hasher.update(body.to_string().as_bytes());
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// Selection applied when reviewing the trail; unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    /// Dataset or any of its descendants.
+    pub dataset: Option<String>,
+    pub event: Option<AuditEvent>,
+    /// Records written at or after this instant.
+    pub since: Option<DateTime<Utc>>,
+}
+
+impl AuditFilter {
+    /// Whether `record` passes every criterion.
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        let dataset_ok = self.dataset.as_deref().is_none_or(|wanted| {
+            let dataset = record.entry.dataset.as_str();
+            dataset == wanted
+                || dataset
+                    .strip_prefix(wanted)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        });
+        let event_ok = self.event.is_none_or(|event| record.entry.event == event);
+        let since_ok = self
+            .since
+            .is_none_or(|since| record.time().is_some_and(|time| time >= since));
+        dataset_ok && event_ok && since_ok
+    }
+}
+
+/// One unlock or lock of a dataset, as recorded in the trail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistorySample {
+    pub time: DateTime<Utc>,
+    /// [`AuditEvent::Unlock`] or [`AuditEvent::Lock`].
+    pub event: AuditEvent,
+    pub success: bool,
+    /// How long the operation took, where recorded.
+    pub duration: Option<Duration>,
+}
+
+/// Unlock and lock history of one dataset, oldest first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatasetHistory {
+    pub dataset: String,
+    pub samples: Vec<HistorySample>,
+}
+
+impl DatasetHistory {
+    /// Durations of successful unlocks that recorded one, oldest first.
+    pub fn unlock_latencies(&self) -> Vec<Duration> {
+        self.samples
+            .iter()
+            .filter(|sample| sample.event == AuditEvent::Unlock && sample.success)
+            .filter_map(|sample| sample.duration)
+            .collect()
+    }
+
+    /// Successful unlocks and locks that changed the dataset's state from
+    /// the previous one; a token that keeps dropping out shows up here.
+    pub fn transitions(&self) -> usize {
+        let mut last = None;
+        let mut changes = 0;
+        for sample in self.samples.iter().filter(|sample| sample.success) {
+            if last != Some(sample.event) {
+                changes += 1;
+                last = Some(sample.event);
+            }
+        }
+        changes
+    }
+
+    /// Failed unlocks and locks.
+    pub fn failures(&self) -> usize {
+        self.samples.iter().filter(|sample| !sample.success).count()
+    }
+}
+
+/// Group the unlock and lock records written at or after `since` by
+/// dataset, in order of each dataset's first appearance.
+pub fn dataset_history(
+    records: &[AuditRecord],
+    since: Option<DateTime<Utc>>,
+) -> Vec<DatasetHistory> {
+    let filter = AuditFilter {
+        since,
+        ..AuditFilter::default()
+    };
+    let mut histories: Vec<DatasetHistory> = Vec::new();
+    for record in records.iter().filter(|record| filter.matches(record)) {
+        let entry = &record.entry;
+        if !matches!(entry.event, AuditEvent::Unlock | AuditEvent::Lock) {
+            continue;
+        }
+        let Some(time) = record.time() else {
+            continue;
+        };
+        let sample = HistorySample {
+            time,
+            event: entry.event,
+            success: entry.result == AuditResult::Success,
+            duration: entry.duration_ms.map(Duration::from_millis),
+        };
+        match histories
+            .iter_mut()
+            .find(|history| history.dataset == entry.dataset)
+        {
+            Some(history) => history.samples.push(sample),
+            None => histories.push(DatasetHistory {
+                dataset: entry.dataset.clone(),
+                samples: vec![sample],
+            }),
+        }
+    }
+    histories
+}
+
+/// Appends an entry for the given actor somewhere else, e.g. through a
+/// process that may write the trail when this one may not.
+pub type AuditSink =
+    Arc<dyn Fn(&str, AuditEntry) -> LockchainResult<Option<AuditRecord>> + Send + Sync>;
+
+/// Writer for the audit trail configured under `[audit]`.
+#[derive(Clone)]
+pub struct AuditLog {
+    cfg: AuditCfg,
+    actor: String,
+    /// Group that may read the trail, which is then kept at 0640.
+    group: Option<u32>,
+    sink: Option<AuditSink>,
+}
+
+impl std::fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("cfg", &self.cfg)
+            .field("actor", &self.actor)
+            .field("group", &self.group)
+            .field("sink", &self.sink.is_some())
+            .finish()
+    }
+}
+
+impl AuditLog {
+    /// Build a log writer that attributes records to the current process/user.
+    pub fn new(cfg: &AuditCfg) -> Self {
+        Self {
+            cfg: cfg.clone(),
+            actor: default_actor(),
+            group: None,
+            sink: None,
+        }
+    }
+
+    /// Override the actor recorded on every entry.
+    pub fn with_actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = actor.into();
+        self
+    }
+
+    /// Let `gid` read the trail: each append gives the active file that
+    /// group and mode 0640.
+    pub fn with_group(mut self, gid: u32) -> Self {
+        self.group = Some(gid);
+        self
+    }
+
+    /// Hand every entry to `sink` instead of writing the file directly.
+    pub fn with_sink(mut self, sink: AuditSink) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+
+    /// Path of the active log file.
+    pub fn path(&self) -> PathBuf {
+        self.cfg.log_path()
+    }
+
+    /// Append `entry` to the trail, rotating first if the file is full.
+    ///
+    /// Returns `Ok(None)` when auditing is disabled.
+    pub fn record(&self, entry: AuditEntry) -> LockchainResult<Option<AuditRecord>> {
+        if !self.cfg.enabled {
+            return Ok(None);
+        }
+        if let Some(sink) = &self.sink {
+            return sink(&self.actor, entry);
+        }
+        let path = self.path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .read(true)
+            .mode(0o600)
+            .open(&path)?;
+        let lock = FileLock::acquire(&file)?;
+        if let Some(gid) = self.group {
+            let meta = file.metadata()?;
+            if meta.gid() != gid {
+                std::os::unix::fs::fchown(&file, None, Some(gid))?;
+            }
+            if meta.mode() & 0o777 != 0o640 {
+                file.set_permissions(fs::Permissions::from_mode(0o640))?;
+            }
+        }
+
+        let mut prev_hash = last_hash(&mut file)?;
+        if self.cfg.max_bytes > 0 && file.metadata()?.len() >= self.cfg.max_bytes {
+            drop(lock);
+            self.rotate(&path)?;
+            return self.record(entry);
+        }
+        if prev_hash.is_none() {
+            prev_hash = last_hash_in_rotated(&path)?;
+        }
+
+        let mut record = AuditRecord {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            actor: self.actor.clone(),
+            entry,
+            prev_hash: prev_hash.unwrap_or_else(|| GENESIS_HASH.to_string()),
+            hash: String::new(),
+        };
+        record.hash = record.compute_hash();
+
+        let mut line = serde_json::to_string(&record)
+            .map_err(|err| LockchainError::InvalidConfig(format!("audit encode: {err}")))?;
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+        drop(lock);
+        Ok(Some(record))
+    }
+
+    /// Record `entry`, logging instead of failing when the trail is unwritable.
+    pub fn record_or_warn(&self, entry: AuditEntry) {
+        if let Err(err) = self.record(entry) {
+            warn!(
+                "failed to append audit record to {}: {err}",
+                self.path().display()
+            );
+        }
+    }
+
+    /// Shift `audit.jsonl` → `audit.jsonl.1` → … and apply the retention policy.
+    fn rotate(&self, path: &Path) -> LockchainResult<()> {
+        let keep = self.cfg.max_files.max(1);
+        let _ = fs::remove_file(rotated_path(path, keep));
+        for idx in (1..keep).rev() {
+            let from = rotated_path(path, idx);
+            if from.exists() {
+                fs::rename(&from, rotated_path(path, idx + 1))?;
+            }
+        }
+        fs::rename(path, rotated_path(path, 1))?;
+
+        if self.cfg.retention_days > 0 {
+            let max_age = Duration::from_secs(u64::from(self.cfg.retention_days) * 86_400);
+            for idx in 1..=keep {
+                let candidate = rotated_path(path, idx);
+                let expired = fs::metadata(&candidate)
+                    .and_then(|meta| meta.modified())
+                    .ok()
+                    .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                    .is_some_and(|age| age > max_age);
+                if expired {
+                    fs::remove_file(&candidate)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read every record across rotated files, oldest first.
+pub fn read_records(path: &Path) -> LockchainResult<Vec<AuditRecord>> {
+    let mut files: Vec<PathBuf> = (1..)
+        .map(|idx| rotated_path(path, idx))
+        .take_while(|candidate| candidate.exists())
+        .collect();
+    files.reverse();
+    if path.exists() {
+        files.push(path.to_path_buf());
+    }
+
+    let mut records = Vec::new();
+    for file in files {
+        let reader = BufReader::new(File::open(&file)?);
+        for (lineno, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = serde_json::from_str(&line).map_err(|err| {
+                LockchainError::InvalidConfig(format!(
+                    "{}:{}: malformed audit record ({err})",
+                    file.display(),
+                    lineno + 1
+                ))
+            })?;
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+/// Check the hash chain; returns the index and reason of the first broken link.
+///
+/// The first record may point at a rotated-away predecessor, so only its own
+/// hash is checked.
+pub fn verify_chain(records: &[AuditRecord]) -> Result<(), (usize, String)> {
+    for (idx, record) in records.iter().enumerate() {
+        if idx > 0 && record.prev_hash != records[idx - 1].hash {
+            return Err((idx, "prev_hash does not match preceding record".to_string()));
+        }
+        if record.compute_hash() != record.hash {
+            return Err((idx, "record content does not match its hash".to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// `<path>.<idx>` naming used for rotated segments.
+fn rotated_path(path: &Path, idx: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{idx}"));
+    PathBuf::from(name)
+}
+
+/// Hash of the last record in `file`, if any.
+fn last_hash(file: &mut File) -> LockchainResult<Option<String>> {
+    let len = file.metadata()?.len();
+    if len == 0 {
+        return Ok(None);
+    }
+    let start = len.saturating_sub(TAIL_WINDOW);
+    file.seek(SeekFrom::Start(start))?;
+    let mut tail = String::new();
+    file.read_to_string(&mut tail)?;
+    Ok(tail
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .and_then(|line| serde_json::from_str::<AuditRecord>(line).ok())
+        .map(|record| record.hash))
+}
+
+/// Continue the chain from the most recent rotated segment.
+fn last_hash_in_rotated(path: &Path) -> LockchainResult<Option<String>> {
+    match File::open(rotated_path(path, 1)) {
+        Ok(mut file) => last_hash(&mut file),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// `program:user` for the current process, honouring `sudo`.
+fn default_actor() -> String {
+    let program = std::env::args()
+        .next()
+        .and_then(|arg| {
+            Path::new(&arg)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "lockchain".to_string());
+    let user = std::env::var("SUDO_USER")
+        .or_else(|_| std::env::var("USER"))
+        // SAFETY: getuid has no preconditions.
+        .unwrap_or_else(|_| format!("uid:{}", unsafe { libc::getuid() }));
+    format!("{program}:{user}")
+}
+
+/// Exclusive `flock` held while appending so concurrent writers keep the chain linear.
+struct FileLock(i32);
+
+impl FileLock {
+    fn acquire(file: &File) -> LockchainResult<Self> {
+        let fd = file.as_raw_fd();
+        // SAFETY: `fd` is a valid descriptor owned by `file` for the guard's lifetime.
+        if unsafe { libc::flock(fd, libc::LOCK_EX) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Self(fd))
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // SAFETY: releasing the lock taken in `acquire`.
+        unsafe {
+            libc::flock(self.0, libc::LOCK_UN);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn cfg(path: &Path) -> AuditCfg {
+        AuditCfg {
+            enabled: true,
+            path: path.display().to_string(),
+            ..AuditCfg::default()
+        }
+    }
+
+    #[test]
+    fn records_form_a_verifiable_chain() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let log = AuditLog::new(&cfg(&path)).with_actor("test");
+
+        log.record(AuditEntry::success(AuditEvent::Unlock, "tank/secure"))
+            .unwrap();
+        let err = LockchainError::MissingKeySource("tank/secure".into());
+        log.record(AuditEntry::failure(AuditEvent::Unlock, "tank/secure", &err))
+            .unwrap();
+
+        let mut records = read_records(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].prev_hash, GENESIS_HASH);
+        assert_eq!(records[1].entry.error_code.as_deref(), Some("LC1201"));
+        assert!(verify_chain(&records).is_ok());
+
+        records[0].entry.dataset = "tank/other".into();
+        assert_eq!(verify_chain(&records).unwrap_err().0, 0);
+    }
+
+    #[test]
+    fn a_reader_group_gets_the_trail_at_0640_and_sinks_replace_the_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        // SAFETY: getegid cannot fail.
+        let gid = unsafe { libc::getegid() };
+        AuditLog::new(&cfg(&path))
+            .with_group(gid)
+            .record(AuditEntry::success(AuditEvent::Lock, "tank/secure"))
+            .unwrap();
+        let meta = fs::metadata(&path).unwrap();
+        assert_eq!((meta.gid(), meta.mode() & 0o777), (gid, 0o640));
+
+        let elsewhere = dir.path().join("elsewhere.jsonl");
+        let target = AuditLog::new(&cfg(&elsewhere));
+        let log = AuditLog::new(&cfg(&path))
+            .with_actor("daemon")
+            .with_sink(Arc::new(move |actor, entry| {
+                target.clone().with_actor(actor).record(entry)
+            }));
+        log.record(AuditEntry::success(AuditEvent::Unlock, "tank/secure"))
+            .unwrap();
+        assert_eq!(read_records(&path).unwrap().len(), 1);
+        let forwarded = read_records(&elsewhere).unwrap();
+        assert_eq!(forwarded.len(), 1);
+        assert_eq!(forwarded[0].actor, "daemon");
+    }
+
+    #[test]
+    fn rotation_keeps_chain_across_segments() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let config = AuditCfg {
+            max_bytes: 1,
+            max_files: 2,
+            ..cfg(&path)
+        };
+        let log = AuditLog::new(&config).with_actor("test");
+        for _ in 0..4 {
+            log.record(AuditEntry::success(AuditEvent::Lock, "tank/secure"))
+                .unwrap();
+        }
+
+        assert!(rotated_path(&path, 2).exists());
+        assert!(!rotated_path(&path, 3).exists());
+        let records = read_records(&path).unwrap();
+        assert_eq!(records.len(), 3);
+        assert!(verify_chain(&records).is_ok());
+    }
+
+    #[test]
+    fn filter_selects_by_dataset_event_and_time() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let log = AuditLog::new(&cfg(&path)).with_actor("test");
+        log.record(AuditEntry::success(AuditEvent::Unlock, "tank/secure"))
+            .unwrap();
+        log.record(AuditEntry::success(
+            AuditEvent::Breakglass,
+            "tank/secure/vm",
+        ))
+        .unwrap();
+        log.record(AuditEntry::success(AuditEvent::Unlock, "tank/secured"))
+            .unwrap();
+        let records = read_records(&path).unwrap();
+
+        let by_dataset = AuditFilter {
+            dataset: Some("tank/secure".into()),
+            ..AuditFilter::default()
+        };
+        assert_eq!(records.iter().filter(|r| by_dataset.matches(r)).count(), 2);
+
+        let by_event = AuditFilter {
+            event: Some("breakglass".parse().unwrap()),
+            ..by_dataset
+        };
+        let selected: Vec<_> = records.iter().filter(|r| by_event.matches(r)).collect();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].entry.dataset, "tank/secure/vm");
+
+        let future = AuditFilter {
+            since: Some(Utc::now() + chrono::Duration::hours(1)),
+            ..AuditFilter::default()
+        };
+        assert!(!records.iter().any(|r| future.matches(r)));
+        assert_eq!(
+            "provision".parse::<AuditEvent>().unwrap(),
+            AuditEvent::Provision
+        );
+        assert!("mount".parse::<AuditEvent>().is_err());
+    }
+
+    #[test]
+    fn history_tracks_latency_and_transitions() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let log = AuditLog::new(&cfg(&path)).with_actor("test");
+        let unlock = |ms| {
+            AuditEntry::success(AuditEvent::Unlock, "tank/secure")
+                .with_duration(Duration::from_millis(ms))
+        };
+        log.record(unlock(120)).unwrap();
+        log.record(AuditEntry::success(AuditEvent::Lock, "tank/secure"))
+            .unwrap();
+        let err = LockchainError::MissingKeySource("tank/secure".into());
+        log.record(AuditEntry::failure(AuditEvent::Unlock, "tank/secure", &err))
+            .unwrap();
+        log.record(unlock(4_500)).unwrap();
+        log.record(AuditEntry::success(AuditEvent::Rotation, "tank/secure"))
+            .unwrap();
+        log.record(AuditEntry::success(AuditEvent::Unlock, "tank/other"))
+            .unwrap();
+
+        let records = read_records(&path).unwrap();
+        assert!(verify_chain(&records).is_ok());
+        let history = dataset_history(&records, None);
+        assert_eq!(history.len(), 2);
+        let secure = &history[0];
+        assert_eq!(secure.dataset, "tank/secure");
+        assert_eq!(secure.samples.len(), 4);
+        assert_eq!(
+            secure.unlock_latencies(),
+            vec![Duration::from_millis(120), Duration::from_millis(4_500)]
+        );
+        assert_eq!(secure.transitions(), 3);
+        assert_eq!(secure.failures(), 1);
+        assert!(history[1].unlock_latencies().is_empty());
+
+        let future = Some(Utc::now() + chrono::Duration::hours(1));
+        assert!(dataset_history(&records, future).is_empty());
+    }
+
+    #[test]
+    fn disabled_log_writes_nothing() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let config = AuditCfg {
+            enabled: false,
+            ..cfg(&path)
+        };
+        let written = AuditLog::new(&config)
+            .record(AuditEntry::success(AuditEvent::Rotation, "tank/secure"))
+            .unwrap();
+        assert!(written.is_none());
+        assert!(!path.exists());
+    }
+}
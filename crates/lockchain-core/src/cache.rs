@@ -0,0 +1,84 @@
+//! Short-lived cache of verified key material so repeated unlocks skip disk reads.
+
+use crate::secret::SecretBuffer;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Holds verified keys in locked, zeroizing memory until their TTL elapses.
+pub struct KeyCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<PathBuf, CachedKey>>,
+}
+
+/// A single cached key and the moment it was verified.
+struct CachedKey {
+    key: SecretBuffer,
+    stored_at: Instant,
+}
+
+impl KeyCache {
+    /// Build a cache whose entries expire `ttl` after they were stored.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Return a copy of the key cached for `source`, dropping it if expired.
+    pub fn get(&self, source: &Path) -> Option<SecretBuffer> {
+        let mut entries = self.entries.lock().unwrap();
+        let expired = entries
+            .get(source)
+            .map(|entry| entry.stored_at.elapsed() >= self.ttl)?;
+        if expired {
+            entries.remove(source);
+            return None;
+        }
+        entries.get(source).map(|entry| entry.key.clone())
+    }
+
+    /// Cache verified key material read from `source`.
+    pub fn insert(&self, source: &Path, key: &[u8]) {
+        let entry = CachedKey {
+            key: SecretBuffer::from_slice(key),
+            stored_at: Instant::now(),
+        };
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(source.to_path_buf(), entry);
+    }
+
+    /// Forget every cached key (e.g. after the token was removed).
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_returns_key_until_ttl_expires() {
+        let cache = KeyCache::new(Duration::from_millis(50));
+        let path = Path::new("/run/lockchain/key.hex");
+        cache.insert(path, &[0x42; 32]);
+        assert_eq!(&cache.get(path).unwrap()[..], &[0x42; 32]);
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(cache.get(path).is_none());
+    }
+
+    #[test]
+    fn clear_drops_entries() {
+        let cache = KeyCache::new(Duration::from_secs(60));
+        let path = Path::new("/run/lockchain/key.hex");
+        cache.insert(path, &[0x11; 32]);
+        cache.clear();
+        assert!(cache.get(path).is_none());
+    }
+}
@@ -1,13 +1,24 @@
 //! Configuration model and helpers used by Lockchain services.
 
 use crate::error::{LockchainError, LockchainResult};
+use crate::remote;
+use crate::seal;
+use crate::units;
+use chrono::NaiveTime;
+use log::warn;
+use rand::Rng;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::env;
 use std::fs;
+use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
 
-const KEY_PATH_ENV: &str = "LOCKCHAIN_KEY_PATH";
+pub(crate) const KEY_PATH_ENV: &str = "LOCKCHAIN_KEY_PATH";
+pub(crate) const AUDIT_PATH_ENV: &str = "LOCKCHAIN_AUDIT_PATH";
 
 /// Describes which datasets we manage and the paths to supporting tooling.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -25,23 +36,121 @@ pub struct Policy {
 
     #[serde(default)]
     pub allow_root: bool,
+
+    /// Local-time window (`HH:MM-HH:MM`) outside which unlocks are refused.
+    #[serde(default)]
+    pub unlock_window: Option<String>,
+
+    /// Refuse unlocks that would fall back to the passphrase instead of the USB key.
+    #[serde(default)]
+    pub require_strict_usb: bool,
+}
+
+impl Policy {
+    /// Parse `policy.unlock_window`, if configured.
+    pub fn unlock_window(&self) -> LockchainResult<Option<UnlockWindow>> {
+        self.unlock_window
+            .as_deref()
+            .map(UnlockWindow::parse)
+            .transpose()
+    }
+
+    /// Entries of `datasets` selected by a dataset pattern, in policy order.
+    pub fn datasets_matching(&self, pattern: &str) -> Vec<String> {
+        self.datasets
+            .iter()
+            .filter(|dataset| dataset_pattern_matches(pattern, dataset))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Whether `value` uses dataset pattern syntax rather than naming one dataset.
+pub fn is_dataset_pattern(value: &str) -> bool {
+    value.contains(['*', '?', '%'])
+}
+
+/// Match `dataset` against `pattern`.
+///
+/// `*` matches any run of characters and `?` one character within a single
+/// path component; `%` matches any run including `/`, so `tank/*` selects the
+/// direct children of `tank` and `tank/vms/%` everything beneath `tank/vms`.
+pub fn dataset_pattern_matches(pattern: &str, dataset: &str) -> bool {
+    fn matches(pattern: &[char], name: &[char]) -> bool {
+        match pattern.split_first() {
+            None => name.is_empty(),
+            Some(('%', rest)) => (0..=name.len()).any(|skip| matches(rest, &name[skip..])),
+            Some(('*', rest)) => {
+                let component = name.iter().position(|c| *c == '/').unwrap_or(name.len());
+                (0..=component).any(|skip| matches(rest, &name[skip..]))
+            }
+            Some(('?', rest)) => name
+                .split_first()
+                .is_some_and(|(c, tail)| *c != '/' && matches(rest, tail)),
+            Some((literal, rest)) => name
+                .split_first()
+                .is_some_and(|(c, tail)| c == literal && matches(rest, tail)),
+        }
+    }
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = dataset.chars().collect();
+    matches(&pattern, &name)
+}
+
+/// Daily interval during which unlocks are permitted; may wrap past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnlockWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl UnlockWindow {
+    /// Parse `HH:MM-HH:MM` (e.g. `06:00-22:00` or `22:00-06:00`).
+    pub fn parse(raw: &str) -> LockchainResult<Self> {
+        let invalid = || {
+            LockchainError::InvalidConfig(format!(
+                "policy.unlock_window `{raw}` must look like HH:MM-HH:MM"
+            ))
+        };
+        let (start, end) = raw.split_once('-').ok_or_else(invalid)?;
+        let parse = |value: &str| NaiveTime::parse_from_str(value.trim(), "%H:%M");
+        Ok(Self {
+            start: parse(start).map_err(|_| invalid())?,
+            end: parse(end).map_err(|_| invalid())?,
+        })
+    }
+
+    /// Whether `time` falls inside the window (start inclusive, end exclusive).
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
 }
 
 /// Timeouts and other crypto-related knobs for CLI interactions.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CryptoCfg {
-    #[serde(default = "default_timeout_secs")]
-    pub timeout_secs: u64,
+    /// Limit for each `zfs`/`zpool` invocation (`"10s"`; integers are seconds).
+    #[serde(
+        default = "default_timeout",
+        alias = "timeout_secs",
+        with = "units::secs"
+    )]
+    #[schemars(with = "units::HumanDuration")]
+    pub timeout: Duration,
 }
 
-fn default_timeout_secs() -> u64 {
-    10
+fn default_timeout() -> Duration {
+    Duration::from_secs(10)
 }
 
 impl Default for CryptoCfg {
     fn default() -> Self {
         Self {
-            timeout_secs: default_timeout_secs(),
+            timeout: default_timeout(),
         }
     }
 }
@@ -64,8 +173,236 @@ pub struct Usb {
     #[serde(default = "default_usb_device_key_path")]
     pub device_key_path: String,
 
-    #[serde(default = "default_usb_mount_timeout_secs")]
-    pub mount_timeout_secs: u64,
+    /// How long to wait for the token to mount (`"10s"`; integers are seconds).
+    #[serde(
+        default = "default_usb_mount_timeout",
+        alias = "mount_timeout_secs",
+        with = "units::secs"
+    )]
+    #[schemars(with = "units::HumanDuration")]
+    pub mount_timeout: Duration,
+
+    /// How long a verified key stays cached in locked memory; `"0s"` disables caching.
+    #[serde(default, alias = "key_cache_ttl_secs", with = "units::secs")]
+    #[schemars(with = "units::HumanDuration")]
+    pub key_cache_ttl: Duration,
+
+    /// Per-dataset (or per-encryption-root) key files that override `key_hex_path`.
+    #[serde(default)]
+    pub keys: BTreeMap<String, DatasetKey>,
+
+    /// Additional tokens (e.g. a spare) recognised alongside `device_label`/`device_uuid`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tokens: Vec<UsbToken>,
+
+    /// What the daemon does when the staged key disappears.
+    #[serde(default)]
+    pub removal: RemovalCfg,
+}
+
+impl Usb {
+    /// Every token the watcher accepts, highest precedence first.
+    ///
+    /// The top-level `device_label`/`device_uuid` token comes first, followed by
+    /// `[[usb.tokens]]` in file order. With no `[[usb.tokens]]` at all the
+    /// top-level token is always returned, even without selectors, so any USB
+    /// partition still matches as before. Key paths and checksums left unset on
+    /// an entry inherit `device_key_path` and `expected_sha256`.
+    pub fn recognized_tokens(&self) -> Vec<UsbToken> {
+        let mut tokens = Vec::with_capacity(self.tokens.len() + 1);
+        if self.tokens.is_empty() || self.device_label.is_some() || self.device_uuid.is_some() {
+            tokens.push(UsbToken {
+                name: Some("primary".to_string()),
+                label: self.device_label.clone(),
+                uuid: self.device_uuid.clone(),
+                serial: None,
+                key_path: None,
+                sha256: None,
+            });
+        }
+        tokens.extend(self.tokens.iter().cloned());
+        for (index, token) in tokens.iter_mut().enumerate() {
+            token
+                .name
+                .get_or_insert_with(|| format!("token {}", index + 1));
+            token
+                .key_path
+                .get_or_insert_with(|| self.device_key_path.clone());
+            if token.sha256.is_none() {
+                token.sha256 = self.expected_sha256.clone();
+            }
+        }
+        tokens
+    }
+}
+
+/// `[usb.removal]`: turn pulling the token into a physical killswitch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RemovalCfg {
+    /// Unload every managed dataset's key once the staged key has been gone
+    /// for `grace`; the daemon then leaves them locked until the key returns.
+    #[serde(default)]
+    pub lock: bool,
+
+    /// How long the key may be missing before locking (`"30s"`; integers are
+    /// seconds), so a token reseated in time does not lock anything.
+    #[serde(default = "default_removal_grace", with = "units::secs")]
+    #[schemars(with = "units::HumanDuration")]
+    pub grace: Duration,
+
+    /// Unmount datasets before unloading their keys.
+    #[serde(default)]
+    pub unmount: bool,
+}
+
+impl Default for RemovalCfg {
+    fn default() -> Self {
+        Self {
+            lock: false,
+            grace: default_removal_grace(),
+            unmount: false,
+        }
+    }
+}
+
+/// A USB token listed under `[[usb.tokens]]`.
+///
+/// Every selector that is set (`label`, `uuid`, `serial`) must match the
+/// inserted partition. When several recognised tokens are inserted, the one
+/// listed first wins.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct UsbToken {
+    /// Name shown in logs and diagnostics, e.g. `"spare"`.
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Filesystem label (`ID_FS_LABEL`).
+    #[serde(default)]
+    pub label: Option<String>,
+
+    /// Filesystem UUID (`ID_FS_UUID`).
+    #[serde(default)]
+    pub uuid: Option<String>,
+
+    /// Device serial (`ID_SERIAL_SHORT` or `ID_SERIAL`).
+    #[serde(default)]
+    pub serial: Option<String>,
+
+    /// Key file on the token; defaults to `usb.device_key_path`.
+    #[serde(default)]
+    pub key_path: Option<String>,
+
+    /// Expected SHA-256 of the decoded key; defaults to `usb.expected_sha256`.
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+impl UsbToken {
+    /// Display name; set on every token returned by [`Usb::recognized_tokens`].
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("token")
+    }
+
+    /// Key file path relative to the token's mount point.
+    pub fn key_path(&self) -> &str {
+        self.key_path.as_deref().unwrap_or("key.hex")
+    }
+
+    /// Whether any selector is set; a token without one matches every USB partition.
+    pub fn has_selector(&self) -> bool {
+        [&self.label, &self.uuid, &self.serial]
+            .iter()
+            .any(|value| value.as_deref().is_some_and(|v| !v.trim().is_empty()))
+    }
+
+    /// Check a device's label, UUID, and serials against every configured selector.
+    pub fn matches(
+        &self,
+        label: Option<&str>,
+        uuid: Option<&str>,
+        serials: &[Option<&str>],
+    ) -> bool {
+        let accepts = |expected: &Option<String>, actual: Option<&str>| {
+            expected
+                .as_deref()
+                .is_none_or(|expected| actual == Some(expected))
+        };
+        accepts(&self.label, label)
+            && accepts(&self.uuid, uuid)
+            && self
+                .serial
+                .as_deref()
+                .is_none_or(|expected| serials.contains(&Some(expected)))
+    }
+
+    /// Human-readable list of the selectors, e.g. `label LOCKCHAIN, uuid 1234-ABCD`.
+    pub fn describe(&self) -> String {
+        let selectors: Vec<String> = [
+            ("label", &self.label),
+            ("uuid", &self.uuid),
+            ("serial", &self.serial),
+        ]
+        .into_iter()
+        .filter_map(|(kind, value)| value.as_ref().map(|value| format!("{kind} {value}")))
+        .collect();
+        if selectors.is_empty() {
+            "any USB partition".to_string()
+        } else {
+            selectors.join(", ")
+        }
+    }
+}
+
+/// Key file assigned to a single dataset under `[usb.keys]`.
+///
+/// Accepts either a bare path (`"tank/secure" = "secure.key"`) or a table with a
+/// checksum (`"tank/secure" = { path = "secure.key", sha256 = "..." }`). Relative
+/// paths resolve next to `usb.key_hex_path`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum DatasetKey {
+    Path(String),
+    Detailed {
+        path: String,
+        #[serde(default)]
+        sha256: Option<String>,
+    },
+}
+
+impl DatasetKey {
+    /// Configured key file path, possibly relative.
+    pub fn path(&self) -> &str {
+        match self {
+            DatasetKey::Path(path) | DatasetKey::Detailed { path, .. } => path,
+        }
+    }
+
+    /// Expected SHA-256 of the decoded key, when pinned.
+    pub fn sha256(&self) -> Option<&str> {
+        match self {
+            DatasetKey::Path(_) => None,
+            DatasetKey::Detailed { sha256, .. } => sha256.as_deref(),
+        }
+    }
+}
+
+/// Resolved location and checksum for the key protecting a dataset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySource {
+    pub path: PathBuf,
+    pub expected_sha256: Option<String>,
+    /// `[usb.keys]` entry this source came from; `None` for the shared key.
+    pub entry: Option<String>,
+}
+
+impl KeySource {
+    /// Config setting that pins this source's checksum, for diagnostics.
+    pub fn setting(&self) -> String {
+        match &self.entry {
+            Some(name) => format!("usb.keys.\"{name}\".sha256"),
+            None => "usb.expected_sha256".to_string(),
+        }
+    }
 }
 
 fn default_usb_key_path() -> String {
@@ -76,8 +413,12 @@ fn default_usb_device_key_path() -> String {
     "key.hex".to_string()
 }
 
-fn default_usb_mount_timeout_secs() -> u64 {
-    10
+fn default_usb_mount_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_removal_grace() -> Duration {
+    Duration::from_secs(30)
 }
 
 impl Default for Usb {
@@ -88,7 +429,11 @@ impl Default for Usb {
             device_label: None,
             device_uuid: None,
             device_key_path: default_usb_device_key_path(),
-            mount_timeout_secs: default_usb_mount_timeout_secs(),
+            mount_timeout: default_usb_mount_timeout(),
+            key_cache_ttl: Duration::ZERO,
+            keys: BTreeMap::new(),
+            tokens: Vec::new(),
+            removal: RemovalCfg::default(),
         }
     }
 }
@@ -113,8 +458,49 @@ pub struct Fallback {
 
     #[serde(default = "default_passphrase_iters")]
     pub passphrase_iters: u32,
+
+    /// Salt and mask were sealed on disk and are re-sealed on save.
+    #[serde(skip)]
+    pub sealed: bool,
 }
 
+impl Fallback {
+    /// Sensitive fields with their config names, which double as sealing context.
+    fn secret_fields(&mut self) -> [(&'static str, &mut Option<String>); 2] {
+        [
+            ("fallback.passphrase_salt", &mut self.passphrase_salt),
+            ("fallback.passphrase_xor", &mut self.passphrase_xor),
+        ]
+    }
+
+    /// Decrypt sealed fields in place, remembering that they were sealed.
+    fn unseal_secrets(&mut self) -> LockchainResult<()> {
+        let mut sealed = false;
+        for (field, value) in self.secret_fields() {
+            if let Some(raw) = value.as_mut().filter(|raw| seal::is_sealed(raw)) {
+                *raw = seal::unseal_value(field, raw)?;
+                sealed = true;
+            }
+        }
+        self.sealed |= sealed;
+        Ok(())
+    }
+
+    /// Encrypt every populated sensitive field in place.
+    fn seal_secrets(&mut self) -> LockchainResult<()> {
+        for (field, value) in self.secret_fields() {
+            if let Some(raw) = value.as_mut().filter(|raw| !seal::is_sealed(raw)) {
+                *raw = seal::seal_value(field, raw)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// PBKDF2 iteration count for the fallback passphrase below which `validate`
+/// warns and `benchmark-kdf` will not go.
+pub const MIN_PASSPHRASE_ITERS: u32 = 100_000;
+
 fn default_passphrase_iters() -> u32 {
     250_000
 }
@@ -128,6 +514,7 @@ impl Default for Fallback {
             passphrase_salt: None,
             passphrase_xor: None,
             passphrase_iters: default_passphrase_iters(),
+            sealed: false,
         }
     }
 }
@@ -138,26 +525,113 @@ pub struct RetryCfg {
     #[serde(default = "default_retry_attempts")]
     pub max_attempts: u32,
 
-    #[serde(default = "default_retry_base_delay")]
-    pub base_delay_ms: u64,
-
-    #[serde(default = "default_retry_max_delay")]
-    pub max_delay_ms: u64,
+    /// First backoff delay (`"500ms"`; integers are milliseconds).
+    #[serde(
+        default = "default_retry_base_delay",
+        alias = "base_delay_ms",
+        with = "units::millis"
+    )]
+    #[schemars(with = "units::HumanDuration")]
+    pub base_delay: Duration,
+
+    /// Upper bound for the backoff delay (`"5s"`; integers are milliseconds).
+    #[serde(
+        default = "default_retry_max_delay",
+        alias = "max_delay_ms",
+        with = "units::millis"
+    )]
+    #[schemars(with = "units::HumanDuration")]
+    pub max_delay: Duration,
 
     #[serde(default = "default_retry_jitter")]
     pub jitter_ratio: f64,
+
+    #[serde(default)]
+    pub strategy: RetryStrategy,
+}
+
+/// How the wait between unlock attempts grows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryStrategy {
+    /// Wait `base_delay` (± `jitter_ratio`) between every attempt.
+    Fixed,
+    /// Double the delay after each failure up to `max_delay`, ± `jitter_ratio`.
+    #[default]
+    Exponential,
+    /// Wait a uniformly random time between zero and the exponential delay.
+    ExponentialFullJitter,
+}
+
+impl std::str::FromStr for RetryStrategy {
+    type Err = LockchainError;
+
+    /// Parse the config spelling (`fixed`, `exponential`, `exponential_full_jitter`).
+    fn from_str(value: &str) -> LockchainResult<Self> {
+        match value.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "fixed" => Ok(Self::Fixed),
+            "exponential" => Ok(Self::Exponential),
+            "exponential_full_jitter" => Ok(Self::ExponentialFullJitter),
+            other => Err(LockchainError::InvalidConfig(format!(
+                "unknown retry strategy `{other}` (expected fixed, exponential, or exponential_full_jitter)"
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for RetryStrategy {
+    /// The config spelling, as accepted by [`str::parse`].
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Fixed => "fixed",
+            Self::Exponential => "exponential",
+            Self::ExponentialFullJitter => "exponential_full_jitter",
+        })
+    }
+}
+
+impl RetryCfg {
+    /// Delay to wait after failed attempt number `attempt` (1-based).
+    pub fn delay_for<R: Rng + ?Sized>(&self, attempt: u32, rng: &mut R) -> Duration {
+        let base = millis(self.base_delay).max(1);
+        let cap = millis(self.max_delay).max(base);
+        let growth = 1u64
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u64::MAX);
+        let exponential = base.saturating_mul(growth).min(cap);
+
+        let millis = match self.strategy {
+            RetryStrategy::Fixed => jittered(base, self.jitter_ratio, rng),
+            RetryStrategy::Exponential => jittered(exponential, self.jitter_ratio, rng),
+            RetryStrategy::ExponentialFullJitter => rng.gen_range(0..=exponential),
+        };
+        Duration::from_millis(millis.max(1))
+    }
+}
+
+fn millis(delay: Duration) -> u64 {
+    u64::try_from(delay.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Spread `delay` uniformly across `±ratio / 2`.
+fn jittered<R: Rng + ?Sized>(delay: u64, ratio: f64, rng: &mut R) -> u64 {
+    if ratio <= 0.0 {
+        return delay;
+    }
+    let offset = rng.gen_range(-0.5..0.5) * ratio;
+    (delay as f64 * (1.0 + offset)).round().max(1.0) as u64
 }
 
 fn default_retry_attempts() -> u32 {
     3
 }
 
-fn default_retry_base_delay() -> u64 {
-    500
+fn default_retry_base_delay() -> Duration {
+    Duration::from_millis(500)
 }
 
-fn default_retry_max_delay() -> u64 {
-    5_000
+fn default_retry_max_delay() -> Duration {
+    Duration::from_secs(5)
 }
 
 fn default_retry_jitter() -> f64 {
@@ -168,194 +642,2106 @@ impl Default for RetryCfg {
     fn default() -> Self {
         Self {
             max_attempts: default_retry_attempts(),
-            base_delay_ms: default_retry_base_delay(),
-            max_delay_ms: default_retry_max_delay(),
+            base_delay: default_retry_base_delay(),
+            max_delay: default_retry_max_delay(),
             jitter_ratio: default_retry_jitter(),
+            strategy: RetryStrategy::default(),
         }
     }
 }
 
-/// Top-level configuration snapshot loaded from disk.
+/// External commands run around unlock and lock operations.
+///
+/// Each entry is a program path followed by optional whitespace-separated
+/// arguments. Hooks receive `LOCKCHAIN_HOOK_EVENT`, `LOCKCHAIN_DATASET`,
+/// `LOCKCHAIN_ENCRYPTION_ROOT`, and `LOCKCHAIN_DATASETS` in their environment.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-pub struct LockchainConfig {
-    pub policy: Policy,
-
+pub struct HooksCfg {
     #[serde(default)]
-    pub crypto: CryptoCfg,
+    pub pre_unlock: Vec<String>,
 
     #[serde(default)]
-    pub usb: Usb,
+    pub post_unlock: Vec<String>,
 
     #[serde(default)]
-    pub fallback: Fallback,
+    pub pre_lock: Vec<String>,
 
     #[serde(default)]
-    pub retry: RetryCfg,
-
-    #[serde(skip)]
-    pub path: PathBuf,
-
-    #[serde(skip)]
-    pub format: ConfigFormat,
+    pub post_lock: Vec<String>,
+
+    /// Per-command limit before the hook is killed (`"30s"`; integers are seconds).
+    #[serde(
+        default = "default_hook_timeout",
+        alias = "timeout_secs",
+        with = "units::secs"
+    )]
+    #[schemars(with = "units::HumanDuration")]
+    pub timeout: Duration,
 }
 
-/// Tracks whether we parsed TOML or YAML so writes preserve format.
-#[derive(Debug, Clone, Copy)]
-pub enum ConfigFormat {
-    Toml,
-    Yaml,
+fn default_hook_timeout() -> Duration {
+    Duration::from_secs(30)
 }
 
-impl Default for ConfigFormat {
+impl Default for HooksCfg {
     fn default() -> Self {
-        ConfigFormat::Toml
+        Self {
+            pre_unlock: Vec::new(),
+            post_unlock: Vec::new(),
+            pre_lock: Vec::new(),
+            post_lock: Vec::new(),
+            timeout: default_hook_timeout(),
+        }
     }
 }
 
-impl LockchainConfig {
-    /// Read a config file from disk, detect format, and validate basics.
-    pub fn load<P: AsRef<Path>>(path: P) -> LockchainResult<Self> {
-        let path = path.as_ref();
-        let contents = fs::read_to_string(path)?;
-        let is_toml = matches!(
-            path.extension().and_then(|ext| ext.to_str()),
-            Some(ext) if ext.eq_ignore_ascii_case("toml")
-        );
-        let mut cfg = if is_toml {
-            toml::from_str::<Self>(&contents)?
-        } else {
-            serde_yaml::from_str::<Self>(&contents)?
-        };
+/// Where the audit trail lives and how much of it to keep.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AuditCfg {
+    #[serde(default = "default_audit_enabled")]
+    pub enabled: bool,
 
-        cfg.path = path.to_path_buf();
-        cfg.format = if is_toml {
-            ConfigFormat::Toml
-        } else {
-            ConfigFormat::Yaml
-        };
+    #[serde(default = "default_audit_path")]
+    pub path: String,
 
-        if cfg.policy.datasets.is_empty() {
-            return Err(LockchainError::InvalidConfig(
-                "policy.datasets must list at least one dataset".to_string(),
-            ));
-        }
+    /// Rotate once the active file reaches this size (`"8MiB"` or bytes); `0` disables rotation.
+    #[serde(default = "default_audit_max_bytes", with = "units::bytes")]
+    #[schemars(with = "units::HumanSize")]
+    pub max_bytes: u64,
 
-        Ok(cfg)
-    }
+    /// Rotated segments to keep (`audit.jsonl.1` … `audit.jsonl.N`).
+    #[serde(default = "default_audit_max_files")]
+    pub max_files: u32,
 
-    /// Returns true when `dataset` is listed under `policy.datasets`.
-    pub fn contains_dataset(&self, dataset: &str) -> bool {
-        self.policy.datasets.iter().any(|d| d == dataset)
-    }
+    /// Drop rotated segments older than this many days; `0` keeps them forever.
+    #[serde(default = "default_audit_retention_days")]
+    pub retention_days: u32,
+}
 
-    /// Perform a best-effort validation pass and return human-readable issues.
-    pub fn validate(&self) -> Vec<String> {
-        let mut issues = Vec::new();
+fn default_audit_enabled() -> bool {
+    true
+}
 
-        if self.policy.datasets.is_empty() {
-            issues.push("policy.datasets must contain at least one dataset".to_string());
-        }
+fn default_audit_path() -> String {
+    "/var/lib/lockchain/audit.jsonl".to_string()
+}
 
-        let mut seen = std::collections::HashSet::new();
-        for ds in &self.policy.datasets {
-            if ds.trim().is_empty() {
-                issues.push("policy.datasets contains an empty dataset entry".to_string());
-            }
-            if !seen.insert(ds) {
-                issues.push(format!("duplicate dataset entry detected: {ds}"));
-            }
-        }
+fn default_audit_max_bytes() -> u64 {
+    8 * 1024 * 1024
+}
 
-        if let Some(expected) = &self.usb.expected_sha256 {
-            if expected.len() != 64 || hex::decode(expected).is_err() {
-                issues.push("usb.expected_sha256 must be a 64-character hex string".to_string());
-            }
-        }
+fn default_audit_max_files() -> u32 {
+    5
+}
 
-        if self.fallback.enabled {
-            if self.fallback.passphrase_salt.is_none() {
-                issues.push(
-                    "fallback.enabled is true but fallback.passphrase_salt is missing".to_string(),
-                );
-            }
-            if self.fallback.passphrase_xor.is_none() {
-                issues.push(
-                    "fallback.enabled is true but fallback.passphrase_xor is missing".to_string(),
-                );
-            }
-        }
+fn default_audit_retention_days() -> u32 {
+    365
+}
 
-        if self.retry.max_attempts == 0 {
-            issues.push("retry.max_attempts must be at least 1".to_string());
-        }
-        if self.retry.base_delay_ms == 0 {
-            issues.push("retry.base_delay_ms must be greater than 0".to_string());
-        }
-        if self.retry.max_delay_ms < self.retry.base_delay_ms {
-            issues.push(
-                "retry.max_delay_ms must be greater than or equal to retry.base_delay_ms"
-                    .to_string(),
-            );
-        }
-        if !(0.0..=1.0).contains(&self.retry.jitter_ratio) {
-            issues.push("retry.jitter_ratio must be between 0.0 and 1.0".to_string());
+impl Default for AuditCfg {
+    fn default() -> Self {
+        Self {
+            enabled: default_audit_enabled(),
+            path: default_audit_path(),
+            max_bytes: default_audit_max_bytes(),
+            max_files: default_audit_max_files(),
+            retention_days: default_audit_retention_days(),
         }
-
-        issues
     }
+}
 
-    /// Resolve the path where the USB key material should live.
-    pub fn key_hex_path(&self) -> PathBuf {
-        if let Ok(override_path) = env::var(KEY_PATH_ENV) {
-            if !override_path.is_empty() {
-                return PathBuf::from(override_path);
-            }
+impl AuditCfg {
+    /// Resolve the active audit file, honouring `LOCKCHAIN_AUDIT_PATH`.
+    pub fn log_path(&self) -> PathBuf {
+        match env::var(AUDIT_PATH_ENV) {
+            Ok(path) if !path.is_empty() => PathBuf::from(path),
+            _ => PathBuf::from(&self.path),
         }
-        PathBuf::from(&self.usb.key_hex_path)
     }
+}
 
-    /// Translate the stored timeout into a `Duration`.
-    pub fn zfs_timeout(&self) -> std::time::Duration {
-        std::time::Duration::from_secs(self.crypto.timeout_secs)
-    }
+/// Who besides root may use the daemon's control socket.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ControlCfg {
+    /// Group whose members may call `status`, `events`, and `recent_events`; the socket
+    /// is then created group-accessible (0660) instead of owner-only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
 
-    /// Optional override for the `zfs` CLI path.
-    pub fn zfs_binary_path(&self) -> Option<PathBuf> {
-        self.policy.zfs_path.as_ref().map(PathBuf::from)
-    }
+    /// Let members of `group` also call `unlock`, `lock`, and `reload`.
+    #[serde(default)]
+    pub group_operate: bool,
 
-    /// Optional override for the `zpool` CLI path.
-    pub fn zpool_binary_path(&self) -> Option<PathBuf> {
-        self.policy.zpool_path.as_ref().map(PathBuf::from)
-    }
+    /// Users, by uid, who may call the read-only methods whatever their
+    /// groups. Listing any uid makes the socket world-connectable (0666);
+    /// every request is still checked against the caller's credentials.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub read_uids: Vec<u32>,
 
-    /// Access the shared retry configuration helpers rely on.
-    pub fn retry_config(&self) -> &RetryCfg {
-        &self.retry
-    }
+    /// Users, by uid, who may call every method.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub operate_uids: Vec<u32>,
+}
 
-    /// Persist the configuration back to its original on-disk format.
-    pub fn save(&self) -> LockchainResult<()> {
-        let payload = match self.format {
-            ConfigFormat::Toml => toml::to_string_pretty(self)?,
-            ConfigFormat::Yaml => serde_yaml::to_string(self)?,
-        };
-        fs::write(&self.path, payload)?;
-        Ok(())
+impl ControlCfg {
+    /// Whether any uid allow-list is configured.
+    pub fn lists_uids(&self) -> bool {
+        !self.read_uids.is_empty() || !self.operate_uids.is_empty()
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// `[daemon]`: how often `lockchain-daemon` checks and unlocks datasets.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct DaemonCfg {
+    /// Time between unlock passes over `policy.datasets` (`"30s"`; integers
+    /// are seconds). The USB key arriving starts a pass at once.
+    #[serde(default = "default_unlock_interval", with = "units::secs")]
+    #[schemars(with = "units::HumanDuration")]
+    pub unlock_interval: Duration,
+
+    /// Longest wait before retrying a dataset whose unlock keeps failing;
+    /// the wait doubles from `unlock_interval` after each failed pass.
+    #[serde(default = "default_max_backoff", with = "units::secs")]
+    #[schemars(with = "units::HumanDuration")]
+    pub max_backoff: Duration,
+
+    /// How long a dataset may stay locked before the daemon warns about it.
+    #[serde(default = "default_warn_locked_after", with = "units::secs")]
+    #[schemars(with = "units::HumanDuration")]
+    pub warn_locked_after: Duration,
+
+    /// Import key material from inserted USB tokens inside the daemon, as
+    /// `lockchain-key-usb` does, so that service can be left disabled. Needs
+    /// a daemon built with the `usb-import` feature; read at start-up.
+    #[serde(default)]
+    pub import_usb: bool,
 
-    struct EnvGuard {
-        key: &'static str,
-        prev: Option<String>,
-    }
+    /// `[daemon.idle_lock]`: relock datasets nobody has vouched for lately.
+    #[serde(default)]
+    pub idle_lock: IdleLockCfg,
 
-    impl EnvGuard {
-        fn set(key: &'static str, value: impl Into<String>) -> Self {
+    /// Unmount every managed dataset and unload its key when the daemon
+    /// stops, after in-flight unlocks and locks have finished.
+    #[serde(default)]
+    pub shutdown_lock: bool,
+
+    /// How long a stopping daemon waits for in-flight unlocks and locks
+    /// before giving up on them (`"30s"`; integers are seconds). Keep it
+    /// below the unit's `TimeoutStopSec=`.
+    #[serde(default = "default_shutdown_timeout", with = "units::secs")]
+    #[schemars(with = "units::HumanDuration")]
+    pub shutdown_timeout: Duration,
+
+    /// User a daemon started as root switches to, leaving `zfs` and `zpool`
+    /// to a privileged helper that only serves the managed datasets. Read at
+    /// start-up.
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
+fn default_unlock_interval() -> Duration {
+    Duration::from_secs(30)
+}
+
+fn default_max_backoff() -> Duration {
+    Duration::from_secs(600)
+}
+
+fn default_warn_locked_after() -> Duration {
+    Duration::from_secs(300)
+}
+
+fn default_shutdown_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
+impl Default for DaemonCfg {
+    fn default() -> Self {
+        Self {
+            unlock_interval: default_unlock_interval(),
+            max_backoff: default_max_backoff(),
+            warn_locked_after: default_warn_locked_after(),
+            import_usb: false,
+            idle_lock: IdleLockCfg::default(),
+            shutdown_lock: false,
+            shutdown_timeout: default_shutdown_timeout(),
+            user: None,
+        }
+    }
+}
+
+impl DaemonCfg {
+    /// Wait before the next unlock attempt on a dataset whose last
+    /// `failures` attempts failed in a row: `unlock_interval` after the
+    /// first, doubling after each further one, at most `max_backoff`.
+    pub fn backoff(&self, failures: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(failures.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.unlock_interval
+            .saturating_mul(factor)
+            .min(self.max_backoff.max(self.unlock_interval))
+    }
+}
+
+/// `[health]`: who may read `lockchain-daemon`'s HTTP health endpoint, and
+/// how the process serving it is confined.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct HealthCfg {
+    /// File holding a bearer token that every request must present as
+    /// `Authorization: Bearer <token>`; without it the endpoint is open to
+    /// anyone who can reach `LOCKCHAIN_HEALTH_ADDR`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_file: Option<String>,
+
+    /// Serve the endpoint from a separate worker process that holds no key
+    /// material and runs under a seccomp filter, instead of inside the
+    /// daemon. Read at start-up.
+    #[serde(default = "default_health_isolate")]
+    pub isolate: bool,
+
+    /// User the worker switches to when the daemon runs as root.
+    #[serde(default = "default_health_user")]
+    pub user: String,
+}
+
+fn default_health_isolate() -> bool {
+    true
+}
+
+fn default_health_user() -> String {
+    "nobody".into()
+}
+
+impl Default for HealthCfg {
+    fn default() -> Self {
+        Self {
+            token_file: None,
+            isolate: default_health_isolate(),
+            user: default_health_user(),
+        }
+    }
+}
+
+impl HealthCfg {
+    /// The configured bearer token, read fresh from `token_file` so a
+    /// rotated token applies without a restart.
+    pub fn token(&self) -> LockchainResult<Option<String>> {
+        let Some(path) = self.token_file.as_deref() else {
+            return Ok(None);
+        };
+        let token = fs::read_to_string(path)?.trim().to_string();
+        if token.is_empty() {
+            return Err(LockchainError::InvalidConfig(format!(
+                "health token file {path} is empty"
+            )));
+        }
+        Ok(Some(token))
+    }
+}
+
+/// `[remote_unlock]`: let an administrator unlock a dataset over the network
+/// while the machine waits in the initramfs, the way dropbear setups do for
+/// LUKS. Only clients presenting a certificate signed by `client_ca` get to
+/// send anything.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RemoteUnlockCfg {
+    /// Dataset to unlock, normally the one holding the root filesystem.
+    pub dataset: String,
+
+    /// Address the TLS listener binds.
+    #[serde(default = "default_remote_unlock_listen")]
+    pub listen: String,
+
+    /// PEM certificate chain the listener presents.
+    pub cert: String,
+
+    /// PEM private key for `cert`.
+    pub key: String,
+
+    /// PEM certificates of the CA that signs administrators' client certificates.
+    pub client_ca: String,
+}
+
+fn default_remote_unlock_listen() -> String {
+    "0.0.0.0:4433".into()
+}
+
+impl RemoteUnlockCfg {
+    /// Files the listener reads, which the initramfs must carry.
+    pub fn files(&self) -> [&str; 3] {
+        [&self.cert, &self.key, &self.client_ca]
+    }
+}
+
+/// `[daemon.idle_lock]`: lock datasets again once nobody has sent a
+/// keep-alive (`lockchain daemon keep-alive`) for a while, e.g. so a
+/// workstation does not stay unlocked overnight.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct IdleLockCfg {
+    /// Unload the keys of managed datasets `after` the last keep-alive; the
+    /// daemon then leaves them locked until the next keep-alive, an unlock
+    /// requested through it, or the USB key arriving.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Idle time before locking (`"8h"`; integers are seconds). The clock
+    /// starts when the daemon does.
+    #[serde(default = "default_idle_after", with = "units::secs")]
+    #[schemars(with = "units::HumanDuration")]
+    pub after: Duration,
+
+    /// How long before locking to warn in the log, the event journal, and
+    /// `idle_lock_warning` webhooks.
+    #[serde(default = "default_idle_warn_before", with = "units::secs")]
+    #[schemars(with = "units::HumanDuration")]
+    pub warn_before: Duration,
+
+    /// Managed datasets that are never idle-locked. Datasets sharing an
+    /// encryption root with one of them stay unlocked as well.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exempt: Vec<String>,
+
+    /// Unmount datasets before unloading their keys.
+    #[serde(default)]
+    pub unmount: bool,
+}
+
+fn default_idle_after() -> Duration {
+    Duration::from_secs(8 * 3600)
+}
+
+fn default_idle_warn_before() -> Duration {
+    Duration::from_secs(900)
+}
+
+impl Default for IdleLockCfg {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            after: default_idle_after(),
+            warn_before: default_idle_warn_before(),
+            exempt: Vec::new(),
+            unmount: false,
+        }
+    }
+}
+
+impl IdleLockCfg {
+    /// Whether `dataset` is listed in `exempt`.
+    pub fn exempts(&self, dataset: &str) -> bool {
+        self.exempt.iter().any(|exempt| exempt == dataset)
+    }
+}
+
+/// State transitions `lockchain-daemon` can report to a webhook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    UnlockSuccess,
+    UnlockFailure,
+    /// A break-glass recovery showed up in the audit trail.
+    Breakglass,
+    /// The staged key disappeared while the daemon was watching it.
+    UsbRemoved,
+    /// Datasets will be idle-locked soon unless a keep-alive arrives.
+    IdleLockWarning,
+    /// Datasets were locked for lack of a keep-alive.
+    IdleLock,
+}
+
+impl WebhookEvent {
+    /// Stable identifier used in config and the JSON payload.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::UnlockSuccess => "unlock_success",
+            WebhookEvent::UnlockFailure => "unlock_failure",
+            WebhookEvent::Breakglass => "breakglass",
+            WebhookEvent::UsbRemoved => "usb_removed",
+            WebhookEvent::IdleLockWarning => "idle_lock_warning",
+            WebhookEvent::IdleLock => "idle_lock",
+        }
+    }
+}
+
+/// A `[[webhooks]]` endpoint the daemon POSTs a JSON notification to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct WebhookCfg {
+    /// `http://` or `https://` URL.
+    pub url: String,
+
+    /// Extra request headers, e.g. `Authorization = "Bearer …"`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+
+    /// Events to send; every event when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<WebhookEvent>,
+
+    /// Deliveries tried before a notification is dropped.
+    #[serde(default = "default_webhook_attempts")]
+    pub attempts: u32,
+
+    /// Wait before the first retry (`"2s"`; integers are seconds), doubling
+    /// after each further failure.
+    #[serde(default = "default_webhook_backoff", with = "units::secs")]
+    #[schemars(with = "units::HumanDuration")]
+    pub backoff: Duration,
+
+    /// Per-request limit (`"10s"`; integers are seconds).
+    #[serde(default = "default_webhook_timeout", with = "units::secs")]
+    #[schemars(with = "units::HumanDuration")]
+    pub timeout: Duration,
+}
+
+fn default_webhook_attempts() -> u32 {
+    5
+}
+
+fn default_webhook_backoff() -> Duration {
+    Duration::from_secs(2)
+}
+
+fn default_webhook_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+impl WebhookCfg {
+    /// Whether `event` should be sent to this endpoint.
+    pub fn wants(&self, event: WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+/// Preferences for `lockchain tui`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TuiCfg {
+    /// Keys bound to each action, replacing its defaults, e.g.
+    /// `down = ["Down", "n"]`. Keys are single characters or names such as
+    /// `Enter`, `Esc`, `PageUp`, and `F1`; the TUI lists actions under `?`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keys: BTreeMap<String, Vec<String>>,
+
+    /// Colour preset and per-element overrides.
+    #[serde(default)]
+    pub theme: TuiTheme,
+
+    /// Directory for status exports written with `e`; defaults to the
+    /// working directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub export_dir: Option<String>,
+}
+
+/// Colour scheme for `lockchain tui`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TuiTheme {
+    /// Built-in scheme to start from.
+    #[serde(default)]
+    pub preset: ThemePreset,
+
+    /// Per-element colours over the preset, e.g. `encryption_root = "blue"`.
+    /// Values are colour names, `#rrggbb`, or a 0–255 palette index.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub colors: BTreeMap<String, String>,
+}
+
+/// Built-in colour schemes for the TUI.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemePreset {
+    #[default]
+    Default,
+    /// Darker colours for light terminal backgrounds.
+    Light,
+    /// Bright colours and bold text.
+    HighContrast,
+    /// No colour at all; emphasis through bold and reverse video only.
+    /// Also used whenever `NO_COLOR` is set.
+    Mono,
+}
+
+/// Preferences for the Control Deck (`lockchain-ui`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct UiCfg {
+    /// Colour scheme; `system` follows the desktop's dark/light preference.
+    #[serde(default)]
+    pub theme: UiTheme,
+
+    /// Hold spinners and other animations still.
+    #[serde(default)]
+    pub reduced_motion: bool,
+
+    /// Interface language such as `en` or `de`; the locale environment
+    /// (`LC_ALL`, `LC_MESSAGES`, `LANG`) decides when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
+    /// SSH destinations (`user@host`) the deck can switch to besides this
+    /// machine; their `zfs`/`zpool` run over `ssh` like `unlock --remote`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hosts: Vec<String>,
+}
+
+/// Colour schemes for the Control Deck.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum UiTheme {
+    /// `neon` or `light`, whichever matches the desktop's colour-scheme
+    /// preference when the deck starts.
+    #[default]
+    System,
+    /// The original dark neon palette.
+    Neon,
+    /// Dark text on light panels.
+    Light,
+    /// White on black with saturated status colours and heavier outlines.
+    HighContrast,
+}
+
+impl std::fmt::Display for UiTheme {
+    /// The config spelling.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::System => "system",
+            Self::Neon => "neon",
+            Self::Light => "light",
+            Self::HighContrast => "high-contrast",
+        })
+    }
+}
+
+/// Remote key source backed by HashiCorp Vault (or a KMS exposing the same API).
+///
+/// Requires `lockchain-core` built with the `vault` feature.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct VaultCfg {
+    /// Base URL, e.g. `https://vault.example.com:8200`.
+    pub address: String,
+
+    #[serde(default)]
+    pub namespace: Option<String>,
+
+    #[serde(default)]
+    pub auth: VaultAuth,
+
+    #[serde(default)]
+    pub mode: VaultMode,
+
+    /// Secrets engine mount (`transit` or a KV v2 mount such as `secret`).
+    #[serde(default = "default_vault_mount")]
+    pub mount: String,
+
+    /// Transit key name, or KV secret path.
+    pub key_name: String,
+
+    /// Transit only: file holding the `vault:v1:...` ciphertext of the dataset key.
+    #[serde(default)]
+    pub wrapped_key_path: Option<String>,
+
+    /// KV only: field of the secret holding the hex-encoded key.
+    #[serde(default = "default_vault_field")]
+    pub field: String,
+
+    /// Datasets (or encryption roots) served by Vault; empty means all of them.
+    #[serde(default)]
+    pub datasets: Vec<String>,
+
+    /// HTTP timeout for each Vault request (`"10s"`; integers are seconds).
+    #[serde(
+        default = "default_vault_timeout",
+        alias = "timeout_secs",
+        with = "units::secs"
+    )]
+    #[schemars(with = "units::HumanDuration")]
+    pub timeout: Duration,
+}
+
+/// How Lockchain authenticates to Vault.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum VaultAuth {
+    /// Static token read from `token_path`, else `VAULT_TOKEN`.
+    Token {
+        #[serde(default)]
+        token_path: Option<String>,
+    },
+    /// AppRole login; the secret id is read from `secret_id_path`.
+    Approle {
+        role_id: String,
+        secret_id_path: String,
+        #[serde(default = "default_approle_mount")]
+        mount: String,
+    },
+}
+
+impl Default for VaultAuth {
+    fn default() -> Self {
+        VaultAuth::Token { token_path: None }
+    }
+}
+
+/// Whether the key is unwrapped through transit or fetched from KV.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultMode {
+    #[default]
+    Transit,
+    Kv,
+}
+
+fn default_vault_mount() -> String {
+    "transit".to_string()
+}
+
+fn default_vault_field() -> String {
+    "key".to_string()
+}
+
+fn default_approle_mount() -> String {
+    "approle".to_string()
+}
+
+fn default_vault_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+impl VaultCfg {
+    /// Whether Vault supplies the key for `dataset` (or its encryption root).
+    pub fn serves(&self, dataset: &str, encryption_root: &str) -> bool {
+        self.datasets.is_empty()
+            || self
+                .datasets
+                .iter()
+                .any(|name| name == dataset || name == encryption_root)
+    }
+
+    /// Short description used in logs and the audit trail.
+    pub fn describe(&self) -> String {
+        format!(
+            "{}/{}/{}",
+            self.address.trim_end_matches('/'),
+            self.mount,
+            self.key_name
+        )
+    }
+}
+
+/// Pointer to a centrally published config, fetched over HTTP(S) on load.
+///
+/// A local file whose only section is `[remote]` stands in for the published
+/// document; drop-ins and profiles still apply on top of it. The download must
+/// carry a valid minisign signature from `public_key`, and the last verified
+/// copy is kept at `cache_path` for boots without network access. Fetching
+/// requires the `remote` feature; without it only the cached copy is used.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RemoteCfg {
+    /// Published config, e.g. `https://config.example.com/lockchain/host.toml`.
+    pub url: String,
+
+    /// Minisign public key (the base64 `RW...` line of `minisign.pub`).
+    pub public_key: String,
+
+    /// Detached signature; defaults to `<url>.minisig`.
+    #[serde(default)]
+    pub signature_url: Option<String>,
+
+    /// Where the last verified copy (and its `.minisig`) is kept.
+    #[serde(default = "default_remote_cache_path")]
+    pub cache_path: String,
+
+    /// HTTP timeout for each request (`"10s"`; integers are seconds).
+    #[serde(default = "default_remote_timeout", with = "units::secs")]
+    #[schemars(with = "units::HumanDuration")]
+    pub timeout: Duration,
+}
+
+impl RemoteCfg {
+    /// URL of the detached signature.
+    pub fn signature_url(&self) -> String {
+        self.signature_url
+            .clone()
+            .unwrap_or_else(|| format!("{}.minisig", self.url))
+    }
+
+    /// Whether the published document is YAML (judged by the URL's extension).
+    pub fn is_yaml(&self) -> bool {
+        let path = self.url.split(['?', '#']).next().unwrap_or_default();
+        path.ends_with(".yaml") || path.ends_with(".yml")
+    }
+}
+
+fn default_remote_cache_path() -> String {
+    "/var/lib/lockchain/remote-config".to_string()
+}
+
+fn default_remote_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+/// Top-level configuration snapshot loaded from disk.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LockchainConfig {
+    pub policy: Policy,
+
+    #[serde(default)]
+    pub crypto: CryptoCfg,
+
+    #[serde(default)]
+    pub usb: Usb,
+
+    #[serde(default)]
+    pub fallback: Fallback,
+
+    #[serde(default)]
+    pub retry: RetryCfg,
+
+    #[serde(default)]
+    pub hooks: HooksCfg,
+
+    #[serde(default)]
+    pub audit: AuditCfg,
+
+    #[serde(default)]
+    pub control: ControlCfg,
+
+    #[serde(default)]
+    pub daemon: DaemonCfg,
+
+    #[serde(default)]
+    pub health: HealthCfg,
+
+    /// Endpoints the daemon notifies of unlocks, break-glass use, and USB removal.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<WebhookCfg>,
+
+    #[serde(default)]
+    pub tui: TuiCfg,
+
+    #[serde(default)]
+    pub ui: UiCfg,
+
+    #[serde(default)]
+    pub vault: Option<VaultCfg>,
+
+    /// Set when `path` is a `[remote]` pointer rather than the config itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<RemoteCfg>,
+
+    /// Network unlock served from the initramfs by `lockchain-daemon --remote-unlock`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_unlock: Option<RemoteUnlockCfg>,
+
+    #[serde(skip)]
+    pub path: PathBuf,
+
+    #[serde(skip)]
+    pub format: ConfigFormat,
+
+    /// Drop-in fragments from `<path>.d/` merged over the base file, in order.
+    #[serde(skip)]
+    pub fragments: Vec<PathBuf>,
+
+    /// Named per-machine overrides (`[profile.<name>]`) selected with `--profile`.
+    #[serde(
+        default,
+        rename = "profile",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub profiles: BTreeMap<String, serde_json::Value>,
+
+    /// Profile applied on load, if any.
+    #[serde(skip)]
+    pub profile: Option<ActiveProfile>,
+
+    /// Unknown or deprecated keys noticed on load; repeated by [`Self::validate`].
+    #[serde(skip)]
+    pub load_issues: Vec<ConfigIssue>,
+
+    /// What was read from `path`; `None` for a config built in memory.
+    #[serde(skip)]
+    pub source: Option<LoadedSource>,
+}
+
+/// A `[profile.<name>]` overlay applied on top of the base configuration.
+#[derive(Debug, Clone)]
+pub struct ActiveProfile {
+    name: String,
+}
+
+/// The base file as written and the settings it resolved to on load, so a
+/// save writes back only what changed since.
+#[derive(Debug, Clone)]
+pub struct LoadedSource {
+    /// Base file alone, before drop-ins and profiles.
+    raw: serde_json::Value,
+    /// Settings after drop-ins and profiles, secrets as found on disk.
+    resolved: serde_json::Value,
+}
+
+impl ActiveProfile {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Tracks whether we parsed TOML or YAML so writes preserve format.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum ConfigFormat {
+    #[default]
+    Toml,
+    Yaml,
+}
+
+/// Whether a validation issue blocks use of the configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueSeverity {
+    Warning,
+    Error,
+}
+
+/// One finding from [`LockchainConfig::validate`], keyed by a stable `LC11xx` code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigIssue {
+    pub code: &'static str,
+    /// Dotted path of the offending key, e.g. `usb.device_label`.
+    pub field: String,
+    pub severity: IssueSeverity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+impl ConfigIssue {
+    fn error(code: &'static str, field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(code, field, IssueSeverity::Error, message)
+    }
+
+    fn warning(code: &'static str, field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(code, field, IssueSeverity::Warning, message)
+    }
+
+    fn new(
+        code: &'static str,
+        field: impl Into<String>,
+        severity: IssueSeverity,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            code,
+            field: field.into(),
+            severity,
+            message: message.into(),
+            suggestion: None,
+        }
+    }
+
+    fn suggest(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+
+    /// Whether this issue makes the configuration unusable.
+    pub fn is_error(&self) -> bool {
+        self.severity == IssueSeverity::Error
+    }
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.code, self.message)
+    }
+}
+
+impl LockchainConfig {
+    /// Fresh configuration for `datasets` with every other section at its
+    /// defaults, saved to `path` as TOML.
+    pub fn starter(path: impl Into<PathBuf>, datasets: Vec<String>) -> Self {
+        Self {
+            policy: Policy {
+                datasets,
+                zfs_path: None,
+                zpool_path: None,
+                binary_path: None,
+                allow_root: false,
+                unlock_window: None,
+                require_strict_usb: false,
+            },
+            crypto: CryptoCfg::default(),
+            usb: Usb::default(),
+            fallback: Fallback::default(),
+            retry: RetryCfg::default(),
+            hooks: HooksCfg::default(),
+            audit: AuditCfg::default(),
+            control: ControlCfg::default(),
+            daemon: DaemonCfg::default(),
+            health: HealthCfg::default(),
+            webhooks: Vec::new(),
+            tui: TuiCfg::default(),
+            ui: UiCfg::default(),
+            vault: None,
+            remote: None,
+            remote_unlock: None,
+            path: path.into(),
+            format: ConfigFormat::Toml,
+            fragments: Vec::new(),
+            profiles: BTreeMap::new(),
+            profile: None,
+            load_issues: Vec::new(),
+            source: None,
+        }
+    }
+
+    /// Read a config file from disk, detect format, and validate basics.
+    ///
+    /// `*.toml` fragments in the drop-in directory `<path>.d/` are merged over
+    /// the base file in lexical order: `policy.datasets` entries are appended
+    /// (skipping duplicates), tables merge key by key, and any other value in a
+    /// fragment replaces the base value. A file holding a `[remote]` table is
+    /// replaced by the signed document it points at (see [`RemoteCfg`]).
+    pub fn load<P: AsRef<Path>>(path: P) -> LockchainResult<Self> {
+        Self::load_profile(path, None)
+    }
+
+    /// Like [`Self::load`], then overlay `[profile.<name>]` when `profile` is set.
+    ///
+    /// Tables in the profile merge key by key into the base; arrays (such as
+    /// `policy.datasets`) and scalars replace the base value outright.
+    ///
+    /// Unknown keys (usually typos) and deprecated spellings do not fail the
+    /// load; they are logged once per process and kept in `load_issues`.
+    pub fn load_profile<P: AsRef<Path>>(path: P, profile: Option<&str>) -> LockchainResult<Self> {
+        let path = path.as_ref();
+        let mut contents = fs::read_to_string(path)?;
+        let mut is_toml = matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some(ext) if ext.eq_ignore_ascii_case("toml")
+        );
+        let remote = remote::pointer(&contents, is_toml)?;
+        if let Some(pointer) = &remote {
+            contents = remote::fetch(pointer)?;
+            is_toml = !pointer.is_yaml();
+        }
+        let fragments = fragment_paths(path)?;
+        let mut active = None;
+        let mut ignored = Vec::new();
+        let mut record = |key: serde_ignored::Path| ignored.push(key_segments(&key));
+        let mut base: serde_json::Value = if is_toml {
+            toml::from_str(&contents)?
+        } else {
+            serde_yaml::from_str(&contents)?
+        };
+        let mut deprecated = rename_deprecated(&mut base);
+        let mut cfg: Self = if fragments.is_empty() && profile.is_none() && deprecated.is_empty() {
+            if is_toml {
+                serde_ignored::deserialize(toml::Deserializer::new(&contents), &mut record)?
+            } else {
+                serde_ignored::deserialize(
+                    serde_yaml::Deserializer::from_str(&contents),
+                    &mut record,
+                )?
+            }
+        } else {
+            // Each document gets current key names before merging, so a
+            // deprecated spelling in one and the current name in another do
+            // not meet as duplicate fields.
+            let mut merged = base.clone();
+            for fragment in &fragments {
+                let mut overlay: serde_json::Value = toml::from_str(&fs::read_to_string(fragment)?)
+                    .map_err(|err| {
+                        LockchainError::InvalidConfig(format!("{}: {err}", fragment.display()))
+                    })?;
+                deprecated.extend(rename_deprecated(&mut overlay));
+                merge_fragment(&mut merged, overlay, "");
+            }
+            if let Some(name) = profile {
+                apply_profile(&mut merged, name)?;
+                active = Some(ActiveProfile {
+                    name: name.to_string(),
+                });
+            }
+            serde_ignored::deserialize(merged, &mut record).map_err(|err: serde_json::Error| {
+                let source = match profile {
+                    Some(name) => format!("{} with profile {name}", path.display()),
+                    None if fragments.is_empty() => path.display().to_string(),
+                    None => format!("{} with drop-ins", path.display()),
+                };
+                LockchainError::InvalidConfig(format!("{source}: {err}"))
+            })?
+        };
+
+        cfg.path = path.to_path_buf();
+        cfg.format = if is_toml {
+            ConfigFormat::Toml
+        } else {
+            ConfigFormat::Yaml
+        };
+        cfg.fragments = fragments;
+        cfg.profile = active;
+        cfg.remote = remote;
+        cfg.load_issues = key_issues(&deprecated, &ignored);
+        report_load_issues(path, &cfg.load_issues);
+        cfg.source = Some(LoadedSource {
+            raw: base,
+            resolved: serde_json::to_value(&cfg).map_err(|err| {
+                LockchainError::InvalidConfig(format!("cannot serialize config: {err}"))
+            })?,
+        });
+        cfg.fallback.unseal_secrets()?;
+
+        if cfg.policy.datasets.is_empty() {
+            return Err(LockchainError::InvalidConfig(
+                "policy.datasets must list at least one dataset".to_string(),
+            ));
+        }
+
+        Ok(cfg)
+    }
+
+    /// Directory scanned for drop-in fragments of this config.
+    pub fn drop_in_dir(&self) -> PathBuf {
+        drop_in_dir(&self.path)
+    }
+
+    /// Returns true when `dataset` is listed under `policy.datasets`.
+    pub fn contains_dataset(&self, dataset: &str) -> bool {
+        self.policy.datasets.iter().any(|d| d == dataset)
+    }
+
+    /// Perform a best-effort validation pass and return typed issues.
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let mut issues = self.load_issues.clone();
+
+        if self.policy.datasets.is_empty() {
+            issues.push(
+                ConfigIssue::error(
+                    "LC1101",
+                    "policy.datasets",
+                    "policy.datasets must contain at least one dataset",
+                )
+                .suggest("list the encryption roots lockchain should manage"),
+            );
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        for ds in &self.policy.datasets {
+            if ds.trim().is_empty() {
+                issues.push(ConfigIssue::error(
+                    "LC1101",
+                    "policy.datasets",
+                    "policy.datasets contains an empty dataset entry",
+                ));
+            }
+            if !seen.insert(ds) {
+                issues.push(
+                    ConfigIssue::error(
+                        "LC1102",
+                        "policy.datasets",
+                        format!("duplicate dataset entry detected: {ds}"),
+                    )
+                    .suggest("remove the repeated entry (drop-ins may already add it)"),
+                );
+            }
+        }
+
+        if let Err(LockchainError::InvalidConfig(msg)) = self.policy.unlock_window() {
+            issues.push(
+                ConfigIssue::error("LC1103", "policy.unlock_window", msg)
+                    .suggest("use a local-time range such as \"08:00-18:00\""),
+            );
+        }
+
+        for (field, path) in [
+            ("policy.zfs_path", &self.policy.zfs_path),
+            ("policy.zpool_path", &self.policy.zpool_path),
+            ("policy.binary_path", &self.policy.binary_path),
+        ] {
+            if let Some(path) = path.as_deref().filter(|path| !Path::new(path).exists()) {
+                issues.push(
+                    ConfigIssue::error("LC1104", field, format!("{field} {path} does not exist"))
+                        .suggest(format!(
+                            "install the binary or remove {field} to use the default"
+                        )),
+                );
+            }
+        }
+
+        if let Some(expected) = &self.usb.expected_sha256 {
+            if expected.len() != 64 || hex::decode(expected).is_err() {
+                issues.push(ConfigIssue::error(
+                    "LC1110",
+                    "usb.expected_sha256",
+                    "usb.expected_sha256 must be a 64-character hex string",
+                ));
+            }
+        }
+
+        if self.usb.device_label.is_some() && self.usb.device_uuid.is_some() {
+            issues.push(
+                ConfigIssue::warning(
+                    "LC1111",
+                    "usb.device_label",
+                    "usb.device_label and usb.device_uuid are both set; units mount by UUID while the USB watcher requires both to match",
+                )
+                .suggest("keep only one of usb.device_label or usb.device_uuid after re-labelling a token"),
+            );
+        }
+
+        for (dataset, entry) in &self.usb.keys {
+            let field = format!("usb.keys.\"{dataset}\"");
+            if entry.path().trim().is_empty() {
+                issues.push(ConfigIssue::error(
+                    "LC1112",
+                    field.clone(),
+                    format!("{field} must name a key file"),
+                ));
+            }
+            if let Some(sha) = entry.sha256() {
+                if sha.len() != 64 || hex::decode(sha).is_err() {
+                    issues.push(ConfigIssue::error(
+                        "LC1110",
+                        format!("{field}.sha256"),
+                        format!("{field}.sha256 must be a 64-character hex string"),
+                    ));
+                }
+            }
+        }
+
+        for (index, token) in self.usb.tokens.iter().enumerate() {
+            let field = format!("usb.tokens[{index}]");
+            if !token.has_selector() {
+                issues.push(
+                    ConfigIssue::error(
+                        "LC1113",
+                        field.clone(),
+                        format!("{field} needs a label, uuid, or serial"),
+                    )
+                    .suggest("copy the values from `lsblk -o NAME,LABEL,UUID,SERIAL`"),
+                );
+            }
+            if token
+                .key_path
+                .as_deref()
+                .is_some_and(|path| path.trim().is_empty())
+            {
+                issues.push(ConfigIssue::error(
+                    "LC1112",
+                    format!("{field}.key_path"),
+                    format!("{field}.key_path must name a key file"),
+                ));
+            }
+            if let Some(sha) = &token.sha256 {
+                if sha.len() != 64 || hex::decode(sha).is_err() {
+                    issues.push(ConfigIssue::error(
+                        "LC1110",
+                        format!("{field}.sha256"),
+                        format!("{field}.sha256 must be a 64-character hex string"),
+                    ));
+                }
+            }
+        }
+
+        if self.fallback.enabled {
+            for (field, value) in [
+                ("fallback.passphrase_salt", &self.fallback.passphrase_salt),
+                ("fallback.passphrase_xor", &self.fallback.passphrase_xor),
+            ] {
+                if value.is_none() {
+                    issues.push(
+                        ConfigIssue::error(
+                            "LC1120",
+                            field,
+                            format!("fallback.enabled is true but {field} is missing"),
+                        )
+                        .suggest(
+                            "run `lockchain init --passphrase` or set fallback.enabled = false",
+                        ),
+                    );
+                }
+            }
+            if self.fallback.passphrase_iters < MIN_PASSPHRASE_ITERS {
+                issues.push(
+                    ConfigIssue::warning(
+                        "LC1121",
+                        "fallback.passphrase_iters",
+                        format!(
+                            "fallback.passphrase_iters is below {MIN_PASSPHRASE_ITERS} (found {}), which makes the passphrase cheap to guess",
+                            self.fallback.passphrase_iters
+                        ),
+                    )
+                    .suggest(
+                        "run `lockchain benchmark-kdf --write` to raise it as far as this machine allows",
+                    ),
+                );
+            }
+        }
+
+        if let Some(vault) = &self.vault {
+            if vault.address.trim().is_empty() {
+                issues.push(ConfigIssue::error(
+                    "LC1130",
+                    "vault.address",
+                    "vault.address must not be empty",
+                ));
+            }
+            if vault.key_name.trim().is_empty() {
+                issues.push(ConfigIssue::error(
+                    "LC1130",
+                    "vault.key_name",
+                    "vault.key_name must not be empty",
+                ));
+            }
+            if vault.mode == VaultMode::Transit && vault.wrapped_key_path.is_none() {
+                issues.push(
+                    ConfigIssue::error(
+                        "LC1131",
+                        "vault.wrapped_key_path",
+                        "vault.mode = \"transit\" requires vault.wrapped_key_path",
+                    )
+                    .suggest("point vault.wrapped_key_path at the transit ciphertext or use mode = \"kv\""),
+                );
+            }
+        }
+
+        if self.retry.max_attempts == 0 {
+            issues.push(ConfigIssue::error(
+                "LC1140",
+                "retry.max_attempts",
+                "retry.max_attempts must be at least 1",
+            ));
+        }
+        if self.retry.base_delay.is_zero() {
+            issues.push(ConfigIssue::error(
+                "LC1140",
+                "retry.base_delay",
+                "retry.base_delay must be greater than 0",
+            ));
+        }
+        if self.retry.max_delay < self.retry.base_delay {
+            issues.push(ConfigIssue::error(
+                "LC1141",
+                "retry.max_delay",
+                "retry.max_delay must be greater than or equal to retry.base_delay",
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.retry.jitter_ratio) {
+            issues.push(ConfigIssue::error(
+                "LC1140",
+                "retry.jitter_ratio",
+                "retry.jitter_ratio must be between 0.0 and 1.0",
+            ));
+        }
+
+        if self.audit.enabled && self.audit.path.trim().is_empty() {
+            issues.push(
+                ConfigIssue::error(
+                    "LC1150",
+                    "audit.path",
+                    "audit.path must not be empty when auditing is enabled",
+                )
+                .suggest("set audit.path or audit.enabled = false"),
+            );
+        }
+
+        if self.hooks.timeout.is_zero() {
+            issues.push(ConfigIssue::error(
+                "LC1160",
+                "hooks.timeout",
+                "hooks.timeout must be greater than 0",
+            ));
+        }
+        for (stage, commands) in [
+            ("pre_unlock", &self.hooks.pre_unlock),
+            ("post_unlock", &self.hooks.post_unlock),
+            ("pre_lock", &self.hooks.pre_lock),
+            ("post_lock", &self.hooks.post_lock),
+        ] {
+            if commands.iter().any(|cmd| cmd.trim().is_empty()) {
+                issues.push(ConfigIssue::error(
+                    "LC1160",
+                    format!("hooks.{stage}"),
+                    format!("hooks.{stage} contains an empty command"),
+                ));
+            }
+        }
+
+        if self.daemon.unlock_interval.is_zero() {
+            issues.push(ConfigIssue::error(
+                "LC1195",
+                "daemon.unlock_interval",
+                "daemon.unlock_interval must be greater than 0",
+            ));
+        }
+        if self.daemon.max_backoff < self.daemon.unlock_interval {
+            issues.push(
+                ConfigIssue::warning(
+                    "LC1196",
+                    "daemon.max_backoff",
+                    "daemon.max_backoff is shorter than daemon.unlock_interval, so failed unlocks are retried every pass",
+                )
+                .suggest("raise daemon.max_backoff to enable backoff"),
+            );
+        }
+
+        let idle = &self.daemon.idle_lock;
+        if idle.enabled {
+            if idle.after.is_zero() {
+                issues.push(ConfigIssue::error(
+                    "LC1199",
+                    "daemon.idle_lock.after",
+                    "daemon.idle_lock.after must be greater than 0",
+                ));
+            } else if idle.warn_before >= idle.after {
+                issues.push(
+                    ConfigIssue::warning(
+                        "LC1199",
+                        "daemon.idle_lock.warn_before",
+                        "daemon.idle_lock.warn_before is not shorter than daemon.idle_lock.after, so the warning goes out as soon as the clock starts",
+                    )
+                    .suggest("lower daemon.idle_lock.warn_before"),
+                );
+            }
+            for dataset in &idle.exempt {
+                if !self.contains_dataset(dataset) {
+                    issues.push(
+                        ConfigIssue::warning(
+                            "LC1199",
+                            "daemon.idle_lock.exempt",
+                            format!("daemon.idle_lock.exempt lists {dataset}, which policy.datasets does not manage"),
+                        )
+                        .suggest("exempt datasets from policy.datasets"),
+                    );
+                }
+            }
+        }
+
+        match self.health.token_file.as_deref().map(str::trim) {
+            Some("") => issues.push(ConfigIssue::error(
+                "LC1197",
+                "health.token_file",
+                "health.token_file must not be empty",
+            )),
+            Some(path) if !Path::new(path).is_file() => issues.push(
+                ConfigIssue::warning(
+                    "LC1197",
+                    "health.token_file",
+                    format!("health.token_file {path} does not exist; the health endpoint refuses every request"),
+                )
+                .suggest("create it with e.g. `openssl rand -hex 32`, readable by the daemon only"),
+            ),
+            _ => {}
+        }
+        let user = self.health.user.trim();
+        if self.health.isolate && (user.is_empty() || user == "root") {
+            issues.push(ConfigIssue::error(
+                "LC1198",
+                "health.user",
+                "health.user must name an unprivileged user",
+            ));
+        }
+
+        for (index, webhook) in self.webhooks.iter().enumerate() {
+            let field = format!("webhooks[{index}]");
+            let url = webhook.url.trim();
+            let scheme_ok = url.split_once("://").is_some_and(|(scheme, rest)| {
+                matches!(scheme, "http" | "https") && !rest.is_empty()
+            });
+            if !scheme_ok {
+                issues.push(
+                    ConfigIssue::error(
+                        "LC1190",
+                        format!("{field}.url"),
+                        format!("{field}.url must be an http:// or https:// URL"),
+                    )
+                    .suggest("paste the full endpoint, e.g. https://hooks.slack.com/services/…"),
+                );
+            }
+            for name in webhook.headers.keys() {
+                if name.is_empty() || name.contains(|c: char| c == ':' || c.is_whitespace()) {
+                    issues.push(ConfigIssue::error(
+                        "LC1190",
+                        format!("{field}.headers"),
+                        format!("{field}.headers has an invalid header name `{name}`"),
+                    ));
+                }
+            }
+            if webhook.attempts == 0 {
+                issues.push(ConfigIssue::error(
+                    "LC1191",
+                    format!("{field}.attempts"),
+                    format!("{field}.attempts must be at least 1"),
+                ));
+            }
+            if webhook.timeout.is_zero() {
+                issues.push(ConfigIssue::error(
+                    "LC1191",
+                    format!("{field}.timeout"),
+                    format!("{field}.timeout must be greater than 0"),
+                ));
+            }
+        }
+
+        if let Some(remote_unlock) = &self.remote_unlock {
+            if !self.contains_dataset(&remote_unlock.dataset) {
+                issues.push(
+                    ConfigIssue::error(
+                        "LC1185",
+                        "remote_unlock.dataset",
+                        format!(
+                            "remote_unlock.dataset {} is not in policy.datasets",
+                            remote_unlock.dataset
+                        ),
+                    )
+                    .suggest("name the dataset holding the root filesystem"),
+                );
+            }
+            if remote_unlock
+                .listen
+                .parse::<std::net::SocketAddr>()
+                .is_err()
+            {
+                issues.push(ConfigIssue::error(
+                    "LC1185",
+                    "remote_unlock.listen",
+                    format!(
+                        "remote_unlock.listen `{}` is not an address:port",
+                        remote_unlock.listen
+                    ),
+                ));
+            }
+            for (name, path) in ["cert", "key", "client_ca"]
+                .into_iter()
+                .zip(remote_unlock.files())
+            {
+                if !Path::new(path).is_file() {
+                    issues.push(ConfigIssue::error(
+                        "LC1186",
+                        format!("remote_unlock.{name}"),
+                        format!("remote_unlock.{name} {path} does not exist"),
+                    ));
+                }
+            }
+        }
+
+        let mut hosts = std::collections::HashSet::new();
+        for host in &self.ui.hosts {
+            if host.is_empty() || host.starts_with('-') || host.contains(char::is_whitespace) {
+                issues.push(
+                    ConfigIssue::error(
+                        "LC1180",
+                        "ui.hosts",
+                        format!("ui.hosts entry `{host}` is not an ssh destination"),
+                    )
+                    .suggest("use user@host, e.g. root@nas1"),
+                );
+            } else if !hosts.insert(host) {
+                issues.push(ConfigIssue::warning(
+                    "LC1180",
+                    "ui.hosts",
+                    format!("duplicate ui.hosts entry: {host}"),
+                ));
+            }
+        }
+
+        issues
+    }
+
+    /// Resolve the path where the USB key material should live.
+    pub fn key_hex_path(&self) -> PathBuf {
+        if let Ok(override_path) = env::var(KEY_PATH_ENV) {
+            if !override_path.is_empty() {
+                return PathBuf::from(override_path);
+            }
+        }
+        PathBuf::from(&self.usb.key_hex_path)
+    }
+
+    /// Pick the key file for `dataset`, preferring a `[usb.keys]` entry for the
+    /// dataset, then one for its encryption root, then the shared key.
+    pub fn key_source(&self, dataset: &str, encryption_root: &str) -> KeySource {
+        [dataset, encryption_root]
+            .into_iter()
+            .find_map(|name| self.usb.keys.get_key_value(name))
+            .map(|(name, entry)| self.dataset_key_source(name, entry))
+            .unwrap_or_else(|| self.default_key_source())
+    }
+
+    /// Vault settings when Vault supplies the key for `dataset`.
+    pub fn vault_for(&self, dataset: &str, encryption_root: &str) -> Option<&VaultCfg> {
+        self.vault
+            .as_ref()
+            .filter(|vault| vault.serves(dataset, encryption_root))
+    }
+
+    /// Every distinct key file the configuration refers to, shared key first.
+    pub fn key_sources(&self) -> Vec<KeySource> {
+        let mut sources = vec![self.default_key_source()];
+        for (name, entry) in &self.usb.keys {
+            let source = self.dataset_key_source(name, entry);
+            if !sources.iter().any(|known| known.path == source.path) {
+                sources.push(source);
+            }
+        }
+        sources
+    }
+
+    /// Record `digest` as the expected checksum for the shared key or a `[usb.keys]` entry.
+    pub fn pin_key_checksum(&mut self, entry: Option<&str>, digest: String) {
+        match entry {
+            Some(name) => {
+                if let Some(key) = self.usb.keys.get_mut(name) {
+                    let path = key.path().to_string();
+                    *key = DatasetKey::Detailed {
+                        path,
+                        sha256: Some(digest),
+                    };
+                }
+            }
+            None => self.usb.expected_sha256 = Some(digest),
+        }
+    }
+
+    /// Key source backed by `usb.key_hex_path` and `usb.expected_sha256`.
+    fn default_key_source(&self) -> KeySource {
+        KeySource {
+            path: self.key_hex_path(),
+            expected_sha256: self.usb.expected_sha256.clone(),
+            entry: None,
+        }
+    }
+
+    /// Resolve a `[usb.keys]` entry relative to the shared key's directory.
+    fn dataset_key_source(&self, name: &str, entry: &DatasetKey) -> KeySource {
+        let configured = Path::new(entry.path());
+        let path = if configured.is_absolute() {
+            configured.to_path_buf()
+        } else {
+            self.key_hex_path()
+                .parent()
+                .unwrap_or_else(|| Path::new("/"))
+                .join(configured)
+        };
+        KeySource {
+            path,
+            expected_sha256: entry.sha256().map(str::to_string),
+            entry: Some(name.to_string()),
+        }
+    }
+
+    /// Limit applied to each `zfs`/`zpool` invocation.
+    pub fn zfs_timeout(&self) -> Duration {
+        self.crypto.timeout
+    }
+
+    /// Optional override for the `zfs` CLI path.
+    pub fn zfs_binary_path(&self) -> Option<PathBuf> {
+        self.policy.zfs_path.as_ref().map(PathBuf::from)
+    }
+
+    /// Optional override for the `zpool` CLI path.
+    pub fn zpool_binary_path(&self) -> Option<PathBuf> {
+        self.policy.zpool_path.as_ref().map(PathBuf::from)
+    }
+
+    /// Access the shared retry configuration helpers rely on.
+    pub fn retry_config(&self) -> &RetryCfg {
+        &self.retry
+    }
+
+    /// Seal fallback secrets with the seal key from the next save on,
+    /// creating the key with the config file's group if there is none.
+    pub fn seal_secrets(&mut self) -> LockchainResult<()> {
+        self.ensure_seal_key()?;
+        // Fail early if this host cannot derive the sealing key.
+        let mut probe = self.fallback.clone();
+        probe.seal_secrets()?;
+        self.fallback.sealed = true;
+        Ok(())
+    }
+
+    /// Create the seal key, group-readable like the config file, if missing.
+    fn ensure_seal_key(&self) -> LockchainResult<PathBuf> {
+        let group = fs::metadata(&self.path).ok().map(|meta| meta.gid());
+        seal::ensure_seal_key(group)
+    }
+
+    /// Persist the configuration back to its original on-disk format.
+    ///
+    /// The file is replaced atomically, keeping its permissions.
+    ///
+    /// For a config loaded from disk only the settings changed since the load
+    /// are written, into the base file as it was read; values contributed by
+    /// drop-in fragments stay in their fragments. Datasets added or removed
+    /// are added to or removed from the base list.
+    ///
+    /// Fallback secrets that were sealed when loaded (or after
+    /// [`Self::seal_secrets`]) are sealed again before writing.
+    ///
+    /// With a profile active, changes to sections the profile overrides are
+    /// written into `[profile.<name>]` and every other change into the base.
+    pub fn save(&self) -> LockchainResult<()> {
+        if let Some(remote) = &self.remote {
+            return Err(LockchainError::InvalidConfig(format!(
+                "{} points at the published config {}; change it there or add a drop-in",
+                self.path.display(),
+                remote.url
+            )));
+        }
+        let mut on_disk = self.clone();
+        if on_disk.fallback.sealed {
+            self.ensure_seal_key()?;
+            on_disk.fallback.seal_secrets()?;
+        }
+        let payload = match &self.source {
+            Some(source) => {
+                let resolved = serde_json::to_value(&on_disk).map_err(|err| {
+                    LockchainError::InvalidConfig(format!("cannot serialize config: {err}"))
+                })?;
+                let mut changes = Vec::new();
+                collect_changes(&source.resolved, &resolved, &mut Vec::new(), &mut changes);
+                let mut document = source.raw.clone();
+                let profile = self.profile.as_ref().map(ActiveProfile::name);
+                for (key, before, after) in changes {
+                    write_change(&mut document, profile, &key, before, after);
+                }
+                strip_nulls(&mut document);
+                match self.format {
+                    ConfigFormat::Toml => toml::to_string_pretty(&document)?,
+                    ConfigFormat::Yaml => serde_yaml::to_string(&document)?,
+                }
+            }
+            None => match self.format {
+                ConfigFormat::Toml => toml::to_string_pretty(&on_disk)?,
+                ConfigFormat::Yaml => serde_yaml::to_string(&on_disk)?,
+            },
+        };
+        crate::edit::write_atomic(&self.path, &payload)
+    }
+}
+
+/// Legacy keys still accepted as serde aliases: `(section, old, new)`.
+pub(crate) const DEPRECATED_KEYS: &[(&str, &str, &str)] = &[
+    ("crypto", "timeout_secs", "timeout"),
+    ("usb", "mount_timeout_secs", "mount_timeout"),
+    ("usb", "key_cache_ttl_secs", "key_cache_ttl"),
+    ("retry", "base_delay_ms", "base_delay"),
+    ("retry", "max_delay_ms", "max_delay"),
+    ("hooks", "timeout_secs", "timeout"),
+    ("vault", "timeout_secs", "timeout"),
+];
+
+/// Dotted segments of a key serde skipped, without `Option`/newtype markers.
+fn key_segments(path: &serde_ignored::Path) -> Vec<String> {
+    use serde_ignored::Path;
+    match path {
+        Path::Root => Vec::new(),
+        Path::Seq { parent, index } => {
+            let mut segments = key_segments(parent);
+            segments.push(index.to_string());
+            segments
+        }
+        Path::Map { parent, key } => {
+            let mut segments = key_segments(parent);
+            segments.push(key.clone());
+            segments
+        }
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => key_segments(parent),
+    }
+}
+
+/// Rename deprecated keys in `document`, and in each of its `[profile.*]`
+/// tables, to their current names. Returns the dotted old and new names of
+/// each key renamed; where both spellings are set the current one wins.
+fn rename_deprecated(document: &mut serde_json::Value) -> Vec<(String, String)> {
+    fn rename(table: &mut serde_json::Value, prefix: &str, renamed: &mut Vec<(String, String)>) {
+        for (section, old, new) in DEPRECATED_KEYS {
+            let Some(section_table) = table.get_mut(section).and_then(|t| t.as_object_mut()) else {
+                continue;
+            };
+            if let Some(value) = section_table.remove(*old) {
+                section_table.entry(*new).or_insert(value);
+                renamed.push((
+                    format!("{prefix}{section}.{old}"),
+                    format!("{prefix}{section}.{new}"),
+                ));
+            }
+        }
+    }
+
+    let mut renamed = Vec::new();
+    rename(document, "", &mut renamed);
+    if let Some(profiles) = document
+        .get_mut("profile")
+        .and_then(|profiles| profiles.as_object_mut())
+    {
+        for (name, table) in profiles.iter_mut() {
+            rename(table, &format!("profile.{name}."), &mut renamed);
+        }
+    }
+    renamed
+}
+
+/// Warnings for keys serde ignored and for the `deprecated` spellings
+/// [`rename_deprecated`] replaced.
+fn key_issues(deprecated: &[(String, String)], ignored: &[Vec<String>]) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+    if !ignored.is_empty() {
+        let schema = serde_json::to_value(schemars::schema_for!(LockchainConfig))
+            .unwrap_or(serde_json::Value::Null);
+        for segments in ignored {
+            let Some((key, parent)) = segments.split_last() else {
+                continue;
+            };
+            let field = segments.join(".");
+            let mut issue = ConfigIssue::warning(
+                "LC1170",
+                field.clone(),
+                format!("unknown key `{field}` is ignored"),
+            );
+            if let Some(nearest) = nearest_key(key, &schema_fields(&schema, parent)) {
+                let mut suggested = parent.to_vec();
+                suggested.push(nearest);
+                issue = issue.suggest(format!("did you mean `{}`?", suggested.join(".")));
+            }
+            issues.push(issue);
+        }
+    }
+    for (old, new) in deprecated {
+        issues.push(
+            ConfigIssue::warning("LC1171", old.clone(), format!("`{old}` is deprecated")).suggest(
+                format!("rename it to `{new}` and give a duration such as \"10s\""),
+            ),
+        );
+    }
+    issues
+}
+
+/// Property names the schema allows under `parent`.
+fn schema_fields(root: &serde_json::Value, parent: &[String]) -> Vec<String> {
+    let mut node = root;
+    for segment in parent {
+        let next = schema_variants(root, node).into_iter().find_map(|variant| {
+            variant
+                .get("properties")
+                .and_then(|props| props.get(segment))
+                .or_else(|| {
+                    segment
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|_| variant.get("items"))
+                })
+                .or_else(|| {
+                    variant
+                        .get("additionalProperties")
+                        .filter(|v| v.is_object())
+                })
+        });
+        match next {
+            Some(next) => node = next,
+            None => return Vec::new(),
+        }
+    }
+    schema_variants(root, node)
+        .into_iter()
+        .filter_map(|variant| variant.get("properties")?.as_object())
+        .flat_map(|props| props.keys().cloned())
+        .collect()
+}
+
+/// `node` plus every schema it references or combines through `allOf`/`anyOf`/`oneOf`.
+fn schema_variants<'a>(
+    root: &'a serde_json::Value,
+    node: &'a serde_json::Value,
+) -> Vec<&'a serde_json::Value> {
+    let mut variants = vec![node];
+    if let Some(target) = node
+        .get("$ref")
+        .and_then(|reference| reference.as_str())
+        .and_then(|reference| reference.strip_prefix("#/definitions/"))
+        .and_then(|name| root.get("definitions")?.get(name))
+    {
+        variants.extend(schema_variants(root, target));
+    }
+    for combinator in ["allOf", "anyOf", "oneOf"] {
+        for member in node
+            .get(combinator)
+            .and_then(|members| members.as_array())
+            .into_iter()
+            .flatten()
+        {
+            variants.extend(schema_variants(root, member));
+        }
+    }
+    variants
+}
+
+/// Closest candidate to `key` by edit distance, if it is plausibly a typo.
+pub(crate) fn nearest_key(key: &str, candidates: &[String]) -> Option<String> {
+    let limit = (key.len() / 3).max(2);
+    candidates
+        .iter()
+        .map(|candidate| (strsim::levenshtein(key, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.clone())
+}
+
+/// Log load-time issues once per process, so repeated reloads stay quiet.
+fn report_load_issues(path: &Path, issues: &[ConfigIssue]) {
+    static REPORTED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    let mut reported = REPORTED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    for issue in issues {
+        if reported.insert(format!("{}:{}", path.display(), issue.field)) {
+            match &issue.suggestion {
+                Some(hint) => warn!("{}: {issue} ({hint})", path.display()),
+                None => warn!("{}: {issue}", path.display()),
+            }
+        }
+    }
+}
+
+/// `<config path>.d`, e.g. `/etc/lockchain-zfs.toml.d`.
+pub(crate) fn drop_in_dir(path: &Path) -> PathBuf {
+    let mut dir = path.as_os_str().to_os_string();
+    dir.push(".d");
+    PathBuf::from(dir)
+}
+
+/// Sorted `*.toml` fragments next to `path`; a missing directory means none.
+fn fragment_paths(path: &Path) -> LockchainResult<Vec<PathBuf>> {
+    let entries = match fs::read_dir(drop_in_dir(path)) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut fragments = Vec::new();
+    for entry in entries {
+        let candidate = entry?.path();
+        let is_toml = candidate
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+        if is_toml && candidate.is_file() {
+            fragments.push(candidate);
+        }
+    }
+    fragments.sort();
+    Ok(fragments)
+}
+
+/// Overlay `fragment` onto `base`; `key_path` is the dotted path of `base`.
+fn merge_fragment(base: &mut serde_json::Value, fragment: serde_json::Value, key_path: &str) {
+    use serde_json::Value;
+    match (base, fragment) {
+        (Value::Object(base), Value::Object(fragment)) => {
+            for (key, value) in fragment {
+                let child_path = if key_path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{key_path}.{key}")
+                };
+                match base.get_mut(&key) {
+                    Some(existing) => merge_fragment(existing, value, &child_path),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(base), Value::Array(fragment)) if key_path == "policy.datasets" => {
+            for dataset in fragment {
+                if !base.contains(&dataset) {
+                    base.push(dataset);
+                }
+            }
+        }
+        (base, fragment) => *base = fragment,
+    }
+}
+
+/// Overlay `[profile.<name>]` from `merged` onto its base sections.
+fn apply_profile(merged: &mut serde_json::Value, name: &str) -> LockchainResult<()> {
+    let profiles = merged
+        .get("profile")
+        .and_then(|profiles| profiles.as_object());
+    let Some(overlay) = profiles.and_then(|profiles| profiles.get(name)).cloned() else {
+        let known: Vec<&str> = profiles
+            .map(|profiles| profiles.keys().map(String::as_str).collect())
+            .unwrap_or_default();
+        return Err(LockchainError::InvalidConfig(if known.is_empty() {
+            format!("profile `{name}` requested but no [profile.*] tables are defined")
+        } else {
+            format!(
+                "profile `{name}` is not defined (available: {})",
+                known.join(", ")
+            )
+        }));
+    };
+    if !overlay.is_object() {
+        return Err(LockchainError::InvalidConfig(format!(
+            "profile.{name} must be a table"
+        )));
+    }
+    if overlay.get("profile").is_some() {
+        return Err(LockchainError::InvalidConfig(format!(
+            "profile.{name} cannot nest further profiles"
+        )));
+    }
+    overlay_profile(merged, overlay);
+    Ok(())
+}
+
+/// Merge tables recursively; anything else in `overlay` replaces `base`.
+fn overlay_profile(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    use serde_json::Value;
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => overlay_profile(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Drop `null` entries (unset options), which TOML cannot represent.
+fn strip_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, value| !value.is_null());
+            map.values_mut().for_each(strip_nulls);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(strip_nulls),
+        _ => {}
+    }
+}
+
+/// Settings that differ between `before` and `after`, as dotted key segments
+/// with the old and new value (`None` when absent).
+fn collect_changes<'a>(
+    before: &'a serde_json::Value,
+    after: &'a serde_json::Value,
+    key: &mut Vec<String>,
+    changes: &mut Vec<(
+        Vec<String>,
+        Option<&'a serde_json::Value>,
+        Option<&'a serde_json::Value>,
+    )>,
+) {
+    use serde_json::Value;
+    let (Value::Object(before), Value::Object(after)) = (before, after) else {
+        if before != after {
+            changes.push((key.clone(), Some(before), Some(after)));
+        }
+        return;
+    };
+    for (name, new) in after {
+        key.push(name.clone());
+        match before.get(name) {
+            Some(old) => collect_changes(old, new, key, changes),
+            None => changes.push((key.clone(), None, Some(new))),
+        }
+        key.pop();
+    }
+    for (name, old) in before {
+        if !after.contains_key(name) {
+            key.push(name.clone());
+            changes.push((key.clone(), Some(old), None));
+            key.pop();
+        }
+    }
+}
+
+/// Write one changed setting into `document`, the base file as read: into
+/// `[profile.<name>]` when the active profile overrides its section, else
+/// into the base. Datasets are edited entry by entry in the base list, so
+/// those merged from drop-ins stay out of it.
+fn write_change(
+    document: &mut serde_json::Value,
+    profile: Option<&str>,
+    key: &[String],
+    before: Option<&serde_json::Value>,
+    after: Option<&serde_json::Value>,
+) {
+    use serde_json::Value;
+    let Some((last, parents)) = key.split_last() else {
+        return;
+    };
+    let profile = profile.filter(|name| {
+        document
+            .pointer(&format!("/profile/{name}/{}", key[0]))
+            .is_some()
+    });
+    let mut table = document;
+    for segment in profile
+        .into_iter()
+        .flat_map(|name| ["profile", name])
+        .chain(parents.iter().map(String::as_str))
+    {
+        table = child(table, segment);
+    }
+    match (after, before) {
+        (Some(Value::Array(after)), Some(Value::Array(before)))
+            if profile.is_none() && key == ["policy", "datasets"] =>
+        {
+            let list = child(table, last);
+            if !list.is_array() {
+                *list = Value::Array(Vec::new());
+            }
+            if let Value::Array(list) = list {
+                list.retain(|dataset| after.contains(dataset));
+                for dataset in after {
+                    if !before.contains(dataset) && !list.contains(dataset) {
+                        list.push(dataset.clone());
+                    }
+                }
+            }
+        }
+        (Some(after), _) => *child(table, last) = after.clone(),
+        (None, _) => {
+            if let Value::Object(map) = table {
+                map.remove(last);
+            }
+        }
+    }
+}
+
+/// The entry `segment` of `table`, turning `table` into a table and adding
+/// the entry (as `null`) when missing.
+fn child<'a>(table: &'a mut serde_json::Value, segment: &str) -> &'a mut serde_json::Value {
+    use serde_json::{Map, Value};
+    if !table.is_object() {
+        *table = Value::Object(Map::new());
+    }
+    match table {
+        Value::Object(map) => map.entry(segment).or_insert(Value::Null),
+        _ => unreachable!("just made a table"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    struct EnvGuard {
+        key: &'static str,
+        prev: Option<String>,
+    }
+
+    impl EnvGuard {
+        fn set(key: &'static str, value: impl Into<String>) -> Self {
             let prev = env::var(key).ok();
             env::set_var(key, value.into());
             Self { key, prev }
@@ -381,18 +2767,873 @@ mod tests {
                 zpool_path: None,
                 binary_path: None,
                 allow_root: false,
+                unlock_window: None,
+                require_strict_usb: false,
+            },
+            crypto: CryptoCfg {
+                timeout: Duration::from_secs(1),
             },
-            crypto: CryptoCfg { timeout_secs: 1 },
             usb: Usb::default(),
             fallback: Fallback::default(),
             retry: RetryCfg::default(),
+            hooks: HooksCfg::default(),
+            audit: AuditCfg::default(),
+            control: ControlCfg::default(),
+            daemon: DaemonCfg::default(),
+            health: HealthCfg::default(),
+            webhooks: Vec::new(),
+            tui: TuiCfg::default(),
+            ui: UiCfg::default(),
+            vault: None,
+            remote: None,
+            remote_unlock: None,
             path: PathBuf::new(),
             format: ConfigFormat::Toml,
+            fragments: Vec::new(),
+            profiles: BTreeMap::new(),
+            profile: None,
+            load_issues: Vec::new(),
+            source: None,
         };
 
+        let _lock = ENV_LOCK.lock().unwrap();
         let guard = EnvGuard::set(KEY_PATH_ENV, "/tmp/override.key");
         assert_eq!(config.key_hex_path(), PathBuf::from("/tmp/override.key"));
         drop(guard);
         assert_eq!(config.key_hex_path(), PathBuf::from(default_usb_key_path()));
     }
+
+    #[test]
+    fn key_source_prefers_dataset_then_encryption_root() {
+        let toml = r#"
+            [policy]
+            datasets = ["tank/secure", "tank/media", "tank/other"]
+
+            [usb]
+            key_hex_path = "/run/lockchain/key.hex"
+            expected_sha256 = "aa"
+
+            [usb.keys]
+            "tank/secure" = "secure.key"
+            "tank" = { path = "/etc/lockchain/tank.key", sha256 = "bb" }
+        "#;
+        let config: LockchainConfig = toml::from_str(toml).unwrap();
+        let _lock = ENV_LOCK.lock().unwrap();
+
+        let secure = config.key_source("tank/secure", "tank");
+        assert_eq!(secure.path, PathBuf::from("/run/lockchain/secure.key"));
+        assert_eq!(secure.expected_sha256, None);
+
+        let media = config.key_source("tank/media", "tank");
+        assert_eq!(media.path, PathBuf::from("/etc/lockchain/tank.key"));
+        assert_eq!(media.expected_sha256.as_deref(), Some("bb"));
+
+        let other = config.key_source("vault/other", "vault/other");
+        assert_eq!(other.path, PathBuf::from("/run/lockchain/key.hex"));
+        assert_eq!(other.setting(), "usb.expected_sha256");
+
+        assert_eq!(config.key_sources().len(), 3);
+    }
+
+    #[test]
+    fn unlock_window_handles_overnight_ranges() {
+        let at = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+
+        let day = UnlockWindow::parse("06:00-22:00").unwrap();
+        assert!(day.contains(at(6, 0)));
+        assert!(!day.contains(at(22, 0)));
+        assert!(!day.contains(at(3, 30)));
+
+        let night = UnlockWindow::parse("22:00 - 06:00").unwrap();
+        assert!(night.contains(at(23, 15)));
+        assert!(night.contains(at(5, 59)));
+        assert!(!night.contains(at(12, 0)));
+
+        assert!(UnlockWindow::parse("6am-10pm").is_err());
+    }
+
+    #[test]
+    fn dataset_patterns_match_components_and_prefixes() {
+        assert!(dataset_pattern_matches("tank/*", "tank/secure"));
+        assert!(!dataset_pattern_matches("tank/*", "tank/secure/vm"));
+        assert!(!dataset_pattern_matches("tank/*", "tank"));
+        assert!(dataset_pattern_matches("tank/vm?", "tank/vm1"));
+        assert!(dataset_pattern_matches("tank/vms/%", "tank/vms/web/disk0"));
+        assert!(!dataset_pattern_matches("tank/vms/%", "tank/vms"));
+        assert!(dataset_pattern_matches("*/secure", "backup/secure"));
+        assert!(is_dataset_pattern("tank/%") && !is_dataset_pattern("tank/secure"));
+
+        let policy = Policy {
+            datasets: vec!["tank/a".into(), "pool/b".into(), "tank/c".into()],
+            zfs_path: None,
+            zpool_path: None,
+            binary_path: None,
+            allow_root: false,
+            unlock_window: None,
+            require_strict_usb: false,
+        };
+        assert_eq!(policy.datasets_matching("tank/*"), vec!["tank/a", "tank/c"]);
+        assert!(policy.datasets_matching("media/%").is_empty());
+    }
+
+    #[test]
+    fn retry_delays_follow_strategy() {
+        let mut rng = rand::rngs::mock::StepRng::new(u64::MAX / 2, 0);
+        let mut retry = RetryCfg {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            jitter_ratio: 0.0,
+            ..RetryCfg::default()
+        };
+        fn delays<R: Rng>(retry: &RetryCfg, rng: &mut R) -> Vec<u128> {
+            (1..=5)
+                .map(|n| retry.delay_for(n, rng).as_millis())
+                .collect()
+        }
+
+        assert_eq!(delays(&retry, &mut rng), vec![100, 200, 400, 800, 1_000]);
+
+        retry.strategy = RetryStrategy::Fixed;
+        assert_eq!(delays(&retry, &mut rng), vec![100; 5]);
+
+        retry.strategy = RetryStrategy::ExponentialFullJitter;
+        let mut real = rand::thread_rng();
+        for (n, delay) in (1..=5).zip(delays(&retry, &mut real)) {
+            assert!(delay <= (100u128 << (n - 1)).min(1_000));
+        }
+
+        let parsed: RetryCfg = toml::from_str("strategy = \"exponential_full_jitter\"").unwrap();
+        assert_eq!(parsed.strategy, RetryStrategy::ExponentialFullJitter);
+    }
+
+    #[test]
+    fn vault_section_selects_datasets() {
+        let toml = r#"
+            [policy]
+            datasets = ["tank/secure", "tank/media"]
+
+            [vault]
+            address = "https://vault.example.com:8200"
+            key_name = "lockchain"
+            wrapped_key_path = "/etc/lockchain/tank.wrapped"
+            datasets = ["tank/secure"]
+
+            [vault.auth]
+            method = "approle"
+            role_id = "lockchain"
+            secret_id_path = "/etc/lockchain/secret-id"
+        "#;
+        let config: LockchainConfig = toml::from_str(toml).unwrap();
+        let vault = config.vault_for("tank/secure", "tank/secure").unwrap();
+        assert_eq!(vault.mode, VaultMode::Transit);
+        assert_eq!(vault.mount, "transit");
+        assert!(matches!(vault.auth, VaultAuth::Approle { ref mount, .. } if mount == "approle"));
+        assert!(config.vault_for("tank/media", "tank/media").is_none());
+        assert!(!config
+            .validate()
+            .iter()
+            .any(|issue| issue.field.starts_with("vault")));
+    }
+
+    #[test]
+    fn drop_in_fragments_merge_over_base() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lockchain-zfs.toml");
+        fs::write(
+            &path,
+            r#"
+                [policy]
+                datasets = ["tank/secure"]
+
+                [usb]
+                key_hex_path = "/run/lockchain/key.hex"
+                device_label = "LOCKCHAIN"
+            "#,
+        )
+        .unwrap();
+        let drop_in = dir.path().join("lockchain-zfs.toml.d");
+        fs::create_dir(&drop_in).unwrap();
+        fs::write(
+            drop_in.join("10-media.toml"),
+            "[policy]\ndatasets = [\"tank/media\", \"tank/secure\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            drop_in.join("20-usb.toml"),
+            "[usb]\ndevice_label = \"VAULTKEY\"\n",
+        )
+        .unwrap();
+        fs::write(drop_in.join("README"), "ignored").unwrap();
+
+        let config = LockchainConfig::load(&path).unwrap();
+        assert_eq!(config.policy.datasets, vec!["tank/secure", "tank/media"]);
+        assert_eq!(config.usb.device_label.as_deref(), Some("VAULTKEY"));
+        assert_eq!(config.usb.key_hex_path, "/run/lockchain/key.hex");
+        assert_eq!(config.fragments.len(), 2);
+    }
+
+    #[test]
+    fn save_keeps_drop_in_values_out_of_the_base_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lockchain-zfs.toml");
+        fs::write(
+            &path,
+            "[policy]\ndatasets = [\"tank/a\", \"tank/old\"]\n\n[retry]\nbase_delay = \"2s\"\n",
+        )
+        .unwrap();
+        let drop_in = dir.path().join("lockchain-zfs.toml.d");
+        fs::create_dir(&drop_in).unwrap();
+        let fragment = drop_in.join("10-site.toml");
+        fs::write(
+            &fragment,
+            "[policy]\ndatasets = [\"tank/b\"]\n\n[retry]\nmax_attempts = 9\n",
+        )
+        .unwrap();
+
+        let mut config = LockchainConfig::load(&path).unwrap();
+        assert_eq!(config.retry.max_attempts, 9);
+        config.usb.expected_sha256 = Some("ab".repeat(32));
+        config
+            .policy
+            .datasets
+            .retain(|dataset| dataset != "tank/old");
+        config.policy.datasets.push("tank/c".into());
+        config.save().unwrap();
+
+        let base: toml::Value = toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            base["policy"]["datasets"],
+            toml::Value::try_from(["tank/a", "tank/c"]).unwrap()
+        );
+        assert!(base["retry"].get("max_attempts").is_none());
+        assert_eq!(base["retry"]["base_delay"].as_str(), Some("2s"));
+        assert_eq!(
+            base["usb"]["expected_sha256"].as_str(),
+            Some(&*"ab".repeat(32))
+        );
+
+        fs::remove_file(&fragment).unwrap();
+        let config = LockchainConfig::load(&path).unwrap();
+        assert_eq!(config.policy.datasets, vec!["tank/a", "tank/c"]);
+        assert_eq!(config.retry.max_attempts, RetryCfg::default().max_attempts);
+    }
+
+    #[test]
+    fn deprecated_keys_give_way_to_current_names_in_other_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lockchain-zfs.toml");
+        fs::write(
+            &path,
+            r#"
+            [policy]
+            datasets = ["tank/secure"]
+
+            [crypto]
+            timeout_secs = 5
+
+            [profile.lab.retry]
+            base_delay_ms = 250
+        "#,
+        )
+        .unwrap();
+        let drop_in = dir.path().join("lockchain-zfs.toml.d");
+        fs::create_dir(&drop_in).unwrap();
+        fs::write(drop_in.join("10-site.toml"), "[crypto]\ntimeout = \"7s\"\n").unwrap();
+        fs::write(
+            drop_in.join("20-retry.toml"),
+            "[retry]\nbase_delay = \"2s\"\nmax_delay_ms = 9000\n",
+        )
+        .unwrap();
+
+        let config = LockchainConfig::load_profile(&path, Some("lab")).unwrap();
+        assert_eq!(config.zfs_timeout(), Duration::from_secs(7));
+        assert_eq!(config.retry.base_delay, Duration::from_millis(250));
+        assert_eq!(config.retry.max_delay, Duration::from_secs(9));
+        let deprecated: Vec<&str> = config
+            .load_issues
+            .iter()
+            .filter(|issue| issue.code == "LC1171")
+            .map(|issue| issue.field.as_str())
+            .collect();
+        assert_eq!(
+            deprecated,
+            [
+                "crypto.timeout_secs",
+                "profile.lab.retry.base_delay_ms",
+                "retry.max_delay_ms"
+            ]
+        );
+    }
+
+    #[test]
+    fn sealed_fallback_secrets_round_trip() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let _lock = ENV_LOCK.lock().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let machine_id = dir.path().join("machine-id");
+        fs::write(&machine_id, "0123456789abcdef0123456789abcdef\n").unwrap();
+        let _guard = EnvGuard::set(seal::MACHINE_ID_ENV, machine_id.display().to_string());
+        let seal_key = dir.path().join("lockchain").join("seal.key");
+        let _key_guard = EnvGuard::set(seal::SEAL_KEY_ENV, seal_key.display().to_string());
+
+        let path = dir.path().join("lockchain-zfs.toml");
+        fs::write(
+            &path,
+            r#"
+                [policy]
+                datasets = ["tank/secure"]
+
+                [fallback]
+                passphrase_salt = "aabb"
+                passphrase_xor = "ccdd"
+            "#,
+        )
+        .unwrap();
+
+        let mut config = LockchainConfig::load(&path).unwrap();
+        assert!(!config.fallback.sealed);
+        config.seal_secrets().unwrap();
+        config.save().unwrap();
+
+        let on_disk = fs::read_to_string(&path).unwrap();
+        assert!(!on_disk.contains("aabb") && !on_disk.contains("ccdd"));
+        assert!(on_disk.contains(seal::SEALED_PREFIX));
+        let mode = fs::metadata(&seal_key).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o440);
+
+        let reloaded = LockchainConfig::load(&path).unwrap();
+        assert!(reloaded.fallback.sealed);
+        assert_eq!(reloaded.fallback.passphrase_salt.as_deref(), Some("aabb"));
+        assert_eq!(reloaded.fallback.passphrase_xor.as_deref(), Some("ccdd"));
+
+        let salt = seal::seal_value("fallback.passphrase_salt", "aabb").unwrap();
+        assert!(seal::unseal_value("fallback.passphrase_xor", &salt).is_err());
+
+        // The machine-id no longer matters; only the seal key does.
+        fs::write(&machine_id, "ffffffffffffffffffffffffffffffff\n").unwrap();
+        assert!(LockchainConfig::load(&path).is_ok());
+        fs::set_permissions(&seal_key, fs::Permissions::from_mode(0o444)).unwrap();
+        assert!(LockchainConfig::load(&path).is_err());
+        fs::remove_file(&seal_key).unwrap();
+        assert!(LockchainConfig::load(&path).is_err());
+        assert!(!seal_key.exists());
+    }
+
+    #[test]
+    fn machine_id_sealed_values_still_open_and_are_resealed() {
+        use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+        use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+        use sha2::{Digest, Sha256};
+
+        let _lock = ENV_LOCK.lock().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let machine_id = dir.path().join("machine-id");
+        fs::write(&machine_id, "0123456789abcdef0123456789abcdef\n").unwrap();
+        let _guard = EnvGuard::set(seal::MACHINE_ID_ENV, machine_id.display().to_string());
+        let seal_key = dir.path().join("seal.key");
+        let _key_guard = EnvGuard::set(seal::SEAL_KEY_ENV, seal_key.display().to_string());
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"lockchain-zfs config seal v1\0");
+        hasher.update(b"0123456789abcdef0123456789abcdef");
+        let cipher = ChaCha20Poly1305::new_from_slice(&hasher.finalize()).unwrap();
+        let legacy = |field: &str, value: &str| {
+            let nonce = [7u8; 12];
+            let mut blob = nonce.to_vec();
+            blob.extend(
+                cipher
+                    .encrypt(
+                        &Nonce::from(nonce),
+                        Payload {
+                            msg: value.as_bytes(),
+                            aad: field.as_bytes(),
+                        },
+                    )
+                    .unwrap(),
+            );
+            format!("{}{}", seal::LEGACY_SEALED_PREFIX, hex::encode(blob))
+        };
+
+        let path = dir.path().join("lockchain-zfs.toml");
+        fs::write(
+            &path,
+            format!(
+                "[policy]\ndatasets = [\"tank/secure\"]\n\n[fallback]\npassphrase_salt = \"{}\"\npassphrase_xor = \"{}\"\n",
+                legacy("fallback.passphrase_salt", "aabb"),
+                legacy("fallback.passphrase_xor", "ccdd"),
+            ),
+        )
+        .unwrap();
+
+        let config = LockchainConfig::load(&path).unwrap();
+        assert!(config.fallback.sealed);
+        assert_eq!(config.fallback.passphrase_salt.as_deref(), Some("aabb"));
+        config.save().unwrap();
+
+        let on_disk = fs::read_to_string(&path).unwrap();
+        assert!(!on_disk.contains(seal::LEGACY_SEALED_PREFIX));
+        assert!(on_disk.contains(seal::SEALED_PREFIX));
+        let reloaded = LockchainConfig::load(&path).unwrap();
+        assert_eq!(reloaded.fallback.passphrase_xor.as_deref(), Some("ccdd"));
+    }
+
+    #[test]
+    fn validate_reports_typed_cross_field_issues() {
+        let toml = r#"
+            [policy]
+            datasets = ["tank/secure"]
+            zfs_path = "/nonexistent/sbin/zfs"
+
+            [usb]
+            device_label = "LOCKCHAIN"
+            device_uuid = "1234-ABCD"
+
+            [fallback]
+            enabled = true
+            passphrase_salt = "aabb"
+            passphrase_xor = "ccdd"
+            passphrase_iters = 1000
+        "#;
+        let config: LockchainConfig = toml::from_str(toml).unwrap();
+        let issues = config.validate();
+        let find = |code: &str| issues.iter().find(|issue| issue.code == code).unwrap();
+
+        let binary = find("LC1104");
+        assert_eq!(binary.field, "policy.zfs_path");
+        assert!(binary.is_error());
+
+        let usb = find("LC1111");
+        assert_eq!(usb.severity, IssueSeverity::Warning);
+        assert!(usb.suggestion.is_some());
+
+        let iters = find("LC1121");
+        assert_eq!(iters.field, "fallback.passphrase_iters");
+        assert!(iters.to_string().starts_with("[LC1121] "));
+        assert_eq!(issues.len(), 3);
+
+        let json = serde_json::to_value(iters).unwrap();
+        assert_eq!(json["severity"], "warning");
+        assert_eq!(json["code"], "LC1121");
+        assert!(json["suggestion"]
+            .as_str()
+            .unwrap()
+            .contains("benchmark-kdf --write"));
+    }
+
+    #[test]
+    fn ui_hosts_must_be_ssh_destinations() {
+        let toml = r#"
+            [policy]
+            datasets = ["tank/secure"]
+
+            [ui]
+            hosts = ["root@nas1", "-oProxyCommand=x", "root@nas1"]
+        "#;
+        let config: LockchainConfig = toml::from_str(toml).unwrap();
+        let hosts: Vec<_> = config
+            .validate()
+            .into_iter()
+            .filter(|issue| issue.code == "LC1180")
+            .collect();
+        assert_eq!(hosts.len(), 2);
+        assert!(hosts[0].is_error());
+        assert_eq!(hosts[1].severity, IssueSeverity::Warning);
+    }
+
+    #[test]
+    fn profiles_overlay_base_and_route_saves() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lockchain-zfs.toml");
+        fs::write(
+            &path,
+            r#"
+                [policy]
+                datasets = ["tank/secure", "tank/home"]
+
+                [usb]
+                device_label = "LOCKCHAIN"
+
+                [retry]
+                max_attempts = 5
+
+                [profile.lab.policy]
+                datasets = ["lab/scratch"]
+
+                [profile.lab.usb]
+                device_label = "LABKEY"
+            "#,
+        )
+        .unwrap();
+
+        let base = LockchainConfig::load(&path).unwrap();
+        assert!(base.profile.is_none());
+        assert_eq!(base.policy.datasets, vec!["tank/secure", "tank/home"]);
+
+        let mut lab = LockchainConfig::load_profile(&path, Some("lab")).unwrap();
+        assert_eq!(lab.profile.as_ref().unwrap().name(), "lab");
+        assert_eq!(lab.policy.datasets, vec!["lab/scratch"]);
+        assert_eq!(lab.usb.device_label.as_deref(), Some("LABKEY"));
+        assert_eq!(lab.retry.max_attempts, 5);
+
+        lab.usb.expected_sha256 = Some("ab".repeat(32));
+        lab.retry.max_attempts = 7;
+        lab.save().unwrap();
+
+        let base = LockchainConfig::load(&path).unwrap();
+        assert_eq!(base.policy.datasets, vec!["tank/secure", "tank/home"]);
+        assert_eq!(base.usb.device_label.as_deref(), Some("LOCKCHAIN"));
+        assert!(base.usb.expected_sha256.is_none());
+        assert_eq!(base.retry.max_attempts, 7);
+        let lab = LockchainConfig::load_profile(&path, Some("lab")).unwrap();
+        assert_eq!(lab.usb.expected_sha256, Some("ab".repeat(32)));
+
+        let err = LockchainConfig::load_profile(&path, Some("prod")).unwrap_err();
+        assert!(err.to_string().contains("available: lab"));
+    }
+
+    #[test]
+    fn legacy_integer_durations_still_load() {
+        let toml = r#"
+            [policy]
+            datasets = ["tank/secure"]
+
+            [crypto]
+            timeout_secs = 7
+
+            [usb]
+            mount_timeout_secs = 20
+            key_cache_ttl = "5m"
+
+            [retry]
+            base_delay_ms = 250
+            max_delay = "2s"
+
+            [audit]
+            max_bytes = "1MiB"
+        "#;
+        let config: LockchainConfig = toml::from_str(toml).unwrap();
+        assert_eq!(config.zfs_timeout(), Duration::from_secs(7));
+        assert_eq!(config.usb.mount_timeout, Duration::from_secs(20));
+        assert_eq!(config.usb.key_cache_ttl, Duration::from_secs(300));
+        assert_eq!(config.retry.base_delay, Duration::from_millis(250));
+        assert_eq!(config.retry.max_delay, Duration::from_secs(2));
+        assert_eq!(config.audit.max_bytes, 1 << 20);
+
+        let written = toml::to_string(&config).unwrap();
+        assert!(written.contains("timeout = \"7s\""));
+        assert!(written.contains("base_delay = \"250ms\""));
+    }
+
+    #[test]
+    fn usb_removal_lock_is_opt_in() {
+        let toml = r#"
+            [policy]
+            datasets = ["tank/secure"]
+        "#;
+        let config: LockchainConfig = toml::from_str(toml).unwrap();
+        assert_eq!(config.usb.removal, RemovalCfg::default());
+        assert!(!config.usb.removal.lock);
+
+        let toml = r#"
+            [policy]
+            datasets = ["tank/secure"]
+
+            [usb.removal]
+            lock = true
+            grace = 5
+            unmount = true
+        "#;
+        let config: LockchainConfig = toml::from_str(toml).unwrap();
+        assert!(config.usb.removal.lock && config.usb.removal.unmount);
+        assert_eq!(config.usb.removal.grace, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn daemon_backoff_doubles_up_to_its_cap() {
+        let toml = r#"
+            [policy]
+            datasets = ["tank/secure"]
+
+            [daemon]
+            unlock_interval = "10s"
+            max_backoff = "1m"
+        "#;
+        let config: LockchainConfig = toml::from_str(toml).unwrap();
+        let daemon = &config.daemon;
+        assert_eq!(daemon.warn_locked_after, Duration::from_secs(300));
+        assert!(!daemon.shutdown_lock);
+        assert_eq!(daemon.shutdown_timeout, Duration::from_secs(30));
+        let waits: Vec<u64> = (1..6).map(|n| daemon.backoff(n).as_secs()).collect();
+        assert_eq!(waits, [10, 20, 40, 60, 60]);
+        assert_eq!(daemon.backoff(40), Duration::from_secs(60));
+        assert!(!config
+            .validate()
+            .iter()
+            .any(|issue| issue.field.starts_with("daemon.")));
+
+        let never = DaemonCfg {
+            max_backoff: Duration::ZERO,
+            ..DaemonCfg::default()
+        };
+        assert_eq!(never.backoff(3), never.unlock_interval);
+    }
+
+    #[test]
+    fn idle_lock_is_opt_in_and_checks_its_exemptions() {
+        let toml = r#"
+            [policy]
+            datasets = ["tank/secure", "tank/vms"]
+        "#;
+        let config: LockchainConfig = toml::from_str(toml).unwrap();
+        assert_eq!(config.daemon.idle_lock, IdleLockCfg::default());
+        assert!(!config.daemon.idle_lock.enabled);
+
+        let toml = r#"
+            [policy]
+            datasets = ["tank/secure", "tank/vms"]
+
+            [daemon.idle_lock]
+            enabled = true
+            after = "2h"
+            warn_before = "10m"
+            exempt = ["tank/vms", "tank/gone"]
+        "#;
+        let mut config: LockchainConfig = toml::from_str(toml).unwrap();
+        let idle = &config.daemon.idle_lock;
+        assert_eq!(idle.after, Duration::from_secs(7200));
+        assert_eq!(idle.warn_before, Duration::from_secs(600));
+        assert!(idle.exempts("tank/vms") && !idle.exempts("tank/secure"));
+        let issues: Vec<_> = config
+            .validate()
+            .into_iter()
+            .filter(|issue| issue.code == "LC1199")
+            .collect();
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.contains("tank/gone"));
+
+        config.daemon.idle_lock.after = Duration::ZERO;
+        assert!(config
+            .validate()
+            .iter()
+            .any(|issue| issue.field == "daemon.idle_lock.after" && issue.is_error()));
+    }
+
+    #[test]
+    fn health_token_is_read_trimmed_and_validated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("health.token");
+        let health = HealthCfg {
+            token_file: Some(path.to_string_lossy().into_owned()),
+            ..HealthCfg::default()
+        };
+        let mut config =
+            LockchainConfig::starter(dir.path().join("config.toml"), vec!["tank/secure".into()]);
+        config.health = health.clone();
+        let issues = config.validate();
+        let missing = issues
+            .iter()
+            .find(|issue| issue.field == "health.token_file")
+            .unwrap();
+        assert_eq!(missing.code, "LC1197");
+        assert!(health.token().is_err());
+
+        fs::write(&path, "s3cret\n").unwrap();
+        assert_eq!(health.token().unwrap().as_deref(), Some("s3cret"));
+        assert!(!config
+            .validate()
+            .iter()
+            .any(|issue| issue.field == "health.token_file"));
+
+        fs::write(&path, "  \n").unwrap();
+        assert!(health.token().is_err());
+        assert_eq!(HealthCfg::default().token().unwrap(), None);
+    }
+
+    #[test]
+    fn health_worker_is_isolated_by_default_and_never_root() {
+        let mut config: LockchainConfig = toml::from_str(
+            r#"
+            [policy]
+            datasets = ["tank/secure"]
+        "#,
+        )
+        .unwrap();
+        assert!(config.health.isolate);
+        assert_eq!(config.health.user, "nobody");
+        let health_issues = |config: &LockchainConfig| {
+            config
+                .validate()
+                .into_iter()
+                .filter(|issue| issue.field.starts_with("health."))
+                .count()
+        };
+        assert_eq!(health_issues(&config), 0);
+
+        config.health.user = "root".into();
+        assert_eq!(health_issues(&config), 1);
+        config.health.isolate = false;
+        assert_eq!(health_issues(&config), 0);
+    }
+
+    #[test]
+    fn remote_unlock_needs_a_managed_dataset_and_its_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let pem = dir.path().join("server.pem");
+        fs::write(&pem, "").unwrap();
+        let pem = pem.display();
+        let mut config: LockchainConfig = toml::from_str(&format!(
+            r#"
+            [policy]
+            datasets = ["rpool/ROOT"]
+
+            [remote_unlock]
+            dataset = "rpool/ROOT"
+            cert = "{pem}"
+            key = "{pem}"
+            client_ca = "{pem}"
+        "#
+        ))
+        .unwrap();
+        let remote = config.remote_unlock.clone().unwrap();
+        assert_eq!(remote.listen, "0.0.0.0:4433");
+        let codes = |config: &LockchainConfig| {
+            config
+                .validate()
+                .into_iter()
+                .filter(|issue| issue.field.starts_with("remote_unlock."))
+                .map(|issue| issue.code)
+                .collect::<Vec<_>>()
+        };
+        assert!(codes(&config).is_empty());
+
+        config.remote_unlock = Some(RemoteUnlockCfg {
+            dataset: "tank/other".into(),
+            listen: "somewhere".into(),
+            client_ca: dir.path().join("missing.pem").display().to_string(),
+            ..remote
+        });
+        assert_eq!(codes(&config), ["LC1185", "LC1185", "LC1186"]);
+    }
+
+    #[test]
+    fn webhooks_parse_with_defaults_and_validate_urls() {
+        let toml = r#"
+            [policy]
+            datasets = ["tank/secure"]
+
+            [[webhooks]]
+            url = "https://hooks.example.com/lockchain"
+            events = ["unlock_failure", "usb_removed"]
+            headers = { Authorization = "Bearer t0ken" }
+
+            [[webhooks]]
+            url = "hooks.example.com"
+            attempts = 0
+        "#;
+        let config: LockchainConfig = toml::from_str(toml).unwrap();
+        let hook = &config.webhooks[0];
+        assert_eq!(hook.attempts, 5);
+        assert_eq!(hook.backoff, Duration::from_secs(2));
+        assert!(hook.wants(WebhookEvent::UsbRemoved));
+        assert!(!hook.wants(WebhookEvent::UnlockSuccess));
+        assert!(config.webhooks[1].wants(WebhookEvent::Breakglass));
+
+        let issues: Vec<_> = config
+            .validate()
+            .into_iter()
+            .map(|issue| (issue.code, issue.field))
+            .collect();
+        assert!(issues.contains(&("LC1190", "webhooks[1].url".into())));
+        assert!(issues.contains(&("LC1191", "webhooks[1].attempts".into())));
+        assert!(!issues
+            .iter()
+            .any(|(_, field)| field.starts_with("webhooks[0]")));
+    }
+
+    #[test]
+    fn usb_tokens_follow_primary_in_precedence_order() {
+        let toml = r#"
+            [policy]
+            datasets = ["tank/secure"]
+
+            [usb]
+            device_label = "LOCKCHAIN"
+            expected_sha256 = "aa"
+
+            [[usb.tokens]]
+            name = "spare"
+            serial = "4C530001"
+            key_path = "spare.key"
+
+            [[usb.tokens]]
+            uuid = "1234-ABCD"
+        "#;
+        let config: LockchainConfig = toml::from_str(toml).unwrap();
+        let tokens = config.usb.recognized_tokens();
+        let names: Vec<&str> = tokens.iter().map(UsbToken::name).collect();
+        assert_eq!(names, vec!["primary", "spare", "token 3"]);
+        assert_eq!(tokens[1].key_path(), "spare.key");
+        assert_eq!(tokens[2].key_path(), "key.hex");
+        assert_eq!(tokens[2].sha256.as_deref(), Some("aa"));
+
+        assert!(tokens[1].matches(None, None, &[Some("4C530001"), None]));
+        assert!(!tokens[1].matches(None, None, &[Some("OTHER"), None]));
+        assert!(!tokens[0].matches(Some("SPARE"), None, &[]));
+
+        let mut bare = config.clone();
+        bare.usb.device_label = None;
+        bare.usb.tokens.push(UsbToken::default());
+        assert_eq!(bare.usb.recognized_tokens().len(), 3);
+        assert!(bare
+            .validate()
+            .iter()
+            .any(|issue| issue.code == "LC1113" && issue.field == "usb.tokens[2]"));
+    }
+
+    #[test]
+    fn load_warns_on_unknown_and_deprecated_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lockchain-zfs.toml");
+        fs::write(
+            &path,
+            r#"
+            [policy]
+            datasets = ["tank/secure"]
+
+            [crypto]
+            timeout_secs = 5
+
+            [usb]
+            expected_sha246 = "abc"
+
+            [[usb.tokens]]
+            lable = "SPARE"
+            serial = "4C53"
+
+            [telemetry]
+            enabled = true
+        "#,
+        )
+        .unwrap();
+
+        let config = LockchainConfig::load(&path).unwrap();
+        assert_eq!(config.zfs_timeout(), Duration::from_secs(5));
+        let issues = config.validate();
+        let find = |field: &str| issues.iter().find(|issue| issue.field == field).unwrap();
+
+        let typo = find("usb.expected_sha246");
+        assert_eq!(typo.code, "LC1170");
+        assert_eq!(typo.severity, IssueSeverity::Warning);
+        assert_eq!(
+            typo.suggestion.as_deref(),
+            Some("did you mean `usb.expected_sha256`?")
+        );
+        assert_eq!(
+            find("usb.tokens.0.lable").suggestion.as_deref(),
+            Some("did you mean `usb.tokens.0.label`?")
+        );
+        assert!(find("telemetry").suggestion.is_none());
+        assert_eq!(find("crypto.timeout_secs").code, "LC1171");
+    }
 }
@@ -0,0 +1,610 @@
+//! Control-socket protocol between `lockchain-daemon` and its clients: one
+//! JSON-RPC 2.0 request per line on a Unix socket, answered by one response line.
+
+use crate::config::ControlCfg;
+use crate::error::{LockchainError, LockchainResult};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::env;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Where the daemon listens unless `LOCKCHAIN_CONTROL_SOCKET` says otherwise.
+pub const DEFAULT_SOCKET_PATH: &str = "/run/lockchain/daemon.sock";
+
+const SOCKET_ENV: &str = "LOCKCHAIN_CONTROL_SOCKET";
+
+/// JSON-RPC error code for a request line that is not valid JSON-RPC.
+pub const PARSE_ERROR: i64 = -32700;
+
+/// JSON-RPC error code for a method that ran and failed.
+pub const CALL_FAILED: i64 = -32000;
+
+/// JSON-RPC error code for a caller whose credentials do not allow the method.
+pub const ACCESS_DENIED: i64 = -32001;
+
+/// Audit records `recent_events` returns when no limit is given.
+pub const DEFAULT_EVENT_LIMIT: usize = 20;
+
+/// Journal entries `lockchain daemon events` shows when neither a limit nor
+/// a start time is given.
+pub const DEFAULT_JOURNAL_LIMIT: usize = 50;
+
+/// Resolve the control socket path, honouring `LOCKCHAIN_CONTROL_SOCKET`.
+pub fn socket_path() -> PathBuf {
+    match env::var(SOCKET_ENV) {
+        Ok(path) if !path.is_empty() => PathBuf::from(path),
+        _ => PathBuf::from(DEFAULT_SOCKET_PATH),
+    }
+}
+
+/// Methods the daemon answers; `None` datasets mean the first in `policy.datasets`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum ControlCall {
+    /// Keystatus for one dataset, or every configured dataset.
+    Status { dataset: Option<String> },
+    Unlock {
+        dataset: Option<String>,
+        #[serde(default)]
+        strict_usb: bool,
+    },
+    Lock {
+        dataset: Option<String>,
+        #[serde(default)]
+        unmount: bool,
+    },
+    /// Re-read the daemon's configuration file.
+    Reload,
+    /// Restart the `[daemon.idle_lock]` clock, and let the daemon unlock
+    /// datasets again if it already idle-locked them.
+    KeepAlive,
+    /// The newest audit records, oldest first, for one dataset (and its
+    /// descendants) or all of them.
+    RecentEvents {
+        dataset: Option<String>,
+        #[serde(default = "default_event_limit")]
+        limit: usize,
+    },
+    /// Entries from the daemon's own event journal, oldest first; every
+    /// matching entry the journal still holds when `limit` is `None`.
+    Events {
+        /// RFC 3339 time of the oldest entry wanted.
+        #[serde(default)]
+        since: Option<String>,
+        #[serde(default)]
+        dataset: Option<String>,
+        #[serde(default)]
+        kind: Option<DaemonEventKind>,
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+}
+
+fn default_event_limit() -> usize {
+    DEFAULT_EVENT_LIMIT
+}
+
+impl ControlCall {
+    /// The JSON-RPC method name.
+    pub fn method(&self) -> &'static str {
+        match self {
+            ControlCall::Status { .. } => "status",
+            ControlCall::Unlock { .. } => "unlock",
+            ControlCall::Lock { .. } => "lock",
+            ControlCall::Reload => "reload",
+            ControlCall::KeepAlive => "keep_alive",
+            ControlCall::RecentEvents { .. } => "recent_events",
+            ControlCall::Events { .. } => "events",
+        }
+    }
+
+    /// Whether the method only reports state.
+    pub fn read_only(&self) -> bool {
+        matches!(
+            self,
+            ControlCall::Status { .. }
+                | ControlCall::RecentEvents { .. }
+                | ControlCall::Events { .. }
+        )
+    }
+}
+
+/// What a connected peer may call, decided from its socket credentials.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlAccess {
+    /// `status`, `recent_events`, and `events` only.
+    ReadOnly,
+    Full,
+}
+
+impl ControlAccess {
+    /// Access for a peer running as `uid`: root, the daemon's own user, and
+    /// `[control] operate_uids` get everything, `read_uids` the read-only
+    /// methods, members of `group` what `group_operate` allows, and anyone
+    /// else nothing. The most generous match wins. `in_group` is only asked
+    /// when a group is configured.
+    pub fn grant(
+        cfg: &ControlCfg,
+        uid: u32,
+        daemon_uid: u32,
+        in_group: impl FnOnce(&str) -> bool,
+    ) -> Option<Self> {
+        if uid == 0 || uid == daemon_uid || cfg.operate_uids.contains(&uid) {
+            return Some(ControlAccess::Full);
+        }
+        let by_group = cfg
+            .group
+            .as_deref()
+            .filter(|group| in_group(group))
+            .map(|_| {
+                if cfg.group_operate {
+                    ControlAccess::Full
+                } else {
+                    ControlAccess::ReadOnly
+                }
+            });
+        by_group.or_else(|| {
+            cfg.read_uids
+                .contains(&uid)
+                .then_some(ControlAccess::ReadOnly)
+        })
+    }
+
+    /// Whether `call` is allowed at this level.
+    pub fn permits(self, call: &ControlCall) -> bool {
+        self == ControlAccess::Full || call.read_only()
+    }
+}
+
+/// A request line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlRequest {
+    pub jsonrpc: String,
+    pub id: u64,
+    #[serde(flatten)]
+    pub call: ControlCall,
+}
+
+/// Failure reported by the daemon.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlError {
+    pub code: i64,
+    pub message: String,
+    /// LCxxxx code of the underlying error, when there is one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+}
+
+/// A response line; exactly one of `result` and `error` is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlResponse {
+    pub jsonrpc: String,
+    /// `None` when the request could not be parsed far enough to read its id.
+    pub id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ControlError>,
+}
+
+impl ControlResponse {
+    /// Answer `id` with the outcome of a method call.
+    pub fn from_result(id: u64, outcome: LockchainResult<Value>) -> Self {
+        match outcome {
+            Ok(result) => Self {
+                jsonrpc: "2.0".into(),
+                id: Some(id),
+                result: Some(result),
+                error: None,
+            },
+            Err(err) => Self::failure(
+                Some(id),
+                ControlError {
+                    code: CALL_FAILED,
+                    message: err.to_string(),
+                    data: Some(err.code().to_string()),
+                },
+            ),
+        }
+    }
+
+    /// Error response, e.g. for a malformed request line.
+    pub fn failure(id: Option<u64>, error: ControlError) -> Self {
+        Self {
+            jsonrpc: "2.0".into(),
+            id,
+            result: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// Outcome of a `reload` call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReloadReport {
+    pub config_path: PathBuf,
+    /// Settings that differ from the previous config (secrets redacted).
+    pub changes: Vec<String>,
+}
+
+/// Outcome of a `keep_alive` call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeepAliveReport {
+    /// RFC 3339 time datasets are idle-locked unless another keep-alive
+    /// arrives; `None` when `[daemon.idle_lock]` is disabled.
+    pub lock_at: Option<String>,
+    /// Whether datasets were idle-locked, so the daemon resumes unlocking them.
+    pub resumed: bool,
+}
+
+/// What a daemon journal entry is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DaemonEventKind {
+    Unlock,
+    Lock,
+    Usb,
+    Health,
+    Reload,
+}
+
+impl DaemonEventKind {
+    /// Stable identifier used in the JSON payload and CLI filters.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DaemonEventKind::Unlock => "unlock",
+            DaemonEventKind::Lock => "lock",
+            DaemonEventKind::Usb => "usb",
+            DaemonEventKind::Health => "health",
+            DaemonEventKind::Reload => "reload",
+        }
+    }
+}
+
+impl FromStr for DaemonEventKind {
+    type Err = LockchainError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        [
+            DaemonEventKind::Unlock,
+            DaemonEventKind::Lock,
+            DaemonEventKind::Usb,
+            DaemonEventKind::Health,
+            DaemonEventKind::Reload,
+        ]
+        .into_iter()
+        .find(|kind| kind.as_str() == value)
+        .ok_or_else(|| {
+            LockchainError::InvalidConfig(format!(
+                "unknown daemon event `{value}` (expected unlock, lock, usb, health, or reload)"
+            ))
+        })
+    }
+}
+
+/// One entry in the daemon's event journal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaemonEvent {
+    pub timestamp: String,
+    pub kind: DaemonEventKind,
+    /// Dataset the event concerns; `None` for USB, health, and reload events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dataset: Option<String>,
+    pub message: String,
+}
+
+impl DaemonEvent {
+    /// A `kind` event happening now.
+    pub fn now(kind: DaemonEventKind, dataset: Option<&str>, message: impl Into<String>) -> Self {
+        Self {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            kind,
+            dataset: dataset.map(str::to_string),
+            message: message.into(),
+        }
+    }
+
+    /// When the event happened, if its timestamp parses.
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.timestamp)
+            .ok()
+            .map(|time| time.with_timezone(&Utc))
+    }
+}
+
+/// Which journal entries an `events` call wants.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    /// Dataset or any of its descendants; events without a dataset never match.
+    pub dataset: Option<String>,
+    pub kind: Option<DaemonEventKind>,
+    /// Entries at or after this instant.
+    pub since: Option<DateTime<Utc>>,
+}
+
+impl EventFilter {
+    /// Filter for the parameters of an `events` call.
+    pub fn parse(
+        since: Option<&str>,
+        dataset: Option<String>,
+        kind: Option<DaemonEventKind>,
+    ) -> LockchainResult<Self> {
+        let since = since
+            .map(|since| {
+                DateTime::parse_from_rfc3339(since)
+                    .map(|time| time.with_timezone(&Utc))
+                    .map_err(|err| {
+                        LockchainError::Control(format!("invalid `since` time `{since}`: {err}"))
+                    })
+            })
+            .transpose()?;
+        Ok(Self {
+            dataset,
+            kind,
+            since,
+        })
+    }
+
+    /// Whether `event` passes every criterion.
+    pub fn matches(&self, event: &DaemonEvent) -> bool {
+        let dataset_ok = self.dataset.as_deref().is_none_or(|wanted| {
+            event.dataset.as_deref().is_some_and(|dataset| {
+                dataset == wanted
+                    || dataset
+                        .strip_prefix(wanted)
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+        });
+        let kind_ok = self.kind.is_none_or(|kind| event.kind == kind);
+        let since_ok = self
+            .since
+            .is_none_or(|since| event.time().is_some_and(|time| time >= since));
+        dataset_ok && kind_ok && since_ok
+    }
+}
+
+/// Blocking client for the daemon's control socket.
+pub struct ControlClient {
+    path: PathBuf,
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    next_id: u64,
+}
+
+impl ControlClient {
+    /// Connect to the daemon listening on `path`.
+    pub fn connect(path: &Path) -> LockchainResult<Self> {
+        let stream = UnixStream::connect(path).map_err(|err| {
+            LockchainError::Control(format!(
+                "cannot reach lockchain-daemon at {}: {err}",
+                path.display()
+            ))
+        })?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            next_id: 1,
+        })
+    }
+
+    /// Send `call` and decode its result.
+    pub fn call<T: DeserializeOwned>(&mut self, call: ControlCall) -> LockchainResult<T> {
+        let request = ControlRequest {
+            jsonrpc: "2.0".into(),
+            id: self.next_id,
+            call,
+        };
+        self.next_id += 1;
+        let mut line = serde_json::to_string(&request)
+            .map_err(|err| LockchainError::Control(format!("encode request: {err}")))?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes())?;
+
+        let mut reply = String::new();
+        if self.reader.read_line(&mut reply)? == 0 {
+            return Err(LockchainError::Control(format!(
+                "{} closed the connection without answering",
+                self.path.display()
+            )));
+        }
+        let response: ControlResponse = serde_json::from_str(&reply)
+            .map_err(|err| LockchainError::Control(format!("malformed response: {err}")))?;
+        if let Some(error) = response.error {
+            return Err(LockchainError::Control(error.message));
+        }
+        serde_json::from_value(response.result.unwrap_or(Value::Null))
+            .map_err(|err| LockchainError::Control(format!("unexpected result: {err}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+
+    #[test]
+    fn client_round_trips_requests_over_the_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            for _ in 0..2 {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let request: ControlRequest = serde_json::from_str(&line).unwrap();
+                let outcome = match request.call {
+                    ControlCall::Status { dataset } => Ok(serde_json::json!([dataset])),
+                    _ => Err(LockchainError::DatasetNotConfigured("tank/x".into())),
+                };
+                let response = ControlResponse::from_result(request.id, outcome);
+                let mut reply = serde_json::to_string(&response).unwrap();
+                reply.push('\n');
+                writer.write_all(reply.as_bytes()).unwrap();
+            }
+        });
+
+        let mut client = ControlClient::connect(&path).unwrap();
+        let status: Vec<String> = client
+            .call(ControlCall::Status {
+                dataset: Some("tank/secure".into()),
+            })
+            .unwrap();
+        assert_eq!(status, vec!["tank/secure"]);
+
+        let err = client.call::<Value>(ControlCall::Reload).unwrap_err();
+        assert!(err.to_string().contains("[LC1200]"));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn recent_events_defaults_its_limit() {
+        let parsed: ControlRequest = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","id":3,"method":"recent_events","params":{"dataset":"tank"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.call,
+            ControlCall::RecentEvents {
+                dataset: Some("tank".into()),
+                limit: DEFAULT_EVENT_LIMIT
+            }
+        );
+        assert_eq!(parsed.call.method(), "recent_events");
+    }
+
+    #[test]
+    fn access_follows_peer_credentials() {
+        let unlock = ControlCall::Unlock {
+            dataset: None,
+            strict_usb: false,
+        };
+        let status = ControlCall::Status { dataset: None };
+        let owner_only = ControlCfg::default();
+        let grant = |cfg: &ControlCfg, uid: u32, member: bool| {
+            ControlAccess::grant(cfg, uid, 990, |group| {
+                assert_eq!(group, "lockchain");
+                member
+            })
+        };
+
+        assert_eq!(grant(&owner_only, 0, false), Some(ControlAccess::Full));
+        assert_eq!(grant(&owner_only, 990, false), Some(ControlAccess::Full));
+        assert_eq!(grant(&owner_only, 1000, true), None);
+
+        let mut cfg = ControlCfg {
+            group: Some("lockchain".into()),
+            ..ControlCfg::default()
+        };
+        assert_eq!(grant(&cfg, 1000, false), None);
+        let member = grant(&cfg, 1000, true).unwrap();
+        assert!(member.permits(&status));
+        assert!(!member.permits(&unlock));
+        assert!(!member.permits(&ControlCall::Reload));
+        assert!(!member.permits(&ControlCall::KeepAlive));
+
+        cfg.group_operate = true;
+        assert!(grant(&cfg, 1000, true).unwrap().permits(&unlock));
+    }
+
+    #[test]
+    fn access_honours_uid_allow_lists() {
+        let unlock = ControlCall::Unlock {
+            dataset: None,
+            strict_usb: false,
+        };
+        let cfg = ControlCfg {
+            read_uids: vec![1000],
+            operate_uids: vec![1001],
+            ..ControlCfg::default()
+        };
+        let grant = |uid| ControlAccess::grant(&cfg, uid, 990, |_| unreachable!());
+
+        let reader = grant(1000).unwrap();
+        assert!(reader.permits(&ControlCall::Status { dataset: None }));
+        assert!(!reader.permits(&unlock));
+        assert!(grant(1001).unwrap().permits(&unlock));
+        assert_eq!(grant(1002), None);
+
+        let with_group = ControlCfg {
+            group: Some("lockchain".into()),
+            group_operate: true,
+            ..cfg.clone()
+        };
+        let member = ControlAccess::grant(&with_group, 1000, 990, |_| true);
+        assert_eq!(member, Some(ControlAccess::Full));
+    }
+
+    #[test]
+    fn unit_methods_omit_params() {
+        let request = ControlRequest {
+            jsonrpc: "2.0".into(),
+            id: 7,
+            call: ControlCall::Reload,
+        };
+        let line = serde_json::to_string(&request).unwrap();
+        assert_eq!(line, r#"{"jsonrpc":"2.0","id":7,"method":"reload"}"#);
+        let parsed: ControlRequest = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","id":8,"method":"lock","params":{"dataset":null}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.call,
+            ControlCall::Lock {
+                dataset: None,
+                unmount: false
+            }
+        );
+    }
+
+    #[test]
+    fn event_filter_matches_kind_dataset_and_time() {
+        let parsed: ControlRequest = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","id":4,"method":"events","params":{"since":"2024-05-01T12:00:00Z","dataset":"tank","kind":"unlock"}}"#,
+        )
+        .unwrap();
+        assert!(parsed.call.read_only());
+        let ControlCall::Events {
+            since,
+            dataset,
+            kind,
+            limit: None,
+        } = parsed.call
+        else {
+            panic!("unexpected call {:?}", parsed.call);
+        };
+        let filter = EventFilter::parse(since.as_deref(), dataset, kind).unwrap();
+
+        let event = |timestamp: &str, kind, dataset: Option<&str>| DaemonEvent {
+            timestamp: timestamp.into(),
+            kind,
+            dataset: dataset.map(str::to_string),
+            message: String::new(),
+        };
+        let unlock = DaemonEventKind::Unlock;
+        assert!(filter.matches(&event("2024-05-01T12:30:00Z", unlock, Some("tank/secure"))));
+        assert!(!filter.matches(&event("2024-05-01T11:59:59Z", unlock, Some("tank"))));
+        assert!(!filter.matches(&event("2024-05-01T12:30:00Z", unlock, Some("tankard"))));
+        assert!(!filter.matches(&event("2024-05-01T12:30:00Z", unlock, None)));
+        assert!(!filter.matches(&event(
+            "2024-05-01T12:30:00Z",
+            DaemonEventKind::Lock,
+            Some("tank")
+        )));
+
+        assert!(EventFilter::parse(Some("an hour ago"), None, None).is_err());
+        assert_eq!(
+            "health".parse::<DaemonEventKind>().unwrap(),
+            DaemonEventKind::Health
+        );
+    }
+}
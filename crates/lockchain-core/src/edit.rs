@@ -0,0 +1,285 @@
+//! Single-setting reads and edits behind `lockchain config get/set`, so scripts
+//! and configuration management never have to parse the file themselves.
+
+use crate::config::{self, ConfigFormat, LockchainConfig};
+use crate::error::{LockchainError, LockchainResult};
+use serde_json::Value;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use tempfile::NamedTempFile;
+use toml_edit::{DocumentMut, TableLike};
+
+impl LockchainConfig {
+    /// Effective value of the dotted `field`, exactly as `config diff` reports it.
+    ///
+    /// Secrets stay redacted and a known but unset optional key yields `null`.
+    pub fn setting(&self, field: &str) -> LockchainResult<Value> {
+        if let Some(setting) = self
+            .effective_settings()?
+            .into_iter()
+            .find(|setting| setting.field == field)
+        {
+            return Ok(setting.value);
+        }
+        let resolved = serde_json::to_value(self)
+            .map_err(|err| LockchainError::InvalidConfig(err.to_string()))?;
+        match resolve(&resolved, field)? {
+            Value::Object(_) => Err(LockchainError::InvalidConfig(format!(
+                "`{field}` is a table; name one of its keys, e.g. `{field}.{}`",
+                first_key(&resolved, field)
+            ))),
+            other => Ok(other.clone()),
+        }
+    }
+
+    /// Write `raw` to the dotted `field` of the base file and return the reloaded config.
+    ///
+    /// `raw` is read as a TOML literal (`3`, `true`, `["tank/a"]`, `"quoted"`)
+    /// and otherwise taken as a plain string. The edited file is loaded and
+    /// validated before it atomically replaces the original; unknown keys,
+    /// type mismatches, and new validation errors leave the file untouched.
+    /// Comments and layout in TOML files are preserved; YAML files are
+    /// re-serialised. Drop-ins and profiles are not edited.
+    pub fn set_setting(&self, field: &str, raw: &str) -> LockchainResult<LockchainConfig> {
+        if let Some(remote) = &self.remote {
+            return Err(LockchainError::InvalidConfig(format!(
+                "{} points at the published config {}; change it there or add a drop-in",
+                self.path.display(),
+                remote.url
+            )));
+        }
+        let segments: Vec<&str> = field.split('.').collect();
+        if segments.iter().any(|segment| segment.is_empty()) {
+            return Err(LockchainError::InvalidConfig(format!(
+                "`{field}` is not a dotted key such as usb.device_label"
+            )));
+        }
+
+        let contents = fs::read_to_string(&self.path)?;
+        let edited = match self.format {
+            ConfigFormat::Toml => set_toml(&contents, &segments, raw)?,
+            ConfigFormat::Yaml => set_yaml(&contents, &segments, raw)?,
+        };
+
+        let staged = stage(&self.path, &edited)?;
+        let profile = self.profile.as_ref().map(|active| active.name());
+        let candidate = LockchainConfig::load_profile(staged.path(), profile)
+            .map_err(|err| LockchainError::InvalidConfig(format!("{field} = {raw}: {err}")))?;
+        if let Some(unknown) = candidate.load_issues.iter().find(|issue| {
+            issue.code == "LC1170"
+                && (issue.field == field || field.starts_with(&format!("{}.", issue.field)))
+        }) {
+            let mut message = unknown.message.clone();
+            if let Some(suggestion) = &unknown.suggestion {
+                message.push_str(&format!("; {suggestion}"));
+            }
+            return Err(LockchainError::InvalidConfig(message));
+        }
+        let before = self.validate();
+        if let Some(issue) = candidate.validate().into_iter().find(|issue| {
+            issue.is_error()
+                && !before
+                    .iter()
+                    .any(|old| old.code == issue.code && old.field == issue.field)
+        }) {
+            return Err(LockchainError::InvalidConfig(format!(
+                "refusing to set {field}: {issue}"
+            )));
+        }
+
+        staged
+            .persist(&self.path)
+            .map_err(|err| LockchainError::Io(err.error))?;
+        LockchainConfig::load_profile(&self.path, profile)
+    }
+}
+
+/// Replace `path` with `contents` through a sibling temp file and a rename,
+/// so readers never see a half-written config.
+pub(crate) fn write_atomic(path: &Path, contents: &str) -> LockchainResult<()> {
+    stage(path, contents)?
+        .persist(path)
+        .map_err(|err| LockchainError::Io(err.error))?;
+    Ok(())
+}
+
+/// Write `contents` next to `path`, keeping its extension (format detection
+/// relies on it) and its permissions.
+fn stage(path: &Path, contents: &str) -> LockchainResult<NamedTempFile> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let suffix = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let mut staged = tempfile::Builder::new()
+        .prefix(".lockchain-")
+        .suffix(&suffix)
+        .tempfile_in(dir)?;
+    staged.write_all(contents.as_bytes())?;
+    staged.as_file().sync_all()?;
+    if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(staged.path(), metadata.permissions())?;
+    }
+    Ok(staged)
+}
+
+/// Find `field` in the resolved config, suggesting the nearest key on a miss.
+fn resolve<'a>(resolved: &'a Value, field: &str) -> LockchainResult<&'a Value> {
+    let mut node = resolved;
+    let mut walked = Vec::new();
+    for segment in field.split('.') {
+        let Some(next) = node.get(segment) else {
+            let mut message = format!("unknown setting `{field}`");
+            let candidates: Vec<String> = node
+                .as_object()
+                .map(|map| map.keys().cloned().collect())
+                .unwrap_or_default();
+            if let Some(nearest) = config::nearest_key(segment, &candidates) {
+                walked.push(nearest);
+                message.push_str(&format!("; did you mean `{}`?", walked.join(".")));
+            }
+            return Err(LockchainError::InvalidConfig(message));
+        };
+        walked.push(segment.to_string());
+        node = next;
+    }
+    Ok(node)
+}
+
+fn first_key(resolved: &Value, field: &str) -> String {
+    resolve(resolved, field)
+        .ok()
+        .and_then(Value::as_object)
+        .and_then(|map| map.keys().next().cloned())
+        .unwrap_or_else(|| "…".into())
+}
+
+/// Deprecated spelling of `segments`, which must go when the new key is set.
+fn deprecated_alias(segments: &[&str]) -> Option<&'static str> {
+    let [section, key] = segments else {
+        return None;
+    };
+    config::DEPRECATED_KEYS
+        .iter()
+        .find(|(deprecated_section, _, new)| deprecated_section == section && new == key)
+        .map(|(_, old, _)| *old)
+}
+
+fn set_toml(contents: &str, segments: &[&str], raw: &str) -> LockchainResult<String> {
+    let mut doc: DocumentMut = contents
+        .parse()
+        .map_err(|err| LockchainError::InvalidConfig(format!("cannot edit config: {err}")))?;
+    let (last, parent) = segments.split_last().expect("split('.') yields a segment");
+    let mut table: &mut dyn TableLike = doc.as_table_mut();
+    for (depth, segment) in parent.iter().enumerate() {
+        table = table
+            .entry(segment)
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .ok_or_else(|| {
+                LockchainError::InvalidConfig(format!(
+                    "`{}` is a value, not a table",
+                    segments[..=depth].join(".")
+                ))
+            })?;
+    }
+    if let Some(old) = deprecated_alias(segments) {
+        table.remove(old);
+    }
+    let mut value = raw
+        .parse::<toml_edit::Value>()
+        .unwrap_or_else(|_| raw.into());
+    match table.get_mut(last) {
+        Some(existing) => {
+            if let Some(old) = existing.as_value() {
+                *value.decor_mut() = old.decor().clone();
+            }
+            *existing = toml_edit::Item::Value(value);
+        }
+        None => {
+            table.insert(last, toml_edit::Item::Value(value));
+        }
+    }
+    Ok(doc.to_string())
+}
+
+fn set_yaml(contents: &str, segments: &[&str], raw: &str) -> LockchainResult<String> {
+    let mut doc: serde_yaml::Value = serde_yaml::from_str(contents)?;
+    let (last, parent) = segments.split_last().expect("split('.') yields a segment");
+    let mut node = &mut doc;
+    for (depth, segment) in parent.iter().enumerate() {
+        node = node
+            .as_mapping_mut()
+            .ok_or_else(|| {
+                LockchainError::InvalidConfig(format!(
+                    "`{}` is a value, not a table",
+                    segments[..depth].join(".")
+                ))
+            })?
+            .entry((*segment).into())
+            .or_insert_with(|| serde_yaml::Mapping::new().into());
+    }
+    let table = node.as_mapping_mut().ok_or_else(|| {
+        LockchainError::InvalidConfig(format!("`{}` is a value, not a table", parent.join(".")))
+    })?;
+    if let Some(old) = deprecated_alias(segments) {
+        table.remove(old);
+    }
+    let value = toml::from_str::<toml::Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut parsed| parsed.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.into()));
+    table.insert((*last).into(), serde_yaml::to_value(value)?);
+    Ok(serde_yaml::to_string(&doc)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_setting_edits_in_place_and_refuses_bad_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lockchain-zfs.toml");
+        fs::write(
+            &path,
+            "# managed by hand\n[policy]\ndatasets = [\"tank/secure\"]\n\n[usb]\n# label on the stick\ndevice_label = \"OLD\"\nmount_timeout_secs = 5\n",
+        )
+        .unwrap();
+        let config = LockchainConfig::load(&path).unwrap();
+
+        let updated = config
+            .set_setting("usb.device_label", "LOCKCHAINKEY")
+            .unwrap();
+        assert_eq!(updated.usb.device_label.as_deref(), Some("LOCKCHAINKEY"));
+        let updated = updated.set_setting("usb.mount_timeout", "\"30s\"").unwrap();
+        assert_eq!(
+            updated.setting("usb.mount_timeout").unwrap(),
+            serde_json::json!("30s")
+        );
+        let updated = updated.set_setting("retry.max_attempts", "4").unwrap();
+        assert_eq!(updated.retry.max_attempts, 4);
+
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(written.contains("# managed by hand"));
+        assert!(written.contains("# label on the stick\ndevice_label = \"LOCKCHAINKEY\""));
+        assert!(!written.contains("mount_timeout_secs"));
+
+        let typo = updated.set_setting("usb.device_lable", "X").unwrap_err();
+        assert!(typo.to_string().contains("usb.device_label"));
+        assert!(updated.set_setting("retry.max_attempts", "many").is_err());
+        assert!(updated.set_setting("policy.datasets", "[]").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), written);
+
+        assert_eq!(
+            updated.setting("fallback.passphrase_xor").unwrap(),
+            Value::Null
+        );
+        assert!(updated.setting("usb").is_err());
+        assert!(updated.setting("usb.labl").is_err());
+    }
+}
@@ -30,12 +30,24 @@ pub enum LockchainError {
     #[error("[LC1201] no key source configured for dataset `{0}`")]
     MissingKeySource(String),
 
+    #[error("[LC1202] unlock refused by policy: {0}")]
+    PolicyViolation(String),
+
     #[error("[LC1300] failed to decode hex key at {path}: {reason}")]
     InvalidHexKey { path: PathBuf, reason: String },
 
     #[error("[LC2000] provider error: {0}")]
     Provider(String),
 
+    #[error("[LC2100] hook failed: {0}")]
+    Hook(String),
+
+    #[error("[LC2200] key service error: {0}")]
+    KeyService(String),
+
+    #[error("[LC2300] daemon control error: {0}")]
+    Control(String),
+
     #[error("[LC3000] unlock retries exhausted after {attempts} attempts: {last_error}")]
     RetryExhausted { attempts: u32, last_error: String },
 }
@@ -51,9 +63,36 @@ impl LockchainError {
             LockchainError::InvalidConfig(_) => "LC1100",
             LockchainError::DatasetNotConfigured(_) => "LC1200",
             LockchainError::MissingKeySource(_) => "LC1201",
+            LockchainError::PolicyViolation(_) => "LC1202",
             LockchainError::InvalidHexKey { .. } => "LC1300",
             LockchainError::Provider(_) => "LC2000",
+            LockchainError::Hook(_) => "LC2100",
+            LockchainError::KeyService(_) => "LC2200",
+            LockchainError::Control(_) => "LC2300",
             LockchainError::RetryExhausted { .. } => "LC3000",
         }
     }
+    /// Process exit status for this error family, so scripts can branch on it.
+    ///
+    /// Configuration problems map to 2, missing datasets to 3, missing key
+    /// sources to 4, provider failures to 5, and exhausted retries to 6;
+    /// everything else exits 1.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            LockchainError::Toml(_)
+            | LockchainError::Yaml(_)
+            | LockchainError::TomlSer(_)
+            | LockchainError::InvalidConfig(_) => 2,
+            LockchainError::DatasetNotConfigured(_) => 3,
+            LockchainError::MissingKeySource(_) => 4,
+            LockchainError::Provider(_) => 5,
+            LockchainError::RetryExhausted { .. } => 6,
+            LockchainError::Io(_)
+            | LockchainError::PolicyViolation(_)
+            | LockchainError::InvalidHexKey { .. }
+            | LockchainError::Hook(_)
+            | LockchainError::KeyService(_)
+            | LockchainError::Control(_) => 1,
+        }
+    }
 }
@@ -0,0 +1,240 @@
+//! Pre/post unlock and lock hooks: in-process callbacks plus config-driven commands.
+
+use crate::config::HooksCfg;
+use crate::error::{LockchainError, LockchainResult};
+use log::{debug, info};
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Points in the service lifecycle where hooks fire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookEvent {
+    PreUnlock,
+    PostUnlock,
+    PreLock,
+    PostLock,
+}
+
+impl HookEvent {
+    /// Stable identifier matching the `[hooks]` config keys.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HookEvent::PreUnlock => "pre_unlock",
+            HookEvent::PostUnlock => "post_unlock",
+            HookEvent::PreLock => "pre_lock",
+            HookEvent::PostLock => "post_lock",
+        }
+    }
+}
+
+/// Dataset context handed to every hook invocation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookContext {
+    pub event: HookEvent,
+    pub dataset: String,
+    pub encryption_root: String,
+    /// Datasets whose key state changed (empty for `pre_*` events).
+    pub datasets: Vec<String>,
+}
+
+/// Callback signature accepted by [`HookRegistry::register`].
+pub type HookFn = Box<dyn Fn(&HookContext) -> LockchainResult<()> + Send + Sync>;
+
+/// Ordered set of hooks per event; callbacks run before configured commands.
+pub struct HookRegistry {
+    callbacks: Vec<(HookEvent, HookFn)>,
+    commands: HooksCfg,
+}
+
+impl HookRegistry {
+    /// Build a registry that runs the commands declared under `[hooks]`.
+    pub fn from_config(commands: &HooksCfg) -> Self {
+        Self {
+            callbacks: Vec::new(),
+            commands: commands.clone(),
+        }
+    }
+
+    /// Register an in-process callback for `event`.
+    pub fn register<F>(&mut self, event: HookEvent, hook: F)
+    where
+        F: Fn(&HookContext) -> LockchainResult<()> + Send + Sync + 'static,
+    {
+        self.callbacks.push((event, Box::new(hook)));
+    }
+
+    /// Run every hook for `ctx.event`, stopping at the first failure.
+    pub fn run(&self, ctx: &HookContext) -> LockchainResult<()> {
+        for (_, hook) in self.callbacks.iter().filter(|(ev, _)| *ev == ctx.event) {
+            hook(ctx)?;
+        }
+        let timeout = self.commands.timeout.max(Duration::from_secs(1));
+        for command in self.commands_for(ctx.event) {
+            run_command(command, ctx, timeout)?;
+        }
+        Ok(())
+    }
+
+    fn commands_for(&self, event: HookEvent) -> &[String] {
+        match event {
+            HookEvent::PreUnlock => &self.commands.pre_unlock,
+            HookEvent::PostUnlock => &self.commands.post_unlock,
+            HookEvent::PreLock => &self.commands.pre_lock,
+            HookEvent::PostLock => &self.commands.post_lock,
+        }
+    }
+}
+
+impl Default for HookRegistry {
+    fn default() -> Self {
+        Self::from_config(&HooksCfg::default())
+    }
+}
+
+/// Execute a single hook command with dataset context, killing it on timeout.
+fn run_command(command: &str, ctx: &HookContext, timeout: Duration) -> LockchainResult<()> {
+    let mut parts = command.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| LockchainError::Hook(format!("{} hook is empty", ctx.event.as_str())))?;
+
+    let mut child = Command::new(program)
+        .args(parts)
+        .env("LOCKCHAIN_HOOK_EVENT", ctx.event.as_str())
+        .env("LOCKCHAIN_DATASET", &ctx.dataset)
+        .env("LOCKCHAIN_ENCRYPTION_ROOT", &ctx.encryption_root)
+        .env("LOCKCHAIN_DATASETS", ctx.datasets.join(" "))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| LockchainError::Hook(format!("{command}: failed to start ({err})")))?;
+
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if start.elapsed() > timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(LockchainError::Hook(format!(
+                "{command}: timed out after {timeout:?}"
+            )));
+        }
+        thread::sleep(Duration::from_millis(25));
+    };
+
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if !stdout.trim().is_empty() {
+        debug!(
+            "{} hook {command} stdout: {}",
+            ctx.event.as_str(),
+            stdout.trim()
+        );
+    }
+    if !status.success() {
+        let detail = if stderr.trim().is_empty() {
+            "no additional output"
+        } else {
+            stderr.trim()
+        };
+        return Err(LockchainError::Hook(format!(
+            "{command}: exited with {status} ({detail})"
+        )));
+    }
+    info!(
+        "{} hook {command} completed for {}",
+        ctx.event.as_str(),
+        ctx.dataset
+    );
+    Ok(())
+}
+
+/// Read a child pipe to completion on a helper thread.
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut reader) = pipe {
+            let _ = reader.read_to_end(&mut buf);
+        }
+        String::from_utf8_lossy(&buf).into_owned()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::{Arc, Mutex};
+    use tempfile::tempdir;
+
+    fn context(event: HookEvent) -> HookContext {
+        HookContext {
+            event,
+            dataset: "tank/secure".into(),
+            encryption_root: "tank/secure".into(),
+            datasets: vec!["tank/secure".into(), "tank/secure/home".into()],
+        }
+    }
+
+    #[test]
+    fn callbacks_only_fire_for_their_event() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut registry = HookRegistry::default();
+        let sink = seen.clone();
+        registry.register(HookEvent::PostUnlock, move |ctx| {
+            sink.lock().unwrap().push(ctx.dataset.clone());
+            Ok(())
+        });
+
+        registry.run(&context(HookEvent::PreUnlock)).unwrap();
+        registry.run(&context(HookEvent::PostUnlock)).unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec!["tank/secure".to_string()]);
+    }
+
+    #[test]
+    fn commands_receive_dataset_context() {
+        let dir = tempdir().unwrap();
+        let out = dir.path().join("env.txt");
+        let script = dir.path().join("hook.sh");
+        fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\necho \"$LOCKCHAIN_HOOK_EVENT $LOCKCHAIN_DATASET $LOCKCHAIN_DATASETS\" > {}\n",
+                out.display()
+            ),
+        )
+        .unwrap();
+        let cfg = HooksCfg {
+            post_unlock: vec![format!("/bin/sh {}", script.display())],
+            ..HooksCfg::default()
+        };
+
+        HookRegistry::from_config(&cfg)
+            .run(&context(HookEvent::PostUnlock))
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(&out).unwrap().trim(),
+            "post_unlock tank/secure tank/secure tank/secure/home"
+        );
+    }
+
+    #[test]
+    fn slow_commands_time_out() {
+        let cfg = HooksCfg {
+            pre_lock: vec!["/bin/sleep 5".into()],
+            timeout: Duration::from_secs(1),
+            ..HooksCfg::default()
+        };
+        let err = HookRegistry::from_config(&cfg)
+            .run(&context(HookEvent::PreLock))
+            .unwrap_err();
+        assert!(matches!(err, LockchainError::Hook(msg) if msg.contains("timed out")));
+    }
+}
@@ -0,0 +1,179 @@
+//! Fallback passphrase derivation (PBKDF2-HMAC-SHA256) and calibration of its
+//! iteration count against a target unlock time on this machine.
+
+use crate::config::{Fallback, LockchainConfig, MIN_PASSPHRASE_ITERS};
+use crate::error::{LockchainError, LockchainResult};
+use crate::keyfile::read_key_file;
+use crate::secret::SecretBuffer;
+use crate::units;
+use hex::FromHex;
+use pbkdf2::pbkdf2_hmac;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+
+/// Calibration runs until a single derivation takes at least this long.
+const CALIBRATION_FLOOR: Duration = Duration::from_millis(200);
+
+/// Recommendations are rounded up to a multiple of this.
+const ITERATION_STEP: u64 = 10_000;
+
+impl Fallback {
+    /// Derive the fallback key using the configured PBKDF2 parameters and mask.
+    pub fn derive_key(&self, passphrase: &[u8]) -> LockchainResult<SecretBuffer> {
+        let salt_hex = self.passphrase_salt.as_ref().ok_or_else(|| {
+            LockchainError::InvalidConfig("fallback.passphrase_salt missing".into())
+        })?;
+        let xor_hex = self.passphrase_xor.as_ref().ok_or_else(|| {
+            LockchainError::InvalidConfig("fallback.passphrase_xor missing".into())
+        })?;
+
+        let salt = Vec::from_hex(salt_hex).map_err(|err| {
+            LockchainError::InvalidConfig(format!("invalid fallback.passphrase_salt: {}", err))
+        })?;
+        let cipher = Vec::from_hex(xor_hex).map_err(|err| {
+            LockchainError::InvalidConfig(format!("invalid fallback.passphrase_xor: {}", err))
+        })?;
+
+        if cipher.len() != 32 {
+            return Err(LockchainError::InvalidConfig(format!(
+                "fallback.passphrase_xor length must be 32 bytes, got {}",
+                cipher.len()
+            )));
+        }
+
+        let iterations = self.passphrase_iters.max(1);
+        let mut derived = SecretBuffer::zeroed(cipher.len());
+        pbkdf2_hmac::<Sha256>(passphrase, &salt, iterations, &mut derived);
+
+        let mut key = SecretBuffer::zeroed(cipher.len());
+        for ((out, c), d) in key.iter_mut().zip(&cipher).zip(derived.iter()) {
+            *out = c ^ d;
+        }
+
+        Ok(key)
+    }
+
+    /// Mask `key` under `passphrase` with a fresh salt and `iterations`.
+    pub fn remask(&mut self, passphrase: &[u8], key: &[u8], iterations: u32) {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+
+        let mut derived = SecretBuffer::zeroed(key.len());
+        pbkdf2_hmac::<Sha256>(passphrase, &salt, iterations, &mut derived);
+
+        let xor: Vec<u8> = key.iter().zip(derived.iter()).map(|(a, b)| a ^ b).collect();
+        self.passphrase_salt = Some(hex::encode(salt));
+        self.passphrase_xor = Some(hex::encode(xor));
+        self.passphrase_iters = iterations;
+    }
+}
+
+/// PBKDF2 throughput measured on this machine and the iteration count it suggests.
+#[derive(Debug, Clone, Serialize)]
+pub struct KdfBenchmark {
+    pub algorithm: &'static str,
+    pub iterations_per_second: u64,
+    #[serde(serialize_with = "units::millis::serialize")]
+    pub target: Duration,
+    /// Iterations that take about `target`, never below the enforced minimum.
+    pub recommended_iters: u32,
+    pub current_iters: u32,
+    /// Estimated derivation time with `current_iters`.
+    #[serde(serialize_with = "units::millis::serialize")]
+    pub current_time: Duration,
+}
+
+/// Time PBKDF2-HMAC-SHA256 on this machine and recommend iterations for `target`.
+///
+/// Argon2 is not offered: the fallback mask is only ever derived with PBKDF2.
+pub fn benchmark_pbkdf2(target: Duration, current_iters: u32) -> KdfBenchmark {
+    let mut iterations: u32 = 1_000;
+    let mut derived = SecretBuffer::zeroed(32);
+    let elapsed = loop {
+        let started = Instant::now();
+        pbkdf2_hmac::<Sha256>(b"lockchain-benchmark", &[0u8; 16], iterations, &mut derived);
+        let elapsed = started.elapsed();
+        if elapsed >= CALIBRATION_FLOOR || iterations >= u32::MAX / 2 {
+            break elapsed;
+        }
+        iterations *= 2;
+    };
+    let rate = (f64::from(iterations) / elapsed.as_secs_f64()).max(1.0);
+
+    let wanted = (rate * target.as_secs_f64()).ceil() as u64;
+    let rounded = wanted.div_ceil(ITERATION_STEP) * ITERATION_STEP;
+    let recommended = rounded.clamp(u64::from(MIN_PASSPHRASE_ITERS), u64::from(u32::MAX)) as u32;
+
+    KdfBenchmark {
+        algorithm: "pbkdf2-hmac-sha256",
+        iterations_per_second: rate as u64,
+        target,
+        recommended_iters: recommended,
+        current_iters,
+        current_time: Duration::from_secs_f64(f64::from(current_iters) / rate),
+    }
+}
+
+/// Re-derive the fallback mask with `iterations`, keeping the same passphrase.
+///
+/// The passphrase is checked first: the key it yields must match the token
+/// key file or `usb.expected_sha256`, otherwise nothing changes. The caller
+/// saves the config.
+pub fn retune_fallback(
+    config: &mut LockchainConfig,
+    passphrase: &[u8],
+    iterations: u32,
+) -> LockchainResult<()> {
+    if iterations < MIN_PASSPHRASE_ITERS {
+        return Err(LockchainError::InvalidConfig(format!(
+            "fallback.passphrase_iters must be at least {MIN_PASSPHRASE_ITERS} (asked for {iterations})"
+        )));
+    }
+    let key = config.fallback.derive_key(passphrase)?;
+    let matches = if let Some(expected) = config.usb.expected_sha256.as_deref() {
+        hex::encode(Sha256::digest(&key[..])).eq_ignore_ascii_case(expected)
+    } else if let Ok((on_token, _)) = read_key_file(&config.key_hex_path()) {
+        on_token[..] == key[..]
+    } else {
+        return Err(LockchainError::InvalidConfig(
+            "cannot confirm the passphrase: pin usb.expected_sha256 or insert the token".into(),
+        ));
+    };
+    if !matches {
+        return Err(LockchainError::InvalidConfig(
+            "the passphrase does not reproduce the token key; fallback left unchanged".into(),
+        ));
+    }
+    config.fallback.remask(passphrase, &key, iterations);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retune_keeps_the_passphrase_working() {
+        let key = [9u8; 32];
+        let mut config =
+            LockchainConfig::starter("/nonexistent.toml", vec!["tank/secure".to_string()]);
+        config.usb.key_hex_path = "/nonexistent/lockchain.key".into();
+        config.fallback.remask(b"hunter2", &key, 1_000);
+        config.usb.expected_sha256 = Some(hex::encode(Sha256::digest(key)));
+
+        assert!(retune_fallback(&mut config, b"wrong", 120_000).is_err());
+        assert!(retune_fallback(&mut config, b"hunter2", 1_000).is_err());
+        assert_eq!(config.fallback.passphrase_iters, 1_000);
+
+        retune_fallback(&mut config, b"hunter2", 120_000).unwrap();
+        assert_eq!(config.fallback.passphrase_iters, 120_000);
+        assert_eq!(&config.fallback.derive_key(b"hunter2").unwrap()[..], &key);
+
+        let bench = benchmark_pbkdf2(Duration::from_millis(1), 120_000);
+        assert_eq!(bench.recommended_iters, MIN_PASSPHRASE_ITERS);
+        assert!(bench.iterations_per_second > 0);
+    }
+}
@@ -1,7 +1,9 @@
 //! Keyfile parsing and persistence helpers shared by CLI, daemon, and UI.
 
+use crate::config::KeySource;
 use crate::error::{LockchainError, LockchainResult};
-use hex::FromHex;
+use crate::secret::SecretBuffer;
+use sha2::{Digest, Sha256};
 use std::fs;
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
@@ -11,11 +13,11 @@ use zeroize::Zeroizing;
 ///
 /// The boolean flag indicates whether the original material was hex encoded and
 /// therefore required normalisation to raw bytes.
-pub type DecodedKey = (Zeroizing<Vec<u8>>, bool);
+pub type DecodedKey = (SecretBuffer, bool);
 
 /// Read bytes from `path` and decode them into raw key material.
 pub fn read_key_file(path: &Path) -> LockchainResult<DecodedKey> {
-    let contents = fs::read(path)?;
+    let contents = Zeroizing::new(fs::read(path)?);
     decode_key_bytes(path, &contents)
 }
 
@@ -23,14 +25,14 @@ pub fn read_key_file(path: &Path) -> LockchainResult<DecodedKey> {
 /// or a 64-digit hex string (whitespace ignored).
 pub fn decode_key_bytes(origin: &Path, bytes: &[u8]) -> LockchainResult<DecodedKey> {
     if bytes.len() == 32 {
-        return Ok((Zeroizing::new(bytes.to_vec()), false));
+        return Ok((SecretBuffer::from_slice(bytes), false));
     }
 
     if bytes.is_empty() {
         return Err(invalid_key(origin, "file is empty"));
     }
 
-    let mut filtered = Vec::with_capacity(bytes.len());
+    let mut filtered = Zeroizing::new(Vec::with_capacity(bytes.len()));
     for byte in bytes {
         if byte.is_ascii_whitespace() {
             continue;
@@ -58,19 +60,11 @@ pub fn decode_key_bytes(origin: &Path, bytes: &[u8]) -> LockchainResult<DecodedK
         ));
     }
 
-    let filtered = String::from_utf8(filtered)
-        .map_err(|_| invalid_key(origin, "hex key contains non-UTF-8 characters"))?;
-    let key = Vec::from_hex(filtered.as_str())
+    let mut key = SecretBuffer::zeroed(32);
+    hex::decode_to_slice(&filtered[..], &mut key[..])
         .map_err(|err| invalid_key(origin, format!("hex decode failed: {err}")))?;
 
-    if key.len() != 32 {
-        return Err(invalid_key(
-            origin,
-            format!("decoded key must be 32 bytes (got {})", key.len()),
-        ));
-    }
-
-    Ok((Zeroizing::new(key), true))
+    Ok((key, true))
 }
 
 /// Write raw key material to `path`, applying restrictive permissions.
@@ -79,10 +73,200 @@ pub fn write_raw_key_file(path: &Path, key: &[u8]) -> LockchainResult<()> {
         fs::create_dir_all(parent)?;
     }
     fs::write(path, key)?;
-    fs::set_permissions(path, std::fs::Permissions::from_mode(0o400))?;
+    fs::set_permissions(path, std::fs::Permissions::from_mode(KEY_FILE_MODE))?;
+    Ok(())
+}
+
+/// Replace the key file at `path` with `key` in one step: the key goes to a
+/// 0400 file in the same directory, which is then renamed over the old one,
+/// so a failure part-way leaves the previous key in place.
+pub fn replace_raw_key_file(path: &Path, key: &[u8]) -> LockchainResult<()> {
+    use std::io::Write;
+
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir)?;
+    let mut staged = tempfile::Builder::new()
+        .prefix(".lockchain-key-")
+        .tempfile_in(dir)?;
+    staged.write_all(key)?;
+    staged.as_file().sync_all()?;
+    fs::set_permissions(staged.path(), fs::Permissions::from_mode(KEY_FILE_MODE))?;
+    staged
+        .persist(path)
+        .map_err(|err| LockchainError::Io(err.error))?;
+    Ok(())
+}
+
+/// Overwrite the key file at `path` with random bytes, flush it, and remove it.
+///
+/// Best effort on copy-on-write or journaling filesystems, where old blocks
+/// may survive; it still keeps the key out of casual reach.
+pub fn shred_key_file(path: &Path) -> LockchainResult<()> {
+    use rand::RngCore;
+    use std::io::Write;
+
+    let len = fs::metadata(path)?.len() as usize;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    let mut noise = Zeroizing::new(vec![0u8; len]);
+    rand::rngs::OsRng.fill_bytes(&mut noise);
+    let mut file = fs::OpenOptions::new().write(true).open(path)?;
+    file.write_all(&noise)?;
+    file.sync_all()?;
+    drop(file);
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+/// Hand `key` to the first process that opens the named pipe at `path`.
+///
+/// The pipe is created (0600) when missing and removed afterwards; an
+/// existing pipe is reused and left in place, but only when it is owned by
+/// the caller and closed to group and others. Symlinks are not followed, and
+/// the opened pipe is checked again so a path swapped after the first check
+/// is refused before anything is written. Blocks until a reader appears.
+pub fn write_key_to_fifo(path: &Path, key: &[u8]) -> LockchainResult<()> {
+    use std::ffi::CString;
+    use std::io::{ErrorKind, Write};
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let created = match fs::symlink_metadata(path) {
+        Ok(metadata) => {
+            check_private_fifo(path, &metadata)?;
+            false
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            let c_path = CString::new(path.as_os_str().as_bytes()).map_err(|_| {
+                LockchainError::InvalidConfig(format!("{} contains a NUL byte", path.display()))
+            })?;
+            // SAFETY: `c_path` is a valid NUL-terminated string for the call.
+            if unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) } != 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            true
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let written = fs::OpenOptions::new()
+        .write(true)
+        .custom_flags(libc::O_NOFOLLOW | libc::O_CLOEXEC)
+        .open(path)
+        .map_err(LockchainError::from)
+        .and_then(|mut pipe| {
+            check_private_fifo(path, &pipe.metadata()?)?;
+            pipe.write_all(key)?;
+            Ok(())
+        });
+    if created {
+        fs::remove_file(path)?;
+    }
+    written
+}
+
+/// Refuse anything but a named pipe owned by the caller and closed to group
+/// and others.
+fn check_private_fifo(path: &Path, metadata: &fs::Metadata) -> LockchainResult<()> {
+    use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+    if !metadata.file_type().is_fifo() {
+        return Err(LockchainError::InvalidConfig(format!(
+            "{} exists and is not a named pipe",
+            path.display()
+        )));
+    }
+    // SAFETY: geteuid has no preconditions and cannot fail.
+    let euid = unsafe { libc::geteuid() };
+    if metadata.uid() != euid || metadata.mode() & 0o077 != 0 {
+        return Err(LockchainError::InvalidConfig(format!(
+            "refusing named pipe {} (owner uid {}, mode {:o}): it must belong to uid {euid} and be closed to group and others",
+            path.display(),
+            metadata.uid(),
+            metadata.mode() & 0o777
+        )));
+    }
     Ok(())
 }
 
+/// Mode key files are expected to carry on disk.
+pub const KEY_FILE_MODE: u32 = 0o400;
+
+/// Read-only assessment of the key material behind one [`KeySource`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyVerification {
+    pub path: PathBuf,
+    /// Config entry holding the pinned checksum (see [`KeySource::setting`]).
+    pub setting: String,
+    pub exists: bool,
+    /// The file decodes to exactly 32 bytes of key material.
+    pub length_ok: bool,
+    /// `None` when no checksum is pinned for this source.
+    pub checksum_match: Option<bool>,
+    /// The file mode is exactly [`KEY_FILE_MODE`].
+    pub permissions_ok: bool,
+    pub mode: Option<u32>,
+    /// The file still holds legacy hex text rather than raw bytes.
+    pub hex_encoded: bool,
+    /// SHA-256 of the decoded key, when it could be read.
+    pub sha256: Option<String>,
+    /// Why the file could not be read or decoded.
+    pub problem: Option<String>,
+}
+
+impl KeyVerification {
+    /// True when every check passed (an unpinned checksum is not a failure).
+    pub fn is_healthy(&self) -> bool {
+        self.exists && self.length_ok && self.permissions_ok && self.checksum_match != Some(false)
+    }
+}
+
+/// Inspect `source` without touching the file or the configuration.
+pub fn verify_key_source(source: &KeySource) -> KeyVerification {
+    let mut verification = KeyVerification {
+        path: source.path.clone(),
+        setting: source.setting(),
+        exists: false,
+        length_ok: false,
+        checksum_match: None,
+        permissions_ok: false,
+        mode: None,
+        hex_encoded: false,
+        sha256: None,
+        problem: None,
+    };
+
+    let mode = match fs::metadata(&source.path) {
+        Ok(meta) => meta.permissions().mode() & 0o777,
+        Err(err) => {
+            verification.problem = Some(err.to_string());
+            return verification;
+        }
+    };
+    verification.exists = true;
+    verification.mode = Some(mode);
+    verification.permissions_ok = mode == KEY_FILE_MODE;
+
+    let (key, hex_encoded) = match read_key_file(&source.path) {
+        Ok(decoded) => decoded,
+        Err(err) => {
+            verification.problem = Some(err.to_string());
+            return verification;
+        }
+    };
+    verification.hex_encoded = hex_encoded;
+    verification.length_ok = key.len() == 32;
+
+    let digest = hex::encode(Sha256::digest(&key[..]));
+    verification.checksum_match = source
+        .expected_sha256
+        .as_ref()
+        .map(|expected| expected.eq_ignore_ascii_case(&digest));
+    verification.sha256 = Some(digest);
+    verification
+}
+
 fn invalid_key(path: &Path, reason: impl Into<String>) -> LockchainError {
     LockchainError::InvalidHexKey {
         path: PathBuf::from(path),
@@ -147,6 +331,87 @@ mod tests {
         assert_eq!(metadata.permissions().mode() & 0o777, 0o400);
     }
 
+    #[test]
+    fn ephemeral_outputs_leave_nothing_behind() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("key.bin");
+        write_raw_key_file(&path, &[0x42; 32]).unwrap();
+        shred_key_file(&path).unwrap();
+        assert!(!path.exists());
+
+        let fifo = dir.path().join("key.fifo");
+        let reader = {
+            let fifo = fifo.clone();
+            std::thread::spawn(move || loop {
+                if let Ok(bytes) = fs::read(&fifo) {
+                    return bytes;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            })
+        };
+        write_key_to_fifo(&fifo, &[0x42; 32]).unwrap();
+        assert_eq!(reader.join().unwrap(), vec![0x42; 32]);
+        assert!(!fifo.exists());
+
+        assert!(write_key_to_fifo(&dir.path().join("key.bin.missing/x"), &[0; 32]).is_err());
+    }
+
+    #[test]
+    fn write_key_to_fifo_refuses_shared_pipes_and_symlinks() {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempdir().unwrap();
+        let shared = dir.path().join("shared.fifo");
+        let c_path = CString::new(shared.as_os_str().as_bytes()).unwrap();
+        // SAFETY: `c_path` is a valid NUL-terminated string for the call.
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o644)).unwrap();
+        let err = write_key_to_fifo(&shared, &[0x42; 32]).unwrap_err();
+        assert!(err.to_string().contains("closed to group and others"));
+        assert!(shared.exists());
+
+        let target = dir.path().join("target");
+        fs::write(&target, b"").unwrap();
+        let link = dir.path().join("link.fifo");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        let err = write_key_to_fifo(&link, &[0x42; 32]).unwrap_err();
+        assert!(err.to_string().contains("is not a named pipe"));
+        assert!(fs::read(&target).unwrap().is_empty());
+    }
+
+    #[test]
+    fn verify_key_source_reports_each_check() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("key.hex");
+        fs::write(&path, hex::encode([0x42u8; 32])).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        let source = KeySource {
+            path: path.clone(),
+            expected_sha256: Some(hex::encode(Sha256::digest([0x42u8; 32]))),
+            entry: None,
+        };
+
+        let verification = verify_key_source(&source);
+        assert!(verification.exists && verification.length_ok);
+        assert!(verification.hex_encoded);
+        assert_eq!(verification.checksum_match, Some(true));
+        assert!(!verification.permissions_ok);
+        assert!(!verification.is_healthy());
+        assert_eq!(
+            fs::read(&path).unwrap().len(),
+            64,
+            "verification must not rewrite"
+        );
+
+        let missing = verify_key_source(&KeySource {
+            path: dir.path().join("absent"),
+            ..source
+        });
+        assert!(!missing.exists);
+        assert!(missing.problem.is_some());
+    }
+
     #[test]
     fn write_raw_key_file_creates_parent() {
         let dir = tempdir().unwrap();
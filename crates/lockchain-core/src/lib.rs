@@ -2,15 +2,42 @@
 //! provider traits, workflows, and services all live here so downstream crates
 //! can focus on user experience instead of reimplementing plumbing.
 
+pub mod audit;
+pub mod cache;
 pub mod config;
+pub mod control;
+pub mod edit;
 pub mod error;
+pub mod hooks;
+pub mod kdf;
 pub mod keyfile;
 pub mod logging;
+pub mod provenance;
 pub mod provider;
+pub mod reload;
+pub mod remote;
+pub mod seal;
+pub mod secret;
 pub mod service;
+pub mod token;
+pub mod units;
+pub mod vault;
 pub mod workflow;
 
-pub use config::{ConfigFormat, CryptoCfg, Fallback, LockchainConfig, Policy, Usb};
+pub use config::{
+    ActiveProfile, AuditCfg, ConfigFormat, ConfigIssue, CryptoCfg, DatasetKey, Fallback,
+    IssueSeverity, KeySource, LockchainConfig, Policy, RemoteCfg, RemoteUnlockCfg, RetryStrategy,
+    ThemePreset, TuiCfg, TuiTheme, UiCfg, UiTheme, UnlockWindow, Usb, UsbToken, VaultAuth,
+    VaultCfg, VaultMode,
+};
 pub use error::{LockchainError, LockchainResult};
+pub use hooks::{HookContext, HookEvent, HookRegistry};
+pub use keyfile::KeyVerification;
+pub use provenance::{EffectiveSetting, SettingOrigin};
 pub use provider::{DatasetKeyDescriptor, KeyState, KeyStatusSnapshot, ZfsProvider};
-pub use service::{LockchainService, UnlockOptions, UnlockReport};
+pub use reload::{ConfigChange, ConfigUpdate};
+pub use secret::SecretBuffer;
+pub use service::{
+    DatasetStatus, EstateStatus, LockOptions, LockReport, LockchainService, RetryAttempt,
+    RootStatus, SuppliedKey, UnlockOptions, UnlockReport,
+};
@@ -1,22 +1,45 @@
 //! Lightweight logging bootstrapper shared by every Lockchain binary.
+//!
+//! Records may carry `log` key-values such as `dataset`, `event`, and
+//! `error_code`; JSON lines include them as extra keys and the journal gets
+//! them as `DATASET=`, `EVENT=`, and `ERROR_CODE=` fields.
 
 use env_logger::Env;
+use log::kv::{Error as KvError, Key, Value, VisitSource};
+use log::{Level, Log, Metadata, Record};
 use serde_json::json;
 use std::env;
 use std::io::Write;
-use std::sync::OnceLock;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::net::UnixDatagram;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
 
 static INIT: OnceLock<()> = OnceLock::new();
 
+/// Where log records go instead of stderr while a [`LogCapture`] is alive.
+static CAPTURE: Mutex<Option<(u64, SyncSender<LogLine>)>> = Mutex::new(None);
+static NEXT_CAPTURE: AtomicU64 = AtomicU64::new(0);
+
+/// Records buffered for a capture before new ones are dropped.
+const CAPTURE_BUFFER: usize = 1024;
+
 const FORMAT_ENV: &str = "LOCKCHAIN_LOG_FORMAT";
 const LEVEL_ENV: &str = "LOCKCHAIN_LOG_LEVEL";
 
+/// Socket journald reads native protocol datagrams from.
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
 /// Initialize a global logger for Lockchain binaries.
 ///
 /// The first caller wins; subsequent calls are no-ops. If `RUST_LOG` is
 /// unset, the `default_level` argument is used, overridable via
-/// `LOCKCHAIN_LOG_LEVEL`. `LOCKCHAIN_LOG_FORMAT` can be set to `plain` to
-/// disable JSON output.
+/// `LOCKCHAIN_LOG_LEVEL`. `LOCKCHAIN_LOG_FORMAT` picks `json` (the
+/// default), `plain`, or `journald`; when it is unset and stderr is
+/// connected to the journal, as under systemd, records go to journald with
+/// structured fields instead.
 pub fn init(default_level: &str) {
     let _ = INIT.get_or_init(|| configure(default_level));
 }
@@ -27,20 +50,38 @@ fn configure(default_level: &str) {
         env::set_var("RUST_LOG", &default_level);
     }
 
-    let format = env::var(FORMAT_ENV)
-        .unwrap_or_else(|_| String::from("json"))
-        .to_lowercase();
+    let format = match env::var(FORMAT_ENV) {
+        Ok(format) => format.to_lowercase(),
+        Err(_) if stderr_is_journal() => String::from("journald"),
+        Err(_) => String::from("json"),
+    };
+    let journal = (format == "journald")
+        .then(Journal::connect)
+        .and_then(|journal| {
+            journal
+                .inspect_err(|err| {
+                    eprintln!("journald unavailable ({err}); logging JSON to stderr")
+                })
+                .ok()
+        });
 
     let mut builder = env_logger::Builder::from_env(Env::default());
-    if format == "json" {
+    if format != "plain" {
         builder.format(|buf, record| {
             let ts = buf.timestamp().to_string();
-            let payload = json!({
+            let mut payload = json!({
                 "timestamp": ts,
                 "level": record.level().to_string().to_lowercase(),
                 "target": record.target(),
                 "message": record.args().to_string(),
             });
+            let mut fields = Fields::default();
+            let _ = record.key_values().visit(&mut fields);
+            if let Some(object) = payload.as_object_mut() {
+                for (key, value) in fields.0 {
+                    object.entry(key).or_insert(value.into());
+                }
+            }
             writeln!(buf, "{}", payload)
         });
     } else {
@@ -56,7 +97,286 @@ fn configure(default_level: &str) {
         });
     }
 
-    if let Err(err) = builder.try_init() {
-        eprintln!("failed to initialize logger: {}", err);
+    let inner = builder.build();
+    let max_level = inner.filter();
+    match log::set_boxed_logger(Box::new(Tee { inner, journal })) {
+        Ok(()) => log::set_max_level(max_level),
+        Err(err) => eprintln!("failed to initialize logger: {}", err),
+    }
+}
+
+/// A log record handed to a [`LogCapture`].
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub at: SystemTime,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// Diverts records that pass the configured filter away from stderr, e.g.
+/// while a full-screen interface owns the terminal.
+///
+/// Only one capture is active at a time: the newest replaces any earlier one,
+/// and dropping it restores stderr output.
+pub struct LogCapture {
+    id: u64,
+    receiver: Receiver<LogLine>,
+}
+
+impl LogCapture {
+    /// Start capturing; has no effect on records until [`init`] has run.
+    pub fn start() -> Self {
+        let (sender, receiver) = mpsc::sync_channel(CAPTURE_BUFFER);
+        let id = NEXT_CAPTURE.fetch_add(1, Ordering::Relaxed);
+        *CAPTURE.lock().unwrap_or_else(|err| err.into_inner()) = Some((id, sender));
+        Self { id, receiver }
+    }
+
+    /// Records captured since the last call, oldest first.
+    pub fn drain(&self) -> impl Iterator<Item = LogLine> + '_ {
+        self.receiver.try_iter()
+    }
+}
+
+impl Drop for LogCapture {
+    fn drop(&mut self) {
+        let mut capture = CAPTURE.lock().unwrap_or_else(|err| err.into_inner());
+        if matches!(capture.as_ref(), Some((id, _)) if *id == self.id) {
+            *capture = None;
+        }
+    }
+}
+
+/// Whether stderr is the stream systemd connected to the journal, per the
+/// `JOURNAL_STREAM` (`device:inode`) it exports.
+fn stderr_is_journal() -> bool {
+    let Ok(stream) = env::var("JOURNAL_STREAM") else {
+        return false;
+    };
+    let Some((dev, ino)) = stream.split_once(':') else {
+        return false;
+    };
+    std::fs::metadata("/proc/self/fd/2")
+        .is_ok_and(|meta| dev.parse() == Ok(meta.dev()) && ino.parse() == Ok(meta.ino()))
+}
+
+/// Key-values attached to a record, in the order they were given.
+#[derive(Default)]
+struct Fields(Vec<(String, String)>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), KvError> {
+        self.0.push((key.as_str().to_string(), value.to_string()));
+        Ok(())
+    }
+}
+
+/// Client for journald's native protocol: one datagram of fields per record.
+struct Journal {
+    socket: UnixDatagram,
+    identifier: String,
+}
+
+impl Journal {
+    fn connect() -> std::io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(JOURNAL_SOCKET)?;
+        let identifier = env::args()
+            .next()
+            .as_deref()
+            .and_then(|arg0| arg0.rsplit('/').next())
+            .filter(|name| !name.is_empty())
+            .unwrap_or("lockchain")
+            .to_string();
+        Ok(Self { socket, identifier })
+    }
+
+    fn send(&self, record: &Record<'_>) -> std::io::Result<()> {
+        let datagram = journal_entry(&self.identifier, record);
+        self.socket.send(&datagram).map(drop)
+    }
+}
+
+/// Encode `record` as a native protocol entry: `MESSAGE`, `PRIORITY`, the
+/// caller's location, and each key-value upper-cased as its own field.
+/// `ERROR_CODE` falls back to the first `[LCxxxx]` code in the message.
+fn journal_entry(identifier: &str, record: &Record<'_>) -> Vec<u8> {
+    let message = record.args().to_string();
+    let priority = match record.level() {
+        Level::Error => "3",
+        Level::Warn => "4",
+        Level::Info => "6",
+        Level::Debug | Level::Trace => "7",
+    };
+    let mut entry = Vec::new();
+    let mut field = |name: &str, value: &str| {
+        entry.extend_from_slice(name.as_bytes());
+        if value.contains('\n') {
+            // Binary form: the name, a newline, then the length-prefixed value.
+            entry.push(b'\n');
+            entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            entry.push(b'=');
+        }
+        entry.extend_from_slice(value.as_bytes());
+        entry.push(b'\n');
+    };
+    field("MESSAGE", &message);
+    field("PRIORITY", priority);
+    field("SYSLOG_IDENTIFIER", identifier);
+    field("TARGET", record.target());
+    if let Some(file) = record.file() {
+        field("CODE_FILE", file);
+    }
+    if let Some(line) = record.line() {
+        field("CODE_LINE", &line.to_string());
+    }
+
+    let mut fields = Fields::default();
+    let _ = record.key_values().visit(&mut fields);
+    let mut has_code = false;
+    for (key, value) in &fields.0 {
+        let name = journal_field_name(key);
+        if name.is_empty() || matches!(name.as_str(), "MESSAGE" | "PRIORITY") {
+            continue;
+        }
+        has_code |= name == "ERROR_CODE";
+        field(&name, value);
+    }
+    if !has_code {
+        if let Some(code) = message_code(&message) {
+            field("ERROR_CODE", code);
+        }
+    }
+    entry
+}
+
+/// Journal field names are upper-case letters, digits, and underscores, and
+/// may not start with an underscore (reserved for trusted fields) or a digit.
+fn journal_field_name(key: &str) -> String {
+    let name: String = key
+        .chars()
+        .map(|c| match c.to_ascii_uppercase() {
+            c @ ('A'..='Z' | '0'..='9') => c,
+            _ => '_',
+        })
+        .collect();
+    name.trim_start_matches(|c: char| c == '_' || c.is_ascii_digit())
+        .to_string()
+}
+
+/// The `LCxxxx` code of a `LockchainError` rendered into `message`.
+fn message_code(message: &str) -> Option<&str> {
+    let start = message.find("[LC")? + 1;
+    let code = message.get(start..start + 6)?;
+    let closed = message[start + 6..].starts_with(']');
+    (closed && code[2..].bytes().all(|b| b.is_ascii_digit())).then_some(code)
+}
+
+/// env_logger, or journald when it is the configured sink, unless a
+/// [`LogCapture`] is listening.
+struct Tee {
+    inner: env_logger::Logger,
+    journal: Option<Journal>,
+}
+
+impl Log for Tee {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.inner.matches(record) {
+            return;
+        }
+        if let Some((_, sender)) = CAPTURE
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .as_ref()
+        {
+            // A full buffer drops the record rather than stall the caller.
+            let _ = sender.try_send(LogLine {
+                at: SystemTime::now(),
+                level: record.level(),
+                target: record.target().to_string(),
+                message: record.args().to_string(),
+            });
+            return;
+        }
+        if let Some(journal) = &self.journal {
+            if journal.send(record).is_ok() {
+                return;
+            }
+        }
+        self.inner.log(record);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines_of(entry: &[u8]) -> Vec<String> {
+        String::from_utf8_lossy(entry)
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn journal_entries_carry_key_values_as_fields() {
+        let pairs = [("dataset", "tank/secure"), ("event", "unlock")];
+        let entry = journal_entry(
+            "lockchain-daemon",
+            &Record::builder()
+                .args(format_args!("unlock failed: [LC2000] provider error: boom"))
+                .level(Level::Warn)
+                .target("lockchain_daemon")
+                .key_values(&pairs)
+                .build(),
+        );
+        let fields = lines_of(&entry);
+        for expected in [
+            "MESSAGE=unlock failed: [LC2000] provider error: boom",
+            "PRIORITY=4",
+            "SYSLOG_IDENTIFIER=lockchain-daemon",
+            "DATASET=tank/secure",
+            "EVENT=unlock",
+            "ERROR_CODE=LC2000",
+        ] {
+            assert!(fields.iter().any(|field| field == expected), "{expected}");
+        }
+
+        let pairs = [("error_code", "LC4100"), ("_pid", "1")];
+        let entry = journal_entry(
+            "lockchain-daemon",
+            &Record::builder()
+                .args(format_args!("two\nlines [LC2000]"))
+                .level(Level::Error)
+                .key_values(&pairs)
+                .build(),
+        );
+        let mut binary = b"MESSAGE\n".to_vec();
+        binary.extend_from_slice(&18u64.to_le_bytes());
+        binary.extend_from_slice(b"two\nlines [LC2000]\n");
+        assert!(entry.starts_with(&binary));
+        let fields = lines_of(&entry);
+        assert!(fields.contains(&"ERROR_CODE=LC4100".to_string()));
+        assert!(fields.contains(&"PID=1".to_string()));
+        assert!(!fields.iter().any(|field| field == "ERROR_CODE=LC2000"));
+    }
+
+    #[test]
+    fn message_codes_need_the_full_bracketed_form() {
+        assert_eq!(message_code("x [LC1200] y"), Some("LC1200"));
+        assert_eq!(message_code("[LC12] y"), None);
+        assert_eq!(message_code("[LC1200"), None);
+        assert_eq!(journal_field_name("error-code"), "ERROR_CODE");
+        assert_eq!(journal_field_name("__9x"), "X");
     }
 }
@@ -0,0 +1,263 @@
+//! Effective-configuration provenance: which layer (defaults, base file,
+//! drop-ins, profile, or environment) supplied each resolved setting.
+
+use crate::config::{self, ConfigFormat, LockchainConfig};
+use crate::error::{LockchainError, LockchainResult};
+use crate::reload::REDACTED_FIELDS;
+use serde::Serialize;
+use serde_json::Value;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Layer that supplied an effective setting, from lowest to highest precedence.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "source", rename_all = "snake_case")]
+pub enum SettingOrigin {
+    /// Built-in default; nothing on disk sets it.
+    Default,
+    /// The base config file.
+    File,
+    /// A fragment in `<config>.d/`.
+    DropIn(PathBuf),
+    /// The active `[profile.<name>]` overlay.
+    Profile(String),
+    /// An environment variable such as `LOCKCHAIN_KEY_PATH`.
+    Env(String),
+}
+
+impl std::fmt::Display for SettingOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingOrigin::Default => f.write_str("default"),
+            SettingOrigin::File => f.write_str("file"),
+            SettingOrigin::DropIn(path) => write!(f, "drop-in {}", path.display()),
+            SettingOrigin::Profile(name) => write!(f, "profile {name}"),
+            SettingOrigin::Env(var) => write!(f, "env {var}"),
+        }
+    }
+}
+
+/// One resolved setting and where its value came from.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EffectiveSetting {
+    /// Dotted path, e.g. `retry.max_attempts`.
+    pub field: String,
+    /// Value the services actually use; secrets are redacted.
+    pub value: Value,
+    pub origin: SettingOrigin,
+    /// What the base file says, when a later layer replaced it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_disk: Option<Value>,
+}
+
+impl EffectiveSetting {
+    /// Whether the value differs from what the base file alone would give.
+    pub fn overridden(&self) -> bool {
+        self.origin != SettingOrigin::File
+    }
+}
+
+impl LockchainConfig {
+    /// Resolve every setting and attribute it to the layer that supplied it.
+    ///
+    /// Tables are expanded key by key; arrays are reported whole and credited
+    /// to the last layer that set them. Unset optional values are omitted.
+    pub fn effective_settings(&self) -> LockchainResult<Vec<EffectiveSetting>> {
+        let mut layers = vec![(SettingOrigin::File, self.raw_file()?)];
+        for fragment in &self.fragments {
+            let contents = fs::read_to_string(fragment)?;
+            let value: Value = toml::from_str(&contents).map_err(|err| {
+                LockchainError::InvalidConfig(format!("{}: {err}", fragment.display()))
+            })?;
+            layers.push((SettingOrigin::DropIn(fragment.clone()), value));
+        }
+        if let Some(active) = &self.profile {
+            if let Some(overlay) = self.profiles.get(active.name()) {
+                layers.push((
+                    SettingOrigin::Profile(active.name().to_string()),
+                    overlay.clone(),
+                ));
+            }
+        }
+
+        let mut effective = serde_json::to_value(self)
+            .map_err(|err| LockchainError::InvalidConfig(err.to_string()))?;
+        if let Value::Object(map) = &mut effective {
+            map.remove("profile");
+        }
+        let mut env_origins = Vec::new();
+        for (field, var, value) in [
+            (
+                "usb.key_hex_path",
+                config::KEY_PATH_ENV,
+                self.key_hex_path(),
+            ),
+            ("audit.path", config::AUDIT_PATH_ENV, self.audit.log_path()),
+        ] {
+            if env::var(var).is_ok_and(|v| !v.is_empty()) {
+                let segments: Vec<&str> = field.split('.').collect();
+                if let Some(slot) = lookup_mut(&mut effective, &segments) {
+                    *slot = Value::String(value.to_string_lossy().into_owned());
+                }
+                env_origins.push((field, var));
+            }
+        }
+
+        let mut leaves = Vec::new();
+        flatten(&effective, &mut Vec::new(), &mut leaves);
+
+        let base = &layers[0].1;
+        let settings = leaves
+            .into_iter()
+            .filter_map(|(segments, value)| {
+                let field = segments.join(".");
+                let origin = env_origins
+                    .iter()
+                    .find(|(env_field, _)| *env_field == field)
+                    .map(|(_, var)| SettingOrigin::Env(var.to_string()))
+                    .or_else(|| {
+                        layers
+                            .iter()
+                            .rev()
+                            .find(|(_, layer)| lookup(layer, &segments).is_some())
+                            .map(|(origin, _)| origin.clone())
+                    })
+                    .unwrap_or(SettingOrigin::Default);
+                if value.is_null() && origin == SettingOrigin::Default {
+                    return None;
+                }
+                let on_disk = match origin {
+                    SettingOrigin::File => None,
+                    _ => lookup(base, &segments).cloned(),
+                };
+                let redacted = REDACTED_FIELDS.contains(&field.as_str());
+                let redact = |value: Value| {
+                    if redacted && !value.is_null() {
+                        Value::String("<redacted>".into())
+                    } else {
+                        value
+                    }
+                };
+                Some(EffectiveSetting {
+                    field,
+                    value: redact(value.clone()),
+                    origin,
+                    on_disk: on_disk.map(redact),
+                })
+            })
+            .collect();
+        Ok(settings)
+    }
+
+    /// The base file parsed without defaults, drop-ins, or profiles.
+    ///
+    /// For a `[remote]` pointer this is the verified copy cached on load.
+    fn raw_file(&self) -> LockchainResult<Value> {
+        if let Some(remote) = &self.remote {
+            let contents = fs::read_to_string(&remote.cache_path)?;
+            return Ok(if remote.is_yaml() {
+                serde_yaml::from_str(&contents)?
+            } else {
+                toml::from_str(&contents)?
+            });
+        }
+        let contents = fs::read_to_string(&self.path)?;
+        Ok(match self.format {
+            ConfigFormat::Toml => toml::from_str(&contents)?,
+            ConfigFormat::Yaml => serde_yaml::from_str(&contents)?,
+        })
+    }
+}
+
+/// Collect `(path, value)` for every non-table value under `value`.
+fn flatten<'a>(value: &'a Value, path: &mut Vec<String>, out: &mut Vec<(Vec<String>, &'a Value)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                path.push(key.clone());
+                flatten(child, path, out);
+                path.pop();
+            }
+        }
+        _ => out.push((path.clone(), value)),
+    }
+}
+
+/// Find `segments` in a raw layer, accepting deprecated spellings of the last key.
+fn lookup<'a, S: AsRef<str>>(layer: &'a Value, segments: &[S]) -> Option<&'a Value> {
+    let (last, parent) = segments.split_last()?;
+    let table = parent
+        .iter()
+        .try_fold(layer, |node, segment| node.get(segment.as_ref()))?;
+    table.get(last.as_ref()).or_else(|| {
+        let section = parent.first()?.as_ref();
+        config::DEPRECATED_KEYS
+            .iter()
+            .filter(|(deprecated_section, _, new)| {
+                parent.len() == 1 && *deprecated_section == section && *new == last.as_ref()
+            })
+            .find_map(|(_, old, _)| table.get(old))
+    })
+}
+
+fn lookup_mut<'a>(value: &'a mut Value, segments: &[&str]) -> Option<&'a mut Value> {
+    segments
+        .iter()
+        .try_fold(value, |node, segment| node.get_mut(*segment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effective_settings_credit_each_layer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lockchain-zfs.toml");
+        fs::write(
+            &path,
+            r#"
+            [policy]
+            datasets = ["tank/secure"]
+
+            [retry]
+            base_delay_ms = 250
+            max_attempts = 2
+
+            [profile.lab.retry]
+            max_attempts = 7
+        "#,
+        )
+        .unwrap();
+        let drop_ins = dir.path().join("lockchain-zfs.toml.d");
+        fs::create_dir(&drop_ins).unwrap();
+        fs::write(
+            drop_ins.join("10-media.toml"),
+            "[policy]\ndatasets = [\"tank/media\"]\n",
+        )
+        .unwrap();
+
+        let config = LockchainConfig::load_profile(&path, Some("lab")).unwrap();
+        let settings = config.effective_settings().unwrap();
+        let find = |field: &str| settings.iter().find(|s| s.field == field).unwrap();
+
+        let datasets = find("policy.datasets");
+        assert_eq!(
+            datasets.origin,
+            SettingOrigin::DropIn(drop_ins.join("10-media.toml"))
+        );
+        assert_eq!(datasets.on_disk, Some(serde_json::json!(["tank/secure"])));
+
+        let attempts = find("retry.max_attempts");
+        assert_eq!(attempts.origin, SettingOrigin::Profile("lab".into()));
+        assert_eq!(attempts.value, serde_json::json!(7));
+        assert_eq!(attempts.on_disk, Some(serde_json::json!(2)));
+
+        assert_eq!(find("retry.base_delay").origin, SettingOrigin::File);
+        assert_eq!(find("retry.max_delay").origin, SettingOrigin::Default);
+        assert!(!find("retry.base_delay").overridden());
+        assert!(settings.iter().all(|s| !s.field.starts_with("profile")));
+        assert!(settings.iter().all(|s| s.field != "usb.device_uuid"));
+    }
+}
@@ -1,9 +1,11 @@
 //! Abstractions that describe how we talk to ZFS providers and report their state.
 
-use crate::error::LockchainResult;
+use crate::error::{LockchainError, LockchainResult};
+use serde::{Deserialize, Serialize};
 
 /// Normalised keystatus for a dataset.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum KeyState {
     Available,
     Unavailable,
@@ -11,11 +13,16 @@ pub enum KeyState {
 }
 
 /// High-level descriptor for dataset encryption metadata.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DatasetKeyDescriptor {
     pub dataset: String,
     pub encryption_root: String,
     pub state: KeyState,
+    /// Whether the filesystem is mounted; `None` for volumes or when the
+    /// provider cannot tell.
+    pub mounted: Option<bool>,
+    pub keyformat: Option<String>,
+    pub keylocation: Option<String>,
 }
 
 /// Snapshot of keystatus information for a group of datasets.
@@ -43,4 +50,87 @@ pub trait ZfsProvider {
     /// should return entries for each dataset in the input slice, preserving
     /// that order.
     fn describe_datasets(&self, datasets: &[String]) -> LockchainResult<KeyStatusSnapshot>;
+
+    /// Keystatus for `root` and every descendant sharing it, root first and
+    /// the rest sorted by name. Backends should answer with a single recursive
+    /// query; the default only knows about locked descendants.
+    fn key_tree_status(&self, root: &str) -> LockchainResult<KeyStatusSnapshot> {
+        let locked = self.locked_descendants(root)?;
+        let state = |ds: &str| {
+            if locked.iter().any(|name| name == ds) {
+                KeyState::Unavailable
+            } else {
+                KeyState::Available
+            }
+        };
+        let mut snapshot = vec![DatasetKeyDescriptor {
+            dataset: root.to_string(),
+            encryption_root: root.to_string(),
+            state: state(root),
+            mounted: None,
+            keyformat: None,
+            keylocation: None,
+        }];
+        snapshot.extend(locked.iter().filter(|ds| ds.as_str() != root).map(|ds| {
+            DatasetKeyDescriptor {
+                dataset: ds.clone(),
+                encryption_root: root.to_string(),
+                state: KeyState::Unavailable,
+                mounted: None,
+                keyformat: None,
+                keylocation: None,
+            }
+        }));
+        Ok(snapshot)
+    }
+
+    /// Every encryption root the backend can see, sorted by name.
+    fn encryption_roots(&self) -> LockchainResult<Vec<String>> {
+        Err(LockchainError::Provider(
+            "provider cannot enumerate encryption roots".into(),
+        ))
+    }
+
+    /// Health `zpool` reports for `pool`, e.g. `ONLINE` or `DEGRADED`.
+    fn pool_health(&self, pool: &str) -> LockchainResult<String> {
+        Err(LockchainError::Provider(format!(
+            "provider cannot report pool health (requested for {pool})"
+        )))
+    }
+
+    /// Unload the key for `root` and every descendant sharing it, unmounting
+    /// them first when `unmount` is set. Returns the datasets that were
+    /// unlocked before the call, in sorted order.
+    fn unload_key_tree(&self, root: &str, unmount: bool) -> LockchainResult<Vec<String>> {
+        let _ = unmount;
+        Err(LockchainError::Provider(format!(
+            "provider cannot unload keys (requested for {root})"
+        )))
+    }
+
+    /// Mount the filesystem `dataset`; one that is already mounted is left
+    /// alone.
+    fn mount_dataset(&self, dataset: &str) -> LockchainResult<()> {
+        Err(LockchainError::Provider(format!(
+            "provider cannot mount datasets (requested for {dataset})"
+        )))
+    }
+
+    /// Ask ZFS whether `key` would unlock `root` without loading it. Only a
+    /// locked root can be checked this way.
+    fn check_key(&self, root: &str, key: &[u8]) -> LockchainResult<()> {
+        let _ = key;
+        Err(LockchainError::Provider(format!(
+            "provider cannot dry-run keys (requested for {root})"
+        )))
+    }
+
+    /// Re-wrap the encryption root `root` (which must be unlocked) under new
+    /// raw key material. Descendants sharing the root follow automatically.
+    fn change_key(&self, root: &str, key: &[u8]) -> LockchainResult<()> {
+        let _ = key;
+        Err(LockchainError::Provider(format!(
+            "provider cannot change keys (requested for {root})"
+        )))
+    }
 }
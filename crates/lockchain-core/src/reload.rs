@@ -0,0 +1,357 @@
+//! Config hot reload: an inotify watch on the config file and its drop-ins,
+//! delivering each reloaded snapshot together with the fields that changed.
+
+use crate::config::{self, LockchainConfig};
+use crate::error::LockchainResult;
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+use log::{debug, warn};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// Fields whose values never appear in a [`ConfigChange`].
+pub(crate) const REDACTED_FIELDS: &[&str] = &[
+    "fallback.passphrase_salt",
+    "fallback.passphrase_xor",
+    // Webhook URLs and headers routinely embed tokens.
+    "webhooks",
+];
+
+/// Wait after the first event so editors that write in several steps settle.
+const SETTLE: Duration = Duration::from_millis(100);
+
+/// One setting that differs between two config snapshots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    /// Dotted path of the setting, e.g. `retry.max_attempts`.
+    pub field: String,
+    /// Previous value (`None` when the setting was added); secrets are redacted.
+    pub old: Option<Value>,
+    /// New value (`None` when the setting was removed); secrets are redacted.
+    pub new: Option<Value>,
+}
+
+impl std::fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let show = |value: &Option<Value>| match value {
+            Some(value) => value.to_string(),
+            None => "(unset)".to_string(),
+        };
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.field,
+            show(&self.old),
+            show(&self.new)
+        )
+    }
+}
+
+/// A config reloaded from disk after a file change.
+#[derive(Debug, Clone)]
+pub struct ConfigUpdate {
+    pub config: LockchainConfig,
+    /// What differs from the previously delivered (or initial) snapshot.
+    pub changes: Vec<ConfigChange>,
+}
+
+impl ConfigUpdate {
+    /// Whether any setting under `section` (e.g. `policy` or `retry`) changed.
+    pub fn touches(&self, section: &str) -> bool {
+        self.changes.iter().any(|change| {
+            change.field == section
+                || change
+                    .field
+                    .strip_prefix(section)
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+    }
+}
+
+impl LockchainConfig {
+    /// List the settings that differ between `self` and `other`.
+    ///
+    /// Tables are compared key by key; arrays and scalars are compared whole.
+    pub fn diff(&self, other: &LockchainConfig) -> Vec<ConfigChange> {
+        let mut changes = Vec::new();
+        let old = serde_json::to_value(self).unwrap_or(Value::Null);
+        let new = serde_json::to_value(other).unwrap_or(Value::Null);
+        diff_values("", Some(&old), Some(&new), &mut changes);
+        changes
+    }
+
+    /// Watch `path` (and its `.d` drop-in directory) and deliver a
+    /// [`ConfigUpdate`] whenever a write produces a different configuration.
+    ///
+    /// Reload failures (for example a half-edited file) are delivered as `Err`
+    /// so callers can keep running on the last good snapshot. The watcher
+    /// thread exits once the receiver is dropped and the next event arrives.
+    pub fn watch<P: AsRef<Path>>(
+        path: P,
+    ) -> LockchainResult<Receiver<LockchainResult<ConfigUpdate>>> {
+        let path = path.as_ref().to_path_buf();
+        let current = LockchainConfig::load(&path)?;
+        let mut watch = ConfigWatch::new(&path)?;
+        let (tx, rx) = mpsc::channel();
+        thread::Builder::new()
+            .name("lockchain-config-watch".into())
+            .spawn(move || watch.run(current, tx))?;
+        Ok(rx)
+    }
+}
+
+/// inotify state for one config file.
+struct ConfigWatch {
+    path: PathBuf,
+    inotify: Inotify,
+    file_name: PathBuf,
+    drop_in_name: PathBuf,
+    drop_in: Option<WatchDescriptor>,
+}
+
+impl ConfigWatch {
+    fn new(path: &Path) -> LockchainResult<Self> {
+        let inotify = Inotify::init()?;
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        // Watch the directory rather than the file: editors and config
+        // management usually replace the file through a rename.
+        inotify.watches().add(
+            parent,
+            WatchMask::CLOSE_WRITE
+                | WatchMask::MOVED_TO
+                | WatchMask::MOVED_FROM
+                | WatchMask::CREATE
+                | WatchMask::DELETE,
+        )?;
+        let drop_in_dir = config::drop_in_dir(path);
+        let mut watch = Self {
+            path: path.to_path_buf(),
+            inotify,
+            file_name: path.file_name().map(PathBuf::from).unwrap_or_default(),
+            drop_in_name: drop_in_dir
+                .file_name()
+                .map(PathBuf::from)
+                .unwrap_or_default(),
+            drop_in: None,
+        };
+        watch.watch_drop_ins();
+        Ok(watch)
+    }
+
+    /// Start watching the drop-in directory if it exists and is not yet watched.
+    fn watch_drop_ins(&mut self) {
+        if self.drop_in.is_some() {
+            return;
+        }
+        let dir = config::drop_in_dir(&self.path);
+        if dir.is_dir() {
+            match self.inotify.watches().add(
+                &dir,
+                WatchMask::CLOSE_WRITE
+                    | WatchMask::MOVED_TO
+                    | WatchMask::MOVED_FROM
+                    | WatchMask::DELETE,
+            ) {
+                Ok(wd) => self.drop_in = Some(wd),
+                Err(err) => warn!("cannot watch drop-ins in {}: {err}", dir.display()),
+            }
+        }
+    }
+
+    fn run(&mut self, mut current: LockchainConfig, tx: Sender<LockchainResult<ConfigUpdate>>) {
+        let mut buffer = [0u8; 4096];
+        loop {
+            if !self.wait_for_change(&mut buffer) {
+                return;
+            }
+            thread::sleep(SETTLE);
+            self.drain(&mut buffer);
+
+            let update = match LockchainConfig::load(&self.path) {
+                Ok(config) => {
+                    let changes = current.diff(&config);
+                    if changes.is_empty() {
+                        debug!("{} rewritten without changes", self.path.display());
+                        continue;
+                    }
+                    current = config.clone();
+                    Ok(ConfigUpdate { config, changes })
+                }
+                Err(err) => Err(err),
+            };
+            if tx.send(update).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Block until an event touches the config or its drop-ins; false on a read error.
+    fn wait_for_change(&mut self, buffer: &mut [u8]) -> bool {
+        loop {
+            let events = match self.inotify.read_events_blocking(buffer) {
+                Ok(events) => events,
+                Err(err) => {
+                    warn!("config watch on {} stopped: {err}", self.path.display());
+                    return false;
+                }
+            };
+            let mut relevant = false;
+            let mut drop_in_created = false;
+            let mut drop_in_removed = false;
+            for event in events {
+                if Some(&event.wd) == self.drop_in.as_ref() {
+                    relevant |= !event.mask.contains(EventMask::IGNORED);
+                    drop_in_removed |= event.mask.contains(EventMask::IGNORED);
+                    continue;
+                }
+                let name = event.name.map(Path::new);
+                if name == Some(self.drop_in_name.as_path()) {
+                    drop_in_created |= event.mask.contains(EventMask::ISDIR);
+                    relevant = true;
+                } else if name == Some(self.file_name.as_path())
+                    && !event.mask.contains(EventMask::CREATE)
+                {
+                    relevant = true;
+                }
+            }
+            if drop_in_removed {
+                self.drop_in = None;
+            }
+            if drop_in_created {
+                self.watch_drop_ins();
+            }
+            if relevant {
+                return true;
+            }
+        }
+    }
+
+    /// Discard events queued while waiting for the writer to settle.
+    fn drain(&mut self, buffer: &mut [u8]) {
+        while let Ok(mut events) = self.inotify.read_events(buffer) {
+            if events.next().is_none() {
+                break;
+            }
+        }
+        self.watch_drop_ins();
+    }
+}
+
+fn diff_values(
+    field: &str,
+    old: Option<&Value>,
+    new: Option<&Value>,
+    changes: &mut Vec<ConfigChange>,
+) {
+    if let (Some(Value::Object(old)), Some(Value::Object(new))) = (old, new) {
+        let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            let child = if field.is_empty() {
+                key.clone()
+            } else {
+                format!("{field}.{key}")
+            };
+            diff_values(&child, old.get(key), new.get(key), changes);
+        }
+        return;
+    }
+    if old == new {
+        return;
+    }
+    let redact = |value: Option<&Value>| {
+        value.map(|value| {
+            if REDACTED_FIELDS.contains(&field) && !value.is_null() {
+                Value::String("<redacted>".into())
+            } else {
+                value.clone()
+            }
+        })
+    };
+    changes.push(ConfigChange {
+        field: field.to_string(),
+        old: redact(old),
+        new: redact(new),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const BASE: &str = r#"
+        [policy]
+        datasets = ["tank/secure"]
+
+        [fallback]
+        passphrase_salt = "aabb"
+        passphrase_xor = "ccdd"
+    "#;
+
+    #[test]
+    fn diff_lists_changed_fields_and_redacts_secrets() {
+        let old: LockchainConfig = toml::from_str(BASE).unwrap();
+        let mut new = old.clone();
+        new.retry.max_attempts += 1;
+        new.policy.datasets.push("tank/media".into());
+        new.fallback.passphrase_salt = Some("eeff".into());
+
+        let fields: Vec<String> = old.diff(&new).into_iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "fallback.passphrase_salt: \"<redacted>\" -> \"<redacted>\"".to_string(),
+                "policy.datasets: [\"tank/secure\"] -> [\"tank/secure\",\"tank/media\"]"
+                    .to_string(),
+                format!(
+                    "retry.max_attempts: {} -> {}",
+                    old.retry.max_attempts, new.retry.max_attempts
+                ),
+            ]
+        );
+        assert!(old.diff(&old).is_empty());
+    }
+
+    #[test]
+    fn watch_delivers_changes_on_rewrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lockchain-zfs.toml");
+        fs::write(&path, BASE).unwrap();
+        let updates = LockchainConfig::watch(&path).unwrap();
+
+        let staged = dir.path().join("lockchain-zfs.toml.tmp");
+        fs::write(&staged, BASE.replace("tank/secure", "tank/vault")).unwrap();
+        fs::rename(&staged, &path).unwrap();
+
+        let update = updates
+            .recv_timeout(Duration::from_secs(5))
+            .unwrap()
+            .unwrap();
+        assert_eq!(update.config.policy.datasets, vec!["tank/vault"]);
+        assert!(update.touches("policy"));
+        assert!(!update.touches("retry"));
+
+        let drop_ins = dir.path().join("lockchain-zfs.toml.d");
+        fs::create_dir(&drop_ins).unwrap();
+        thread::sleep(SETTLE * 3);
+        fs::write(
+            drop_ins.join("10-retry.toml"),
+            "[retry]\nmax_attempts = 9\n",
+        )
+        .unwrap();
+
+        let update = updates
+            .recv_timeout(Duration::from_secs(5))
+            .unwrap()
+            .unwrap();
+        assert_eq!(update.config.retry.max_attempts, 9);
+        assert!(update.touches("retry"));
+    }
+}
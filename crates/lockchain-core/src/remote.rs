@@ -0,0 +1,298 @@
+//! Remote config source: download a published config, check its detached
+//! minisign signature, and keep the last verified copy for offline boots.
+//!
+//! A download signed earlier than the cached copy, going by the
+//! `timestamp:` minisign puts in the trusted comment, is refused as a
+//! rollback and the cached copy is used instead.
+//!
+//! The HTTP client is only compiled with the `remote` feature; without it a
+//! `[remote]` pointer is served from the cache alone.
+
+use crate::config::RemoteCfg;
+use crate::error::{LockchainError, LockchainResult};
+use log::warn;
+use minisign_verify::{PublicKey, Signature};
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+/// The `[remote]` table of `contents`, if the file is a pointer to a published config.
+pub(crate) fn pointer(contents: &str, is_toml: bool) -> LockchainResult<Option<RemoteCfg>> {
+    let raw: serde_json::Value = if is_toml {
+        toml::from_str(contents)?
+    } else {
+        serde_yaml::from_str(contents)?
+    };
+    let Some(tables) = raw.as_object() else {
+        return Ok(None);
+    };
+    let others: Vec<&str> = tables
+        .keys()
+        .map(String::as_str)
+        .filter(|key| *key != "remote")
+        .collect();
+    if tables.contains_key("remote") && !others.is_empty() {
+        return Err(LockchainError::InvalidConfig(format!(
+            "a config with a [remote] table is only a pointer to the published config; \
+             move {} into a drop-in",
+            others.join(", ")
+        )));
+    }
+    match tables.get("remote") {
+        Some(table) => serde_json::from_value(table.clone())
+            .map(Some)
+            .map_err(|err| LockchainError::InvalidConfig(format!("invalid [remote] table: {err}"))),
+        None => Ok(None),
+    }
+}
+
+/// Download and verify the published config, falling back to the verified cache.
+pub fn fetch(cfg: &RemoteCfg) -> LockchainResult<String> {
+    let fresh = download(cfg).and_then(|(body, signature)| {
+        verify(cfg, &body, &signature)?;
+        refuse_rollback(cfg, &signature)?;
+        Ok((body, signature))
+    });
+    match fresh {
+        Ok((body, signature)) => {
+            if let Err(err) = store_cache(cfg, &body, &signature) {
+                warn!("cannot cache remote config at {}: {err}", cfg.cache_path);
+            }
+            Ok(body)
+        }
+        Err(err) => {
+            let (body, signature) = read_cache(cfg).map_err(|cache_err| {
+                LockchainError::InvalidConfig(format!(
+                    "{err}; no cached copy at {} ({cache_err})",
+                    cfg.cache_path
+                ))
+            })?;
+            verify(cfg, &body, &signature)?;
+            warn!(
+                "remote config {} unavailable ({err}); using cached copy from {}",
+                cfg.url, cfg.cache_path
+            );
+            Ok(body)
+        }
+    }
+}
+
+/// Check `signature` (a `.minisig` document) over `body` against `cfg.public_key`.
+pub fn verify(cfg: &RemoteCfg, body: &str, signature: &str) -> LockchainResult<()> {
+    let invalid = |reason: String| {
+        LockchainError::InvalidConfig(format!("remote config {}: {reason}", cfg.url))
+    };
+    let key = PublicKey::from_base64(cfg.public_key.trim())
+        .map_err(|err| invalid(format!("invalid public_key ({err})")))?;
+    let signature = Signature::decode(signature)
+        .map_err(|err| invalid(format!("malformed signature ({err})")))?;
+    key.verify(body.as_bytes(), &signature, false)
+        .map_err(|err| invalid(format!("signature verification failed ({err})")))
+}
+
+/// Fail when the verified cache was signed later than `signature`, or
+/// carries a timestamp that `signature` lacks.
+fn refuse_rollback(cfg: &RemoteCfg, signature: &str) -> LockchainResult<()> {
+    let Ok((body, cached)) = read_cache(cfg) else {
+        return Ok(());
+    };
+    if verify(cfg, &body, &cached).is_err() {
+        return Ok(());
+    }
+    let Some(cached) = signed_timestamp(&cached) else {
+        return Ok(());
+    };
+    match signed_timestamp(signature) {
+        Some(fresh) if fresh >= cached => Ok(()),
+        fresh => Err(LockchainError::InvalidConfig(format!(
+            "remote config {} is older than the cached copy (signed at {}, cached copy at {cached}); refusing the rollback",
+            cfg.url,
+            fresh.map_or_else(|| "an unknown time".to_string(), |fresh| fresh.to_string())
+        ))),
+    }
+}
+
+/// The `timestamp:` field of the signature's trusted comment.
+fn signed_timestamp(signature: &str) -> Option<u64> {
+    Signature::decode(signature)
+        .ok()?
+        .trusted_comment()
+        .split('\t')
+        .find_map(|field| field.strip_prefix("timestamp:"))?
+        .parse()
+        .ok()
+}
+
+/// Cached signature path next to the cached document.
+fn signature_cache_path(cfg: &RemoteCfg) -> PathBuf {
+    let mut path = cfg.cache_path.clone();
+    path.push_str(".minisig");
+    PathBuf::from(path)
+}
+
+fn read_cache(cfg: &RemoteCfg) -> LockchainResult<(String, String)> {
+    Ok((
+        fs::read_to_string(&cfg.cache_path)?,
+        fs::read_to_string(signature_cache_path(cfg))?,
+    ))
+}
+
+/// Persist a verified download; the signature is written last so a torn
+/// update fails verification instead of pairing old and new halves.
+fn store_cache(cfg: &RemoteCfg, body: &str, signature: &str) -> LockchainResult<()> {
+    let path = Path::new(&cfg.cache_path);
+    if let Some(parent) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    write_private(path, body)?;
+    write_private(&signature_cache_path(cfg), signature)
+}
+
+/// Replace `path` atomically with owner-only permissions.
+fn write_private(path: &Path, contents: &str) -> LockchainResult<()> {
+    let mut staged = path.as_os_str().to_os_string();
+    staged.push(".tmp");
+    let staged = PathBuf::from(staged);
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&staged)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&staged, path)?;
+    Ok(())
+}
+
+#[cfg(feature = "remote")]
+fn download(cfg: &RemoteCfg) -> LockchainResult<(String, String)> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(cfg.timeout.max(std::time::Duration::from_secs(1)))
+        .build();
+    let get = |url: &str| {
+        agent
+            .get(url)
+            .call()
+            .map_err(|err| LockchainError::InvalidConfig(format!("fetching {url} failed: {err}")))?
+            .into_string()
+            .map_err(|err| LockchainError::InvalidConfig(format!("reading {url} failed: {err}")))
+    };
+    Ok((get(&cfg.url)?, get(&cfg.signature_url())?))
+}
+
+#[cfg(not(feature = "remote"))]
+fn download(cfg: &RemoteCfg) -> LockchainResult<(String, String)> {
+    Err(LockchainError::InvalidConfig(format!(
+        "{} is configured but lockchain was built without the `remote` feature",
+        cfg.url
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LockchainConfig;
+
+    const PUBLIC_KEY: &str = "RWQBAgMEBQYHCAOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4";
+    const BODY: &str = "[policy]\ndatasets = [\"tank/fleet\"]\n";
+    const SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQBAgMEBQYHCATP7KsbXGS+S64HoWbD4UKWDT1UHQkTrBm4yIjtup/M6+LSEaStqhaYGDI26m3mrAa/zOE7diKFXmdvnzjTLQY=
+trusted comment: lockchain fleet config
+26NTaZCdIXsYfrusQUVJ/n2VLULRpy4HDxncXroX3Izmcn7pwYbN5b0Yyuitxyp3WVicROzWDZUtg3pKlE5xDg==
+";
+
+    fn pointer_file(dir: &Path) -> PathBuf {
+        let path = dir.join("lockchain-zfs.toml");
+        fs::write(
+            &path,
+            format!(
+                "[remote]\nurl = \"http://127.0.0.1:9/fleet.toml\"\npublic_key = \"{PUBLIC_KEY}\"\ncache_path = \"{}\"\ntimeout = \"1s\"\n",
+                dir.join("cache/remote.toml").display()
+            ),
+        )
+        .unwrap();
+        path
+    }
+
+    #[test]
+    fn verify_rejects_tampered_documents() {
+        let cfg = pointer(
+            &format!("[remote]\nurl = \"https://example.invalid/c.toml\"\npublic_key = \"{PUBLIC_KEY}\"\n"),
+            true,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            cfg.signature_url(),
+            "https://example.invalid/c.toml.minisig"
+        );
+        verify(&cfg, BODY, SIGNATURE).unwrap();
+        assert!(verify(&cfg, &BODY.replace("fleet", "other"), SIGNATURE).is_err());
+    }
+
+    #[test]
+    fn unreachable_remote_falls_back_to_verified_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = pointer_file(dir.path());
+        assert!(LockchainConfig::load(&path).is_err());
+
+        let cache = dir.path().join("cache");
+        fs::create_dir(&cache).unwrap();
+        fs::write(cache.join("remote.toml"), BODY).unwrap();
+        fs::write(cache.join("remote.toml.minisig"), SIGNATURE).unwrap();
+        let config = LockchainConfig::load(&path).unwrap();
+        assert_eq!(config.policy.datasets, vec!["tank/fleet"]);
+        assert!(config.remote.is_some());
+        assert!(config.save().is_err());
+
+        fs::write(cache.join("remote.toml"), BODY.replace("fleet", "evil")).unwrap();
+        assert!(LockchainConfig::load(&path).is_err());
+    }
+
+    #[test]
+    fn older_downloads_are_refused_as_rollbacks() {
+        const NEWER_BODY: &str = "[policy]\ndatasets = [\"tank/newer\"]\n";
+        let signed = |line: &str, comment: &str, global: &str| {
+            format!("untrusted comment: signature from minisign secret key\n{line}\ntrusted comment: {comment}\n{global}\n")
+        };
+        let older = signed(
+            "RUQBAgMEBQYHCATP7KsbXGS+S64HoWbD4UKWDT1UHQkTrBm4yIjtup/M6+LSEaStqhaYGDI26m3mrAa/zOE7diKFXmdvnzjTLQY=",
+            "timestamp:1700000000\tfile:fleet.toml\thashed",
+            "QS6C/5uzVvye+Lvf66PdLSweo+AuVfP/nDu3VUr39N3MJ5DsB5h9qdAJZ9zn/WpWNdfUjRLZIw68PInx0lHZAA==",
+        );
+        let newer = signed(
+            "RUQBAgMEBQYHCG6/Kp/QFjxE0zxt8fYzZVZp+39R/m7jtE+/prcFtja34Bloxau0mJ+bEtom42xizOArk+lgQc/RYcAQnSYkpwQ=",
+            "timestamp:1800000000\tfile:fleet.toml\thashed",
+            "WD32MmvfgkoNOqeHsd2/nAtxV+/61mAR4qUbBcXub5Z2J5M42CK9Pea0Z2wq0i9ku/RbHx7MbJhsfzwS12JcAg==",
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let contents = fs::read_to_string(pointer_file(dir.path())).unwrap();
+        let cfg = pointer(&contents, true).unwrap().unwrap();
+        verify(&cfg, BODY, &older).unwrap();
+        verify(&cfg, NEWER_BODY, &newer).unwrap();
+        assert_eq!(signed_timestamp(&older), Some(1_700_000_000));
+        assert_eq!(signed_timestamp(SIGNATURE), None);
+
+        // Nothing cached yet: any verified download goes.
+        refuse_rollback(&cfg, &older).unwrap();
+        store_cache(&cfg, NEWER_BODY, &newer).unwrap();
+        refuse_rollback(&cfg, &newer).unwrap();
+        let err = refuse_rollback(&cfg, &older).unwrap_err();
+        assert!(err.to_string().contains("rollback"));
+        assert!(refuse_rollback(&cfg, SIGNATURE).is_err());
+    }
+
+    #[test]
+    fn pointer_files_hold_nothing_but_the_remote_table() {
+        let contents = format!(
+            "[remote]\nurl = \"https://example.invalid/c.toml\"\npublic_key = \"{PUBLIC_KEY}\"\n\n[policy]\nrequire_strict_usb = true\n"
+        );
+        let err = pointer(&contents, true).unwrap_err();
+        assert!(err.to_string().contains("move policy into a drop-in"));
+        assert!(pointer("[policy]\ndatasets = []\n", true)
+            .unwrap()
+            .is_none());
+    }
+}
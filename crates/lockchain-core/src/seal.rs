@@ -0,0 +1,311 @@
+//! Host-bound encryption for sensitive config fields (fallback salt and mask).
+//!
+//! Sealed values look like `sealed:v2:<hex nonce||ciphertext>` and can only be
+//! opened by someone who can read the seal key, a random secret owned by root
+//! and readable only by the config file's group (the daemon's). Values sealed
+//! by older releases (`sealed:v1:`) used a key derived from `/etc/machine-id`, which any local
+//! user can read; they still open and are re-sealed under the seal key on the
+//! next save. The field name is bound as associated data so sealed values
+//! cannot be swapped between keys.
+
+use crate::error::{LockchainError, LockchainResult};
+use crate::secret::SecretBuffer;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::{chown, fchown, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+/// Prefix marking a value sealed with the seal key.
+pub const SEALED_PREFIX: &str = "sealed:v2:";
+
+/// Prefix of values sealed with the machine-id derived key.
+pub const LEGACY_SEALED_PREFIX: &str = "sealed:v1:";
+
+/// Environment variable overriding the seal key location (tests, containers).
+pub const SEAL_KEY_ENV: &str = "LOCKCHAIN_SEAL_KEY_PATH";
+
+/// Environment variable overriding the machine-id location for `sealed:v1:` values.
+pub const MACHINE_ID_ENV: &str = "LOCKCHAIN_MACHINE_ID_PATH";
+
+const DEFAULT_SEAL_KEY_PATH: &str = "/etc/lockchain/seal.key";
+const DEFAULT_MACHINE_ID_PATH: &str = "/etc/machine-id";
+const KEY_CONTEXT: &[u8] = b"lockchain-zfs config seal v2\0";
+const LEGACY_KEY_CONTEXT: &[u8] = b"lockchain-zfs config seal v1\0";
+const SEAL_KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+/// Whether `value` carries a sealed prefix.
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(SEALED_PREFIX) || value.starts_with(LEGACY_SEALED_PREFIX)
+}
+
+/// Where the seal key lives, honouring [`SEAL_KEY_ENV`].
+pub fn seal_key_path() -> PathBuf {
+    env::var_os(SEAL_KEY_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SEAL_KEY_PATH))
+}
+
+/// Create the seal key unless it exists, readable by its owner and `group`.
+///
+/// An existing key of the right length gets that owner, group, and mode back;
+/// one of the wrong length can never have sealed anything and is replaced.
+pub fn ensure_seal_key(group: Option<u32>) -> LockchainResult<PathBuf> {
+    let path = seal_key_path();
+    ensure_seal_key_at(&path, group)?;
+    Ok(path)
+}
+
+fn ensure_seal_key_at(path: &Path, group: Option<u32>) -> LockchainResult<()> {
+    let mode = if group.is_some() { 0o440 } else { 0o400 };
+    match fs::metadata(path) {
+        Ok(meta) if meta.len() == SEAL_KEY_LEN as u64 => {
+            // SAFETY: geteuid has no preconditions and cannot fail.
+            let euid = unsafe { libc::geteuid() };
+            if meta.uid() != euid || group.is_some_and(|gid| meta.gid() != gid) {
+                chown(path, Some(euid), group)?;
+            }
+            if meta.mode() & 0o777 != mode {
+                fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+            }
+            Ok(())
+        }
+        Ok(_) => write_seal_key(path, group, mode, true),
+        Err(err) if err.kind() == ErrorKind::NotFound => write_seal_key(path, group, mode, false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Write a fresh random key to a temp file beside `path` and rename it into
+/// place, so a failure part-way never leaves a short key behind.
+fn write_seal_key(
+    path: &Path,
+    group: Option<u32>,
+    mode: u32,
+    replace: bool,
+) -> LockchainResult<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir)?;
+    let mut secret = Zeroizing::new([0u8; SEAL_KEY_LEN]);
+    OsRng.fill_bytes(&mut secret[..]);
+
+    let mut staged = tempfile::Builder::new()
+        .prefix(".lockchain-seal-")
+        .tempfile_in(dir)?;
+    if let Some(gid) = group {
+        fchown(staged.as_file(), None, Some(gid))?;
+    }
+    staged
+        .as_file()
+        .set_permissions(fs::Permissions::from_mode(mode))?;
+    staged.write_all(&secret[..])?;
+    staged.as_file().sync_all()?;
+    let persisted = if replace {
+        staged.persist(path)
+    } else {
+        staged.persist_noclobber(path)
+    };
+    match persisted {
+        Ok(_) => {}
+        // Another process created it first; settle its ownership instead.
+        Err(err) if err.error.kind() == ErrorKind::AlreadyExists => {
+            return ensure_seal_key_at(path, group)
+        }
+        Err(err) => return Err(err.error.into()),
+    }
+    fs::File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+/// Encrypt `plaintext` for `field` with the seal key.
+pub fn seal_value(field: &str, plaintext: &str) -> LockchainResult<String> {
+    let cipher = cipher(&seal_key()?)?;
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(
+            &Nonce::from(nonce),
+            Payload {
+                msg: plaintext.as_bytes(),
+                aad: field.as_bytes(),
+            },
+        )
+        .map_err(|_| LockchainError::InvalidConfig(format!("failed to seal {field}")))?;
+
+    let mut blob = nonce.to_vec();
+    blob.extend_from_slice(&ciphertext);
+    Ok(format!("{SEALED_PREFIX}{}", hex::encode(blob)))
+}
+
+/// Decrypt a sealed `value` for `field`; plaintext values are returned unchanged.
+pub fn unseal_value(field: &str, value: &str) -> LockchainResult<String> {
+    let (encoded, key) = if let Some(encoded) = value.strip_prefix(SEALED_PREFIX) {
+        (encoded, seal_key()?)
+    } else if let Some(encoded) = value.strip_prefix(LEGACY_SEALED_PREFIX) {
+        (encoded, machine_key()?)
+    } else {
+        return Ok(value.to_string());
+    };
+    let invalid =
+        |reason: &str| LockchainError::InvalidConfig(format!("{field} is sealed but {reason}"));
+    let blob = hex::decode(encoded).map_err(|_| invalid("is not valid hex"))?;
+    let Some((nonce, ciphertext)) = blob
+        .split_first_chunk::<NONCE_LEN>()
+        .filter(|(_, ciphertext)| !ciphertext.is_empty())
+    else {
+        return Err(invalid("is truncated"));
+    };
+    let plaintext = Zeroizing::new(
+        cipher(&key)?
+            .decrypt(
+                &Nonce::from(*nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: field.as_bytes(),
+                },
+            )
+            .map_err(|_| invalid("cannot be opened with this host's seal key"))?,
+    );
+    String::from_utf8(plaintext.to_vec()).map_err(|_| invalid("does not decode to text"))
+}
+
+fn cipher(key: &SecretBuffer) -> LockchainResult<ChaCha20Poly1305> {
+    ChaCha20Poly1305::new_from_slice(key)
+        .map_err(|_| LockchainError::InvalidConfig("seal key has the wrong length".into()))
+}
+
+/// Derive the sealing key from the seal key file.
+fn seal_key() -> LockchainResult<SecretBuffer> {
+    let path = seal_key_path();
+    let secret = read_seal_key(&path).map_err(|err| match err {
+        LockchainError::Io(err) => LockchainError::InvalidConfig(format!(
+            "cannot read seal key {} ({err}); `lockchain config seal` creates it",
+            path.display()
+        )),
+        other => other,
+    })?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(KEY_CONTEXT);
+    hasher.update(&secret[..]);
+    Ok(SecretBuffer::from_slice(&hasher.finalize()))
+}
+
+/// Read the seal key, refusing one that is writable by its group or that
+/// anybody outside it may read.
+fn read_seal_key(path: &Path) -> LockchainResult<Zeroizing<Vec<u8>>> {
+    let mode = fs::metadata(path)?.permissions().mode();
+    if mode & 0o037 != 0 {
+        return Err(LockchainError::InvalidConfig(format!(
+            "seal key {} has mode {:o}; chmod 0440 it",
+            path.display(),
+            mode & 0o777
+        )));
+    }
+    let secret = Zeroizing::new(fs::read(path)?);
+    if secret.len() != SEAL_KEY_LEN {
+        return Err(LockchainError::InvalidConfig(format!(
+            "seal key {} holds {} bytes, expected {SEAL_KEY_LEN}",
+            path.display(),
+            secret.len()
+        )));
+    }
+    Ok(secret)
+}
+
+/// Derive the `sealed:v1:` key from the host's machine-id.
+fn machine_key() -> LockchainResult<SecretBuffer> {
+    let path = env::var_os(MACHINE_ID_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_MACHINE_ID_PATH));
+    let machine_id = Zeroizing::new(fs::read_to_string(&path).map_err(|err| {
+        LockchainError::InvalidConfig(format!(
+            "cannot read machine id from {} ({err})",
+            path.display()
+        ))
+    })?);
+    let machine_id = machine_id.trim();
+    if machine_id.is_empty() {
+        return Err(LockchainError::InvalidConfig(format!(
+            "machine id at {} is empty",
+            path.display()
+        )));
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(LEGACY_KEY_CONTEXT);
+    hasher.update(machine_id.as_bytes());
+    Ok(SecretBuffer::from_slice(&hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn own_gid(dir: &Path) -> u32 {
+        fs::metadata(dir).unwrap().gid()
+    }
+
+    fn leftovers(dir: &Path) -> Vec<String> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with(".lockchain-seal-"))
+            .collect()
+    }
+
+    #[test]
+    fn new_keys_are_random_and_readable_by_owner_and_group_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("etc").join("seal.key");
+        ensure_seal_key_at(&path, None).unwrap();
+        let meta = fs::metadata(&path).unwrap();
+        assert_eq!(meta.len(), SEAL_KEY_LEN as u64);
+        assert_eq!(meta.mode() & 0o777, 0o400);
+
+        let grouped = dir.path().join("grouped.key");
+        let gid = own_gid(dir.path());
+        ensure_seal_key_at(&grouped, Some(gid)).unwrap();
+        let meta = fs::metadata(&grouped).unwrap();
+        assert_eq!((meta.mode() & 0o777, meta.gid()), (0o440, gid));
+        assert_ne!(fs::read(&path).unwrap(), fs::read(&grouped).unwrap());
+        assert!(read_seal_key(&grouped).is_ok());
+        assert!(leftovers(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn existing_keys_keep_their_bytes_but_get_the_requested_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("seal.key");
+        fs::write(&path, [9u8; SEAL_KEY_LEN]).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(read_seal_key(&path).is_err());
+
+        ensure_seal_key_at(&path, Some(own_gid(dir.path()))).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().mode() & 0o777, 0o440);
+        assert_eq!(&read_seal_key(&path).unwrap()[..], &[9u8; SEAL_KEY_LEN]);
+    }
+
+    #[test]
+    fn short_keys_left_by_a_failed_write_are_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("seal.key");
+        fs::write(&path, b"").unwrap();
+        assert!(read_seal_key(&path).is_err());
+
+        ensure_seal_key_at(&path, None).unwrap();
+        assert_eq!(read_seal_key(&path).unwrap().len(), SEAL_KEY_LEN);
+        assert_eq!(fs::metadata(&path).unwrap().mode() & 0o777, 0o400);
+        assert!(leftovers(dir.path()).is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+//! Page-locked, non-dumpable buffers for key material and derived secrets.
+
+use log::warn;
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, Ordering};
+use zeroize::Zeroize;
+
+static MLOCK_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Fixed-size byte buffer that is `mlock`ed, excluded from core dumps, and zeroized on drop.
+///
+/// The backing allocation is page aligned so the kernel hints cover exactly the
+/// pages holding the secret. Failure to lock (e.g. a low `RLIMIT_MEMLOCK`) is
+/// tolerated and reported once; the buffer is still wiped on drop.
+pub struct SecretBuffer {
+    ptr: NonNull<u8>,
+    len: usize,
+    layout: Layout,
+    locked: bool,
+}
+
+// SAFETY: the buffer owns its allocation exclusively and exposes it only via
+// `&self`/`&mut self` borrows, exactly like `Box<[u8]>`.
+unsafe impl Send for SecretBuffer {}
+unsafe impl Sync for SecretBuffer {}
+
+impl SecretBuffer {
+    /// Allocate a zero-filled buffer of `len` bytes.
+    pub fn zeroed(len: usize) -> Self {
+        let page = page_size();
+        let size = len.max(1).div_ceil(page) * page;
+        let layout = Layout::from_size_align(size, page).expect("valid secret buffer layout");
+        // SAFETY: `layout` has a non-zero size.
+        let raw = unsafe { alloc::alloc_zeroed(layout) };
+        let ptr = NonNull::new(raw).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+
+        // SAFETY: `ptr`/`size` describe the page-aligned allocation made above.
+        let locked = unsafe { libc::mlock(ptr.as_ptr().cast(), size) } == 0;
+        if !locked && !MLOCK_WARNED.swap(true, Ordering::Relaxed) {
+            warn!("mlock failed for secret buffer; key material may be swapped to disk");
+        }
+        // SAFETY: as above; MADV_DONTDUMP only needs a page-aligned range.
+        unsafe {
+            libc::madvise(ptr.as_ptr().cast(), size, libc::MADV_DONTDUMP);
+        }
+
+        Self {
+            ptr,
+            len,
+            layout,
+            locked,
+        }
+    }
+
+    /// Copy `bytes` into a new protected buffer.
+    pub fn from_slice(bytes: &[u8]) -> Self {
+        let mut buffer = Self::zeroed(bytes.len());
+        buffer.copy_from_slice(bytes);
+        buffer
+    }
+
+    /// Whether the pages backing this buffer are pinned in RAM.
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+}
+
+impl Deref for SecretBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: `len` never exceeds the allocation and the memory is initialised.
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl DerefMut for SecretBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // SAFETY: as in `deref`, with exclusive access through `&mut self`.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl AsRef<[u8]> for SecretBuffer {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Clone for SecretBuffer {
+    fn clone(&self) -> Self {
+        Self::from_slice(self)
+    }
+}
+
+impl fmt::Debug for SecretBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretBuffer({} bytes, redacted)", self.len)
+    }
+}
+
+impl Drop for SecretBuffer {
+    fn drop(&mut self) {
+        let size = self.layout.size();
+        // SAFETY: the whole allocation is owned by `self` and still live.
+        unsafe {
+            std::slice::from_raw_parts_mut(self.ptr.as_ptr(), size).zeroize();
+            if self.locked {
+                libc::munlock(self.ptr.as_ptr().cast(), size);
+            }
+            libc::madvise(self.ptr.as_ptr().cast(), size, libc::MADV_DODUMP);
+            alloc::dealloc(self.ptr.as_ptr(), self.layout);
+        }
+    }
+}
+
+fn page_size() -> usize {
+    // SAFETY: sysconf has no preconditions.
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 {
+        size as usize
+    } else {
+        4096
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_is_page_aligned_and_sized() {
+        let buffer = SecretBuffer::from_slice(&[0xAB; 32]);
+        assert_eq!(buffer.len(), 32);
+        assert_eq!(&buffer[..], &[0xAB; 32]);
+        assert_eq!(buffer.as_ptr() as usize % page_size(), 0);
+
+        let empty = SecretBuffer::zeroed(0);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn debug_output_is_redacted() {
+        let buffer = SecretBuffer::from_slice(b"super-secret");
+        let rendered = format!("{buffer:?}");
+        assert!(!rendered.contains("super-secret"));
+        assert!(rendered.contains("12 bytes"));
+    }
+}
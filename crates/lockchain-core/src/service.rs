@@ -1,105 +1,349 @@
 //! High-level unlock service that coordinates config, providers, and key sources.
 
-use crate::config::LockchainConfig;
+use crate::audit::{AuditEntry, AuditEvent, AuditLog};
+use crate::cache::KeyCache;
+use crate::config::{KeySource, LockchainConfig};
 use crate::error::{LockchainError, LockchainResult};
-use crate::keyfile::{read_key_file, write_raw_key_file};
-use crate::provider::{KeyStatusSnapshot, ZfsProvider};
-use hex::FromHex;
+use crate::hooks::{HookContext, HookEvent, HookRegistry};
+use crate::keyfile::{
+    decode_key_bytes, read_key_file, verify_key_source, write_raw_key_file, KeyVerification,
+};
+use crate::provider::{KeyState, KeyStatusSnapshot, ZfsProvider};
+use crate::secret::SecretBuffer;
+use crate::vault;
+use chrono::{Local, NaiveTime};
 use log::warn;
-use pbkdf2::pbkdf2_hmac;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::cmp::min;
-use std::path::Path;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread::sleep;
-use std::time::Duration;
-use zeroize::Zeroizing;
+use std::time::{Duration, Instant};
 
 /// Options that tune the unlock workflow.
 #[derive(Debug, Clone, Default)]
 pub struct UnlockOptions {
     pub strict_usb: bool,
     pub fallback_passphrase: Option<String>,
-    pub key_override: Option<Vec<u8>>,
+    /// Key to load instead of resolving one from the configured sources.
+    pub key_override: Option<SecretBuffer>,
+    /// Mount the unlocked filesystems once their keys are loaded.
+    pub mount: bool,
+}
+
+/// Key material an operator hands over directly, e.g. to
+/// `lockchain-daemon --remote-unlock`, instead of it being read from the token.
+#[derive(Debug, Clone)]
+pub enum SuppliedKey {
+    /// The fallback passphrase, run through the `[fallback]` derivation.
+    Passphrase(SecretBuffer),
+    /// The key itself, raw or as 64 hex digits.
+    Key(SecretBuffer),
+}
+
+impl SuppliedKey {
+    fn kind(&self) -> &'static str {
+        match self {
+            SuppliedKey::Passphrase(_) => "passphrase",
+            SuppliedKey::Key(_) => "key",
+        }
+    }
 }
 
 /// Result of an unlock attempt.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct UnlockReport {
     pub dataset: String,
     pub encryption_root: String,
     pub unlocked: Vec<String>,
     pub already_unlocked: bool,
+    /// Filesystems mounted after the unlock when [`UnlockOptions::mount`] was set.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mounted: Vec<String>,
+}
+
+/// Options that tune the lock workflow.
+#[derive(Debug, Clone, Default)]
+pub struct LockOptions {
+    pub unmount: bool,
+}
+
+/// A failed unlock attempt that is about to be retried.
+#[derive(Debug)]
+pub struct RetryAttempt<'a> {
+    pub dataset: &'a str,
+    /// 1-based number of the attempt that failed.
+    pub attempt: u32,
+    pub max_attempts: u32,
+    /// Backoff before the next attempt.
+    pub delay: Duration,
+    pub error: &'a LockchainError,
+}
+
+/// Result of a lock attempt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockReport {
+    pub dataset: String,
+    pub encryption_root: String,
+    pub locked: Vec<String>,
+    pub already_locked: bool,
 }
 
 /// Current key status for a dataset and its encryption root.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DatasetStatus {
     pub dataset: String,
     pub encryption_root: String,
     pub root_locked: bool,
     pub locked_descendants: Vec<String>,
+    /// Access columns of `dataset` itself, as in [`DatasetKeyDescriptor`].
+    #[serde(default)]
+    pub mounted: Option<bool>,
+    #[serde(default)]
+    pub keyformat: Option<String>,
+    #[serde(default)]
+    pub keylocation: Option<String>,
+}
+
+/// Keystatus for one encryption root and every dataset sharing its key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootStatus {
+    pub encryption_root: String,
+    /// Datasets from `policy.datasets` protected by this root.
+    pub datasets: Vec<String>,
+    /// Root first, then descendants sorted by name.
+    pub tree: KeyStatusSnapshot,
+}
+
+impl RootStatus {
+    /// Whether the encryption root itself is sealed.
+    pub fn root_locked(&self) -> bool {
+        self.tree.iter().any(|entry| {
+            entry.dataset == self.encryption_root && !matches!(entry.state, KeyState::Available)
+        })
+    }
+
+    /// Sealed datasets below the encryption root.
+    pub fn locked_descendants(&self) -> Vec<String> {
+        self.tree
+            .iter()
+            .filter(|entry| entry.dataset != self.encryption_root)
+            .filter(|entry| !matches!(entry.state, KeyState::Available))
+            .map(|entry| entry.dataset.clone())
+            .collect()
+    }
+
+    /// Per-dataset view of this root for `dataset`.
+    pub fn dataset_status(&self, dataset: &str) -> DatasetStatus {
+        let entry = self.tree.iter().find(|entry| entry.dataset == dataset);
+        DatasetStatus {
+            dataset: dataset.to_string(),
+            encryption_root: self.encryption_root.clone(),
+            root_locked: self.root_locked(),
+            locked_descendants: self.locked_descendants(),
+            mounted: entry.and_then(|entry| entry.mounted),
+            keyformat: entry.and_then(|entry| entry.keyformat.clone()),
+            keylocation: entry.and_then(|entry| entry.keylocation.clone()),
+        }
+    }
+}
+
+/// Keystatus for every managed dataset, grouped by encryption root.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EstateStatus {
+    /// Roots in the order their first dataset appears in `policy.datasets`.
+    pub roots: Vec<RootStatus>,
+}
+
+impl EstateStatus {
+    /// Per-dataset view matching [`LockchainService::status`].
+    pub fn dataset_status(&self, dataset: &str) -> Option<DatasetStatus> {
+        let root = self
+            .roots
+            .iter()
+            .find(|root| root.datasets.iter().any(|ds| ds == dataset))?;
+        Some(root.dataset_status(dataset))
+    }
+
+    /// Encryption roots whose key is not loaded.
+    pub fn locked_roots(&self) -> impl Iterator<Item = &RootStatus> {
+        self.roots.iter().filter(|root| root.root_locked())
+    }
 }
 
+/// Reads the key at a path somewhere else, e.g. through a process that may
+/// open key files when this one may not.
+pub type KeyReader = Arc<dyn Fn(&Path) -> LockchainResult<SecretBuffer> + Send + Sync>;
+
 /// Coordinates configuration, providers, and key sources to unlock datasets.
 pub struct LockchainService<P: ZfsProvider> {
     config: Arc<LockchainConfig>,
     provider: P,
+    key_cache: Option<KeyCache>,
+    hooks: HookRegistry,
+    audit: AuditLog,
+    key_reader: Option<KeyReader>,
 }
 
 impl<P: ZfsProvider> LockchainService<P> {
     /// Build a service with shared configuration and a concrete provider implementation.
     pub fn new(config: Arc<LockchainConfig>, provider: P) -> Self {
-        Self { config, provider }
+        let key_cache = match config.usb.key_cache_ttl {
+            ttl if ttl.is_zero() => None,
+            ttl => Some(KeyCache::new(ttl)),
+        };
+        let hooks = HookRegistry::from_config(&config.hooks);
+        let audit = AuditLog::new(&config.audit);
+        Self {
+            config,
+            provider,
+            key_cache,
+            hooks,
+            audit,
+            key_reader: None,
+        }
+    }
+
+    /// Read key files through `reader` instead of opening them here; hex
+    /// keys are then left as they are.
+    pub fn with_key_reader(mut self, reader: KeyReader) -> Self {
+        self.key_reader = Some(reader);
+        self
+    }
+
+    /// Write the audit trail through `audit` instead of the writer built
+    /// from `[audit]`.
+    pub fn with_audit_log(mut self, audit: AuditLog) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Audit trail writer shared by service operations and workflows.
+    pub fn audit_log(&self) -> &AuditLog {
+        &self.audit
+    }
+
+    /// Provider the service drives, e.g. to reuse it for a reloaded config.
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// Register an in-process callback that fires around unlock/lock operations.
+    ///
+    /// `pre_*` hooks can veto the operation by returning an error; failures in
+    /// `post_*` hooks are logged because the key state has already changed.
+    pub fn register_hook<F>(&mut self, event: HookEvent, hook: F)
+    where
+        F: Fn(&HookContext) -> LockchainResult<()> + Send + Sync + 'static,
+    {
+        self.hooks.register(event, hook);
+    }
+
+    /// Drop any cached key material, forcing the next unlock to re-read the token.
+    pub fn invalidate_key_cache(&self) {
+        if let Some(cache) = &self.key_cache {
+            cache.clear();
+        }
     }
 
     /// Attempt to unlock `dataset` once, returning a report of what changed.
     pub fn unlock(&self, dataset: &str, options: UnlockOptions) -> LockchainResult<UnlockReport> {
-        self.perform_unlock(dataset, options)
+        let started = Instant::now();
+        let mut key_source = None;
+        let result = self.perform_unlock(dataset, options, &mut key_source);
+        self.audit_unlock(dataset, &result, key_source, started.elapsed());
+        result
     }
 
-    /// Unlock `dataset` with exponential backoff guided by retry policy.
+    /// Unlock `dataset` once with key material an operator supplied, held to
+    /// the same checks as the token: the key must match the dataset's pinned
+    /// checksum, and a passphrase is only taken when `[fallback]` is enabled
+    /// and `policy.require_strict_usb` is off. `origin` says where it came
+    /// from in the audit trail.
+    pub fn unlock_supplied(
+        &self,
+        dataset: &str,
+        supplied: &SuppliedKey,
+        origin: &str,
+    ) -> LockchainResult<UnlockReport> {
+        let started = Instant::now();
+        let result = self.supplied_key(dataset, supplied).and_then(|key| {
+            let options = UnlockOptions {
+                key_override: Some(key),
+                ..UnlockOptions::default()
+            };
+            self.perform_unlock(dataset, options, &mut None)
+        });
+        let key_source = format!("{}:{origin}", supplied.kind());
+        self.audit_unlock(dataset, &result, Some(key_source), started.elapsed());
+        result
+    }
+
+    /// Unlock `dataset`, backing off between attempts as `[retry]` prescribes.
     pub fn unlock_with_retry(
         &self,
         dataset: &str,
         options: UnlockOptions,
     ) -> LockchainResult<UnlockReport> {
+        self.unlock_with_retry_observed(dataset, options, |_| {})
+    }
+
+    /// Like [`Self::unlock_with_retry`], calling `on_retry` after every failed
+    /// attempt that will be retried, just before the backoff sleep.
+    pub fn unlock_with_retry_observed<F>(
+        &self,
+        dataset: &str,
+        options: UnlockOptions,
+        mut on_retry: F,
+    ) -> LockchainResult<UnlockReport>
+    where
+        F: FnMut(&RetryAttempt<'_>),
+    {
+        let started = Instant::now();
         let policy = &self.config.retry;
+        let mut rng = rand::thread_rng();
         let mut attempt: u32 = 0;
-        let mut delay_ms = policy.base_delay_ms.max(1);
+        let mut key_source = None;
 
-        loop {
+        let result = loop {
             attempt += 1;
-            match self.perform_unlock(dataset, options.clone()) {
-                Ok(report) => return Ok(report),
+            match self.perform_unlock(dataset, options.clone(), &mut key_source) {
+                Ok(report) => break Ok(report),
+                Err(err @ LockchainError::PolicyViolation(_)) => break Err(err),
                 Err(err) => {
                     if attempt >= policy.max_attempts {
-                        return Err(LockchainError::RetryExhausted {
+                        break Err(LockchainError::RetryExhausted {
                             attempts: attempt,
                             last_error: err.to_string(),
                         });
                     }
 
-                    let jitter_ms = if policy.jitter_ratio > 0.0 {
-                        let pseudo = ((attempt * 37) % 100) as f64 / 100.0 - 0.5;
-                        let factor = 1.0 + (policy.jitter_ratio * pseudo);
-                        ((delay_ms as f64 * factor).max(1.0)).round() as u64
-                    } else {
-                        delay_ms
-                    };
-
-                    sleep(Duration::from_millis(jitter_ms));
-                    delay_ms = min(delay_ms.saturating_mul(2), policy.max_delay_ms.max(1));
+                    let delay = policy.delay_for(attempt, &mut rng);
+                    on_retry(&RetryAttempt {
+                        dataset,
+                        attempt,
+                        max_attempts: policy.max_attempts,
+                        delay,
+                        error: &err,
+                    });
+                    sleep(delay);
                 }
             }
-        }
+        };
+
+        self.audit_unlock(dataset, &result, key_source, started.elapsed());
+        result
     }
 
     /// Internal helper shared by the eager and retrying unlock paths.
+    ///
+    /// `key_source` is set to a description of the key material used, if any.
     fn perform_unlock(
         &self,
         dataset: &str,
         options: UnlockOptions,
+        key_source: &mut Option<String>,
     ) -> LockchainResult<UnlockReport> {
         if !self.config.contains_dataset(dataset) {
             return Err(LockchainError::DatasetNotConfigured(dataset.to_string()));
@@ -113,10 +357,16 @@ impl<P: ZfsProvider> LockchainService<P> {
                 encryption_root: root,
                 unlocked: Vec::new(),
                 already_unlocked: true,
+                mounted: Vec::new(),
             });
         }
 
-        let key = self.key_material(dataset, &options)?;
+        self.check_unlock_policy(Local::now().time())?;
+
+        self.hooks
+            .run(&hook_context(HookEvent::PreUnlock, dataset, &root, &[]))?;
+
+        let key = self.key_material(dataset, &root, &options, key_source)?;
         let unlocked = self.provider.load_key_tree(&root, &key)?;
 
         let locked_after = self.provider.locked_descendants(&root)?;
@@ -127,33 +377,208 @@ impl<P: ZfsProvider> LockchainService<P> {
             )));
         }
 
+        self.run_post_hook(HookEvent::PostUnlock, dataset, &root, &unlocked);
+
+        let mounted = if options.mount {
+            self.mount_unlocked(&unlocked)
+        } else {
+            Vec::new()
+        };
+
         Ok(UnlockReport {
             dataset: dataset.to_string(),
             encryption_root: root,
             unlocked,
             already_unlocked: false,
+            mounted,
         })
     }
 
-    /// Summarise the current keystatus for `dataset` and its encryption root.
-    pub fn status(&self, dataset: &str) -> LockchainResult<DatasetStatus> {
+    /// Mount whichever of `unlocked` are filesystems not yet mounted. The keys
+    /// are already loaded, so failures are logged rather than returned.
+    fn mount_unlocked(&self, unlocked: &[String]) -> Vec<String> {
+        let snapshot = match self.provider.describe_datasets(unlocked) {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                warn!("cannot read mount state after unlock: {err}");
+                return Vec::new();
+            }
+        };
+        let mut mounted = Vec::new();
+        for entry in snapshot {
+            if entry.mounted != Some(false) {
+                continue;
+            }
+            match self.provider.mount_dataset(&entry.dataset) {
+                Ok(()) => mounted.push(entry.dataset),
+                Err(err) => warn!("mount {} failed: {err}", entry.dataset),
+            }
+        }
+        mounted
+    }
+
+    /// Unload the key protecting `dataset` (and its encryption root's tree).
+    pub fn lock(&self, dataset: &str, options: LockOptions) -> LockchainResult<LockReport> {
+        let result = self.perform_lock(dataset, options);
+        if !matches!(&result, Ok(report) if report.already_locked) {
+            self.audit
+                .record_or_warn(AuditEntry::from_result(AuditEvent::Lock, dataset, &result));
+        }
+        result
+    }
+
+    /// Derive the emergency key for `dataset` from the fallback passphrase and audit the use.
+    pub fn break_glass(&self, dataset: &str, passphrase: &[u8]) -> LockchainResult<SecretBuffer> {
+        let result = self.derive_fallback_key(passphrase);
+        self.audit.record_or_warn(
+            AuditEntry::from_result(AuditEvent::Breakglass, dataset, &result)
+                .with_key_source(Some("fallback-passphrase".to_string())),
+        );
+        result
+    }
+
+    /// Refuse unlocks outside `policy.unlock_window`.
+    fn check_unlock_policy(&self, now: NaiveTime) -> LockchainResult<()> {
+        let window = self.config.policy.unlock_window()?;
+        if let Some(window) = window {
+            if !window.contains(now) {
+                return Err(LockchainError::PolicyViolation(format!(
+                    "{} is outside policy.unlock_window {}-{}",
+                    now.format("%H:%M"),
+                    window.start.format("%H:%M"),
+                    window.end.format("%H:%M")
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Lock implementation; `lock` wraps it with auditing.
+    fn perform_lock(&self, dataset: &str, options: LockOptions) -> LockchainResult<LockReport> {
         if !self.config.contains_dataset(dataset) {
             return Err(LockchainError::DatasetNotConfigured(dataset.to_string()));
         }
 
         let root = self.provider.encryption_root(dataset)?;
-        let locked = self.provider.locked_descendants(&root)?;
-        let root_locked = locked.iter().any(|ds| ds == &root);
-        let locked_descendants: Vec<String> = locked.into_iter().filter(|ds| ds != &root).collect();
+        let locked_before = self.provider.locked_descendants(&root)?;
+        if locked_before.iter().any(|ds| ds == &root) {
+            return Ok(LockReport {
+                dataset: dataset.to_string(),
+                encryption_root: root,
+                locked: Vec::new(),
+                already_locked: true,
+            });
+        }
+
+        self.hooks
+            .run(&hook_context(HookEvent::PreLock, dataset, &root, &[]))?;
+
+        let locked = self.provider.unload_key_tree(&root, options.unmount)?;
 
-        Ok(DatasetStatus {
+        let locked_after = self.provider.locked_descendants(&root)?;
+        if !locked_after.iter().any(|ds| ds == &root) {
+            return Err(LockchainError::Provider(format!(
+                "encryption root {} still unlocked after unload-key",
+                root
+            )));
+        }
+
+        self.run_post_hook(HookEvent::PostLock, dataset, &root, &locked);
+
+        Ok(LockReport {
             dataset: dataset.to_string(),
             encryption_root: root,
-            root_locked,
-            locked_descendants,
+            locked,
+            already_locked: false,
         })
     }
 
+    /// Append an unlock record, skipping no-op checks where nothing was locked.
+    fn audit_unlock(
+        &self,
+        dataset: &str,
+        result: &LockchainResult<UnlockReport>,
+        key_source: Option<String>,
+        elapsed: Duration,
+    ) {
+        if matches!(result, Ok(report) if report.already_unlocked) {
+            return;
+        }
+        self.audit.record_or_warn(
+            AuditEntry::from_result(AuditEvent::Unlock, dataset, result)
+                .with_key_source(key_source)
+                .with_duration(elapsed),
+        );
+    }
+
+    /// Fire a post-operation hook; the key state already changed, so only warn on failure.
+    fn run_post_hook(&self, event: HookEvent, dataset: &str, root: &str, datasets: &[String]) {
+        if let Err(err) = self
+            .hooks
+            .run(&hook_context(event, dataset, root, datasets))
+        {
+            warn!("{} hook for {dataset} failed: {err}", event.as_str());
+        }
+    }
+
+    /// Summarise the current keystatus for `dataset` and its encryption root.
+    pub fn status(&self, dataset: &str) -> LockchainResult<DatasetStatus> {
+        if !self.config.contains_dataset(dataset) {
+            return Err(LockchainError::DatasetNotConfigured(dataset.to_string()));
+        }
+
+        let encryption_root = self.provider.encryption_root(dataset)?;
+        let tree = self.provider.key_tree_status(&encryption_root)?;
+        Ok(RootStatus {
+            encryption_root,
+            datasets: vec![dataset.to_string()],
+            tree,
+        }
+        .dataset_status(dataset))
+    }
+
+    /// Keystatus for every dataset in the policy, querying each encryption root once.
+    ///
+    /// Roots are resolved and their trees queried on a small pool of threads so
+    /// large estates do not pay for one `zfs` round trip after another.
+    pub fn status_all(&self) -> LockchainResult<EstateStatus>
+    where
+        P: Sync,
+    {
+        let datasets = &self.config.policy.datasets;
+        let roots = parallel_map(datasets, |ds| self.provider.encryption_root(ds))?;
+
+        let mut grouped: Vec<(String, Vec<String>)> = Vec::new();
+        for (dataset, root) in datasets.iter().zip(roots) {
+            match grouped.iter_mut().find(|(name, _)| *name == root) {
+                Some((_, members)) => members.push(dataset.clone()),
+                None => grouped.push((root, vec![dataset.clone()])),
+            }
+        }
+
+        let trees = parallel_map(&grouped, |(root, _)| self.provider.key_tree_status(root))?;
+        Ok(EstateStatus {
+            roots: grouped
+                .into_iter()
+                .zip(trees)
+                .map(|((encryption_root, datasets), tree)| RootStatus {
+                    encryption_root,
+                    datasets,
+                    tree,
+                })
+                .collect(),
+        })
+    }
+
+    /// Check every configured key file without modifying it or the config.
+    pub fn verify_key_material(&self) -> Vec<KeyVerification> {
+        self.config
+            .key_sources()
+            .iter()
+            .map(verify_key_source)
+            .collect()
+    }
+
     /// Pull keystatus for every dataset declared in the policy.
     pub fn list_keys(&self) -> LockchainResult<KeyStatusSnapshot> {
         self.provider
@@ -164,16 +589,50 @@ impl<P: ZfsProvider> LockchainService<P> {
     fn key_material(
         &self,
         dataset: &str,
+        encryption_root: &str,
         options: &UnlockOptions,
-    ) -> LockchainResult<Zeroizing<Vec<u8>>> {
-        if let Some(raw) = &options.key_override {
-            return Ok(Zeroizing::new(raw.clone()));
+        key_source: &mut Option<String>,
+    ) -> LockchainResult<SecretBuffer> {
+        if let Some(key) = &options.key_override {
+            *key_source = Some("override".to_string());
+            return Ok(key.clone());
+        }
+
+        if let Some(vault) = self.config.vault_for(dataset, encryption_root) {
+            let label = format!("vault:{}", vault.describe());
+            *key_source = Some(label.clone());
+            let cache_key = PathBuf::from(label);
+            if let Some(key) = self
+                .key_cache
+                .as_ref()
+                .and_then(|cache| cache.get(&cache_key))
+            {
+                return Ok(key);
+            }
+            let key = vault::fetch_key(vault)?;
+            if let Some(cache) = &self.key_cache {
+                cache.insert(&cache_key, &key);
+            }
+            return Ok(key);
+        }
+
+        let source = self.config.key_source(dataset, encryption_root);
+        if let Some(key) = self
+            .key_cache
+            .as_ref()
+            .and_then(|cache| cache.get(&source.path))
+        {
+            *key_source = Some(format!("cache:{}", source.path.display()));
+            return Ok(key);
         }
 
-        let usb_key_path = self.config.key_hex_path();
-        match self.load_usb_key(&usb_key_path) {
+        *key_source = Some(format!("usb:{}", source.path.display()));
+        match self.load_usb_key(&source.path) {
             Ok(key) => {
-                self.verify_checksum(&key)?;
+                self.verify_checksum(&key, &source)?;
+                if let Some(cache) = &self.key_cache {
+                    cache.insert(&source.path, &key);
+                }
                 return Ok(key);
             }
             Err(err) => {
@@ -183,7 +642,14 @@ impl<P: ZfsProvider> LockchainService<P> {
                     LockchainError::Io(io_err) if io_err.kind() == std::io::ErrorKind::NotFound
                 );
 
-                if !io_error || options.strict_usb || !self.config.fallback.enabled {
+                let fallback_allowed =
+                    io_error && !options.strict_usb && self.config.fallback.enabled;
+                if fallback_allowed && self.config.policy.require_strict_usb {
+                    return Err(LockchainError::PolicyViolation(format!(
+                        "policy.require_strict_usb forbids passphrase fallback for {dataset} ({err})"
+                    )));
+                }
+                if !fallback_allowed {
                     return Err(if missing {
                         LockchainError::MissingKeySource(dataset.to_string())
                     } else {
@@ -198,13 +664,44 @@ impl<P: ZfsProvider> LockchainService<P> {
             .as_ref()
             .ok_or_else(|| LockchainError::MissingKeySource(dataset.to_string()))?;
 
-        let passphrase = Zeroizing::new(passphrase.clone().into_bytes());
+        *key_source = Some("fallback-passphrase".to_string());
+        let passphrase = SecretBuffer::from_slice(passphrase.as_bytes());
         let key = self.derive_fallback_key(&passphrase)?;
         Ok(key)
     }
 
+    /// Turn supplied material into the key for `dataset`, refusing what the
+    /// token's own key would not pass.
+    fn supplied_key(&self, dataset: &str, supplied: &SuppliedKey) -> LockchainResult<SecretBuffer> {
+        if !self.config.contains_dataset(dataset) {
+            return Err(LockchainError::DatasetNotConfigured(dataset.to_string()));
+        }
+        let key = match supplied {
+            SuppliedKey::Key(bytes) => decode_key_bytes(Path::new("supplied key"), bytes)?.0,
+            SuppliedKey::Passphrase(passphrase) => {
+                if !self.config.fallback.enabled {
+                    return Err(LockchainError::PolicyViolation(format!(
+                        "fallback.enabled is off; passphrase unlock of {dataset} refused"
+                    )));
+                }
+                if self.config.policy.require_strict_usb {
+                    return Err(LockchainError::PolicyViolation(format!(
+                        "policy.require_strict_usb forbids passphrase fallback for {dataset}"
+                    )));
+                }
+                self.derive_fallback_key(passphrase)?
+            }
+        };
+        let root = self.provider.encryption_root(dataset)?;
+        self.verify_checksum(&key, &self.config.key_source(dataset, &root))?;
+        Ok(key)
+    }
+
     /// Read and normalise key material stored on disk.
-    fn load_usb_key(&self, path: &Path) -> LockchainResult<Zeroizing<Vec<u8>>> {
+    fn load_usb_key(&self, path: &Path) -> LockchainResult<SecretBuffer> {
+        if let Some(reader) = &self.key_reader {
+            return reader(path);
+        }
         let (key, converted) = read_key_file(path)?;
         if converted {
             write_raw_key_file(path, &key)?;
@@ -213,72 +710,89 @@ impl<P: ZfsProvider> LockchainService<P> {
     }
 
     /// Make sure the loaded key matches the expected checksum when configured.
-    fn verify_checksum(&self, key: &[u8]) -> LockchainResult<()> {
-        if let Some(expected) = &self.config.usb.expected_sha256 {
+    fn verify_checksum(&self, key: &[u8], source: &KeySource) -> LockchainResult<()> {
+        if let Some(expected) = &source.expected_sha256 {
             let digest = Sha256::digest(key);
             let actual = hex::encode(digest);
             if !expected.eq_ignore_ascii_case(&actual) {
                 return Err(LockchainError::InvalidConfig(format!(
-                    "usb.expected_sha256 mismatch: expected {}, got {}",
-                    expected, actual
+                    "{} mismatch: expected {}, got {}",
+                    source.setting(),
+                    expected,
+                    actual
                 )));
             }
         } else {
-            warn!("usb.expected_sha256 not configured; skipping checksum verification");
+            warn!(
+                "{} not configured; skipping checksum verification for {}",
+                source.setting(),
+                source.path.display()
+            );
         }
         Ok(())
     }
 
     /// Derive the fallback key using the configured PBKDF2 parameters and mask.
-    pub fn derive_fallback_key(&self, passphrase: &[u8]) -> LockchainResult<Zeroizing<Vec<u8>>> {
-        let fallback = &self.config.fallback;
-        let salt_hex = fallback.passphrase_salt.as_ref().ok_or_else(|| {
-            LockchainError::InvalidConfig("fallback.passphrase_salt missing".into())
-        })?;
-        let xor_hex = fallback.passphrase_xor.as_ref().ok_or_else(|| {
-            LockchainError::InvalidConfig("fallback.passphrase_xor missing".into())
-        })?;
-
-        let salt = Vec::from_hex(salt_hex).map_err(|err| {
-            LockchainError::InvalidConfig(format!("invalid fallback.passphrase_salt: {}", err))
-        })?;
-        let cipher = Vec::from_hex(xor_hex).map_err(|err| {
-            LockchainError::InvalidConfig(format!("invalid fallback.passphrase_xor: {}", err))
-        })?;
-
-        if cipher.len() != 32 {
-            return Err(LockchainError::InvalidConfig(format!(
-                "fallback.passphrase_xor length must be 32 bytes, got {}",
-                cipher.len()
-            )));
-        }
-
-        let iterations = fallback.passphrase_iters.max(1);
-        let mut derived = Zeroizing::new(vec![0u8; cipher.len()]);
-        pbkdf2_hmac::<Sha256>(passphrase, &salt, iterations, &mut derived);
+    pub fn derive_fallback_key(&self, passphrase: &[u8]) -> LockchainResult<SecretBuffer> {
+        self.config.fallback.derive_key(passphrase)
+    }
+}
 
-        let raw: Vec<u8> = cipher
-            .iter()
-            .zip(derived.iter())
-            .map(|(c, d)| c ^ d)
-            .collect();
+/// Assemble the context passed to hooks for a dataset operation.
+fn hook_context(event: HookEvent, dataset: &str, root: &str, datasets: &[String]) -> HookContext {
+    HookContext {
+        event,
+        dataset: dataset.to_string(),
+        encryption_root: root.to_string(),
+        datasets: datasets.to_vec(),
+    }
+}
 
-        Ok(Zeroizing::new(raw))
+/// Upper bound on concurrent provider queries issued by [`LockchainService::status_all`].
+const STATUS_WORKERS: usize = 8;
+
+/// Apply `f` to every item on a bounded set of scoped threads, preserving order.
+fn parallel_map<T, R, F>(items: &[T], f: F) -> LockchainResult<Vec<R>>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> LockchainResult<R> + Sync,
+{
+    let workers = STATUS_WORKERS.min(items.len());
+    if workers <= 1 {
+        return items.iter().map(&f).collect();
     }
+
+    let next = AtomicUsize::new(0);
+    let slots: Vec<Mutex<Option<LockchainResult<R>>>> =
+        items.iter().map(|_| Mutex::new(None)).collect();
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(idx) else { break };
+                *slots[idx].lock().unwrap() = Some(f(item));
+            });
+        }
+    });
+    slots
+        .into_iter()
+        .map(|slot| slot.into_inner().unwrap().expect("every item is processed"))
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::audit;
     use crate::config::{
-        ConfigFormat, CryptoCfg, Fallback, LockchainConfig, Policy, RetryCfg, Usb,
+        AuditCfg, ConfigFormat, ControlCfg, CryptoCfg, DaemonCfg, DatasetKey, Fallback, HealthCfg,
+        HooksCfg, LockchainConfig, Policy, RetryCfg, TuiCfg, UiCfg, Usb,
     };
     use crate::provider::{DatasetKeyDescriptor, KeyState, KeyStatusSnapshot, ZfsProvider};
-    use std::collections::HashSet;
+    use std::collections::{BTreeMap, HashSet};
     use std::fs;
     use std::os::unix::fs::PermissionsExt;
-    use std::path::PathBuf;
-    use std::sync::Mutex;
     use tempfile::tempdir;
 
     struct MockProvider {
@@ -345,12 +859,20 @@ mod tests {
                     } else {
                         KeyState::Available
                     },
+                    mounted: None,
+                    keyformat: None,
+                    keylocation: None,
                 })
                 .collect())
         }
+
+        fn unload_key_tree(&self, root: &str, _unmount: bool) -> LockchainResult<Vec<String>> {
+            self.locked.lock().unwrap().insert(root.to_string());
+            Ok(vec![root.to_string()])
+        }
     }
 
-    fn base_config(key_path: &PathBuf) -> LockchainConfig {
+    fn base_config(key_path: &Path) -> LockchainConfig {
         LockchainConfig {
             policy: Policy {
                 datasets: vec!["tank/secure".to_string()],
@@ -358,8 +880,12 @@ mod tests {
                 zpool_path: None,
                 binary_path: None,
                 allow_root: false,
+                unlock_window: None,
+                require_strict_usb: false,
+            },
+            crypto: CryptoCfg {
+                timeout: Duration::from_secs(5),
             },
-            crypto: CryptoCfg { timeout_secs: 5 },
             usb: Usb {
                 key_hex_path: key_path.display().to_string(),
                 expected_sha256: None,
@@ -372,10 +898,30 @@ mod tests {
                 passphrase_salt: None,
                 passphrase_xor: None,
                 passphrase_iters: 1,
+                sealed: false,
             },
             retry: RetryCfg::default(),
-            path: key_path.clone(),
+            hooks: HooksCfg::default(),
+            audit: AuditCfg {
+                path: key_path.with_file_name("audit.jsonl").display().to_string(),
+                ..AuditCfg::default()
+            },
+            control: ControlCfg::default(),
+            daemon: DaemonCfg::default(),
+            health: HealthCfg::default(),
+            webhooks: Vec::new(),
+            tui: TuiCfg::default(),
+            ui: UiCfg::default(),
+            vault: None,
+            remote: None,
+            remote_unlock: None,
+            path: key_path.to_path_buf(),
             format: ConfigFormat::Toml,
+            fragments: Vec::new(),
+            profiles: BTreeMap::new(),
+            profile: None,
+            load_issues: Vec::new(),
+            source: None,
         }
     }
 
@@ -405,6 +951,170 @@ mod tests {
         assert_eq!(fs::read(&key_path).unwrap().len(), 32);
     }
 
+    #[test]
+    fn lock_unloads_keys_and_fires_hooks() {
+        let dir = tempdir().unwrap();
+        let key_path = dir.path().join("key.hex");
+        let cfg = Arc::new(base_config(&key_path));
+        let provider = MockProvider::new("tank/secure", &[]);
+        let mut service = LockchainService::new(cfg, provider);
+
+        let fired = Arc::new(Mutex::new(Vec::new()));
+        for event in [HookEvent::PreLock, HookEvent::PostLock] {
+            let sink = fired.clone();
+            service.register_hook(event, move |ctx| {
+                sink.lock().unwrap().push((ctx.event, ctx.datasets.clone()));
+                Ok(())
+            });
+        }
+
+        let report = service.lock("tank/secure", LockOptions::default()).unwrap();
+        assert_eq!(report.locked, vec!["tank/secure".to_string()]);
+        assert_eq!(
+            *fired.lock().unwrap(),
+            vec![
+                (HookEvent::PreLock, Vec::new()),
+                (HookEvent::PostLock, vec!["tank/secure".to_string()]),
+            ]
+        );
+
+        let again = service.lock("tank/secure", LockOptions::default()).unwrap();
+        assert!(again.already_locked);
+    }
+
+    #[test]
+    fn key_operations_are_audited() {
+        let dir = tempdir().unwrap();
+        let key_path = dir.path().join("key.hex");
+        fs::write(
+            &key_path,
+            "00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff",
+        )
+        .unwrap();
+        let cfg = Arc::new(base_config(&key_path));
+        let provider = MockProvider::new("tank/secure", &["tank/secure"]);
+        let service = LockchainService::new(cfg, provider);
+
+        service
+            .unlock("tank/secure", UnlockOptions::default())
+            .unwrap();
+        service
+            .unlock("tank/secure", UnlockOptions::default())
+            .unwrap();
+        service.lock("tank/secure", LockOptions::default()).unwrap();
+
+        let records = audit::read_records(&service.audit_log().path()).unwrap();
+        let events: Vec<_> = records.iter().map(|r| r.entry.event).collect();
+        assert_eq!(events, vec![AuditEvent::Unlock, AuditEvent::Lock]);
+        assert_eq!(
+            records[0].entry.key_source.as_deref(),
+            Some(format!("usb:{}", key_path.display()).as_str())
+        );
+        assert!(audit::verify_chain(&records).is_ok());
+    }
+
+    #[test]
+    fn status_all_groups_datasets_by_root() {
+        let dir = tempdir().unwrap();
+        let mut cfg = base_config(&dir.path().join("key.hex"));
+        cfg.policy.datasets = vec!["tank/secure".into(), "tank/secure/home".into()];
+        let provider = MockProvider::new("tank/secure", &["tank/secure", "tank/secure/home"]);
+        let service = LockchainService::new(Arc::new(cfg), provider);
+
+        let estate = service.status_all().unwrap();
+        assert_eq!(estate.roots.len(), 1);
+        assert_eq!(estate.roots[0].datasets.len(), 2);
+        assert_eq!(estate.locked_roots().count(), 1);
+
+        let home = estate.dataset_status("tank/secure/home").unwrap();
+        assert_eq!(home, service.status("tank/secure/home").unwrap());
+    }
+
+    #[test]
+    fn pre_unlock_hook_can_veto_unlock() {
+        let dir = tempdir().unwrap();
+        let key_path = dir.path().join("key.hex");
+        fs::write(&key_path, [0x33; 32]).unwrap();
+        let provider = MockProvider::new("tank/secure", &["tank/secure"]);
+        let mut service = LockchainService::new(Arc::new(base_config(&key_path)), provider);
+        service.register_hook(HookEvent::PreUnlock, |_| {
+            Err(LockchainError::Hook("maintenance window".into()))
+        });
+
+        let err = service
+            .unlock("tank/secure", UnlockOptions::default())
+            .unwrap_err();
+        assert!(matches!(err, LockchainError::Hook(_)));
+        assert!(service.provider.observed_keys.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn unlock_prefers_per_dataset_key_file() {
+        let dir = tempdir().unwrap();
+        let key_path = dir.path().join("key.hex");
+        fs::write(&key_path, [0x11; 32]).unwrap();
+        fs::write(dir.path().join("secure.key"), [0x22; 32]).unwrap();
+
+        let mut config = base_config(&key_path);
+        config.usb.keys.insert(
+            "tank/secure".into(),
+            DatasetKey::Detailed {
+                path: "secure.key".into(),
+                sha256: Some(hex::encode(Sha256::digest([0x22; 32]))),
+            },
+        );
+        let provider = MockProvider::new("tank/secure", &["tank/secure"]);
+        let service = LockchainService::new(Arc::new(config), provider);
+
+        service
+            .unlock("tank/secure", UnlockOptions::default())
+            .unwrap();
+        assert_eq!(
+            service.provider.observed_keys.lock().unwrap()[0],
+            vec![0x22; 32]
+        );
+    }
+
+    #[test]
+    fn cached_key_survives_token_removal_until_invalidated() {
+        let dir = tempdir().unwrap();
+        let key_path = dir.path().join("key.hex");
+        fs::write(&key_path, [0x5a; 32]).unwrap();
+
+        let mut config = base_config(&key_path);
+        config.usb.key_cache_ttl = Duration::from_secs(300);
+        let provider = MockProvider::new("tank/secure", &["tank/secure"]);
+        let service = LockchainService::new(Arc::new(config), provider);
+
+        service
+            .unlock("tank/secure", UnlockOptions::default())
+            .unwrap();
+        fs::remove_file(&key_path).unwrap();
+
+        service
+            .provider
+            .locked
+            .lock()
+            .unwrap()
+            .insert("tank/secure".into());
+        service
+            .unlock("tank/secure", UnlockOptions::default())
+            .unwrap();
+        assert_eq!(service.provider.observed_keys.lock().unwrap().len(), 2);
+
+        service.invalidate_key_cache();
+        service
+            .provider
+            .locked
+            .lock()
+            .unwrap()
+            .insert("tank/secure".into());
+        let err = service
+            .unlock("tank/secure", UnlockOptions::default())
+            .unwrap_err();
+        assert!(matches!(err, LockchainError::MissingKeySource(_)));
+    }
+
     #[test]
     fn unlock_bails_when_dataset_not_in_policy() {
         let dir = tempdir().unwrap();
@@ -509,6 +1219,27 @@ mod tests {
         assert!(!report.already_unlocked);
     }
 
+    #[test]
+    fn retry_observer_sees_each_failed_attempt() {
+        let dir = tempdir().unwrap();
+        let key_path = dir.path().join("key.hex");
+        fs::write(&key_path, [0x11u8; 32]).unwrap();
+
+        let mut cfg = base_config(&key_path);
+        cfg.retry.base_delay = Duration::from_millis(1);
+        cfg.retry.max_delay = Duration::from_millis(2);
+        let provider = MockProvider::with_failures("tank/secure", &["tank/secure"], 2);
+        let service = LockchainService::new(Arc::new(cfg), provider);
+
+        let mut seen = Vec::new();
+        service
+            .unlock_with_retry_observed("tank/secure", UnlockOptions::default(), |retry| {
+                seen.push((retry.attempt, retry.max_attempts, retry.error.code()));
+            })
+            .unwrap();
+        assert_eq!(seen, vec![(1, 3, "LC2000"), (2, 3, "LC2000")]);
+    }
+
     #[test]
     fn unlock_with_retry_reports_exhaustion() {
         let dir = tempdir().unwrap();
@@ -521,8 +1252,8 @@ mod tests {
 
         let mut cfg = base_config(&key_path);
         cfg.retry.max_attempts = 2;
-        cfg.retry.base_delay_ms = 1;
-        cfg.retry.max_delay_ms = 2;
+        cfg.retry.base_delay = Duration::from_millis(1);
+        cfg.retry.max_delay = Duration::from_millis(2);
         let cfg = Arc::new(cfg);
 
         let provider = MockProvider::with_failures("tank/secure", &["tank/secure"], 5);
@@ -537,4 +1268,101 @@ mod tests {
             other => panic!("unexpected error {other:?}"),
         }
     }
+
+    #[test]
+    fn unlock_window_refuses_outside_hours() {
+        let dir = tempdir().unwrap();
+        let key_path = dir.path().join("key.hex");
+        let mut cfg = base_config(&key_path);
+        cfg.policy.unlock_window = Some("06:00-22:00".into());
+        let provider = MockProvider::new("tank/secure", &["tank/secure"]);
+        let service = LockchainService::new(Arc::new(cfg), provider);
+
+        let noon = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+        let night = NaiveTime::from_hms_opt(23, 30, 0).unwrap();
+        assert!(service.check_unlock_policy(noon).is_ok());
+        let err = service.check_unlock_policy(night).unwrap_err();
+        assert!(matches!(err, LockchainError::PolicyViolation(_)));
+    }
+
+    #[test]
+    fn strict_usb_policy_blocks_passphrase_fallback() {
+        let dir = tempdir().unwrap();
+        let key_path = dir.path().join("missing.key");
+        let mut cfg = base_config(&key_path);
+        cfg.fallback.enabled = true;
+        cfg.policy.require_strict_usb = true;
+        let provider = MockProvider::new("tank/secure", &["tank/secure"]);
+        let service = LockchainService::new(Arc::new(cfg), provider);
+
+        let options = UnlockOptions {
+            fallback_passphrase: Some("correct horse".into()),
+            ..UnlockOptions::default()
+        };
+        let err = service
+            .unlock_with_retry("tank/secure", options)
+            .unwrap_err();
+        assert!(matches!(err, LockchainError::PolicyViolation(_)));
+    }
+
+    #[test]
+    fn supplied_key_must_match_the_pinned_checksum() {
+        let dir = tempdir().unwrap();
+        let key_path = dir.path().join("missing.key");
+        let key = [7u8; 32];
+        let mut cfg = base_config(&key_path);
+        cfg.usb.expected_sha256 = Some(hex::encode(Sha256::digest(key)));
+        let provider = MockProvider::new("tank/secure", &["tank/secure"]);
+        let service = LockchainService::new(Arc::new(cfg), provider);
+
+        let wrong = SuppliedKey::Key(SecretBuffer::from_slice(&[8u8; 32]));
+        let err = service
+            .unlock_supplied("tank/secure", &wrong, "tls:192.0.2.7")
+            .unwrap_err();
+        assert!(matches!(err, LockchainError::InvalidConfig(_)));
+        assert!(service.provider.observed_keys.lock().unwrap().is_empty());
+
+        let hex_key = SuppliedKey::Key(SecretBuffer::from_slice(hex::encode(key).as_bytes()));
+        let report = service
+            .unlock_supplied("tank/secure", &hex_key, "tls:192.0.2.7")
+            .unwrap();
+        assert_eq!(report.unlocked, vec!["tank/secure".to_string()]);
+        assert_eq!(service.provider.observed_keys.lock().unwrap()[0], key);
+
+        let records = audit::read_records(&service.audit_log().path()).unwrap();
+        let sources: Vec<_> = records
+            .iter()
+            .map(|record| record.entry.key_source.as_deref())
+            .collect();
+        assert_eq!(sources, [Some("key:tls:192.0.2.7"); 2]);
+    }
+
+    #[test]
+    fn supplied_passphrase_follows_the_fallback_policy() {
+        let dir = tempdir().unwrap();
+        let key_path = dir.path().join("missing.key");
+        let mut cfg = base_config(&key_path);
+        cfg.fallback.passphrase_salt = Some("00".repeat(16));
+        cfg.fallback.passphrase_xor = Some("00".repeat(32));
+        let passphrase = SuppliedKey::Passphrase(SecretBuffer::from_slice(b"correct horse"));
+
+        let provider = MockProvider::new("tank/secure", &["tank/secure"]);
+        let service = LockchainService::new(Arc::new(cfg.clone()), provider);
+        let err = service
+            .unlock_supplied("tank/secure", &passphrase, "tls:192.0.2.7")
+            .unwrap_err();
+        assert!(matches!(err, LockchainError::PolicyViolation(_)));
+
+        cfg.fallback.enabled = true;
+        let provider = MockProvider::new("tank/secure", &["tank/secure"]);
+        let service = LockchainService::new(Arc::new(cfg), provider);
+        service
+            .unlock_supplied("tank/secure", &passphrase, "tls:192.0.2.7")
+            .unwrap();
+        let expected = service.derive_fallback_key(b"correct horse").unwrap();
+        assert_eq!(
+            service.provider.observed_keys.lock().unwrap()[0],
+            expected.to_vec()
+        );
+    }
 }
@@ -0,0 +1,209 @@
+//! Helpers for locating the configured USB tokens on the host and the key file they carry.
+
+use crate::config::{Usb, UsbToken};
+use crate::error::LockchainResult;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::ErrorKind;
+use std::os::unix::ffi::OsStringExt;
+use std::path::{Path, PathBuf};
+
+/// Mount table read instead of `/proc/mounts`, for tests and fixtures.
+pub const MOUNTS_OVERRIDE_ENV: &str = "LOCKCHAIN_KEY_USB_MOUNTS_PATH";
+const DISK_BY_ID_DIR: &str = "/dev/disk/by-id";
+
+/// A recognised token that is currently inserted and mounted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountedToken {
+    pub token: UsbToken,
+    /// Position in [`Usb::recognized_tokens`]; lower wins.
+    pub rank: usize,
+    pub mount_point: PathBuf,
+}
+
+impl MountedToken {
+    /// Path of the key file on this token.
+    pub fn key_path(&self) -> PathBuf {
+        self.mount_point.join(self.token.key_path())
+    }
+}
+
+/// Resolve the `/dev/disk/by-*` selector of the highest-precedence token that has one.
+pub fn token_device_path(usb: &Usb) -> Option<PathBuf> {
+    usb.recognized_tokens().iter().find_map(device_path)
+}
+
+/// Resolve the `/dev/disk/by-*` selector for one token, preferring UUID, then label, then serial.
+///
+/// Serials have no stable by-id name of their own, so the first partition
+/// whose by-id link mentions the serial is used.
+pub fn device_path(token: &UsbToken) -> Option<PathBuf> {
+    let set = |value: &Option<String>| value.clone().filter(|v| !v.trim().is_empty());
+    if let Some(uuid) = set(&token.uuid) {
+        return Some(PathBuf::from(format!("/dev/disk/by-uuid/{uuid}")));
+    }
+    if let Some(label) = set(&token.label) {
+        return Some(PathBuf::from(format!("/dev/disk/by-label/{label}")));
+    }
+    let serial = set(&token.serial)?;
+    let mut links: Vec<PathBuf> = fs::read_dir(DISK_BY_ID_DIR)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.contains(&serial) && name.contains("-part"))
+        })
+        .collect();
+    links.sort();
+    links.into_iter().next()
+}
+
+/// Find the highest-precedence recognised token that is inserted and mounted.
+pub fn mounted_token(usb: &Usb) -> LockchainResult<Option<MountedToken>> {
+    let mut mounts = None;
+    for (rank, token) in usb.recognized_tokens().into_iter().enumerate() {
+        let Some(selector) = device_path(&token) else {
+            continue;
+        };
+        let devnode = match fs::canonicalize(&selector) {
+            Ok(path) => path,
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+        if mounts.is_none() {
+            mounts = Some(read_mount_table()?);
+        }
+        if let Some(mount_point) = mounts.as_deref().and_then(|m| parse_mounts(m, &devnode)) {
+            return Ok(Some(MountedToken {
+                token,
+                rank,
+                mount_point,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Locate the mountpoint of the preferred inserted token, if any is mounted.
+pub fn token_mount_point(usb: &Usb) -> LockchainResult<Option<PathBuf>> {
+    Ok(mounted_token(usb)?.map(|mounted| mounted.mount_point))
+}
+
+/// Path of the key file on the preferred inserted token, if any is available.
+pub fn token_key_path(usb: &Usb) -> LockchainResult<Option<PathBuf>> {
+    Ok(mounted_token(usb)?.map(|mounted| mounted.key_path()))
+}
+
+/// Read `/proc/mounts` or its override for testing purposes.
+pub fn read_mount_table() -> LockchainResult<String> {
+    let path = env::var(MOUNTS_OVERRIDE_ENV).unwrap_or_else(|_| "/proc/mounts".to_string());
+    Ok(fs::read_to_string(path)?)
+}
+
+/// Parse the mount table content and return the mountpoint for `devnode`.
+pub fn parse_mounts(mounts: &str, devnode: &Path) -> Option<PathBuf> {
+    let devnode = devnode.to_string_lossy();
+    mounts.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let device = parts.next()?;
+        let mountpoint = parts.next()?;
+        (device == devnode).then(|| unescape_mount_field(mountpoint))
+    })
+}
+
+/// Convert fstab-style octal escapes (e.g. `\040`) back into the raw bytes
+/// of the path, so multi-byte UTF-8 names survive.
+fn unescape_mount_field(input: &str) -> PathBuf {
+    let bytes = input.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let octal = bytes
+            .get(idx + 1..idx + 4)
+            .filter(|_| bytes[idx] == b'\\')
+            .filter(|digits| digits.iter().all(|b| (b'0'..=b'7').contains(b)))
+            .and_then(|digits| {
+                digits.iter().try_fold(0u8, |value, digit| {
+                    value.checked_mul(8)?.checked_add(digit - b'0')
+                })
+            });
+        match octal {
+            Some(value) => {
+                output.push(value);
+                idx += 4;
+            }
+            None => {
+                output.push(bytes[idx]);
+                idx += 1;
+            }
+        }
+    }
+    PathBuf::from(OsString::from_vec(output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_mounts_finds_matching_device() {
+        let snapshot = "/dev/sda1 / ext4 rw 0 0\n/dev/sdb1 /media/LOCK\\040CHAIN ext4 ro 0 0\n";
+        let mount = parse_mounts(snapshot, Path::new("/dev/sdb1")).unwrap();
+        assert_eq!(mount, PathBuf::from("/media/LOCK CHAIN"));
+        assert!(parse_mounts(snapshot, Path::new("/dev/sdc1")).is_none());
+    }
+
+    #[test]
+    fn unescape_mount_field_decodes_octals() {
+        assert_eq!(
+            unescape_mount_field("/media/LOCK\\040CHAIN"),
+            PathBuf::from("/media/LOCK CHAIN")
+        );
+        assert_eq!(
+            unescape_mount_field("/mnt/keys"),
+            PathBuf::from("/mnt/keys")
+        );
+        assert_eq!(
+            unescape_mount_field("/mnt/a\\9b"),
+            PathBuf::from("/mnt/a\\9b")
+        );
+    }
+
+    #[test]
+    fn parse_mounts_keeps_non_ascii_labels() {
+        let snapshot = "/dev/sdb1 /media/CL\\303\\211S ext4 ro 0 0\n";
+        let mount = parse_mounts(snapshot, Path::new("/dev/sdb1")).unwrap();
+        assert_eq!(mount, PathBuf::from("/media/CLÉS"));
+    }
+
+    #[test]
+    fn token_device_path_prefers_uuid() {
+        let mut usb = Usb {
+            device_label: Some("LOCKCHAINKEY".into()),
+            ..Usb::default()
+        };
+        assert_eq!(
+            token_device_path(&usb),
+            Some(PathBuf::from("/dev/disk/by-label/LOCKCHAINKEY"))
+        );
+        usb.device_uuid = Some("1234-ABCD".into());
+        assert_eq!(
+            token_device_path(&usb),
+            Some(PathBuf::from("/dev/disk/by-uuid/1234-ABCD"))
+        );
+
+        usb.device_label = None;
+        usb.device_uuid = None;
+        usb.tokens.push(UsbToken {
+            label: Some("SPARE".into()),
+            ..UsbToken::default()
+        });
+        assert_eq!(
+            token_device_path(&usb),
+            Some(PathBuf::from("/dev/disk/by-label/SPARE"))
+        );
+    }
+}
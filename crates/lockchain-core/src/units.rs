@@ -0,0 +1,173 @@
+//! Human-friendly durations ("10s", "500ms", "5m") and sizes ("8MiB") for config fields.
+//!
+//! Bare integers are still accepted so existing files keep working; they are
+//! read in the unit the field used before it became typed (seconds,
+//! milliseconds, or bytes).
+
+use crate::error::{LockchainError, LockchainResult};
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serializer};
+use std::time::Duration;
+
+/// Schema stand-in for duration fields: a humantime string or a legacy integer.
+#[derive(JsonSchema)]
+#[serde(untagged)]
+#[allow(dead_code)]
+pub enum HumanDuration {
+    /// Duration such as `"10s"`, `"500ms"`, or `"5m"`.
+    Text(String),
+    /// Legacy integer in the field's historical unit.
+    Integer(u64),
+}
+
+/// Schema stand-in for size fields: `"8MiB"`-style strings or a byte count.
+#[derive(JsonSchema)]
+#[serde(untagged)]
+#[allow(dead_code)]
+pub enum HumanSize {
+    /// Size such as `"512KiB"`, `"8MiB"`, or `"1G"`.
+    Text(String),
+    /// Byte count.
+    Integer(u64),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Raw {
+    Integer(u64),
+    Text(String),
+}
+
+/// Parse a humantime duration such as `"1m 30s"`.
+pub fn parse_duration(value: &str) -> LockchainResult<Duration> {
+    humantime::parse_duration(value.trim())
+        .map_err(|err| LockchainError::InvalidConfig(format!("invalid duration `{value}`: {err}")))
+}
+
+/// Render a duration the way config files spell it (`"1m 30s"`, `"500ms"`).
+pub fn format_duration(value: Duration) -> String {
+    if value.is_zero() {
+        "0s".to_string()
+    } else {
+        humantime::format_duration(value).to_string()
+    }
+}
+
+/// Parse a byte size: plain bytes, SI (`K`, `KB`, `M`, ...) or IEC (`KiB`, `MiB`, ...).
+pub fn parse_size(value: &str) -> LockchainResult<u64> {
+    let invalid = || LockchainError::InvalidConfig(format!("invalid size `{value}`"));
+    let trimmed = value.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(trimmed.len());
+    let (digits, unit) = trimmed.split_at(split);
+    let amount: u64 = digits.parse().map_err(|_| invalid())?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1_000,
+        "m" | "mb" => 1_000_000,
+        "g" | "gb" => 1_000_000_000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        _ => return Err(invalid()),
+    };
+    amount.checked_mul(multiplier).ok_or_else(invalid)
+}
+
+fn deserialize_duration<'de, D>(
+    deserializer: D,
+    legacy: fn(u64) -> Duration,
+) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Raw::deserialize(deserializer)? {
+        Raw::Integer(value) => Ok(legacy(value)),
+        Raw::Text(text) => parse_duration(&text).map_err(serde::de::Error::custom),
+    }
+}
+
+fn serialize_duration<S: Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format_duration(*value))
+}
+
+/// Duration fields whose bare integers mean seconds.
+pub mod secs {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_duration(value, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        deserialize_duration(deserializer, Duration::from_secs)
+    }
+}
+
+/// Duration fields whose bare integers mean milliseconds.
+pub mod millis {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_duration(value, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        deserialize_duration(deserializer, Duration::from_millis)
+    }
+}
+
+/// Size fields stored as a byte count.
+pub mod bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(*value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        match Raw::deserialize(deserializer)? {
+            Raw::Integer(value) => Ok(value),
+            Raw::Text(text) => parse_size(&text).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+
+    #[derive(Serialize, Deserialize)]
+    struct Sample {
+        #[serde(with = "secs")]
+        timeout: Duration,
+        #[serde(with = "millis")]
+        delay: Duration,
+        #[serde(with = "bytes")]
+        size: u64,
+    }
+
+    #[test]
+    fn accepts_strings_and_legacy_integers() {
+        let legacy: Sample = toml::from_str("timeout = 10\ndelay = 500\nsize = 2048").unwrap();
+        assert_eq!(legacy.timeout, Duration::from_secs(10));
+        assert_eq!(legacy.delay, Duration::from_millis(500));
+        assert_eq!(legacy.size, 2048);
+
+        let typed: Sample =
+            toml::from_str("timeout = \"5m\"\ndelay = \"1s 500ms\"\nsize = \"8MiB\"").unwrap();
+        assert_eq!(typed.timeout, Duration::from_secs(300));
+        assert_eq!(typed.delay, Duration::from_millis(1_500));
+        assert_eq!(typed.size, 8 << 20);
+
+        let written = toml::to_string(&typed).unwrap();
+        assert!(written.contains("timeout = \"5m\""));
+        assert!(written.contains("delay = \"1s 500ms\""));
+
+        assert!(toml::from_str::<Sample>("timeout = \"soon\"\ndelay = 1\nsize = 1").is_err());
+        assert!(parse_size("12 parsecs").is_err());
+        assert_eq!(parse_size("1 KB").unwrap(), 1_000);
+    }
+}
@@ -0,0 +1,281 @@
+//! Remote key source: unwrap the dataset key through Vault transit or read it from KV v2.
+//!
+//! The HTTP client is only compiled with the `vault` feature; without it a
+//! configured `[vault]` section fails with a clear error instead of silently
+//! falling back to the USB token.
+
+use crate::config::VaultCfg;
+use crate::error::{LockchainError, LockchainResult};
+use crate::secret::SecretBuffer;
+
+/// Fetch (KV) or unwrap (transit) the key described by `cfg`.
+#[cfg(feature = "vault")]
+pub fn fetch_key(cfg: &VaultCfg) -> LockchainResult<SecretBuffer> {
+    client::fetch_key(cfg)
+}
+
+/// Fetch (KV) or unwrap (transit) the key described by `cfg`.
+#[cfg(not(feature = "vault"))]
+pub fn fetch_key(cfg: &VaultCfg) -> LockchainResult<SecretBuffer> {
+    Err(LockchainError::KeyService(format!(
+        "{} is configured but lockchain was built without the `vault` feature",
+        cfg.describe()
+    )))
+}
+
+#[cfg(feature = "vault")]
+mod client {
+    use super::*;
+    use crate::config::{VaultAuth, VaultMode};
+    use crate::keyfile::decode_key_bytes;
+    use base64::Engine;
+    use serde_json::{json, Value};
+    use std::fs;
+    use std::path::Path;
+    use std::time::Duration;
+    use zeroize::Zeroizing;
+
+    pub(super) fn fetch_key(cfg: &VaultCfg) -> LockchainResult<SecretBuffer> {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(cfg.timeout.max(Duration::from_secs(1)))
+            .build();
+        let base = cfg.address.trim_end_matches('/');
+        let token = login(&agent, cfg, base)?;
+        match cfg.mode {
+            VaultMode::Transit => unwrap_transit(&agent, cfg, base, &token),
+            VaultMode::Kv => read_kv(&agent, cfg, base, &token),
+        }
+    }
+
+    /// Obtain a client token for the configured auth method.
+    fn login(
+        agent: &ureq::Agent,
+        cfg: &VaultCfg,
+        base: &str,
+    ) -> LockchainResult<Zeroizing<String>> {
+        match &cfg.auth {
+            VaultAuth::Token {
+                token_path: Some(path),
+            } => read_secret_file(path),
+            VaultAuth::Token { token_path: None } => std::env::var("VAULT_TOKEN")
+                .map(Zeroizing::new)
+                .map_err(|_| {
+                    LockchainError::KeyService(
+                        "vault.auth.token_path is unset and VAULT_TOKEN is not exported".into(),
+                    )
+                }),
+            VaultAuth::Approle {
+                role_id,
+                secret_id_path,
+                mount,
+            } => {
+                let secret_id = read_secret_file(secret_id_path)?;
+                let body = call(
+                    agent,
+                    cfg,
+                    "POST",
+                    &format!("{base}/v1/auth/{mount}/login"),
+                    None,
+                    Some(json!({ "role_id": role_id, "secret_id": secret_id.as_str() })),
+                )?;
+                body["auth"]["client_token"]
+                    .as_str()
+                    .map(|token| Zeroizing::new(token.to_string()))
+                    .ok_or_else(|| {
+                        LockchainError::KeyService(
+                            "approle login response lacks auth.client_token".into(),
+                        )
+                    })
+            }
+        }
+    }
+
+    /// Send the locally stored ciphertext to `transit/decrypt`.
+    fn unwrap_transit(
+        agent: &ureq::Agent,
+        cfg: &VaultCfg,
+        base: &str,
+        token: &str,
+    ) -> LockchainResult<SecretBuffer> {
+        let wrapped_path = cfg.wrapped_key_path.as_deref().ok_or_else(|| {
+            LockchainError::InvalidConfig("vault.wrapped_key_path is required for transit".into())
+        })?;
+        let ciphertext = read_secret_file(wrapped_path)?;
+        let body = call(
+            agent,
+            cfg,
+            "POST",
+            &format!("{base}/v1/{}/decrypt/{}", cfg.mount, cfg.key_name),
+            Some(token),
+            Some(json!({ "ciphertext": ciphertext.as_str() })),
+        )?;
+        let encoded = body["data"]["plaintext"].as_str().ok_or_else(|| {
+            LockchainError::KeyService("transit decrypt response lacks data.plaintext".into())
+        })?;
+        let raw = Zeroizing::new(
+            base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .map_err(|err| {
+                    LockchainError::KeyService(format!("transit plaintext is not base64 ({err})"))
+                })?,
+        );
+        decode(cfg, &raw)
+    }
+
+    /// Read the hex-encoded key from a KV v2 secret.
+    fn read_kv(
+        agent: &ureq::Agent,
+        cfg: &VaultCfg,
+        base: &str,
+        token: &str,
+    ) -> LockchainResult<SecretBuffer> {
+        let body = call(
+            agent,
+            cfg,
+            "GET",
+            &format!("{base}/v1/{}/data/{}", cfg.mount, cfg.key_name),
+            Some(token),
+            None,
+        )?;
+        let value = body["data"]["data"][cfg.field.as_str()]
+            .as_str()
+            .ok_or_else(|| {
+                LockchainError::KeyService(format!("KV secret lacks field `{}`", cfg.field))
+            })?;
+        decode(cfg, value.as_bytes())
+    }
+
+    fn decode(cfg: &VaultCfg, bytes: &[u8]) -> LockchainResult<SecretBuffer> {
+        decode_key_bytes(Path::new(&cfg.describe()), bytes).map(|(key, _)| key)
+    }
+
+    /// Issue one Vault API request and parse the JSON body.
+    fn call(
+        agent: &ureq::Agent,
+        cfg: &VaultCfg,
+        method: &str,
+        url: &str,
+        token: Option<&str>,
+        body: Option<Value>,
+    ) -> LockchainResult<Value> {
+        let mut request = agent.request(method, url);
+        if let Some(namespace) = &cfg.namespace {
+            request = request.set("X-Vault-Namespace", namespace);
+        }
+        if let Some(token) = token {
+            request = request.set("X-Vault-Token", token);
+        }
+        let response = match body {
+            Some(body) => request.send_json(body),
+            None => request.call(),
+        };
+        match response {
+            Ok(response) => response.into_json().map_err(|err| {
+                LockchainError::KeyService(format!("{url}: invalid JSON response ({err})"))
+            }),
+            Err(ureq::Error::Status(code, response)) => {
+                let detail = response.into_string().unwrap_or_default();
+                Err(LockchainError::KeyService(format!(
+                    "{url}: HTTP {code} {}",
+                    detail.trim()
+                )))
+            }
+            Err(err) => Err(LockchainError::KeyService(format!("{url}: {err}"))),
+        }
+    }
+
+    fn read_secret_file(path: &str) -> LockchainResult<Zeroizing<String>> {
+        let contents = Zeroizing::new(fs::read_to_string(path)?);
+        Ok(Zeroizing::new(contents.trim().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{VaultAuth, VaultMode};
+
+    fn vault_cfg(address: &str) -> VaultCfg {
+        VaultCfg {
+            address: address.to_string(),
+            namespace: None,
+            auth: VaultAuth::default(),
+            mode: VaultMode::Transit,
+            mount: "transit".into(),
+            key_name: "lockchain".into(),
+            wrapped_key_path: None,
+            field: "key".into(),
+            datasets: Vec::new(),
+            timeout: std::time::Duration::from_secs(2),
+        }
+    }
+
+    #[cfg(not(feature = "vault"))]
+    #[test]
+    fn fetch_requires_feature() {
+        let err = fetch_key(&vault_cfg("https://vault.invalid")).unwrap_err();
+        assert!(matches!(err, LockchainError::KeyService(msg) if msg.contains("`vault` feature")));
+    }
+
+    #[cfg(feature = "vault")]
+    #[test]
+    fn transit_unwraps_key() {
+        use base64::Engine;
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut head = Vec::new();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                if line == "\r\n" {
+                    break;
+                }
+                head.push(line);
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let plaintext = base64::engine::general_purpose::STANDARD.encode([0x42u8; 32]);
+            let payload = format!(r#"{{"data":{{"plaintext":"{plaintext}"}}}}"#);
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{payload}",
+                payload.len()
+            )
+            .unwrap();
+            (head, String::from_utf8(body).unwrap())
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let token = dir.path().join("token");
+        let wrapped = dir.path().join("tank.wrapped");
+        std::fs::write(&token, "s.test\n").unwrap();
+        std::fs::write(&wrapped, "vault:v1:abc\n").unwrap();
+        let cfg = VaultCfg {
+            auth: VaultAuth::Token {
+                token_path: Some(token.display().to_string()),
+            },
+            wrapped_key_path: Some(wrapped.display().to_string()),
+            ..vault_cfg(&format!("http://{addr}"))
+        };
+
+        let key = fetch_key(&cfg).unwrap();
+        assert_eq!(&key[..], &[0x42u8; 32]);
+
+        let (head, body) = server.join().unwrap();
+        assert!(head[0].starts_with("POST /v1/transit/decrypt/lockchain "));
+        assert!(head
+            .iter()
+            .any(|line| line.eq_ignore_ascii_case("x-vault-token: s.test\r\n")));
+        assert_eq!(body, r#"{"ciphertext":"vault:v1:abc"}"#);
+    }
+}
@@ -1,12 +1,16 @@
 //! Self-healing and diagnostic workflows that keep Lockchain deployments healthy.
 
-use super::{event, repair_environment, WorkflowEvent, WorkflowLevel, WorkflowReport};
-use crate::config::LockchainConfig;
-use crate::error::LockchainResult;
-use crate::keyfile::{read_key_file, write_raw_key_file};
+use super::{
+    event, repair_environment, EventSink, EventStream, WorkflowEvent, WorkflowLevel, WorkflowReport,
+};
+use crate::config::{KeySource, LockchainConfig};
+use crate::error::{LockchainError, LockchainResult};
+use crate::keyfile::{read_key_file, verify_key_source, write_raw_key_file, KEY_FILE_MODE};
+use crate::provenance::EffectiveSetting;
 use crate::provider::{DatasetKeyDescriptor, KeyState, ZfsProvider};
 use crate::service::LockchainService;
-use sha2::{Digest, Sha256};
+use crate::token;
+use serde::Serialize;
 use std::env;
 use std::fs;
 use std::os::unix::fs::PermissionsExt;
@@ -23,14 +27,97 @@ const DEFAULT_SERVICES: &[&str] = &[
 
 const INITRAMFS_TOOLS: &[&str] = &["dracut", "update-initramfs", "lsinitrd", "lsinitramfs"];
 
+/// Whether diagnostics may modify the host or only report what they find.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DoctorMode {
+    /// Read-only: report problems and the repairs `Fix` would make.
+    CheckOnly,
+    /// Apply permission fixes, checksum updates, and unit installs.
+    Fix,
+}
+
+/// Everything `doctor` saw, for attaching to support tickets or feeding monitoring.
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorReport {
+    pub generated_at: String,
+    pub hostname: Option<String>,
+    pub lockchain_version: &'static str,
+    pub mode: &'static str,
+    pub warnings: usize,
+    pub errors: usize,
+    /// The report `doctor` prints.
+    pub report: WorkflowReport,
+    pub remedies: Vec<String>,
+    /// `remedies` again, each with the fix that resolves it when there is one.
+    pub actions: Vec<Remedy>,
+    /// Repairs made, or in check-only mode the repairs `--fix` would make.
+    pub changes: Vec<String>,
+    pub units: Vec<UnitState>,
+    pub journal: Vec<JournalExcerpt>,
+    /// Effective settings with their origins; secrets are redacted.
+    pub config: Vec<EffectiveSetting>,
+}
+
+/// A repair that can be applied on request to resolve a remedy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DoctorFix {
+    /// Reinstall the mount unit and re-enable the units ([`repair_environment`]).
+    Repair,
+    /// Set loose key files back to 0400 ([`tighten_key_permissions`]).
+    TightenPermissions,
+    /// Regenerate the initramfs so early boot pins the current key
+    /// ([`refresh_initramfs`](super::refresh_initramfs)).
+    RebuildInitramfs,
+    /// Everything `doctor --fix` would change.
+    ApplyAll,
+}
+
+/// One remediation suggestion.
+#[derive(Debug, Clone, Serialize)]
+pub struct Remedy {
+    pub summary: String,
+    pub fix: Option<DoctorFix>,
+}
+
+impl Remedy {
+    fn new(summary: impl Into<String>, fix: Option<DoctorFix>) -> Self {
+        Self {
+            summary: summary.into(),
+            fix,
+        }
+    }
+}
+
+/// `systemctl show` properties for one unit.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnitState {
+    pub unit: String,
+    pub load_state: String,
+    pub active_state: String,
+    pub unit_file_state: String,
+}
+
+/// Tail of one unit's journal.
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalExcerpt {
+    pub unit: String,
+    pub lines: Vec<String>,
+}
+
 /// Aggregates the raw results from the self-heal pass before we build a report.
 #[derive(Default)]
 struct SelfHealOutcome {
-    events: Vec<WorkflowEvent>,
+    /// Repairs made (or, in check-only mode, that would be made).
+    changes: Vec<String>,
     warnings: usize,
     errors: usize,
     key_valid: bool,
     checksum_match: bool,
+    /// Key files still readable beyond 0400 after the pass.
+    loose_keys: Vec<PathBuf>,
+    /// Whether a drifted checksum was re-pinned in the config.
+    repinned: bool,
     updated_config: Option<LockchainConfig>,
 }
 
@@ -39,48 +126,120 @@ pub fn self_heal<P>(config: &LockchainConfig, provider: P) -> LockchainResult<Wo
 where
     P: ZfsProvider + Clone,
 {
-    let outcome = run_self_heal(config, provider)?;
+    self_heal_observed(config, provider, |_| {})
+}
+
+/// Like [`self_heal`], calling `on_event` as each check reports.
+pub fn self_heal_observed<P, F>(
+    config: &LockchainConfig,
+    provider: P,
+    on_event: F,
+) -> LockchainResult<WorkflowReport>
+where
+    P: ZfsProvider + Clone,
+    F: FnMut(&WorkflowEvent),
+{
+    let mut events = EventStream::new(on_event);
+    run_self_heal(config, provider, DoctorMode::Fix, &mut events)?;
     Ok(WorkflowReport {
         title: "Self-heal diagnostics".into(),
-        events: outcome.events,
+        events: events.into_events(),
     })
 }
 
 /// Wraps `self_heal` with deeper inspections and actionable remediation tips.
-pub fn doctor<P>(config: &LockchainConfig, provider: P) -> LockchainResult<WorkflowReport>
+///
+/// In [`DoctorMode::CheckOnly`] nothing on the host or in the config is
+/// modified; the report lists what [`DoctorMode::Fix`] would change instead.
+pub fn doctor<P>(
+    config: &LockchainConfig,
+    provider: P,
+    mode: DoctorMode,
+) -> LockchainResult<WorkflowReport>
+where
+    P: ZfsProvider + Clone,
+{
+    Ok(doctor_report(config, provider, mode)?.report)
+}
+
+/// Like [`doctor`], also returning the unit states, journal excerpts, and
+/// redacted config snapshot behind the report.
+pub fn doctor_report<P>(
+    config: &LockchainConfig,
+    provider: P,
+    mode: DoctorMode,
+) -> LockchainResult<DoctorReport>
 where
     P: ZfsProvider + Clone,
 {
-    let outcome = run_self_heal(config, provider.clone())?;
+    doctor_report_observed(config, provider, mode, |_| {})
+}
+
+/// Like [`doctor_report`], calling `on_event` as each check reports.
+pub fn doctor_report_observed<P, F>(
+    config: &LockchainConfig,
+    provider: P,
+    mode: DoctorMode,
+    on_event: F,
+) -> LockchainResult<DoctorReport>
+where
+    P: ZfsProvider + Clone,
+    F: FnMut(&WorkflowEvent),
+{
+    let mut events = EventStream::new(on_event);
+    events.push(event(
+        WorkflowLevel::Info,
+        "Self-heal baseline diagnostics follow.",
+    ));
+    let outcome = run_self_heal(config, provider, mode, &mut events)?;
     let SelfHealOutcome {
-        events: heal_events,
+        mut changes,
         key_valid,
         checksum_match,
+        loose_keys,
+        repinned,
         updated_config,
         ..
     } = outcome;
-    let mut events = Vec::new();
-    let mut remedies = Vec::new();
-
-    events.push(event(
-        WorkflowLevel::Info,
-        "Self-heal baseline diagnostics follow.",
-    ));
-    events.extend(heal_events.into_iter());
+    let mut actions = Vec::new();
+    let mut units = Vec::new();
+    let mut journal = Vec::new();
 
     if !key_valid {
-        remedies.push("Re-import USB key material or re-run the provisioning directive.".into());
+        actions.push(Remedy::new(
+            "Re-import USB key material or re-run the provisioning directive.",
+            None,
+        ));
     }
     if !checksum_match {
-        remedies.push("Update usb.expected_sha256 to match on-disk key material.".into());
+        actions.push(Remedy::new(
+            "Update usb.expected_sha256 (and any usb.keys checksums) to match on-disk key material.",
+            None,
+        ));
+    }
+    if !loose_keys.is_empty() {
+        let paths: Vec<String> = loose_keys
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        actions.push(Remedy::new(
+            format!("Tighten {} to 0400.", paths.join(", ")),
+            Some(DoctorFix::TightenPermissions),
+        ));
+    }
+    if repinned && mode == DoctorMode::Fix {
+        actions.push(Remedy::new(
+            "Rebuild the initramfs so early boot checks the re-pinned key checksum.",
+            Some(DoctorFix::RebuildInitramfs),
+        ));
     }
 
     events.push(event(
         WorkflowLevel::Info,
         "Inspecting lockchain-key-usb journal tail.",
     ));
-    if let Some(remedy) = audit_journal("lockchain-key-usb.service", &mut events) {
-        remedies.push(remedy);
+    if let Some(remedy) = audit_journal("lockchain-key-usb.service", &mut events, &mut journal) {
+        actions.push(Remedy::new(remedy, None));
     }
 
     events.push(event(
@@ -88,8 +247,8 @@ where
         "Evaluating systemd units required for boot flow.",
     ));
     for unit in DEFAULT_SERVICES {
-        if let Some(remedy) = audit_systemd_unit(unit, &mut events) {
-            remedies.push(remedy);
+        if let Some(remedy) = audit_systemd_unit(unit, &mut events, &mut units) {
+            actions.push(remedy);
         }
     }
 
@@ -97,24 +256,57 @@ where
         WorkflowLevel::Info,
         "Verifying initramfs tooling presence.",
     ));
-    remedies.extend(audit_initramfs_tooling(&mut events));
+    actions.extend(
+        audit_initramfs_tooling(&mut events)
+            .into_iter()
+            .map(|remedy| Remedy::new(remedy, None)),
+    );
 
-    events.push(event(
-        WorkflowLevel::Info,
-        "Reapplying system integration policies.",
-    ));
-    let repair_cfg = updated_config.as_ref().unwrap_or(config);
-    match repair_environment(repair_cfg) {
-        Ok(report) => events.extend(report.events.into_iter()),
-        Err(err) => {
+    match mode {
+        DoctorMode::Fix => {
             events.push(event(
-                WorkflowLevel::Warn,
-                format!("System integration repair failed: {err}"),
+                WorkflowLevel::Info,
+                "Reapplying system integration policies.",
             ));
-            remedies.push("Run lockchain repair with elevated privileges.".into());
+            let repair_cfg = updated_config.as_ref().unwrap_or(config);
+            match repair_environment(repair_cfg) {
+                Ok(report) => {
+                    for repaired in report.events {
+                        events.push(repaired);
+                    }
+                    changes
+                        .push("reinstalled the mount unit and re-enabled lockchain units".into());
+                }
+                Err(err) => {
+                    events.push(event(
+                        WorkflowLevel::Warn,
+                        format!("System integration repair failed: {err}"),
+                    ));
+                    actions.push(Remedy::new(
+                        "Run lockchain repair with elevated privileges.",
+                        Some(DoctorFix::Repair),
+                    ));
+                }
+            }
+        }
+        DoctorMode::CheckOnly => {
+            events.push(event(
+                WorkflowLevel::Info,
+                "Check-only: system integration repair skipped.",
+            ));
+            if !changes.is_empty() {
+                actions.push(Remedy::new(
+                    "Run lockchain doctor --fix to apply the repairs listed below.",
+                    Some(DoctorFix::ApplyAll),
+                ));
+            }
         }
     }
 
+    let remedies: Vec<String> = actions
+        .iter()
+        .map(|remedy| remedy.summary.clone())
+        .collect();
     if !remedies.is_empty() {
         events.push(event(
             WorkflowLevel::Warn,
@@ -122,7 +314,18 @@ where
         ));
     }
 
-    let (warnings, errors) = count_levels(&events);
+    let config_snapshot = match config.effective_settings() {
+        Ok(settings) => settings,
+        Err(err) => {
+            events.push(event(
+                WorkflowLevel::Warn,
+                format!("Could not snapshot the effective config ({err})."),
+            ));
+            Vec::new()
+        }
+    };
+
+    let (warnings, errors) = count_levels(events.recorded());
     let summary_level = if errors > 0 {
         WorkflowLevel::Error
     } else if warnings > 0 {
@@ -134,156 +337,93 @@ where
         summary_level,
         format!("Doctor summary :: warnings={} errors={}", warnings, errors),
     ));
+    events.push(match (mode, changes.is_empty()) {
+        (DoctorMode::Fix, true) => event(WorkflowLevel::Info, "No changes were needed."),
+        (DoctorMode::Fix, false) => event(
+            WorkflowLevel::Info,
+            format!("Changes applied: {}", changes.join(" | ")),
+        ),
+        (DoctorMode::CheckOnly, true) => event(
+            WorkflowLevel::Info,
+            "Check-only: nothing was modified and no repairs are pending.",
+        ),
+        (DoctorMode::CheckOnly, false) => event(
+            WorkflowLevel::Info,
+            format!(
+                "Check-only: nothing was modified; --fix would {}",
+                changes.join(" | ")
+            ),
+        ),
+    });
 
-    Ok(WorkflowReport {
-        title: "System doctor diagnostics".into(),
-        events,
+    Ok(DoctorReport {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        hostname: fs::read_to_string("/proc/sys/kernel/hostname")
+            .ok()
+            .map(|name| name.trim().to_string()),
+        lockchain_version: env!("CARGO_PKG_VERSION"),
+        mode: match mode {
+            DoctorMode::CheckOnly => "check-only",
+            DoctorMode::Fix => "fix",
+        },
+        warnings,
+        errors,
+        report: WorkflowReport {
+            title: "System doctor diagnostics".into(),
+            events: events.into_events(),
+        },
+        remedies,
+        actions,
+        changes,
+        units,
+        journal,
+        config: config_snapshot,
     })
 }
 
 /// Core implementation shared by doctor/self-heal flows so we only probe the system once.
-fn run_self_heal<P>(config: &LockchainConfig, provider: P) -> LockchainResult<SelfHealOutcome>
+fn run_self_heal<P>(
+    config: &LockchainConfig,
+    provider: P,
+    mode: DoctorMode,
+    events: &mut impl EventSink,
+) -> LockchainResult<SelfHealOutcome>
 where
     P: ZfsProvider + Clone,
 {
+    let first = events.recorded().len();
     let mut outcome = SelfHealOutcome::default();
     let mut cfg = config.clone();
     let mut config_dirty = false;
-    let key_path = cfg.key_hex_path();
-
-    let metadata = match fs::metadata(&key_path) {
-        Ok(meta) => {
-            let mode = meta.permissions().mode() & 0o777;
-            outcome.events.push(event(
-                WorkflowLevel::Info,
-                format!(
-                    "Key file located at {} (mode {:o})",
-                    key_path.display(),
-                    mode
-                ),
-            ));
-            if mode != 0o400 {
-                match fs::set_permissions(&key_path, fs::Permissions::from_mode(0o400)) {
-                    Ok(_) => outcome.events.push(event(
-                        WorkflowLevel::Warn,
-                        format!(
-                            "Key file permissions were {:o}; tightened to 0400 for compliance.",
-                            mode
-                        ),
-                    )),
-                    Err(err) => outcome.events.push(event(
-                        WorkflowLevel::Error,
-                        format!(
-                            "Key file permissions {:o}; failed to set 0400 ({err}).",
-                            mode
-                        ),
-                    )),
-                }
-            }
-            Some(meta)
+    let mut key_valid = true;
+    let mut checksum_match = true;
+    for source in cfg.key_sources() {
+        let check = inspect_key_source(&source, mode, events);
+        key_valid &= check.key_valid;
+        checksum_match &= check.checksum_match;
+        outcome.changes.extend(check.changes);
+        if check.loose_permissions {
+            outcome.loose_keys.push(source.path.clone());
         }
-        Err(err) => {
-            outcome.events.push(event(
-                WorkflowLevel::Error,
-                format!(
-                    "Key file {} missing or unreadable ({err})",
-                    key_path.display()
-                ),
-            ));
-            None
-        }
-    };
-
-    if metadata.is_some() {
-        match read_key_file(&key_path) {
-            Ok((key, converted)) => {
-                if converted {
-                    match write_raw_key_file(&key_path, &key[..]) {
-                        Ok(_) => outcome.events.push(event(
-                            WorkflowLevel::Warn,
-                            "Normalised legacy hex key to raw 32-byte format on disk.",
-                        )),
-                        Err(err) => outcome.events.push(event(
-                            WorkflowLevel::Error,
-                            format!("Failed to rewrite key as raw bytes ({err})."),
-                        )),
-                    }
-                }
-
-                if key.len() == 32 {
-                    outcome.key_valid = true;
-                    outcome.events.push(event(
-                        WorkflowLevel::Success,
-                        "Key material validated as raw 32-byte payload.",
-                    ));
-                } else {
-                    outcome.events.push(event(
-                        WorkflowLevel::Error,
-                        format!(
-                            "Key material must be 32 bytes; detected {} bytes.",
-                            key.len()
-                        ),
-                    ));
-                }
-
-                let digest = hex::encode(Sha256::digest(&key[..]));
-                if let Some(expected) = &config.usb.expected_sha256 {
-                    if expected.eq_ignore_ascii_case(&digest) {
-                        outcome.checksum_match = true;
-                        outcome.events.push(event(
-                            WorkflowLevel::Success,
-                            "usb.expected_sha256 matches on-disk key material.",
-                        ));
-                    } else {
-                        cfg.usb.expected_sha256 = Some(digest.clone());
-                        config_dirty = true;
-                        outcome.events.push(event(
-                            WorkflowLevel::Warn,
-                            format!(
-                                "usb.expected_sha256 mismatch: config={} actual={digest}",
-                                expected
-                            ),
-                        ));
-                    }
-                } else {
-                    outcome.events.push(event(
-                        WorkflowLevel::Warn,
-                        format!(
-                            "Computed key SHA-256={digest}; usb.expected_sha256 not configured."
-                        ),
-                    ));
-                }
+        if let Some(digest) = check.repinned_sha256 {
+            outcome.repinned = true;
+            if mode == DoctorMode::Fix {
+                outcome
+                    .changes
+                    .push(format!("re-pinned {} to {digest}", source.setting()));
+                cfg.pin_key_checksum(source.entry.as_deref(), digest);
+                config_dirty = true;
+            } else {
+                outcome
+                    .changes
+                    .push(format!("re-pin {} to {digest}", source.setting()));
             }
-            Err(err) => outcome.events.push(event(
-                WorkflowLevel::Error,
-                format!("Unable to decode key file {} ({err})", key_path.display()),
-            )),
         }
     }
+    outcome.key_valid = key_valid;
+    outcome.checksum_match = checksum_match;
 
-    if let Some(label) = &cfg.usb.device_label {
-        outcome.events.push(event(
-            WorkflowLevel::Info,
-            format!("Configured USB label requirement: {label}"),
-        ));
-    } else {
-        outcome.events.push(event(
-            WorkflowLevel::Warn,
-            "usb.device_label not set; relying on generic mount discovery.",
-        ));
-    }
-
-    if let Some(uuid) = &cfg.usb.device_uuid {
-        outcome.events.push(event(
-            WorkflowLevel::Info,
-            format!("Configured USB UUID requirement: {uuid}"),
-        ));
-    } else {
-        outcome.events.push(event(
-            WorkflowLevel::Warn,
-            "usb.device_uuid not set; ensure label-based matching is resilient.",
-        ));
-    }
+    inspect_usb_tokens(&cfg, events);
 
     let service = LockchainService::new(Arc::new(cfg.clone()), provider.clone());
     match service.list_keys() {
@@ -292,25 +432,26 @@ where
                 dataset,
                 encryption_root,
                 state,
+                ..
             } in snapshot
             {
                 match state {
-                    KeyState::Available => outcome.events.push(event(
+                    KeyState::Available => events.push(event(
                         WorkflowLevel::Success,
                         format!("{dataset} :: {encryption_root} reports available"),
                     )),
-                    KeyState::Unavailable => outcome.events.push(event(
+                    KeyState::Unavailable => events.push(event(
                         WorkflowLevel::Warn,
                         format!("{dataset} :: {encryption_root} remains locked"),
                     )),
-                    KeyState::Unknown(detail) => outcome.events.push(event(
+                    KeyState::Unknown(detail) => events.push(event(
                         WorkflowLevel::Warn,
                         format!("{dataset} :: status unknown ({detail})"),
                     )),
                 }
             }
         }
-        Err(err) => outcome.events.push(event(
+        Err(err) => events.push(event(
             WorkflowLevel::Error,
             format!("Unable to enumerate dataset status ({err})"),
         )),
@@ -320,18 +461,18 @@ where
         let salt = cfg.fallback.passphrase_salt.is_some();
         let xor = cfg.fallback.passphrase_xor.is_some();
         if salt && xor {
-            outcome.events.push(event(
+            events.push(event(
                 WorkflowLevel::Info,
                 "Fallback passphrase material present.",
             ));
         } else {
-            outcome.events.push(event(
+            events.push(event(
                 WorkflowLevel::Warn,
                 "Fallback enabled but salt/xor material incomplete.",
             ));
         }
     } else {
-        outcome.events.push(event(
+        events.push(event(
             WorkflowLevel::Info,
             "Fallback passphrase disabled by configuration.",
         ));
@@ -339,11 +480,16 @@ where
 
     if config_dirty {
         match cfg.save() {
-            Ok(_) => outcome.events.push(event(
-                WorkflowLevel::Info,
-                format!("Persisted configuration updates to {}", cfg.path.display()),
-            )),
-            Err(err) => outcome.events.push(event(
+            Ok(_) => {
+                events.push(event(
+                    WorkflowLevel::Info,
+                    format!("Persisted configuration updates to {}", cfg.path.display()),
+                ));
+                outcome
+                    .changes
+                    .push(format!("saved {}", cfg.path.display()));
+            }
+            Err(err) => events.push(event(
                 WorkflowLevel::Warn,
                 format!("Failed to persist configuration updates ({err})"),
             )),
@@ -351,14 +497,279 @@ where
         outcome.updated_config = Some(cfg);
     }
 
-    let (warnings, errors) = count_levels(&outcome.events);
+    let (warnings, errors) = count_levels(&events.recorded()[first..]);
     outcome.warnings = warnings;
     outcome.errors = errors;
     Ok(outcome)
 }
 
+/// What inspecting a single key file concluded.
+#[derive(Default)]
+struct KeyCheck {
+    key_valid: bool,
+    checksum_match: bool,
+    repinned_sha256: Option<String>,
+    /// Permissions are still looser than 0400.
+    loose_permissions: bool,
+    /// File repairs made (or pending, in check-only mode).
+    changes: Vec<String>,
+}
+
+/// Describe each recognised USB token and report which one is currently inserted.
+fn inspect_usb_tokens(cfg: &LockchainConfig, events: &mut impl EventSink) {
+    let tokens = cfg.usb.recognized_tokens();
+    if tokens.iter().all(|token| !token.has_selector()) {
+        events.push(event(
+            WorkflowLevel::Warn,
+            "No USB token label, UUID, or serial configured; relying on generic mount discovery.",
+        ));
+    } else {
+        for (rank, token) in tokens.iter().enumerate() {
+            events.push(event(
+                WorkflowLevel::Info,
+                format!(
+                    "USB token `{}` (precedence {}): {}; key file {}",
+                    token.name(),
+                    rank + 1,
+                    token.describe(),
+                    token.key_path()
+                ),
+            ));
+        }
+    }
+
+    match token::mounted_token(&cfg.usb) {
+        Ok(Some(mounted)) => {
+            let message = format!(
+                "USB token `{}` is inserted at {}",
+                mounted.token.name(),
+                mounted.mount_point.display()
+            );
+            events.push(if mounted.rank == 0 {
+                event(WorkflowLevel::Info, format!("{message}."))
+            } else {
+                event(
+                    WorkflowLevel::Warn,
+                    format!("{message}; no higher-precedence token is inserted."),
+                )
+            });
+        }
+        Ok(None) => {}
+        Err(err) => events.push(event(
+            WorkflowLevel::Warn,
+            format!("Unable to inspect USB token mounts: {err}"),
+        )),
+    }
+}
+
+/// Validate one key file, then repair what can be fixed in place: permissions,
+/// legacy hex encoding, and a drifted pinned checksum. Check-only mode
+/// reports the repairs without making them.
+fn inspect_key_source(
+    source: &KeySource,
+    doctor_mode: DoctorMode,
+    events: &mut impl EventSink,
+) -> KeyCheck {
+    let fix = doctor_mode == DoctorMode::Fix;
+    let mut check = KeyCheck::default();
+    let verification = verify_key_source(source);
+    let key_path = &source.path;
+    let setting = &verification.setting;
+
+    let Some(mode) = verification.mode else {
+        events.push(event(
+            WorkflowLevel::Error,
+            format!(
+                "Key file {} missing or unreadable ({})",
+                key_path.display(),
+                verification.problem.as_deref().unwrap_or("unknown error")
+            ),
+        ));
+        return check;
+    };
+    events.push(event(
+        WorkflowLevel::Info,
+        format!(
+            "Key file located at {} (mode {:o})",
+            key_path.display(),
+            mode
+        ),
+    ));
+    if !verification.permissions_ok && !fix {
+        events.push(event(
+            WorkflowLevel::Warn,
+            format!("Key file permissions are {:o}; expected 0400.", mode),
+        ));
+        check
+            .changes
+            .push(format!("tighten {} to 0400", key_path.display()));
+        check.loose_permissions = true;
+    } else if !verification.permissions_ok {
+        if tighten_permissions(key_path, mode, events) {
+            check
+                .changes
+                .push(format!("tightened {} to 0400", key_path.display()));
+        } else {
+            check.loose_permissions = true;
+        }
+    }
+
+    let Some(digest) = verification.sha256.clone() else {
+        events.push(event(
+            WorkflowLevel::Error,
+            format!(
+                "Unable to decode key file {} ({})",
+                key_path.display(),
+                verification.problem.as_deref().unwrap_or("unknown error")
+            ),
+        ));
+        return check;
+    };
+
+    if verification.hex_encoded && !fix {
+        events.push(event(
+            WorkflowLevel::Warn,
+            format!(
+                "Key at {} is stored as legacy hex text.",
+                key_path.display()
+            ),
+        ));
+        check
+            .changes
+            .push(format!("rewrite {} as raw bytes", key_path.display()));
+    } else if verification.hex_encoded {
+        let rewritten =
+            read_key_file(key_path).and_then(|(key, _)| write_raw_key_file(key_path, &key[..]));
+        match rewritten {
+            Ok(_) => {
+                events.push(event(
+                    WorkflowLevel::Warn,
+                    format!(
+                        "Normalised legacy hex key at {} to raw 32-byte format on disk.",
+                        key_path.display()
+                    ),
+                ));
+                check
+                    .changes
+                    .push(format!("rewrote {} as raw bytes", key_path.display()));
+            }
+            Err(err) => events.push(event(
+                WorkflowLevel::Error,
+                format!("Failed to rewrite key as raw bytes ({err})."),
+            )),
+        }
+    }
+
+    if verification.length_ok {
+        check.key_valid = true;
+        events.push(event(
+            WorkflowLevel::Success,
+            format!(
+                "Key material at {} validated as raw 32-byte payload.",
+                key_path.display()
+            ),
+        ));
+    } else {
+        events.push(event(
+            WorkflowLevel::Error,
+            format!("Key material at {} must be 32 bytes.", key_path.display()),
+        ));
+    }
+
+    match (verification.checksum_match, &source.expected_sha256) {
+        (Some(true), _) => {
+            check.checksum_match = true;
+            events.push(event(
+                WorkflowLevel::Success,
+                format!("{setting} matches on-disk key material."),
+            ));
+        }
+        (Some(false), Some(expected)) => {
+            events.push(event(
+                WorkflowLevel::Warn,
+                format!("{setting} mismatch: config={expected} actual={digest}"),
+            ));
+            check.repinned_sha256 = Some(digest);
+        }
+        _ => events.push(event(
+            WorkflowLevel::Warn,
+            format!("Computed key SHA-256={digest}; {setting} not configured."),
+        )),
+    }
+
+    check
+}
+
+/// Set `key_path` to 0400, reporting the outcome; `true` when it worked.
+fn tighten_permissions(key_path: &Path, mode: u32, events: &mut impl EventSink) -> bool {
+    match fs::set_permissions(key_path, fs::Permissions::from_mode(KEY_FILE_MODE)) {
+        Ok(_) => {
+            events.push(event(
+                WorkflowLevel::Warn,
+                format!(
+                    "Key file permissions were {:o}; tightened to 0400 for compliance.",
+                    mode
+                ),
+            ));
+            true
+        }
+        Err(err) => {
+            events.push(event(
+                WorkflowLevel::Error,
+                format!(
+                    "Key file permissions {:o}; failed to set 0400 ({err}).",
+                    mode
+                ),
+            ));
+            false
+        }
+    }
+}
+
+/// Set every configured key file that is readable beyond its owner back to
+/// 0400, leaving everything else `doctor --fix` would touch alone.
+pub fn tighten_key_permissions(config: &LockchainConfig) -> LockchainResult<WorkflowReport> {
+    let mut events = Vec::new();
+    let mut failed = Vec::new();
+    for source in config.key_sources() {
+        let verification = verify_key_source(&source);
+        match verification.mode {
+            None => events.push(event(
+                WorkflowLevel::Warn,
+                format!(
+                    "Key file {} missing; nothing to tighten.",
+                    source.path.display()
+                ),
+            )),
+            Some(_) if verification.permissions_ok => events.push(event(
+                WorkflowLevel::Info,
+                format!("Key file {} is already 0400.", source.path.display()),
+            )),
+            Some(mode) => {
+                if !tighten_permissions(&source.path, mode, &mut events) {
+                    failed.push(source.path.display().to_string());
+                }
+            }
+        }
+    }
+    if !failed.is_empty() {
+        return Err(LockchainError::Provider(format!(
+            "failed to tighten {}",
+            failed.join(", ")
+        )));
+    }
+    Ok(WorkflowReport {
+        title: "Tightened key file permissions".into(),
+        events,
+    })
+}
+
 /// Sample a service's journal tail and flag any warnings or errors we spot.
-fn audit_journal(service: &str, events: &mut Vec<WorkflowEvent>) -> Option<String> {
+fn audit_journal(
+    service: &str,
+    events: &mut impl EventSink,
+    excerpts: &mut Vec<JournalExcerpt>,
+) -> Option<String> {
     let output = Command::new("journalctl")
         .args([
             "-u",
@@ -386,6 +797,10 @@ fn audit_journal(service: &str, events: &mut Vec<WorkflowEvent>) -> Option<Strin
             }
 
             let text = String::from_utf8_lossy(&output.stdout);
+            excerpts.push(JournalExcerpt {
+                unit: service.to_string(),
+                lines: text.lines().map(str::to_string).collect(),
+            });
             if text.trim().is_empty() {
                 events.push(event(
                     WorkflowLevel::Warn,
@@ -457,7 +872,11 @@ fn audit_journal(service: &str, events: &mut Vec<WorkflowEvent>) -> Option<Strin
 }
 
 /// Inspect a systemd unit's state and suggest follow-up when it's unhealthy.
-fn audit_systemd_unit(unit: &str, events: &mut Vec<WorkflowEvent>) -> Option<String> {
+fn audit_systemd_unit(
+    unit: &str,
+    events: &mut impl EventSink,
+    states: &mut Vec<UnitState>,
+) -> Option<Remedy> {
     let output = Command::new("systemctl")
         .args([
             "show",
@@ -479,8 +898,9 @@ fn audit_systemd_unit(unit: &str, events: &mut Vec<WorkflowEvent>) -> Option<Str
                     WorkflowLevel::Warn,
                     format!("systemctl show {unit} failed: {detail}"),
                 ));
-                return Some(format!(
-                    "Ensure {unit} is installed and systemd is available."
+                return Some(Remedy::new(
+                    format!("Ensure {unit} is installed and systemd is available."),
+                    Some(DoctorFix::Repair),
                 ));
             }
 
@@ -498,23 +918,39 @@ fn audit_systemd_unit(unit: &str, events: &mut Vec<WorkflowEvent>) -> Option<Str
                 }
             }
 
+            states.push(UnitState {
+                unit: unit.to_string(),
+                load_state: load.to_string(),
+                active_state: active.to_string(),
+                unit_file_state: unit_file.to_string(),
+            });
+
             let mut severity = WorkflowLevel::Info;
             let mut remedy = None;
 
             if load != "loaded" {
                 severity = WorkflowLevel::Error;
-                remedy = Some(format!(
-                    "{unit} is not loaded (LoadState={load}); reinstall or re-enable the unit."
+                remedy = Some(Remedy::new(
+                    format!(
+                        "{unit} is not loaded (LoadState={load}); reinstall or re-enable the unit."
+                    ),
+                    Some(DoctorFix::Repair),
                 ));
             } else if active != "active" && active != "activating" {
                 severity = WorkflowLevel::Warn;
-                remedy = Some(format!(
-                    "{unit} is not active (ActiveState={active}); review `systemctl status {unit}`."
+                remedy = Some(Remedy::new(
+                    format!(
+                        "{unit} is not active (ActiveState={active}); review `systemctl status {unit}`."
+                    ),
+                    None,
                 ));
             } else if unit_file != "enabled" && unit_file != "static" {
                 severity = WorkflowLevel::Warn;
-                remedy = Some(format!(
-                    "{unit} is not enabled (UnitFileState={unit_file}); run `systemctl enable {unit}`."
+                remedy = Some(Remedy::new(
+                    format!(
+                        "{unit} is not enabled (UnitFileState={unit_file}); run `systemctl enable {unit}`."
+                    ),
+                    Some(DoctorFix::Repair),
                 ));
             }
 
@@ -529,13 +965,16 @@ fn audit_systemd_unit(unit: &str, events: &mut Vec<WorkflowEvent>) -> Option<Str
                 WorkflowLevel::Warn,
                 format!("systemctl not available to inspect {unit} ({err})."),
             ));
-            Some("Systemd not present; validate service management manually.".into())
+            Some(Remedy::new(
+                "Systemd not present; validate service management manually.",
+                None,
+            ))
         }
     }
 }
 
 /// Confirm the expected initramfs utilities are present in PATH.
-fn audit_initramfs_tooling(events: &mut Vec<WorkflowEvent>) -> Vec<String> {
+fn audit_initramfs_tooling(events: &mut impl EventSink) -> Vec<String> {
     let mut remedies = Vec::new();
     let mut available = false;
 
@@ -602,3 +1041,60 @@ fn count_levels(events: &[WorkflowEvent]) -> (usize, usize) {
     }
     (warnings, errors)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_only_reports_key_repairs_without_applying_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("key.hex");
+        fs::write(&path, hex::encode([5u8; 32])).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        let source = KeySource {
+            path: path.clone(),
+            expected_sha256: None,
+            entry: None,
+        };
+
+        let mut events = Vec::new();
+        let check = inspect_key_source(&source, DoctorMode::CheckOnly, &mut events);
+        assert_eq!(check.changes.len(), 2);
+        assert!(check.loose_permissions);
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o644
+        );
+        assert_eq!(fs::read(&path).unwrap().len(), 64);
+
+        let check = inspect_key_source(&source, DoctorMode::Fix, &mut events);
+        assert_eq!(check.changes.len(), 2);
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            KEY_FILE_MODE
+        );
+        assert_eq!(fs::read(&path).unwrap(), [5u8; 32]);
+    }
+
+    #[test]
+    fn tighten_key_permissions_only_touches_modes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("key.hex");
+        fs::write(&path, hex::encode([7u8; 32])).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        let mut config = LockchainConfig::starter(dir.path().join("lockchain.toml"), Vec::new());
+        config.usb.key_hex_path = path.display().to_string();
+
+        let report = tighten_key_permissions(&config).unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            KEY_FILE_MODE
+        );
+        assert_eq!(fs::read(&path).unwrap().len(), 64);
+
+        let report_again = tighten_key_permissions(&config).unwrap();
+        assert_eq!(report.events.len(), 1);
+        assert_eq!(report_again.events[0].level, WorkflowLevel::Info);
+    }
+}
@@ -0,0 +1,306 @@
+//! Key escrow: `age`-encrypted copies of token key material kept off the
+//! token, and recovery from those copies when the token is lost.
+
+use super::{event, import_key, WorkflowLevel, WorkflowReport};
+use crate::audit::{AuditEntry, AuditEvent, AuditLog};
+use crate::config::LockchainConfig;
+use crate::error::{LockchainError, LockchainResult};
+use crate::keyfile::{decode_key_bytes, read_key_file, replace_raw_key_file, verify_key_source};
+use crate::provider::ZfsProvider;
+use crate::secret::SecretBuffer;
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Where escrow copies go unless the caller names a file.
+pub const ESCROW_DIR: &str = "/var/lib/lockchain/escrow";
+const AGE_BINARIES: &[&str] = &["/usr/bin/age", "/usr/local/bin/age"];
+
+/// Encrypt the key behind `dataset` to `recipients` and write it to `output`,
+/// or to a timestamped file under `escrow_dir`.
+///
+/// Every attempt is recorded as an `escrow` event in the audit trail.
+pub fn export_escrow<P: ZfsProvider>(
+    config: &LockchainConfig,
+    provider: &P,
+    dataset: &str,
+    recipients: &[String],
+    output: Option<&Path>,
+    escrow_dir: &Path,
+) -> LockchainResult<WorkflowReport> {
+    let result = export_inner(config, provider, dataset, recipients, output, escrow_dir);
+    let mut entry = AuditEntry::from_result(AuditEvent::Escrow, dataset, &result);
+    if result.is_ok() {
+        entry = entry.with_detail(format!("export to {}", recipients.join(", ")));
+    }
+    AuditLog::new(&config.audit).record_or_warn(entry);
+    result
+}
+
+fn export_inner<P: ZfsProvider>(
+    config: &LockchainConfig,
+    provider: &P,
+    dataset: &str,
+    recipients: &[String],
+    output: Option<&Path>,
+    escrow_dir: &Path,
+) -> LockchainResult<WorkflowReport> {
+    if !config.contains_dataset(dataset) {
+        return Err(LockchainError::DatasetNotConfigured(dataset.to_string()));
+    }
+    if recipients.is_empty() {
+        return Err(LockchainError::InvalidConfig(
+            "escrow needs at least one age recipient".into(),
+        ));
+    }
+    let root = provider.encryption_root(dataset)?;
+    if config.vault_for(dataset, &root).is_some() {
+        return Err(LockchainError::InvalidConfig(format!(
+            "the key for {dataset} is served by Vault; escrow it there"
+        )));
+    }
+
+    let mut events = Vec::new();
+    let source = config.key_source(dataset, &root);
+    let verification = verify_key_source(&source);
+    if verification.checksum_match == Some(false) {
+        events.push(event(
+            WorkflowLevel::Warn,
+            format!(
+                "{} does not match {}; the escrow copy may not unlock {root}",
+                source.path.display(),
+                verification.setting
+            ),
+        ));
+    }
+    let (key, _) = read_key_file(&source.path)?;
+
+    let path = match output {
+        Some(path) => {
+            escrow_key(&key, recipients, path)?;
+            path.to_path_buf()
+        }
+        None => escrow_into(&key, recipients, escrow_dir, dataset)?,
+    };
+    events.push(event(
+        WorkflowLevel::Security,
+        format!(
+            "Key for {root} from {} encrypted to {} and written to {}",
+            source.path.display(),
+            recipients.join(", "),
+            path.display()
+        ),
+    ));
+    if let Some(sha256) = verification.sha256 {
+        events.push(event(
+            WorkflowLevel::Info,
+            format!("SHA-256 of escrowed key: {sha256}"),
+        ));
+    }
+    events.push(event(
+        WorkflowLevel::Info,
+        "Store the escrow file away from this host; it is only as safe as the recipients' identities.",
+    ));
+
+    Ok(WorkflowReport {
+        title: format!("Escrowed key for {dataset}"),
+        events,
+    })
+}
+
+/// Decrypt the escrow file `input` with the age identity at `identity`.
+///
+/// With `output` the plaintext key is written there (0400) for the operator
+/// to move; without it the key is installed at the runtime key path like
+/// `import-key`. Every attempt is recorded as an `escrow` audit event.
+pub fn restore_escrow(
+    config: &mut LockchainConfig,
+    dataset: &str,
+    input: &Path,
+    identity: &Path,
+    output: Option<&Path>,
+) -> LockchainResult<WorkflowReport> {
+    let result = restore_inner(config, input, identity, output);
+    let mut entry = AuditEntry::from_result(AuditEvent::Escrow, dataset, &result);
+    if result.is_ok() {
+        entry = entry.with_detail(format!("restore from {}", input.display()));
+    }
+    AuditLog::new(&config.audit).record_or_warn(entry);
+    result
+}
+
+fn restore_inner(
+    config: &mut LockchainConfig,
+    input: &Path,
+    identity: &Path,
+    output: Option<&Path>,
+) -> LockchainResult<WorkflowReport> {
+    let mut args = vec!["-d".into(), "-i".into(), identity.as_os_str().to_owned()];
+    args.push(input.as_os_str().to_owned());
+    let plaintext = SecretBuffer::from_slice(&run_age(&args, &[])?);
+    let (key, _) = decode_key_bytes(input, &plaintext)?;
+
+    let Some(path) = output else {
+        let mut report = import_key(config, &key, input, false)?;
+        report.title = format!("Restored escrowed key from {}", input.display());
+        return Ok(report);
+    };
+    replace_raw_key_file(path, &key)?;
+    let events = vec![
+        event(
+            WorkflowLevel::Security,
+            format!(
+                "Decrypted {} to {} (permissions 0400)",
+                input.display(),
+                path.display()
+            ),
+        ),
+        event(
+            WorkflowLevel::Warn,
+            format!(
+                "{} holds the plaintext key; install it with `lockchain import-key --file {}`, then `shred -u {}`.",
+                path.display(),
+                path.display(),
+                path.display()
+            ),
+        ),
+    ];
+    Ok(WorkflowReport {
+        title: format!("Restored escrowed key from {}", input.display()),
+        events,
+    })
+}
+
+/// Escrow `key` as `<dataset>-<UTC timestamp>.key.age` under `dir` and
+/// return the file's path.
+pub(super) fn escrow_into(
+    key: &[u8],
+    recipients: &[String],
+    dir: &Path,
+    dataset: &str,
+) -> LockchainResult<PathBuf> {
+    fs::create_dir_all(dir)?;
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    let path = dir.join(format!(
+        "{}-{}.key.age",
+        dataset.replace('/', "_"),
+        chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+    ));
+    escrow_key(key, recipients, &path)?;
+    Ok(path)
+}
+
+/// Encrypt `key` to every recipient with `age`, writing the result to `path`.
+fn escrow_key(key: &[u8], recipients: &[String], path: &Path) -> LockchainResult<()> {
+    let mut args = Vec::new();
+    for recipient in recipients {
+        args.push("-r".into());
+        args.push(recipient.into());
+    }
+    args.push("-o".into());
+    args.push(path.as_os_str().to_owned());
+    run_age(&args, key)?;
+    Ok(())
+}
+
+/// Run the system `age` with `stdin` piped in and return what it printed.
+fn run_age(args: &[std::ffi::OsString], stdin: &[u8]) -> LockchainResult<Vec<u8>> {
+    let binary = AGE_BINARIES
+        .iter()
+        .find(|candidate| Path::new(candidate).exists())
+        .ok_or_else(|| {
+            LockchainError::Provider(format!(
+                "none of {:?} are available on this system",
+                AGE_BINARIES
+            ))
+        })?;
+    let mut child = Command::new(binary)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| LockchainError::Provider(err.to_string()))?;
+    if let Some(mut pipe) = child.stdin.take() {
+        pipe.write_all(stdin)?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|err| LockchainError::Provider(err.to_string()))?;
+    if !output.status.success() {
+        return Err(LockchainError::Provider(format!(
+            "age exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::read_records;
+    use crate::provider::KeyStatusSnapshot;
+
+    struct RootProvider;
+
+    impl ZfsProvider for RootProvider {
+        fn encryption_root(&self, dataset: &str) -> LockchainResult<String> {
+            Ok(dataset.to_string())
+        }
+
+        fn locked_descendants(&self, _root: &str) -> LockchainResult<Vec<String>> {
+            Ok(Vec::new())
+        }
+
+        fn load_key_tree(&self, _root: &str, _key: &[u8]) -> LockchainResult<Vec<String>> {
+            unreachable!("escrow never loads keys")
+        }
+
+        fn describe_datasets(&self, _datasets: &[String]) -> LockchainResult<KeyStatusSnapshot> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[test]
+    fn failed_exports_are_audited_without_writing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = LockchainConfig::starter(
+            dir.path().join("lockchain-zfs.toml"),
+            vec!["tank/secure".into()],
+        );
+        config.audit.path = dir.path().join("audit.jsonl").display().to_string();
+        let escrow_dir = dir.path().join("escrow");
+
+        let err = export_escrow(
+            &config,
+            &RootProvider,
+            "tank/other",
+            &["age1example".into()],
+            None,
+            &escrow_dir,
+        )
+        .unwrap_err();
+        assert!(matches!(err, LockchainError::DatasetNotConfigured(_)));
+        let err = export_escrow(
+            &config,
+            &RootProvider,
+            "tank/secure",
+            &[],
+            None,
+            &escrow_dir,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("recipient"));
+        assert!(!escrow_dir.exists());
+
+        let records = read_records(&config.audit.log_path()).unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(records
+            .iter()
+            .all(|record| record.entry.event == AuditEvent::Escrow));
+    }
+}
@@ -0,0 +1,222 @@
+//! Emergency lockdown: unload every configured key, destroy the runtime key
+//! file, and optionally stop the daemon so nothing unlocks again unattended.
+
+use super::repair::{systemctl_path, SYSTEMCTL_SKIP_ENV};
+use super::{event, WorkflowEvent, WorkflowLevel, WorkflowReport};
+use crate::config::LockchainConfig;
+use crate::error::LockchainResult;
+use crate::keyfile::shred_key_file;
+use crate::provider::ZfsProvider;
+use crate::service::{LockOptions, LockchainService};
+use std::env;
+use std::process::Command;
+use std::sync::Arc;
+
+/// Unit that unlocks datasets on its own when the token appears.
+const DAEMON_UNIT: &str = "lockchain-zfs.service";
+
+/// Caller-provided knobs for [`killswitch`].
+#[derive(Debug, Clone, Default)]
+pub struct KillswitchOptions {
+    /// Stop `lockchain-zfs.service` before locking, so a token still in the
+    /// port cannot unlock the datasets again.
+    pub stop_daemon: bool,
+}
+
+/// Unmount and unload the key of every configured encryption root, then
+/// shred the runtime key file.
+///
+/// Each step reports failures as events and the run carries on, so one
+/// stuck dataset does not leave the rest unlocked. Locks go through the
+/// service and are audited as usual.
+pub fn killswitch<P: ZfsProvider>(
+    config: &LockchainConfig,
+    provider: P,
+    options: KillswitchOptions,
+) -> LockchainResult<WorkflowReport> {
+    let mut events = Vec::new();
+
+    if options.stop_daemon {
+        stop_daemon(&mut events);
+    }
+
+    let service = LockchainService::new(Arc::new(config.clone()), provider);
+    let mut roots: Vec<String> = Vec::new();
+    let mut failures = 0usize;
+    for dataset in &config.policy.datasets {
+        match service.lock(dataset, LockOptions { unmount: true }) {
+            Ok(report) if roots.contains(&report.encryption_root) => {}
+            Ok(report) => {
+                if report.already_locked {
+                    events.push(event(
+                        WorkflowLevel::Info,
+                        format!("{} was already locked", report.encryption_root),
+                    ));
+                } else {
+                    events.push(event(
+                        WorkflowLevel::Security,
+                        format!(
+                            "Unloaded key for {} ({} datasets)",
+                            report.encryption_root,
+                            report.locked.len()
+                        ),
+                    ));
+                }
+                roots.push(report.encryption_root);
+            }
+            Err(err) => {
+                failures += 1;
+                events.push(event(
+                    WorkflowLevel::Error,
+                    format!("Could not lock {dataset}: {err}"),
+                ));
+            }
+        }
+    }
+
+    let key_path = config.key_hex_path();
+    if key_path.exists() {
+        match shred_key_file(&key_path) {
+            Ok(()) => events.push(event(
+                WorkflowLevel::Security,
+                format!("Shredded runtime key file {}", key_path.display()),
+            )),
+            Err(err) => {
+                failures += 1;
+                events.push(event(
+                    WorkflowLevel::Error,
+                    format!(
+                        "Could not remove {} ({err}); pull the key token now.",
+                        key_path.display()
+                    ),
+                ));
+            }
+        }
+    } else {
+        events.push(event(
+            WorkflowLevel::Info,
+            format!("No runtime key file at {}", key_path.display()),
+        ));
+    }
+
+    events.push(if failures == 0 {
+        event(
+            WorkflowLevel::Security,
+            format!(
+                "Killswitch engaged: {} encryption roots locked",
+                roots.len()
+            ),
+        )
+    } else {
+        event(
+            WorkflowLevel::Error,
+            format!("Killswitch incomplete: {failures} steps failed; review the errors above"),
+        )
+    });
+
+    Ok(WorkflowReport {
+        title: "Killswitch".into(),
+        events,
+    })
+}
+
+/// Run `systemctl stop` on the daemon unit and record the outcome.
+fn stop_daemon(events: &mut Vec<WorkflowEvent>) {
+    if env::var_os(SYSTEMCTL_SKIP_ENV).is_some() {
+        events.push(event(
+            WorkflowLevel::Warn,
+            format!("LOCKCHAIN_SKIP_SYSTEMCTL set – leaving {DAEMON_UNIT} running."),
+        ));
+        return;
+    }
+    let Some(systemctl) = systemctl_path() else {
+        events.push(event(
+            WorkflowLevel::Warn,
+            format!("systemctl not found; stop {DAEMON_UNIT} manually."),
+        ));
+        return;
+    };
+    match Command::new(systemctl).args(["stop", DAEMON_UNIT]).output() {
+        Ok(result) if result.status.success() => events.push(event(
+            WorkflowLevel::Security,
+            format!("Stopped {DAEMON_UNIT}"),
+        )),
+        Ok(result) => {
+            let stderr = String::from_utf8_lossy(&result.stderr);
+            events.push(event(
+                WorkflowLevel::Warn,
+                format!("systemctl stop {DAEMON_UNIT} failed: {}", stderr.trim()),
+            ));
+        }
+        Err(err) => events.push(event(
+            WorkflowLevel::Warn,
+            format!("systemctl stop {DAEMON_UNIT} failed: {err}"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::KeyStatusSnapshot;
+    use std::sync::Mutex;
+
+    /// Two datasets under one root; unloading the key locks both.
+    #[derive(Clone, Default)]
+    struct SharedRoot {
+        unloads: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl ZfsProvider for SharedRoot {
+        fn encryption_root(&self, _dataset: &str) -> LockchainResult<String> {
+            Ok("tank/secure".into())
+        }
+
+        fn locked_descendants(&self, _root: &str) -> LockchainResult<Vec<String>> {
+            Ok(if self.unloads.lock().unwrap().is_empty() {
+                Vec::new()
+            } else {
+                vec!["tank/secure".into(), "tank/secure/home".into()]
+            })
+        }
+
+        fn load_key_tree(&self, _root: &str, _key: &[u8]) -> LockchainResult<Vec<String>> {
+            unreachable!("the killswitch never loads keys")
+        }
+
+        fn describe_datasets(&self, _datasets: &[String]) -> LockchainResult<KeyStatusSnapshot> {
+            Ok(Vec::new())
+        }
+
+        fn unload_key_tree(&self, root: &str, unmount: bool) -> LockchainResult<Vec<String>> {
+            assert!(unmount);
+            self.unloads.lock().unwrap().push(root.to_string());
+            Ok(vec!["tank/secure".into(), "tank/secure/home".into()])
+        }
+    }
+
+    #[test]
+    fn locks_each_root_once_and_shreds_the_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("key.hex");
+        crate::keyfile::write_raw_key_file(&key_path, &[7u8; 32]).unwrap();
+        let mut config = LockchainConfig::starter(
+            dir.path().join("lockchain-zfs.toml"),
+            vec!["tank/secure".into(), "tank/secure/home".into()],
+        );
+        config.usb.key_hex_path = key_path.display().to_string();
+        config.audit.path = dir.path().join("audit.jsonl").display().to_string();
+
+        let provider = SharedRoot::default();
+        let report = killswitch(&config, provider.clone(), KillswitchOptions::default()).unwrap();
+
+        assert_eq!(*provider.unloads.lock().unwrap(), vec!["tank/secure"]);
+        assert!(!key_path.exists());
+        let last = report.events.last().unwrap();
+        assert_eq!(last.level, WorkflowLevel::Security);
+        assert!(
+            last.message.contains("1 encryption roots locked"),
+            "{last:?}"
+        );
+    }
+}
@@ -1,28 +1,50 @@
 //! Workflow orchestration for provisioning, diagnostics, repair, and drills.
 
 mod diagnostics;
+mod escrow;
+mod killswitch;
+mod params;
 mod provisioning;
 mod repair;
+mod rotation;
 mod self_test;
+mod verify;
 
 use crate::config::LockchainConfig;
 use crate::error::{LockchainError, LockchainResult};
 use crate::provider::ZfsProvider;
 use crate::service::{LockchainService, UnlockOptions};
+use serde::Serialize;
 use sha2::{Digest, Sha256};
 use std::fs;
 use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Instant;
 
-pub use diagnostics::{doctor, self_heal};
-pub use provisioning::{forge_key, ForgeMode, ProvisionOptions};
+pub use diagnostics::{
+    doctor, doctor_report, doctor_report_observed, self_heal, self_heal_observed,
+    tighten_key_permissions, DoctorFix, DoctorMode, DoctorReport, JournalExcerpt, Remedy,
+    UnitState,
+};
+pub use escrow::{export_escrow, restore_escrow, ESCROW_DIR};
+pub use killswitch::{killswitch, KillswitchOptions};
+pub use params::{ParamKind, ParamSpec, BREAKGLASS_PARAMS, FORGE_PARAMS, SELF_TEST_PARAMS};
+pub use provisioning::{
+    forge_key, forge_key_observed, plan_forge, refresh_initramfs, removable_devices, wipe_token,
+    wipe_token_observed, ForgeMode, ForgePlan, ProvisionOptions, RemovableDevice,
+};
 pub use repair::repair_environment;
-pub use self_test::self_test;
+pub use rotation::{rotate_key, RotateOptions};
+pub use self_test::{self_test, self_test_observed, SelfTestOptions};
+pub use verify::verify_token;
 
 /// Severity levels used when reporting workflow events.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum WorkflowLevel {
+    /// Step detail such as commands run and how long they took.
+    Debug,
     Info,
     Success,
     Warn,
@@ -31,19 +53,89 @@ pub enum WorkflowLevel {
 }
 
 /// Single line of output produced by a workflow step.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct WorkflowEvent {
     pub level: WorkflowLevel,
     pub message: String,
 }
 
 /// Aggregated report returned by any workflow entry point.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct WorkflowReport {
     pub title: String,
     pub events: Vec<WorkflowEvent>,
 }
 
+/// Destination for workflow events as steps complete.
+///
+/// A plain `Vec` collects silently; [`EventStream`] also hands every event to
+/// an observer so front ends can show progress while the workflow runs.
+pub(crate) trait EventSink {
+    fn push(&mut self, event: WorkflowEvent);
+
+    /// Everything pushed so far, oldest first.
+    fn recorded(&self) -> &[WorkflowEvent];
+}
+
+impl EventSink for Vec<WorkflowEvent> {
+    fn push(&mut self, event: WorkflowEvent) {
+        Vec::push(self, event);
+    }
+
+    fn recorded(&self) -> &[WorkflowEvent] {
+        self
+    }
+}
+
+/// Collects events for the final report while streaming each to `observer`.
+pub(crate) struct EventStream<F> {
+    events: Vec<WorkflowEvent>,
+    observer: F,
+}
+
+impl<F: FnMut(&WorkflowEvent)> EventStream<F> {
+    pub(crate) fn new(observer: F) -> Self {
+        Self {
+            events: Vec::new(),
+            observer,
+        }
+    }
+
+    pub(crate) fn into_events(self) -> Vec<WorkflowEvent> {
+        self.events
+    }
+}
+
+impl<F: FnMut(&WorkflowEvent)> EventSink for EventStream<F> {
+    fn push(&mut self, event: WorkflowEvent) {
+        (self.observer)(&event);
+        self.events.push(event);
+    }
+
+    fn recorded(&self) -> &[WorkflowEvent] {
+        &self.events
+    }
+}
+
+/// Run `step`, then record what ran and how long it took as a `Debug` event.
+pub(crate) fn timed<T>(
+    events: &mut impl EventSink,
+    what: impl std::fmt::Display,
+    step: impl FnOnce() -> LockchainResult<T>,
+) -> LockchainResult<T> {
+    let started = Instant::now();
+    let result = step();
+    let outcome = if result.is_ok() { "ok" } else { "failed" };
+    events.push(event(
+        WorkflowLevel::Debug,
+        format!(
+            "{what}: {outcome} after {} ms",
+            started.elapsed().as_millis()
+        ),
+    ));
+    result
+}
+
 /// Convenience constructor that wraps the repeated boilerplate.
 pub(crate) fn event(level: WorkflowLevel, message: impl Into<String>) -> WorkflowEvent {
     WorkflowEvent {
@@ -64,8 +156,10 @@ where
 {
     let mut events = Vec::new();
     let service = LockchainService::new(Arc::new(config.clone()), provider.clone());
-    let mut options = UnlockOptions::default();
-    options.strict_usb = strict_usb;
+    let options = UnlockOptions {
+        strict_usb,
+        ..UnlockOptions::default()
+    };
     let report = service.unlock_with_retry(dataset, options)?;
 
     if report.already_unlocked {
@@ -120,7 +214,7 @@ where
     let mut events = Vec::new();
     let service = LockchainService::new(Arc::new(config.clone()), provider);
     let key = service
-        .derive_fallback_key(passphrase)
+        .break_glass(dataset, passphrase)
         .map_err(|err| LockchainError::InvalidConfig(err.to_string()))?;
     crate::keyfile::write_raw_key_file(output_path, &key)?;
     let digest = hex::encode(Sha256::digest(&key[..]));
@@ -141,3 +235,129 @@ where
         events,
     })
 }
+
+/// Install externally supplied key material at the runtime key path.
+///
+/// `material` may be 32 raw bytes or 64 hex digits; `origin` names where it
+/// came from in messages. With `pin_checksum` the key's SHA-256 is recorded
+/// as `usb.expected_sha256` and the configuration saved.
+pub fn import_key(
+    config: &mut LockchainConfig,
+    material: &[u8],
+    origin: &Path,
+    pin_checksum: bool,
+) -> LockchainResult<WorkflowReport> {
+    let mut events = Vec::new();
+    let (key, was_hex) = crate::keyfile::decode_key_bytes(origin, material)?;
+    let target = config.key_hex_path();
+
+    crate::keyfile::replace_raw_key_file(&target, &key)?;
+    events.push(event(
+        WorkflowLevel::Security,
+        format!(
+            "Wrote 32-byte key from {} to {} (permissions 0400)",
+            origin.display(),
+            target.display()
+        ),
+    ));
+    if was_hex {
+        events.push(event(
+            WorkflowLevel::Info,
+            "Input was hex encoded; stored as raw bytes.",
+        ));
+    }
+
+    let digest = hex::encode(Sha256::digest(&key[..]));
+    events.push(event(
+        WorkflowLevel::Info,
+        format!("SHA-256 of imported key: {digest}"),
+    ));
+    let pinned = config.usb.expected_sha256.as_deref();
+    if pinned == Some(digest.as_str()) {
+        events.push(event(
+            WorkflowLevel::Success,
+            "Key matches usb.expected_sha256.",
+        ));
+    } else if pin_checksum {
+        config.pin_key_checksum(None, digest);
+        config.save()?;
+        events.push(event(
+            WorkflowLevel::Success,
+            format!("Pinned usb.expected_sha256 in {}", config.path.display()),
+        ));
+    } else if pinned.is_some() {
+        events.push(event(
+            WorkflowLevel::Warn,
+            "Key does not match usb.expected_sha256; unlocks will refuse it until the checksum is updated.",
+        ));
+    }
+
+    Ok(WorkflowReport {
+        title: format!("Imported key material to {}", target.display()),
+        events,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_normalises_hex_and_pins_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("lockchain.key");
+        crate::keyfile::write_raw_key_file(&key_path, &[1u8; 32]).unwrap();
+
+        let mut config = LockchainConfig::starter(
+            dir.path().join("lockchain-zfs.toml"),
+            vec!["tank/secure".into()],
+        );
+        config.usb.key_hex_path = key_path.display().to_string();
+        config.usb.expected_sha256 = Some(hex::encode(Sha256::digest([1u8; 32])));
+
+        let hex_input = format!("{}\n", "ab".repeat(32));
+        let report =
+            import_key(&mut config, hex_input.as_bytes(), Path::new("<hex>"), false).unwrap();
+        assert_eq!(fs::read(&key_path).unwrap(), vec![0xab; 32]);
+        let mode = fs::metadata(&key_path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o400);
+        assert_eq!(report.events.last().unwrap().level, WorkflowLevel::Warn);
+
+        import_key(&mut config, &[0xab; 32], Path::new("<stdin>"), true).unwrap();
+        let expected = hex::encode(Sha256::digest([0xab; 32]));
+        assert_eq!(
+            config.usb.expected_sha256.as_deref(),
+            Some(expected.as_str())
+        );
+        let saved = LockchainConfig::load(dir.path().join("lockchain-zfs.toml")).unwrap();
+        assert_eq!(saved.usb.expected_sha256, Some(expected));
+
+        assert!(import_key(&mut config, b"not a key", Path::new("<hex>"), false).is_err());
+        assert_eq!(fs::read(&key_path).unwrap(), vec![0xab; 32]);
+        let leftovers = fs::read_dir(dir.path())
+            .unwrap()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(".lockchain-key-")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[test]
+    fn timed_steps_report_outcome_at_debug_level() {
+        let mut events = Vec::new();
+        assert_eq!(timed(&mut events, "zfs list", || Ok(7)).unwrap(), 7);
+        let failed: LockchainResult<()> = timed(&mut events, "zpool create", || {
+            Err(LockchainError::Provider("no space".into()))
+        });
+        assert!(failed.is_err());
+        assert!(events.iter().all(|e| e.level == WorkflowLevel::Debug));
+        assert!(events[0].message.starts_with("zfs list: ok after"));
+        assert!(events[1].message.starts_with("zpool create: failed after"));
+    }
+}
@@ -0,0 +1,149 @@
+//! Parameter schema for the workflows both front ends expose, so the CLI
+//! subcommands and the Control Deck forms offer the same options under the
+//! same names and help text.
+
+use serde::Serialize;
+
+/// What kind of value a parameter takes; front ends pick their widget from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParamKind {
+    /// A managed dataset; left empty, the first entry in policy.datasets.
+    Dataset,
+    /// A removable block device such as `/dev/sdb1`.
+    Device,
+    /// A filesystem path.
+    Path,
+    /// Free text.
+    Text,
+    /// A size such as `512MiB`, read with [`crate::units::parse_size`].
+    Size,
+    /// An on/off switch, off unless given.
+    Flag,
+}
+
+/// One workflow parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ParamSpec {
+    /// Argument id: the CLI field name, spelled with dashes as a long flag.
+    pub name: &'static str,
+    /// Short label for forms.
+    pub label: &'static str,
+    pub kind: ParamKind,
+    /// Help text, the same as the CLI's `--help` line.
+    pub help: &'static str,
+    /// What an empty value falls back to, when that is a fixed value.
+    pub default: Option<&'static str>,
+}
+
+const fn param(
+    name: &'static str,
+    label: &'static str,
+    kind: ParamKind,
+    help: &'static str,
+    default: Option<&'static str>,
+) -> ParamSpec {
+    ParamSpec {
+        name,
+        label,
+        kind,
+        help,
+        default,
+    }
+}
+
+/// `lockchain init` and the deck's New Key directives.
+pub const FORGE_PARAMS: &[ParamSpec] = &[
+    param(
+        "dataset",
+        "Dataset",
+        ParamKind::Dataset,
+        "Target dataset; defaults to the first entry in policy.datasets.",
+        None,
+    ),
+    param(
+        "device",
+        "USB device",
+        ParamKind::Device,
+        "USB block device (e.g. /dev/sdb1). When omitted, autodetect via label/UUID.",
+        None,
+    ),
+    param(
+        "mount",
+        "Mountpoint",
+        ParamKind::Path,
+        "Mountpoint used during provisioning.",
+        Some("/run/lockchain"),
+    ),
+    param(
+        "filename",
+        "Key filename",
+        ParamKind::Text,
+        "Filename to write inside the mounted token (default: lockchain.key).",
+        Some("lockchain.key"),
+    ),
+    param(
+        "force_wipe",
+        "Force wipe",
+        ParamKind::Flag,
+        "Force a wipe even in safe mode.",
+        None,
+    ),
+    param(
+        "no_rebuild",
+        "Skip initramfs rebuild",
+        ParamKind::Flag,
+        "Skip initramfs rebuild after provisioning.",
+        None,
+    ),
+];
+
+/// `lockchain self-test` and the deck's Self-test directive.
+pub const SELF_TEST_PARAMS: &[ParamSpec] = &[
+    param(
+        "dataset",
+        "Dataset",
+        ParamKind::Dataset,
+        "Dataset to validate; defaults to the first entry in policy.datasets.",
+        None,
+    ),
+    param(
+        "strict_usb",
+        "Strict USB",
+        ParamKind::Flag,
+        "Require the USB token and skip fallback handling during the drill.",
+        None,
+    ),
+    param(
+        "pool_size",
+        "Scratch pool size",
+        ParamKind::Size,
+        "Size of the file backing the scratch pool (e.g. `512MiB`, at least 64MiB).",
+        Some("256MiB"),
+    ),
+    param(
+        "keep_on_failure",
+        "Keep on failure",
+        ParamKind::Flag,
+        "Leave the scratch pool and its backing file in place if a step fails.",
+        None,
+    ),
+];
+
+/// `lockchain breakglass` and the deck's Recover Key directive.
+pub const BREAKGLASS_PARAMS: &[ParamSpec] = &[
+    param(
+        "dataset",
+        "Dataset",
+        ParamKind::Dataset,
+        "Dataset to target; defaults to the first entry in policy.datasets.",
+        None,
+    ),
+    param(
+        "output",
+        "Output file",
+        ParamKind::Path,
+        "File path to write the derived key material to.",
+        None,
+    ),
+];
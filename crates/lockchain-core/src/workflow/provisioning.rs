@@ -1,20 +1,22 @@
 //! Provisioning workflow that wipes, seeds, and configures the USB key token.
 
-use super::{event, WorkflowEvent, WorkflowLevel, WorkflowReport};
-use crate::config::{LockchainConfig, Usb};
+use super::{event, timed, EventSink, EventStream, WorkflowEvent, WorkflowLevel, WorkflowReport};
+use crate::audit::{AuditEntry, AuditEvent, AuditLog};
+use crate::config::{DatasetKey, LockchainConfig, Usb};
 use crate::error::{LockchainError, LockchainResult};
-use crate::keyfile::write_raw_key_file;
+use crate::keyfile::{read_key_file, write_raw_key_file};
 use crate::provider::ZfsProvider;
-use pbkdf2::pbkdf2_hmac;
+use crate::secret::SecretBuffer;
 use rand::rngs::OsRng;
 use rand::RngCore;
+use serde::Serialize;
 use sha2::{Digest, Sha256};
 use std::ffi::OsString;
 use std::fs;
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use zeroize::Zeroizing;
+use std::time::Duration;
 
 const LOCKCHAIN_LABEL: &str = "LOCKCHAINKEY";
 const DEFAULT_MOUNTPOINT: &str = "/run/lockchain";
@@ -66,48 +68,81 @@ impl Default for ProvisionOptions {
 }
 
 /// Prepare the USB token, generate new key material, and refresh integration assets.
+///
+/// Every attempt is recorded as a `provision` event in the audit trail.
 pub fn forge_key<P: ZfsProvider + Clone>(
     config: &mut LockchainConfig,
     provider: &P,
     dataset: &str,
     mode: ForgeMode,
-    mut options: ProvisionOptions,
+    options: ProvisionOptions,
 ) -> LockchainResult<WorkflowReport> {
-    let mut events = Vec::new();
+    forge_key_observed(config, provider, dataset, mode, options, |_| {})
+}
 
-    if !config.contains_dataset(dataset) {
-        return Err(LockchainError::DatasetNotConfigured(dataset.to_string()));
-    }
+/// Like [`forge_key`], calling `on_event` as each step reports its outcome.
+pub fn forge_key_observed<P, F>(
+    config: &mut LockchainConfig,
+    provider: &P,
+    dataset: &str,
+    mode: ForgeMode,
+    options: ProvisionOptions,
+    on_event: F,
+) -> LockchainResult<WorkflowReport>
+where
+    P: ZfsProvider + Clone,
+    F: FnMut(&WorkflowEvent),
+{
+    let audit = AuditLog::new(&config.audit);
+    let result = forge_key_inner(config, provider, dataset, mode, options, on_event);
+    let key_source = config
+        .key_source(dataset, dataset)
+        .path
+        .display()
+        .to_string();
+    audit.record_or_warn(
+        AuditEntry::from_result(AuditEvent::Provision, dataset, &result)
+            .with_key_source(Some(format!("usb:{key_source}"))),
+    );
+    result
+}
 
-    let encryption_root = provider.encryption_root(dataset)?;
+fn forge_key_inner<P, F>(
+    config: &mut LockchainConfig,
+    provider: &P,
+    dataset: &str,
+    mode: ForgeMode,
+    mut options: ProvisionOptions,
+    on_event: F,
+) -> LockchainResult<WorkflowReport>
+where
+    P: ZfsProvider + Clone,
+    F: FnMut(&WorkflowEvent),
+{
+    let mut events = EventStream::new(on_event);
+    let plan = plan_forge(config, provider, dataset, mode, &options)?;
     events.push(event(
         WorkflowLevel::Info,
-        format!("Encryption root resolved to {encryption_root}"),
+        format!("Encryption root resolved to {}", plan.encryption_root),
     ));
-
-    let locked_descendants = provider.locked_descendants(&encryption_root)?;
-    if locked_descendants.iter().any(|ds| ds == &encryption_root) {
-        return Err(LockchainError::Provider(format!(
-            "encryption root {encryption_root} is still locked; unlock before forging a new key"
-        )));
+    if let Some(name) = &plan.key_entry {
+        events.push(event(
+            WorkflowLevel::Info,
+            format!("Dataset key is managed by usb.keys.\"{name}\""),
+        ));
     }
-
-    let usb_device = resolve_usb_device(&options, config)?;
     events.push(event(
         WorkflowLevel::Info,
-        format!("Using USB device {usb_device}"),
+        format!("Using USB device {}", plan.device),
     ));
-
-    let (usb_disk, usb_partition) = derive_device_layout(&usb_device)?;
     events.push(event(
         WorkflowLevel::Info,
-        format!("Disk {usb_disk} partition {usb_partition} selected"),
+        format!("Disk {} partition {} selected", plan.disk, plan.partition),
     ));
 
-    let safe_mode = matches!(mode, ForgeMode::Safe);
-
-    if options.force_wipe || !safe_mode {
-        wipe_usb_token(&usb_disk, &usb_partition)?;
+    let usb_partition = &plan.partition;
+    if plan.wipe {
+        wipe_usb_token(&plan.disk, usb_partition, &mut events)?;
         events.push(event(
             WorkflowLevel::Success,
             format!(
@@ -116,7 +151,7 @@ pub fn forge_key<P: ZfsProvider + Clone>(
             ),
         ));
     } else {
-        ensure_partition_label(&usb_partition)?;
+        ensure_partition_label(usb_partition)?;
         events.push(event(
             WorkflowLevel::Info,
             format!(
@@ -126,35 +161,37 @@ pub fn forge_key<P: ZfsProvider + Clone>(
         ));
     }
 
-    settle_udev()?;
+    timed(&mut events, "udevadm settle", settle_udev)?;
 
-    let mountpoint = options
-        .mountpoint
-        .clone()
-        .unwrap_or_else(|| PathBuf::from(DEFAULT_MOUNTPOINT));
-    let filename = options
-        .key_filename
-        .clone()
+    let mountpoint = &plan.mountpoint;
+    let key_path = &plan.key_path;
+    let filename = key_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
         .unwrap_or_else(|| DEFAULT_KEY_FILENAME.to_string());
-    let key_path = mountpoint.join(&filename);
 
-    fs::create_dir_all(&mountpoint)?;
+    fs::create_dir_all(mountpoint)?;
 
-    let mount_guard = MountGuard::mount(&usb_partition, &mountpoint)?;
+    let mount_guard = timed(
+        &mut events,
+        format!("mount {usb_partition} {}", mountpoint.display()),
+        || MountGuard::mount(usb_partition, mountpoint),
+    )?;
     events.push(event(
         WorkflowLevel::Info,
         format!("Mounted {} at {}", usb_partition, mountpoint.display()),
     ));
 
-    let mut key_material = vec![0u8; 32];
+    let mut key_material = SecretBuffer::zeroed(32);
     OsRng.fill_bytes(&mut key_material);
-    write_raw_key_file(&key_path, &key_material)?;
+    write_raw_key_file(key_path, &key_material)?;
     events.push(event(
         WorkflowLevel::Success,
         format!("Wrote key material to {}", key_path.display()),
     ));
 
-    let digest = hex::encode(Sha256::digest(&key_material));
+    let digest = hex::encode(Sha256::digest(&key_material[..]));
+    audit_token_keys(config, mountpoint, &filename, &mut events);
 
     mount_guard.sync()?; // flush writes before unmount
     drop(mount_guard); // unmount
@@ -166,11 +203,12 @@ pub fn forge_key<P: ZfsProvider + Clone>(
         &key_material,
     )?;
 
-    let device_uuid = detect_partition_uuid(&usb_partition).ok().flatten();
+    let device_uuid = detect_partition_uuid(usb_partition).ok().flatten();
 
     update_config(
         config,
         dataset,
+        plan.key_entry.as_deref(),
         key_path.clone(),
         digest.clone(),
         device_uuid,
@@ -184,8 +222,8 @@ pub fn forge_key<P: ZfsProvider + Clone>(
         ),
     ));
 
-    install_dracut_module(&key_path, Some(&digest), &mut events)?;
-    if options.rebuild_initramfs {
+    install_dracut_module(config, key_path, Some(&digest), &mut events)?;
+    if plan.rebuild_initramfs {
         rebuild_initramfs(&mut events)?;
         audit_initramfs(&mut events)?;
     } else {
@@ -197,10 +235,298 @@ pub fn forge_key<P: ZfsProvider + Clone>(
 
     Ok(WorkflowReport {
         title: format!("Forged new key for {dataset}"),
-        events,
+        events: events.into_events(),
     })
 }
 
+/// What [`forge_key`] is about to do, resolved before anything is touched so
+/// it can be reviewed with `init --dry-run`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ForgePlan {
+    pub dataset: String,
+    pub encryption_root: String,
+    /// `usb.keys` entry that owns the key, when the dataset has its own.
+    pub key_entry: Option<String>,
+    pub device: String,
+    pub disk: String,
+    pub partition: String,
+    /// Repartition and format `disk`; safe mode keeps the existing filesystem.
+    pub wipe: bool,
+    pub mountpoint: PathBuf,
+    pub key_path: PathBuf,
+    /// Mask the key under a fallback passphrase; otherwise fallback is disabled.
+    pub fallback_passphrase: bool,
+    pub config_path: PathBuf,
+    pub dracut_module_dir: PathBuf,
+    pub rebuild_initramfs: bool,
+}
+
+impl ForgePlan {
+    /// One line per step, in the order they run.
+    pub fn steps(&self) -> Vec<String> {
+        let mut steps = Vec::new();
+        if self.wipe {
+            steps.push(format!(
+                "Unmount everything on {} and write a new GPT label; all data on {} is destroyed",
+                self.disk, self.disk
+            ));
+            steps.push(format!(
+                "Create partition {} (LOCKCHAIN_PART, 1MiB to end) and format it ext4 with label {LOCKCHAIN_LABEL}",
+                self.partition
+            ));
+        } else {
+            steps.push(format!(
+                "Keep the filesystem on {} and require its label to be {LOCKCHAIN_LABEL}",
+                self.partition
+            ));
+        }
+        steps.push(format!(
+            "Mount {} at {}",
+            self.partition,
+            self.mountpoint.display()
+        ));
+        steps.push(format!(
+            "Write 32 random bytes to {} (mode 0400), then unmount",
+            self.key_path.display()
+        ));
+        steps.push(if self.fallback_passphrase {
+            "Mask the key under the fallback passphrase (PBKDF2-HMAC-SHA256, 250000 iterations)"
+                .to_string()
+        } else {
+            "Disable the fallback passphrase (no --passphrase given)".to_string()
+        });
+        steps.push(match &self.key_entry {
+            Some(name) => format!(
+                "Record usb.keys.\"{name}\" path and checksum in {}",
+                self.config_path.display()
+            ),
+            None => format!(
+                "Point usb.key_hex_path at {} and pin usb.expected_sha256 in {}",
+                self.key_path.display(),
+                self.config_path.display()
+            ),
+        });
+        steps.push(format!(
+            "Install the dracut module in {} (lockchain-load-key.service plus zfs-load-key/zfs-load-module drop-ins)",
+            self.dracut_module_dir.display()
+        ));
+        steps.push(if self.rebuild_initramfs {
+            "Rebuild the initramfs (dracut -f, else update-initramfs -u) and check it with lsinitrd"
+                .to_string()
+        } else {
+            "Skip the initramfs rebuild".to_string()
+        });
+        steps
+    }
+}
+
+/// Resolve the dataset, token device, and paths [`forge_key`] would use,
+/// without writing anything.
+///
+/// Fails for the same reasons the real run would fail before touching the
+/// token: unknown dataset, locked encryption root, or no usable device.
+pub fn plan_forge<P: ZfsProvider>(
+    config: &LockchainConfig,
+    provider: &P,
+    dataset: &str,
+    mode: ForgeMode,
+    options: &ProvisionOptions,
+) -> LockchainResult<ForgePlan> {
+    if !config.contains_dataset(dataset) {
+        return Err(LockchainError::DatasetNotConfigured(dataset.to_string()));
+    }
+
+    let encryption_root = provider.encryption_root(dataset)?;
+    let key_entry = [dataset, encryption_root.as_str()]
+        .into_iter()
+        .find(|name| config.usb.keys.contains_key(*name))
+        .map(str::to_string);
+
+    let locked_descendants = provider.locked_descendants(&encryption_root)?;
+    if locked_descendants.iter().any(|ds| ds == &encryption_root) {
+        return Err(LockchainError::Provider(format!(
+            "encryption root {encryption_root} is still locked; unlock before forging a new key"
+        )));
+    }
+
+    let device = resolve_usb_device(options, config)?;
+    let (disk, partition) = derive_device_layout(&device)?;
+
+    let mountpoint = options
+        .mountpoint
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_MOUNTPOINT));
+    let filename = options
+        .key_filename
+        .clone()
+        .or_else(|| {
+            let entry = config.usb.keys.get(key_entry.as_deref()?)?;
+            Path::new(entry.path())
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| DEFAULT_KEY_FILENAME.to_string());
+    let key_path = mountpoint.join(&filename);
+
+    Ok(ForgePlan {
+        dataset: dataset.to_string(),
+        encryption_root,
+        key_entry,
+        device,
+        disk,
+        partition,
+        wipe: options.force_wipe || mode == ForgeMode::Standard,
+        mountpoint,
+        key_path,
+        fallback_passphrase: options.passphrase.is_some(),
+        config_path: config.path.clone(),
+        dracut_module_dir: determine_module_dir(),
+        rebuild_initramfs: options.rebuild_initramfs,
+    })
+}
+
+/// Erase `device` and leave it as an empty ext4 token labelled `LOCKCHAINKEY`,
+/// without generating key material or touching the configuration.
+///
+/// With `passes > 0` the whole disk is first overwritten that many times with
+/// random data. Devices holding `/`, `/boot`, or `/usr`, or a ZFS vdev, LVM
+/// physical volume, RAID member, or swap are refused, and so are disks that
+/// are neither removable nor hotplugged unless `allow_fixed` is set.
+pub fn wipe_token(device: &str, passes: u32, allow_fixed: bool) -> LockchainResult<WorkflowReport> {
+    wipe_token_observed(device, passes, allow_fixed, |_| {})
+}
+
+/// Like [`wipe_token`], calling `on_event` as each step reports its outcome.
+pub fn wipe_token_observed<F>(
+    device: &str,
+    passes: u32,
+    allow_fixed: bool,
+    on_event: F,
+) -> LockchainResult<WorkflowReport>
+where
+    F: FnMut(&WorkflowEvent),
+{
+    let mut events = EventStream::new(on_event);
+    let (disk, partition) = derive_device_layout(device)?;
+    refuse_system_disk(&disk, allow_fixed)?;
+    events.push(event(
+        WorkflowLevel::Info,
+        format!("Disk {disk} partition {partition} selected"),
+    ));
+
+    if passes > 0 {
+        dismantle_mounts(&disk)?;
+        for pass in 1..=passes {
+            let written = timed(
+                &mut events,
+                format!("overwrite pass {pass} on {disk}"),
+                || overwrite_random(Path::new(&disk)),
+            )?;
+            events.push(event(
+                WorkflowLevel::Security,
+                format!(
+                    "Pass {pass}/{passes}: overwrote {} MiB of {disk} with random data",
+                    written / (1024 * 1024)
+                ),
+            ));
+        }
+    }
+
+    wipe_usb_token(&disk, &partition, &mut events)?;
+    events.push(event(
+        WorkflowLevel::Success,
+        format!("Reinitialised {disk} with an empty {LOCKCHAIN_LABEL} filesystem on {partition}"),
+    ));
+    events.push(event(
+        WorkflowLevel::Info,
+        "Run `lockchain init` or `lockchain import-key` to put key material on it.",
+    ));
+
+    Ok(WorkflowReport {
+        title: format!("Wiped token {disk}"),
+        events: events.into_events(),
+    })
+}
+
+/// Filesystem types whose disks belong to a pool, volume group, array, or swap.
+const MEMBER_FSTYPES: &[&str] = &["zfs_member", "LVM2_member", "linux_raid_member", "swap"];
+
+/// Fail when `disk` carries something the running system depends on, or is
+/// a fixed disk and `allow_fixed` is not set.
+fn refuse_system_disk(disk: &str, allow_fixed: bool) -> LockchainResult<()> {
+    let output = run_external(
+        LSBLK_BINARIES,
+        &[
+            OsString::from("-P"),
+            OsString::from("-p"),
+            OsString::from("-o"),
+            OsString::from("PATH,TYPE,MOUNTPOINT,FSTYPE,RM,HOTPLUG,TRAN"),
+            OsString::from(disk),
+        ],
+    )?;
+    if !output.status.success() {
+        return Err(LockchainError::Provider(format!(
+            "lsblk {disk} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    match system_disk_reason(&String::from_utf8_lossy(&output.stdout), allow_fixed) {
+        Some(reason) => Err(LockchainError::InvalidConfig(format!(
+            "{disk} {reason}; refusing to wipe it"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Why the disk listed first in `lsblk -P` output (its partitions after it)
+/// must not be wiped, if it must not.
+fn system_disk_reason(listing: &str, allow_fixed: bool) -> Option<String> {
+    let rows: Vec<Vec<(String, String)>> = listing.lines().map(lsblk_pairs).collect();
+    let field = |row: &[(String, String)], key: &str| {
+        row.iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.trim().to_string())
+            .unwrap_or_default()
+    };
+    for row in &rows {
+        let path = field(row, "PATH");
+        let mount = field(row, "MOUNTPOINT");
+        if ["/", "/boot", "/usr"].contains(&mount.as_str()) || mount.starts_with("/boot/") {
+            return Some(format!("holds {mount} on {path}"));
+        }
+        let fstype = field(row, "FSTYPE");
+        if MEMBER_FSTYPES.contains(&fstype.as_str()) {
+            return Some(format!("holds a {fstype} on {path}"));
+        }
+    }
+    let disk = rows.first()?;
+    let removable =
+        field(disk, "RM") == "1" || field(disk, "HOTPLUG") == "1" || field(disk, "TRAN") == "usb";
+    (!removable && !allow_fixed).then(|| {
+        "is neither removable nor hotplugged (pass --allow-fixed-disk if it really is the token)"
+            .to_string()
+    })
+}
+
+/// Fill the whole block device with random bytes and return how many were written.
+fn overwrite_random(disk: &Path) -> LockchainResult<u64> {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let mut file = fs::OpenOptions::new().write(true).open(disk)?;
+    let size = file.seek(SeekFrom::End(0))?;
+    file.seek(SeekFrom::Start(0))?;
+    let mut chunk = vec![0u8; 1024 * 1024];
+    let mut written = 0u64;
+    while written < size {
+        let len = chunk.len().min((size - written) as usize);
+        OsRng.fill_bytes(&mut chunk[..len]);
+        file.write_all(&chunk[..len])?;
+        written += len as u64;
+    }
+    file.sync_all()?;
+    Ok(written)
+}
+
 /// Determine which block device to operate on, using CLI options or config hints.
 fn resolve_usb_device(
     options: &ProvisionOptions,
@@ -209,8 +535,13 @@ fn resolve_usb_device(
     if let Some(device) = options.usb_device.as_ref() {
         return Ok(device.clone());
     }
+    if let Some(uuid) = config.usb.device_uuid.as_ref() {
+        if let Some(device) = device_from_blkid("-U", uuid)? {
+            return Ok(device);
+        }
+    }
     if let Some(label) = config.usb.device_label.as_ref() {
-        if let Some(device) = device_from_label(label)? {
+        if let Some(device) = device_from_blkid("-L", label)? {
             return Ok(device);
         }
     }
@@ -219,12 +550,13 @@ fn resolve_usb_device(
     ))
 }
 
-/// Probe blkid for a device matching the requested filesystem label.
-fn device_from_label(label: &str) -> LockchainResult<Option<String>> {
+/// Probe blkid for a device with the filesystem label (`-L`) or UUID (`-U`)
+/// `value`.
+fn device_from_blkid(flag: &str, value: &str) -> LockchainResult<Option<String>> {
     for candidate in BLKID_BINARIES {
         if Path::new(candidate).exists() {
             let output = Command::new(candidate)
-                .args(["-L", label])
+                .args([flag, value])
                 .stderr(Stdio::null())
                 .output();
             if let Ok(out) = output {
@@ -274,6 +606,136 @@ fn derive_device_layout(device: &str) -> LockchainResult<(String, String)> {
     }
 }
 
+/// A whole disk that could take a key token: removable, hot-pluggable, or
+/// attached over USB.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RemovableDevice {
+    /// Disk node, e.g. `/dev/sdb`.
+    pub path: String,
+    pub size_bytes: u64,
+    /// Filesystem label of the disk or its first labelled partition.
+    pub label: Option<String>,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+}
+
+impl std::fmt::Display for RemovableDevice {
+    /// One-line summary, e.g. `/dev/sdb · 14.9 GiB · LOCKCHAINKEY · SanDisk Ultra (4C53)`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} · {:.1} GiB",
+            self.path,
+            self.size_bytes as f64 / (1u64 << 30) as f64
+        )?;
+        if let Some(label) = &self.label {
+            write!(f, " · {label}")?;
+        }
+        if let Some(model) = &self.model {
+            write!(f, " · {model}")?;
+        }
+        if let Some(serial) = &self.serial {
+            write!(f, " ({serial})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Candidate key token disks currently attached, in `lsblk` order.
+pub fn removable_devices() -> LockchainResult<Vec<RemovableDevice>> {
+    let args = vec![
+        OsString::from("-P"),
+        OsString::from("-b"),
+        OsString::from("-o"),
+        OsString::from("PATH,PKNAME,TYPE,RM,HOTPLUG,TRAN,SIZE,LABEL,MODEL,SERIAL"),
+    ];
+    let output = run_external(LSBLK_BINARIES, &args)?;
+    if !output.status.success() {
+        return Err(LockchainError::Provider(format!(
+            "lsblk failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(parse_removable_devices(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+/// Pick removable disks out of `lsblk -P` output, borrowing a label from
+/// their partitions when the disk itself has none.
+fn parse_removable_devices(listing: &str) -> Vec<RemovableDevice> {
+    let rows: Vec<Vec<(String, String)>> = listing.lines().map(lsblk_pairs).collect();
+    let field = |row: &[(String, String)], key: &str| {
+        row.iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    let mut devices = Vec::new();
+    for row in &rows {
+        if field(row, "TYPE").as_deref() != Some("disk") {
+            continue;
+        }
+        let removable = field(row, "RM").as_deref() == Some("1")
+            || field(row, "HOTPLUG").as_deref() == Some("1")
+            || field(row, "TRAN").as_deref() == Some("usb");
+        let Some(path) = field(row, "PATH").filter(|_| removable) else {
+            continue;
+        };
+        let kernel_name = path.trim_start_matches("/dev/");
+        let label = field(row, "LABEL").or_else(|| {
+            rows.iter()
+                .filter(|child| field(child, "PKNAME").as_deref() == Some(kernel_name))
+                .find_map(|child| field(child, "LABEL"))
+        });
+        devices.push(RemovableDevice {
+            size_bytes: field(row, "SIZE")
+                .and_then(|size| size.parse().ok())
+                .unwrap_or(0),
+            label,
+            model: field(row, "MODEL"),
+            serial: field(row, "SERIAL"),
+            path,
+        });
+    }
+    devices
+}
+
+/// Split one `lsblk -P` line into `KEY="value"` pairs, undoing `\xNN` escapes.
+fn lsblk_pairs(line: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut rest = line.trim();
+    while let Some((key, tail)) = rest.split_once("=\"") {
+        let Some(end) = tail.find('"') else {
+            break;
+        };
+        pairs.push((key.trim().to_string(), unescape_lsblk(&tail[..end])));
+        rest = &tail[end + 1..];
+    }
+    pairs
+}
+
+fn unescape_lsblk(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            let escape: String = chars.clone().take(3).collect();
+            if let Some(byte) = escape
+                .strip_prefix('x')
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                out.push(byte as char);
+                chars.nth(2);
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
 /// Run `lsblk` for a single field and normalise the output.
 fn query_block_info(device: &str, field: &str) -> LockchainResult<String> {
     let args = vec![
@@ -373,11 +835,12 @@ fn ensure_partition_label(partition: &str) -> LockchainResult<()> {
 }
 
 /// Repartition and format the USB device with a fresh ext4 filesystem.
-fn wipe_usb_token(disk: &str, partition: &str) -> LockchainResult<()> {
+fn wipe_usb_token(disk: &str, partition: &str, events: &mut impl EventSink) -> LockchainResult<()> {
     dismantle_mounts(disk)?;
     dismantle_mounts(partition)?;
 
-    run_external(
+    run_traced(
+        events,
         PARTED_BINARIES,
         &[
             OsString::from("-s"),
@@ -386,7 +849,8 @@ fn wipe_usb_token(disk: &str, partition: &str) -> LockchainResult<()> {
             OsString::from("gpt"),
         ],
     )?;
-    run_external(
+    run_traced(
+        events,
         PARTED_BINARIES,
         &[
             OsString::from("-s"),
@@ -398,8 +862,9 @@ fn wipe_usb_token(disk: &str, partition: &str) -> LockchainResult<()> {
             OsString::from("100%"),
         ],
     )?;
-    settle_udev()?;
-    run_external(
+    timed(events, "udevadm settle", settle_udev)?;
+    run_traced(
+        events,
         MKFS_BINARIES,
         &[
             OsString::from("-F"),
@@ -465,28 +930,16 @@ fn detect_partition_uuid(partition: &str) -> LockchainResult<Option<String>> {
 
 /// Optionally seed fallback passphrase material based on supplied input.
 fn configure_fallback_passphrase(
-    events: &mut Vec<WorkflowEvent>,
+    events: &mut impl EventSink,
     config: &mut LockchainConfig,
     passphrase: Option<String>,
     key_material: &[u8],
 ) -> LockchainResult<()> {
     if let Some(passphrase) = passphrase {
-        let mut salt = [0u8; 16];
-        OsRng.fill_bytes(&mut salt);
-
-        let mut derived = Zeroizing::new(vec![0u8; key_material.len()]);
-        pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), &salt, 250_000, &mut derived);
-
-        let xor: Vec<u8> = key_material
-            .iter()
-            .zip(derived.iter())
-            .map(|(a, b)| a ^ b)
-            .collect();
-
         config.fallback.enabled = true;
-        config.fallback.passphrase_salt = Some(hex::encode(salt));
-        config.fallback.passphrase_xor = Some(hex::encode(xor));
-        config.fallback.passphrase_iters = 250_000;
+        config
+            .fallback
+            .remask(passphrase.as_bytes(), key_material, 250_000);
         events.push(event(
             WorkflowLevel::Security,
             "Fallback passphrase material generated.",
@@ -500,10 +953,27 @@ fn configure_fallback_passphrase(
     Ok(())
 }
 
+/// Point the config at the token just written: by UUID when blkid reported
+/// one, else by the label forge gives it. Only one selector is kept, so the
+/// two cannot disagree after the token is re-labelled.
+fn select_token(usb: &mut Usb, device_uuid: Option<String>) {
+    match device_uuid {
+        Some(uuid) => {
+            usb.device_uuid = Some(uuid);
+            usb.device_label = None;
+        }
+        None => {
+            usb.device_label = Some(LOCKCHAIN_LABEL.to_string());
+            usb.device_uuid = None;
+        }
+    }
+}
+
 /// Persist the new key metadata and sane defaults back into the config file.
 fn update_config(
     config: &mut LockchainConfig,
     dataset: &str,
+    key_entry: Option<&str>,
     key_path: PathBuf,
     checksum: String,
     device_uuid: Option<String>,
@@ -517,14 +987,40 @@ fn update_config(
         .and_then(|f| f.to_str())
         .unwrap_or(DEFAULT_KEY_FILENAME)
         .to_string();
+
+    if let Some(name) = key_entry {
+        // Only this dataset's entry changes; the shared key and its checksum stay put.
+        let shared_dir = config.key_hex_path().parent().map(Path::to_path_buf);
+        let path = if key_path.parent() == shared_dir.as_deref() {
+            file_name
+        } else {
+            key_path.to_string_lossy().into_owned()
+        };
+        config.usb.keys.insert(
+            name.to_string(),
+            DatasetKey::Detailed {
+                path,
+                sha256: Some(checksum),
+            },
+        );
+        select_token(&mut config.usb, device_uuid);
+        config.save()?;
+        return Ok(());
+    }
+
     config.usb = Usb {
         key_hex_path: key_path.to_string_lossy().into_owned(),
         expected_sha256: Some(checksum),
-        device_label: Some(LOCKCHAIN_LABEL.to_string()),
-        device_uuid,
+        device_label: None,
+        device_uuid: None,
         device_key_path: file_name,
-        mount_timeout_secs: config.usb.mount_timeout_secs.max(10),
+        mount_timeout: config.usb.mount_timeout.max(Duration::from_secs(10)),
+        key_cache_ttl: config.usb.key_cache_ttl,
+        keys: std::mem::take(&mut config.usb.keys),
+        tokens: std::mem::take(&mut config.usb.tokens),
+        removal: config.usb.removal.clone(),
     };
+    select_token(&mut config.usb, device_uuid);
 
     if config.policy.binary_path.is_none() {
         config.policy.binary_path = Some("/usr/bin/lockchain-cli".to_string());
@@ -538,6 +1034,52 @@ fn update_config(
     Ok(())
 }
 
+/// Check the other key files configured for the token while it is mounted.
+fn audit_token_keys(
+    config: &LockchainConfig,
+    mountpoint: &Path,
+    forged: &str,
+    events: &mut impl EventSink,
+) {
+    for source in config.key_sources() {
+        let Some(name) = source.path.file_name() else {
+            continue;
+        };
+        if name == forged {
+            continue;
+        }
+        let on_token = mountpoint.join(name);
+        match read_key_file(&on_token) {
+            Ok((key, _)) => {
+                let digest = hex::encode(Sha256::digest(&key[..]));
+                match &source.expected_sha256 {
+                    Some(expected) if !expected.eq_ignore_ascii_case(&digest) => {
+                        events.push(event(
+                            WorkflowLevel::Warn,
+                            format!(
+                                "{} does not match {} (actual {digest})",
+                                on_token.display(),
+                                source.setting()
+                            ),
+                        ))
+                    }
+                    _ => events.push(event(
+                        WorkflowLevel::Success,
+                        format!("Existing key {} verified on token", on_token.display()),
+                    )),
+                }
+            }
+            Err(err) => events.push(event(
+                WorkflowLevel::Warn,
+                format!(
+                    "Configured key {} not readable on token ({err})",
+                    on_token.display()
+                ),
+            )),
+        }
+    }
+}
+
 /// Scratch wrapper around `std::process::Output` for external command wrappers.
 struct CommandOutput {
     stdout: Vec<u8>,
@@ -545,6 +1087,24 @@ struct CommandOutput {
     status: std::process::ExitStatus,
 }
 
+/// [`run_external`], recording the command line and its duration as a `Debug` event.
+fn run_traced(
+    events: &mut impl EventSink,
+    candidates: &[&str],
+    args: &[OsString],
+) -> LockchainResult<CommandOutput> {
+    let mut line = candidates
+        .first()
+        .and_then(|candidate| Path::new(candidate).file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    for arg in args {
+        line.push(' ');
+        line.push_str(&arg.to_string_lossy());
+    }
+    timed(events, line, || run_external(candidates, args))
+}
+
 /// Try each binary in `candidates` until one executes successfully.
 fn run_external(candidates: &[&str], args: &[OsString]) -> LockchainResult<CommandOutput> {
     for candidate in candidates {
@@ -568,23 +1128,39 @@ fn run_external(candidates: &[&str], args: &[OsString]) -> LockchainResult<Comma
 }
 
 /// RAII helper that unmounts the USB device when dropped.
-struct MountGuard {
+pub(super) struct MountGuard {
     mountpoint: PathBuf,
 }
 
 impl MountGuard {
     /// Mount the partition and return a guard that unmounts on drop.
     fn mount(partition: &str, mountpoint: &Path) -> LockchainResult<Self> {
+        Self::mount_with(partition, mountpoint, "defaults")
+    }
+
+    /// Mount the partition read-only so inspection cannot modify the token.
+    pub(super) fn mount_read_only(partition: &str, mountpoint: &Path) -> LockchainResult<Self> {
+        Self::mount_with(partition, mountpoint, "ro")
+    }
+
+    fn mount_with(partition: &str, mountpoint: &Path, options: &str) -> LockchainResult<Self> {
         let mountpoint_str = mountpoint.to_string_lossy().into_owned();
-        run_external(
+        let output = run_external(
             MOUNT_BINARIES,
             &[
                 OsString::from("-o"),
-                OsString::from("defaults"),
+                OsString::from(options),
                 OsString::from(partition),
                 OsString::from(mountpoint_str),
             ],
         )?;
+        if !output.status.success() {
+            return Err(LockchainError::Provider(format!(
+                "mount {partition} at {} failed: {}",
+                mountpoint.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
         Ok(Self {
             mountpoint: mountpoint.to_path_buf(),
         })
@@ -610,11 +1186,13 @@ impl Drop for MountGuard {
     }
 }
 
-/// Stage the dracut hook and systemd drop-ins that load the key during boot.
-fn install_dracut_module(
+/// Stage the dracut hook and systemd drop-ins that load the key during boot,
+/// plus the remote unlock listener when `[remote_unlock]` is configured.
+pub(super) fn install_dracut_module(
+    config: &LockchainConfig,
     key_path: &Path,
     checksum: Option<&str>,
-    events: &mut Vec<WorkflowEvent>,
+    events: &mut impl EventSink,
 ) -> LockchainResult<()> {
     let ctx = DracutContext {
         mountpoint: key_path
@@ -623,23 +1201,59 @@ fn install_dracut_module(
             .unwrap_or_else(|| DEFAULT_MOUNTPOINT.to_string()),
         key_path: key_path.to_string_lossy().into_owned(),
         checksum: checksum.map(|s| s.to_string()),
+        config_path: config.path.to_string_lossy().into_owned(),
+        remote_files: config.remote_unlock.as_ref().map(|remote| {
+            std::iter::once(&config.path)
+                .chain(&config.fragments)
+                .map(|path| path.to_string_lossy().into_owned())
+                .chain(remote.files().map(str::to_string))
+                .collect()
+        }),
     };
     let module = DracutModule::install(&ctx)?;
     events.push(event(
         WorkflowLevel::Info,
         format!("Dracut module installed at {}", module.root.display()),
     ));
+    if let Some(remote) = &config.remote_unlock {
+        events.push(event(
+            WorkflowLevel::Security,
+            format!(
+                "Initramfs listens on {} for a remote unlock of {}; it carries {} and the config",
+                remote.listen, remote.dataset, remote.key
+            ),
+        ));
+    }
     Ok(())
 }
 
+/// Re-stage the dracut hook for the configured key and checksum, then
+/// rebuild and audit the initramfs.
+pub fn refresh_initramfs(config: &LockchainConfig) -> LockchainResult<WorkflowReport> {
+    let mut events = Vec::new();
+    let key_path = config.key_hex_path();
+    install_dracut_module(
+        config,
+        &key_path,
+        config.usb.expected_sha256.as_deref(),
+        &mut events,
+    )?;
+    rebuild_initramfs(&mut events)?;
+    audit_initramfs(&mut events)?;
+    Ok(WorkflowReport {
+        title: "Rebuilt initramfs".into(),
+        events,
+    })
+}
+
 /// Run whichever initramfs tool is available to pick up the new hook.
-fn rebuild_initramfs(events: &mut Vec<WorkflowEvent>) -> LockchainResult<()> {
-    if let Ok(_) = run_external(DRACUT_BINARIES, &[OsString::from("-f")]) {
+pub(super) fn rebuild_initramfs(events: &mut impl EventSink) -> LockchainResult<()> {
+    if run_traced(events, DRACUT_BINARIES, &[OsString::from("-f")]).is_ok() {
         events.push(event(WorkflowLevel::Success, "Dracut rebuild completed."));
         return Ok(());
     }
 
-    if let Ok(_) = run_external(UPDATE_INITRAMFS_BINARIES, &[OsString::from("-u")]) {
+    if run_traced(events, UPDATE_INITRAMFS_BINARIES, &[OsString::from("-u")]).is_ok() {
         events.push(event(
             WorkflowLevel::Success,
             "update-initramfs rebuild completed.",
@@ -653,7 +1267,7 @@ fn rebuild_initramfs(events: &mut Vec<WorkflowEvent>) -> LockchainResult<()> {
 }
 
 /// Inspect the generated initramfs to ensure our assets were included.
-fn audit_initramfs(events: &mut Vec<WorkflowEvent>) -> LockchainResult<()> {
+pub(super) fn audit_initramfs(events: &mut impl EventSink) -> LockchainResult<()> {
     for candidate in LSINITRD_BINARIES {
         if Path::new(candidate).exists() {
             let output = Command::new(candidate)
@@ -699,6 +1313,9 @@ struct DracutContext {
     mountpoint: String,
     key_path: String,
     checksum: Option<String>,
+    config_path: String,
+    /// Files the remote unlock listener needs, when `[remote_unlock]` is set.
+    remote_files: Option<Vec<String>>,
 }
 
 /// Represents the installed dracut module directory.
@@ -729,6 +1346,31 @@ impl DracutModule {
         write_template(&dropin_module, LOCKCHAIN_ZFS_DROPIN_TEMPLATE, ctx, 0o644)?;
         write_template(&setup, LOCKCHAIN_MODULE_SETUP_TEMPLATE, ctx, 0o750)?;
 
+        // module-setup.sh pulls the listener in only when its unit is present.
+        let remote_service = module.join(REMOTE_SERVICE_NAME);
+        let remote_files = module.join(REMOTE_FILES_NAME);
+        match &ctx.remote_files {
+            Some(files) => {
+                write_template(
+                    &remote_service,
+                    LOCKCHAIN_REMOTE_UNLOCK_TEMPLATE,
+                    ctx,
+                    0o644,
+                )?;
+                fs::write(&remote_files, files.join("\n") + "\n")?;
+            }
+            None => {
+                for stale in [&remote_service, &remote_files] {
+                    match fs::remove_file(stale) {
+                        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                            return Err(err.into())
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
         Ok(Self { root: module })
     }
 }
@@ -772,6 +1414,9 @@ fn write_template(
         .replace("{{DROPIN_NAME}}", "lockchain.conf")
         .replace("{{DROPIN_DIR}}", "zfs-load-key.service.d")
         .replace("{{MODULE_DROPIN_DIR}}", "zfs-load-module.service.d")
+        .replace("{{REMOTE_SERVICE_NAME}}", REMOTE_SERVICE_NAME)
+        .replace("{{REMOTE_FILES_NAME}}", REMOTE_FILES_NAME)
+        .replace("{{CONFIG_PATH}}", &ctx.config_path)
         .replace("{{VERSION}}", env!("CARGO_PKG_VERSION"));
 
     fs::write(path, rendered)?;
@@ -786,3 +1431,110 @@ const LOCKCHAIN_ZFS_DROPIN_TEMPLATE: &str =
     include_str!("../../templates/lockchain-zfs-load-module.conf");
 const LOCKCHAIN_MODULE_SETUP_TEMPLATE: &str =
     include_str!("../../templates/lockchain-module-setup.sh");
+const LOCKCHAIN_REMOTE_UNLOCK_TEMPLATE: &str =
+    include_str!("../../templates/lockchain-remote-unlock.service");
+const REMOTE_SERVICE_NAME: &str = "lockchain-remote-unlock.service";
+const REMOTE_FILES_NAME: &str = "lockchain-remote-unlock.files";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_steps_only_destroy_the_disk_when_wiping() {
+        let mut plan = ForgePlan {
+            dataset: "tank/secure".into(),
+            encryption_root: "tank/secure".into(),
+            key_entry: None,
+            device: "/dev/sdz".into(),
+            disk: "/dev/sdz".into(),
+            partition: "/dev/sdz1".into(),
+            wipe: true,
+            mountpoint: PathBuf::from(DEFAULT_MOUNTPOINT),
+            key_path: PathBuf::from("/run/lockchain/lockchain.key"),
+            fallback_passphrase: false,
+            config_path: PathBuf::from("/etc/lockchain-zfs.toml"),
+            dracut_module_dir: PathBuf::from("/usr/lib/dracut/modules.d/90lockchain"),
+            rebuild_initramfs: true,
+        };
+        let steps = plan.steps();
+        assert!(steps[0].contains("all data on /dev/sdz is destroyed"));
+        assert!(steps
+            .iter()
+            .any(|step| step.contains("/run/lockchain/lockchain.key (mode 0400)")));
+
+        plan.wipe = false;
+        plan.rebuild_initramfs = false;
+        let steps = plan.steps();
+        assert!(steps.iter().all(|step| !step.contains("destroyed")));
+        assert_eq!(steps.last().unwrap(), "Skip the initramfs rebuild");
+    }
+
+    #[test]
+    fn removable_disks_are_read_from_lsblk_pairs() {
+        let listing = [
+            r#"PATH="/dev/nvme0n1" PKNAME="" TYPE="disk" RM="0" HOTPLUG="0" TRAN="nvme" SIZE="512110190592" LABEL="" MODEL="Samsung SSD" SERIAL="S1""#,
+            r#"PATH="/dev/sdb" PKNAME="" TYPE="disk" RM="1" HOTPLUG="1" TRAN="usb" SIZE="16008609792" LABEL="" MODEL="SanDisk\x20Ultra" SERIAL="4C53""#,
+            r#"PATH="/dev/sdb1" PKNAME="sdb" TYPE="part" RM="1" HOTPLUG="1" TRAN="" SIZE="16007561216" LABEL="LOCKCHAINKEY" MODEL="" SERIAL="""#,
+        ]
+        .join("\n");
+        let devices = parse_removable_devices(&listing);
+        assert_eq!(
+            devices,
+            vec![RemovableDevice {
+                path: "/dev/sdb".into(),
+                size_bytes: 16008609792,
+                label: Some("LOCKCHAINKEY".into()),
+                model: Some("SanDisk Ultra".into()),
+                serial: Some("4C53".into()),
+            }]
+        );
+        assert_eq!(
+            devices[0].to_string(),
+            "/dev/sdb · 14.9 GiB · LOCKCHAINKEY · SanDisk Ultra (4C53)"
+        );
+    }
+
+    #[test]
+    fn system_disks_are_not_wiped() {
+        let vdev = [
+            r#"PATH="/dev/nvme0n1" TYPE="disk" MOUNTPOINT="" FSTYPE="" RM="0" HOTPLUG="0" TRAN="nvme""#,
+            r#"PATH="/dev/nvme0n1p1" TYPE="part" MOUNTPOINT="/boot/efi" FSTYPE="vfat" RM="0" HOTPLUG="0" TRAN="nvme""#,
+            r#"PATH="/dev/nvme0n1p2" TYPE="part" MOUNTPOINT="" FSTYPE="zfs_member" RM="0" HOTPLUG="0" TRAN="nvme""#,
+        ];
+        let reason = system_disk_reason(&vdev.join("\n"), true).unwrap();
+        assert!(reason.contains("/boot/efi"));
+        let reason = system_disk_reason(&[vdev[0], vdev[2]].join("\n"), true).unwrap();
+        assert!(reason.contains("zfs_member on /dev/nvme0n1p2"));
+        let swap = r#"PATH="/dev/sdc" TYPE="disk" MOUNTPOINT="[SWAP]" FSTYPE="swap" RM="1" HOTPLUG="1" TRAN="usb""#;
+        assert!(system_disk_reason(swap, false).unwrap().contains("swap"));
+
+        let fixed = r#"PATH="/dev/sdd" TYPE="disk" MOUNTPOINT="" FSTYPE="ext4" RM="0" HOTPLUG="0" TRAN="sata""#;
+        assert!(system_disk_reason(fixed, false)
+            .unwrap()
+            .contains("--allow-fixed-disk"));
+        assert_eq!(system_disk_reason(fixed, true), None);
+
+        let token = [
+            r#"PATH="/dev/sdb" TYPE="disk" MOUNTPOINT="" FSTYPE="" RM="1" HOTPLUG="1" TRAN="usb""#,
+            r#"PATH="/dev/sdb1" TYPE="part" MOUNTPOINT="/run/lockchain" FSTYPE="ext4" RM="1" HOTPLUG="1" TRAN="""#,
+        ];
+        assert_eq!(system_disk_reason(&token.join("\n"), false), None);
+    }
+
+    #[test]
+    fn provisioning_keeps_a_single_token_selector() {
+        let mut usb = Usb {
+            device_label: Some("OLD".into()),
+            device_uuid: Some("0000-0000".into()),
+            ..Usb::default()
+        };
+        select_token(&mut usb, Some("1234-ABCD".into()));
+        assert_eq!(usb.device_uuid.as_deref(), Some("1234-ABCD"));
+        assert_eq!(usb.device_label, None);
+
+        select_token(&mut usb, None);
+        assert_eq!(usb.device_label.as_deref(), Some(LOCKCHAIN_LABEL));
+        assert_eq!(usb.device_uuid, None);
+    }
+}
@@ -3,6 +3,7 @@
 use super::{event, WorkflowEvent, WorkflowLevel, WorkflowReport};
 use crate::config::LockchainConfig;
 use crate::error::{LockchainError, LockchainResult};
+use crate::token;
 use std::env;
 use std::fs;
 use std::os::unix::fs::PermissionsExt;
@@ -11,7 +12,7 @@ use std::process::Command;
 
 const SYSTEMD_DIR_ENV: &str = "LOCKCHAIN_SYSTEMD_DIR";
 const SYSTEMCTL_PATH_ENV: &str = "LOCKCHAIN_SYSTEMCTL";
-const SYSTEMCTL_SKIP_ENV: &str = "LOCKCHAIN_SKIP_SYSTEMCTL";
+pub(super) const SYSTEMCTL_SKIP_ENV: &str = "LOCKCHAIN_SKIP_SYSTEMCTL";
 const RUN_DIR: &str = "/run/lockchain";
 
 /// Repair the host integration by ensuring systemd units exist and are enabled.
@@ -114,22 +115,16 @@ WantedBy=local-fs-pre.target
     Ok(())
 }
 
-/// Build a systemd-friendly identifier for the USB key device.
+/// Build a systemd-friendly identifier for the highest-precedence USB token.
 fn usb_identifier(config: &LockchainConfig) -> LockchainResult<String> {
-    if let Some(uuid) = config.usb.device_uuid.as_ref() {
-        if !uuid.trim().is_empty() {
-            return Ok(format!("/dev/disk/by-uuid/{uuid}"));
-        }
-    }
-    if let Some(label) = config.usb.device_label.as_ref() {
-        if !label.trim().is_empty() {
-            return Ok(format!("/dev/disk/by-label/{label}"));
-        }
-    }
-    Err(LockchainError::InvalidConfig(
-        "usb.device_uuid or usb.device_label must be configured before repairing systemd units"
-            .into(),
-    ))
+    token::token_device_path(&config.usb)
+        .map(|path| path.to_string_lossy().into_owned())
+        .ok_or_else(|| {
+            LockchainError::InvalidConfig(
+                "usb.device_uuid, usb.device_label, or a [[usb.tokens]] entry must be configured before repairing systemd units"
+                    .into(),
+            )
+        })
 }
 
 /// Run `systemctl daemon-reload` and surface any warnings.
@@ -199,7 +194,7 @@ fn systemd_dir() -> PathBuf {
 }
 
 /// Locate the `systemctl` binary, checking overrides first.
-fn systemctl_path() -> Option<PathBuf> {
+pub(super) fn systemctl_path() -> Option<PathBuf> {
     if let Some(explicit) = env::var_os(SYSTEMCTL_PATH_ENV) {
         return Some(PathBuf::from(explicit));
     }
@@ -226,8 +221,13 @@ fn systemd_escape_path() -> Option<PathBuf> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{CryptoCfg, Fallback, LockchainConfig, Policy, RetryCfg, Usb};
+    use crate::config::{
+        AuditCfg, ControlCfg, CryptoCfg, DaemonCfg, Fallback, HealthCfg, HooksCfg, LockchainConfig,
+        Policy, RetryCfg, TuiCfg, UiCfg, Usb,
+    };
+    use std::collections::BTreeMap;
     use std::env;
+    use std::time::Duration;
     use tempfile::tempdir;
 
     struct EnvGuard {
@@ -261,20 +261,44 @@ mod tests {
                 zpool_path: None,
                 binary_path: None,
                 allow_root: false,
+                unlock_window: None,
+                require_strict_usb: false,
+            },
+            crypto: CryptoCfg {
+                timeout: Duration::from_secs(5),
             },
-            crypto: CryptoCfg { timeout_secs: 5 },
             usb: Usb {
                 key_hex_path: "/run/lockchain/key.hex".into(),
                 expected_sha256: None,
                 device_label: Some("LOCKCHAINKEY".into()),
                 device_uuid: Some("UUID-TEST".into()),
                 device_key_path: "key.hex".into(),
-                mount_timeout_secs: 10,
+                mount_timeout: Duration::from_secs(10),
+                key_cache_ttl: Duration::ZERO,
+                keys: Default::default(),
+                tokens: Vec::new(),
+                removal: Default::default(),
             },
             fallback: Fallback::default(),
             retry: RetryCfg::default(),
+            hooks: HooksCfg::default(),
+            audit: AuditCfg::default(),
+            control: ControlCfg::default(),
+            daemon: DaemonCfg::default(),
+            health: HealthCfg::default(),
+            webhooks: Vec::new(),
+            tui: TuiCfg::default(),
+            ui: UiCfg::default(),
+            vault: None,
+            remote: None,
+            remote_unlock: None,
             path,
             format: crate::config::ConfigFormat::Toml,
+            fragments: Vec::new(),
+            profiles: BTreeMap::new(),
+            profile: None,
+            load_issues: Vec::new(),
+            source: None,
         }
     }
 
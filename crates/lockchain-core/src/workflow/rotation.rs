@@ -0,0 +1,356 @@
+//! Key rotation workflow: re-wrap encryption roots under fresh key material
+//! written to the token already in place, without reformatting it.
+
+use super::escrow::{escrow_into, ESCROW_DIR};
+use super::provisioning::{audit_initramfs, install_dracut_module, rebuild_initramfs};
+use super::{event, WorkflowEvent, WorkflowLevel, WorkflowReport};
+use crate::audit::{AuditEntry, AuditEvent, AuditLog};
+use crate::config::LockchainConfig;
+use crate::error::{LockchainError, LockchainResult};
+use crate::keyfile::{read_key_file, write_raw_key_file};
+use crate::provider::ZfsProvider;
+use crate::secret::SecretBuffer;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Caller-provided knobs for [`rotate_key`].
+#[derive(Debug, Clone)]
+pub struct RotateOptions {
+    /// Keep the retired key on the token as `<file>.old`, e.g. for replicas
+    /// received before the rotation.
+    pub keep_old_token: bool,
+    pub rebuild_initramfs: bool,
+    /// age recipient (`age1…` or an SSH public key) that receives an
+    /// encrypted copy of the new key.
+    pub escrow: Option<String>,
+    pub escrow_dir: PathBuf,
+}
+
+impl Default for RotateOptions {
+    fn default() -> Self {
+        Self {
+            keep_old_token: false,
+            rebuild_initramfs: true,
+            escrow: None,
+            escrow_dir: PathBuf::from(ESCROW_DIR),
+        }
+    }
+}
+
+/// Replace the key behind `dataset` with fresh material and re-wrap every
+/// encryption root that shares it.
+///
+/// Steps after the key change (escrow, initramfs) report failures as
+/// `Error` events instead of aborting, since the new key is already live.
+/// Every attempt is recorded as a `rotation` event in the audit trail.
+pub fn rotate_key<P: ZfsProvider>(
+    config: &mut LockchainConfig,
+    provider: &P,
+    dataset: &str,
+    options: RotateOptions,
+) -> LockchainResult<WorkflowReport> {
+    let audit = AuditLog::new(&config.audit);
+    let result = rotate_key_inner(config, provider, dataset, &options);
+    let key_source = config
+        .key_source(dataset, dataset)
+        .path
+        .display()
+        .to_string();
+    audit.record_or_warn(
+        AuditEntry::from_result(AuditEvent::Rotation, dataset, &result)
+            .with_key_source(Some(format!("usb:{key_source}"))),
+    );
+    result
+}
+
+fn rotate_key_inner<P: ZfsProvider>(
+    config: &mut LockchainConfig,
+    provider: &P,
+    dataset: &str,
+    options: &RotateOptions,
+) -> LockchainResult<WorkflowReport> {
+    let mut events = Vec::new();
+
+    if !config.contains_dataset(dataset) {
+        return Err(LockchainError::DatasetNotConfigured(dataset.to_string()));
+    }
+
+    let encryption_root = provider.encryption_root(dataset)?;
+    if config.vault_for(dataset, &encryption_root).is_some() {
+        return Err(LockchainError::InvalidConfig(format!(
+            "the key for {dataset} is served by Vault; rotate it there"
+        )));
+    }
+    let source = config.key_source(dataset, &encryption_root);
+    events.push(event(
+        WorkflowLevel::Info,
+        format!(
+            "Rotating {} (checksum pinned by {})",
+            source.path.display(),
+            source.setting()
+        ),
+    ));
+
+    let roots = roots_sharing(config, provider, &source.path, &encryption_root)?;
+    for root in &roots {
+        if provider
+            .locked_descendants(root)?
+            .iter()
+            .any(|ds| ds == root)
+        {
+            return Err(LockchainError::Provider(format!(
+                "encryption root {root} is still locked; unlock before rotating its key"
+            )));
+        }
+    }
+    events.push(event(
+        WorkflowLevel::Info,
+        format!("Encryption roots using this key: {}", roots.join(", ")),
+    ));
+
+    let (old_key, _) = read_key_file(&source.path)?;
+
+    let mut new_key = SecretBuffer::zeroed(32);
+    OsRng.fill_bytes(&mut new_key);
+    let staged = sibling(&source.path, "new");
+    write_raw_key_file(&staged, &new_key)?;
+
+    let mut stale = Vec::new();
+    for (index, root) in roots.iter().enumerate() {
+        match provider.change_key(root, &new_key) {
+            Ok(()) => events.push(event(
+                WorkflowLevel::Success,
+                format!("{root} re-wrapped under the new key"),
+            )),
+            Err(err) if index == 0 => {
+                let _ = fs::remove_file(&staged);
+                return Err(err);
+            }
+            Err(err) => {
+                events.push(event(
+                    WorkflowLevel::Error,
+                    format!("{root} still uses the previous key: {err}"),
+                ));
+                stale.push(root.as_str());
+            }
+        }
+    }
+
+    // Roots that failed to re-wrap still need the old key, so keep it regardless.
+    if options.keep_old_token || !stale.is_empty() {
+        let retired = sibling(&source.path, "old");
+        fs::rename(&source.path, &retired)?;
+        events.push(event(
+            WorkflowLevel::Info,
+            format!("Previous key kept at {}", retired.display()),
+        ));
+    }
+    fs::rename(&staged, &source.path)?;
+    events.push(event(
+        WorkflowLevel::Success,
+        format!("Wrote new key material to {}", source.path.display()),
+    ));
+
+    if source.entry.is_none() {
+        remask_fallback(config, &old_key, &new_key, &mut events);
+    }
+
+    let digest = hex::encode(Sha256::digest(&new_key[..]));
+    config.pin_key_checksum(source.entry.as_deref(), digest.clone());
+    config.save()?;
+    events.push(event(
+        WorkflowLevel::Info,
+        format!("Config updated with checksum {digest}"),
+    ));
+
+    if let Some(recipient) = &options.escrow {
+        match escrow_into(
+            &new_key,
+            std::slice::from_ref(recipient),
+            &options.escrow_dir,
+            dataset,
+        ) {
+            Ok(path) => events.push(event(
+                WorkflowLevel::Security,
+                format!("Escrow copy for {recipient} written to {}", path.display()),
+            )),
+            Err(err) => events.push(event(
+                WorkflowLevel::Error,
+                format!("Escrow for {recipient} failed: {err}"),
+            )),
+        }
+    }
+
+    if options.rebuild_initramfs {
+        let refreshed = install_dracut_module(config, &source.path, Some(&digest), &mut events)
+            .and_then(|()| rebuild_initramfs(&mut events))
+            .and_then(|()| audit_initramfs(&mut events));
+        if let Err(err) = refreshed {
+            events.push(event(
+                WorkflowLevel::Error,
+                format!("Initramfs refresh failed: {err}"),
+            ));
+        }
+    } else {
+        events.push(event(
+            WorkflowLevel::Warn,
+            "Initramfs rebuild skipped; the boot loader still pins the previous checksum until it is regenerated.",
+        ));
+    }
+
+    Ok(WorkflowReport {
+        title: format!("Rotated key for {dataset}"),
+        events,
+    })
+}
+
+/// Encryption roots unlocked from the key file at `path`, starting with `root`.
+fn roots_sharing<P: ZfsProvider>(
+    config: &LockchainConfig,
+    provider: &P,
+    path: &Path,
+    root: &str,
+) -> LockchainResult<Vec<String>> {
+    let mut roots = vec![root.to_string()];
+    for dataset in &config.policy.datasets {
+        let other = provider.encryption_root(dataset)?;
+        if !roots.contains(&other)
+            && config.vault_for(dataset, &other).is_none()
+            && config.key_source(dataset, &other).path == path
+        {
+            roots.push(other);
+        }
+    }
+    Ok(roots)
+}
+
+/// `<file>.<suffix>` next to `path`.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{suffix}"));
+    path.with_file_name(name)
+}
+
+/// Re-point the fallback mask at the new key; the passphrase itself is unchanged.
+fn remask_fallback(
+    config: &mut LockchainConfig,
+    old_key: &[u8],
+    new_key: &[u8],
+    events: &mut Vec<WorkflowEvent>,
+) {
+    let Some(mask) = config.fallback.passphrase_xor.as_deref() else {
+        return;
+    };
+    match hex::decode(mask) {
+        Ok(mask) if mask.len() == new_key.len() && old_key.len() == new_key.len() => {
+            let remasked: Vec<u8> = mask
+                .iter()
+                .zip(old_key)
+                .zip(new_key)
+                .map(|((m, old), new)| m ^ old ^ new)
+                .collect();
+            config.fallback.passphrase_xor = Some(hex::encode(remasked));
+            events.push(event(
+                WorkflowLevel::Security,
+                "Fallback passphrase now unlocks the new key.",
+            ));
+        }
+        _ => events.push(event(
+            WorkflowLevel::Warn,
+            "fallback.passphrase_xor is malformed and was not updated; re-run init with --passphrase.",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::KeyStatusSnapshot;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RotatingProvider {
+        changed: Mutex<Vec<(String, Vec<u8>)>>,
+    }
+
+    impl ZfsProvider for RotatingProvider {
+        fn encryption_root(&self, dataset: &str) -> LockchainResult<String> {
+            Ok(dataset.to_string())
+        }
+
+        fn locked_descendants(&self, _root: &str) -> LockchainResult<Vec<String>> {
+            Ok(Vec::new())
+        }
+
+        fn load_key_tree(&self, _root: &str, _key: &[u8]) -> LockchainResult<Vec<String>> {
+            unreachable!("rotation never loads keys")
+        }
+
+        fn describe_datasets(&self, _datasets: &[String]) -> LockchainResult<KeyStatusSnapshot> {
+            Ok(Vec::new())
+        }
+
+        fn change_key(&self, root: &str, key: &[u8]) -> LockchainResult<()> {
+            self.changed
+                .lock()
+                .unwrap()
+                .push((root.to_string(), key.to_vec()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn rotation_rewraps_shared_roots_and_keeps_old_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("lockchain.key");
+        let old_key = [7u8; 32];
+        write_raw_key_file(&key_path, &old_key).unwrap();
+
+        let mut config = LockchainConfig::starter(
+            dir.path().join("lockchain-zfs.toml"),
+            vec!["tank/secure".into(), "tank/media".into()],
+        );
+        config.usb.key_hex_path = key_path.display().to_string();
+        config.audit.enabled = false;
+        config.fallback.passphrase_salt = Some("00".repeat(16));
+        config.fallback.passphrase_xor = Some(hex::encode([1u8; 32]));
+
+        let provider = RotatingProvider::default();
+        let report = rotate_key(
+            &mut config,
+            &provider,
+            "tank/secure",
+            RotateOptions {
+                keep_old_token: true,
+                rebuild_initramfs: false,
+                ..RotateOptions::default()
+            },
+        )
+        .unwrap();
+        assert!(report
+            .events
+            .iter()
+            .all(|e| e.level != WorkflowLevel::Error));
+
+        let changed = provider.changed.lock().unwrap();
+        let roots: Vec<&str> = changed.iter().map(|(root, _)| root.as_str()).collect();
+        assert_eq!(roots, vec!["tank/secure", "tank/media"]);
+        let new_key = &changed[0].1;
+        assert_ne!(new_key.as_slice(), old_key.as_slice());
+
+        assert_eq!(&fs::read(&key_path).unwrap(), new_key);
+        assert_eq!(fs::read(sibling(&key_path, "old")).unwrap(), old_key);
+        assert_eq!(
+            config.usb.expected_sha256,
+            Some(hex::encode(Sha256::digest(new_key)))
+        );
+
+        // The mask still yields the same passphrase-derived bytes for the new key.
+        let mask = hex::decode(config.fallback.passphrase_xor.as_ref().unwrap()).unwrap();
+        let derived: Vec<u8> = mask.iter().zip(new_key).map(|(m, k)| m ^ k).collect();
+        assert_eq!(derived, vec![1 ^ 7; 32]);
+    }
+}
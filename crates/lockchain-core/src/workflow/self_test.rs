@@ -1,6 +1,6 @@
 //! End-to-end self-test that spins up a temporary ZFS pool to validate unlock flows.
 
-use super::{event, WorkflowLevel, WorkflowReport};
+use super::{event, timed, EventSink, EventStream, WorkflowEvent, WorkflowLevel, WorkflowReport};
 use crate::config::LockchainConfig;
 use crate::error::{LockchainError, LockchainResult};
 use crate::keyfile::{read_key_file, write_raw_key_file};
@@ -29,14 +29,63 @@ const DEFAULT_ZPOOL_PATHS: &[&str] = &[
     "/bin/zpool",
 ];
 
+/// Size of the file backing the scratch pool unless the caller asks otherwise.
+const DEFAULT_POOL_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Smallest vdev `zpool create` accepts.
+const MIN_POOL_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Caller-provided knobs for the self-test drill.
+#[derive(Debug, Clone)]
+pub struct SelfTestOptions {
+    /// Require the USB token and skip fallback handling during the drill.
+    pub strict_usb: bool,
+    /// Bytes allocated for the file backing the scratch pool.
+    pub pool_size: u64,
+    /// Leave the scratch pool and its backing file in place when a step fails.
+    pub keep_on_failure: bool,
+}
+
+impl Default for SelfTestOptions {
+    fn default() -> Self {
+        Self {
+            strict_usb: false,
+            pool_size: DEFAULT_POOL_SIZE,
+            keep_on_failure: false,
+        }
+    }
+}
+
 /// Spin up a throwaway ZFS pool, exercise the unlock workflow, and tear it down.
 pub fn self_test<P: ZfsProvider + Clone>(
     config: &LockchainConfig,
     provider: P,
     dataset: &str,
-    strict_usb: bool,
+    options: SelfTestOptions,
 ) -> LockchainResult<WorkflowReport> {
-    let mut events = Vec::new();
+    self_test_observed(config, provider, dataset, options, |_| {})
+}
+
+/// Like [`self_test`], calling `on_event` as each step reports its outcome.
+pub fn self_test_observed<P, F>(
+    config: &LockchainConfig,
+    provider: P,
+    dataset: &str,
+    options: SelfTestOptions,
+    on_event: F,
+) -> LockchainResult<WorkflowReport>
+where
+    P: ZfsProvider + Clone,
+    F: FnMut(&WorkflowEvent),
+{
+    if options.pool_size < MIN_POOL_SIZE {
+        return Err(LockchainError::InvalidConfig(format!(
+            "self-test pool size must be at least {} MiB (asked for {} bytes)",
+            MIN_POOL_SIZE >> 20,
+            options.pool_size
+        )));
+    }
+    let mut events = EventStream::new(on_event);
     let key_path = config.key_hex_path();
     if !key_path.exists() {
         return Err(LockchainError::MissingKeySource(dataset.to_string()));
@@ -83,7 +132,8 @@ pub fn self_test<P: ZfsProvider + Clone>(
         ),
     ));
 
-    let mut ctx = SimulationContext::prepare(&zfs_path, &zpool_path)?;
+    let mut ctx =
+        SimulationContext::prepare(&zfs_path, &zpool_path, options.pool_size, &mut events)?;
     events.push(event(
         WorkflowLevel::Info,
         format!(
@@ -93,16 +143,57 @@ pub fn self_test<P: ZfsProvider + Clone>(
         ),
     ));
 
-    create_encrypted_dataset(&zfs_path, &ctx.dataset_name, &key_path, &mut events)?;
+    let outcome = exercise(
+        config,
+        provider,
+        &mut ctx,
+        &key_path,
+        &key_material,
+        options.strict_usb,
+        &mut events,
+    );
+    if let Err(err) = outcome {
+        if options.keep_on_failure {
+            ctx.keep(&mut events);
+        }
+        return Err(err);
+    }
+
+    events.push(event(
+        WorkflowLevel::Success,
+        "Self-test completed; ephemeral pool dismantled.",
+    ));
+
+    Ok(WorkflowReport {
+        title: "Self-test vault simulation".into(),
+        events: events.into_events(),
+    })
+}
+
+/// Create, lock, unlock, and dismantle the dataset inside the scratch pool.
+fn exercise<P: ZfsProvider + Clone>(
+    config: &LockchainConfig,
+    provider: P,
+    ctx: &mut SimulationContext,
+    key_path: &Path,
+    key_material: &[u8],
+    strict_usb: bool,
+    events: &mut impl EventSink,
+) -> LockchainResult<()> {
+    create_encrypted_dataset(&ctx.zfs_path, &ctx.dataset_name, key_path, events)?;
     ctx.dataset_created = true;
 
-    unload_key(&zfs_path, &ctx.dataset_name, &mut events)?;
+    unload_key(&ctx.zfs_path, &ctx.dataset_name, events)?;
 
-    let sim_config = build_simulation_config(config, &ctx.dataset_name, &key_path, &key_material);
-    let mut options = UnlockOptions::default();
-    options.strict_usb = strict_usb;
-    let service = LockchainService::new(Arc::new(sim_config.clone()), provider.clone());
-    let report = service.unlock_with_retry(&ctx.dataset_name, options)?;
+    let sim_config = build_simulation_config(config, &ctx.dataset_name, key_path, key_material);
+    let options = UnlockOptions {
+        strict_usb,
+        ..UnlockOptions::default()
+    };
+    let service = LockchainService::new(Arc::new(sim_config), provider);
+    let report = timed(events, format!("unlock {}", ctx.dataset_name), || {
+        service.unlock_with_retry(&ctx.dataset_name, options)
+    })?;
 
     if report.already_unlocked {
         events.push(event(
@@ -129,26 +220,18 @@ pub fn self_test<P: ZfsProvider + Clone>(
         ));
     }
 
-    verify_keystatus(&zfs_path, &ctx.dataset_name, "available", &mut events)?;
+    verify_keystatus(&ctx.zfs_path, &ctx.dataset_name, "available", events)?;
 
-    unload_key(&zfs_path, &ctx.dataset_name, &mut events)?;
-    verify_keystatus(&zfs_path, &ctx.dataset_name, "unavailable", &mut events)?;
+    unload_key(&ctx.zfs_path, &ctx.dataset_name, events)?;
+    verify_keystatus(&ctx.zfs_path, &ctx.dataset_name, "unavailable", events)?;
 
-    destroy_dataset(&zfs_path, &ctx.dataset_name, &mut events)?;
+    destroy_dataset(&ctx.zfs_path, &ctx.dataset_name, events)?;
     ctx.dataset_created = false;
-    destroy_pool(&zpool_path, &ctx.pool_name, &mut events)?;
+    destroy_pool(&ctx.zpool_path, &ctx.pool_name, events)?;
     ctx.pool_created = false;
     ctx.cleaned = true;
 
-    events.push(event(
-        WorkflowLevel::Success,
-        "Self-test completed; ephemeral pool dismantled.",
-    ));
-
-    Ok(WorkflowReport {
-        title: "Self-test vault simulation".into(),
-        events,
-    })
+    Ok(())
 }
 
 /// Locate the requested binary, preferring explicit config over defaults.
@@ -185,7 +268,7 @@ fn create_encrypted_dataset(
     zfs_path: &Path,
     dataset: &str,
     key_path: &Path,
-    events: &mut Vec<super::WorkflowEvent>,
+    events: &mut impl EventSink,
 ) -> LockchainResult<()> {
     let keylocation = format!("keylocation=file://{}", key_path.display());
     let args = vec![
@@ -200,7 +283,7 @@ fn create_encrypted_dataset(
         "mountpoint=none".to_string(),
         dataset.to_string(),
     ];
-    run_command(zfs_path, &args)?;
+    run_command(zfs_path, &args, events)?;
     events.push(event(
         WorkflowLevel::Info,
         format!("Created encrypted dataset {dataset} using key {key_path:?}"),
@@ -209,13 +292,9 @@ fn create_encrypted_dataset(
 }
 
 /// Run `zfs unload-key` for the generated dataset.
-fn unload_key(
-    zfs_path: &Path,
-    dataset: &str,
-    events: &mut Vec<super::WorkflowEvent>,
-) -> LockchainResult<()> {
+fn unload_key(zfs_path: &Path, dataset: &str, events: &mut impl EventSink) -> LockchainResult<()> {
     let args = vec!["unload-key".to_string(), dataset.to_string()];
-    run_command(zfs_path, &args)?;
+    run_command(zfs_path, &args, events)?;
     events.push(event(
         WorkflowLevel::Info,
         format!("Unloaded key for {dataset}"),
@@ -227,10 +306,10 @@ fn unload_key(
 fn destroy_dataset(
     zfs_path: &Path,
     dataset: &str,
-    events: &mut Vec<super::WorkflowEvent>,
+    events: &mut impl EventSink,
 ) -> LockchainResult<()> {
     let args = vec!["destroy".to_string(), "-r".to_string(), dataset.to_string()];
-    run_command(zfs_path, &args)?;
+    run_command(zfs_path, &args, events)?;
     events.push(event(
         WorkflowLevel::Info,
         format!("Destroyed dataset {dataset}"),
@@ -239,13 +318,9 @@ fn destroy_dataset(
 }
 
 /// Tear down the temporary pool after the drill finishes.
-fn destroy_pool(
-    zpool_path: &Path,
-    pool: &str,
-    events: &mut Vec<super::WorkflowEvent>,
-) -> LockchainResult<()> {
+fn destroy_pool(zpool_path: &Path, pool: &str, events: &mut impl EventSink) -> LockchainResult<()> {
     let args = vec!["destroy".to_string(), pool.to_string()];
-    run_command(zpool_path, &args)?;
+    run_command(zpool_path, &args, events)?;
     events.push(event(WorkflowLevel::Info, format!("Destroyed pool {pool}")));
     Ok(())
 }
@@ -255,7 +330,7 @@ fn verify_keystatus(
     zfs_path: &Path,
     dataset: &str,
     expected: &str,
-    events: &mut Vec<super::WorkflowEvent>,
+    events: &mut impl EventSink,
 ) -> LockchainResult<()> {
     let output = Command::new(zfs_path)
         .args(["get", "-H", "-o", "value", "keystatus", dataset])
@@ -286,21 +361,22 @@ fn verify_keystatus(
 }
 
 /// Execute a ZFS/ZPOOL command and convert failures into provider errors.
-fn run_command(binary: &Path, args: &[String]) -> LockchainResult<()> {
-    let output = Command::new(binary)
-        .args(args)
-        .output()
-        .map_err(|err| LockchainError::Provider(err.to_string()))?;
+fn run_command(binary: &Path, args: &[String], events: &mut impl EventSink) -> LockchainResult<()> {
+    let command = format!("{} {}", binary.display(), args.join(" "));
+    timed(events, &command, || {
+        let output = Command::new(binary)
+            .args(args)
+            .output()
+            .map_err(|err| LockchainError::Provider(err.to_string()))?;
 
-    if !output.status.success() {
-        return Err(LockchainError::Provider(format!(
-            "{} {} failed: {}",
-            binary.display(),
-            args.join(" "),
-            String::from_utf8_lossy(&output.stderr)
-        )));
-    }
-    Ok(())
+        if !output.status.success() {
+            return Err(LockchainError::Provider(format!(
+                "{command} failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+        Ok(())
+    })
 }
 
 /// Prepare a config clone wired to the simulated dataset and USB path.
@@ -323,7 +399,7 @@ fn build_simulation_config(
 
 /// Tracks the temporary resources created for the self-test run.
 struct SimulationContext {
-    _temp_dir: TempDir,
+    temp_dir: TempDir,
     image_path: PathBuf,
     pool_name: String,
     dataset_name: String,
@@ -336,13 +412,18 @@ struct SimulationContext {
 
 impl SimulationContext {
     /// Allocate backing storage, create a pool, and return the guard context.
-    fn prepare(zfs_path: &Path, zpool_path: &Path) -> LockchainResult<Self> {
+    fn prepare(
+        zfs_path: &Path,
+        zpool_path: &Path,
+        pool_size: u64,
+        events: &mut impl EventSink,
+    ) -> LockchainResult<Self> {
         let temp_dir = TempDir::new().map_err(|err| LockchainError::Provider(err.to_string()))?;
         let image_path = temp_dir.path().join("lockchain-selftest.img");
         let backing =
             File::create(&image_path).map_err(|err| LockchainError::Provider(err.to_string()))?;
         backing
-            .set_len(256 * 1024 * 1024)
+            .set_len(pool_size)
             .map_err(|err| LockchainError::Provider(err.to_string()))?;
 
         let pool_name = format!(
@@ -363,10 +444,10 @@ impl SimulationContext {
             pool_name.clone(),
             backing,
         ];
-        run_command(zpool_path, &args)?;
+        run_command(zpool_path, &args, events)?;
 
         Ok(Self {
-            _temp_dir: temp_dir,
+            temp_dir,
             image_path,
             pool_name,
             dataset_name,
@@ -379,6 +460,26 @@ impl SimulationContext {
     }
 }
 
+impl SimulationContext {
+    /// Leave the pool, dataset, and backing file for inspection and say how
+    /// to remove them afterwards.
+    fn keep(&mut self, events: &mut impl EventSink) {
+        self.temp_dir.disable_cleanup(true);
+        self.cleaned = true;
+        self.dataset_created = false;
+        events.push(event(
+            WorkflowLevel::Warn,
+            format!(
+                "Kept scratch pool {} backed by {} for debugging; remove it with `zpool destroy {}` and `rm -r {}`.",
+                self.pool_name,
+                self.image_path.display(),
+                self.pool_name,
+                self.temp_dir.path().display()
+            ),
+        ));
+    }
+}
+
 impl Drop for SimulationContext {
     fn drop(&mut self) {
         if self.dataset_created {
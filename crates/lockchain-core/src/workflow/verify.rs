@@ -0,0 +1,241 @@
+//! Read-only token check: mount the token without write access, decode each
+//! configured key file, and ask ZFS whether it would accept the key.
+
+use super::provisioning::MountGuard;
+use super::{event, WorkflowEvent, WorkflowLevel, WorkflowReport};
+use crate::config::{KeySource, LockchainConfig};
+use crate::error::{LockchainError, LockchainResult};
+use crate::keyfile::{read_key_file, verify_key_source};
+use crate::provider::ZfsProvider;
+use crate::token;
+use std::path::Path;
+
+/// Check the inserted token against the config and ZFS without writing anything.
+///
+/// A token that is already mounted is read in place; otherwise it is mounted
+/// read-only on a scratch directory for the duration of the check.
+pub fn verify_token<P: ZfsProvider>(
+    config: &LockchainConfig,
+    provider: &P,
+) -> LockchainResult<WorkflowReport> {
+    let mut events = Vec::new();
+    let title = "Token verification".to_string();
+
+    if let Some(mounted) = token::mounted_token(&config.usb)? {
+        events.push(event(
+            WorkflowLevel::Info,
+            format!(
+                "Token {} already mounted at {}; reading in place",
+                mounted.token.describe(),
+                mounted.mount_point.display()
+            ),
+        ));
+        check_mounted_token(config, provider, &mounted.mount_point, &mut events);
+        return Ok(WorkflowReport { title, events });
+    }
+
+    let device = token::token_device_path(&config.usb).ok_or_else(|| {
+        LockchainError::InvalidConfig(
+            "no usb.device_uuid, usb.device_label, or [[usb.tokens]] selector to locate the token"
+                .into(),
+        )
+    })?;
+    if !device.exists() {
+        events.push(event(
+            WorkflowLevel::Error,
+            format!("Token not inserted ({} not present)", device.display()),
+        ));
+        return Ok(WorkflowReport { title, events });
+    }
+
+    let scratch = tempfile::Builder::new()
+        .prefix("lockchain-verify-")
+        .tempdir()?;
+    let guard = MountGuard::mount_read_only(&device.to_string_lossy(), scratch.path())?;
+    events.push(event(
+        WorkflowLevel::Info,
+        format!(
+            "Mounted {} read-only at {}",
+            device.display(),
+            scratch.path().display()
+        ),
+    ));
+    check_mounted_token(config, provider, scratch.path(), &mut events);
+    drop(guard);
+
+    Ok(WorkflowReport { title, events })
+}
+
+/// Verify every configured key file found under `mount_point`.
+fn check_mounted_token<P: ZfsProvider>(
+    config: &LockchainConfig,
+    provider: &P,
+    mount_point: &Path,
+    events: &mut Vec<WorkflowEvent>,
+) {
+    let mut roots: Vec<(String, KeySource)> = Vec::new();
+    for dataset in &config.policy.datasets {
+        match provider.encryption_root(dataset) {
+            Ok(root) => {
+                if config.vault_for(dataset, &root).is_none()
+                    && !roots.iter().any(|(known, _)| *known == root)
+                {
+                    let source = config.key_source(dataset, &root);
+                    roots.push((root, source));
+                }
+            }
+            Err(err) => events.push(event(
+                WorkflowLevel::Error,
+                format!("Cannot resolve encryption root for {dataset}: {err}"),
+            )),
+        }
+    }
+
+    for source in config.key_sources() {
+        let Some(name) = source.path.file_name() else {
+            continue;
+        };
+        let on_token = KeySource {
+            path: mount_point.join(name),
+            ..source.clone()
+        };
+        let verification = verify_key_source(&on_token);
+        let shown = on_token.path.display();
+        if let Some(problem) = &verification.problem {
+            events.push(event(
+                WorkflowLevel::Error,
+                format!("{shown} cannot be used: {problem}"),
+            ));
+            continue;
+        }
+        let sha256 = verification.sha256.as_deref().unwrap_or_default();
+        match verification.checksum_match {
+            Some(true) => events.push(event(
+                WorkflowLevel::Success,
+                format!("{shown} decodes and matches {}", verification.setting),
+            )),
+            Some(false) => events.push(event(
+                WorkflowLevel::Error,
+                format!(
+                    "{shown} does not match {} (actual {sha256})",
+                    verification.setting
+                ),
+            )),
+            None => events.push(event(
+                WorkflowLevel::Warn,
+                format!(
+                    "{shown} decodes but {} is not pinned (actual {sha256})",
+                    verification.setting
+                ),
+            )),
+        }
+        if verification.hex_encoded {
+            events.push(event(
+                WorkflowLevel::Warn,
+                format!("{shown} still holds legacy hex text"),
+            ));
+        }
+
+        let Ok((key, _)) = read_key_file(&on_token.path) else {
+            continue;
+        };
+        for (root, _) in roots.iter().filter(|(_, used)| used.path == source.path) {
+            check_root(provider, root, &key, events);
+        }
+    }
+}
+
+/// Dry-run the key against `root` when ZFS can check it without loading.
+fn check_root<P: ZfsProvider>(
+    provider: &P,
+    root: &str,
+    key: &[u8],
+    events: &mut Vec<WorkflowEvent>,
+) {
+    let locked = match provider.locked_descendants(root) {
+        Ok(locked) => locked.iter().any(|ds| ds == root),
+        Err(err) => {
+            events.push(event(
+                WorkflowLevel::Error,
+                format!("keystatus for {root} unavailable: {err}"),
+            ));
+            return;
+        }
+    };
+    if !locked {
+        events.push(event(
+            WorkflowLevel::Info,
+            format!("{root} is unlocked; ZFS can only dry-run keys against a locked root"),
+        ));
+        return;
+    }
+    match provider.check_key(root, key) {
+        Ok(()) => events.push(event(
+            WorkflowLevel::Success,
+            format!("ZFS accepts the key for {root} (dry run, not loaded)"),
+        )),
+        Err(err) => events.push(event(
+            WorkflowLevel::Error,
+            format!("ZFS rejects the key for {root}: {err}"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyfile::write_raw_key_file;
+    use crate::provider::KeyStatusSnapshot;
+    use sha2::{Digest, Sha256};
+
+    struct DryRunProvider;
+
+    impl ZfsProvider for DryRunProvider {
+        fn encryption_root(&self, dataset: &str) -> LockchainResult<String> {
+            Ok(dataset.to_string())
+        }
+
+        fn locked_descendants(&self, root: &str) -> LockchainResult<Vec<String>> {
+            Ok(vec![root.to_string()])
+        }
+
+        fn load_key_tree(&self, _root: &str, _key: &[u8]) -> LockchainResult<Vec<String>> {
+            unreachable!("verification never loads keys")
+        }
+
+        fn describe_datasets(&self, _datasets: &[String]) -> LockchainResult<KeyStatusSnapshot> {
+            Ok(Vec::new())
+        }
+
+        fn check_key(&self, root: &str, key: &[u8]) -> LockchainResult<()> {
+            if key == [3u8; 32] {
+                Ok(())
+            } else {
+                Err(LockchainError::Provider(format!("wrong key for {root}")))
+            }
+        }
+    }
+
+    #[test]
+    fn mounted_token_is_checked_against_config_and_zfs() {
+        let token = tempfile::tempdir().unwrap();
+        write_raw_key_file(&token.path().join("lockchain.key"), &[3u8; 32]).unwrap();
+
+        let mut config =
+            LockchainConfig::starter("/nonexistent.toml", vec!["tank/secure".to_string()]);
+        config.usb.key_hex_path = "/run/lockchain/lockchain.key".into();
+        config.usb.expected_sha256 = Some(hex::encode(Sha256::digest([3u8; 32])));
+
+        let mut events = Vec::new();
+        check_mounted_token(&config, &DryRunProvider, token.path(), &mut events);
+        let levels: Vec<WorkflowLevel> = events.iter().map(|e| e.level).collect();
+        assert_eq!(levels, vec![WorkflowLevel::Success, WorkflowLevel::Success]);
+
+        std::fs::remove_file(token.path().join("lockchain.key")).unwrap();
+        write_raw_key_file(&token.path().join("lockchain.key"), &[4u8; 32]).unwrap();
+        let mut events = Vec::new();
+        check_mounted_token(&config, &DryRunProvider, token.path(), &mut events);
+        assert!(events.iter().all(|e| e.level == WorkflowLevel::Error));
+        assert_eq!(events.len(), 2);
+    }
+}
@@ -3,6 +3,7 @@
 
 depends() {
     echo systemd zfs
+    [ -f "$moddir/{{REMOTE_SERVICE_NAME}}" ] && echo network
     return 0
 }
 
@@ -17,4 +18,15 @@ install() {
     inst_simple "$moddir/{{MODULE_DROPIN_DIR}}/{{DROPIN_NAME}}" "$systemdsystemunitdir/{{MODULE_DROPIN_DIR}}/{{DROPIN_NAME}}"
     mkdir -p "$systemdsystemunitdir/initrd-root-fs.target.wants"
     ln -sf "../{{SERVICE_NAME}}" "$systemdsystemunitdir/initrd-root-fs.target.wants/{{SERVICE_NAME}}"
+
+    if [ -f "$moddir/{{REMOTE_SERVICE_NAME}}" ]; then
+        inst_simple "$(command -v lockchain-daemon)" /usr/bin/lockchain-daemon
+        inst_multiple pkill
+        while read -r file; do
+            inst_simple "$file"
+        done < "$moddir/{{REMOTE_FILES_NAME}}"
+        inst_simple "$moddir/{{REMOTE_SERVICE_NAME}}" "$systemdsystemunitdir/{{REMOTE_SERVICE_NAME}}"
+        mkdir -p "$systemdsystemunitdir/initrd.target.wants"
+        ln -sf "../{{REMOTE_SERVICE_NAME}}" "$systemdsystemunitdir/initrd.target.wants/{{REMOTE_SERVICE_NAME}}"
+    fi
 }
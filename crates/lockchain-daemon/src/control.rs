@@ -0,0 +1,406 @@
+//! Unix control socket that lets `lockchain daemon …` drive the running service.
+//!
+//! Callers are authorised by their peer credentials: root and the daemon's
+//! own user may call everything, members of `[control] group` the read-only
+//! methods (or all of them with `group_operate`), the uids in `read_uids` and
+//! `operate_uids` what those lists allow, and nobody else anything. Peers
+//! with no access at all are dropped before anything is read from them.
+
+use anyhow::{Context, Result};
+use lockchain_core::audit::{self, AuditFilter};
+use lockchain_core::config::{ControlCfg, WebhookEvent};
+use lockchain_core::control::{
+    self, ControlAccess, ControlCall, ControlError, ControlRequest, ControlResponse,
+    DaemonEventKind, EventFilter, KeepAliveReport, ReloadReport, ACCESS_DENIED, PARSE_ERROR,
+};
+use lockchain_core::{LockOptions, LockchainError, LockchainResult, UnlockOptions};
+use log::{info, warn};
+use serde::Serialize;
+use serde_json::Value;
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::io::ErrorKind;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::{reload, DaemonState};
+
+/// Longest request line accepted; the connection is closed after a longer one.
+const MAX_REQUEST: usize = 64 * 1024;
+/// Connections served at once; further ones are closed straight away.
+const MAX_CONNECTIONS: usize = 32;
+
+/// Accept control connections until the daemon shuts down.
+pub async fn serve(state: Arc<DaemonState>) -> Result<()> {
+    let path = control::socket_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("create control socket directory {}", parent.display()))?;
+    }
+    match fs::remove_file(&path) {
+        Err(err) if err.kind() != ErrorKind::NotFound => {
+            return Err(err).with_context(|| format!("remove stale socket {}", path.display()))
+        }
+        _ => {}
+    }
+    let listener = UnixListener::bind(&path)
+        .with_context(|| format!("bind control socket {}", path.display()))?;
+    apply_socket_access(&path, &state.config().control)?;
+    info!("control socket listening on {}", path.display());
+
+    let slots = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let peer = match stream.peer_cred() {
+            Ok(peer) => peer,
+            Err(err) => {
+                warn!("control connection without peer credentials dropped: {err}");
+                continue;
+            }
+        };
+        if peer_access(&state.config().control, peer.uid(), Some(peer.gid())).is_none() {
+            warn!(event = "control_denied"; "control: uid {} has no access; connection dropped", peer.uid());
+            continue;
+        }
+        let Ok(slot) = slots.clone().try_acquire_owned() else {
+            warn!(
+                "control: {MAX_CONNECTIONS} connections already open; dropped one from uid {}",
+                peer.uid()
+            );
+            continue;
+        };
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(err) = handle_connection(state, stream, slot).await {
+                warn!("control connection failed: {err:#}");
+            }
+        });
+    }
+}
+
+/// Remove the control socket, so clients of a stopped daemon fail to connect
+/// rather than hang.
+pub fn remove_socket() {
+    let path = control::socket_path();
+    match fs::remove_file(&path) {
+        Err(err) if err.kind() != ErrorKind::NotFound => {
+            warn!("control socket {} not removed: {err}", path.display())
+        }
+        _ => {}
+    }
+}
+
+/// Owner-only, group-accessible for `[control] group`, or open to everyone
+/// when uid allow-lists are configured, so those callers can connect at all;
+/// what they may call is still checked per request.
+pub fn apply_socket_access(path: &Path, cfg: &ControlCfg) -> Result<()> {
+    let gid = match cfg.group.as_deref().map(group_id).transpose() {
+        Ok(gid) => gid,
+        Err(err) => {
+            warn!("control socket stays owner-only: {err:#}");
+            None
+        }
+    };
+    if let Some(gid) = gid {
+        std::os::unix::fs::chown(path, None, Some(gid))
+            .with_context(|| format!("hand {} to group {gid}", path.display()))?;
+    }
+    let mode = if cfg.lists_uids() {
+        0o666
+    } else if gid.is_some() {
+        0o660
+    } else {
+        0o600
+    };
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .with_context(|| format!("set mode of {}", path.display()))
+}
+
+/// Answer each request line on `stream` in order, holding one of the
+/// connection `_slot`s until the peer hangs up.
+async fn handle_connection(
+    state: Arc<DaemonState>,
+    stream: UnixStream,
+    _slot: OwnedSemaphorePermit,
+) -> Result<()> {
+    let peer = stream.peer_cred().context("read peer credentials")?;
+    let (read, mut write) = stream.into_split();
+    let mut reader = BufReader::new(read);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = (&mut reader)
+            .take(MAX_REQUEST as u64 + 1)
+            .read_until(b'\n', &mut line)
+            .await?;
+        if read == 0 {
+            break;
+        }
+        if line.len() > MAX_REQUEST && !line.ends_with(b"\n") {
+            warn!(
+                "control: uid {} sent a request over {MAX_REQUEST} bytes; connection closed",
+                peer.uid()
+            );
+            let response = ControlResponse::failure(
+                None,
+                ControlError {
+                    code: PARSE_ERROR,
+                    message: format!("request longer than {MAX_REQUEST} bytes"),
+                    data: None,
+                },
+            );
+            let mut reply = serde_json::to_string(&response)?;
+            reply.push('\n');
+            write.write_all(reply.as_bytes()).await?;
+            break;
+        }
+        if line.trim_ascii().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_slice::<ControlRequest>(&line) {
+            Ok(request) => {
+                let access = peer_access(&state.config().control, peer.uid(), Some(peer.gid()));
+                if access.is_some_and(|access| access.permits(&request.call)) {
+                    let state = state.clone();
+                    let uid = peer.uid();
+                    let outcome =
+                        tokio::task::spawn_blocking(move || dispatch(&state, uid, request.call))
+                            .await?;
+                    ControlResponse::from_result(request.id, outcome)
+                } else {
+                    let method = request.call.method();
+                    warn!(event = "control_denied"; "control: uid {} denied {method}", peer.uid());
+                    ControlResponse::failure(
+                        Some(request.id),
+                        ControlError {
+                            code: ACCESS_DENIED,
+                            message: format!("uid {} may not call {method}", peer.uid()),
+                            data: None,
+                        },
+                    )
+                }
+            }
+            Err(err) => ControlResponse::failure(
+                None,
+                ControlError {
+                    code: PARSE_ERROR,
+                    message: format!("invalid request: {err}"),
+                    data: None,
+                },
+            ),
+        };
+        let mut reply = serde_json::to_string(&response)?;
+        reply.push('\n');
+        write.write_all(reply.as_bytes()).await?;
+    }
+    Ok(())
+}
+
+/// What the peer running as `uid` (with effective group `gid`, when known)
+/// may call under `cfg`.
+pub fn peer_access(cfg: &ControlCfg, uid: u32, gid: Option<u32>) -> Option<ControlAccess> {
+    // SAFETY: geteuid has no preconditions.
+    let daemon_uid = unsafe { libc::geteuid() };
+    ControlAccess::grant(cfg, uid, daemon_uid, |group| in_group(group, uid, gid))
+}
+
+/// Run one control method for the peer running as `uid`; the D-Bus
+/// interface goes through here too.
+pub fn dispatch(state: &DaemonState, uid: u32, call: ControlCall) -> LockchainResult<Value> {
+    // Unlocks and locks hold off the daemon's exit until they finish.
+    let _in_flight = match call {
+        ControlCall::Unlock { .. } | ControlCall::Lock { .. } => {
+            Some(state.shutdown.enter().ok_or_else(|| {
+                LockchainError::Control("lockchain-daemon is shutting down".into())
+            })?)
+        }
+        _ => None,
+    };
+    let config = state.config();
+    let service = state.service();
+    let target = |dataset: Option<String>| {
+        dataset
+            .or_else(|| config.policy.datasets.first().cloned())
+            .ok_or_else(|| LockchainError::InvalidConfig("policy.datasets is empty".into()))
+    };
+    match call {
+        ControlCall::Status {
+            dataset: Some(dataset),
+        } => to_value(vec![service.status(&dataset)?]),
+        ControlCall::Status { dataset: None } => {
+            let estate = service.status_all()?;
+            let statuses: Vec<_> = config
+                .policy
+                .datasets
+                .iter()
+                .filter_map(|ds| estate.dataset_status(ds))
+                .collect();
+            to_value(statuses)
+        }
+        ControlCall::Unlock {
+            dataset,
+            strict_usb,
+        } => {
+            let dataset = target(dataset)?;
+            info!(dataset:% = dataset, event = "control_unlock"; "control: unlock {dataset} requested by uid {uid}");
+            if state.idle.keep_alive() {
+                info!("idle lock lifted by the unlock request of uid {uid}");
+            }
+            let options = UnlockOptions {
+                strict_usb,
+                ..UnlockOptions::default()
+            };
+            let report = service.unlock_with_retry(&dataset, options);
+            let (event, message) = match &report {
+                Ok(report) => (
+                    WebhookEvent::UnlockSuccess,
+                    format!(
+                        "unlocked {} datasets on request of uid {uid}",
+                        report.unlocked.len()
+                    ),
+                ),
+                Err(err) => (
+                    WebhookEvent::UnlockFailure,
+                    format!("unlock requested by uid {uid} failed: {err}"),
+                ),
+            };
+            if !report.as_ref().is_ok_and(|report| report.already_unlocked) {
+                state.webhooks.notify(event, Some(&dataset), &message);
+            }
+            state
+                .journal
+                .record(DaemonEventKind::Unlock, Some(&dataset), message);
+            to_value(report?)
+        }
+        ControlCall::Lock { dataset, unmount } => {
+            let dataset = target(dataset)?;
+            info!(dataset:% = dataset, event = "control_lock"; "control: lock {dataset} requested by uid {uid}");
+            let report = service.lock(&dataset, LockOptions { unmount });
+            let message = match &report {
+                Ok(report) => format!(
+                    "locked {} datasets on request of uid {uid}",
+                    report.locked.len()
+                ),
+                Err(err) => format!("lock requested by uid {uid} failed: {err}"),
+            };
+            state
+                .journal
+                .record(DaemonEventKind::Lock, Some(&dataset), message);
+            to_value(report?)
+        }
+        ControlCall::Reload => {
+            info!("control: reload requested by uid {uid}");
+            let changes = reload::apply(state)?;
+            to_value(ReloadReport {
+                config_path: state.config_path.clone(),
+                changes: changes.iter().map(ToString::to_string).collect(),
+            })
+        }
+        ControlCall::KeepAlive => {
+            let resumed = state.idle.keep_alive();
+            if resumed {
+                info!("control: keep-alive from uid {uid} lifts the idle lock");
+                state.journal.record(
+                    DaemonEventKind::Unlock,
+                    None,
+                    format!("idle lock lifted by a keep-alive from uid {uid}"),
+                );
+                state.unlock_now.notify_one();
+            }
+            to_value(KeepAliveReport {
+                lock_at: state.idle.lock_at(&config),
+                resumed,
+            })
+        }
+        ControlCall::RecentEvents { dataset, limit } => {
+            let filter = AuditFilter {
+                dataset,
+                ..AuditFilter::default()
+            };
+            let mut records = audit::read_records(&config.audit.log_path())?;
+            records.retain(|record| filter.matches(record));
+            let skip = records.len().saturating_sub(limit);
+            to_value(&records[skip..])
+        }
+        ControlCall::Events {
+            since,
+            dataset,
+            kind,
+            limit,
+        } => {
+            let filter = EventFilter::parse(since.as_deref(), dataset, kind)?;
+            to_value(state.journal.query(&filter, limit))
+        }
+    }
+}
+
+/// Gid of the group called `name`.
+fn group_id(name: &str) -> Result<u32> {
+    let c_name = CString::new(name).context("group name contains NUL")?;
+    // SAFETY: libc::group is plain C data, for which all zeroes is valid.
+    let mut group: libc::group = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    let mut buf = vec![0 as libc::c_char; 16 * 1024];
+    // SAFETY: every pointer is valid for the call and `buf` outlives `group`'s use.
+    let rc = unsafe {
+        libc::getgrnam_r(
+            c_name.as_ptr(),
+            &mut group,
+            buf.as_mut_ptr(),
+            buf.len(),
+            &mut result,
+        )
+    };
+    if rc != 0 || result.is_null() {
+        anyhow::bail!("no group named {name}");
+    }
+    Ok(group.gr_gid)
+}
+
+/// Whether the user `uid` belongs to the group called `name`, through the
+/// process's effective `gid`, its primary group, or a supplementary one.
+fn in_group(name: &str, uid: u32, gid: Option<u32>) -> bool {
+    let Ok(wanted) = group_id(name) else {
+        return false;
+    };
+    if gid == Some(wanted) {
+        return true;
+    }
+    // SAFETY: libc::passwd is plain C data, for which all zeroes is valid.
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    let mut buf = vec![0 as libc::c_char; 16 * 1024];
+    // SAFETY: every pointer is valid for the call and `buf` outlives `passwd`'s use.
+    let rc =
+        unsafe { libc::getpwuid_r(uid, &mut passwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+    if rc != 0 || result.is_null() {
+        return false;
+    }
+    // SAFETY: getpwuid_r succeeded, so pw_name points at a NUL-terminated name in `buf`.
+    let user = unsafe { CStr::from_ptr(passwd.pw_name) };
+    let mut groups = vec![0 as libc::gid_t; 256];
+    let mut count = groups.len() as libc::c_int;
+    // SAFETY: `groups` holds `count` entries; getgrouplist writes at most that many.
+    let rc = unsafe {
+        libc::getgrouplist(
+            user.as_ptr(),
+            passwd.pw_gid,
+            groups.as_mut_ptr(),
+            &mut count,
+        )
+    };
+    if rc < 0 {
+        warn!("uid {uid} is in more than {} groups", groups.len());
+        return false;
+    }
+    groups[..count as usize].contains(&wanted)
+}
+
+fn to_value<T: Serialize>(value: T) -> LockchainResult<Value> {
+    serde_json::to_value(value)
+        .map_err(|err| LockchainError::Control(format!("encode result: {err}")))
+}
@@ -0,0 +1,252 @@
+//! `org.lockchain.Manager` on the system bus, so desktops and other services
+//! can unlock, lock, and watch datasets without the control socket or the
+//! health endpoint.
+//!
+//! Calls go through the same dispatch and peer authorisation as the control
+//! socket; the caller's uid comes from the bus daemon.
+
+use anyhow::Result;
+use lockchain_core::control::{ControlCall, KeepAliveReport};
+use lockchain_core::{DatasetStatus, LockReport, LockchainResult, UnlockReport};
+use log::{info, warn};
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::runtime::Handle;
+use tokio::sync::{watch, Notify};
+use tokio::time::{interval, Duration};
+use zbus::fdo::{self, DBusProxy};
+use zbus::message::Header;
+use zbus::{interface, Connection, SignalContext};
+
+use crate::{control, DaemonState};
+
+/// Well-known name and object path the manager is served under.
+pub const BUS_NAME: &str = "org.lockchain.Manager";
+pub const OBJECT_PATH: &str = "/org/lockchain/Manager";
+
+/// How often keystatus is polled for `StateChanged`.
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// The exported object.
+struct Manager {
+    state: Arc<DaemonState>,
+    runtime: Handle,
+    health: watch::Receiver<bool>,
+    /// Wakes the poller after an unlock or lock, so its signal is prompt.
+    changed: Arc<Notify>,
+}
+
+impl Manager {
+    /// Authorise `call` for the message's sender and run it on the blocking
+    /// pool, decoding the result as `T`.
+    async fn call<T: DeserializeOwned + Send + 'static>(
+        &self,
+        connection: &Connection,
+        header: &Header<'_>,
+        call: ControlCall,
+    ) -> fdo::Result<T> {
+        let sender = header
+            .sender()
+            .ok_or_else(|| fdo::Error::AccessDenied("message has no sender".into()))?;
+        let uid = DBusProxy::new(connection)
+            .await?
+            .get_connection_unix_user(sender.clone().into())
+            .await?;
+        let method = call.method();
+        let allowed = control::peer_access(&self.state.config().control, uid, None)
+            .is_some_and(|access| access.permits(&call));
+        if !allowed {
+            warn!("dbus: uid {uid} denied {method}");
+            return Err(fdo::Error::AccessDenied(format!(
+                "uid {uid} may not call {method}"
+            )));
+        }
+        let state = self.state.clone();
+        let outcome = self
+            .runtime
+            .spawn_blocking(move || control::dispatch(&state, uid, call))
+            .await
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        let value = outcome.map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        serde_json::from_value(value).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+}
+
+/// An empty string means the daemon's first configured dataset, as an
+/// omitted dataset does on the control socket.
+fn dataset_arg(dataset: String) -> Option<String> {
+    Some(dataset).filter(|dataset| !dataset.is_empty())
+}
+
+#[interface(name = "org.lockchain.Manager")]
+impl Manager {
+    /// `(dataset, encryption root, locked)` for every configured dataset, or
+    /// just `dataset` when it is not empty.
+    async fn status(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: Header<'_>,
+        dataset: String,
+    ) -> fdo::Result<Vec<(String, String, bool)>> {
+        let call = ControlCall::Status {
+            dataset: dataset_arg(dataset),
+        };
+        let statuses: Vec<DatasetStatus> = self.call(connection, &header, call).await?;
+        Ok(statuses
+            .into_iter()
+            .map(|status| (status.dataset, status.encryption_root, status.root_locked))
+            .collect())
+    }
+
+    /// Unlock `dataset` with the daemon's key sources; returns the datasets
+    /// whose keys were loaded.
+    async fn unlock(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: Header<'_>,
+        dataset: String,
+        strict_usb: bool,
+    ) -> fdo::Result<Vec<String>> {
+        let call = ControlCall::Unlock {
+            dataset: dataset_arg(dataset),
+            strict_usb,
+        };
+        let report: UnlockReport = self.call(connection, &header, call).await?;
+        self.changed.notify_one();
+        Ok(report.unlocked)
+    }
+
+    /// Unload `dataset`'s key, unmounting first when asked; returns the
+    /// datasets that were locked.
+    async fn lock(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: Header<'_>,
+        dataset: String,
+        unmount: bool,
+    ) -> fdo::Result<Vec<String>> {
+        let call = ControlCall::Lock {
+            dataset: dataset_arg(dataset),
+            unmount,
+        };
+        let report: LockReport = self.call(connection, &header, call).await?;
+        self.changed.notify_one();
+        Ok(report.locked)
+    }
+
+    /// Restart the idle lock clock, lifting an idle lock already in place;
+    /// returns the RFC 3339 time datasets lock next, or an empty string when
+    /// `[daemon.idle_lock]` is disabled.
+    async fn keep_alive(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: Header<'_>,
+    ) -> fdo::Result<String> {
+        let report: KeepAliveReport = self
+            .call(connection, &header, ControlCall::KeepAlive)
+            .await?;
+        Ok(report.lock_at.unwrap_or_default())
+    }
+
+    /// The readiness the health endpoint reports; changes arrive as
+    /// `HealthChanged` rather than `PropertiesChanged`.
+    #[zbus(property(emits_changed_signal = "false"))]
+    async fn healthy(&self) -> bool {
+        *self.health.borrow()
+    }
+
+    /// A configured dataset's key was loaded or unloaded.
+    #[zbus(signal)]
+    async fn state_changed(
+        ctxt: &SignalContext<'_>,
+        dataset: &str,
+        locked: bool,
+    ) -> zbus::Result<()>;
+
+    /// Overall readiness changed.
+    #[zbus(signal)]
+    async fn health_changed(ctxt: &SignalContext<'_>, healthy: bool) -> zbus::Result<()>;
+}
+
+/// Serve the manager and emit its signals until the daemon shuts down.
+///
+/// Hosts without a system bus, or where the bus policy does not let the
+/// daemon own [`BUS_NAME`], keep running without it.
+pub async fn serve(state: Arc<DaemonState>, health: watch::Receiver<bool>) -> Result<()> {
+    let changed = Arc::new(Notify::new());
+    let manager = Manager {
+        state: state.clone(),
+        runtime: Handle::current(),
+        health: health.clone(),
+        changed: changed.clone(),
+    };
+    let connection = match connect(manager).await {
+        Ok(connection) => connection,
+        Err(err) => {
+            warn!("D-Bus interface unavailable: {err}");
+            return std::future::pending().await;
+        }
+    };
+    info!("D-Bus interface {BUS_NAME} at {OBJECT_PATH}");
+    let ctxt = SignalContext::new(&connection, OBJECT_PATH)?;
+    let mut health = health;
+    let mut known = BTreeMap::new();
+    let mut ticker = interval(POLL_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = changed.notified() => {}
+            res = health.changed() => {
+                if res.is_err() {
+                    return Ok(());
+                }
+                let healthy = *health.borrow_and_update();
+                if let Err(err) = Manager::health_changed(&ctxt, healthy).await {
+                    warn!("D-Bus HealthChanged not sent: {err}");
+                }
+                continue;
+            }
+        }
+        let state = state.clone();
+        let current = match tokio::task::spawn_blocking(move || lock_states(&state)).await? {
+            Ok(current) => current,
+            Err(err) => {
+                warn!("D-Bus keystatus poll failed: {err}");
+                continue;
+            }
+        };
+        for (dataset, locked) in &current {
+            // The first poll only records the starting point.
+            if known.is_empty() || known.get(dataset) == Some(locked) {
+                continue;
+            }
+            if let Err(err) = Manager::state_changed(&ctxt, dataset, *locked).await {
+                warn!("D-Bus StateChanged not sent: {err}");
+            }
+        }
+        known = current;
+    }
+}
+
+async fn connect(manager: Manager) -> zbus::Result<Connection> {
+    zbus::connection::Builder::system()?
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, manager)?
+        .build()
+        .await
+}
+
+/// Whether each configured dataset's encryption root is locked.
+fn lock_states(state: &DaemonState) -> LockchainResult<BTreeMap<String, bool>> {
+    let config = state.config();
+    let estate = state.service().status_all()?;
+    Ok(config
+        .policy
+        .datasets
+        .iter()
+        .filter_map(|dataset| estate.dataset_status(dataset))
+        .map(|status| (status.dataset, status.root_locked))
+        .collect())
+}
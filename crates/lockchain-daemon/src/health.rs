@@ -0,0 +1,551 @@
+//! Component health tracking and the HTTP endpoint that reports it.
+//!
+//! The endpoint serves load balancers, systemd watchdogs, and `curl`: one
+//! `GET` or `HEAD` per connection, parsed by `httparse`, answered with a
+//! JSON body and closed. At most [`MAX_CONNECTIONS`] are served at once, and
+//! each gets one deadline for its whole request head. With `[health]
+//! token_file` set, every request must carry `Authorization: Bearer <token>`
+//! or is answered 401. Requests are answered from [`Snapshot`]s, so the same
+//! code serves them inside the daemon and in the isolated worker (see
+//! [`worker`]).
+
+use anyhow::{Context, Result};
+use lockchain_core::config::HealthCfg;
+use lockchain_core::LockchainResult;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::{watch, Semaphore};
+use tokio::time::{timeout, Duration};
+
+use crate::{worker, DaemonState};
+
+/// Most of a request head read before it is answered.
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+/// How long a client gets to send its whole request head.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// Connections answered at once; further ones are dropped until one closes.
+pub const MAX_CONNECTIONS: usize = 32;
+
+/// Tracks whether USB discovery, unlock routines, config, and the ZFS
+/// provider consider the world healthy.
+struct HealthState {
+    usb_ready: bool,
+    /// Each managed dataset and, when it is not unlocked, why.
+    datasets: BTreeMap<String, Option<String>>,
+    key_intact: bool,
+    /// Why the last config reload failed; the previous config stays live.
+    config_error: Option<String>,
+    /// Why the last provider probe failed.
+    provider_error: Option<String>,
+}
+
+impl Default for HealthState {
+    fn default() -> Self {
+        Self {
+            usb_ready: false,
+            datasets: BTreeMap::new(),
+            key_intact: true,
+            config_error: None,
+            provider_error: None,
+        }
+    }
+}
+
+impl HealthState {
+    /// Overall readiness: every component must agree.
+    fn healthy(&self) -> bool {
+        self.live() && self.usb_ready && self.unlocked() && self.key_intact
+    }
+
+    /// Whether every managed dataset has been seen unlocked.
+    fn unlocked(&self) -> bool {
+        !self.datasets.is_empty() && self.datasets.values().all(Option::is_none)
+    }
+
+    /// Liveness: the daemon can still reach its config and ZFS.
+    fn live(&self) -> bool {
+        self.config_error.is_none() && self.provider_error.is_none()
+    }
+
+    fn report(&self) -> HealthReport {
+        let flag = |ready: bool| Component {
+            ready,
+            detail: None,
+        };
+        let error = |err: &Option<String>| Component {
+            ready: err.is_none(),
+            detail: err.clone(),
+        };
+        let locked: Vec<&str> = self
+            .datasets
+            .iter()
+            .filter(|(_, error)| error.is_some())
+            .map(|(dataset, _)| dataset.as_str())
+            .collect();
+        HealthReport {
+            status: if self.healthy() { "ready" } else { "degraded" },
+            components: Components {
+                usb: flag(self.usb_ready),
+                unlock: Component {
+                    ready: self.unlocked(),
+                    detail: (!locked.is_empty()).then(|| format!("locked: {}", locked.join(", "))),
+                },
+                key: flag(self.key_intact),
+                config: error(&self.config_error),
+                provider: error(&self.provider_error),
+            },
+            datasets: self
+                .datasets
+                .iter()
+                .map(|(dataset, err)| (dataset.clone(), error(err)))
+                .collect(),
+        }
+    }
+}
+
+/// JSON body served by every health route.
+#[derive(Serialize)]
+struct HealthReport {
+    status: &'static str,
+    components: Components,
+    datasets: BTreeMap<String, Component>,
+}
+
+#[derive(Serialize)]
+struct Components {
+    usb: Component,
+    unlock: Component,
+    key: Component,
+    config: Component,
+    provider: Component,
+}
+
+#[derive(Serialize)]
+struct Component {
+    ready: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+/// Shared handle used to notify other tasks when overall health changes.
+#[derive(Clone)]
+pub struct HealthChannel {
+    inner: Arc<HealthInner>,
+}
+
+struct HealthInner {
+    state: Mutex<HealthState>,
+    tx: watch::Sender<bool>,
+}
+
+impl HealthChannel {
+    /// Create a new channel bound to the provided watch sender.
+    pub fn new(tx: watch::Sender<bool>) -> Self {
+        Self {
+            inner: Arc::new(HealthInner {
+                state: Mutex::new(HealthState::default()),
+                tx,
+            }),
+        }
+    }
+
+    /// Record the latest USB availability status.
+    pub fn set_usb_ready(&self, ready: bool) {
+        self.update(|state| state.usb_ready = ready);
+    }
+
+    /// Record whether `dataset` is unlocked, or why it is not.
+    pub fn set_dataset_error(&self, dataset: &str, error: Option<String>) {
+        self.update(|state| {
+            state.datasets.insert(dataset.to_string(), error);
+        });
+    }
+
+    /// Forget datasets that are no longer managed, e.g. after a reload.
+    pub fn retain_datasets(&self, datasets: &[String]) {
+        self.update(|state| {
+            state
+                .datasets
+                .retain(|dataset, _| datasets.contains(dataset))
+        });
+    }
+
+    /// Record whether the latest scrub found the staged key intact.
+    pub fn set_key_intact(&self, intact: bool) {
+        self.update(|state| state.key_intact = intact);
+    }
+
+    /// Record why the latest config reload failed, or `None` once one works.
+    pub fn set_config_error(&self, error: Option<String>) {
+        self.update(|state| state.config_error = error);
+    }
+
+    /// Record why the latest provider probe failed, or `None` once one works.
+    pub fn set_provider_error(&self, error: Option<String>) {
+        self.update(|state| state.provider_error = error);
+    }
+
+    /// Names of the components that are not ready, as in the JSON report.
+    pub fn unready(&self) -> Vec<&'static str> {
+        let state = self.inner.state.lock().unwrap();
+        [
+            ("usb", state.usb_ready),
+            ("unlock", state.unlocked()),
+            ("key", state.key_intact),
+            ("config", state.config_error.is_none()),
+            ("provider", state.provider_error.is_none()),
+        ]
+        .into_iter()
+        .filter(|(_, ready)| !ready)
+        .map(|(name, _)| name)
+        .collect()
+    }
+
+    /// Apply `mutate` and broadcast the overall health if it changed.
+    fn update(&self, mutate: impl FnOnce(&mut HealthState)) {
+        let mut state = self.inner.state.lock().unwrap();
+        mutate(&mut state);
+        let healthy = state.healthy();
+        drop(state);
+        self.inner
+            .tx
+            .send_if_modified(|current| std::mem::replace(current, healthy) != healthy);
+    }
+
+    /// The current responses, to be served under `auth`.
+    pub fn snapshot(&self, auth: Auth) -> Snapshot {
+        let state = self.inner.state.lock().unwrap();
+        let body = serde_json::to_string(&state.report()).unwrap_or_default();
+        let status = |ok: bool| if ok { 200 } else { 503 };
+        Snapshot {
+            ready: (status(state.healthy()), body.clone()),
+            live: (status(state.live()), body),
+            auth,
+        }
+    }
+}
+
+/// Everything needed to answer a request: the responses as of one moment
+/// and who may read them. The isolated worker only ever holds these.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Status and body of `/readyz` and `/`.
+    ready: (u16, String),
+    /// Status and body of `/healthz`.
+    live: (u16, String),
+    auth: Auth,
+}
+
+impl Snapshot {
+    /// What the worker serves before the daemon's first snapshot arrives.
+    pub fn starting() -> Self {
+        let body = r#"{"status":"starting"}"#.to_string();
+        Self {
+            ready: (503, body.clone()),
+            live: (503, body),
+            auth: Auth::Refuse,
+        }
+    }
+
+    /// Status code and JSON body for `path`.
+    fn respond(&self, path: &str) -> (u16, String) {
+        match path {
+            "/healthz" => self.live.clone(),
+            "/readyz" | "/" => self.ready.clone(),
+            _ => (404, r#"{"error":"not found"}"#.into()),
+        }
+    }
+}
+
+/// Who may read the endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Auth {
+    Open,
+    /// Hex SHA-256 of the bearer token; the token itself stays in the daemon.
+    Token(String),
+    /// The token file cannot be read, so nobody may.
+    Refuse,
+}
+
+impl Auth {
+    /// What `[health] token_file` asks for right now.
+    pub fn load(cfg: &HealthCfg) -> LockchainResult<Self> {
+        Ok(match cfg.token()? {
+            Some(token) => Auth::Token(hex::encode(Sha256::digest(token.as_bytes()))),
+            None => Auth::Open,
+        })
+    }
+
+    /// Whether a request carrying `authorization` may be answered.
+    fn permits(&self, authorization: Option<&[u8]>) -> bool {
+        let expected = match self {
+            Auth::Open => return true,
+            Auth::Refuse => return false,
+            Auth::Token(digest) => digest.as_bytes(),
+        };
+        let presented = authorization.and_then(|value| {
+            let value = std::str::from_utf8(value).ok()?.trim();
+            let scheme = value.get(..7)?;
+            scheme
+                .eq_ignore_ascii_case("bearer ")
+                .then(|| value[7..].trim())
+        });
+        // Compare digests so the time taken says nothing about the token.
+        presented.is_some_and(|token| {
+            let digest = hex::encode(Sha256::digest(token.as_bytes()));
+            digest.len() == expected.len()
+                && digest
+                    .bytes()
+                    .zip(expected)
+                    .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                    == 0
+        })
+    }
+}
+
+/// Serve `/healthz` (liveness), `/readyz` (readiness), and `/` (an alias of
+/// `/readyz`) on `LOCKCHAIN_HEALTH_ADDR`, from the sandboxed worker process
+/// unless `[health] isolate` is off.
+pub async fn serve(state: Arc<DaemonState>) -> Result<()> {
+    let addr: SocketAddr = std::env::var("LOCKCHAIN_HEALTH_ADDR")
+        .unwrap_or_else(|_| "127.0.0.1:8787".to_string())
+        .parse()
+        .context("parse LOCKCHAIN_HEALTH_ADDR")?;
+
+    let listener = TcpListener::bind(addr).await?;
+    info!("health endpoint listening on http://{addr}");
+    if state.config().health.isolate {
+        return worker::supervise(state, listener.into_std()?).await;
+    }
+
+    let slots = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let Ok(slot) = slots.clone().try_acquire_owned() else {
+            warn!("health: {MAX_CONNECTIONS} connections already open; dropped {peer}");
+            continue;
+        };
+        let auth = Auth::load(&state.config().health).unwrap_or_else(|err| {
+            warn!("health request refused: {err}");
+            Auth::Refuse
+        });
+        let snapshot = state.health.snapshot(auth);
+        tokio::spawn(async move {
+            if let Err(err) = answer(stream, &snapshot).await {
+                warn!("failed to respond to {peer}: {err}");
+            }
+            drop(slot);
+        });
+    }
+}
+
+/// Read one request head from `stream` and write the response. The client
+/// gets [`READ_TIMEOUT`] for the whole head, however it trickles in.
+pub async fn answer(
+    mut stream: impl AsyncRead + AsyncWrite + Unpin,
+    snapshot: &Snapshot,
+) -> std::io::Result<()> {
+    let head = match timeout(READ_TIMEOUT, read_head(&mut stream)).await {
+        Ok(head) => head?,
+        Err(_) => return Ok(()),
+    };
+
+    let mut headers = [httparse::EMPTY_HEADER; 32];
+    let mut request = httparse::Request::new(&mut headers);
+    let parsed = matches!(request.parse(&head), Ok(httparse::Status::Complete(_)));
+    let authorization = request
+        .headers
+        .iter()
+        .find(|header| header.name.eq_ignore_ascii_case("authorization"))
+        .map(|header| header.value);
+    let (status, body, include_body) = match (parsed, request.method, request.path) {
+        (true, Some(_), Some(_)) if !snapshot.auth.permits(authorization) => {
+            (401, r#"{"error":"unauthorized"}"#.into(), true)
+        }
+        (true, Some(method @ ("GET" | "HEAD")), Some(target)) => {
+            let path = target.split('?').next().unwrap_or(target);
+            let (status, body) = snapshot.respond(path);
+            (status, body, method == "GET")
+        }
+        (true, Some(_), Some(_)) => (405, r#"{"error":"method not allowed"}"#.into(), true),
+        _ => (400, r#"{"error":"bad request"}"#.into(), true),
+    };
+
+    let mut response = format!(
+        "HTTP/1.1 {status} {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\ncache-control: no-store\r\nconnection: close\r\n",
+        reason(status),
+        body.len()
+    );
+    match status {
+        401 => response.push_str("www-authenticate: Bearer realm=\"lockchain\"\r\n"),
+        405 => response.push_str("allow: GET, HEAD\r\n"),
+        _ => {}
+    }
+    response.push_str("\r\n");
+    if include_body {
+        response.push_str(&body);
+    }
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Read until `stream` has sent a whole request head, closed, or sent
+/// [`MAX_REQUEST_HEAD`] bytes.
+async fn read_head(stream: &mut (impl AsyncRead + Unpin)) -> std::io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while head.len() < MAX_REQUEST_HEAD && !head_complete(&head) {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..read]);
+    }
+    Ok(head)
+}
+
+/// Whether `head` holds a whole request head, or one `httparse` already
+/// rejects; either way there is nothing more worth reading.
+fn head_complete(head: &[u8]) -> bool {
+    let mut headers = [httparse::EMPTY_HEADER; 32];
+    !matches!(
+        httparse::Request::new(&mut headers).parse(head),
+        Ok(httparse::Status::Partial)
+    )
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Service Unavailable",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::duplex;
+
+    const TOKEN: &str = "s3cret";
+
+    fn snapshot(auth: Auth) -> Snapshot {
+        Snapshot {
+            ready: (200, r#"{"status":"ready"}"#.into()),
+            live: (200, r#"{"status":"live"}"#.into()),
+            auth,
+        }
+    }
+
+    fn token_auth() -> Auth {
+        Auth::Token(hex::encode(Sha256::digest(TOKEN.as_bytes())))
+    }
+
+    /// Send `request` and return the whole response.
+    async fn exchange(snapshot: &Snapshot, request: &[u8]) -> String {
+        let (mut client, server) = duplex(64 * 1024);
+        client.write_all(request).await.unwrap();
+        answer(server, snapshot).await.unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    fn status_line(response: &str) -> &str {
+        response.lines().next().unwrap_or_default()
+    }
+
+    #[test]
+    fn bearer_tokens_are_checked_against_the_digest() {
+        let auth = token_auth();
+        assert!(auth.permits(Some(b"Bearer s3cret")));
+        assert!(auth.permits(Some(b"bearer   s3cret  ")));
+        assert!(!auth.permits(Some(b"Bearer s3cre")));
+        assert!(!auth.permits(Some(b"Bearer s3cret2")));
+        assert!(!auth.permits(Some(b"Basic s3cret")));
+        assert!(!auth.permits(Some(b"Bearer")));
+        assert!(!auth.permits(Some(b"Bearer \xff")));
+        assert!(!auth.permits(None));
+
+        assert!(Auth::Open.permits(None));
+        assert!(!Auth::Refuse.permits(Some(b"Bearer s3cret")));
+    }
+
+    #[tokio::test]
+    async fn requests_need_the_token_when_one_is_set() {
+        let snapshot = snapshot(token_auth());
+        let ok = exchange(
+            &snapshot,
+            b"GET /healthz HTTP/1.1\r\nAuthorization: Bearer s3cret\r\n\r\n",
+        )
+        .await;
+        assert_eq!(status_line(&ok), "HTTP/1.1 200 OK");
+        assert!(ok.ends_with(r#"{"status":"live"}"#));
+
+        let missing = exchange(&snapshot, b"GET /healthz HTTP/1.1\r\n\r\n").await;
+        assert_eq!(status_line(&missing), "HTTP/1.1 401 Unauthorized");
+        assert!(missing.contains("www-authenticate: Bearer"));
+
+        let wrong = exchange(
+            &snapshot,
+            b"GET /readyz HTTP/1.1\r\nauthorization: Bearer nope\r\n\r\n",
+        )
+        .await;
+        assert_eq!(status_line(&wrong), "HTTP/1.1 401 Unauthorized");
+        assert!(!wrong.contains("ready\""));
+    }
+
+    #[tokio::test]
+    async fn routes_methods_and_queries_are_answered() {
+        let snapshot = snapshot(Auth::Open);
+        let ready = exchange(&snapshot, b"GET /?verbose=1 HTTP/1.1\r\n\r\n").await;
+        assert!(ready.ends_with(r#"{"status":"ready"}"#));
+
+        let head = exchange(&snapshot, b"HEAD /readyz HTTP/1.1\r\n\r\n").await;
+        assert_eq!(status_line(&head), "HTTP/1.1 200 OK");
+        assert!(head.ends_with("\r\n\r\n"));
+
+        let missing = exchange(&snapshot, b"GET /metrics HTTP/1.1\r\n\r\n").await;
+        assert_eq!(status_line(&missing), "HTTP/1.1 404 Not Found");
+
+        let post = exchange(&snapshot, b"POST /readyz HTTP/1.1\r\n\r\n").await;
+        assert_eq!(status_line(&post), "HTTP/1.1 405 Method Not Allowed");
+        assert!(post.contains("allow: GET, HEAD"));
+    }
+
+    #[tokio::test]
+    async fn malformed_and_oversized_heads_are_bad_requests() {
+        let snapshot = snapshot(Auth::Open);
+        for request in [
+            b"garbage\x01 x\r\n\r\n".to_vec(),
+            b"GET /readyz\r\n\r\n".to_vec(),
+            format!(
+                "GET /readyz HTTP/1.1\r\nX-Pad: {}\r\n\r\n",
+                "a".repeat(MAX_REQUEST_HEAD)
+            )
+            .into_bytes(),
+            format!("GET /readyz HTTP/1.1\r\n{}\r\n", "X-A: b\r\n".repeat(40)).into_bytes(),
+        ] {
+            let response = exchange(&snapshot, &request).await;
+            assert_eq!(status_line(&response), "HTTP/1.1 400 Bad Request");
+        }
+
+        // A client that hangs up mid-head gets an answer, not a hang.
+        let (mut client, server) = duplex(1024);
+        client.write_all(b"GET /readyz HT").await.unwrap();
+        client.shutdown().await.unwrap();
+        answer(server, &snapshot).await.unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        assert_eq!(status_line(&response), "HTTP/1.1 400 Bad Request");
+    }
+}
@@ -0,0 +1,216 @@
+//! `[daemon.idle_lock]`: lock managed datasets once nobody has sent a
+//! keep-alive for a while, warning ahead of time.
+//!
+//! Keep-alives arrive over the control socket or D-Bus (`keep_alive`); an
+//! unlock requested through either, and the USB key arriving, count as one
+//! too. Once idle-locked, the daemon leaves those datasets locked until the
+//! next keep-alive.
+
+use anyhow::Result;
+use chrono::{SecondsFormat, Utc};
+use lockchain_core::config::{LockchainConfig, WebhookEvent};
+use lockchain_core::control::DaemonEventKind;
+use lockchain_core::service::LockOptions;
+use lockchain_core::units;
+use log::{error, info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tokio::time::{sleep_until, Duration, Instant};
+
+use crate::DaemonState;
+
+/// Longest sleep between checks, so a reload that enables or shortens the
+/// idle lock takes effect promptly.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// When someone last vouched for the unlocked datasets.
+pub struct IdleClock {
+    last_seen: Mutex<Instant>,
+    /// Set once datasets were idle-locked; periodic unlocks skip them.
+    locked: AtomicBool,
+    touched: Notify,
+}
+
+impl IdleClock {
+    pub fn new() -> Self {
+        Self {
+            last_seen: Mutex::new(Instant::now()),
+            locked: AtomicBool::new(false),
+            touched: Notify::new(),
+        }
+    }
+
+    /// Restart the clock; returns whether datasets were idle-locked.
+    pub fn keep_alive(&self) -> bool {
+        *self.last_seen.lock().unwrap() = Instant::now();
+        self.touched.notify_one();
+        self.locked.swap(false, Ordering::SeqCst)
+    }
+
+    /// Whether the idle lock is holding `dataset` locked under `config`.
+    pub fn holds(&self, config: &LockchainConfig, dataset: &str) -> bool {
+        self.locked.load(Ordering::SeqCst) && !config.daemon.idle_lock.exempts(dataset)
+    }
+
+    /// When datasets are idle-locked unless a keep-alive arrives, as shown
+    /// to callers; `None` when the idle lock is disabled.
+    pub fn lock_at(&self, config: &LockchainConfig) -> Option<String> {
+        let idle = &config.daemon.idle_lock;
+        idle.enabled.then(|| {
+            let left = idle
+                .after
+                .saturating_sub(self.last_seen.lock().unwrap().elapsed());
+            (Utc::now() + left).to_rfc3339_opts(SecondsFormat::Secs, true)
+        })
+    }
+
+    fn deadline(&self, after: Duration) -> Instant {
+        *self.last_seen.lock().unwrap() + after
+    }
+}
+
+/// Warn `warn_before` ahead of the deadline, then lock every managed dataset
+/// that is not exempt, and start over after each keep-alive. The clock also
+/// restarts after an idle lock, so datasets unlocked by hand meanwhile are
+/// locked again once they sit idle as long.
+pub async fn watch(state: Arc<DaemonState>) -> Result<()> {
+    let mut warned = false;
+    loop {
+        let config = state.config();
+        let idle = &config.daemon.idle_lock;
+        let now = Instant::now();
+        let mut wake = now + RECHECK_INTERVAL;
+        if idle.enabled {
+            let deadline = state.idle.deadline(idle.after);
+            let warn_at = deadline
+                .checked_sub(idle.warn_before)
+                .unwrap_or(now)
+                .max(now);
+            if deadline <= now {
+                warned = false;
+                let state = state.clone();
+                tokio::task::spawn_blocking(move || lock_idle(&state)).await?;
+                continue;
+            }
+            if !warned && warn_at <= now {
+                warned = true;
+                let state = state.clone();
+                tokio::task::spawn_blocking(move || warn_idle(&state, deadline - now)).await?;
+            }
+            wake = wake.min(if warned { deadline } else { warn_at });
+        }
+        tokio::select! {
+            _ = sleep_until(wake) => {}
+            _ = state.idle.touched.notified() => warned = false,
+        }
+    }
+}
+
+/// Datasets the idle lock applies to: managed, not exempt, not sharing an
+/// encryption root with an exempt one, and currently unlocked. Each is
+/// paired with its encryption root.
+fn targets(state: &DaemonState) -> Vec<(String, String)> {
+    let (config, service) = (state.config(), state.service());
+    let idle = &config.daemon.idle_lock;
+    let exempt_roots: Vec<String> = idle
+        .exempt
+        .iter()
+        .filter_map(|dataset| service.status(dataset).ok())
+        .map(|status| status.encryption_root)
+        .collect();
+    let mut targets = Vec::new();
+    for dataset in &config.policy.datasets {
+        if idle.exempts(dataset) {
+            continue;
+        }
+        match service.status(dataset) {
+            Ok(status) if status.root_locked => {}
+            Ok(status) if exempt_roots.contains(&status.encryption_root) => {
+                warn!(
+                    "idle lock skips {dataset}: its encryption root {} is shared with an exempt dataset",
+                    status.encryption_root
+                );
+            }
+            Ok(status) => targets.push((dataset.clone(), status.encryption_root)),
+            Err(err) => warn!("idle lock cannot check {dataset}: {err}"),
+        }
+    }
+    targets
+}
+
+/// Announce the coming idle lock of whatever is unlocked now.
+fn warn_idle(state: &DaemonState, left: Duration) {
+    let datasets: Vec<String> = targets(state)
+        .into_iter()
+        .map(|(dataset, _)| dataset)
+        .collect();
+    if datasets.is_empty() {
+        return;
+    }
+    let message = format!(
+        "locking {} in {} unless a keep-alive arrives (`lockchain daemon keep-alive`)",
+        datasets.join(", "),
+        units::format_duration(Duration::from_secs(left.as_secs()))
+    );
+    warn!(event = "idle_lock_warning"; "{message}");
+    state
+        .webhooks
+        .notify(WebhookEvent::IdleLockWarning, None, &message);
+    state.journal.record(DaemonEventKind::Lock, None, message);
+}
+
+/// Unload the keys of every dataset the idle lock applies to, and keep the
+/// daemon from unlocking them again until the next keep-alive.
+fn lock_idle(state: &DaemonState) {
+    let Some(_in_flight) = state.shutdown.enter() else {
+        return;
+    };
+    let (config, service) = (state.config(), state.service());
+    let idle = &config.daemon.idle_lock;
+    let options = LockOptions {
+        unmount: idle.unmount,
+    };
+    let targets = targets(state);
+    state.idle.locked.store(true, Ordering::SeqCst);
+    *state.idle.last_seen.lock().unwrap() = Instant::now();
+    let after = units::format_duration(idle.after);
+    if targets.is_empty() {
+        info!("no keep-alive for {after}; nothing unlocked to idle-lock");
+        return;
+    }
+    let mut roots: Vec<String> = Vec::new();
+    for (dataset, root) in targets {
+        if roots.contains(&root) {
+            continue;
+        }
+        match service.lock(&dataset, options.clone()) {
+            Ok(report) => {
+                let message = format!(
+                    "no keep-alive for {after}: unloaded key ({} datasets)",
+                    report.locked.len()
+                );
+                warn!(dataset:% = report.encryption_root, event = "idle_lock"; "{}: {message}", report.encryption_root);
+                state.webhooks.notify(
+                    WebhookEvent::IdleLock,
+                    Some(&report.encryption_root),
+                    &message,
+                );
+                state.journal.record(
+                    DaemonEventKind::Lock,
+                    Some(&report.encryption_root),
+                    message,
+                );
+                roots.push(report.encryption_root);
+            }
+            Err(err) => {
+                error!(dataset:% = dataset, event = "idle_lock_failed"; "idle lock of {dataset} failed: {err}");
+                state.journal.record(
+                    DaemonEventKind::Lock,
+                    Some(&dataset),
+                    format!("idle lock failed: {err}"),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! In-process import of key material from USB tokens.
+//!
+//! With `[daemon] import_usb` and the `usb-import` feature the daemon runs
+//! the same udev loop as `lockchain-key-usb`, reading the config it already
+//! holds (reloads included) and recording each import, rejection, and
+//! removal in its event journal. The staged key it writes is picked up by
+//! the USB watcher like any other, so health, removal locking, and unlocks
+//! behave exactly as with the separate service.
+
+use anyhow::Result;
+use std::sync::Arc;
+
+use crate::DaemonState;
+
+/// Run the import loop when `[daemon] import_usb` is set; idle otherwise.
+pub async fn serve(state: Arc<DaemonState>) -> Result<()> {
+    if !state.config().daemon.import_usb {
+        return std::future::pending().await;
+    }
+    run(state).await
+}
+
+#[cfg(feature = "usb-import")]
+async fn run(state: Arc<DaemonState>) -> Result<()> {
+    use lockchain_core::control::DaemonEventKind;
+    use lockchain_key_usb::{ImportEvent, KeyImporter};
+
+    let source = state.clone();
+    let importer = KeyImporter::new(move || source.config()).observe(move |event| {
+        let message = match event {
+            ImportEvent::Imported { token, dest } => {
+                format!("imported key from token `{token}` to {}", dest.display())
+            }
+            ImportEvent::Rejected { token, reason } => {
+                format!("token `{token}` rejected: {reason}")
+            }
+            ImportEvent::Removed { token } => {
+                format!("token `{token}` removed; staged key cleared")
+            }
+        };
+        state.journal.record(DaemonEventKind::Usb, None, message);
+    });
+    tokio::task::spawn_blocking(move || importer.run()).await?
+}
+
+#[cfg(not(feature = "usb-import"))]
+async fn run(_state: Arc<DaemonState>) -> Result<()> {
+    log::warn!(
+        "[daemon] import_usb is set, but this daemon was built without the usb-import feature; \
+         keep lockchain-key-usb running instead"
+    );
+    std::future::pending().await
+}
@@ -0,0 +1,171 @@
+//! Bounded journal of what the daemon did: unlock attempts, locks, USB key
+//! transitions, health changes, and reloads, answered over the control
+//! socket's `events` method.
+//!
+//! Entries are kept in memory. With `LOCKCHAIN_EVENT_JOURNAL` set they are
+//! also appended to that file as JSON lines and replayed at start-up, so
+//! `lockchain daemon events` still reaches back past a restart.
+
+use anyhow::Result;
+use lockchain_core::control::{DaemonEvent, DaemonEventKind, EventFilter};
+use log::warn;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+use crate::HealthChannel;
+
+/// Most entries kept; older ones are dropped first.
+const CAPACITY: usize = 1000;
+
+/// Shared handle every task records its events through.
+#[derive(Clone)]
+pub struct Journal {
+    inner: Arc<Mutex<JournalInner>>,
+}
+
+struct JournalInner {
+    events: VecDeque<DaemonEvent>,
+    file: Option<PathBuf>,
+    /// Lines appended since the file was last rewritten; it is compacted
+    /// back to the in-memory entries once this reaches [`CAPACITY`].
+    appended: usize,
+    /// Whether the last write to the file failed, so failures are logged once.
+    write_failed: bool,
+}
+
+impl Journal {
+    /// Journal persisted to `LOCKCHAIN_EVENT_JOURNAL` when it is set.
+    pub fn open() -> Self {
+        let file = std::env::var_os("LOCKCHAIN_EVENT_JOURNAL")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from);
+        let mut events = VecDeque::with_capacity(CAPACITY);
+        if let Some(path) = &file {
+            match replay(path) {
+                Ok(replayed) => events = replayed,
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => warn!("event journal {} not replayed: {err}", path.display()),
+            }
+        }
+        Self {
+            inner: Arc::new(Mutex::new(JournalInner {
+                events,
+                file,
+                appended: CAPACITY,
+                write_failed: false,
+            })),
+        }
+    }
+
+    /// Record a `kind` event happening now.
+    pub fn record(&self, kind: DaemonEventKind, dataset: Option<&str>, message: impl Into<String>) {
+        let event = DaemonEvent::now(kind, dataset, message);
+        let mut inner = self.inner.lock().unwrap();
+        if inner.events.len() == CAPACITY {
+            inner.events.pop_front();
+        }
+        inner.events.push_back(event);
+        inner.persist();
+    }
+
+    /// Matching entries, oldest first; only the newest `limit` when given.
+    pub fn query(&self, filter: &EventFilter, limit: Option<usize>) -> Vec<DaemonEvent> {
+        let inner = self.inner.lock().unwrap();
+        let matching: Vec<&DaemonEvent> = inner
+            .events
+            .iter()
+            .filter(|event| filter.matches(event))
+            .collect();
+        let skip = limit.map_or(0, |limit| matching.len().saturating_sub(limit));
+        matching[skip..]
+            .iter()
+            .map(|event| (*event).clone())
+            .collect()
+    }
+}
+
+impl JournalInner {
+    /// Append the newest entry to the file, rewriting it from memory instead
+    /// when enough lines have piled up.
+    fn persist(&mut self) {
+        let Some(path) = self.file.clone() else {
+            return;
+        };
+        let written = if self.appended >= CAPACITY {
+            self.appended = 0;
+            rewrite(&path, &self.events)
+        } else {
+            self.appended += 1;
+            let newest = self.events.back().into_iter();
+            open_private(&path, OpenOptions::new().append(true))
+                .and_then(|file| write(file, newest))
+        };
+        match written {
+            Ok(()) => self.write_failed = false,
+            Err(err) if !self.write_failed => {
+                warn!("event journal {} not written: {err}", path.display());
+                self.write_failed = true;
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+/// The newest [`CAPACITY`] entries in the file at `path`; malformed lines,
+/// such as one cut short by a crash, are skipped.
+fn replay(path: &Path) -> std::io::Result<VecDeque<DaemonEvent>> {
+    let mut events = VecDeque::with_capacity(CAPACITY);
+    for line in BufReader::new(File::open(path)?).lines() {
+        let Ok(event) = serde_json::from_str(&line?) else {
+            continue;
+        };
+        if events.len() == CAPACITY {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+    Ok(events)
+}
+
+/// Replace the file at `path` with `events`, atomically.
+fn rewrite(path: &Path, events: &VecDeque<DaemonEvent>) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    let file = open_private(&tmp, OpenOptions::new().write(true).truncate(true))?;
+    write(file, events.iter())?;
+    fs::rename(&tmp, path)
+}
+
+fn open_private(path: &Path, options: &mut OpenOptions) -> std::io::Result<File> {
+    options.create(true).mode(0o600).open(path)
+}
+
+fn write<'a>(mut file: File, events: impl Iterator<Item = &'a DaemonEvent>) -> std::io::Result<()> {
+    let mut lines = String::new();
+    for event in events {
+        lines.push_str(&serde_json::to_string(event)?);
+        lines.push('\n');
+    }
+    file.write_all(lines.as_bytes())
+}
+
+/// Record every change of overall health, naming what is not ready.
+pub async fn record_health(
+    journal: Journal,
+    health: HealthChannel,
+    mut healthy: watch::Receiver<bool>,
+) -> Result<()> {
+    while healthy.changed().await.is_ok() {
+        if *healthy.borrow_and_update() {
+            journal.record(DaemonEventKind::Health, None, "ready");
+        } else {
+            let message = format!("degraded: {} not ready", health.unready().join(", "));
+            journal.record(DaemonEventKind::Health, None, message);
+        }
+    }
+    Ok(())
+}
@@ -1,198 +1,515 @@
 //! Background daemon that watches the USB token and keeps datasets unlocked.
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use lockchain_core::{
-    config::LockchainConfig,
+    config::{LockchainConfig, WebhookEvent},
+    control::DaemonEventKind,
     logging,
     service::{LockchainService, UnlockOptions},
+    ConfigChange, LockchainError, LockchainResult,
 };
 use lockchain_zfs::SystemZfsProvider;
 use log::{error, info, warn};
-use std::net::SocketAddr;
-use std::sync::{Arc, Mutex};
-use tokio::io::AsyncWriteExt;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
 use tokio::{
-    net::TcpListener,
-    select, signal,
+    select,
     sync::watch,
-    time::{interval, Duration, Instant},
+    sync::Notify,
+    task::JoinError,
+    time::{sleep, Duration, Instant},
 };
 
+mod control;
+mod dbus;
+mod health;
+mod idle;
+mod import;
+mod journal;
+mod privsep;
+mod reload;
+mod remote_unlock;
+mod sandbox;
+mod scrub;
+mod shutdown;
+mod systemd;
 mod usb;
+mod webhook;
+mod worker;
 
-/// Tracks whether USB discovery and unlock routines consider the world healthy.
-#[derive(Default)]
-struct HealthState {
-    usb_ready: bool,
-    unlock_ready: bool,
-}
+use health::HealthChannel;
+use idle::IdleClock;
+use journal::Journal;
+use privsep::{HelperZfs, Zfs};
+use shutdown::Shutdown;
+use systemd::Watchdog;
+use webhook::Webhooks;
 
-/// Shared handle used to notify other tasks when overall health changes.
-#[derive(Clone)]
-struct HealthChannel {
-    inner: Arc<HealthInner>,
+/// Config and service the daemon is currently running with.
+///
+/// Tasks fetch both on every pass, so a reload takes effect on their next tick.
+struct DaemonState {
+    config_path: PathBuf,
+    health: HealthChannel,
+    journal: Journal,
+    webhooks: Webhooks,
+    /// Set when the USB watcher locked datasets because the key was pulled;
+    /// periodic unlocks stay off until the key is back.
+    removal_locked: AtomicBool,
+    idle: IdleClock,
+    shutdown: Shutdown,
+    /// Woken by the USB watcher when the key arrives, to unlock at once.
+    unlock_now: Notify,
+    /// Runs ZFS for the daemon once it has switched to `[daemon] user`.
+    helper: Option<HelperZfs>,
+    current: RwLock<(Arc<LockchainConfig>, Arc<LockchainService<Zfs>>)>,
 }
 
-struct HealthInner {
-    state: Mutex<HealthState>,
-    tx: watch::Sender<bool>,
-}
+impl DaemonState {
+    /// Load the config at `config_path` and build the initial service.
+    fn load(
+        config_path: PathBuf,
+        health: HealthChannel,
+        journal: Journal,
+        webhooks: Webhooks,
+        helper: Option<HelperZfs>,
+    ) -> LockchainResult<Self> {
+        let current = Self::build(&config_path, helper.as_ref())?;
+        Ok(Self {
+            config_path,
+            health,
+            journal,
+            webhooks,
+            removal_locked: AtomicBool::new(false),
+            idle: IdleClock::new(),
+            shutdown: Shutdown::new(),
+            unlock_now: Notify::new(),
+            helper,
+            current: RwLock::new(current),
+        })
+    }
 
-impl HealthChannel {
-    /// Create a new channel bound to the provided watch sender.
-    fn new(tx: watch::Sender<bool>) -> Self {
-        Self {
-            inner: Arc::new(HealthInner {
-                state: Mutex::new(HealthState::default()),
-                tx,
-            }),
-        }
+    fn build(
+        config_path: &Path,
+        helper: Option<&HelperZfs>,
+    ) -> LockchainResult<(Arc<LockchainConfig>, Arc<LockchainService<Zfs>>)> {
+        let config = Arc::new(LockchainConfig::load(config_path)?);
+        let provider = match helper {
+            Some(helper) => Zfs::Helper(helper.clone()),
+            None => Zfs::Direct(SystemZfsProvider::from_config(&config)?),
+        };
+        let service = Self::new_service(&config, provider, helper);
+        Ok((config, service))
     }
 
-    /// Record the latest USB availability status.
-    fn set_usb_ready(&self, ready: bool) {
-        let mut state = self.inner.state.lock().unwrap();
-        let changed = state.usb_ready != ready;
-        state.usb_ready = ready;
-        let healthy = state.usb_ready && state.unlock_ready;
-        drop(state);
-        if changed {
-            let _ = self.inner.tx.send(healthy);
-        }
+    /// The service for `config`; with the ZFS helper, key files and the
+    /// audit trail go through it as well, since the daemon cannot open them.
+    fn new_service(
+        config: &Arc<LockchainConfig>,
+        provider: Zfs,
+        helper: Option<&HelperZfs>,
+    ) -> Arc<LockchainService<Zfs>> {
+        let service = LockchainService::new(config.clone(), provider);
+        Arc::new(match helper {
+            Some(helper) => service
+                .with_key_reader(helper.key_reader())
+                .with_audit_log(helper.audit_log(&config.audit)),
+            None => service,
+        })
+    }
+
+    /// Build a service for `config`, reusing the running provider unless
+    /// the ZFS binary paths or the command timeout changed. The ZFS helper,
+    /// when there is one, re-reads the config itself.
+    fn rebuild(
+        &self,
+        config: LockchainConfig,
+    ) -> LockchainResult<(Arc<LockchainConfig>, Arc<LockchainService<Zfs>>)> {
+        let config = Arc::new(config);
+        let current = self.config();
+        let provider = if let Some(helper) = &self.helper {
+            helper.reload()?;
+            Zfs::Helper(helper.clone())
+        } else if current.zfs_binary_path() == config.zfs_binary_path()
+            && current.zpool_binary_path() == config.zpool_binary_path()
+            && current.zfs_timeout() == config.zfs_timeout()
+        {
+            self.service().provider().clone()
+        } else {
+            info!("ZFS binary paths or timeout changed; re-initialising the provider");
+            Zfs::Direct(SystemZfsProvider::from_config(&config)?)
+        };
+        let service = Self::new_service(&config, provider, self.helper.as_ref());
+        Ok((config, service))
+    }
+
+    fn config(&self) -> Arc<LockchainConfig> {
+        self.current.read().unwrap().0.clone()
     }
 
-    /// Record whether unlock attempts have been succeeding lately.
-    fn set_unlock_ready(&self, ready: bool) {
-        let mut state = self.inner.state.lock().unwrap();
-        let changed = state.unlock_ready != ready;
-        state.unlock_ready = ready;
-        let healthy = state.usb_ready && state.unlock_ready;
-        drop(state);
-        if changed {
-            let _ = self.inner.tx.send(healthy);
+    fn service(&self) -> Arc<LockchainService<Zfs>> {
+        self.current.read().unwrap().1.clone()
+    }
+
+    /// Swap in a freshly loaded config and a service built for it.
+    ///
+    /// When `loaded` is an error the running config is kept, and the config
+    /// component reports unhealthy until a later reload succeeds.
+    fn install(
+        &self,
+        loaded: LockchainResult<LockchainConfig>,
+    ) -> LockchainResult<Vec<ConfigChange>> {
+        let next = loaded
+            .and_then(|config| self.rebuild(config))
+            .inspect_err(|err| {
+                self.health
+                    .set_config_error(Some(format!("reload failed, previous config kept: {err}")));
+            })?;
+        self.health.set_config_error(None);
+        let changes = self.config().diff(&next.0);
+        *self.current.write().unwrap() = next;
+        info!(
+            event = "reload";
+            "reloaded {} ({} settings changed)",
+            self.config_path.display(),
+            changes.len()
+        );
+        for change in &changes {
+            info!(event = "reload"; "config change: {change}");
         }
+        Ok(changes)
     }
 }
 
-/// Entry point for the Tokio runtime; logs failures before exit.
-#[tokio::main(flavor = "multi_thread")]
-async fn main() {
-    if let Err(err) = run().await {
+/// Start the daemon, or its health worker, ZFS helper, or the early-boot
+/// remote unlock listener when asked to; logs failures before exit.
+fn main() {
+    let result = match std::env::args().nth(1).as_deref() {
+        Some(worker::ARG) => worker::run(),
+        Some(privsep::ARG) => privsep::run(),
+        Some(remote_unlock::ARG) => remote_unlock::run(),
+        _ => privsep::start().and_then(|helper| {
+            tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .map_err(Into::into)
+                .and_then(|runtime| {
+                    let result = runtime.block_on(run(helper));
+                    // Do not wait on a `zfs` call abandoned at shutdown_timeout.
+                    runtime.shutdown_timeout(Duration::from_secs(1));
+                    result
+                })
+        }),
+    };
+    if let Err(err) = result {
         error!("daemon exit: {err:?}");
-        std::process::exit(1);
+        std::process::exit(shutdown::exit_code(&err));
     }
 }
 
+/// Config file named by `LOCKCHAIN_CONFIG`, else the packaged default.
+fn config_path() -> String {
+    std::env::var("LOCKCHAIN_CONFIG").unwrap_or_else(|_| "/etc/lockchain-zfs.toml".to_string())
+}
+
 /// Load configuration, start background tasks, and juggle shutdown signals.
-async fn run() -> Result<()> {
+async fn run(helper: Option<HelperZfs>) -> Result<()> {
     logging::init("info");
-    let config_path =
-        std::env::var("LOCKCHAIN_CONFIG").unwrap_or_else(|_| "/etc/lockchain-zfs.toml".to_string());
-    let config = Arc::new(
-        LockchainConfig::load(&config_path)
-            .with_context(|| format!("load config {config_path}"))?,
-    );
-
-    info!("LockChain daemon booting (config: {config_path})");
-
-    let provider = SystemZfsProvider::from_config(&config).context("initialise zfs provider")?;
-    let service = Arc::new(LockchainService::new(config.clone(), provider));
+    let config_path = config_path();
 
     // health status broadcast (true = ready, false = degraded)
-    let (health_tx, health_rx) = watch::channel(false);
+    let (health_tx, _) = watch::channel(false);
     let health_channel = HealthChannel::new(health_tx.clone());
+    let (webhooks, notifications) = Webhooks::channel();
+    let state = Arc::new(
+        DaemonState::load(
+            PathBuf::from(&config_path),
+            health_channel.clone(),
+            Journal::open(),
+            webhooks,
+            helper,
+        )
+        .with_context(|| format!("load config {config_path}"))?,
+    );
+
+    info!("LockChain daemon booting (config: {config_path})");
 
-    let usb_handle = tokio::spawn(usb::watch_usb(config.clone(), health_channel.clone()));
+    let watchdog = Watchdog::default();
+    let usb_handle = tokio::spawn(usb::watch_usb(
+        state.clone(),
+        health_channel.clone(),
+        watchdog.clone(),
+    ));
     let unlock_handle = tokio::spawn(periodic_unlock(
-        service.clone(),
-        config.clone(),
+        state.clone(),
         health_channel.clone(),
+        watchdog.clone(),
     ));
-    let health_handle = tokio::spawn(health_server(health_rx));
-
-    select! {
-        res = usb_handle => res??,
-        res = unlock_handle => res??,
-        res = health_handle => res??,
-        _ = signal::ctrl_c() => {
-            info!("received shutdown signal");
-        }
+    let scrub_handle = tokio::spawn(scrub::scrub_staged_key(
+        state.clone(),
+        health_channel.clone(),
+    ));
+    let health_handle = tokio::spawn(health::serve(state.clone()));
+    let mut control_handle = tokio::spawn(control::serve(state.clone()));
+    let dbus_handle = tokio::spawn(dbus::serve(state.clone(), health_tx.subscribe()));
+    let reload_handle = tokio::spawn(reload::watch(state.clone()));
+    let systemd_handle = tokio::spawn(systemd::serve(watchdog, health_tx.subscribe()));
+    let webhook_handle = tokio::spawn(webhook::deliver(state.clone(), notifications));
+    let breakglass_handle = tokio::spawn(webhook::follow_audit(state.clone()));
+    let import_handle = tokio::spawn(import::serve(state.clone()));
+    let idle_handle = tokio::spawn(idle::watch(state.clone()));
+    let journal_handle = tokio::spawn(journal::record_health(
+        state.journal.clone(),
+        health_channel.clone(),
+        health_tx.subscribe(),
+    ));
+    systemd::ready();
+
+    let outcome = select! {
+        res = usb_handle => joined(res),
+        res = unlock_handle => joined(res),
+        res = scrub_handle => joined(res),
+        res = health_handle => joined(res),
+        res = &mut control_handle => joined(res),
+        res = dbus_handle => joined(res),
+        res = reload_handle => joined(res),
+        res = systemd_handle => joined(res),
+        res = journal_handle => joined(res),
+        res = webhook_handle => joined(res),
+        res = breakglass_handle => joined(res),
+        res = import_handle => joined(res),
+        res = idle_handle => joined(res),
+        _ = helper_lost(&state) => Err(anyhow!("ZFS helper exited")),
+        signal = shutdown::signalled() => signal.map(|signal| {
+            info!("received {signal}; shutting down");
+        }),
+    };
+    systemd::stopping();
+
+    // A failed task still gets the orderly stop; its error wins the exit.
+    control_handle.abort();
+    control::remove_socket();
+    let stopped = shutdown::stop(&state).await;
+    outcome.and(stopped)
+}
+
+/// Resolves if the ZFS helper goes away; never without one.
+async fn helper_lost(state: &DaemonState) {
+    match &state.helper {
+        Some(helper) => helper.closed().await,
+        None => std::future::pending().await,
     }
+}
+
+/// Outcome of a background task, counting a panic as an error.
+fn joined(res: Result<Result<()>, JoinError>) -> Result<()> {
+    res.unwrap_or_else(|err| Err(err.into()))
+}
+
+/// Why a dataset is still locked after a pass.
+enum StillLocked {
+    /// Nothing was tried, e.g. the key is missing; the next pass tries again.
+    Waiting(String),
+    /// An unlock ran and failed; further attempts back off.
+    Failed(String),
+}
 
-    Ok(())
+/// Consecutive failed unlocks of one dataset.
+struct Backoff {
+    failures: u32,
+    /// When the next attempt is allowed.
+    due: Instant,
+    error: String,
 }
 
-/// Periodically attempt to unlock the configured dataset and update health.
+/// Check every configured dataset each `[daemon] unlock_interval`, unlock
+/// the locked ones, and report each dataset's readiness to the health
+/// channel.
+///
+/// Datasets are handled independently, so a missing key file or a failing
+/// unlock leaves the others alone. A dataset whose unlock fails is retried
+/// after a doubling backoff capped at `max_backoff`, though its keystatus is
+/// still checked every pass. The USB key arriving starts a pass at once and
+/// clears every backoff. The watchdog is beaten before each dataset and
+/// every retry, so only an attempt that hangs past the crypto timeout stops
+/// the pings.
 async fn periodic_unlock(
-    service: Arc<LockchainService<SystemZfsProvider>>,
-    config: Arc<LockchainConfig>,
+    state: Arc<DaemonState>,
     health: HealthChannel,
+    watchdog: Watchdog,
 ) -> Result<()> {
-    let mut ticker = interval(Duration::from_secs(30));
-    let mut last_success = Instant::now();
+    let mut last_unlocked: HashMap<String, Instant> = HashMap::new();
+    let mut backoff: HashMap<String, Backoff> = HashMap::new();
+    let mut wait = Duration::ZERO;
     loop {
-        ticker.tick().await;
-        let dataset = config.policy.datasets.first().cloned().unwrap_or_default();
-        if dataset.is_empty() {
-            warn!("no datasets configured; daemon idle");
-            continue;
+        watchdog.beat("unlock", wait);
+        select! {
+            _ = sleep(wait) => {}
+            _ = state.unlock_now.notified() => {
+                info!("USB key arrived; checking datasets now");
+                backoff.clear();
+            }
         }
-
-        let key_path = config.key_hex_path();
-        let key_ready = std::fs::metadata(&key_path)
-            .map(|meta| meta.is_file() && meta.len() == 32)
-            .unwrap_or(false);
-        if !key_ready {
-            health.set_unlock_ready(false);
+        let (config, service) = (state.config(), state.service());
+        let schedule = &config.daemon;
+        wait = schedule.unlock_interval;
+        let datasets = &config.policy.datasets;
+        health.retain_datasets(datasets);
+        last_unlocked.retain(|dataset, _| datasets.contains(dataset));
+        backoff.retain(|dataset, _| datasets.contains(dataset));
+        if datasets.is_empty() {
+            warn!("no datasets configured; daemon idle");
             continue;
         }
 
-        match service.unlock_with_retry(&dataset, UnlockOptions::default()) {
-            Ok(report) => {
-                if report.already_unlocked {
-                    info!("dataset {dataset} already unlocked");
-                } else {
-                    info!("unlocked {dataset} with {} nodes", report.unlocked.len());
+        let mut provider_error = None;
+        for dataset in datasets {
+            watchdog.beat("unlock", schedule.unlock_interval + config.crypto.timeout);
+            let status = match service.status(dataset) {
+                Ok(status) => status,
+                Err(err) => {
+                    warn!(dataset:% = dataset, event = "keystatus_failed"; "keystatus check failed for {dataset}: {err}");
+                    health.set_dataset_error(dataset, Some(err.to_string()));
+                    // A dataset that does not exist says nothing about ZFS itself.
+                    if matches!(err, LockchainError::Provider(_)) {
+                        provider_error = Some(err.to_string());
+                    }
+                    continue;
                 }
-                health.set_unlock_ready(true);
-                last_success = Instant::now();
-            }
-            Err(err) => {
-                warn!("unlock attempt failed for {dataset}: {err}");
-                health.set_unlock_ready(false);
-                // degrade if failure lasts >5 minutes
-                if last_success.elapsed() > Duration::from_secs(300) {
-                    warn!(
-                        "dataset {dataset} has been locked for {:?}",
-                        last_success.elapsed()
-                    );
+            };
+            let now = Instant::now();
+            let outcome = if !status.root_locked && status.locked_descendants.is_empty() {
+                backoff.remove(dataset);
+                Ok(())
+            } else if let Some(pending) = backoff.get(dataset).filter(|pending| pending.due > now) {
+                Err(format!(
+                    "{}; next attempt in {:?}",
+                    pending.error,
+                    pending.due.saturating_duration_since(now)
+                ))
+            } else {
+                // Hand this worker's other tasks, signal handling among them,
+                // to another thread while `zfs load-key` blocks.
+                let unlocked = tokio::task::block_in_place(|| {
+                    unlock_dataset(&state, dataset, &status.encryption_root, &watchdog)
+                });
+                match unlocked {
+                    Ok(()) => {
+                        backoff.remove(dataset);
+                        Ok(())
+                    }
+                    Err(StillLocked::Waiting(reason)) => Err(reason),
+                    Err(StillLocked::Failed(error)) => {
+                        let failures = backoff.get(dataset).map_or(0, |prev| prev.failures) + 1;
+                        let delay = schedule.backoff(failures);
+                        if failures > 1 {
+                            warn!(dataset:% = dataset, event = "unlock_backoff"; "unlock of {dataset} failed {failures} times in a row; next attempt in {delay:?}");
+                        }
+                        backoff.insert(
+                            dataset.clone(),
+                            Backoff {
+                                failures,
+                                due: now + delay,
+                                error: error.clone(),
+                            },
+                        );
+                        Err(error)
+                    }
                 }
+            };
+            let since = last_unlocked
+                .entry(dataset.clone())
+                .or_insert_with(Instant::now);
+            if outcome.is_ok() {
+                *since = Instant::now();
+            } else if since.elapsed() > schedule.warn_locked_after {
+                warn!(
+                    dataset:% = dataset, event = "locked_too_long";
+                    "dataset {dataset} has been locked for {:?}",
+                    since.elapsed()
+                );
             }
+            health.set_dataset_error(dataset, outcome.err());
         }
+        health.set_provider_error(provider_error);
     }
 }
 
-/// Expose a bare-bones HTTP endpoint for readiness checks.
-async fn health_server(status_rx: watch::Receiver<bool>) -> Result<()> {
-    let addr: SocketAddr = std::env::var("LOCKCHAIN_HEALTH_ADDR")
-        .unwrap_or_else(|_| "127.0.0.1:8787".to_string())
-        .parse()
-        .context("parse LOCKCHAIN_HEALTH_ADDR")?;
-
-    let listener = TcpListener::bind(addr).await?;
-    info!("health endpoint listening on http://{addr}");
+/// Unlock `dataset`, whose encryption root is `root`, when its key is at
+/// hand; the error says why it stays locked.
+fn unlock_dataset(
+    state: &DaemonState,
+    dataset: &str,
+    root: &str,
+    watchdog: &Watchdog,
+) -> Result<(), StillLocked> {
+    if state.removal_locked.load(Ordering::SeqCst) {
+        return Err(StillLocked::Waiting(
+            "locked since the USB token was removed".into(),
+        ));
+    }
+    let Some(_in_flight) = state.shutdown.enter() else {
+        return Err(StillLocked::Waiting("daemon is shutting down".into()));
+    };
+    let (config, service) = (state.config(), state.service());
+    if state.idle.holds(&config, dataset) {
+        return Err(StillLocked::Waiting(
+            "idle-locked; waiting for a keep-alive (`lockchain daemon keep-alive`)".into(),
+        ));
+    }
+    let key_path = config.key_source(dataset, root).path;
+    let key_ready = config.vault_for(dataset, root).is_some()
+        || std::fs::metadata(&key_path)
+            .map(|meta| meta.is_file() && meta.len() == 32)
+            .unwrap_or(false);
+    if !key_ready {
+        return Err(StillLocked::Waiting(format!(
+            "key material missing at {}",
+            key_path.display()
+        )));
+    }
 
-    loop {
-        let (mut stream, peer) = listener.accept().await?;
-        let healthy = *status_rx.borrow();
-        let body = if healthy { "OK" } else { "DEGRADED" };
-        let response = format!(
-            "HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: {}\r\n\r\n{}",
-            body.len(),
-            body
+    let journal = &state.journal;
+    let unlock = service.unlock_with_retry_observed(dataset, UnlockOptions::default(), |retry| {
+        warn!(
+            dataset:% = dataset, event = "unlock_retry";
+            "unlock attempt {}/{} for {dataset} failed: {}; retrying in {:?}",
+            retry.attempt, retry.max_attempts, retry.error, retry.delay
         );
-        if let Err(err) = stream.write_all(response.as_bytes()).await {
-            warn!("failed to respond to {peer}: {err}");
+        journal.record(
+            DaemonEventKind::Unlock,
+            Some(dataset),
+            format!(
+                "attempt {}/{} failed: {}",
+                retry.attempt, retry.max_attempts, retry.error
+            ),
+        );
+        watchdog.beat("unlock", retry.delay + config.crypto.timeout);
+    });
+    match unlock {
+        Ok(report) => {
+            if report.already_unlocked {
+                info!(dataset:% = dataset, event = "unlock"; "dataset {dataset} already unlocked");
+            } else {
+                info!(dataset:% = dataset, event = "unlock"; "unlocked {dataset} with {} nodes", report.unlocked.len());
+                let message = format!("unlocked {} datasets", report.unlocked.len());
+                state
+                    .webhooks
+                    .notify(WebhookEvent::UnlockSuccess, Some(dataset), &message);
+                journal.record(DaemonEventKind::Unlock, Some(dataset), message);
+            }
+            Ok(())
+        }
+        Err(err) => {
+            warn!(dataset:% = dataset, event = "unlock_failed"; "unlock attempt failed for {dataset}: {err}");
+            let message = format!("unlock failed: {err}");
+            state
+                .webhooks
+                .notify(WebhookEvent::UnlockFailure, Some(dataset), &message);
+            journal.record(DaemonEventKind::Unlock, Some(dataset), message);
+            Err(StillLocked::Failed(err.to_string()))
         }
     }
 }
@@ -0,0 +1,914 @@
+//! Privileged helper that runs ZFS for a daemon that gave up root.
+//!
+//! With `[daemon] user` set and the daemon started as root, the daemon
+//! re-executes itself as a helper before anything else starts, then switches
+//! to that user for good. The helper stays root and runs `zfs` and `zpool`
+//! on the daemon's behalf, one JSON line per request over a socketpair. It
+//! offers only what the daemon needs to check, unlock, mount, and lock
+//! datasets (no key changes), and only for the managed datasets and the
+//! encryption roots above them, so a compromised daemon cannot touch other
+//! datasets or rewrap keys. Key files stay readable by root alone, so the
+//! helper also reads the configured keys (and the token's copies) and
+//! appends to the audit trail, which it leaves readable by the daemon's
+//! group. The helper reads the config itself, re-reads it when the daemon
+//! reloads, ignores SIGTERM and SIGINT so a stopping daemon can still lock,
+//! and exits when the daemon closes the channel.
+
+use anyhow::{anyhow, bail, Context, Result};
+use lockchain_core::audit::{AuditEntry, AuditLog};
+use lockchain_core::config::{AuditCfg, LockchainConfig};
+use lockchain_core::keyfile::decode_key_bytes;
+use lockchain_core::provider::{KeyStatusSnapshot, ZfsProvider};
+use lockchain_core::service::KeyReader;
+use lockchain_core::{logging, token, LockchainError, LockchainResult, SecretBuffer};
+use lockchain_zfs::SystemZfsProvider;
+use log::{info, warn};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+use std::os::unix::net::UnixStream;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use tokio::sync::Notify;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{scrub, worker};
+
+/// First argument that starts the binary as the ZFS helper.
+pub const ARG: &str = "--zfs-helper";
+
+/// Descriptor number the channel to the daemon arrives on.
+const CHANNEL_FD: RawFd = 3;
+/// Longest request line the helper accepts; it exits after a longer one.
+const MAX_REQUEST: u64 = 64 * 1024;
+/// Requests the helper runs at once, so a slow `zfs load-key` does not hold
+/// up status queries.
+const THREADS: usize = 4;
+
+/// ZFS calls the daemon may ask of the helper.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Op {
+    EncryptionRoot {
+        dataset: String,
+    },
+    LockedDescendants {
+        root: String,
+    },
+    KeyTreeStatus {
+        root: String,
+    },
+    LoadKeyTree {
+        root: String,
+        /// Raw key, hex-encoded.
+        key: Zeroizing<String>,
+    },
+    UnloadKeyTree {
+        root: String,
+        unmount: bool,
+    },
+    MountDataset {
+        dataset: String,
+    },
+    DescribeDatasets {
+        datasets: Vec<String>,
+    },
+    /// Read a configured key file, or the mounted token's copy of one.
+    ReadKey {
+        path: PathBuf,
+    },
+    /// Append `entry` to the audit trail as `actor`.
+    Audit {
+        actor: String,
+        entry: AuditEntry,
+    },
+    /// Re-read the config after the daemon reloaded it.
+    Reload,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Request {
+    id: u64,
+    op: Op,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Reply {
+    id: u64,
+    result: Result<Value, HelperError>,
+}
+
+/// Failure the helper reports back, mapped onto [`LockchainError`].
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum HelperError {
+    Config(String),
+    Provider(String),
+    /// A file could not be read or written.
+    Io {
+        message: String,
+        missing: bool,
+    },
+    /// The request named something outside the managed datasets.
+    Refused(String),
+}
+
+impl From<LockchainError> for HelperError {
+    fn from(err: LockchainError) -> Self {
+        match err {
+            LockchainError::InvalidConfig(message) => Self::Config(message),
+            LockchainError::Provider(message) => Self::Provider(message),
+            LockchainError::Io(err) => Self::Io {
+                message: err.to_string(),
+                missing: err.kind() == std::io::ErrorKind::NotFound,
+            },
+            other => Self::Provider(other.to_string()),
+        }
+    }
+}
+
+impl From<HelperError> for LockchainError {
+    fn from(err: HelperError) -> Self {
+        match err {
+            HelperError::Config(message) => Self::InvalidConfig(message),
+            HelperError::Provider(message) => Self::Provider(message),
+            HelperError::Io { message, missing } => {
+                let kind = match missing {
+                    true => std::io::ErrorKind::NotFound,
+                    false => std::io::ErrorKind::Other,
+                };
+                Self::Io(std::io::Error::new(kind, message))
+            }
+            HelperError::Refused(message) => Self::PolicyViolation(message),
+        }
+    }
+}
+
+/// When the daemon runs as root and `[daemon] user` is set, start the helper
+/// and switch to that user; `None` when the daemon keeps running ZFS itself.
+///
+/// Runs before the runtime starts, while the process has a single thread,
+/// so the switch covers every thread that follows.
+pub fn start() -> Result<Option<HelperZfs>> {
+    logging::init("info");
+    // SAFETY: geteuid cannot fail.
+    if unsafe { libc::geteuid() } != 0 {
+        return Ok(None);
+    }
+    let config_path = crate::config_path();
+    let config = LockchainConfig::load(&config_path)
+        .with_context(|| format!("load config {config_path}"))?;
+    let Some(user) = config.daemon.user else {
+        return Ok(None);
+    };
+    let (uid, gid) =
+        worker::user_ids(&user).with_context(|| format!("look up daemon.user {user}"))?;
+
+    let (ours, theirs) = UnixStream::pair().context("create ZFS helper socketpair")?;
+    let helper = spawn(&theirs)?;
+    drop(theirs);
+    hand_over_runtime_dir(uid, gid)?;
+    drop_privileges(uid, gid).with_context(|| format!("switch to daemon.user {user}"))?;
+    info!("running as {user}; ZFS helper {helper} runs zfs and zpool");
+    HelperZfs::connect(ours).map(Some)
+}
+
+/// Start the helper with `channel` on [`CHANNEL_FD`].
+fn spawn(channel: &UnixStream) -> Result<u32> {
+    let channel = channel.as_raw_fd();
+    let mut command = Command::new("/proc/self/exe");
+    command
+        .arg0("lockchain-daemon")
+        .arg(ARG)
+        .stdin(Stdio::null());
+    // SAFETY: the closure only makes async-signal-safe system calls.
+    unsafe {
+        command.pre_exec(move || {
+            // Park the descriptor above the target first, so dup2 also
+            // clears close-on-exec when it already sits there.
+            let copy = libc::fcntl(channel, libc::F_DUPFD, 10);
+            if copy < 0 || libc::dup2(copy, CHANNEL_FD) < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            libc::close(copy);
+            Ok(())
+        });
+    }
+    let child = command.spawn().context("start ZFS helper")?;
+    Ok(child.id())
+}
+
+/// Give the directory of the control socket to the daemon user, who binds
+/// the socket in it later.
+fn hand_over_runtime_dir(uid: u32, gid: u32) -> Result<()> {
+    let path = lockchain_core::control::socket_path();
+    let Some(parent) = path.parent() else {
+        return Ok(());
+    };
+    std::fs::create_dir_all(parent)
+        .with_context(|| format!("create control socket directory {}", parent.display()))?;
+    std::os::unix::fs::chown(parent, Some(uid), Some(gid))
+        .with_context(|| format!("hand {} to the daemon user", parent.display()))
+}
+
+/// Clear the supplementary groups and switch to `uid` and `gid`, which also
+/// drops every capability.
+fn drop_privileges(uid: u32, gid: u32) -> std::io::Result<()> {
+    // SAFETY: plain system calls; the process is still single-threaded.
+    unsafe {
+        if libc::setgroups(0, std::ptr::null()) < 0
+            || libc::setresgid(gid, gid, gid) < 0
+            || libc::setresuid(uid, uid, uid) < 0
+        {
+            return Err(std::io::Error::last_os_error());
+        }
+        // Getting root back must fail now.
+        if libc::setuid(0) == 0 {
+            return Err(std::io::Error::other("root could be regained"));
+        }
+    }
+    Ok(())
+}
+
+/// Daemon side of the channel: a [`ZfsProvider`] that forwards each call to
+/// the helper and waits for its reply.
+#[derive(Clone)]
+pub struct HelperZfs(Arc<Channel>);
+
+struct Channel {
+    writer: Mutex<UnixStream>,
+    pending: Mutex<HashMap<u64, mpsc::Sender<Result<Value, HelperError>>>>,
+    next_id: AtomicU64,
+    closed: AtomicBool,
+    gone: Notify,
+}
+
+impl HelperZfs {
+    /// Read replies from `stream` on a thread of their own.
+    fn connect(stream: UnixStream) -> Result<Self> {
+        let reader = stream.try_clone().context("clone ZFS helper channel")?;
+        let channel = Arc::new(Channel {
+            writer: Mutex::new(stream),
+            pending: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+            gone: Notify::new(),
+        });
+        let replies = channel.clone();
+        std::thread::Builder::new()
+            .name("zfs-helper".into())
+            .spawn(move || replies.read_replies(reader))
+            .context("start ZFS helper reader")?;
+        Ok(Self(channel))
+    }
+
+    /// Resolves once the helper has gone away.
+    pub async fn closed(&self) {
+        if !self.0.closed.load(Ordering::SeqCst) {
+            self.0.gone.notified().await;
+        }
+    }
+
+    /// Have the helper re-read the config, so it serves what the reloaded
+    /// config manages.
+    pub fn reload(&self) -> LockchainResult<()> {
+        self.call(Op::Reload)
+    }
+
+    /// Have the helper read the key at `path`, a configured key file or the
+    /// mounted token's copy of one.
+    pub fn read_key(&self, path: &Path) -> LockchainResult<SecretBuffer> {
+        let key: Zeroizing<String> = self.call(Op::ReadKey {
+            path: path.to_path_buf(),
+        })?;
+        let mut buffer = SecretBuffer::zeroed(key.len() / 2);
+        hex::decode_to_slice(key.as_bytes(), &mut buffer[..])
+            .map_err(|err| LockchainError::Provider(format!("bad key from ZFS helper: {err}")))?;
+        Ok(buffer)
+    }
+
+    /// [`Self::read_key`] for a [`LockchainService`](lockchain_core::LockchainService).
+    pub fn key_reader(&self) -> KeyReader {
+        let helper = self.clone();
+        Arc::new(move |path| helper.read_key(path))
+    }
+
+    /// An audit writer for `cfg` that has the helper append each record.
+    pub fn audit_log(&self, cfg: &AuditCfg) -> AuditLog {
+        let helper = self.clone();
+        AuditLog::new(cfg).with_sink(Arc::new(move |actor, entry| {
+            helper.call(Op::Audit {
+                actor: actor.to_string(),
+                entry,
+            })
+        }))
+    }
+
+    fn call<T: DeserializeOwned>(&self, op: Op) -> LockchainResult<T> {
+        let channel = &self.0;
+        let id = channel.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = mpsc::channel();
+        channel.pending.lock().unwrap().insert(id, tx);
+        if channel.closed.load(Ordering::SeqCst) {
+            channel.pending.lock().unwrap().remove(&id);
+            return Err(helper_gone());
+        }
+        let mut line = Zeroizing::new(Vec::with_capacity(1024));
+        serde_json::to_writer(&mut *line, &Request { id, op })
+            .map_err(|err| LockchainError::Provider(format!("encode ZFS helper request: {err}")))?;
+        line.push(b'\n');
+        if let Err(err) = channel.writer.lock().unwrap().write_all(&line) {
+            channel.pending.lock().unwrap().remove(&id);
+            return Err(LockchainError::Provider(format!(
+                "ZFS helper unreachable: {err}"
+            )));
+        }
+        let value = rx.recv().map_err(|_| helper_gone())??;
+        serde_json::from_value(value)
+            .map_err(|err| LockchainError::Provider(format!("bad ZFS helper reply: {err}")))
+    }
+}
+
+impl Channel {
+    /// Hand each reply to its caller until the helper hangs up, then fail
+    /// every call still waiting.
+    fn read_replies(&self, stream: UnixStream) {
+        for line in BufReader::new(stream).lines() {
+            let line = line.map(Zeroizing::new);
+            let reply = match line.map(|line| serde_json::from_str::<Reply>(&line)) {
+                Ok(Ok(reply)) => reply,
+                Ok(Err(err)) => {
+                    warn!("ZFS helper sent a bad reply: {err}");
+                    break;
+                }
+                Err(err) => {
+                    warn!("ZFS helper channel failed: {err}");
+                    break;
+                }
+            };
+            if let Some(tx) = self.pending.lock().unwrap().remove(&reply.id) {
+                let _ = tx.send(reply.result);
+            }
+        }
+        self.closed.store(true, Ordering::SeqCst);
+        self.pending.lock().unwrap().clear();
+        self.gone.notify_one();
+    }
+}
+
+fn helper_gone() -> LockchainError {
+    LockchainError::Provider("ZFS helper exited".into())
+}
+
+impl ZfsProvider for HelperZfs {
+    fn encryption_root(&self, dataset: &str) -> LockchainResult<String> {
+        self.call(Op::EncryptionRoot {
+            dataset: dataset.to_string(),
+        })
+    }
+
+    fn locked_descendants(&self, root: &str) -> LockchainResult<Vec<String>> {
+        self.call(Op::LockedDescendants {
+            root: root.to_string(),
+        })
+    }
+
+    fn load_key_tree(&self, root: &str, key: &[u8]) -> LockchainResult<Vec<String>> {
+        self.call(Op::LoadKeyTree {
+            root: root.to_string(),
+            key: Zeroizing::new(hex::encode(key)),
+        })
+    }
+
+    fn describe_datasets(&self, datasets: &[String]) -> LockchainResult<KeyStatusSnapshot> {
+        self.call(Op::DescribeDatasets {
+            datasets: datasets.to_vec(),
+        })
+    }
+
+    fn key_tree_status(&self, root: &str) -> LockchainResult<KeyStatusSnapshot> {
+        self.call(Op::KeyTreeStatus {
+            root: root.to_string(),
+        })
+    }
+
+    fn unload_key_tree(&self, root: &str, unmount: bool) -> LockchainResult<Vec<String>> {
+        self.call(Op::UnloadKeyTree {
+            root: root.to_string(),
+            unmount,
+        })
+    }
+
+    fn mount_dataset(&self, dataset: &str) -> LockchainResult<()> {
+        self.call(Op::MountDataset {
+            dataset: dataset.to_string(),
+        })
+    }
+}
+
+/// ZFS access of the running daemon: direct, or through the helper.
+#[derive(Clone)]
+pub enum Zfs {
+    Direct(SystemZfsProvider),
+    Helper(HelperZfs),
+}
+
+impl Zfs {
+    fn inner(&self) -> &dyn ZfsProvider {
+        match self {
+            Zfs::Direct(zfs) => zfs,
+            Zfs::Helper(helper) => helper,
+        }
+    }
+}
+
+impl ZfsProvider for Zfs {
+    fn encryption_root(&self, dataset: &str) -> LockchainResult<String> {
+        self.inner().encryption_root(dataset)
+    }
+
+    fn locked_descendants(&self, root: &str) -> LockchainResult<Vec<String>> {
+        self.inner().locked_descendants(root)
+    }
+
+    fn load_key_tree(&self, root: &str, key: &[u8]) -> LockchainResult<Vec<String>> {
+        self.inner().load_key_tree(root, key)
+    }
+
+    fn describe_datasets(&self, datasets: &[String]) -> LockchainResult<KeyStatusSnapshot> {
+        self.inner().describe_datasets(datasets)
+    }
+
+    fn key_tree_status(&self, root: &str) -> LockchainResult<KeyStatusSnapshot> {
+        self.inner().key_tree_status(root)
+    }
+
+    fn encryption_roots(&self) -> LockchainResult<Vec<String>> {
+        self.inner().encryption_roots()
+    }
+
+    fn pool_health(&self, pool: &str) -> LockchainResult<String> {
+        self.inner().pool_health(pool)
+    }
+
+    fn unload_key_tree(&self, root: &str, unmount: bool) -> LockchainResult<Vec<String>> {
+        self.inner().unload_key_tree(root, unmount)
+    }
+
+    fn mount_dataset(&self, dataset: &str) -> LockchainResult<()> {
+        self.inner().mount_dataset(dataset)
+    }
+
+    fn check_key(&self, root: &str, key: &[u8]) -> LockchainResult<()> {
+        self.inner().check_key(root, key)
+    }
+
+    fn change_key(&self, root: &str, key: &[u8]) -> LockchainResult<()> {
+        self.inner().change_key(root, key)
+    }
+}
+
+/// What the helper may touch: the managed datasets and their encryption
+/// roots, as of the last config it read, and the configured key files.
+struct Scope {
+    config: LockchainConfig,
+    zfs: SystemZfsProvider,
+    roots: BTreeSet<String>,
+    /// Uid and gid of `[daemon] user`.
+    daemon: Option<(u32, u32)>,
+}
+
+impl Scope {
+    fn load() -> LockchainResult<Self> {
+        let config = LockchainConfig::load(crate::config_path())?;
+        let zfs = SystemZfsProvider::from_config(&config)?;
+        let daemon = match config.daemon.user.as_deref().map(worker::user_ids) {
+            Some(Ok(ids)) => Some(ids),
+            Some(Err(err)) => {
+                warn!("ZFS helper: {err:#}; the audit trail stays readable by root only");
+                None
+            }
+            None => None,
+        };
+        let mut scope = Self {
+            config,
+            zfs,
+            roots: BTreeSet::new(),
+            daemon,
+        };
+        scope.refresh_roots();
+        Ok(scope)
+    }
+
+    /// Look the encryption roots of the managed datasets up again; those
+    /// that cannot be resolved right now are left out.
+    fn refresh_roots(&mut self) {
+        self.roots = self
+            .config
+            .policy
+            .datasets
+            .iter()
+            .filter_map(|dataset| self.zfs.encryption_root(dataset).ok())
+            .collect();
+    }
+
+    /// Whether `name` is a managed dataset or lies under one of their
+    /// encryption roots.
+    fn covers(&self, name: &str) -> bool {
+        self.config.policy.datasets.iter().any(|ds| ds == name)
+            || self.roots.iter().any(|root| {
+                name == root
+                    || name
+                        .strip_prefix(root.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+    }
+
+    /// Whether `path` is a configured key file or the mounted token's copy
+    /// of one.
+    fn holds_key(&self, path: &Path) -> bool {
+        let mounted = token::mounted_token(&self.config.usb).ok().flatten();
+        self.config.key_sources().iter().any(|source| {
+            source.path == path
+                || mounted
+                    .as_ref()
+                    .and_then(|mounted| scrub::token_copy(mounted, source))
+                    .is_some_and(|copy| copy == path)
+        })
+    }
+
+    /// Read the key at `path` without following a final symlink, refusing
+    /// files the daemon user owns: the staged key directory may be the
+    /// daemon's to write, and it must not point the helper elsewhere.
+    fn read_key(&self, path: &Path) -> LockchainResult<SecretBuffer> {
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NOFOLLOW)
+            .open(path)?;
+        let meta = file.metadata()?;
+        if !meta.is_file() || self.daemon.is_some_and(|(uid, _)| meta.uid() == uid) {
+            return Err(LockchainError::PolicyViolation(format!(
+                "{} is not a root-owned key file",
+                path.display()
+            )));
+        }
+        let mut contents = Zeroizing::new(Vec::with_capacity(64));
+        file.take(4096).read_to_end(&mut contents)?;
+        // The decoder's detail may quote a byte of the file.
+        decode_key_bytes(path, &contents)
+            .map(|(key, _)| key)
+            .map_err(|_| {
+                LockchainError::InvalidConfig(format!("{} holds no valid key", path.display()))
+            })
+    }
+}
+
+/// Which check a request's names must pass.
+enum Needs<'a> {
+    Nothing,
+    Covered(Vec<&'a str>),
+    Root(&'a str),
+    KeyFile(&'a Path),
+}
+
+impl Op {
+    fn needs(&self) -> Needs<'_> {
+        match self {
+            Op::EncryptionRoot { dataset } | Op::MountDataset { dataset } => {
+                Needs::Covered(vec![dataset])
+            }
+            Op::DescribeDatasets { datasets } => {
+                Needs::Covered(datasets.iter().map(String::as_str).collect())
+            }
+            Op::LockedDescendants { root }
+            | Op::KeyTreeStatus { root }
+            | Op::LoadKeyTree { root, .. }
+            | Op::UnloadKeyTree { root, .. } => Needs::Root(root),
+            Op::ReadKey { path } => Needs::KeyFile(path),
+            Op::Audit { .. } | Op::Reload => Needs::Nothing,
+        }
+    }
+}
+
+/// Names the helper passes to `zfs` must be plain dataset names, never
+/// options, snapshots, or bookmarks.
+fn plain_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('-') && !name.contains(['@', '#'])
+}
+
+/// Refuse `op` unless every name it carries is in scope, refreshing the
+/// encryption roots once on a miss; returns the provider to run it with.
+fn admit(scope: &RwLock<Scope>, op: &Op) -> Result<SystemZfsProvider, HelperError> {
+    let allowed = |scope: &Scope| match op.needs() {
+        Needs::Nothing => true,
+        Needs::Covered(names) => names
+            .iter()
+            .all(|name| plain_name(name) && scope.covers(name)),
+        Needs::Root(root) => plain_name(root) && scope.roots.contains(root),
+        Needs::KeyFile(path) => scope.holds_key(path),
+    };
+    {
+        let scope = scope.read().unwrap();
+        if allowed(&scope) {
+            return Ok(scope.zfs.clone());
+        }
+    }
+    let mut scope = scope.write().unwrap();
+    scope.refresh_roots();
+    if allowed(&scope) {
+        return Ok(scope.zfs.clone());
+    }
+    Err(HelperError::Refused(
+        "the ZFS helper only serves managed datasets, their encryption roots, and their key files"
+            .into(),
+    ))
+}
+
+/// Carry out `op`, which [`admit`] let through.
+fn answer(scope: &RwLock<Scope>, zfs: SystemZfsProvider, op: Op) -> LockchainResult<Value> {
+    fn json<T: Serialize>(value: T) -> LockchainResult<Value> {
+        serde_json::to_value(value)
+            .map_err(|err| LockchainError::Provider(format!("encode ZFS helper reply: {err}")))
+    }
+    match op {
+        Op::EncryptionRoot { dataset } => json(zfs.encryption_root(&dataset)?),
+        Op::LockedDescendants { root } => json(zfs.locked_descendants(&root)?),
+        Op::KeyTreeStatus { root } => json(zfs.key_tree_status(&root)?),
+        Op::LoadKeyTree { root, key } => {
+            let key = Zeroizing::new(hex::decode(key.as_str()).map_err(|err| {
+                LockchainError::Provider(format!("key for {root} is not hex: {err}"))
+            })?);
+            json(zfs.load_key_tree(&root, &key)?)
+        }
+        Op::UnloadKeyTree { root, unmount } => json(zfs.unload_key_tree(&root, unmount)?),
+        Op::MountDataset { dataset } => json(zfs.mount_dataset(&dataset)?),
+        Op::DescribeDatasets { datasets } => json(zfs.describe_datasets(&datasets)?),
+        Op::ReadKey { path } => {
+            let key = scope.read().unwrap().read_key(&path)?;
+            json(Zeroizing::new(hex::encode(&key[..])))
+        }
+        Op::Audit { actor, entry } => {
+            let scope = scope.read().unwrap();
+            let mut audit = AuditLog::new(&scope.config.audit).with_actor(actor);
+            if let Some((_, gid)) = scope.daemon {
+                audit = audit.with_group(gid);
+            }
+            json(audit.record(entry)?)
+        }
+        Op::Reload => {
+            let fresh = Scope::load()?;
+            info!(
+                "ZFS helper reloaded; serving {} datasets",
+                fresh.config.policy.datasets.len()
+            );
+            *scope.write().unwrap() = fresh;
+            json(())
+        }
+    }
+}
+
+/// Entry point of the helper process.
+pub fn run() -> Result<()> {
+    logging::init("info");
+    // SAFETY: geteuid cannot fail.
+    if unsafe { libc::geteuid() } != 0 {
+        bail!("the ZFS helper must run as root");
+    }
+    // SAFETY: ignoring signals is always sound.
+    unsafe {
+        libc::signal(libc::SIGTERM, libc::SIG_IGN);
+        libc::signal(libc::SIGINT, libc::SIG_IGN);
+    }
+    // SAFETY: the daemon hands this descriptor over and nothing else here
+    // owns it.
+    let channel = unsafe { UnixStream::from_raw_fd(CHANNEL_FD) };
+    let scope = Scope::load().map_err(|err| anyhow!("ZFS helper: {err}"))?;
+    serve(channel, scope)
+}
+
+/// Answer the daemon's requests on `channel` until it hangs up.
+fn serve(channel: UnixStream, scope: Scope) -> Result<()> {
+    let scope = Arc::new(RwLock::new(scope));
+    let writer = Arc::new(Mutex::new(channel.try_clone()?));
+    let (tx, rx) = mpsc::sync_channel::<Request>(THREADS);
+    let rx = Arc::new(Mutex::new(rx));
+    for _ in 0..THREADS {
+        let (scope, writer, rx) = (scope.clone(), writer.clone(), rx.clone());
+        std::thread::spawn(move || loop {
+            let Ok(Request { id, op }) = rx.lock().unwrap().recv() else {
+                return;
+            };
+            let result = admit(&scope, &op)
+                .and_then(|zfs| answer(&scope, zfs, op).map_err(HelperError::from));
+            if let Err(HelperError::Refused(message)) = &result {
+                warn!(event = "zfs_helper_refused"; "{message}; request {id} refused");
+            }
+            let mut reply = Reply { id, result };
+            let mut line = Zeroizing::new(serde_json::to_vec(&reply).unwrap_or_default());
+            // A key read for the daemon travels as a string.
+            if let Ok(Value::String(text)) = &mut reply.result {
+                text.zeroize();
+            }
+            line.push(b'\n');
+            if writer.lock().unwrap().write_all(&line).is_err() {
+                return;
+            }
+        });
+    }
+
+    let mut reader = BufReader::new(channel);
+    loop {
+        let mut line = Zeroizing::new(Vec::with_capacity(1024));
+        (&mut reader)
+            .take(MAX_REQUEST + 1)
+            .read_until(b'\n', &mut line)?;
+        if line.len() as u64 > MAX_REQUEST {
+            bail!("daemon sent a request longer than {MAX_REQUEST} bytes");
+        }
+        if line.last() != Some(&b'\n') {
+            // The daemon is gone.
+            return Ok(());
+        }
+        let request = serde_json::from_slice(&line).context("read daemon request")?;
+        tx.send(request)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lockchain_core::audit::{self, AuditEvent};
+    use lockchain_core::keyfile::write_raw_key_file;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// Uid and gid of `nobody` on most distributions.
+    const NOBODY: u32 = 65534;
+
+    /// A scope over `tank/secure/home`, whose encryption root a stub `zfs`
+    /// reports as `tank/secure`, with its key and audit trail in `dir`.
+    fn scope(dir: &Path) -> Scope {
+        let stub = |name: &str, body: &str| {
+            let path = dir.join(name);
+            fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+        let zfs = stub("zfs", "echo tank/secure");
+        let zpool = stub("zpool", "printf 'tank\\tONLINE\\n'");
+        let config_path = dir.join("lockchain-zfs.toml");
+        fs::write(
+            &config_path,
+            format!(
+                "[policy]\ndatasets = [\"tank/secure/home\"]\nzfs_path = \"{}\"\nzpool_path = \"{}\"\n\
+                 [usb]\nkey_hex_path = \"{}\"\n[audit]\npath = \"{}\"\n",
+                zfs.display(),
+                zpool.display(),
+                dir.join("key.hex").display(),
+                dir.join("audit.jsonl").display()
+            ),
+        )
+        .unwrap();
+        let config = LockchainConfig::load(&config_path).unwrap();
+        let zfs = SystemZfsProvider::from_config(&config).unwrap();
+        let mut scope = Scope {
+            config,
+            zfs,
+            roots: BTreeSet::new(),
+            daemon: None,
+        };
+        scope.refresh_roots();
+        scope
+    }
+
+    fn admitted(scope: &RwLock<Scope>, op: Op) -> bool {
+        match admit(scope, &op) {
+            Ok(_) => true,
+            Err(HelperError::Refused(_)) => false,
+            Err(_) => panic!("admit failed for another reason"),
+        }
+    }
+
+    #[test]
+    fn names_that_zfs_could_read_as_options_or_snapshots_are_not_plain() {
+        assert!(plain_name("tank/secure"));
+        assert!(!plain_name(""));
+        assert!(!plain_name("-o"));
+        assert!(!plain_name("tank/secure@snap"));
+        assert!(!plain_name("tank/secure#bookmark"));
+    }
+
+    #[test]
+    fn only_managed_datasets_and_their_roots_are_served() {
+        let dir = tempfile::tempdir().unwrap();
+        let scope = RwLock::new(scope(dir.path()));
+        let dataset = |name: &str| Op::EncryptionRoot {
+            dataset: name.into(),
+        };
+        let root = |name: &str| Op::LoadKeyTree {
+            root: name.into(),
+            key: Zeroizing::new("00".repeat(32)),
+        };
+
+        assert!(admitted(&scope, Op::Reload));
+        assert!(admitted(&scope, dataset("tank/secure/home")));
+        assert!(admitted(&scope, dataset("tank/secure/home/child")));
+        assert!(admitted(&scope, root("tank/secure")));
+        assert!(admitted(
+            &scope,
+            Op::DescribeDatasets {
+                datasets: vec!["tank/secure".into(), "tank/secure/home".into()],
+            }
+        ));
+
+        assert!(!admitted(&scope, dataset("tank/securex")));
+        assert!(!admitted(&scope, dataset("tank")));
+        assert!(!admitted(&scope, dataset("tank/secure/home@snap")));
+        assert!(!admitted(&scope, root("tank")));
+        assert!(!admitted(&scope, root("tank/secure/home")));
+        assert!(!admitted(
+            &scope,
+            Op::MountDataset {
+                dataset: "-a".into(),
+            }
+        ));
+        assert!(!admitted(
+            &scope,
+            Op::DescribeDatasets {
+                datasets: vec!["tank/secure/home".into(), "tank/other".into()],
+            }
+        ));
+    }
+
+    /// A thread that switched to `nobody` can open neither the key nor the
+    /// audit trail, but reads the one and appends to the other through the
+    /// helper, and can still read the trail the helper wrote.
+    #[test]
+    fn keys_and_audit_records_go_through_the_helper_after_the_drop() {
+        // SAFETY: geteuid cannot fail.
+        if unsafe { libc::geteuid() } != 0 {
+            eprintln!("skipped: dropping privileges needs root");
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o755)).unwrap();
+        let mut scope = scope(dir.path());
+        scope.daemon = Some((NOBODY, NOBODY));
+        let key_path = scope.config.key_hex_path();
+        let audit_cfg = scope.config.audit.clone();
+        let audit_path = audit_cfg.log_path();
+        write_raw_key_file(&key_path, &[7u8; 32]).unwrap();
+        fs::write(dir.path().join("other.key"), [8u8; 32]).unwrap();
+
+        let (ours, theirs) = UnixStream::pair().unwrap();
+        std::thread::spawn(move || serve(theirs, scope));
+        let helper = HelperZfs::connect(ours).unwrap();
+
+        std::thread::spawn(move || {
+            // SAFETY: plain system calls. Made directly they change only this
+            // thread's credentials, where libc would switch the helper's too.
+            unsafe {
+                assert_eq!(
+                    libc::syscall(libc::SYS_setgroups, 0, std::ptr::null::<libc::gid_t>()),
+                    0
+                );
+                assert_eq!(
+                    libc::syscall(libc::SYS_setresgid, NOBODY, NOBODY, NOBODY),
+                    0
+                );
+                assert_eq!(
+                    libc::syscall(libc::SYS_setresuid, NOBODY, NOBODY, NOBODY),
+                    0
+                );
+            }
+            let denied = fs::read(&key_path).unwrap_err();
+            assert_eq!(denied.kind(), std::io::ErrorKind::PermissionDenied);
+
+            assert_eq!(&helper.read_key(&key_path).unwrap()[..], &[7u8; 32]);
+            let refused = helper.read_key(&key_path.with_file_name("other.key"));
+            assert!(matches!(refused, Err(LockchainError::PolicyViolation(_))));
+
+            let audit = helper.audit_log(&audit_cfg);
+            let written = audit
+                .record(AuditEntry::success(AuditEvent::Unlock, "tank/secure/home"))
+                .unwrap()
+                .expect("auditing is on");
+            let records = audit::read_records(&audit.path()).unwrap();
+            assert_eq!(records, vec![written]);
+        })
+        .join()
+        .unwrap();
+
+        let meta = fs::metadata(audit_path).unwrap();
+        assert_eq!((meta.uid(), meta.gid()), (0, NOBODY));
+        assert_eq!(meta.mode() & 0o777, 0o640);
+    }
+}
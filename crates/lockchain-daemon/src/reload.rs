@@ -0,0 +1,97 @@
+//! Config hot reload on SIGHUP and whenever the config file or one of its
+//! drop-in fragments changes on disk.
+
+use anyhow::Result;
+use lockchain_core::control::DaemonEventKind;
+use lockchain_core::{ConfigChange, ConfigUpdate, LockchainConfig, LockchainResult};
+use log::{info, warn};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::mpsc;
+
+use crate::{control, systemd, DaemonState};
+
+/// Re-read the config file and swap it in; shared by SIGHUP and the control
+/// socket's `reload`.
+pub fn apply(state: &DaemonState) -> LockchainResult<Vec<ConfigChange>> {
+    install(state, LockchainConfig::load(&state.config_path))
+}
+
+/// Swap in `loaded` and re-apply the control socket's access, telling
+/// systemd while the reload runs.
+fn install(
+    state: &DaemonState,
+    loaded: LockchainResult<LockchainConfig>,
+) -> LockchainResult<Vec<ConfigChange>> {
+    systemd::reloading();
+    let changes = state.install(loaded);
+    let message = match &changes {
+        Ok(changes) => format!("reloaded ({} settings changed)", changes.len()),
+        Err(err) => format!("reload failed, previous config kept: {err}"),
+    };
+    state.journal.record(DaemonEventKind::Reload, None, message);
+    if changes.is_ok() {
+        if let Err(err) = control::apply_socket_access(
+            &lockchain_core::control::socket_path(),
+            &state.config().control,
+        ) {
+            warn!("control socket access not updated: {err:#}");
+        }
+    }
+    systemd::ready();
+    changes
+}
+
+/// Reload whenever SIGHUP arrives or the config changes on disk.
+///
+/// A reload that fails keeps the running config; the error is logged and
+/// reported by the health endpoint until a later reload succeeds.
+pub async fn watch(state: Arc<DaemonState>) -> Result<()> {
+    let mut hangup = signal(SignalKind::hangup())?;
+    let mut updates = file_updates(&state.config_path);
+
+    loop {
+        let (trigger, loaded) = tokio::select! {
+            _ = hangup.recv() => ("SIGHUP", None),
+            Some(update) = updates.recv() => {
+                ("config file change", Some(update.map(|update| update.config)))
+            }
+        };
+        info!("reloading {} after {trigger}", state.config_path.display());
+        let state = state.clone();
+        let outcome = tokio::task::spawn_blocking(move || match loaded {
+            Some(loaded) => install(&state, loaded),
+            None => apply(&state),
+        })
+        .await?;
+        if let Err(err) = outcome {
+            warn!("config reload after {trigger} failed; keeping the running config: {err}");
+        }
+    }
+}
+
+/// Updates from [`LockchainConfig::watch`], forwarded onto the runtime; the
+/// channel closes at once when the config cannot be watched.
+fn file_updates(path: &Path) -> mpsc::UnboundedReceiver<LockchainResult<ConfigUpdate>> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let watched = LockchainConfig::watch(path).and_then(|updates| {
+        std::thread::Builder::new()
+            .name("lockchain-reload".into())
+            .spawn(move || {
+                for update in updates {
+                    if tx.send(update).is_err() {
+                        break;
+                    }
+                }
+            })?;
+        Ok(())
+    });
+    if let Err(err) = watched {
+        warn!(
+            "cannot watch {} for changes ({err}); reload with SIGHUP",
+            path.display()
+        );
+    }
+    rx
+}
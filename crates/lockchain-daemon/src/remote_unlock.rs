@@ -0,0 +1,316 @@
+//! Network unlock for early boot, in the spirit of dropbear + cryptsetup.
+//!
+//! `lockchain-daemon --remote-unlock` listens on `[remote_unlock] listen`
+//! until `[remote_unlock] dataset` is unlocked, then exits. The dracut module
+//! starts it in the initramfs next to the USB key loader, so whichever gets
+//! the key first wins; it also stops when the dataset is unlocked some other
+//! way, such as from the console prompt.
+//!
+//! Only clients presenting a certificate signed by `client_ca` complete the
+//! TLS handshake. A connection carries one line, `passphrase <text>` or
+//! `key <hex>`, and gets one line back starting with `ok` or `error`; why an
+//! unlock failed is only logged, never sent to the client. The
+//! material goes through [`LockchainService::unlock_supplied`], so it is held
+//! to the same checksum and fallback policy as the token's key. Handshakes
+//! and requests are read concurrently, up to a connection limit, while the
+//! unlock attempts themselves run one at a time, each failed one holding the
+//! next back for a few seconds.
+
+use anyhow::{bail, Context, Result};
+use lockchain_core::config::{LockchainConfig, RemoteUnlockCfg};
+use lockchain_core::service::{LockchainService, SuppliedKey};
+use lockchain_core::{logging, SecretBuffer};
+use lockchain_zfs::SystemZfsProvider;
+use log::{info, warn};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::select;
+use tokio::sync::{Mutex, Notify, Semaphore};
+use tokio::task::spawn_blocking;
+use tokio::time::{interval, sleep, timeout, Duration};
+use tokio_rustls::TlsAcceptor;
+
+type Service = LockchainService<SystemZfsProvider>;
+
+/// First argument that starts the binary as the remote unlock listener.
+pub const ARG: &str = "--remote-unlock";
+
+/// Time a client gets to finish the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time a client gets to send its request once connected.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// Pause after a failed unlock attempt before the next one runs.
+const FAILURE_DELAY: Duration = Duration::from_secs(3);
+/// Connections attended at once; further ones are closed straight away.
+const MAX_CONNECTIONS: usize = 16;
+/// How often to check whether the dataset was unlocked some other way.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Longest request line accepted.
+const MAX_REQUEST: usize = 4096;
+
+/// Entry point of the listener process.
+pub fn run() -> Result<()> {
+    logging::init("info");
+    let config_path = crate::config_path();
+    let config = Arc::new(
+        LockchainConfig::load(&config_path)
+            .with_context(|| format!("load config {config_path}"))?,
+    );
+    let Some(cfg) = config.remote_unlock.clone() else {
+        bail!("{config_path} has no [remote_unlock] table");
+    };
+    let acceptor = acceptor(&cfg)?;
+    let provider = SystemZfsProvider::from_config(&config)?;
+    let service = Arc::new(LockchainService::new(config, provider));
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(serve(cfg, service, acceptor))
+}
+
+/// TLS setup that presents `cert` and insists on a client certificate
+/// signed by `client_ca`.
+fn acceptor(cfg: &RemoteUnlockCfg) -> Result<TlsAcceptor> {
+    let certs = CertificateDer::pem_file_iter(&cfg.cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("read remote_unlock.cert {}", cfg.cert))?;
+    if certs.is_empty() {
+        bail!("remote_unlock.cert {} holds no certificate", cfg.cert);
+    }
+    let key = PrivateKeyDer::from_pem_file(&cfg.key)
+        .with_context(|| format!("read remote_unlock.key {}", cfg.key))?;
+    let mut roots = RootCertStore::empty();
+    for ca in CertificateDer::pem_file_iter(&cfg.client_ca)
+        .with_context(|| format!("read remote_unlock.client_ca {}", cfg.client_ca))?
+    {
+        roots
+            .add(ca.with_context(|| format!("read remote_unlock.client_ca {}", cfg.client_ca))?)
+            .with_context(|| format!("use remote_unlock.client_ca {}", cfg.client_ca))?;
+    }
+    if roots.is_empty() {
+        bail!(
+            "remote_unlock.client_ca {} holds no certificate",
+            cfg.client_ca
+        );
+    }
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+        .build()
+        .context("build client certificate verifier")?;
+    let config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(certs, key)
+        .context("remote_unlock.key does not match remote_unlock.cert")?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// What every connection shares.
+struct Listener {
+    acceptor: TlsAcceptor,
+    service: Arc<Service>,
+    dataset: String,
+    /// Held for the length of an unlock attempt and the pause after a
+    /// failed one.
+    attempts: Mutex<()>,
+    /// Woken when a client unlocked the dataset.
+    done: Notify,
+}
+
+/// Take requests until the dataset is unlocked.
+async fn serve(cfg: RemoteUnlockCfg, service: Arc<Service>, acceptor: TlsAcceptor) -> Result<()> {
+    let dataset = cfg.dataset;
+    if unlocked(&service, &dataset).await {
+        info!("{dataset} is already unlocked; not listening");
+        return Ok(());
+    }
+    let listener = TcpListener::bind(&cfg.listen)
+        .await
+        .with_context(|| format!("bind remote_unlock.listen {}", cfg.listen))?;
+    info!("waiting for a remote unlock of {dataset} on {}", cfg.listen);
+
+    let shared = Arc::new(Listener {
+        acceptor,
+        service: service.clone(),
+        dataset: dataset.clone(),
+        attempts: Mutex::new(()),
+        done: Notify::new(),
+    });
+    let slots = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+    let mut poll = interval(POLL_INTERVAL);
+    loop {
+        select! {
+            accepted = listener.accept() => {
+                let (stream, peer) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        warn!("remote unlock accept failed: {err}");
+                        continue;
+                    }
+                };
+                let Ok(slot) = slots.clone().try_acquire_owned() else {
+                    warn!("remote unlock: {MAX_CONNECTIONS} connections already open; dropped {peer}");
+                    continue;
+                };
+                let shared = shared.clone();
+                tokio::spawn(async move {
+                    let _slot = slot;
+                    match attend(&shared, stream, peer).await {
+                        Ok(true) => shared.done.notify_one(),
+                        Ok(false) => {}
+                        Err(err) => warn!("remote unlock connection from {peer} failed: {err:#}"),
+                    }
+                });
+            }
+            _ = shared.done.notified() => return Ok(()),
+            _ = poll.tick() => {
+                if unlocked(&service, &dataset).await {
+                    info!("{dataset} was unlocked by other means; stopping the listener");
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Whether the encryption root of `dataset` has its key loaded. Errors,
+/// such as the pool not being imported yet, count as locked.
+async fn unlocked(service: &Arc<Service>, dataset: &str) -> bool {
+    let (service, dataset) = (service.clone(), dataset.to_string());
+    matches!(
+        spawn_blocking(move || service.status(&dataset)).await,
+        Ok(Ok(status)) if !status.root_locked
+    )
+}
+
+/// Serve one connection, returning whether it unlocked the dataset.
+///
+/// Only a client past the handshake gets to wait its turn for an attempt;
+/// when that attempt fails the turn is kept for [`FAILURE_DELAY`], so
+/// guesses cannot come faster than one per pause.
+async fn attend(shared: &Listener, stream: TcpStream, peer: SocketAddr) -> Result<bool> {
+    let mut tls = timeout(HANDSHAKE_TIMEOUT, shared.acceptor.accept(stream))
+        .await
+        .context("TLS handshake timed out")?
+        .context("TLS handshake")?;
+    let request = timeout(REQUEST_TIMEOUT, read_request(&mut tls))
+        .await
+        .context("no request before the timeout")??;
+
+    let _turn = shared.attempts.lock().await;
+    let (reply, done) = match parse(&request) {
+        Err(reason) => {
+            warn!("remote unlock from {peer} refused: {reason}");
+            (format!("error: {reason}"), false)
+        }
+        Ok(supplied) => {
+            let (service, dataset, origin) = (
+                shared.service.clone(),
+                shared.dataset.clone(),
+                format!("tls:{peer}"),
+            );
+            let result =
+                spawn_blocking(move || service.unlock_supplied(&dataset, &supplied, &origin))
+                    .await?;
+            match result {
+                Ok(report) => {
+                    info!(
+                        "{} unlocked by {peer} ({} datasets)",
+                        report.encryption_root,
+                        report.unlocked.len()
+                    );
+                    (format!("ok: {} unlocked", report.encryption_root), true)
+                }
+                Err(err) => {
+                    // The detail (e.g. checksum mismatches) stays in the log.
+                    warn!("remote unlock from {peer} failed: {err}");
+                    ("error: unlock failed".to_string(), false)
+                }
+            }
+        }
+    };
+    let sent = async {
+        tls.write_all(format!("{reply}\n").as_bytes()).await?;
+        tls.shutdown().await
+    }
+    .await;
+    if !done {
+        sleep(FAILURE_DELAY).await;
+    }
+    sent?;
+    Ok(done)
+}
+
+/// Read the request line into memory that is zeroed on drop.
+async fn read_request(stream: &mut (impl AsyncRead + Unpin)) -> Result<SecretBuffer> {
+    let mut buffer = SecretBuffer::zeroed(MAX_REQUEST);
+    let mut len = 0;
+    loop {
+        if let Some(end) = buffer[..len].iter().position(|&byte| byte == b'\n') {
+            len = end;
+            break;
+        }
+        if len == MAX_REQUEST {
+            bail!("request longer than {MAX_REQUEST} bytes");
+        }
+        match stream.read(&mut buffer[len..]).await? {
+            0 => break,
+            read => len += read,
+        }
+    }
+    if buffer[..len].ends_with(b"\r") {
+        len -= 1;
+    }
+    Ok(SecretBuffer::from_slice(&buffer[..len]))
+}
+
+/// Decode `passphrase <text>` or `key <hex>`.
+fn parse(request: &[u8]) -> Result<SuppliedKey, &'static str> {
+    let space = request
+        .iter()
+        .position(|&byte| byte == b' ')
+        .ok_or("expected `passphrase <text>` or `key <hex>`")?;
+    let value = &request[space + 1..];
+    match &request[..space] {
+        _ if value.is_empty() => Err("nothing after the request keyword"),
+        b"passphrase" => Ok(SuppliedKey::Passphrase(SecretBuffer::from_slice(value))),
+        b"key" => Ok(SuppliedKey::Key(SecretBuffer::from_slice(
+            value.trim_ascii(),
+        ))),
+        _ => Err("expected `passphrase <text>` or `key <hex>`"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_name_a_passphrase_or_a_key() {
+        match parse(b"passphrase correct horse ") {
+            Ok(SuppliedKey::Passphrase(text)) => assert_eq!(&text[..], b"correct horse "),
+            other => panic!("parsed {other:?}"),
+        }
+        match parse(b"key  00ff\r") {
+            Ok(SuppliedKey::Key(key)) => assert_eq!(&key[..], b"00ff"),
+            other => panic!("parsed {other:?}"),
+        }
+        for request in [
+            &b""[..],
+            b"passphrase",
+            b"passphrase ",
+            b"key ",
+            b"unlock 00ff",
+            b"KEY 00ff",
+        ] {
+            assert!(parse(request).is_err(), "{request:?} was accepted");
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Seccomp filter that confines the health worker to the system calls its
+//! runtime needs once its sockets are set up.
+//!
+//! Everything else fails with `EPERM`: the worker cannot open files, so it
+//! cannot read the key or the config, and cannot exec `zfs`, trace the
+//! daemon, or create new sockets. A call made under a foreign ABI, such as
+//! the 32-bit one on x86_64, kills the worker outright.
+
+use std::io;
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xc000_003e;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xc000_00b7;
+
+/// Calls allowed on every supported architecture.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const ALLOWED: &[libc::c_long] = &[
+    libc::SYS_read,
+    libc::SYS_write,
+    libc::SYS_readv,
+    libc::SYS_writev,
+    libc::SYS_recvfrom,
+    libc::SYS_sendto,
+    libc::SYS_recvmsg,
+    libc::SYS_sendmsg,
+    libc::SYS_accept4,
+    libc::SYS_shutdown,
+    libc::SYS_close,
+    libc::SYS_getsockopt,
+    libc::SYS_setsockopt,
+    libc::SYS_getsockname,
+    libc::SYS_getpeername,
+    libc::SYS_fcntl,
+    libc::SYS_ioctl,
+    libc::SYS_epoll_ctl,
+    libc::SYS_epoll_pwait,
+    libc::SYS_futex,
+    libc::SYS_sched_yield,
+    libc::SYS_clock_gettime,
+    libc::SYS_clock_nanosleep,
+    libc::SYS_nanosleep,
+    libc::SYS_getrandom,
+    libc::SYS_brk,
+    libc::SYS_mmap,
+    libc::SYS_munmap,
+    libc::SYS_mremap,
+    libc::SYS_mprotect,
+    libc::SYS_madvise,
+    libc::SYS_rt_sigreturn,
+    libc::SYS_rt_sigprocmask,
+    libc::SYS_sigaltstack,
+    libc::SYS_restart_syscall,
+    libc::SYS_exit,
+    libc::SYS_exit_group,
+];
+
+/// Calls only some architectures have.
+#[cfg(target_arch = "x86_64")]
+const ARCH_ALLOWED: &[libc::c_long] = &[libc::SYS_epoll_wait, libc::SYS_epoll_pwait2];
+#[cfg(target_arch = "aarch64")]
+const ARCH_ALLOWED: &[libc::c_long] = &[];
+
+/// Forbid new privileges and install the filter on the calling thread,
+/// which must be the worker's only one.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+pub fn confine() -> io::Result<()> {
+    install(&mut filter())
+}
+
+/// The BPF program: kill foreign ABIs, allow [`ALLOWED`], refuse the rest.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+fn filter() -> Vec<libc::sock_filter> {
+    use libc::sock_filter;
+    use libc::{BPF_ABS, BPF_JEQ, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W};
+
+    let load = |offset: u32| sock_filter {
+        code: (BPF_LD | BPF_W | BPF_ABS) as u16,
+        jt: 0,
+        jf: 0,
+        k: offset,
+    };
+    let ret = |action: u32| sock_filter {
+        code: (BPF_RET | BPF_K) as u16,
+        jt: 0,
+        jf: 0,
+        k: action,
+    };
+    // Skips `jt` instructions when the accumulator equals `value`, `jf` otherwise.
+    let jump_if = |value: u32, jt: u8, jf: u8| sock_filter {
+        code: (BPF_JMP | BPF_JEQ | BPF_K) as u16,
+        jt,
+        jf,
+        k: value,
+    };
+
+    // Offsets of `arch` and `nr` in struct seccomp_data.
+    let mut filter = vec![
+        load(4),
+        jump_if(AUDIT_ARCH, 1, 0),
+        ret(libc::SECCOMP_RET_KILL_PROCESS),
+        load(0),
+    ];
+    for &nr in ALLOWED.iter().chain(ARCH_ALLOWED) {
+        filter.push(jump_if(nr as u32, 0, 1));
+        filter.push(ret(libc::SECCOMP_RET_ALLOW));
+    }
+    filter.push(ret(libc::SECCOMP_RET_ERRNO | libc::EPERM as u32));
+    filter
+}
+
+/// Installs `filter` without allocating, so it is safe to call after `fork`.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+fn install(filter: &mut [libc::sock_filter]) -> io::Result<()> {
+    let program = libc::sock_fprog {
+        len: filter.len() as u16,
+        filter: filter.as_mut_ptr(),
+    };
+
+    // SAFETY: `program` points at `filter`, which outlives both calls; the
+    // kernel copies the filter when installing it.
+    unsafe {
+        if libc::prctl(
+            libc::PR_SET_NO_NEW_PRIVS,
+            1 as libc::c_ulong,
+            0 as libc::c_ulong,
+            0 as libc::c_ulong,
+            0 as libc::c_ulong,
+        ) != 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+        if libc::prctl(
+            libc::PR_SET_SECCOMP,
+            libc::SECCOMP_MODE_FILTER as libc::c_ulong,
+            &program as *const libc::sock_fprog,
+        ) != 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub fn confine() -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "no seccomp filter for this architecture",
+    ))
+}
+
+#[cfg(all(test, any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod tests {
+    use super::*;
+
+    /// Installs the filter in a forked child, which reports what it let
+    /// through as its exit status.
+    #[test]
+    fn confined_processes_can_write_but_not_open_files() {
+        let mut filter = filter();
+        // SAFETY: the filter is built before forking and the child makes
+        // only raw system calls before `_exit`, so it never allocates or
+        // touches locks other test threads may have held at fork.
+        let status = unsafe {
+            let pid = libc::fork();
+            assert!(pid >= 0, "fork failed");
+            if pid == 0 {
+                if install(&mut filter).is_err() {
+                    libc::_exit(10);
+                }
+                let fd = libc::open(c"/etc/hostname".as_ptr(), libc::O_RDONLY);
+                if fd >= 0 || *libc::__errno_location() != libc::EPERM {
+                    libc::_exit(11);
+                }
+                if libc::write(2, b"".as_ptr().cast(), 0) != 0 {
+                    libc::_exit(12);
+                }
+                libc::_exit(0);
+            }
+            let mut status = 0;
+            assert_eq!(libc::waitpid(pid, &mut status, 0), pid);
+            status
+        };
+        assert!(libc::WIFEXITED(status), "child was killed: {status:#x}");
+        assert_eq!(libc::WEXITSTATUS(status), 0);
+    }
+}
@@ -0,0 +1,181 @@
+//! Low-frequency scrub that re-verifies the staged keys against config and the token.
+
+use anyhow::Result;
+use lockchain_core::config::KeySource;
+use lockchain_core::keyfile::read_key_file;
+use lockchain_core::token::{self, MountedToken};
+use lockchain_core::{LockchainConfig, LockchainError, LockchainResult, SecretBuffer};
+use log::{debug, error, info};
+use sha2::{Digest, Sha256};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::time::{interval_at, Duration, Instant};
+
+use crate::privsep::HelperZfs;
+use crate::{DaemonState, HealthChannel};
+
+const SCRUB_INTERVAL_ENV: &str = "LOCKCHAIN_SCRUB_INTERVAL_SECS";
+const DEFAULT_SCRUB_INTERVAL_SECS: u64 = 24 * 60 * 60;
+
+/// What a single scrub pass concluded about the staged keys.
+#[derive(Debug)]
+enum ScrubOutcome {
+    /// Nothing is staged yet (token never inserted or already removed).
+    NotStaged,
+    /// Every staged key decoded cleanly and matched every reference we could check.
+    Intact {
+        /// Each staged key file and its SHA-256.
+        keys: Vec<(PathBuf, String)>,
+        token_checked: bool,
+    },
+    /// A staged key is unreadable or disagrees with config/token.
+    Drift(Vec<String>),
+}
+
+/// Periodically re-read the staged key and alert when it has drifted or rotted.
+pub async fn scrub_staged_key(state: Arc<DaemonState>, health: HealthChannel) -> Result<()> {
+    let period = scrub_interval();
+    let mut ticker = interval_at(Instant::now() + period, period);
+    info!("staged key scrub scheduled every {:?}", period);
+
+    loop {
+        ticker.tick().await;
+        let cfg = state.config();
+        let helper = state.helper.clone();
+        let outcome =
+            tokio::task::spawn_blocking(move || scrub_once(&cfg, helper.as_ref())).await?;
+        match outcome {
+            ScrubOutcome::NotStaged => {
+                debug!("staged key scrub skipped: nothing staged");
+                health.set_key_intact(true);
+            }
+            ScrubOutcome::Intact {
+                keys,
+                token_checked,
+            } => {
+                for (path, digest) in &keys {
+                    info!(
+                        "staged key scrub passed for {} (SHA-256 {digest}, token {})",
+                        path.display(),
+                        if token_checked {
+                            "verified"
+                        } else {
+                            "not inserted"
+                        }
+                    );
+                }
+                health.set_key_intact(true);
+            }
+            ScrubOutcome::Drift(problems) => {
+                for problem in &problems {
+                    error!(event = "scrub_drift"; "[LC4100] staged key integrity drift: {problem}");
+                }
+                health.set_key_intact(false);
+            }
+        }
+    }
+}
+
+/// Where the token holds its copy of `source`: the shared key sits at the
+/// token's configured key path, each `[usb.keys]` entry under its own file
+/// name at the token's root.
+pub(crate) fn token_copy(mounted: &MountedToken, source: &KeySource) -> Option<PathBuf> {
+    match source.entry {
+        None => Some(mounted.key_path()),
+        Some(_) => source
+            .path
+            .file_name()
+            .map(|name| mounted.mount_point.join(name)),
+    }
+}
+
+/// Resolve the scrub period, honouring the environment override.
+fn scrub_interval() -> Duration {
+    std::env::var(SCRUB_INTERVAL_ENV)
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::from_secs(DEFAULT_SCRUB_INTERVAL_SECS))
+}
+
+/// Run one verification pass over the shared key and every `[usb.keys]`
+/// entry without modifying anything. Keys are read through the ZFS helper
+/// when the daemon has one, since it may not open them itself.
+fn scrub_once(config: &LockchainConfig, helper: Option<&HelperZfs>) -> ScrubOutcome {
+    let read = |path: &Path| -> LockchainResult<SecretBuffer> {
+        match helper {
+            Some(helper) => helper.read_key(path),
+            None => read_key_file(path).map(|(key, _)| key),
+        }
+    };
+    let mounted = token::mounted_token(&config.usb).unwrap_or_else(|err| {
+        debug!("unable to inspect token mount: {err}");
+        None
+    });
+
+    let mut keys = Vec::new();
+    let mut problems = Vec::new();
+    let mut token_checked = false;
+    for source in config.key_sources() {
+        let staged_path = &source.path;
+        let staged = match read(staged_path) {
+            Ok(key) => key,
+            Err(LockchainError::Io(err)) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => {
+                problems.push(format!(
+                    "staged key {} unreadable ({err})",
+                    staged_path.display()
+                ));
+                continue;
+            }
+        };
+        let digest = hex::encode(Sha256::digest(&staged[..]));
+
+        if let Some(expected) = &source.expected_sha256 {
+            if !expected.eq_ignore_ascii_case(&digest) {
+                problems.push(format!(
+                    "{} has SHA-256 {digest}; {} is {expected}",
+                    staged_path.display(),
+                    source.setting()
+                ));
+            }
+        }
+
+        let token_path = mounted
+            .as_ref()
+            .and_then(|mounted| token_copy(mounted, &source));
+        if let Some(token_path) = token_path {
+            match read(&token_path) {
+                Ok(token_key) => {
+                    token_checked = true;
+                    let token_digest = hex::encode(Sha256::digest(&token_key[..]));
+                    if token_digest != digest {
+                        problems.push(format!(
+                            "{} differs from token copy {} (SHA-256 {token_digest})",
+                            staged_path.display(),
+                            token_path.display()
+                        ));
+                    }
+                }
+                Err(err) => problems.push(format!(
+                    "token key {} unreadable ({err})",
+                    token_path.display()
+                )),
+            }
+        }
+        keys.push((source.path, digest));
+    }
+
+    if !problems.is_empty() {
+        ScrubOutcome::Drift(problems)
+    } else if keys.is_empty() {
+        ScrubOutcome::NotStaged
+    } else {
+        ScrubOutcome::Intact {
+            keys,
+            token_checked,
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Orderly stop on SIGTERM or SIGINT.
+//!
+//! Once a signal arrives no new unlock or lock starts, the control socket
+//! stops accepting and is removed, and the daemon waits up to `[daemon]
+//! shutdown_timeout` for the ones in flight, so their audit records and
+//! journal entries are written before it exits. With `[daemon]
+//! shutdown_lock` every managed dataset is then unmounted and locked.
+
+use anyhow::Result;
+use lockchain_core::control::DaemonEventKind;
+use lockchain_core::service::LockOptions;
+use log::{error, info, warn};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::Notify;
+use tokio::time::{timeout, Duration};
+
+use crate::DaemonState;
+
+/// Exit status when in-flight unlocks or locks were still running at
+/// `shutdown_timeout`.
+pub const EXIT_ABANDONED: i32 = 3;
+/// Exit status when `shutdown_lock` left a dataset's key loaded.
+pub const EXIT_LOCK_FAILED: i32 = 4;
+
+/// Shutdown state shared by every task that unlocks or locks datasets.
+pub struct Shutdown {
+    stopping: AtomicBool,
+    in_flight: AtomicUsize,
+    settled: Notify,
+}
+
+/// Held while an unlock or lock runs; the daemon waits for every one to be
+/// dropped before it exits.
+pub struct InFlight<'a>(&'a Shutdown);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        if self.0.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.settled.notify_waiters();
+        }
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            stopping: AtomicBool::new(false),
+            in_flight: AtomicUsize::new(0),
+            settled: Notify::new(),
+        }
+    }
+
+    /// Register an unlock or lock about to start; `None` once the daemon is
+    /// stopping, in which case it must not start.
+    pub fn enter(&self) -> Option<InFlight<'_>> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = InFlight(self);
+        (!self.stopping.load(Ordering::SeqCst)).then_some(guard)
+    }
+
+    /// Refuse new work from now on.
+    fn begin(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+    }
+
+    /// Wait until nothing is in flight, at most `limit`; `false` on timeout.
+    async fn drain(&self, limit: Duration) -> bool {
+        let settled = async {
+            loop {
+                let notified = self.settled.notified();
+                if self.in_flight.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                notified.await;
+            }
+        };
+        timeout(limit, settled).await.is_ok()
+    }
+}
+
+/// Name of the first of SIGTERM and SIGINT to arrive.
+pub async fn signalled() -> Result<&'static str> {
+    let mut term = signal(SignalKind::terminate())?;
+    let mut int = signal(SignalKind::interrupt())?;
+    Ok(tokio::select! {
+        _ = term.recv() => "SIGTERM",
+        _ = int.recv() => "SIGINT",
+    })
+}
+
+/// Failure to stop cleanly, carrying the exit status that says why.
+#[derive(Debug)]
+pub struct Unclean {
+    pub code: i32,
+    pub reason: String,
+}
+
+impl std::fmt::Display for Unclean {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.reason)
+    }
+}
+
+impl std::error::Error for Unclean {}
+
+/// Exit status for an error that ended the daemon.
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    err.downcast_ref::<Unclean>()
+        .map_or(1, |unclean| unclean.code)
+}
+
+/// Stop taking work, wait for what is in flight, and lock up when asked to.
+/// The caller has already stopped the control socket.
+pub async fn stop(state: &Arc<DaemonState>) -> Result<()> {
+    let config = state.config();
+    state.shutdown.begin();
+    let in_flight = state.shutdown.in_flight.load(Ordering::SeqCst);
+    if in_flight > 0 {
+        info!(
+            "waiting up to {:?} for {in_flight} unlock or lock operations to finish",
+            config.daemon.shutdown_timeout
+        );
+    }
+    let drained = state.shutdown.drain(config.daemon.shutdown_timeout).await;
+    if !drained {
+        warn!(
+            "in-flight operations still running after {:?}; exiting without them",
+            config.daemon.shutdown_timeout
+        );
+    }
+
+    let mut failed = Vec::new();
+    if config.daemon.shutdown_lock {
+        let state = state.clone();
+        failed = tokio::task::spawn_blocking(move || lock_all(&state)).await?;
+    }
+
+    if !failed.is_empty() {
+        return Err(Unclean {
+            code: EXIT_LOCK_FAILED,
+            reason: format!("shutdown_lock could not lock {}", failed.join(", ")),
+        }
+        .into());
+    }
+    if !drained {
+        return Err(Unclean {
+            code: EXIT_ABANDONED,
+            reason: "in-flight unlock or lock abandoned at shutdown_timeout".into(),
+        }
+        .into());
+    }
+    info!("daemon stopped cleanly");
+    Ok(())
+}
+
+/// Unmount and lock every managed dataset; returns those left unlocked.
+fn lock_all(state: &DaemonState) -> Vec<String> {
+    let (config, service) = (state.config(), state.service());
+    let mut roots: Vec<String> = Vec::new();
+    let mut failed = Vec::new();
+    for dataset in &config.policy.datasets {
+        match service.lock(dataset, LockOptions { unmount: true }) {
+            Ok(report) if report.already_locked || roots.contains(&report.encryption_root) => {}
+            Ok(report) => {
+                info!(
+                    dataset:% = report.encryption_root, event = "shutdown_lock";
+                    "daemon stopping: unloaded key for {} ({} datasets)",
+                    report.encryption_root,
+                    report.locked.len()
+                );
+                state.journal.record(
+                    DaemonEventKind::Lock,
+                    Some(&report.encryption_root),
+                    format!(
+                        "daemon stopping: unloaded key ({} datasets)",
+                        report.locked.len()
+                    ),
+                );
+                roots.push(report.encryption_root);
+            }
+            Err(err) => {
+                error!(
+                    dataset:% = dataset, event = "shutdown_lock_failed";
+                    "daemon stopping but {dataset} could not be locked: {err}"
+                );
+                state.journal.record(
+                    DaemonEventKind::Lock,
+                    Some(dataset),
+                    format!("daemon stopping but lock failed: {err}"),
+                );
+                failed.push(dataset.clone());
+            }
+        }
+    }
+    failed
+}
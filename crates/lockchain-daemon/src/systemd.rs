@@ -0,0 +1,123 @@
+//! systemd readiness and watchdog notifications.
+//!
+//! Every notification is a no-op when the daemon was not started by systemd
+//! (`NOTIFY_SOCKET` unset), so running it by hand behaves as before.
+
+use anyhow::Result;
+use log::{info, warn};
+use sd_notify::NotifyState;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+use tokio::time::{interval, Duration, Instant};
+
+/// Extra time a loop gets past its own deadline before the watchdog gives up
+/// on it, covering slow `zfs` calls and scheduler hiccups.
+const STALL_SLACK: Duration = Duration::from_secs(30);
+
+/// Tell systemd start-up has finished: config is loaded and the provider is
+/// built.
+pub fn ready() {
+    notify(&[NotifyState::Ready]);
+}
+
+/// Tell systemd a config reload has started; [`ready`] ends it.
+pub fn reloading() {
+    match NotifyState::monotonic_usec_now() {
+        Ok(now) => notify(&[NotifyState::Reloading, now]),
+        Err(err) => warn!("systemd reload notification not sent: {err}"),
+    }
+}
+
+/// Tell systemd the daemon is shutting down.
+pub fn stopping() {
+    notify(&[NotifyState::Stopping]);
+}
+
+fn notify(states: &[NotifyState]) {
+    if let Err(err) = sd_notify::notify(false, states) {
+        warn!("systemd notification not sent: {err}");
+    }
+}
+
+/// Heartbeats from the daemon's main loops.
+///
+/// Each loop beats with the time by which it promises to beat again; the
+/// watchdog is only fed while every loop has kept its promise, so a loop
+/// stuck in a hung `zfs` call gets the daemon restarted.
+#[derive(Clone, Default)]
+pub struct Watchdog {
+    deadlines: Arc<Mutex<BTreeMap<&'static str, Instant>>>,
+}
+
+impl Watchdog {
+    /// Record that `name` is alive and will beat again within `within`.
+    pub fn beat(&self, name: &'static str, within: Duration) {
+        self.deadlines
+            .lock()
+            .unwrap()
+            .insert(name, Instant::now() + within + STALL_SLACK);
+    }
+
+    /// Loops that have missed their deadline.
+    fn stalled(&self) -> Vec<&'static str> {
+        let now = Instant::now();
+        self.deadlines
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, deadline)| **deadline < now)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+}
+
+/// Feed systemd's watchdog while every loop is beating, and mirror overall
+/// health into the unit's status line.
+pub async fn serve(watchdog: Watchdog, mut health: watch::Receiver<bool>) -> Result<()> {
+    let mut usec = 0;
+    let period = if sd_notify::watchdog_enabled(false, &mut usec) {
+        let period = Duration::from_micros(usec) / 2;
+        info!("systemd watchdog enabled; pinging every {period:?}");
+        Some(period)
+    } else {
+        None
+    };
+    // Without a watchdog the ticks are ignored.
+    let mut ticker = interval(period.unwrap_or(Duration::from_secs(3600)));
+    let mut reported_stall = false;
+    status(*health.borrow_and_update());
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                if period.is_none() {
+                    continue;
+                }
+                let stalled = watchdog.stalled();
+                if stalled.is_empty() {
+                    notify(&[NotifyState::Watchdog]);
+                    reported_stall = false;
+                } else if !reported_stall {
+                    warn!(
+                        "withholding systemd watchdog ping; stalled: {}",
+                        stalled.join(", ")
+                    );
+                    reported_stall = true;
+                }
+            }
+            res = health.changed() => {
+                if res.is_err() {
+                    return Ok(());
+                }
+                status(*health.borrow_and_update());
+            }
+        }
+    }
+}
+
+/// The unit's status line, as shown by `systemctl status`.
+fn status(healthy: bool) {
+    let status = if healthy { "ready" } else { "degraded" };
+    notify(&[NotifyState::Status(status)]);
+}
@@ -1,42 +1,320 @@
-//! Polling loop that checks whether the USB key material is present on disk.
+//! Watcher that tracks whether the USB key material is present on disk.
 
 use anyhow::Result;
-use lockchain_core::LockchainConfig;
-use log::{info, warn};
+use futures_util::StreamExt;
+use inotify::{EventMask, EventStream, Inotify, WatchMask};
+use lockchain_core::config::WebhookEvent;
+use lockchain_core::control::DaemonEventKind;
+use lockchain_core::service::LockOptions;
+use lockchain_core::token;
+use log::{debug, error, info, warn};
+use std::ffi::{OsStr, OsString};
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use tokio::time::{interval, Duration};
+use tokio::time::{sleep, timeout_at, Duration, Instant};
 
-use crate::HealthChannel;
+use crate::{DaemonState, HealthChannel, Watchdog};
 
-/// Periodically inspect the expected key path and update health status.
-pub async fn watch_usb(config: Arc<LockchainConfig>, health: HealthChannel) -> Result<()> {
-    let key_path = config.key_hex_path();
-    let mut ticker = interval(Duration::from_secs(5));
+/// How often the key path is inspected when inotify is unavailable.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How often the key path is re-inspected while the inotify watch is armed,
+/// catching what events cannot report, such as a filesystem mounted over
+/// the key directory.
+const RESCAN_INTERVAL: Duration = Duration::from_secs(60);
+/// Quiet period after a key file event, so a write seen as create, write,
+/// and chmod is inspected once, when it is finished.
+const SETTLE: Duration = Duration::from_millis(200);
+
+/// inotify watch on the directory holding the key file.
+struct KeyDirWatch {
+    dir: PathBuf,
+    events: EventStream<[u8; 1024]>,
+}
+
+impl KeyDirWatch {
+    fn arm(dir: &Path) -> std::io::Result<Self> {
+        let inotify = Inotify::init()?;
+        inotify.watches().add(
+            dir,
+            WatchMask::CREATE
+                | WatchMask::CLOSE_WRITE
+                | WatchMask::ATTRIB
+                | WatchMask::DELETE
+                | WatchMask::MOVED_FROM
+                | WatchMask::MOVED_TO
+                | WatchMask::DELETE_SELF
+                | WatchMask::MOVE_SELF
+                | WatchMask::ONLYDIR,
+        )?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            events: inotify.into_event_stream([0; 1024])?,
+        })
+    }
+
+    /// Wait until `name` changes or `limit` passes; `false` when the watch
+    /// was lost (directory removed, moved, or unmounted) and must be re-armed.
+    async fn changed(&mut self, name: &OsStr, limit: Duration) -> bool {
+        let mut deadline = Instant::now() + limit;
+        loop {
+            let event = match timeout_at(deadline, self.events.next()).await {
+                Err(_) => return true,
+                Ok(Some(Ok(event))) => event,
+                Ok(Some(Err(err))) => {
+                    warn!("inotify watch on {} failed: {err}", self.dir.display());
+                    return false;
+                }
+                Ok(None) => return false,
+            };
+            if event.mask.intersects(
+                EventMask::IGNORED
+                    | EventMask::DELETE_SELF
+                    | EventMask::MOVE_SELF
+                    | EventMask::UNMOUNT,
+            ) {
+                return false;
+            }
+            if event.mask.contains(EventMask::Q_OVERFLOW) || event.name.as_deref() == Some(name) {
+                deadline = Instant::now() + SETTLE;
+            }
+        }
+    }
+}
+
+/// Inspect the expected key path whenever it changes and update health
+/// status.
+///
+/// The key's directory is watched with inotify, so a token being inserted or
+/// pulled is noticed at once; when the directory is missing or inotify is
+/// unavailable the path is polled every five seconds instead.
+///
+/// Which recognised token supplied the key is logged whenever it changes, so
+/// a spare standing in for the primary token shows up in the journal.
+///
+/// The key arriving wakes the unlock loop, so datasets unlock at once
+/// rather than on its next pass.
+///
+/// When the key disappears, any key cached by the service is discarded so a
+/// pulled token cannot keep unlocking datasets from memory. With
+/// `[usb.removal] lock` set, every managed dataset is also locked once the
+/// key has stayed away for the grace period.
+pub async fn watch_usb(
+    state: Arc<DaemonState>,
+    health: HealthChannel,
+    watchdog: Watchdog,
+) -> Result<()> {
     let mut last_state: Option<bool> = None;
+    let mut last_token: Option<String> = None;
+    let mut watch: Option<KeyDirWatch> = None;
+    let mut polling = false;
+    // When the pulled key's grace period runs out.
+    let mut lock_at: Option<Instant> = None;
 
     loop {
-        ticker.tick().await;
+        let config = state.config();
+        let key_path = config.key_hex_path();
+        let dir = key_path.parent().unwrap_or(Path::new("/")).to_path_buf();
+        let key_name = key_path.file_name().map(OsString::from).unwrap_or_default();
+
+        // A reload may have moved the key elsewhere.
+        if watch.as_ref().is_some_and(|watch| watch.dir != dir) {
+            watch = None;
+        }
+        if watch.is_none() {
+            match KeyDirWatch::arm(&dir) {
+                Ok(armed) => {
+                    debug!("watching {} for key changes", dir.display());
+                    watch = Some(armed);
+                    polling = false;
+                }
+                Err(err) if !polling => {
+                    warn!(
+                        "cannot watch {} ({err}); polling for key material every {:?}",
+                        dir.display(),
+                        POLL_INTERVAL
+                    );
+                    polling = true;
+                }
+                Err(_) => {}
+            }
+        }
+
         let present = match fs::metadata(&key_path) {
             Ok(meta) => meta.is_file() && meta.len() == 32,
             Err(_) => false,
         };
 
         if last_state != Some(present) {
+            let journal = &state.journal;
             if present {
+                journal.record(
+                    DaemonEventKind::Usb,
+                    None,
+                    format!("key material ready at {}", key_path.display()),
+                );
                 info!(
+                    event = "usb_ready";
                     "USB key material ready at {} (32 bytes detected).",
                     key_path.display()
                 );
+                if lock_at.take().is_some() {
+                    info!("USB key material returned within the grace period; not locking.");
+                    journal.record(
+                        DaemonEventKind::Usb,
+                        None,
+                        "key returned within the grace period; lock cancelled",
+                    );
+                }
+                if state.removal_locked.swap(false, Ordering::SeqCst) {
+                    info!("USB key material returned; resuming automatic unlocks.");
+                }
+                if state.idle.keep_alive() {
+                    info!("USB key material arrived; lifting the idle lock.");
+                }
+                if last_state == Some(false) {
+                    state.unlock_now.notify_one();
+                }
             } else {
                 warn!(
+                    event = "usb_missing";
                     "USB key material at {} missing or invalid; waiting for lockchain-key-usb.",
                     key_path.display()
                 );
+                let message = format!("key material missing at {}", key_path.display());
+                if last_state == Some(true) {
+                    state
+                        .webhooks
+                        .notify(WebhookEvent::UsbRemoved, None, &message);
+                }
+                journal.record(DaemonEventKind::Usb, None, message);
+                state.service().invalidate_key_cache();
+                let removal = &config.usb.removal;
+                if last_state == Some(true) && removal.lock {
+                    warn!(
+                        event = "removal_lock_pending";
+                        "locking managed datasets in {:?} unless the token returns",
+                        removal.grace
+                    );
+                    lock_at = Some(Instant::now() + removal.grace);
+                    journal.record(
+                        DaemonEventKind::Usb,
+                        None,
+                        format!("locking managed datasets in {:?}", removal.grace),
+                    );
+                }
             }
             last_state = Some(present);
         }
 
+        if lock_at.is_some_and(|at| at <= Instant::now()) {
+            lock_at = None;
+            if config.usb.removal.lock {
+                watchdog.beat("usb", RESCAN_INTERVAL);
+                let state = state.clone();
+                tokio::task::spawn_blocking(move || lock_on_removal(&state)).await?;
+            }
+        }
+
+        let inserted = if present {
+            match token::mounted_token(&config.usb) {
+                Ok(mounted) => mounted,
+                Err(err) => {
+                    debug!("unable to inspect token mounts: {err}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let name = inserted.as_ref().map(|m| m.token.name().to_string());
+        if name != last_token {
+            match &inserted {
+                Some(mounted) if mounted.rank > 0 => warn!(
+                    "USB token `{}` (precedence {}) is supplying the key; higher-precedence tokens are absent.",
+                    mounted.token.name(),
+                    mounted.rank + 1
+                ),
+                Some(mounted) => info!("USB token `{}` is supplying the key.", mounted.token.name()),
+                None => {}
+            }
+            if let Some(mounted) = &inserted {
+                state.journal.record(
+                    DaemonEventKind::Usb,
+                    None,
+                    format!("token `{}` is supplying the key", mounted.token.name()),
+                );
+            }
+            last_token = name;
+        }
+
         health.set_usb_ready(present);
+
+        // Wake up in time to lock when a grace period is running.
+        let until_lock = |limit: Duration| {
+            lock_at.map_or(limit, |at| {
+                at.saturating_duration_since(Instant::now()).min(limit)
+            })
+        };
+        match watch.as_mut() {
+            Some(armed) => {
+                let limit = until_lock(RESCAN_INTERVAL);
+                watchdog.beat("usb", limit);
+                if !armed.changed(&key_name, limit).await {
+                    debug!("watch on {} lost; re-arming", dir.display());
+                    watch = None;
+                }
+            }
+            None => {
+                let limit = until_lock(POLL_INTERVAL);
+                watchdog.beat("usb", limit);
+                sleep(limit).await;
+            }
+        }
+    }
+}
+
+/// Unload the key of every managed dataset because the token was pulled,
+/// and keep the daemon from unlocking them again until the key returns.
+fn lock_on_removal(state: &DaemonState) {
+    let Some(_in_flight) = state.shutdown.enter() else {
+        return;
+    };
+    let (config, service) = (state.config(), state.service());
+    let options = LockOptions {
+        unmount: config.usb.removal.unmount,
+    };
+    state.removal_locked.store(true, Ordering::SeqCst);
+    let mut roots: Vec<String> = Vec::new();
+    for dataset in &config.policy.datasets {
+        match service.lock(dataset, options.clone()) {
+            Ok(report) if report.already_locked || roots.contains(&report.encryption_root) => {}
+            Ok(report) => {
+                warn!(
+                    dataset:% = report.encryption_root, event = "removal_lock";
+                    "USB token removed: unloaded key for {} ({} datasets)",
+                    report.encryption_root,
+                    report.locked.len()
+                );
+                state.journal.record(
+                    DaemonEventKind::Lock,
+                    Some(&report.encryption_root),
+                    format!(
+                        "USB token removed: unloaded key ({} datasets)",
+                        report.locked.len()
+                    ),
+                );
+                roots.push(report.encryption_root);
+            }
+            Err(err) => {
+                error!(dataset:% = dataset, event = "removal_lock_failed"; "USB token removed but {dataset} could not be locked: {err}");
+                state.journal.record(
+                    DaemonEventKind::Lock,
+                    Some(dataset),
+                    format!("USB token removed but lock failed: {err}"),
+                );
+            }
+        }
     }
 }
@@ -0,0 +1,253 @@
+//! JSON webhooks fired on unlock outcomes, break-glass recoveries, and USB
+//! key removal, so alerting does not have to scrape the journal.
+//!
+//! Notifications are queued and delivered from their own tasks, so a slow or
+//! unreachable endpoint never holds up an unlock. Each endpoint is retried
+//! with doubling backoff up to its `attempts`; after that the notification
+//! is dropped with a warning.
+
+use anyhow::Result;
+use chrono::{SecondsFormat, Utc};
+use lockchain_core::audit::{AuditEvent, AuditRecord, AuditResult};
+use lockchain_core::config::{WebhookCfg, WebhookEvent};
+use log::{debug, warn};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::time::{interval, sleep, Duration};
+
+use crate::DaemonState;
+
+/// Longest wait between two delivery attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+/// How often the audit trail is checked for new break-glass records.
+const AUDIT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// JSON body POSTed to every endpoint that wants the event.
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    event: WebhookEvent,
+    host: String,
+    timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dataset: Option<String>,
+    message: String,
+    /// One-line summary, so chat webhooks such as Slack's render it as is.
+    text: String,
+}
+
+/// Queue feeding [`deliver`].
+#[derive(Clone)]
+pub struct Webhooks {
+    tx: mpsc::UnboundedSender<Notification>,
+}
+
+impl Webhooks {
+    pub fn channel() -> (Self, mpsc::UnboundedReceiver<Notification>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self { tx }, rx)
+    }
+
+    /// Queue `event` for every configured endpoint that wants it.
+    pub fn notify(&self, event: WebhookEvent, dataset: Option<&str>, message: impl Into<String>) {
+        let message = message.into();
+        let host = fs::read_to_string("/proc/sys/kernel/hostname")
+            .map(|name| name.trim().to_string())
+            .unwrap_or_else(|_| "localhost".into());
+        let text = match dataset {
+            Some(dataset) => format!(
+                "lockchain on {host}: {} {dataset}: {message}",
+                event.as_str()
+            ),
+            None => format!("lockchain on {host}: {}: {message}", event.as_str()),
+        };
+        let notification = Notification {
+            event,
+            host,
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            dataset: dataset.map(str::to_string),
+            message,
+            text,
+        };
+        // The receiver only goes away when the daemon is shutting down.
+        let _ = self.tx.send(notification);
+    }
+}
+
+/// Send queued notifications to the endpoints in the current config.
+pub async fn deliver(
+    state: Arc<DaemonState>,
+    mut queue: mpsc::UnboundedReceiver<Notification>,
+) -> Result<()> {
+    while let Some(notification) = queue.recv().await {
+        let event = notification.event;
+        let body = serde_json::to_string(&notification)?;
+        for hook in state
+            .config()
+            .webhooks
+            .iter()
+            .filter(|hook| hook.wants(event))
+        {
+            tokio::spawn(send(hook.clone(), event, body.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// POST `body` to `hook`, retrying with backoff until it is accepted, the
+/// endpoint rejects it outright, or the attempts run out.
+async fn send(hook: WebhookCfg, event: WebhookEvent, body: String) {
+    let endpoint = endpoint(&hook.url).to_string();
+    let mut delay = hook.backoff;
+    for attempt in 1..=hook.attempts.max(1) {
+        let (request_hook, request_body) = (hook.clone(), body.clone());
+        let outcome = tokio::task::spawn_blocking(move || post(&request_hook, &request_body))
+            .await
+            .unwrap_or_else(|err| Err(Failure::Retry(err.to_string())));
+        let reason = match outcome {
+            Ok(()) => {
+                debug!("webhook {endpoint} accepted {}", event.as_str());
+                return;
+            }
+            Err(Failure::Reject(reason)) => {
+                warn!(
+                    "webhook {endpoint} rejected {}: {reason}; not retrying",
+                    event.as_str()
+                );
+                return;
+            }
+            Err(Failure::Retry(reason)) => reason,
+        };
+        if attempt == hook.attempts.max(1) {
+            warn!(
+                "webhook {endpoint} dropped {} after {attempt} attempts: {reason}",
+                event.as_str()
+            );
+            return;
+        }
+        warn!(
+            "webhook {endpoint} attempt {attempt}/{} for {} failed: {reason}; retrying in {delay:?}",
+            hook.attempts,
+            event.as_str()
+        );
+        sleep(delay).await;
+        delay = (delay * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Why a delivery failed, and whether trying again could help.
+enum Failure {
+    Retry(String),
+    Reject(String),
+}
+
+fn post(hook: &WebhookCfg, body: &str) -> Result<(), Failure> {
+    let agent = ureq::AgentBuilder::new().timeout(hook.timeout).build();
+    let mut request = agent
+        .post(&hook.url)
+        .set("content-type", "application/json");
+    for (name, value) in &hook.headers {
+        request = request.set(name, value);
+    }
+    match request.send_string(body) {
+        Ok(_) => Ok(()),
+        // Timeouts, rate limits, and server errors may clear up; other
+        // client errors mean the request itself is wrong.
+        Err(ureq::Error::Status(code @ (408 | 429 | 500..), _)) => {
+            Err(Failure::Retry(format!("HTTP {code}")))
+        }
+        Err(ureq::Error::Status(code, _)) => Err(Failure::Reject(format!("HTTP {code}"))),
+        Err(ureq::Error::Transport(err)) => Err(Failure::Retry(err.to_string())),
+    }
+}
+
+/// Scheme and authority of `url`, for logs; paths and queries often carry
+/// the endpoint's secret.
+fn endpoint(url: &str) -> &str {
+    let start = url.find("://").map_or(0, |at| at + 3);
+    url[start..]
+        .find(['/', '?', '#'])
+        .map_or(url, |end| &url[..start + end])
+}
+
+/// Notify `breakglass` webhooks about break-glass records appended to the
+/// audit trail, whichever process wrote them.
+///
+/// Only records written after the daemon started are reported. The trail
+/// is polled; a rotated or replaced file is read from its start.
+pub async fn follow_audit(state: Arc<DaemonState>) -> Result<()> {
+    let mut ticker = interval(AUDIT_POLL_INTERVAL);
+    let mut position = audit_end(&state.config().audit.log_path());
+    loop {
+        ticker.tick().await;
+        let config = state.config();
+        if !config.audit.enabled {
+            continue;
+        }
+        let path = config.audit.log_path();
+        let (records, next) = match new_records(&path, position) {
+            Ok(read) => read,
+            Err(err) => {
+                debug!("audit trail {} not read: {err}", path.display());
+                continue;
+            }
+        };
+        position = Some(next);
+        for record in records
+            .iter()
+            .filter(|record| record.entry.event == AuditEvent::Breakglass)
+        {
+            let entry = &record.entry;
+            let message = match entry.result {
+                AuditResult::Success => format!("break-glass recovery by {}", record.actor),
+                AuditResult::Failure => format!(
+                    "failed break-glass recovery by {}: {}",
+                    record.actor,
+                    entry.detail.as_deref().unwrap_or("no detail")
+                ),
+            };
+            state
+                .webhooks
+                .notify(WebhookEvent::Breakglass, Some(&entry.dataset), message);
+        }
+    }
+}
+
+/// Inode and length of the audit trail, i.e. where reading resumes.
+type AuditPosition = (u64, u64);
+
+fn audit_end(path: &Path) -> Option<AuditPosition> {
+    fs::metadata(path).ok().map(|meta| (meta.ino(), meta.len()))
+}
+
+/// Complete records appended since `position`, and where the next read
+/// starts.
+fn new_records(
+    path: &Path,
+    position: Option<AuditPosition>,
+) -> std::io::Result<(Vec<AuditRecord>, AuditPosition)> {
+    let mut file = File::open(path)?;
+    let meta = file.metadata()?;
+    let offset = match position {
+        Some((ino, len)) if ino == meta.ino() && len <= meta.len() => len,
+        _ => 0,
+    };
+    file.seek(SeekFrom::Start(offset))?;
+    let mut records = Vec::new();
+    let mut consumed = offset;
+    let mut reader = BufReader::new(file);
+    let mut line = String::new();
+    // A line without its newline is still being written; leave it for later.
+    while reader.read_line(&mut line)? > 0 && line.ends_with('\n') {
+        consumed += line.len() as u64;
+        if let Ok(record) = serde_json::from_str(&line) {
+            records.push(record);
+        }
+        line.clear();
+    }
+    Ok((records, (meta.ino(), consumed)))
+}
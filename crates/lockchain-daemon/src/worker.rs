@@ -0,0 +1,249 @@
+//! Worker process that serves the health endpoint apart from the daemon.
+//!
+//! The health endpoint is the one part of the daemon that takes connections
+//! from the network. With `[health] isolate` (the default) the daemon binds
+//! the listener and re-executes itself as a worker that answers on it. The
+//! worker never sees the config, key material, or ZFS: it runs as
+//! `[health] user` when the daemon is root, starts without capabilities,
+//! forbids itself new privileges, and installs a seccomp filter that leaves
+//! it socket I/O and little else (see [`sandbox`]). The daemon pushes a
+//! [`Snapshot`] down a socketpair whenever the responses change; nothing
+//! flows back, so a compromised worker can neither read the key nor ask the
+//! daemon to do anything.
+
+use anyhow::{bail, Context, Result};
+use lockchain_core::config::HealthCfg;
+use lockchain_core::logging;
+use log::{info, warn};
+use std::ffi::CString;
+use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::net::UnixStream as StdUnixStream;
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, UnixStream};
+use tokio::process::{Child, Command};
+use tokio::select;
+use tokio::sync::Semaphore;
+use tokio::time::{sleep, Duration};
+
+use crate::health::{self, Auth, Snapshot};
+use crate::{sandbox, DaemonState};
+
+/// First argument that starts the binary as a worker instead of the daemon.
+pub const ARG: &str = "--health-worker";
+
+/// Descriptor numbers the listener and the snapshot feed arrive on.
+const LISTENER_FD: RawFd = 3;
+const FEED_FD: RawFd = 4;
+/// How often the daemon checks whether the responses changed.
+const FEED_INTERVAL: Duration = Duration::from_secs(1);
+/// Pause before replacing a worker that exited.
+const RESPAWN_DELAY: Duration = Duration::from_secs(5);
+/// Environment the worker keeps; everything else is cleared.
+const KEPT_ENV: &[&str] = &["RUST_LOG", "LOCKCHAIN_LOG_LEVEL", "LOCKCHAIN_LOG_FORMAT"];
+
+/// Run workers on `listener` for as long as the daemon runs, replacing any
+/// that exit, and keep them fed with snapshots.
+pub async fn supervise(state: Arc<DaemonState>, listener: std::net::TcpListener) -> Result<()> {
+    loop {
+        let (ours, theirs) = StdUnixStream::pair().context("create health worker socketpair")?;
+        let mut child = spawn(&state.config().health, &listener, &theirs)?;
+        drop(theirs);
+        ours.set_nonblocking(true)?;
+        let feed = UnixStream::from_std(ours)?;
+        info!(
+            "health worker {} serving the health endpoint",
+            child.id().unwrap_or_default()
+        );
+        select! {
+            status = child.wait() => warn!("health worker exited ({}); restarting in {RESPAWN_DELAY:?}", status?),
+            err = push(&state, feed) => warn!("health worker stopped reading: {err}; restarting in {RESPAWN_DELAY:?}"),
+        }
+        let _ = child.kill().await;
+        sleep(RESPAWN_DELAY).await;
+    }
+}
+
+/// Start a worker that inherits `listener` and `feed`.
+fn spawn(cfg: &HealthCfg, listener: &std::net::TcpListener, feed: &StdUnixStream) -> Result<Child> {
+    let mut command = Command::new("/proc/self/exe");
+    command
+        .arg0("lockchain-daemon")
+        .arg(ARG)
+        .env_clear()
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    for name in KEPT_ENV {
+        if let Some(value) = std::env::var_os(name) {
+            command.env(name, value);
+        }
+    }
+    // SAFETY: geteuid cannot fail.
+    if unsafe { libc::geteuid() } == 0 {
+        let (uid, gid) =
+            user_ids(&cfg.user).with_context(|| format!("look up health.user {}", cfg.user))?;
+        // Also clears the supplementary groups.
+        command.uid(uid).gid(gid);
+    }
+    let (listener, feed) = (listener.as_raw_fd(), feed.as_raw_fd());
+    // SAFETY: `inherit` only makes async-signal-safe system calls.
+    unsafe {
+        command.pre_exec(move || inherit(listener, feed));
+    }
+    command.spawn().context("start health worker")
+}
+
+/// In the forked child: move the listener and feed to [`LISTENER_FD`] and
+/// [`FEED_FD`], where they survive exec, and drop ambient capabilities, which
+/// would too.
+fn inherit(listener: RawFd, feed: RawFd) -> std::io::Result<()> {
+    // SAFETY: plain system calls on descriptors the parent keeps open.
+    unsafe {
+        // Park both above the target numbers first, so neither dup2
+        // clobbers the other.
+        let listener_copy = libc::fcntl(listener, libc::F_DUPFD, 10);
+        let feed_copy = libc::fcntl(feed, libc::F_DUPFD, 10);
+        if listener_copy < 0
+            || feed_copy < 0
+            || libc::dup2(listener_copy, LISTENER_FD) < 0
+            || libc::dup2(feed_copy, FEED_FD) < 0
+        {
+            return Err(std::io::Error::last_os_error());
+        }
+        libc::close(listener_copy);
+        libc::close(feed_copy);
+        // Fails on kernels without ambient capabilities, which then have none to drop.
+        libc::prctl(
+            libc::PR_CAP_AMBIENT,
+            libc::PR_CAP_AMBIENT_CLEAR_ALL as libc::c_ulong,
+            0 as libc::c_ulong,
+            0 as libc::c_ulong,
+            0 as libc::c_ulong,
+        );
+    }
+    Ok(())
+}
+
+/// Uid and primary gid of the user called `name`.
+pub(crate) fn user_ids(name: &str) -> Result<(u32, u32)> {
+    let c_name = CString::new(name).context("user name contains NUL")?;
+    // SAFETY: libc::passwd is plain C data, for which all zeroes is valid.
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    let mut buf = vec![0 as libc::c_char; 16 * 1024];
+    // SAFETY: every pointer is valid for the call and `buf` outlives `passwd`'s use.
+    let rc = unsafe {
+        libc::getpwnam_r(
+            c_name.as_ptr(),
+            &mut passwd,
+            buf.as_mut_ptr(),
+            buf.len(),
+            &mut result,
+        )
+    };
+    if rc != 0 || result.is_null() {
+        bail!("no user named {name}");
+    }
+    if passwd.pw_uid == 0 {
+        bail!("{name} is root");
+    }
+    Ok((passwd.pw_uid, passwd.pw_gid))
+}
+
+/// Send a snapshot whenever the responses or the token change, until the
+/// worker stops reading.
+async fn push(state: &DaemonState, mut feed: UnixStream) -> std::io::Error {
+    let mut last = None;
+    // Whether the unreadable token was already reported.
+    let mut refusing = false;
+    loop {
+        let auth = match Auth::load(&state.config().health) {
+            Ok(auth) => {
+                refusing = false;
+                auth
+            }
+            Err(err) => {
+                if !refusing {
+                    warn!("health requests refused: {err}");
+                    refusing = true;
+                }
+                Auth::Refuse
+            }
+        };
+        let snapshot = state.health.snapshot(auth);
+        if last.as_ref() != Some(&snapshot) {
+            let mut line = serde_json::to_string(&snapshot).unwrap_or_default();
+            line.push('\n');
+            if let Err(err) = feed.write_all(line.as_bytes()).await {
+                return err;
+            }
+            last = Some(snapshot);
+        }
+        sleep(FEED_INTERVAL).await;
+    }
+}
+
+/// Entry point of the worker process.
+pub fn run() -> Result<()> {
+    logging::init("info");
+    // SAFETY: geteuid cannot fail.
+    if unsafe { libc::geteuid() } == 0 {
+        bail!("the health worker refuses to run as root");
+    }
+    // SAFETY: the daemon hands these descriptors over and nothing else here
+    // owns them.
+    let (listener, feed) = unsafe {
+        (
+            std::net::TcpListener::from_raw_fd(LISTENER_FD),
+            StdUnixStream::from_raw_fd(FEED_FD),
+        )
+    };
+    listener.set_nonblocking(true)?;
+    feed.set_nonblocking(true)?;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .enable_time()
+        .build()?;
+    runtime.block_on(async move {
+        let listener = TcpListener::from_std(listener)?;
+        let feed = UnixStream::from_std(feed)?;
+        match sandbox::confine() {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::Unsupported => {
+                warn!("health worker runs without a seccomp filter: {err}")
+            }
+            Err(err) => return Err(err).context("install seccomp filter"),
+        }
+        serve(listener, feed).await
+    })
+}
+
+/// Answer requests from the latest snapshot until the daemon goes away.
+async fn serve(listener: TcpListener, feed: UnixStream) -> Result<()> {
+    let mut snapshots = BufReader::new(feed).lines();
+    let mut current = Snapshot::starting();
+    let slots = Arc::new(Semaphore::new(health::MAX_CONNECTIONS));
+    loop {
+        select! {
+            line = snapshots.next_line() => match line? {
+                Some(line) => current = serde_json::from_str(&line).context("read snapshot")?,
+                None => return Ok(()),
+            },
+            accepted = listener.accept() => {
+                let (stream, peer) = accepted?;
+                let Ok(slot) = slots.clone().try_acquire_owned() else {
+                    warn!("health: {} connections already open; dropped {peer}", health::MAX_CONNECTIONS);
+                    continue;
+                };
+                let snapshot = current.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = health::answer(stream, &snapshot).await {
+                        warn!("failed to respond to {peer}: {err}");
+                    }
+                    drop(slot);
+                });
+            }
+        }
+    }
+}
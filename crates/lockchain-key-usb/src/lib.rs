@@ -0,0 +1,403 @@
+//! udev-driven import of key material from USB tokens.
+//!
+//! Shared by the `lockchain-key-usb` service and, with its `usb-import`
+//! feature and `[daemon] import_usb`, the daemon itself. Either host hands
+//! [`KeyImporter`] a way to fetch the current config, so a daemon reload
+//! reaches the importer too, and may observe what it does through
+//! [`ImportEvent`]s.
+
+use anyhow::{bail, Context, Result};
+use hex::encode as hex_encode;
+use lockchain_core::{
+    keyfile::{read_key_file, write_raw_key_file},
+    token, LockchainConfig, UsbToken,
+};
+use log::{debug, info, warn};
+use sha2::{Digest, Sha256};
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use udev::{Device, Enumerator, MonitorBuilder};
+
+/// What the importer did with a recognised token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportEvent {
+    /// Key material from `token` now sits at `dest`.
+    Imported { token: String, dest: PathBuf },
+    /// `token` was inserted but its key was not used.
+    Rejected { token: String, reason: String },
+    /// The active `token` was removed and the staged key cleared.
+    Removed { token: String },
+}
+
+/// Tracks the currently mounted USB device so we can clean up on removal.
+#[derive(Debug)]
+struct ActiveDevice {
+    devpath: String,
+    devnode: PathBuf,
+    /// Name and precedence of the token this device matched.
+    token: String,
+    rank: usize,
+    #[allow(dead_code)]
+    mount_point: PathBuf,
+    #[allow(dead_code)]
+    source_path: PathBuf,
+}
+
+/// Handles device discovery, checksum verification, and file synchronisation.
+///
+/// When several recognised tokens are present, the one listed first in the
+/// config supplies the key: a higher-precedence token inserted later takes
+/// over, and removing the active token falls back to any other inserted one.
+pub struct KeyImporter {
+    config: Box<dyn Fn() -> Arc<LockchainConfig> + Send + Sync>,
+    observer: Box<dyn Fn(ImportEvent) + Send + Sync>,
+    active: Mutex<Option<ActiveDevice>>,
+}
+
+impl KeyImporter {
+    /// Construct an importer that consults `config` for every device.
+    pub fn new(config: impl Fn() -> Arc<LockchainConfig> + Send + Sync + 'static) -> Self {
+        Self {
+            config: Box::new(config),
+            observer: Box::new(|_| {}),
+            active: Mutex::new(None),
+        }
+    }
+
+    /// Report every [`ImportEvent`] to `observer`.
+    pub fn observe(mut self, observer: impl Fn(ImportEvent) + Send + Sync + 'static) -> Self {
+        self.observer = Box::new(observer);
+        self
+    }
+
+    /// Import from tokens already inserted, then follow udev forever.
+    pub fn run(&self) -> Result<()> {
+        let config = (self.config)();
+        info!(
+            "USB key watcher started (dest path: {})",
+            config.key_hex_path().display()
+        );
+        for (rank, token) in config.usb.recognized_tokens().iter().enumerate() {
+            info!(
+                "recognising token `{}` (precedence {}): {}",
+                token.name(),
+                rank + 1,
+                token.describe()
+            );
+        }
+        self.scan_existing()?;
+        self.event_loop()
+    }
+
+    /// Look for already-mounted USB devices that match policy.
+    pub fn scan_existing(&self) -> Result<()> {
+        let mut enumerator = Enumerator::new()?;
+        enumerator.match_subsystem("block")?;
+        enumerator.match_property("DEVTYPE", "partition")?;
+        enumerator.match_property("ID_BUS", "usb")?;
+
+        for device in enumerator.scan_devices()? {
+            if let Err(err) = self.try_import(&device) {
+                warn!("importing {} failed: {err:?}", device_syspath(&device));
+            }
+        }
+        Ok(())
+    }
+
+    /// Block on udev events and react to arrivals and removals.
+    pub fn event_loop(&self) -> Result<()> {
+        let mut monitor = MonitorBuilder::new()?.match_subsystem("block")?.listen()?;
+
+        loop {
+            if let Some(event) = monitor.next() {
+                let device = event.device();
+                if let Err(err) = self.process_device(&device) {
+                    warn!(
+                        "handling event for {} failed: {err:?}",
+                        device_syspath(&device)
+                    );
+                }
+            } else {
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
+    }
+
+    /// Dispatch the udev event to either import or cleanup handlers.
+    fn process_device(&self, device: &Device) -> Result<()> {
+        let action = device.action().and_then(os_str_to_str).unwrap_or("change");
+        match action {
+            "add" | "change" | "bind" => self.try_import(device),
+            "remove" | "unbind" => {
+                self.handle_removal(device);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Validate the device, verify content, and copy key material into place.
+    fn try_import(&self, device: &Device) -> Result<()> {
+        let Some((rank, token)) = self.matching_token(device) else {
+            return Ok(());
+        };
+
+        let devpath = device.devpath().to_string_lossy().to_string();
+        {
+            let active = self.active.lock().unwrap();
+            if let Some(current) = active.as_ref() {
+                if current.devpath == devpath {
+                    debug!("device {} already active, skipping import", devpath);
+                    return Ok(());
+                }
+                if current.rank <= rank {
+                    info!(
+                        "token `{}` inserted while `{}` is active; keeping the higher-precedence token",
+                        token.name(),
+                        current.token
+                    );
+                    return Ok(());
+                }
+                info!(
+                    "token `{}` outranks active token `{}`; switching",
+                    token.name(),
+                    current.token
+                );
+            }
+        }
+
+        let devnode = device
+            .devnode()
+            .ok_or_else(|| anyhow::anyhow!("device {} missing devnode", devpath))?
+            .to_path_buf();
+
+        let mount_point = self.wait_for_mount(&devnode)?;
+        let source_path = mount_point.join(token.key_path());
+
+        let (key, converted) = match read_key_file(&source_path) {
+            Ok(result) => result,
+            Err(err) => {
+                warn!("failed to decode key at {}: {err}", source_path.display());
+                self.reject_candidate(&token, format!("undecodable key: {err}"));
+                return Ok(());
+            }
+        };
+
+        if let Some(expected) = &token.sha256 {
+            let digest = Sha256::digest(&key);
+            let checksum = hex_encode(digest);
+            if !expected.eq_ignore_ascii_case(&checksum) {
+                warn!(
+                    "checksum mismatch for {}: expected {}, got {}",
+                    source_path.display(),
+                    expected,
+                    checksum
+                );
+                self.reject_candidate(&token, "checksum mismatch".into());
+                return Ok(());
+            }
+        }
+
+        if converted {
+            info!(
+                "normalised legacy hex key from {} before writing destination",
+                source_path.display()
+            );
+        }
+
+        let dest = (self.config)().key_hex_path();
+        write_raw_key_file(&dest, &key).map_err(|err| anyhow::anyhow!(err))?;
+        info!(
+            "copied key material from token `{}` ({}) to {}",
+            token.name(),
+            source_path.display(),
+            dest.display()
+        );
+        (self.observer)(ImportEvent::Imported {
+            token: token.name().to_string(),
+            dest,
+        });
+
+        let mut guard = self.active.lock().unwrap();
+        *guard = Some(ActiveDevice {
+            devpath,
+            devnode,
+            token: token.name().to_string(),
+            rank,
+            mount_point,
+            source_path,
+        });
+
+        Ok(())
+    }
+
+    /// Tear down state when the matching USB device disappears, then fall
+    /// back to any other recognised token that is still inserted.
+    fn handle_removal(&self, device: &Device) {
+        let mut guard = self.active.lock().unwrap();
+        if guard.is_none() {
+            return;
+        }
+
+        let matches = {
+            let active = guard.as_ref().unwrap();
+            let devpath = device.devpath().to_string_lossy();
+            let devnode = device.devnode().map(|p| p.to_path_buf());
+
+            if devpath == active.devpath {
+                true
+            } else if let Some(node) = devnode {
+                node == active.devnode
+            } else {
+                false
+            }
+        };
+
+        if matches {
+            info!(
+                "device {} removed; clearing destination key",
+                device_syspath(device)
+            );
+            self.clear_destination();
+            let removed = guard.take().map(|active| active.token).unwrap_or_default();
+            drop(guard);
+            (self.observer)(ImportEvent::Removed { token: removed });
+            if let Err(err) = self.scan_existing() {
+                warn!("rescanning for spare tokens failed: {err:?}");
+            }
+        }
+    }
+
+    /// Drop a device that failed verification without disturbing a working token.
+    fn reject_candidate(&self, token: &UsbToken, reason: String) {
+        if self.active.lock().unwrap().is_none() {
+            self.clear_destination();
+        }
+        (self.observer)(ImportEvent::Rejected {
+            token: token.name().to_string(),
+            reason,
+        });
+    }
+
+    /// Remove the destination key to avoid stale material lingering.
+    fn clear_destination(&self) {
+        let dest = (self.config)().key_hex_path();
+        match fs::remove_file(&dest) {
+            Ok(_) => info!("removed destination key {}", dest.display()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => warn!("failed to remove destination key {}: {err}", dest.display()),
+        }
+    }
+
+    /// Poll /proc/mounts until the device shows up or we time out.
+    fn wait_for_mount(&self, devnode: &Path) -> Result<PathBuf> {
+        let timeout = (self.config)().usb.mount_timeout;
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(path) = find_mount_point(devnode)? {
+                return Ok(path);
+            }
+            if Instant::now() >= deadline {
+                bail!(
+                    "timed out waiting for {} to mount ({}s)",
+                    devnode.display(),
+                    timeout.as_secs()
+                );
+            }
+            thread::sleep(Duration::from_millis(250));
+        }
+    }
+
+    /// Find the highest-precedence configured token this udev device matches.
+    fn matching_token(&self, device: &Device) -> Option<(usize, UsbToken)> {
+        if device.property_value("DEVTYPE").and_then(os_str_to_str) != Some("partition") {
+            return None;
+        }
+
+        if device.property_value("ID_BUS").and_then(os_str_to_str) != Some("usb") {
+            return None;
+        }
+
+        let property = |key: &str| device.property_value(key).and_then(os_str_to_str);
+        let serials = [property("ID_SERIAL_SHORT"), property("ID_SERIAL")];
+        (self.config)()
+            .usb
+            .recognized_tokens()
+            .into_iter()
+            .enumerate()
+            .find(|(_, token)| {
+                token.matches(property("ID_FS_LABEL"), property("ID_FS_UUID"), &serials)
+            })
+    }
+}
+
+/// Provide a human-readable path for logging udev devices.
+fn device_syspath(device: &Device) -> String {
+    device.syspath().to_string_lossy().into_owned()
+}
+
+/// Convenience helper for zero-copy OsStr → &str conversions.
+fn os_str_to_str(value: &OsStr) -> Option<&str> {
+    value.to_str()
+}
+
+/// Locate the mountpoint for a block device by scanning the mount table.
+fn find_mount_point(devnode: &Path) -> Result<Option<PathBuf>> {
+    let mounts = token::read_mount_table().context("read mount table")?;
+    Ok(token::parse_mounts(&mounts, devnode))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use tempfile::tempdir;
+
+    struct EnvGuard {
+        key: &'static str,
+        prev: Option<String>,
+    }
+
+    impl EnvGuard {
+        fn set(key: &'static str, value: impl Into<String>) -> Self {
+            let prev = env::var(key).ok();
+            env::set_var(key, value.into());
+            Self { key, prev }
+        }
+    }
+
+    impl Drop for EnvGuard {
+        fn drop(&mut self) {
+            if let Some(prev) = &self.prev {
+                env::set_var(self.key, prev);
+            } else {
+                env::remove_var(self.key);
+            }
+        }
+    }
+
+    #[test]
+    fn find_mount_point_honours_override() {
+        let dir = tempdir().unwrap();
+        let mount_file = dir.path().join("mounts");
+        fs::write(
+            &mount_file,
+            "/dev/sdb1 /media/LOCK\\040CHAIN ext4 rw,relatime 0 0\n",
+        )
+        .unwrap();
+
+        let _guard = EnvGuard::set(
+            token::MOUNTS_OVERRIDE_ENV,
+            mount_file.to_string_lossy().into_owned(),
+        );
+
+        let result = find_mount_point(Path::new("/dev/sdb1")).unwrap();
+        assert_eq!(result, Some(PathBuf::from("/media/LOCK CHAIN")));
+    }
+}
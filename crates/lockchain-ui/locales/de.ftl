@@ -0,0 +1,413 @@
+# Control Deck strings, German. Messages missing here show in English.
+
+language-system = Systemsprache
+
+## Hosts
+
+host-local = Dieser Host
+host-selected = Zielhost: { $host }
+
+## Dataset panel
+
+datasets-heading = Verwaltete Datasets
+datasets-heading-remote = Verwaltete Datasets auf { $host }
+datasets-loading = Lädt…
+datasets-reading = Schlüsselstatus wird gelesen…
+datasets-empty = Keine Datasets konfiguriert; trage sie unter policy.datasets ein.
+key-state-unlocked = ENTSPERRT
+key-state-locked = GESPERRT
+key-state-unknown = UNBEKANNT
+dataset-unlock = Entsperren
+dataset-lock = Sperren
+dataset-root = Wurzel { $root }
+dataset-working = LÄUFT…
+dataset-unlock-failed = Entsperren von { $dataset } fehlgeschlagen: { $error }
+dataset-lock-failed = Sperren von { $dataset } fehlgeschlagen: { $error }
+dataset-already-unlocked = { $root } war bereits entsperrt
+dataset-already-locked = { $root } war bereits gesperrt
+dataset-unlocked = { $root } entsperrt ({ $count ->
+    [one] 1 Dataset
+   *[other] { $count } Datasets
+})
+dataset-locked = { $root } gesperrt ({ $count ->
+    [one] 1 Dataset
+   *[other] { $count } Datasets
+})
+
+## Settings screen
+
+settings-heading = Einstellungen
+nav-back = Zurück
+settings-loading = Konfiguration wird geladen…
+settings-load-failed = Konfiguration konnte nicht geladen werden: { $error }
+settings-saved = Einstellungen in { $path } gespeichert
+settings-save-failed = Speichern der Einstellungen fehlgeschlagen: { $error }
+settings-target-profile = { $path } (Profil { $profile })
+settings-remove-dataset = Entfernen
+settings-add-dataset = Dataset hinzufügen
+settings-usb = USB-Token
+settings-device-label = Gerätebezeichnung
+settings-device-uuid = Geräte-UUID
+any-value = (beliebig)
+settings-datasets = Datasets
+settings-fallback = Ersatz-Passphrase
+settings-fallback-enabled = Aktiviert
+settings-askpass = Mit askpass abfragen
+settings-askpass-path = askpass-Pfad
+settings-pbkdf2-iterations = PBKDF2-Iterationen
+settings-retry = Entsperr-Wiederholungen
+settings-max-attempts = Max. Versuche
+settings-base-delay = Basisverzögerung
+settings-max-delay = Max. Verzögerung
+settings-jitter-ratio = Jitter-Anteil
+settings-strategy = Strategie
+settings-appearance = Darstellung
+settings-theme = Farbschema
+settings-language = Sprache
+settings-reduced-motion = Weniger Bewegung
+settings-hosts = Entfernte Hosts
+settings-ssh-destinations = SSH-Ziele
+settings-saving = Speichert…
+settings-save = Speichern
+
+## Dialogs
+
+dialog-cancel = Abbrechen
+guard-pick-device = Wähle vor dem Schmieden den USB-Token, der gelöscht werden soll.
+guard-wipe-title = USB-Token löschen?
+guard-wipe-destroys = Alles auf { $device } wird zerstört und durch neues Schlüsselmaterial ersetzt.
+guard-wipe-permanent = Schlüsseldateien, die bereits darauf liegen, sind unwiderruflich verloren.
+guard-type-answer = Gib `{ $answer }` ein, um fortzufahren:
+guard-wipe-confirm = Löschen und schmieden
+killswitch-title = Killswitch auslösen?
+killswitch-scope-all = Jede konfigurierte Verschlüsselungswurzel aushängen und ihren Schlüssel entladen.
+killswitch-scope-roots = { $count ->
+    [one] 1 Verschlüsselungswurzel
+   *[other] { $count } Verschlüsselungswurzeln
+} aushängen und Schlüssel entladen: { $roots }.
+killswitch-shred = Die Laufzeit-Schlüsseldatei schreddern, damit sich nichts mehr damit entsperren lässt.
+killswitch-open-files = Offene Dateien auf diesen Datasets gehen laufenden Programmen verloren; erneutes Entsperren braucht den Schlüssel-Token oder die Ersatz-Passphrase.
+killswitch-stop-daemon = Auch lockchain-zfs.service stoppen
+killswitch-confirm = Killswitch auslösen
+
+## USB token picker
+
+devices-scanning = Suche nach USB-Tokens…
+devices-none = Keine Wechseldatenträger gefunden
+devices-select = USB-Token zum Schmieden wählen
+devices-rescan = Neu suchen
+devices-label = USB-Token:
+
+## Directive runs
+
+run-label = { $directive } läuft · { $count ->
+    [one] 1 Ereignis
+   *[other] { $count } Ereignisse
+}
+
+## Header and footer
+
+header-title = Control Deck
+header-subtitle = Kryptografische ZFS-Schlüsselverwaltung — powered by LockChain
+header-subtitle-profile = Profil { $profile } — powered by LockChain
+mode-secure = GESICHERT
+mode-standard = STANDARD
+header-secure = Gesichert
+header-tray = Tray
+header-dashboard = Dashboard
+header-settings = Einstellungen
+header-refresh = Aktualisieren
+footer-total-events = Ereignisse gesamt: { $count }
+footer-status = Status: { $status }
+status-monitoring = Überwachung
+status-forge-complete = Schmieden abgeschlossen
+status-check-diagnostics = Diagnose prüfen
+status-killswitch = Killswitch ausgelöst
+
+## Directives
+
+directives-heading = Modul-Direktive wählen
+directive-new-key = Neuer Schlüssel
+directive-new-key-safe = Neuer Schlüssel (sicherer Modus)
+directive-self-test = Selbsttest
+directive-self-heal = Selbstheilung
+directive-doctor = Doctor
+directive-new-key-summary = Frisches USB-Schlüsselmaterial schmieden
+directive-new-key-safe-summary = Geführtes Schmieden mit Bestätigungen
+directive-self-test-summary = Entsperrübung + Prüfung des Schlüsselstatus
+directive-self-heal-summary = Schlüsselmaterial und Dataset-Zustand diagnostizieren
+directive-doctor-summary = Vollständige Systemprüfung mit Ein-Klick-Korrekturen
+directive-new-key-help = Schmiedet einen neuen 32-Byte-USB-Schlüssel auf den oben gewählten Token und setzt die Ersatz-Passphrase, falls eine gesetzt ist. Wähle ein Dataset, um eine bestimmte Verschlüsselungswurzel anzusprechen.
+directive-new-key-safe-help = Sicheres Schmieden prüft den Token, statt ihn zu löschen, außer „Force wipe“ ist an. Wähle oben den Token und bei Bedarf ein Dataset.
+directive-self-test-help = Legt einen verschlüsselten Test-Pool an, entsperrt ihn mit dem aktuellen Schlüssel und baut ihn wieder ab. Strikter USB-Modus hier oder in der Kopfzeile überspringt die Ersatz-Passphrase.
+directive-self-heal-help = Diagnostik für Schlüsseldatei, Prüfsumme und Schlüsselstatus der Datasets.
+directive-doctor-help = Führt die Selbstheilungsprüfungen plus systemd-/Journal-/initramfs-Prüfungen aus, ohne etwas zu ändern. Jede Abhilfe erscheint als Karte, mit „Korrektur anwenden“, wo das Deck sie beheben kann.
+dataset-none-configured = Kein Dataset konfiguriert; trage eines unter policy.datasets ein
+breakglass-card-title = Notfall-Wiederherstellung
+breakglass-card-summary = Geführte Schlüsselwiederherstellung aus der Ersatz-Passphrase
+
+## Command panel
+
+command-heading = > Parameter für { $directive }
+command-execute = Ausführen
+command-system-status = Systemstatus: { $status }
+command-active-module = Aktives Modul: { $directive }
+command-privileges-root = Rechte: root
+command-privileges-user = Rechte: Benutzer (erhöht über pkexec)
+command-local-only = Direktiven und Killswitch wirken auf diesen Host, nicht auf { $host }
+command-self-test-unavailable = Selbsttest erst verfügbar, wenn ein LockChain-Schlüssel geschmiedet oder eingesteckt ist.
+command-help = Hilfe
+command-killswitch = Killswitch
+command-shortcuts = Tasten: ↑/↓ Direktive · Enter ausführen/bestätigen · Esc abbrechen · Tab Fokus · Strg+L Feed leeren
+
+## Activity feed
+
+activity-heading = Laufzeit-Aktivitäten
+activity-export = Protokoll exportieren
+activity-exporting = Exportiert…
+level-info = INFO
+level-success = ERFOLG
+level-warn = WARNUNG
+level-error = FEHLER
+level-secure = SICHER
+activity-online = Control Deck bereit. Wähle eine Direktive, um zu beginnen.
+activity-unprivileged = Läuft ohne root: Direktiven und Dataset-Aktionen fragen nach, ob sie über pkexec erhöht laufen sollen.
+activity-strict-on = Strikter USB-Modus aktiviert.
+activity-strict-off = Strikter USB-Modus deaktiviert.
+activity-tray-on = Tray-Modus an: Schließen des Fensters minimiert es in die Taskleiste.
+activity-tray-off = Tray-Modus aus: Schließen des Fensters beendet das Programm.
+activity-tray-unavailable = Taskleiste nicht verfügbar ({ $error }); Schließen des Fensters beendet das Programm.
+activity-needs-key = Schmiede oder stecke einen LockChain-Schlüssel ein, bevor du diese Direktive ausführst.
+activity-cancelled = { $action } abgebrochen.
+activity-passphrase-set = Ersatz-Passphrase für das nächste Schmieden gesetzt.
+activity-passphrase-cleared = Ersatz-Passphrase gelöscht.
+activity-complete = { $title } abgeschlossen
+activity-executing = { $directive } wird ausgeführt
+activity-killswitch = Killswitch ausgelöst: jede Verschlüsselungswurzel wird gesperrt…
+activity-killswitch-failed = Killswitch fehlgeschlagen: { $error }
+activity-self-heal = Selbstheilungsdiagnose läuft…
+activity-refresh-only = Selbstheilung braucht root; nur Zustand und Schlüsselstatus werden aktualisiert.
+activity-feed-cleared = Feed geleert; das gespeicherte Aktivitätsprotokoll behält alle Einträge
+activity-exported = Aktivitätsprotokoll nach { $path } exportiert
+activity-export-failed = Export fehlgeschlagen: { $error }
+activity-locked = Gesperrt: { $datasets }
+activity-keystatus-failed = Abfrage des Schlüsselstatus fehlgeschlagen: { $error }
+activity-unlocking = { $dataset } wird entsperrt…
+activity-locking = { $dataset } wird gesperrt…
+activity-device-selected = USB-Token { $device } gewählt
+activity-elevating = Erhöhte Ausführung: { $command }
+activity-elevated-doctor = Erhöhte Doctor-Läufe listen ihre Befunde hier statt als Karten.
+devices-scan-failed = Gerätesuche fehlgeschlagen: { $error }
+notify-dataset-locked = Dataset gesperrt
+notify-unlock-failed = Entsperren fehlgeschlagen
+export-read-failed = { $path } konnte nicht gelesen werden: { $error }
+export-write-failed = { $path } konnte nicht geschrieben werden: { $error }
+
+## Parameter forms
+
+form-optional = optional
+form-dataset-default = Erster Eintrag in policy.datasets
+
+## Fallback passphrase
+
+passphrase-title = Ersatz-Passphrase festlegen
+passphrase-explainer = Das nächste Schmieden leitet aus dieser Passphrase einen Wiederherstellungsschlüssel ab. Bewahre sie sicher und offline auf.
+passphrase-placeholder = Passphrase
+passphrase-confirm-placeholder = Passphrase bestätigen
+passphrase-use = Passphrase verwenden
+passphrase-strength = { $band } · ~{ $bits } Bit
+passphrase-mismatch = Die Passphrasen stimmen nicht überein.
+passphrase-status-set = Ersatz-Passphrase: für das nächste Schmieden gesetzt
+passphrase-status-unset = Ersatz-Passphrase: nicht gesetzt
+passphrase-change = Ändern…
+passphrase-set = Festlegen…
+passphrase-clear = Löschen
+strength-weak = Schwach
+strength-fair = Mäßig
+strength-good = Gut
+strength-strong = Stark
+
+## Health chips
+
+health-chip-usb = USB
+health-chip-daemon = DIENST
+health-chip-zfs = ZFS
+health-checking = Wird geprüft
+health-no-config = KEINE KONFIG
+health-key = SCHLÜSSEL
+health-key-detail = Laufzeitschlüssel liegt unter { $path }
+health-no-key = KEIN SCHLÜSSEL
+health-no-key-detail = Kein Laufzeitschlüssel unter { $path }
+health-ready = BEREIT
+health-ready-detail = Dienst meldet Bereitschaft
+health-degraded = EINGESCHRÄNKT
+health-daemon-degraded-detail = Dienst läuft, meldet aber einen eingeschränkten Zustand: { $components }
+health-running = LÄUFT
+health-running-detail = Steuer-Socket { $socket } antwortet
+health-down = AUS
+health-no-pools = KEINE POOLS
+health-no-pools-detail = Keine Datasets konfiguriert
+health-error = FEHLER
+health-online = ONLINE
+health-online-detail = Online auf { $host }: { $pools }
+health-pools-degraded-detail = Auf { $host }: { $pools }
+
+## Doctor findings
+
+doctor-heading = Doctor-Befunde
+doctor-dismiss = Ausblenden
+doctor-applying = Korrektur wird angewendet: { $fix }
+doctor-fix-failed = Korrektur fehlgeschlagen: { $error }
+fix-repair = Reparatur erneut ausführen
+fix-tighten-permissions = Berechtigungen verschärfen
+fix-rebuild-initramfs = initramfs neu bauen
+fix-apply-all = Alle Reparaturen anwenden
+fix-state-applying = WIRD ANGEWENDET…
+fix-state-fixed = BEHOBEN
+fix-state-failed = FEHLGESCHLAGEN
+fix-state-manual = MANUELL
+
+## Running as root through pkexec
+
+elevate-title = Administratorrechte erforderlich
+elevate-explainer = Das Deck läuft ohne root. { $action } braucht root, daher läuft dieser Befehl über pkexec als root:
+elevate-stdin-note = Die Passphrase wird über die Standardeingabe übergeben und erscheint nicht in der Prozessliste.
+elevate-polkit-note = Der polkit-Agent deines Desktops fragt nach einem Administratorpasswort.
+elevate-confirm = Als root ausführen
+elevate-summary-unlock = { $dataset } entsperren
+elevate-summary-unlock-remote = { $dataset } auf { $host } entsperren
+elevate-summary-lock = { $dataset } sperren
+elevate-summary-lock-all = Alle verwalteten Datasets sperren
+elevate-summary-breakglass = Notfall-Wiederherstellung von { $dataset }
+elevate-no-cli = { $directive } hat kein CLI-Gegenstück, das über pkexec laufen könnte; starte das Deck als root, um es zu nutzen.
+elevate-stop-daemon = Das Stoppen des Dienstes braucht ein als root laufendes Deck; wähle es ab, um alle Datasets über pkexec zu sperren.
+elevate-no-pkexec = pkexec ist nicht installiert; starte das Deck als root, um diese Aktion auszuführen
+elevate-start-failed = pkexec konnte nicht gestartet werden: { $error }
+elevate-stdin-failed = Passphrase konnte nicht an pkexec übergeben werden: { $error }
+elevate-wait-failed = Warten auf pkexec fehlgeschlagen: { $error }
+elevate-read-failed = Lesen von pkexec fehlgeschlagen: { $error }
+elevate-dismissed = Autorisierungsdialog geschlossen; nichts wurde ausgeführt
+elevate-denied = Keine Berechtigung, { $cli } als root auszuführen
+elevate-failed = { $action } fehlgeschlagen ({ $status })
+
+## Dashboard
+
+history-heading = Dataset-Verlauf
+history-reload = Neu laden
+history-day = Letzte 24 Stunden
+history-week = Letzte 7 Tage
+history-month = Letzte 30 Tage
+history-loading = Audit-Protokoll wird gelesen…
+history-empty = In diesem Zeitraum wurden keine Entsperr- oder Sperrvorgänge aufgezeichnet.
+history-read-failed = { $path } konnte nicht gelesen werden: { $error }
+history-legend-unlocked = entsperrt
+history-legend-locked = gesperrt
+history-legend-failed = fehlgeschlagen
+history-legend-slow = Entsperren ≥ { $threshold }
+history-flapping = INSTABIL
+history-slow = LANGSAM
+history-latency = Median Entsperren { $median } · langsamstes { $slowest }
+history-no-timings = keine Entsperrzeiten aufgezeichnet
+history-stats = { $timed ->
+    [one] 1 Entsperren gemessen
+   *[other] { $timed } Entsperrvorgänge gemessen
+} · { $latency } · { $transitions ->
+    [one] 1 Zustandswechsel
+   *[other] { $transitions } Zustandswechsel
+} · { $failures ->
+    [one] 1 Fehlschlag
+   *[other] { $failures } Fehlschläge
+}
+
+## First-run wizard
+
+wizard-heading = Ersteinrichtung
+wizard-step = Schritt { $current } von { $total } · { $title }
+wizard-step-datasets = Datasets
+wizard-step-token = USB-Token
+wizard-step-fallback = Ersatzregelung
+wizard-step-review = Überprüfen
+wizard-skip = Überspringen
+wizard-next = Weiter
+wizard-write = Konfiguration schreiben
+wizard-writing = Schreibt…
+wizard-datasets-intro = Unter { $path } wurde keine Konfiguration gefunden. Wähle die Verschlüsselungswurzeln, die LockChain entsperren soll.
+wizard-discovering = ZFS wird nach Verschlüsselungswurzeln gefragt…
+wizard-discovery-failed = ZFS konnte nicht abgefragt werden ({ $error }); trage die Datasets unten ein.
+wizard-no-roots = Keine verschlüsselten Datasets gefunden; trage sie unten ein.
+wizard-extra-datasets = Weitere Datasets, durch Kommas getrennt
+wizard-token-intro = Wähle den Wechseldatenträger, der den Schlüssel aufnehmen soll. Geschrieben wird erst, wenn du „Neuer Schlüssel“ ausführst.
+wizard-token-label = Token-Bezeichnung:
+wizard-token-label-note = Der Dienst wartet auf eine Partition mit dieser Bezeichnung. Beim Schmieden wird der Token als { $label } formatiert.
+wizard-fallback-intro = Eine Ersatz-Passphrase kann den Schlüssel wiederherstellen, wenn der Token verloren geht. Ihr Material wird beim Schmieden des Schlüssels abgeleitet.
+wizard-seed-fallback = Ersatz-Passphrase für das erste Schmieden festlegen
+wizard-askpass = Beim Booten mit askpass danach fragen, wenn der Token fehlt
+wizard-review-config = Konfiguration: { $path }
+wizard-review-datasets = Datasets: { $datasets }
+wizard-review-label = Token-Bezeichnung: { $label }
+wizard-review-token = Token für „Neuer Schlüssel“: { $token }
+wizard-review-no-token = noch keiner gewählt
+wizard-review-fallback-seeded = Ersatz: Passphrase wird jetzt abgefragt und beim ersten Schmieden gesetzt
+wizard-review-fallback-disabled = Ersatz: deaktiviert
+wizard-config-exists = { $path } existiert bereits; öffne die Einstellungen, um sie zu bearbeiten
+wizard-create-failed = { $path } konnte nicht angelegt werden: { $error }
+wizard-wrote = { $path } geschrieben
+wizard-next-steps = Als Nächstes: schmiede mit „Neuer Schlüssel“ einen Schlüssel auf den USB-Token.
+wizard-write-failed = Schreiben der Konfiguration fehlgeschlagen: { $error }
+wizard-skipped = Einrichtung übersprungen; Direktiven brauchen eine Konfiguration unter { $path }.
+
+## Break-glass recovery
+
+breakglass-step-warnings = 1 · Vorab
+breakglass-step-dataset = 2 · Dataset
+breakglass-step-passphrase = 3 · Ersatz-Passphrase
+breakglass-step-destination = 4 · Ziel
+breakglass-step-done = Wiederhergestellt
+breakglass-close = Schließen
+breakglass-recover = Schlüssel wiederherstellen
+breakglass-recovering = Stellt wieder her…
+breakglass-loading = Konfiguration wird gelesen…
+breakglass-disabled = Die Ersatz-Wiederherstellung ist in dieser Konfiguration nicht aktiviert.
+breakglass-incomplete = Die Ersatz-Konfiguration ist unvollständig (salt/xor fehlt).
+breakglass-warning-derives = Die Notfall-Wiederherstellung leitet den rohen Verschlüsselungsschlüssel eines Datasets aus der Ersatz-Passphrase ab. Nutze sie nur, wenn der USB-Token verloren oder defekt ist.
+breakglass-warning-exposure = Wer den abgeleiteten Schlüssel besitzt, kann das Dataset entsperren. Schreddere Schlüsseldateien, sobald sie benutzt wurden.
+breakglass-warning-audit = Jeder Versuch, ob erfolgreich oder nicht, wird im Audit-Protokoll festgehalten.
+breakglass-acknowledge = Ich verstehe, dass der Schlüssel offengelegt wird, und übernehme die Verantwortung dafür
+breakglass-pick-dataset = Wähle das Dataset, dessen Schlüssel wiederhergestellt werden soll.
+breakglass-type-dataset = Gib zur Bestätigung `{ $dataset }` ein:
+breakglass-passphrase = Ersatz-Passphrase
+breakglass-passphrase-intro = Gib die Passphrase ein, die beim Schmieden dieses Schlüssels gewählt wurde. Sie wird nie angezeigt oder ins Aktivitätsprotokoll geschrieben.
+breakglass-destination-intro = Wohin soll der abgeleitete Schlüssel?
+breakglass-destination-file = In eine Schlüsseldatei schreiben
+breakglass-destination-display = Einmal anzeigen und nie schreiben
+breakglass-key-file = Schlüsseldatei
+breakglass-file-note = Die Datei wird mit den Rechten 0400 angelegt; denk daran, sie danach zu schreddern.
+breakglass-display-note = Der Schlüssel erscheint auf dem nächsten Bildschirm als Hex und ist weg, sobald du ihn schließt; weder Festplatte noch Aktivitätsprotokoll werden berührt.
+breakglass-copy-now = Kopiere den Schlüssel jetzt. Er wird nur angezeigt, bis dieser Bildschirm geschlossen wird.
+breakglass-digest = SHA-256 { $digest }
+breakglass-started = Notfall-Wiederherstellung für { $dataset } gestartet
+breakglass-failed = Notfall-Wiederherstellung fehlgeschlagen: { $error }
+breakglass-displayed = Notfall-Schlüssel für { $dataset } einmal angezeigt (SHA-256 { $digest }); nichts wurde geschrieben.
+breakglass-bad-length = 32 Byte Schlüsselmaterial erwartet, { $length } erhalten
+
+## Directive parameters
+## The English originals live in lockchain-core's parameter schema.
+
+param-dataset = Dataset
+param-dataset-help = Ziel-Dataset; leer bleibt es beim ersten Eintrag in policy.datasets.
+param-device = USB-Gerät
+param-device-help = USB-Blockgerät (z. B. /dev/sdb1). Ohne Angabe wird es über Bezeichnung/UUID erkannt.
+param-mount = Einhängepunkt
+param-mount-help = Einhängepunkt während der Bereitstellung.
+param-filename = Schlüsseldateiname
+param-filename-help = Dateiname im eingehängten Token (Standard: lockchain.key).
+param-force-wipe = Löschen erzwingen
+param-force-wipe-help = Auch im sicheren Modus löschen.
+param-no-rebuild = initramfs nicht neu bauen
+param-no-rebuild-help = initramfs nach der Bereitstellung nicht neu bauen.
+param-strict-usb = Strikter USB-Modus
+param-strict-usb-help = USB-Token verlangen und die Ersatz-Passphrase während der Übung überspringen.
+param-pool-size = Größe des Test-Pools
+param-pool-size-help = Größe der Datei hinter dem Test-Pool (z. B. `512MiB`, mindestens 64MiB).
+param-keep-on-failure = Bei Fehler behalten
+param-keep-on-failure-help = Test-Pool und Datei dahinter behalten, wenn ein Schritt fehlschlägt.
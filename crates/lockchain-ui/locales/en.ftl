@@ -0,0 +1,395 @@
+# Control Deck strings, English. Every other catalog falls back to this one.
+
+language-system = System locale
+
+## Hosts
+
+host-local = This host
+host-selected = Target host: { $host }
+
+## Dataset panel
+
+datasets-heading = Managed Datasets
+datasets-heading-remote = Managed Datasets on { $host }
+datasets-loading = Loading…
+datasets-reading = Reading keystatus…
+datasets-empty = No datasets configured; add them to policy.datasets.
+key-state-unlocked = UNLOCKED
+key-state-locked = LOCKED
+key-state-unknown = UNKNOWN
+dataset-unlock = Unlock
+dataset-lock = Lock
+dataset-root = root { $root }
+dataset-working = WORKING…
+dataset-unlock-failed = Unlock { $dataset } failed: { $error }
+dataset-lock-failed = Lock { $dataset } failed: { $error }
+dataset-already-unlocked = { $root } was already unlocked
+dataset-already-locked = { $root } was already locked
+dataset-unlocked = Unlocked { $root } ({ $count ->
+    [one] 1 dataset
+   *[other] { $count } datasets
+})
+dataset-locked = Locked { $root } ({ $count ->
+    [one] 1 dataset
+   *[other] { $count } datasets
+})
+
+## Settings screen
+
+settings-heading = Settings
+nav-back = Back
+settings-loading = Loading the configuration…
+settings-load-failed = Could not load the config: { $error }
+settings-saved = Saved settings to { $path }
+settings-save-failed = Saving settings failed: { $error }
+settings-target-profile = { $path } (profile { $profile })
+settings-remove-dataset = Remove
+settings-add-dataset = Add dataset
+settings-usb = USB token
+settings-device-label = Device label
+settings-device-uuid = Device UUID
+any-value = (any)
+settings-datasets = Datasets
+settings-fallback = Fallback passphrase
+settings-fallback-enabled = Enabled
+settings-askpass = Prompt with askpass
+settings-askpass-path = Askpass path
+settings-pbkdf2-iterations = PBKDF2 iterations
+settings-retry = Unlock retries
+settings-max-attempts = Max attempts
+settings-base-delay = Base delay
+settings-max-delay = Max delay
+settings-jitter-ratio = Jitter ratio
+settings-strategy = Strategy
+settings-appearance = Appearance
+settings-theme = Theme
+settings-language = Language
+settings-reduced-motion = Reduced motion
+settings-hosts = Remote hosts
+settings-ssh-destinations = SSH destinations
+settings-saving = Saving…
+settings-save = Save
+
+## Dialogs
+
+dialog-cancel = Cancel
+guard-pick-device = Pick the USB token to wipe before forging.
+guard-wipe-title = Wipe USB token?
+guard-wipe-destroys = Everything on { $device } will be destroyed and replaced by new key material.
+guard-wipe-permanent = Key files already on it are gone for good.
+guard-type-answer = Type `{ $answer }` to continue:
+guard-wipe-confirm = Wipe and forge
+killswitch-title = Engage Killswitch?
+killswitch-scope-all = Unmount and unload the key of every configured encryption root.
+killswitch-scope-roots = Unmount and unload the key of { $count ->
+    [one] 1 encryption root
+   *[other] { $count } encryption roots
+}: { $roots }.
+killswitch-shred = Shred the runtime key file so nothing can unlock from it.
+killswitch-open-files = Open files on these datasets are lost to running programs; unlocking again needs the key token or the fallback passphrase.
+killswitch-stop-daemon = Also stop lockchain-zfs.service
+killswitch-confirm = Engage Killswitch
+
+## USB token picker
+
+devices-scanning = Scanning for USB tokens…
+devices-none = No removable devices found
+devices-select = Select the USB token to forge
+devices-rescan = Rescan
+devices-label = USB Token:
+
+## Directive runs
+
+run-label = Running { $directive } · { $count ->
+    [one] 1 event
+   *[other] { $count } events
+}
+
+## Header and footer
+
+header-title = Control Deck
+header-subtitle = Cryptographic ZFS key management — powered by LockChain
+header-subtitle-profile = Profile { $profile } — powered by LockChain
+mode-secure = SECURE
+mode-standard = STANDARD
+header-secure = Secure
+header-tray = Tray
+header-dashboard = Dashboard
+header-settings = Settings
+header-refresh = Refresh
+footer-total-events = Total Events: { $count }
+footer-status = Status: { $status }
+status-monitoring = Monitoring
+status-forge-complete = Forge complete
+status-check-diagnostics = Check diagnostics
+status-killswitch = Killswitch engaged
+
+## Directives
+
+directives-heading = Select Module Directive
+directive-new-key = New Key
+directive-new-key-safe = New Key (Safe mode)
+directive-self-test = Self-test
+directive-self-heal = Self-heal Issues
+directive-doctor = Doctor
+directive-new-key-summary = Forge fresh USB key material
+directive-new-key-safe-summary = Guided forge with confirmations
+directive-self-test-summary = Drill unlock + keystatus verification
+directive-self-heal-summary = Diagnose key material and dataset state
+directive-doctor-summary = Full system audit with one-click fixes
+directive-new-key-help = Forge a new 32-byte USB key onto the token picked above, seeding the fallback passphrase if one is set. Pick a dataset to target a specific encryption root.
+directive-new-key-safe-help = Safe forge checks the token instead of wiping it unless Force wipe is on. Pick the token above and a dataset as needed.
+directive-self-test-help = Provision a scratch encrypted pool, unlock it with the current key, then tear it down. Strict USB here or in the header skips fallback handling.
+directive-self-heal-help = Runs diagnostics against key file, checksum, and dataset keystatus.
+directive-doctor-help = Runs self-heal checks plus systemd/journal/initramfs audits without changing anything. Each remedy appears as a card, with Apply fix where the deck can repair it.
+dataset-none-configured = No dataset configured; add one to policy.datasets
+breakglass-card-title = Break-glass Recovery
+breakglass-card-summary = Guided key recovery from the fallback passphrase
+
+## Command panel
+
+command-heading = > { $directive } Parameters
+command-execute = Execute
+command-system-status = System Status: { $status }
+command-active-module = Active Module: { $directive }
+command-privileges-root = Privileges: root
+command-privileges-user = Privileges: user (elevates through pkexec)
+command-local-only = Directives and Killswitch act on this host, not { $host }
+command-self-test-unavailable = Self-test unavailable until a LockChain key is forged or inserted.
+command-help = Help
+command-killswitch = Killswitch
+command-shortcuts = Keys: ↑/↓ directive · Enter run/confirm · Esc cancel · Tab focus · Ctrl+L clear feed
+
+## Activity feed
+
+activity-heading = Runtime Activity Feed
+activity-export = Export log
+activity-exporting = Exporting…
+level-info = INFO
+level-success = SUCCESS
+level-warn = WARN
+level-error = ERROR
+level-secure = SECURE
+activity-online = Control Deck online. Select a directive to begin.
+activity-unprivileged = Running without root: directives and dataset actions will ask to run elevated through pkexec.
+activity-strict-on = Strict USB mode engaged.
+activity-strict-off = Strict USB mode disengaged.
+activity-tray-on = Tray mode on: closing the window hides it to the tray.
+activity-tray-off = Tray mode off: closing the window quits.
+activity-tray-unavailable = System tray unavailable ({ $error }); closing the window will quit.
+activity-needs-key = Forge or insert a LockChain key before running this directive.
+activity-cancelled = { $action } cancelled.
+activity-passphrase-set = Fallback passphrase set for the next forge.
+activity-passphrase-cleared = Fallback passphrase cleared.
+activity-complete = { $title } complete
+activity-executing = Executing { $directive }
+activity-killswitch = Killswitch engaged: locking every encryption root…
+activity-killswitch-failed = Killswitch failed: { $error }
+activity-self-heal = Running self-heal diagnostics…
+activity-refresh-only = Self-heal needs root; refreshing health and keystatus only.
+activity-feed-cleared = Feed cleared; the saved activity log keeps every entry
+activity-exported = Exported activity log to { $path }
+activity-export-failed = Export failed: { $error }
+activity-locked = Locked: { $datasets }
+activity-keystatus-failed = Keystatus query failed: { $error }
+activity-unlocking = Unlocking { $dataset }…
+activity-locking = Locking { $dataset }…
+activity-device-selected = Selected USB token { $device }
+activity-elevating = Elevating: { $command }
+activity-elevated-doctor = Elevated Doctor runs list their findings here rather than as cards.
+devices-scan-failed = Device scan failed: { $error }
+notify-dataset-locked = Dataset locked
+notify-unlock-failed = Unlock failed
+export-read-failed = failed to read { $path }: { $error }
+export-write-failed = failed to write { $path }: { $error }
+
+## Parameter forms
+
+form-optional = optional
+form-dataset-default = First entry in policy.datasets
+
+## Fallback passphrase
+
+passphrase-title = Set fallback passphrase
+passphrase-explainer = The next forge derives a recovery key from this passphrase. Keep it somewhere safe and offline.
+passphrase-placeholder = Passphrase
+passphrase-confirm-placeholder = Confirm passphrase
+passphrase-use = Use passphrase
+passphrase-strength = { $band } · ~{ $bits } bits
+passphrase-mismatch = Passphrases do not match.
+passphrase-status-set = Fallback passphrase: set for the next forge
+passphrase-status-unset = Fallback passphrase: not set
+passphrase-change = Change…
+passphrase-set = Set…
+passphrase-clear = Clear
+strength-weak = Weak
+strength-fair = Fair
+strength-good = Good
+strength-strong = Strong
+
+## Health chips
+
+health-chip-usb = USB
+health-chip-daemon = DAEMON
+health-chip-zfs = ZFS
+health-checking = Checking
+health-no-config = NO CONFIG
+health-key = KEY
+health-key-detail = Runtime key present at { $path }
+health-no-key = NO KEY
+health-no-key-detail = No runtime key at { $path }
+health-ready = READY
+health-ready-detail = Daemon reports ready
+health-degraded = DEGRADED
+health-daemon-degraded-detail = Daemon is running but reports degraded: { $components }
+health-running = RUNNING
+health-running-detail = Control socket { $socket } answers
+health-down = DOWN
+health-no-pools = NO POOLS
+health-no-pools-detail = No datasets configured
+health-error = ERROR
+health-online = ONLINE
+health-online-detail = Online on { $host }: { $pools }
+health-pools-degraded-detail = On { $host }: { $pools }
+
+## Doctor findings
+
+doctor-heading = Doctor Findings
+doctor-dismiss = Dismiss
+doctor-applying = Applying fix: { $fix }
+doctor-fix-failed = Fix failed: { $error }
+fix-repair = Re-run repair
+fix-tighten-permissions = Tighten permissions
+fix-rebuild-initramfs = Rebuild initramfs
+fix-apply-all = Apply all repairs
+fix-state-applying = APPLYING…
+fix-state-fixed = FIXED
+fix-state-failed = FAILED
+fix-state-manual = MANUAL
+
+## Running as root through pkexec
+
+elevate-title = Administrator rights needed
+elevate-explainer = The deck is running without root. { $action } needs it, so this command will run as root through pkexec:
+elevate-stdin-note = The passphrase is passed on standard input and does not appear in the process list.
+elevate-polkit-note = Your desktop's polkit agent will ask for an administrator password.
+elevate-confirm = Run as root
+elevate-summary-unlock = Unlock { $dataset }
+elevate-summary-unlock-remote = Unlock { $dataset } on { $host }
+elevate-summary-lock = Lock { $dataset }
+elevate-summary-lock-all = Lock every managed dataset
+elevate-summary-breakglass = Break-glass recovery of { $dataset }
+elevate-no-cli = { $directive } has no CLI equivalent to run through pkexec; start the deck as root to use it.
+elevate-stop-daemon = Stopping the daemon needs the deck running as root; untick it to lock every dataset through pkexec.
+elevate-no-pkexec = pkexec is not installed; start the deck as root to run this action
+elevate-start-failed = failed to start pkexec: { $error }
+elevate-stdin-failed = failed to pass the passphrase to pkexec: { $error }
+elevate-wait-failed = failed to wait for pkexec: { $error }
+elevate-read-failed = failed to read from pkexec: { $error }
+elevate-dismissed = Authorization dialog dismissed; nothing ran
+elevate-denied = Not authorized to run { $cli } as root
+elevate-failed = { $action } failed ({ $status })
+
+## Dashboard
+
+history-heading = Dataset History
+history-reload = Reload
+history-day = Last 24 hours
+history-week = Last 7 days
+history-month = Last 30 days
+history-loading = Reading the audit trail…
+history-empty = No unlocks or locks recorded in this window.
+history-read-failed = could not read { $path }: { $error }
+history-legend-unlocked = unlocked
+history-legend-locked = locked
+history-legend-failed = failed
+history-legend-slow = unlock ≥ { $threshold }
+history-flapping = FLAPPING
+history-slow = SLOW
+history-latency = median unlock { $median } · slowest { $slowest }
+history-no-timings = no unlock timings recorded
+history-stats = { $timed ->
+    [one] 1 unlock timed
+   *[other] { $timed } unlocks timed
+} · { $latency } · { $transitions ->
+    [one] 1 state change
+   *[other] { $transitions } state changes
+} · { $failures ->
+    [one] 1 failure
+   *[other] { $failures } failures
+}
+
+## First-run wizard
+
+wizard-heading = First-run setup
+wizard-step = Step { $current } of { $total } · { $title }
+wizard-step-datasets = Datasets
+wizard-step-token = USB token
+wizard-step-fallback = Fallback policy
+wizard-step-review = Review
+wizard-skip = Skip
+wizard-next = Next
+wizard-write = Write config
+wizard-writing = Writing…
+wizard-datasets-intro = No config was found at { $path }. Choose the encryption roots LockChain should unlock.
+wizard-discovering = Asking ZFS for encryption roots…
+wizard-discovery-failed = Could not query ZFS ({ $error }); enter datasets below.
+wizard-no-roots = No encrypted datasets detected; enter them below.
+wizard-extra-datasets = Other datasets, comma-separated
+wizard-token-intro = Pick the removable disk that will hold the key. Nothing is written until you run New Key.
+wizard-token-label = Token label:
+wizard-token-label-note = The daemon waits for a partition with this label. Forging formats the token as { $label }.
+wizard-fallback-intro = A fallback passphrase can recover the key if the token is lost. Its material is derived when the key is forged.
+wizard-seed-fallback = Set a fallback passphrase for the first forge
+wizard-askpass = Prompt for it with askpass at boot when the token is missing
+wizard-review-config = Config: { $path }
+wizard-review-datasets = Datasets: { $datasets }
+wizard-review-label = Token label: { $label }
+wizard-review-token = Token for New Key: { $token }
+wizard-review-no-token = none picked yet
+wizard-review-fallback-seeded = Fallback: passphrase asked for now, seeded by the first forge
+wizard-review-fallback-disabled = Fallback: disabled
+wizard-config-exists = { $path } already exists; open Settings to edit it
+wizard-create-failed = failed to create { $path }: { $error }
+wizard-wrote = Wrote { $path }
+wizard-next-steps = Next: forge a key onto the USB token with New Key.
+wizard-write-failed = Writing the config failed: { $error }
+wizard-skipped = Setup skipped; directives need a config at { $path }.
+
+## Break-glass recovery
+
+breakglass-step-warnings = 1 · Before you start
+breakglass-step-dataset = 2 · Dataset
+breakglass-step-passphrase = 3 · Fallback passphrase
+breakglass-step-destination = 4 · Destination
+breakglass-step-done = Recovered
+breakglass-close = Close
+breakglass-recover = Recover key
+breakglass-recovering = Recovering…
+breakglass-loading = Reading the config…
+breakglass-disabled = Fallback recovery is not enabled in this configuration.
+breakglass-incomplete = The fallback configuration is incomplete (salt/xor missing).
+breakglass-warning-derives = Break-glass recovery derives a dataset's raw encryption key from the fallback passphrase. Use it only when the USB token is lost or broken.
+breakglass-warning-exposure = Anyone holding the derived key can unlock the dataset. Shred key files as soon as they have been used.
+breakglass-warning-audit = Every attempt, successful or not, is recorded in the audit log.
+breakglass-acknowledge = I understand the key is being exposed and take responsibility for it
+breakglass-pick-dataset = Pick the dataset whose key to recover.
+breakglass-type-dataset = Type `{ $dataset }` to confirm:
+breakglass-passphrase = Fallback passphrase
+breakglass-passphrase-intro = Enter the passphrase chosen when this key was forged. It is never shown or written to the activity log.
+breakglass-destination-intro = Where should the derived key go?
+breakglass-destination-file = Write it to a key file
+breakglass-destination-display = Display it once and never write it
+breakglass-key-file = Key file
+breakglass-file-note = The file is created with permissions 0400; remember to shred it when finished.
+breakglass-display-note = The key appears on the next screen as hex and is gone once you close it; nothing touches the disk or the activity log.
+breakglass-copy-now = Copy the key now. It is shown only until this screen is closed.
+breakglass-digest = SHA-256 { $digest }
+breakglass-started = Break-glass recovery started for { $dataset }
+breakglass-failed = Break-glass recovery failed: { $error }
+breakglass-displayed = Break-glass key for { $dataset } displayed once (SHA-256 { $digest }); nothing was written.
+breakglass-bad-length = expected 32 bytes of key material, got { $length }
+
+## Directive parameters
+## Labels and help come from the schema lockchain-core shares with the CLI;
+## other catalogs translate them as param-<name> and param-<name>-help.
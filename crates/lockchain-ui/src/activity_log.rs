@@ -0,0 +1,230 @@
+//! Persistent activity feed: every entry is appended to a JSON-lines file
+//! that rotates by size, the recent tail is replayed on startup, and the
+//! whole log can be exported as JSON or CSV for audits.
+
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, SecondsFormat};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::t;
+use crate::{ActivityItem, ActivityLevel};
+
+/// Overrides where the feed is persisted.
+const LOG_PATH_ENV: &str = "LOCKCHAIN_UI_LOG";
+const DEFAULT_LOG_PATH: &str = "/var/lib/lockchain/control-deck.jsonl";
+/// Size at which the active file is rotated to `.1`.
+const MAX_BYTES: u64 = 1024 * 1024;
+/// Rotated files kept besides the active one.
+const MAX_FILES: u32 = 3;
+/// Entries replayed into the feed on startup.
+pub(crate) const TAIL_ENTRIES: usize = 200;
+
+/// One line of the log file and one row of an export.
+#[derive(Debug, Serialize, Deserialize)]
+struct LogRecord {
+    timestamp: String,
+    level: ActivityLevel,
+    message: String,
+}
+
+impl From<&ActivityItem> for LogRecord {
+    fn from(item: &ActivityItem) -> Self {
+        Self {
+            timestamp: item.timestamp.to_rfc3339_opts(SecondsFormat::Millis, false),
+            level: item.level,
+            message: item.message.clone(),
+        }
+    }
+}
+
+impl LogRecord {
+    fn into_item(self) -> Option<ActivityItem> {
+        let timestamp = DateTime::parse_from_rfc3339(&self.timestamp).ok()?;
+        Some(ActivityItem {
+            timestamp: timestamp.with_timezone(&Local),
+            level: self.level,
+            message: self.message,
+        })
+    }
+}
+
+/// File formats offered by "Export log".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    pub(crate) const ALL: [ExportFormat; 2] = [ExportFormat::Json, ExportFormat::Csv];
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ExportFormat::Json => "JSON",
+            ExportFormat::Csv => "CSV",
+        })
+    }
+}
+
+/// The on-disk feed.
+#[derive(Debug, Clone)]
+pub(crate) struct ActivityLog {
+    path: PathBuf,
+}
+
+impl ActivityLog {
+    /// Log at `$LOCKCHAIN_UI_LOG`, or next to the audit trail.
+    pub(crate) fn from_env() -> Self {
+        let path = std::env::var_os(LOG_PATH_ENV)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_LOG_PATH));
+        Self { path }
+    }
+
+    /// Append `item`, rotating first when the active file is full.
+    pub(crate) fn append(&self, item: &ActivityItem) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if fs::metadata(&self.path).is_ok_and(|meta| meta.len() >= MAX_BYTES) {
+            self.rotate()?;
+        }
+        let mut line = serde_json::to_string(&LogRecord::from(item)).map_err(io::Error::other)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(&self.path)?
+            .write_all(line.as_bytes())
+    }
+
+    /// The newest `limit` entries, oldest first. Unreadable lines are skipped.
+    pub(crate) fn tail(&self, limit: usize) -> Vec<ActivityItem> {
+        let mut items: Vec<ActivityItem> = self
+            .records()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(LogRecord::into_item)
+            .collect();
+        let excess = items.len().saturating_sub(limit);
+        items.drain(..excess);
+        items
+    }
+
+    /// Shift `control-deck.jsonl` → `.1` → … → `.MAX_FILES`, dropping the oldest.
+    fn rotate(&self) -> io::Result<()> {
+        let _ = fs::remove_file(rotated_path(&self.path, MAX_FILES));
+        for idx in (1..MAX_FILES).rev() {
+            let from = rotated_path(&self.path, idx);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, idx + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))
+    }
+
+    /// Every record across the rotated files, oldest first.
+    fn records(&self) -> io::Result<Vec<LogRecord>> {
+        let mut files: Vec<PathBuf> = (1..=MAX_FILES)
+            .map(|idx| rotated_path(&self.path, idx))
+            .filter(|candidate| candidate.exists())
+            .collect();
+        files.reverse();
+        if self.path.exists() {
+            files.push(self.path.clone());
+        }
+
+        let mut records = Vec::new();
+        for file in files {
+            for line in BufReader::new(File::open(&file)?).lines() {
+                if let Ok(record) = serde_json::from_str(&line?) {
+                    records.push(record);
+                }
+            }
+        }
+        Ok(records)
+    }
+}
+
+fn rotated_path(path: &Path, idx: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{idx}"));
+    PathBuf::from(name)
+}
+
+/// Write the whole persisted log, rotated files included, to a timestamped
+/// file beside it and return that file's path.
+pub(crate) async fn export(log: ActivityLog, format: ExportFormat) -> Result<PathBuf, String> {
+    let records = log.records().map_err(|err| {
+        t!(
+            "export-read-failed",
+            path = log.path.display().to_string(),
+            error = err.to_string()
+        )
+    })?;
+    let body = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&records).map_err(|e| e.to_string())?,
+        ExportFormat::Csv => {
+            let mut csv = String::from("timestamp,level,message\n");
+            for record in &records {
+                csv.push_str(&format!(
+                    "{},{},{}\n",
+                    record.timestamp,
+                    record.level.tag(),
+                    csv_field(&record.message)
+                ));
+            }
+            csv
+        }
+    };
+
+    let name = format!(
+        "lockchain-activity-{}.{}",
+        Local::now().format("%Y%m%d-%H%M%S"),
+        format.extension()
+    );
+    let target = log
+        .path
+        .parent()
+        .map(|dir| dir.join(&name))
+        .unwrap_or_else(|| PathBuf::from(&name));
+    OpenOptions::new()
+        .create_new(true)
+        .write(true)
+        .mode(0o600)
+        .open(&target)
+        .and_then(|mut file| file.write_all(body.as_bytes()))
+        .map_err(|err| {
+            t!(
+                "export-write-failed",
+                path = target.display().to_string(),
+                error = err.to_string()
+            )
+        })?;
+    Ok(target)
+}
+
+/// Quote `value` for CSV when it holds a separator, quote, or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,618 @@
+//! Break-glass recovery as a guided screen: the warnings, the dataset
+//! confirmed by name, the fallback passphrase, and where the derived key
+//! goes, including showing it once without writing it anywhere.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use iced::alignment::Vertical;
+use iced::widget::{button, checkbox, column, container, pick_list, radio, row, text, text_input};
+use iced::widget::{Column, Space};
+use iced::{Font, Length, Task};
+use lockchain_core::config::LockchainConfig;
+use lockchain_core::service::LockchainService;
+use lockchain_core::workflow::{self, WorkflowEvent, WorkflowLevel, WorkflowReport};
+use lockchain_zfs::SystemZfsProvider;
+use sha2::{Digest, Sha256};
+
+use crate::i18n::t;
+use crate::passphrase::Secret;
+use crate::privilege::{self, ElevatedAction, ElevatedCommand};
+use crate::theme::palette;
+use crate::{
+    default_recovery_path, help_button, killswitch_button, panel_style, primary_button, text_color,
+    text_input_style, ActivityLevel, LockchainUi, Message,
+};
+
+/// Where the derived key goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Destination {
+    /// A 0400 raw key file.
+    File,
+    /// Shown on this screen until it is closed; never written.
+    DisplayOnce,
+}
+
+impl fmt::Display for Destination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&match self {
+            Destination::File => t!("breakglass-destination-file"),
+            Destination::DisplayOnce => t!("breakglass-destination-display"),
+        })
+    }
+}
+
+/// Steps of the flow, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Warnings,
+    Dataset,
+    Passphrase,
+    Destination,
+    Done,
+}
+
+impl Step {
+    fn title(self) -> String {
+        match self {
+            Step::Warnings => t!("breakglass-step-warnings"),
+            Step::Dataset => t!("breakglass-step-dataset"),
+            Step::Passphrase => t!("breakglass-step-passphrase"),
+            Step::Destination => t!("breakglass-step-destination"),
+            Step::Done => t!("breakglass-step-done"),
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Step::Warnings => Step::Dataset,
+            Step::Dataset => Step::Passphrase,
+            Step::Passphrase => Step::Destination,
+            Step::Destination | Step::Done => Step::Done,
+        }
+    }
+
+    fn previous(self) -> Self {
+        match self {
+            Step::Warnings | Step::Dataset => Step::Warnings,
+            Step::Passphrase => Step::Dataset,
+            Step::Destination | Step::Done => Step::Passphrase,
+        }
+    }
+}
+
+/// What the recovery produced.
+#[derive(Debug, Clone)]
+pub(crate) enum Recovered {
+    Written(WorkflowReport),
+    /// Hex key and its SHA-256, held only by this screen.
+    Shown {
+        key: Secret,
+        digest: String,
+    },
+}
+
+/// Events from the break-glass screen.
+#[derive(Debug, Clone)]
+pub(crate) enum BreakGlassMessage {
+    Open,
+    Close,
+    Loaded(Result<Box<LockchainConfig>, String>),
+    Acknowledge(bool),
+    Dataset(String),
+    Typed(String),
+    Passphrase(Secret),
+    Destination(Destination),
+    Output(String),
+    Back,
+    Next,
+    Recover,
+    Recovered(Result<Recovered, String>),
+}
+
+/// State of the open break-glass screen.
+#[derive(Debug)]
+pub(crate) struct BreakGlass {
+    step: Step,
+    config: Option<Result<Box<LockchainConfig>, String>>,
+    acknowledged: bool,
+    dataset: Option<String>,
+    /// The dataset name retyped as confirmation.
+    typed: String,
+    passphrase: Secret,
+    destination: Destination,
+    output: String,
+    running: bool,
+    error: Option<String>,
+    recovered: Option<Recovered>,
+}
+
+impl BreakGlass {
+    fn new() -> Self {
+        Self {
+            step: Step::Warnings,
+            config: None,
+            acknowledged: false,
+            dataset: None,
+            typed: String::new(),
+            passphrase: Secret::default(),
+            destination: Destination::File,
+            output: String::new(),
+            running: false,
+            error: None,
+            recovered: None,
+        }
+    }
+
+    /// Why recovery cannot work with this config, if it cannot.
+    fn blocker(&self) -> Option<String> {
+        match &self.config {
+            None => Some(t!("breakglass-loading")),
+            Some(Err(err)) => Some(t!("settings-load-failed", error = err.as_str())),
+            Some(Ok(config)) if !config.fallback.enabled => Some(t!("breakglass-disabled")),
+            Some(Ok(config))
+                if config.fallback.passphrase_salt.is_none()
+                    || config.fallback.passphrase_xor.is_none() =>
+            {
+                Some(t!("breakglass-incomplete"))
+            }
+            Some(Ok(_)) => None,
+        }
+    }
+
+    fn datasets(&self) -> Vec<String> {
+        match &self.config {
+            Some(Ok(config)) => config.policy.datasets.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The current step is complete.
+    fn can_advance(&self) -> bool {
+        match self.step {
+            Step::Warnings => self.acknowledged && self.blocker().is_none(),
+            Step::Dataset => self
+                .dataset
+                .as_deref()
+                .is_some_and(|dataset| self.typed.trim() == dataset),
+            Step::Passphrase => !self.passphrase.is_empty(),
+            Step::Destination => {
+                self.destination == Destination::DisplayOnce || !self.output.trim().is_empty()
+            }
+            Step::Done => false,
+        }
+    }
+
+    /// The key file path, `None` when the key is only displayed.
+    fn output_path(&self) -> Option<PathBuf> {
+        (self.destination == Destination::File).then(|| PathBuf::from(self.output.trim()))
+    }
+}
+
+async fn load(
+    config_path: PathBuf,
+    profile: Option<String>,
+) -> Result<Box<LockchainConfig>, String> {
+    LockchainConfig::load_profile(&config_path, profile.as_deref())
+        .map(Box::new)
+        .map_err(|e| e.to_string())
+}
+
+/// Derive the key in-process and send it to `output`, or hand it back for
+/// display when there is none.
+async fn recover(
+    config_path: PathBuf,
+    profile: Option<String>,
+    dataset: String,
+    passphrase: Secret,
+    output: Option<PathBuf>,
+) -> Result<Recovered, String> {
+    let config = LockchainConfig::load_profile(&config_path, profile.as_deref())
+        .map_err(|e| e.to_string())?;
+    let provider = SystemZfsProvider::from_config(&config).map_err(|e| e.to_string())?;
+    let passphrase = passphrase.expose().as_bytes();
+    match output {
+        Some(output) => workflow::recover_key(&config, provider, &dataset, passphrase, &output)
+            .map(Recovered::Written)
+            .map_err(|e| e.to_string()),
+        None => {
+            let key = LockchainService::new(Arc::new(config), provider)
+                .break_glass(&dataset, passphrase)
+                .map_err(|e| e.to_string())?;
+            Ok(shown(&key))
+        }
+    }
+}
+
+/// Run `command` (`lockchain-cli breakglass`) through pkexec.
+async fn recover_elevated(
+    command: ElevatedCommand,
+    destination: Destination,
+) -> Result<Recovered, String> {
+    match destination {
+        Destination::File => privilege::run(&command, |_| {}).map(Recovered::Written),
+        Destination::DisplayOnce => {
+            let key = privilege::capture(&command)?;
+            if key.len() != 32 {
+                return Err(t!("breakglass-bad-length", length = key.len()));
+            }
+            Ok(shown(&key))
+        }
+    }
+}
+
+fn shown(key: &[u8]) -> Recovered {
+    Recovered::Shown {
+        key: Secret::from(hex::encode(key)),
+        digest: hex::encode(Sha256::digest(key)),
+    }
+}
+
+impl LockchainUi {
+    pub(crate) fn update_breakglass(&mut self, message: BreakGlassMessage) -> Task<Message> {
+        if let BreakGlassMessage::Open = message {
+            self.settings_open = false;
+            self.settings = None;
+            self.dashboard = None;
+            self.breakglass = Some(BreakGlass::new());
+            return Task::perform(
+                load(self.config_path.clone(), self.profile.clone()),
+                |result| Message::BreakGlass(BreakGlassMessage::Loaded(result)),
+            );
+        }
+        if let BreakGlassMessage::Close = message {
+            if self.breakglass.as_ref().is_some_and(|flow| !flow.running) {
+                self.breakglass = None;
+            }
+            return Task::none();
+        }
+        let Some(flow) = &mut self.breakglass else {
+            return Task::none();
+        };
+
+        match message {
+            BreakGlassMessage::Loaded(result) => {
+                if let Ok(config) = &result {
+                    flow.dataset = config.policy.datasets.first().cloned();
+                }
+                flow.config = Some(result);
+            }
+            BreakGlassMessage::Acknowledge(state) => flow.acknowledged = state,
+            BreakGlassMessage::Dataset(dataset) => {
+                flow.dataset = Some(dataset);
+                flow.typed.clear();
+            }
+            BreakGlassMessage::Typed(value) => flow.typed = value,
+            BreakGlassMessage::Passphrase(value) => flow.passphrase = value,
+            BreakGlassMessage::Destination(destination) => flow.destination = destination,
+            BreakGlassMessage::Output(value) => flow.output = value,
+            BreakGlassMessage::Back => {
+                if !flow.running && flow.step != Step::Done {
+                    flow.step = flow.step.previous();
+                    flow.error = None;
+                }
+            }
+            BreakGlassMessage::Next => {
+                if flow.can_advance() && flow.step != Step::Destination {
+                    flow.step = flow.step.next();
+                    if flow.step == Step::Destination && flow.output.is_empty() {
+                        if let Some(dataset) = &flow.dataset {
+                            flow.output = default_recovery_path(dataset).display().to_string();
+                        }
+                    }
+                }
+            }
+            BreakGlassMessage::Recover => return self.start_breakglass(),
+            BreakGlassMessage::Recovered(result) => {
+                flow.running = false;
+                match result {
+                    Ok(recovered) => {
+                        flow.step = Step::Done;
+                        flow.passphrase = Secret::default();
+                        let dataset = flow.dataset.clone().unwrap_or_default();
+                        let events = match &recovered {
+                            Recovered::Written(report) => report.events.clone(),
+                            Recovered::Shown { digest, .. } => vec![WorkflowEvent {
+                                level: WorkflowLevel::Security,
+                                message: t!(
+                                    "breakglass-displayed",
+                                    dataset = dataset,
+                                    digest = digest.as_str()
+                                ),
+                            }],
+                        };
+                        flow.recovered = Some(recovered);
+                        self.ingest_events(events);
+                    }
+                    Err(err) => {
+                        flow.error = Some(err.clone());
+                        self.push_activity(
+                            ActivityLevel::Error,
+                            t!("breakglass-failed", error = err),
+                        );
+                    }
+                }
+            }
+            BreakGlassMessage::Open | BreakGlassMessage::Close => {}
+        }
+        Task::none()
+    }
+
+    /// Recover in-process, or ask to run the CLI elevated without root.
+    fn start_breakglass(&mut self) -> Task<Message> {
+        let Some(flow) = &mut self.breakglass else {
+            return Task::none();
+        };
+        if flow.step != Step::Destination || !flow.can_advance() || flow.running {
+            return Task::none();
+        }
+        let Some(dataset) = flow.dataset.clone() else {
+            return Task::none();
+        };
+        let output = flow.output_path();
+        flow.error = None;
+
+        if !self.privileged {
+            let command = privilege::breakglass_command(
+                &self.config_path,
+                self.profile.as_deref(),
+                &dataset,
+                output.as_deref(),
+                flow.passphrase.clone(),
+            );
+            let destination = flow.destination;
+            self.request_elevation(ElevatedAction::BreakGlass(destination), Ok(command));
+            return Task::none();
+        }
+
+        flow.running = true;
+        let passphrase = flow.passphrase.clone();
+        self.push_activity(
+            ActivityLevel::Security,
+            t!("breakglass-started", dataset = dataset.as_str()),
+        );
+        Task::perform(
+            recover(
+                self.config_path.clone(),
+                self.profile.clone(),
+                dataset,
+                passphrase,
+                output,
+            ),
+            |result| Message::BreakGlass(BreakGlassMessage::Recovered(result)),
+        )
+    }
+
+    /// Run the approved elevated recovery.
+    pub(crate) fn breakglass_elevated(
+        &mut self,
+        command: ElevatedCommand,
+        destination: Destination,
+    ) -> Task<Message> {
+        let Some(flow) = &mut self.breakglass else {
+            return Task::none();
+        };
+        flow.running = true;
+        Task::perform(recover_elevated(command, destination), |result| {
+            Message::BreakGlass(BreakGlassMessage::Recovered(result))
+        })
+    }
+
+    /// The screen for the current step, shown in place of the deck body.
+    pub(crate) fn view_breakglass<'a>(&self, flow: &'a BreakGlass) -> iced::Element<'a, Message> {
+        let mut close = button(text(if flow.step == Step::Done {
+            t!("breakglass-close")
+        } else {
+            t!("dialog-cancel")
+        }))
+        .padding([10, 16])
+        .style(help_button());
+        if !flow.running {
+            close = close.on_press(Message::BreakGlass(BreakGlassMessage::Close));
+        }
+        let heading = row![
+            text(t!("breakglass-card-title"))
+                .size(18)
+                .style(text_color(palette().heading)),
+            Space::with_width(Length::Fill),
+            text(flow.step.title())
+                .size(14)
+                .style(text_color(palette().info)),
+            close
+        ]
+        .spacing(12)
+        .align_y(Vertical::Center);
+
+        let body = match flow.step {
+            Step::Warnings => view_warnings(flow),
+            Step::Dataset => view_dataset(flow),
+            Step::Passphrase => view_passphrase(flow),
+            Step::Destination => view_destination(flow),
+            Step::Done => view_done(flow),
+        };
+        let mut body = body.spacing(12);
+        if let Some(err) = &flow.error {
+            body = body.push(text(err).size(14).style(text_color(palette().danger)));
+        }
+
+        let mut footer = row![Space::with_width(Length::Fill)].spacing(12);
+        if !matches!(flow.step, Step::Warnings | Step::Done) {
+            let mut back = button(text(t!("nav-back")))
+                .padding([10, 16])
+                .style(help_button());
+            if !flow.running {
+                back = back.on_press(Message::BreakGlass(BreakGlassMessage::Back));
+            }
+            footer = footer.push(back);
+        }
+        match flow.step {
+            Step::Destination => {
+                let mut recover = button(text(if flow.running {
+                    t!("breakglass-recovering")
+                } else {
+                    t!("breakglass-recover")
+                }))
+                .padding([10, 16])
+                .style(killswitch_button());
+                if flow.can_advance() && !flow.running {
+                    recover = recover.on_press(Message::BreakGlass(BreakGlassMessage::Recover));
+                }
+                footer = footer.push(recover);
+            }
+            Step::Done => {}
+            _ => {
+                let mut next = button(text(t!("wizard-next")))
+                    .padding([10, 16])
+                    .style(primary_button());
+                if flow.can_advance() {
+                    next = next.on_press(Message::BreakGlass(BreakGlassMessage::Next));
+                }
+                footer = footer.push(next);
+            }
+        }
+
+        container(column![heading, body, Space::with_height(Length::Fill), footer].spacing(16))
+            .padding(20)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(panel_style())
+            .into()
+    }
+}
+
+fn line<'a>(message: impl ToString, color: iced::Color) -> iced::Element<'a, Message> {
+    text(message.to_string())
+        .size(14)
+        .style(text_color(color))
+        .into()
+}
+
+fn view_warnings(flow: &BreakGlass) -> Column<'_, Message> {
+    let mut body = column![
+        line(t!("breakglass-warning-derives"), palette().text),
+        line(t!("breakglass-warning-exposure"), palette().warning),
+        line(t!("breakglass-warning-audit"), palette().info),
+    ];
+    match flow.blocker() {
+        Some(blocker) => body = body.push(line(blocker, palette().danger)),
+        None => {
+            body = body.push(
+                checkbox(t!("breakglass-acknowledge"), flow.acknowledged)
+                    .on_toggle(|state| Message::BreakGlass(BreakGlassMessage::Acknowledge(state)))
+                    .text_size(14),
+            )
+        }
+    }
+    body
+}
+
+fn view_dataset(flow: &BreakGlass) -> Column<'_, Message> {
+    let mut body = column![
+        line(t!("breakglass-pick-dataset"), palette().text),
+        pick_list(flow.datasets(), flow.dataset.clone(), |dataset| {
+            Message::BreakGlass(BreakGlassMessage::Dataset(dataset))
+        })
+        .text_size(14),
+    ];
+    if let Some(dataset) = &flow.dataset {
+        body = body
+            .push(line(
+                t!("breakglass-type-dataset", dataset = dataset.as_str()),
+                palette().warning,
+            ))
+            .push(
+                text_input(dataset, &flow.typed)
+                    .on_input(|value| Message::BreakGlass(BreakGlassMessage::Typed(value)))
+                    .size(16)
+                    .padding(10)
+                    .style(text_input_style()),
+            );
+    }
+    body
+}
+
+fn view_passphrase(flow: &BreakGlass) -> Column<'_, Message> {
+    let mut input = text_input(&t!("breakglass-passphrase"), flow.passphrase.expose())
+        .secure(true)
+        .on_input(|value| Message::BreakGlass(BreakGlassMessage::Passphrase(Secret::from(value))))
+        .size(16)
+        .padding(10)
+        .style(text_input_style());
+    if flow.can_advance() {
+        input = input.on_submit(Message::BreakGlass(BreakGlassMessage::Next));
+    }
+    column![
+        line(t!("breakglass-passphrase-intro"), palette().text),
+        input,
+    ]
+}
+
+fn view_destination(flow: &BreakGlass) -> Column<'_, Message> {
+    let choice = |destination: Destination| {
+        radio(
+            destination.to_string(),
+            destination,
+            Some(flow.destination),
+            |destination| Message::BreakGlass(BreakGlassMessage::Destination(destination)),
+        )
+        .text_size(14)
+    };
+    let mut body = column![
+        line(t!("breakglass-destination-intro"), palette().text),
+        choice(Destination::File),
+    ];
+    if flow.destination == Destination::File {
+        body = body.push(
+            row![
+                text(t!("breakglass-key-file"))
+                    .size(14)
+                    .style(text_color(palette().success)),
+                text_input("/var/lib/lockchain/<dataset>_<time>.key", &flow.output)
+                    .on_input(|value| Message::BreakGlass(BreakGlassMessage::Output(value)))
+                    .size(14)
+                    .padding(8)
+                    .style(text_input_style())
+            ]
+            .spacing(12)
+            .align_y(Vertical::Center),
+        );
+    }
+    body = body.push(choice(Destination::DisplayOnce));
+    body.push(line(
+        match flow.destination {
+            Destination::File => t!("breakglass-file-note"),
+            Destination::DisplayOnce => t!("breakglass-display-note"),
+        },
+        palette().info,
+    ))
+}
+
+fn view_done(flow: &BreakGlass) -> Column<'_, Message> {
+    match &flow.recovered {
+        Some(Recovered::Shown { key, digest }) => column![
+            line(t!("breakglass-copy-now"), palette().warning),
+            container(
+                text(key.expose())
+                    .size(16)
+                    .font(Font::MONOSPACE)
+                    .style(text_color(palette().success))
+            )
+            .padding(12)
+            .width(Length::Fill)
+            .style(panel_style()),
+            line(
+                t!("breakglass-digest", digest = digest.as_str()),
+                palette().info
+            ),
+        ],
+        Some(Recovered::Written(report)) => {
+            let mut body = column![line(&report.title, palette().success)];
+            for event in &report.events {
+                body = body.push(line(&event.message, palette().text));
+            }
+            body
+        }
+        None => column![],
+    }
+}
@@ -0,0 +1,110 @@
+//! Typed confirmations for directives that destroy data, mirroring the CLI:
+//! token wipes need the device name.
+
+use iced::widget::{button, column, container, row, text, text_input, Space};
+use iced::Length;
+
+use crate::i18n::t;
+use crate::theme::palette;
+use crate::{
+    dialog_style, help_button, killswitch_button, text_color, text_input_style, Directive,
+    LockchainUi, Message,
+};
+
+/// A directive held back until the operator types `answer`.
+#[derive(Debug, Clone)]
+pub(crate) struct GuardedAction {
+    pub(crate) directive: Directive,
+    title: String,
+    lines: Vec<String>,
+    answer: String,
+    pub(crate) typed: String,
+}
+
+impl GuardedAction {
+    pub(crate) fn confirmed(&self) -> bool {
+        self.typed.trim() == self.answer
+    }
+}
+
+impl LockchainUi {
+    /// The confirmation `directive` needs before it runs, `Ok(None)` when it
+    /// is not destructive, or why it cannot run yet.
+    pub(crate) fn guard_for(&self, directive: Directive) -> Result<Option<GuardedAction>, String> {
+        let params = self.params();
+        match directive {
+            Directive::NewKey | Directive::NewKeySafe => {
+                let wipes = directive == Directive::NewKey || params.flag("force_wipe");
+                if !wipes {
+                    return Ok(None);
+                }
+                let device = self
+                    .selected_device
+                    .as_ref()
+                    .map(|d| d.path.clone())
+                    .ok_or_else(|| t!("guard-pick-device"))?;
+                Ok(Some(GuardedAction {
+                    directive,
+                    title: t!("guard-wipe-title"),
+                    lines: vec![
+                        t!("guard-wipe-destroys", device = device.as_str()),
+                        t!("guard-wipe-permanent"),
+                    ],
+                    answer: device,
+                    typed: String::new(),
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Dialog asking for the typed answer.
+    pub(crate) fn view_guard_dialog<'a>(
+        &self,
+        guard: &'a GuardedAction,
+    ) -> iced::Element<'a, Message> {
+        let mut body = column![text(&guard.title)
+            .size(22)
+            .style(text_color(palette().subheading))]
+        .spacing(14);
+        for line in &guard.lines {
+            body = body.push(text(line).size(14).style(text_color(palette().text)));
+        }
+        body = body.push(
+            text(t!("guard-type-answer", answer = guard.answer.as_str()))
+                .size(14)
+                .style(text_color(palette().warning)),
+        );
+
+        let mut input = text_input(&guard.answer, &guard.typed)
+            .on_input(Message::GuardInput)
+            .size(16)
+            .padding(10)
+            .style(text_input_style());
+        let mut proceed = button(text(t!("guard-wipe-confirm")))
+            .padding([10, 16])
+            .style(killswitch_button());
+        if guard.confirmed() {
+            input = input.on_submit(Message::GuardConfirm);
+            proceed = proceed.on_press(Message::GuardConfirm);
+        }
+
+        container(
+            body.push(input).push(
+                row![
+                    Space::with_width(Length::Fill),
+                    button(text(t!("dialog-cancel")))
+                        .padding([10, 16])
+                        .style(help_button())
+                        .on_press(Message::GuardCancel),
+                    proceed
+                ]
+                .spacing(12),
+            ),
+        )
+        .padding(24)
+        .max_width(560)
+        .style(dialog_style())
+        .into()
+    }
+}
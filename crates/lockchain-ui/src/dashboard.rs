@@ -0,0 +1,337 @@
+//! Dashboard: unlock latency and lock-state history per dataset, read from
+//! the audit trail and drawn as bar sparklines so flapping tokens and slow
+//! pools stand out.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::Utc;
+use iced::alignment::Vertical;
+use iced::widget::{button, column, container, pick_list, row, scrollable, text, Space};
+use iced::{Length, Task};
+use lockchain_core::audit::{self, AuditEvent, DatasetHistory, HistorySample};
+use lockchain_core::config::LockchainConfig;
+use lockchain_core::units::format_duration;
+
+use crate::i18n::t;
+use crate::theme::palette;
+use crate::{help_button, panel_style, primary_button, text_color, LockchainUi, Message};
+
+/// Unlocks slower than this mark the dataset as slow.
+const SLOW_UNLOCK: Duration = Duration::from_secs(5);
+/// State changes per day at which a dataset counts as flapping.
+const FLAP_TRANSITIONS_PER_DAY: usize = 6;
+/// Most recent samples drawn in each sparkline.
+const SPARK_SAMPLES: usize = 60;
+const SPARK_HEIGHT: f32 = 36.0;
+
+const WINDOWS: [HistoryWindow; 3] = [
+    HistoryWindow::Day,
+    HistoryWindow::Week,
+    HistoryWindow::Month,
+];
+
+/// How far back the dashboard looks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HistoryWindow {
+    Day,
+    Week,
+    Month,
+}
+
+impl HistoryWindow {
+    fn days(self) -> usize {
+        match self {
+            HistoryWindow::Day => 1,
+            HistoryWindow::Week => 7,
+            HistoryWindow::Month => 30,
+        }
+    }
+}
+
+impl fmt::Display for HistoryWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&match self {
+            HistoryWindow::Day => t!("history-day"),
+            HistoryWindow::Week => t!("history-week"),
+            HistoryWindow::Month => t!("history-month"),
+        })
+    }
+}
+
+/// Events from the dashboard view.
+#[derive(Debug, Clone)]
+pub(crate) enum DashboardMessage {
+    Open,
+    Close,
+    Reload,
+    Window(HistoryWindow),
+    Loaded(Result<Vec<DatasetHistory>, String>),
+}
+
+/// The open dashboard and the history it shows.
+#[derive(Debug)]
+pub(crate) struct Dashboard {
+    window: HistoryWindow,
+    history: Option<Result<Vec<DatasetHistory>, String>>,
+}
+
+/// Unlock and lock history from the configured audit trail.
+async fn load(
+    config_path: PathBuf,
+    profile: Option<String>,
+    window: HistoryWindow,
+) -> Result<Vec<DatasetHistory>, String> {
+    let config = LockchainConfig::load_profile(&config_path, profile.as_deref())
+        .map_err(|e| e.to_string())?;
+    let path = config.audit.log_path();
+    let records = audit::read_records(&path).map_err(|e| {
+        t!(
+            "history-read-failed",
+            path = path.display().to_string(),
+            error = e.to_string()
+        )
+    })?;
+    let since = Utc::now() - chrono::Duration::days(window.days() as i64);
+    Ok(audit::dataset_history(&records, Some(since)))
+}
+
+impl LockchainUi {
+    pub(crate) fn update_dashboard(&mut self, message: DashboardMessage) -> Task<Message> {
+        match message {
+            DashboardMessage::Open => {
+                self.settings_open = false;
+                self.settings = None;
+                self.breakglass = None;
+                self.dashboard = Some(Dashboard {
+                    window: HistoryWindow::Week,
+                    history: None,
+                });
+                self.load_dashboard()
+            }
+            DashboardMessage::Close => {
+                self.dashboard = None;
+                Task::none()
+            }
+            DashboardMessage::Reload => self.load_dashboard(),
+            DashboardMessage::Window(window) => {
+                if let Some(dashboard) = &mut self.dashboard {
+                    dashboard.window = window;
+                }
+                self.load_dashboard()
+            }
+            DashboardMessage::Loaded(result) => {
+                if let Some(dashboard) = &mut self.dashboard {
+                    dashboard.history = Some(result);
+                }
+                Task::none()
+            }
+        }
+    }
+
+    fn load_dashboard(&mut self) -> Task<Message> {
+        let Some(dashboard) = &mut self.dashboard else {
+            return Task::none();
+        };
+        dashboard.history = None;
+        Task::perform(
+            load(
+                self.config_path.clone(),
+                self.profile.clone(),
+                dashboard.window,
+            ),
+            |result| Message::Dashboard(DashboardMessage::Loaded(result)),
+        )
+    }
+
+    /// The dashboard, shown in place of the deck body.
+    pub(crate) fn view_dashboard<'a>(
+        &self,
+        dashboard: &'a Dashboard,
+    ) -> iced::Element<'a, Message> {
+        let heading = row![
+            text(t!("history-heading"))
+                .size(18)
+                .style(text_color(palette().heading)),
+            Space::with_width(Length::Fill),
+            pick_list(WINDOWS, Some(dashboard.window), |window| {
+                Message::Dashboard(DashboardMessage::Window(window))
+            })
+            .text_size(14),
+            button(text(t!("history-reload")))
+                .padding([10, 16])
+                .style(primary_button())
+                .on_press(Message::Dashboard(DashboardMessage::Reload)),
+            button(text(t!("nav-back")))
+                .padding([10, 16])
+                .style(help_button())
+                .on_press(Message::Dashboard(DashboardMessage::Close))
+        ]
+        .spacing(12)
+        .align_y(Vertical::Center);
+
+        let body: iced::Element<'a, Message> = match &dashboard.history {
+            None => note(t!("history-loading"), palette().info),
+            Some(Err(err)) => note(err, palette().danger),
+            Some(Ok(history)) if history.is_empty() => note(t!("history-empty"), palette().warning),
+            Some(Ok(history)) => {
+                let mut rows = column![legend()].spacing(16);
+                for entry in history {
+                    rows = rows.push(view_history(entry, dashboard.window));
+                }
+                scrollable(rows).height(Length::Fill).into()
+            }
+        };
+
+        container(column![heading, body].spacing(16))
+            .padding(20)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(panel_style())
+            .into()
+    }
+}
+
+fn note<'a>(message: impl ToString, color: iced::Color) -> iced::Element<'a, Message> {
+    text(message.to_string())
+        .size(14)
+        .style(text_color(color))
+        .into()
+}
+
+/// Colour of one state-strip cell.
+fn sample_color(sample: &HistorySample) -> iced::Color {
+    match (sample.success, sample.event) {
+        (false, _) => palette().danger,
+        (true, AuditEvent::Unlock) => palette().success,
+        (true, _) => palette().subheading,
+    }
+}
+
+fn legend<'a>() -> iced::Element<'a, Message> {
+    let entry = |label: String, color: iced::Color| {
+        row![
+            bar(color, 10.0, 10.0),
+            text(label).size(13).style(text_color(palette().text))
+        ]
+        .spacing(6)
+        .align_y(Vertical::Center)
+    };
+    row![
+        entry(t!("history-legend-unlocked"), palette().success),
+        entry(t!("history-legend-locked"), palette().subheading),
+        entry(t!("history-legend-failed"), palette().danger),
+        entry(
+            t!(
+                "history-legend-slow",
+                threshold = format_duration(SLOW_UNLOCK)
+            ),
+            palette().warning
+        ),
+    ]
+    .spacing(18)
+    .into()
+}
+
+fn bar<'a>(color: iced::Color, width: f32, height: f32) -> iced::Element<'a, Message> {
+    container(Space::new(Length::Fixed(width), Length::Fixed(height)))
+        .style(move |_| iced::widget::container::Style {
+            background: Some(iced::Background::Color(color)),
+            ..Default::default()
+        })
+        .into()
+}
+
+/// Latest `SPARK_SAMPLES` entries of `values`.
+fn recent<T>(values: &[T]) -> &[T] {
+    &values[values.len().saturating_sub(SPARK_SAMPLES)..]
+}
+
+/// One dataset: its flags, a latency sparkline, and a strip of state changes.
+fn view_history(history: &DatasetHistory, window: HistoryWindow) -> iced::Element<'_, Message> {
+    let latencies = history.unlock_latencies();
+    let mut sorted = latencies.clone();
+    sorted.sort();
+    let median = sorted.get(sorted.len() / 2).copied();
+    let slowest = sorted.last().copied();
+    let transitions = history.transitions();
+    let flapping = transitions >= FLAP_TRANSITIONS_PER_DAY * window.days();
+    let slow = median.is_some_and(|median| median >= SLOW_UNLOCK);
+
+    let mut title = row![text(&history.dataset)
+        .size(16)
+        .style(text_color(palette().text))]
+    .spacing(10)
+    .align_y(Vertical::Center);
+    if flapping {
+        title = title.push(
+            text(t!("history-flapping"))
+                .size(13)
+                .style(text_color(palette().danger)),
+        );
+    }
+    if slow {
+        title = title.push(
+            text(t!("history-slow"))
+                .size(13)
+                .style(text_color(palette().warning)),
+        );
+    }
+
+    let latency_summary = match (median, slowest) {
+        (Some(median), Some(slowest)) => t!(
+            "history-latency",
+            median = format_duration(round_ms(median)),
+            slowest = format_duration(round_ms(slowest))
+        ),
+        _ => t!("history-no-timings"),
+    };
+    let stats = text(t!(
+        "history-stats",
+        timed = latencies.len(),
+        latency = latency_summary,
+        transitions = transitions,
+        failures = history.failures()
+    ))
+    .size(13)
+    .style(text_color(palette().info));
+
+    let peak = recent(&latencies)
+        .iter()
+        .max()
+        .copied()
+        .unwrap_or_default()
+        .max(Duration::from_millis(1));
+    let mut spark = row![].spacing(2).align_y(Vertical::Bottom);
+    for latency in recent(&latencies) {
+        let height = (latency.as_secs_f32() / peak.as_secs_f32() * SPARK_HEIGHT).max(2.0);
+        let color = if *latency >= SLOW_UNLOCK {
+            palette().warning
+        } else {
+            palette().success
+        };
+        spark = spark.push(bar(color, 5.0, height));
+    }
+
+    let mut strip = row![].spacing(2);
+    for sample in recent(&history.samples) {
+        strip = strip.push(bar(sample_color(sample), 5.0, 12.0));
+    }
+
+    column![
+        title,
+        stats,
+        container(spark)
+            .height(Length::Fixed(SPARK_HEIGHT))
+            .align_y(Vertical::Bottom),
+        strip
+    ]
+    .spacing(6)
+    .into()
+}
+
+/// Drop sub-millisecond noise before formatting.
+fn round_ms(value: Duration) -> Duration {
+    Duration::from_millis(value.as_millis() as u64)
+}
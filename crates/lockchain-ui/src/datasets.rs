@@ -0,0 +1,190 @@
+//! Dataset panel: live keystatus from the service with per-dataset Unlock and Lock.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use iced::alignment::Vertical;
+use iced::widget::{button, column, container, row, scrollable, text, Space};
+use iced::Length;
+use lockchain_core::config::LockchainConfig;
+use lockchain_core::provider::{DatasetKeyDescriptor, KeyState};
+use lockchain_core::service::{LockOptions, LockchainService, UnlockOptions};
+use lockchain_zfs::SystemZfsProvider;
+
+use crate::hosts::{self, Host};
+use crate::i18n::t;
+use crate::shortcuts::Focus;
+use crate::theme::palette;
+use crate::{killswitch_button, panel_style, primary_button, text_color, LockchainUi, Message};
+
+/// Build a service for the configured profile, driving `host`'s ZFS.
+fn service(
+    config_path: &Path,
+    profile: Option<&str>,
+    host: &Host,
+) -> Result<LockchainService<SystemZfsProvider>, String> {
+    let config = LockchainConfig::load_profile(config_path, profile).map_err(|e| e.to_string())?;
+    let provider = hosts::provider(&config, host)?;
+    Ok(LockchainService::new(Arc::new(config), provider))
+}
+
+/// Query keystatus for every managed dataset on `host`.
+pub(crate) async fn load(
+    config_path: PathBuf,
+    profile: Option<String>,
+    host: Host,
+) -> Result<Vec<DatasetKeyDescriptor>, String> {
+    service(&config_path, profile.as_deref(), &host)?
+        .list_keys()
+        .map_err(|e| e.to_string())
+}
+
+/// Unlock `dataset` with its configured key source, retrying as `[retry]` says.
+pub(crate) async fn unlock(
+    config_path: PathBuf,
+    profile: Option<String>,
+    host: Host,
+    dataset: String,
+    strict_usb: bool,
+) -> Result<String, String> {
+    let options = UnlockOptions {
+        strict_usb,
+        ..UnlockOptions::default()
+    };
+    let report = service(&config_path, profile.as_deref(), &host)?
+        .unlock_with_retry(&dataset, options)
+        .map_err(|e| {
+            t!(
+                "dataset-unlock-failed",
+                dataset = dataset.as_str(),
+                error = e.to_string()
+            )
+        })?;
+    Ok(if report.already_unlocked {
+        t!("dataset-already-unlocked", root = report.encryption_root)
+    } else {
+        t!(
+            "dataset-unlocked",
+            root = report.encryption_root,
+            count = report.unlocked.len()
+        )
+    })
+}
+
+/// Unload the key shared by `dataset`'s encryption root.
+pub(crate) async fn lock(
+    config_path: PathBuf,
+    profile: Option<String>,
+    host: Host,
+    dataset: String,
+) -> Result<String, String> {
+    let report = service(&config_path, profile.as_deref(), &host)?
+        .lock(&dataset, LockOptions::default())
+        .map_err(|e| {
+            t!(
+                "dataset-lock-failed",
+                dataset = dataset.as_str(),
+                error = e.to_string()
+            )
+        })?;
+    Ok(if report.already_locked {
+        t!("dataset-already-locked", root = report.encryption_root)
+    } else {
+        t!(
+            "dataset-locked",
+            root = report.encryption_root,
+            count = report.locked.len()
+        )
+    })
+}
+
+impl LockchainUi {
+    /// List managed datasets with their key state and Unlock/Lock buttons.
+    pub(crate) fn view_dataset_panel(&self) -> iced::widget::Container<'_, Message> {
+        let heading = row![
+            text(match self.host.destination() {
+                Some(destination) => t!("datasets-heading-remote", host = destination),
+                None => t!("datasets-heading"),
+            })
+            .size(18)
+            .style(text_color(palette().heading)),
+            Space::with_width(Length::Fill),
+            text(if self.datasets_loading {
+                t!("datasets-loading")
+            } else {
+                String::new()
+            })
+            .size(14)
+            .style(text_color(palette().info))
+        ]
+        .align_y(Vertical::Center);
+
+        let body: iced::Element<'_, Message> = if let Some(err) = &self.datasets_error {
+            text(err)
+                .size(14)
+                .style(text_color(palette().danger))
+                .into()
+        } else if self.datasets.is_empty() {
+            text(if self.datasets_loading {
+                t!("datasets-reading")
+            } else {
+                t!("datasets-empty")
+            })
+            .size(14)
+            .style(text_color(palette().info))
+            .into()
+        } else {
+            let mut list = column![].spacing(8);
+            for entry in &self.datasets {
+                list = list.push(self.view_dataset_row(entry));
+            }
+            scrollable(list).into()
+        };
+
+        container(column![heading, body].spacing(12))
+            .padding(20)
+            .max_height(280)
+            .style(panel_style())
+    }
+
+    fn view_dataset_row<'a>(&self, entry: &'a DatasetKeyDescriptor) -> iced::Element<'a, Message> {
+        let (state, color) = match &entry.state {
+            KeyState::Available => (t!("key-state-unlocked"), palette().success),
+            KeyState::Unavailable => (t!("key-state-locked"), palette().danger),
+            KeyState::Unknown(_) => (t!("key-state-unknown"), palette().warning),
+        };
+        let idle = self.dataset_busy.is_none() && !self.executing;
+        let mut unlock = button(text(t!("dataset-unlock")).size(14))
+            .padding([6, 14])
+            .style(primary_button());
+        let mut lock = button(text(t!("dataset-lock")).size(14))
+            .padding([6, 14])
+            .style(killswitch_button());
+        if idle {
+            unlock = unlock.on_press(Message::UnlockDataset(entry.dataset.clone()));
+            lock = lock.on_press(Message::LockDataset(entry.dataset.clone()));
+        }
+        let busy = self.dataset_busy.as_deref() == Some(entry.dataset.as_str());
+
+        row![
+            column![
+                text(&entry.dataset)
+                    .size(16)
+                    .style(text_color(palette().text)),
+                text(t!("dataset-root", root = entry.encryption_root.as_str()))
+                    .size(12)
+                    .style(text_color(palette().info))
+            ]
+            .spacing(2),
+            Space::with_width(Length::Fill),
+            text(if busy { t!("dataset-working") } else { state })
+                .size(14)
+                .style(text_color(color)),
+            self.focusable(Focus::Unlock(entry.dataset.clone()), unlock),
+            self.focusable(Focus::Lock(entry.dataset.clone()), lock)
+        ]
+        .spacing(12)
+        .align_y(Vertical::Center)
+        .into()
+    }
+}
@@ -0,0 +1,54 @@
+//! USB token picker for the New Key directives.
+
+use iced::alignment::Vertical;
+use iced::widget::{button, column, pick_list, row, text};
+use iced::Length;
+use lockchain_core::workflow::{self, RemovableDevice};
+
+use crate::i18n::t;
+use crate::theme::palette;
+use crate::{help_button, text_color, LockchainUi, Message};
+
+/// Enumerate attached removable disks.
+pub(crate) async fn scan() -> Result<Vec<RemovableDevice>, String> {
+    workflow::removable_devices().map_err(|e| e.to_string())
+}
+
+impl LockchainUi {
+    /// Dropdown of candidate tokens with a re-scan button.
+    pub(crate) fn view_device_picker(&self) -> iced::Element<'_, Message> {
+        let picker = pick_list(
+            self.devices.as_slice(),
+            self.selected_device.as_ref(),
+            Message::DeviceSelected,
+        )
+        .placeholder(if self.devices_scanning {
+            t!("devices-scanning")
+        } else if self.devices.is_empty() {
+            t!("devices-none")
+        } else {
+            t!("devices-select")
+        })
+        .text_size(14)
+        .width(Length::Fill);
+
+        let mut rescan = button(text(t!("devices-rescan")))
+            .padding([8, 14])
+            .style(help_button());
+        if !self.devices_scanning && !self.executing {
+            rescan = rescan.on_press(Message::ScanDevices);
+        }
+
+        let mut body = column![
+            text(t!("devices-label"))
+                .size(14)
+                .style(text_color(palette().success)),
+            row![picker, rescan].spacing(8).align_y(Vertical::Center)
+        ]
+        .spacing(6);
+        if let Some(err) = &self.devices_error {
+            body = body.push(text(err).size(13).style(text_color(palette().danger)));
+        }
+        body.into()
+    }
+}
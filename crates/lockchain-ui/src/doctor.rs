@@ -0,0 +1,213 @@
+//! Doctor results as cards: one per remediation suggestion, with an Apply
+//! fix button for the ones the deck can repair itself.
+
+use std::path::PathBuf;
+
+use iced::alignment::Vertical;
+use iced::border::{Border, Radius};
+use iced::widget::{button, column, container, row, scrollable, text, Space};
+use iced::{Length, Task, Theme};
+use lockchain_core::config::LockchainConfig;
+use lockchain_core::workflow::{self, DoctorFix, DoctorMode, Remedy, WorkflowReport};
+use lockchain_zfs::SystemZfsProvider;
+
+use crate::i18n::t;
+use crate::theme::palette;
+use crate::{
+    help_button, panel_style, primary_button, text_color, ActivityLevel, LockchainUi, Message,
+};
+
+/// Where a card's fix stands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FixState {
+    Pending,
+    Applying,
+    Applied,
+    Failed(String),
+}
+
+/// One remedy from the last Doctor run.
+#[derive(Debug, Clone)]
+pub(crate) struct DoctorCard {
+    remedy: Remedy,
+    state: FixState,
+}
+
+impl DoctorCard {
+    pub(crate) fn new(remedy: Remedy) -> Self {
+        Self {
+            remedy,
+            state: FixState::Pending,
+        }
+    }
+}
+
+/// Button label for a fix.
+fn fix_label(fix: DoctorFix) -> String {
+    match fix {
+        DoctorFix::Repair => t!("fix-repair"),
+        DoctorFix::TightenPermissions => t!("fix-tighten-permissions"),
+        DoctorFix::RebuildInitramfs => t!("fix-rebuild-initramfs"),
+        DoctorFix::ApplyAll => t!("fix-apply-all"),
+    }
+}
+
+/// Run the workflow behind `fix`.
+pub(crate) async fn apply(
+    config_path: PathBuf,
+    profile: Option<String>,
+    fix: DoctorFix,
+) -> Result<WorkflowReport, String> {
+    let config = LockchainConfig::load_profile(&config_path, profile.as_deref())
+        .map_err(|e| e.to_string())?;
+    match fix {
+        DoctorFix::Repair => workflow::repair_environment(&config),
+        DoctorFix::TightenPermissions => workflow::tighten_key_permissions(&config),
+        DoctorFix::RebuildInitramfs => workflow::refresh_initramfs(&config),
+        DoctorFix::ApplyAll => {
+            let provider = SystemZfsProvider::from_config(&config).map_err(|e| e.to_string())?;
+            workflow::doctor_report(&config, provider, DoctorMode::Fix).map(|doctor| doctor.report)
+        }
+    }
+    .map_err(|e| e.to_string())
+}
+
+impl LockchainUi {
+    /// Start the fix on card `index` unless something else is running.
+    pub(crate) fn apply_fix(&mut self, index: usize) -> Task<Message> {
+        if self.executing || self.fix_running() {
+            return Task::none();
+        }
+        let Some(card) = self.doctor_cards.get_mut(index) else {
+            return Task::none();
+        };
+        let Some(fix) = card.remedy.fix else {
+            return Task::none();
+        };
+        card.state = FixState::Applying;
+        self.push_activity(
+            ActivityLevel::Info,
+            t!("doctor-applying", fix = fix_label(fix)),
+        );
+        Task::perform(
+            apply(self.config_path.clone(), self.profile.clone(), fix),
+            move |result| Message::FixApplied(index, result),
+        )
+    }
+
+    /// Record a finished fix on its card and in the activity feed.
+    pub(crate) fn fix_applied(
+        &mut self,
+        index: usize,
+        result: Result<WorkflowReport, String>,
+    ) -> Task<Message> {
+        let state = match result {
+            Ok(report) => {
+                for event in report.events {
+                    self.push_activity(ActivityLevel::from(event.level), event.message);
+                }
+                self.push_activity(
+                    ActivityLevel::Success,
+                    t!("activity-complete", title = report.title),
+                );
+                FixState::Applied
+            }
+            Err(err) => {
+                self.push_activity(
+                    ActivityLevel::Error,
+                    t!("doctor-fix-failed", error = err.as_str()),
+                );
+                FixState::Failed(err)
+            }
+        };
+        if let Some(card) = self.doctor_cards.get_mut(index) {
+            card.state = state;
+        }
+        self.refresh_state()
+    }
+
+    fn fix_running(&self) -> bool {
+        self.doctor_cards
+            .iter()
+            .any(|card| card.state == FixState::Applying)
+    }
+
+    /// Remedies from the last Doctor run, one card each.
+    pub(crate) fn view_doctor_panel(&self) -> iced::widget::Container<'_, Message> {
+        let heading = row![
+            text(t!("doctor-heading"))
+                .size(18)
+                .style(text_color(palette().heading)),
+            Space::with_width(Length::Fill),
+            button(text(t!("doctor-dismiss")).size(14))
+                .padding([6, 14])
+                .style(help_button())
+                .on_press(Message::DismissDoctor)
+        ]
+        .align_y(Vertical::Center);
+
+        let idle = !self.executing && !self.fix_running();
+        let mut cards = column![].spacing(8);
+        for (index, card) in self.doctor_cards.iter().enumerate() {
+            cards = cards.push(view_card(index, card, idle));
+        }
+
+        container(column![heading, scrollable(cards)].spacing(12))
+            .padding(20)
+            .max_height(320)
+            .style(panel_style())
+    }
+}
+
+fn view_card(index: usize, card: &DoctorCard, idle: bool) -> iced::Element<'_, Message> {
+    let (status, color) = match (&card.state, card.remedy.fix) {
+        (FixState::Applying, _) => (t!("fix-state-applying"), palette().info),
+        (FixState::Applied, _) => (t!("fix-state-fixed"), palette().success),
+        (FixState::Failed(_), _) => (t!("fix-state-failed"), palette().danger),
+        (FixState::Pending, Some(_)) => (String::new(), palette().accent),
+        (FixState::Pending, None) => (t!("fix-state-manual"), palette().warning),
+    };
+
+    let mut line = row![
+        text(&card.remedy.summary)
+            .size(14)
+            .width(Length::Fill)
+            .style(text_color(palette().text)),
+        text(status).size(13).style(text_color(color))
+    ]
+    .spacing(12)
+    .align_y(Vertical::Center);
+    if let Some(fix) = card.remedy.fix {
+        let mut apply = button(text(fix_label(fix)).size(14))
+            .padding([6, 14])
+            .style(primary_button());
+        if idle && card.state != FixState::Applied {
+            apply = apply.on_press(Message::ApplyFix(index));
+        }
+        line = line.push(apply);
+    }
+
+    let mut body = column![line].spacing(6);
+    if let FixState::Failed(err) = &card.state {
+        body = body.push(text(err).size(12).style(text_color(palette().danger)));
+    }
+
+    container(body)
+        .padding([10, 14])
+        .width(Length::Fill)
+        .style(card_style(color))
+        .into()
+}
+
+/// Card outlined in its status colour.
+fn card_style(color: iced::Color) -> impl Fn(&Theme) -> iced::widget::container::Style + Copy {
+    move |_| iced::widget::container::Style {
+        background: Some(iced::Background::Color(palette().card)),
+        border: Border {
+            radius: Radius::from(8.0),
+            width: 1.0,
+            color,
+        },
+        ..Default::default()
+    }
+}
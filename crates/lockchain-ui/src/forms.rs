@@ -0,0 +1,170 @@
+//! Directive parameter forms, generated from the workflow parameter schema
+//! the CLI subcommands share, so both front ends offer the same options.
+
+use std::collections::{HashMap, HashSet};
+
+use iced::widget::{column, pick_list, text, text_input, toggler, tooltip};
+use lockchain_core::units;
+use lockchain_core::workflow::{ParamKind, ParamSpec, FORGE_PARAMS, SELF_TEST_PARAMS};
+
+use crate::i18n::{self, t};
+use crate::settings::labelled;
+use crate::shortcuts::Focus;
+use crate::theme::palette;
+use crate::{dialog_style, text_color, text_input_style, Directive, LockchainUi, Message};
+
+/// Edits to the active directive's form.
+#[derive(Debug, Clone)]
+pub(crate) enum FormMessage {
+    Text(&'static str, String),
+    Flag(&'static str, bool),
+}
+
+/// Values entered for one directive, keyed by parameter name.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ParamValues {
+    text: HashMap<&'static str, String>,
+    flags: HashSet<&'static str>,
+}
+
+impl ParamValues {
+    /// The trimmed value of `name`, `None` when left empty.
+    pub(crate) fn text(&self, name: &str) -> Option<&str> {
+        self.text
+            .get(name)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    }
+
+    pub(crate) fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    /// `pool_size`-style values in bytes.
+    pub(crate) fn size(&self, name: &str) -> Result<Option<u64>, String> {
+        self.text(name)
+            .map(|value| units::parse_size(value).map_err(|err| format!("{name}: {err}")))
+            .transpose()
+    }
+
+    fn apply(&mut self, message: FormMessage) {
+        match message {
+            FormMessage::Text(name, value) => {
+                self.text.insert(name, value);
+            }
+            FormMessage::Flag(name, true) => {
+                self.flags.insert(name);
+            }
+            FormMessage::Flag(name, false) => {
+                self.flags.remove(name);
+            }
+        }
+    }
+}
+
+/// Parameters `directive` takes. New Key always wipes, so it has no
+/// force-wipe switch; Self-heal and Doctor take none.
+pub(crate) fn directive_params(directive: Directive) -> Vec<&'static ParamSpec> {
+    let specs: &'static [ParamSpec] = match directive {
+        Directive::NewKey | Directive::NewKeySafe => FORGE_PARAMS,
+        Directive::SelfTest => SELF_TEST_PARAMS,
+        Directive::SelfHeal | Directive::Doctor => &[],
+    };
+    specs
+        .iter()
+        .filter(|spec| !(directive == Directive::NewKey && spec.name == "force_wipe"))
+        .collect()
+}
+
+/// Id of the text field for parameter `name`, so the focus ring can reach it.
+pub(crate) fn input_id(name: &'static str) -> text_input::Id {
+    text_input::Id::new(name)
+}
+
+impl LockchainUi {
+    /// Values entered for the active directive.
+    pub(crate) fn params(&self) -> ParamValues {
+        self.forms
+            .get(&self.active_directive)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn update_form(&mut self, message: FormMessage) {
+        self.forms
+            .entry(self.active_directive)
+            .or_default()
+            .apply(message);
+    }
+
+    /// One row per parameter of the active directive, with the schema's help
+    /// text on hover.
+    pub(crate) fn view_directive_form(&self) -> iced::Element<'_, Message> {
+        let values = self.forms.get(&self.active_directive);
+        let value = |name: &str| {
+            values
+                .and_then(|values| values.text.get(name))
+                .map(String::as_str)
+                .unwrap_or_default()
+        };
+
+        let mut form = column![].spacing(8);
+        for spec in directive_params(self.active_directive) {
+            let placeholder = spec
+                .default
+                .map_or_else(|| t!("form-optional"), str::to_string);
+            let control: iced::Element<'_, Message> = match spec.kind {
+                ParamKind::Dataset => {
+                    let options: Vec<String> = self
+                        .datasets
+                        .iter()
+                        .map(|entry| entry.dataset.clone())
+                        .collect();
+                    let selected = values
+                        .and_then(|values| values.text(spec.name))
+                        .map(str::to_string);
+                    pick_list(options, selected, move |dataset| {
+                        Message::Form(FormMessage::Text(spec.name, dataset))
+                    })
+                    .placeholder(t!("form-dataset-default"))
+                    .text_size(14)
+                    .into()
+                }
+                ParamKind::Device => self.view_device_picker(),
+                ParamKind::Flag => toggler(values.is_some_and(|values| values.flag(spec.name)))
+                    .size(20)
+                    .on_toggle(move |state| Message::Form(FormMessage::Flag(spec.name, state)))
+                    .into(),
+                ParamKind::Path | ParamKind::Text | ParamKind::Size => {
+                    let input = text_input(&placeholder, value(spec.name))
+                        .id(input_id(spec.name))
+                        .on_input(move |value| Message::Form(FormMessage::Text(spec.name, value)))
+                        .size(14)
+                        .padding(8)
+                        .style(text_input_style());
+                    let mut field =
+                        column![self.focusable(Focus::Param(spec.name), input)].spacing(4);
+                    if spec.kind == ParamKind::Size {
+                        if let Some(Err(err)) = values.map(|values| values.size(spec.name)) {
+                            field =
+                                field.push(text(err).size(12).style(text_color(palette().danger)));
+                        }
+                    }
+                    field.into()
+                }
+            };
+            let id = format!("param-{}", spec.name.replace('_', "-"));
+            form = form.push(tooltip(
+                labelled(i18n::lookup_or(&id, spec.label), control),
+                iced::widget::container(
+                    text(i18n::lookup_or(&format!("{id}-help"), spec.help)).size(13),
+                )
+                .padding(8)
+                .max_width(420)
+                .style(dialog_style()),
+                tooltip::Position::Top,
+            ));
+        }
+        form.into()
+    }
+}
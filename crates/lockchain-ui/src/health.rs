@@ -0,0 +1,321 @@
+//! Header health chips: the runtime key, the daemon, and the pools behind
+//! the managed datasets, re-checked on a timer.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use iced::border::{Border, Radius};
+use iced::widget::{container, row, text, tooltip};
+use iced::{Task, Theme};
+use lockchain_core::config::{is_dataset_pattern, HealthCfg, LockchainConfig};
+use lockchain_core::control::{self, ControlCall, ControlClient};
+use lockchain_core::ZfsProvider;
+
+use crate::hosts::{self, Host};
+use crate::i18n::t;
+use crate::theme::palette;
+use crate::{text_color, LockchainUi, Message};
+
+/// How often the chips are refreshed.
+pub(crate) const HEALTH_INTERVAL: Duration = Duration::from_secs(10);
+/// Readiness endpoint served by `lockchain-daemon`.
+const HEALTH_ADDR_ENV: &str = "LOCKCHAIN_HEALTH_ADDR";
+const DEFAULT_HEALTH_ADDR: &str = "127.0.0.1:8787";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HealthLevel {
+    Good,
+    Degraded,
+    Down,
+    Unknown,
+}
+
+impl HealthLevel {
+    fn color(self) -> iced::Color {
+        match self {
+            HealthLevel::Good => palette().success,
+            HealthLevel::Degraded => palette().warning,
+            HealthLevel::Down => palette().danger,
+            HealthLevel::Unknown => palette().info,
+        }
+    }
+}
+
+/// One chip: a short state for the header and the detail behind it.
+#[derive(Debug, Clone)]
+pub(crate) struct Indicator {
+    pub(crate) level: HealthLevel,
+    state: String,
+    detail: String,
+}
+
+impl Indicator {
+    fn new(level: HealthLevel, state: String, detail: impl Into<String>) -> Self {
+        Self {
+            level,
+            state,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Latest result of [`probe`].
+#[derive(Debug, Clone)]
+pub(crate) struct HealthReport {
+    pub(crate) usb: Indicator,
+    pub(crate) daemon: Indicator,
+    pub(crate) zfs: Indicator,
+    /// Host whose pools the ZFS chip describes.
+    pub(crate) host: Host,
+}
+
+impl HealthReport {
+    /// Shown until the first probe answers.
+    pub(crate) fn pending() -> Self {
+        let checking = || Indicator::new(HealthLevel::Unknown, "…".into(), t!("health-checking"));
+        Self {
+            usb: checking(),
+            daemon: checking(),
+            zfs: checking(),
+            host: Host::Local,
+        }
+    }
+}
+
+/// Check the runtime key path, the daemon, and pool health.
+pub(crate) async fn probe(
+    config_path: PathBuf,
+    profile: Option<String>,
+    host: Host,
+) -> HealthReport {
+    let config = match LockchainConfig::load_profile(&config_path, profile.as_deref()) {
+        Ok(config) => config,
+        Err(err) => {
+            let missing = Indicator::new(
+                HealthLevel::Unknown,
+                t!("health-no-config"),
+                err.to_string(),
+            );
+            return HealthReport {
+                usb: missing.clone(),
+                daemon: daemon_indicator(&HealthCfg::default()),
+                zfs: missing,
+                host,
+            };
+        }
+    };
+
+    let daemon = daemon_indicator(&config.health);
+    let key_path = config.key_hex_path();
+    let usb = if key_path.exists() {
+        Indicator::new(
+            HealthLevel::Good,
+            t!("health-key"),
+            t!("health-key-detail", path = key_path.display().to_string()),
+        )
+    } else {
+        Indicator::new(
+            HealthLevel::Down,
+            t!("health-no-key"),
+            t!(
+                "health-no-key-detail",
+                path = key_path.display().to_string()
+            ),
+        )
+    };
+
+    HealthReport {
+        usb,
+        daemon,
+        zfs: pool_indicator(&config, &host),
+        host,
+    }
+}
+
+/// Control socket reachability, refined by the readiness endpoint.
+fn daemon_indicator(auth: &HealthCfg) -> Indicator {
+    let socket = control::socket_path();
+    let control = ControlClient::connect(&socket).and_then(|mut client| {
+        client.call::<serde_json::Value>(ControlCall::Status { dataset: None })
+    });
+    match (readiness(auth), control) {
+        (Some((true, _)), _) => Indicator::new(
+            HealthLevel::Good,
+            t!("health-ready"),
+            t!("health-ready-detail"),
+        ),
+        (Some((false, unready)), _) => Indicator::new(
+            HealthLevel::Degraded,
+            t!("health-degraded"),
+            t!(
+                "health-daemon-degraded-detail",
+                components = unready.join(", ")
+            ),
+        ),
+        (None, Ok(_)) => Indicator::new(
+            HealthLevel::Good,
+            t!("health-running"),
+            t!(
+                "health-running-detail",
+                socket = socket.display().to_string()
+            ),
+        ),
+        (None, Err(err)) => Indicator::new(HealthLevel::Down, t!("health-down"), err.to_string()),
+    }
+}
+
+/// `Some((ready, unready components))` from the daemon's `/readyz`
+/// endpoint, `None` when it does not answer or refuses us; the bearer token
+/// from `[health] token_file` is sent when we can read it.
+fn readiness(auth: &HealthCfg) -> Option<(bool, Vec<String>)> {
+    let addr: SocketAddr = std::env::var(HEALTH_ADDR_ENV)
+        .unwrap_or_else(|_| DEFAULT_HEALTH_ADDR.to_string())
+        .parse()
+        .ok()?;
+    let mut stream = TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).ok()?;
+    stream.set_read_timeout(Some(PROBE_TIMEOUT)).ok()?;
+    let header = match auth.token() {
+        Ok(Some(token)) => format!("authorization: Bearer {token}\r\n"),
+        _ => String::new(),
+    };
+    stream
+        .write_all(format!("GET /readyz HTTP/1.0\r\n{header}\r\n").as_bytes())
+        .ok()?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).ok()?;
+    let (head, body) = response.split_once("\r\n\r\n")?;
+    let ready = match head.split_whitespace().nth(1)? {
+        "200" => true,
+        "503" => false,
+        _ => return None,
+    };
+    let report: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+    let unready = report["components"]
+        .as_object()
+        .map(|components| {
+            components
+                .iter()
+                .filter(|(_, component)| component["ready"] == false)
+                .map(|(name, _)| name.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    Some((ready, unready))
+}
+
+/// Health of every pool holding a managed dataset on `host`.
+fn pool_indicator(config: &LockchainConfig, host: &Host) -> Indicator {
+    let mut pools: Vec<&str> = config
+        .policy
+        .datasets
+        .iter()
+        .filter_map(|dataset| dataset.split('/').next())
+        .filter(|pool| !pool.is_empty() && !is_dataset_pattern(pool))
+        .collect();
+    pools.sort_unstable();
+    pools.dedup();
+    if pools.is_empty() {
+        return Indicator::new(
+            HealthLevel::Unknown,
+            t!("health-no-pools"),
+            t!("health-no-pools-detail"),
+        );
+    }
+
+    let provider = match hosts::provider(config, host) {
+        Ok(provider) => provider,
+        Err(err) => return Indicator::new(HealthLevel::Down, t!("health-error"), err),
+    };
+    let mut unhealthy = Vec::new();
+    for pool in &pools {
+        match provider.pool_health(pool) {
+            Ok(health) if health.eq_ignore_ascii_case("online") => {}
+            Ok(health) => unhealthy.push(format!("{pool} {health}")),
+            Err(err) => {
+                return Indicator::new(HealthLevel::Down, t!("health-error"), err.to_string())
+            }
+        }
+    }
+    if unhealthy.is_empty() {
+        Indicator::new(
+            HealthLevel::Good,
+            t!("health-online"),
+            t!(
+                "health-online-detail",
+                host = host.to_string(),
+                pools = pools.join(", ")
+            ),
+        )
+    } else {
+        Indicator::new(
+            HealthLevel::Degraded,
+            t!("health-degraded"),
+            t!(
+                "health-pools-degraded-detail",
+                host = host.to_string(),
+                pools = unhealthy.join(", ")
+            ),
+        )
+    }
+}
+
+impl LockchainUi {
+    /// Start a health probe unless one is already running.
+    pub(crate) fn check_health(&mut self) -> Task<Message> {
+        if self.health_checking {
+            return Task::none();
+        }
+        self.health_checking = true;
+        Task::perform(
+            probe(
+                self.config_path.clone(),
+                self.profile.clone(),
+                self.host.clone(),
+            ),
+            Message::HealthChecked,
+        )
+    }
+
+    /// USB, daemon, and ZFS chips with their detail on hover.
+    pub(crate) fn view_health_chips(&self) -> iced::Element<'_, Message> {
+        let chip = |name: String, indicator: &Indicator| {
+            tooltip(
+                container(
+                    text(format!("{name} {}", indicator.state))
+                        .size(14)
+                        .style(text_color(indicator.level.color())),
+                )
+                .padding([6, 12])
+                .style(health_chip(indicator.level)),
+                container(text(indicator.detail.clone()).size(13))
+                    .padding(8)
+                    .max_width(420)
+                    .style(crate::dialog_style()),
+                tooltip::Position::Bottom,
+            )
+        };
+        row![
+            chip(t!("health-chip-usb"), &self.health.usb),
+            chip(t!("health-chip-daemon"), &self.health.daemon),
+            chip(t!("health-chip-zfs"), &self.health.zfs)
+        ]
+        .spacing(8)
+        .into()
+    }
+}
+
+/// Pill outlined in the level's colour.
+fn health_chip(level: HealthLevel) -> impl Fn(&Theme) -> iced::widget::container::Style + Copy {
+    move |_| iced::widget::container::Style {
+        background: Some(iced::Background::Color(palette().card)),
+        border: Border {
+            radius: Radius::from(999.0),
+            width: 1.0,
+            color: level.color(),
+        },
+        ..Default::default()
+    }
+}
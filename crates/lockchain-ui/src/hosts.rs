@@ -0,0 +1,100 @@
+//! Target hosts: this machine plus the `[ui] hosts` SSH remotes. The dataset
+//! panel and the ZFS chip follow the selected host; its `zfs` and `zpool`
+//! run over `ssh` while keys and config stay on this machine.
+
+use std::fmt;
+
+use iced::widget::pick_list;
+use iced::Task;
+use lockchain_core::config::LockchainConfig;
+use lockchain_zfs::SystemZfsProvider;
+
+use crate::i18n::t;
+use crate::{ActivityLevel, LockchainUi, Message};
+
+/// An entry in the header's host picker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Host {
+    Local,
+    /// SSH destination such as `root@nas1`.
+    Remote(String),
+}
+
+impl Host {
+    /// The SSH destination, `None` for this machine.
+    pub(crate) fn destination(&self) -> Option<&str> {
+        match self {
+            Host::Local => None,
+            Host::Remote(destination) => Some(destination),
+        }
+    }
+}
+
+impl fmt::Display for Host {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Host::Local => f.write_str(&t!("host-local")),
+            Host::Remote(destination) => f.write_str(destination),
+        }
+    }
+}
+
+/// The ZFS provider for `host`: local binaries or the same ones over SSH.
+pub(crate) fn provider(config: &LockchainConfig, host: &Host) -> Result<SystemZfsProvider, String> {
+    match host.destination() {
+        Some(destination) => SystemZfsProvider::remote(config, destination),
+        None => SystemZfsProvider::from_config(config),
+    }
+    .map_err(|e| e.to_string())
+}
+
+impl LockchainUi {
+    /// This machine followed by the configured remotes.
+    fn host_choices(&self) -> Vec<Host> {
+        std::iter::once(Host::Local)
+            .chain(self.hosts.iter().cloned().map(Host::Remote))
+            .collect()
+    }
+
+    /// Adopt the `[ui] hosts` list, falling back to this machine when the
+    /// selected remote is no longer in it.
+    pub(crate) fn set_hosts(&mut self, hosts: Vec<String>) -> Task<Message> {
+        self.hosts = hosts;
+        if !self.host_choices().contains(&self.host) {
+            return self.update(Message::HostSelected(Host::Local));
+        }
+        Task::none()
+    }
+
+    /// Point the dataset panel and ZFS chip at `host` and re-query both.
+    pub(crate) fn select_host(&mut self, host: Host) -> Task<Message> {
+        if host == self.host || self.dataset_busy.is_some() {
+            return Task::none();
+        }
+        self.push_activity(
+            ActivityLevel::Info,
+            t!("host-selected", host = host.to_string()),
+        );
+        self.host = host;
+        self.datasets.clear();
+        self.datasets_error = None;
+        self.refresh_state()
+    }
+
+    /// Host picker for the header; hidden while only this machine is known.
+    pub(crate) fn view_host_picker(&self) -> Option<iced::Element<'_, Message>> {
+        if self.hosts.is_empty() {
+            return None;
+        }
+        Some(
+            pick_list(
+                self.host_choices(),
+                Some(self.host.clone()),
+                Message::HostSelected,
+            )
+            .text_size(14)
+            .padding([8, 12])
+            .into(),
+        )
+    }
+}
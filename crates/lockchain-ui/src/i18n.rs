@@ -0,0 +1,177 @@
+//! Interface strings: one Fluent catalog per language under `locales/`,
+//! chosen by `[ui] language` or the locale environment. Messages missing
+//! from a translation fall back to English.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::LazyLock;
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use unic_langid::LanguageIdentifier;
+
+/// `t!("id")` or `t!("id", name = value, …)`: a message from the active
+/// catalog, with Fluent variables for the named values.
+macro_rules! t {
+    ($id:literal) => {
+        $crate::i18n::lookup($id, None)
+    };
+    ($id:literal, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = fluent_bundle::FluentArgs::new();
+        $(args.set(stringify!($name), $value);)+
+        $crate::i18n::lookup($id, Some(&args))
+    }};
+}
+pub(crate) use t;
+
+/// Languages the deck ships catalogs for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Language {
+    English,
+    German,
+}
+
+/// Every language, in catalog order.
+const LANGUAGES: [Language; 2] = [Language::English, Language::German];
+
+impl Language {
+    fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Language::English => include_str!("../locales/en.ftl"),
+            Language::German => include_str!("../locales/de.ftl"),
+        }
+    }
+
+    /// The language for a locale such as `de`, `de_AT.UTF-8`, or `en-GB`.
+    fn from_locale(locale: &str) -> Option<Self> {
+        let primary = locale
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default();
+        LANGUAGES
+            .into_iter()
+            .find(|language| language.code().eq_ignore_ascii_case(primary))
+    }
+
+    /// `configured` when it names a catalog, otherwise the first locale
+    /// variable that is set; English when neither helps.
+    pub(crate) fn resolve(configured: Option<&str>) -> Self {
+        if let Some(language) = configured.and_then(Self::from_locale) {
+            return language;
+        }
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::from_locale(&value))
+            .unwrap_or(Language::English)
+    }
+}
+
+/// Language picker entries: follow the locale, or pin a catalog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LanguageChoice {
+    System,
+    Fixed(Language),
+}
+
+pub(crate) const LANGUAGE_CHOICES: [LanguageChoice; 3] = [
+    LanguageChoice::System,
+    LanguageChoice::Fixed(Language::English),
+    LanguageChoice::Fixed(Language::German),
+];
+
+impl LanguageChoice {
+    /// The choice behind a `[ui] language` value.
+    pub(crate) fn from_config(language: Option<&str>) -> Self {
+        language
+            .and_then(Language::from_locale)
+            .map_or(LanguageChoice::System, LanguageChoice::Fixed)
+    }
+
+    /// The `[ui] language` value to save.
+    pub(crate) fn to_config(self) -> Option<String> {
+        match self {
+            LanguageChoice::System => None,
+            LanguageChoice::Fixed(language) => Some(language.code().to_string()),
+        }
+    }
+}
+
+impl fmt::Display for LanguageChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LanguageChoice::System => f.write_str(&t!("language-system")),
+            // Each language is listed under its own name.
+            LanguageChoice::Fixed(Language::English) => f.write_str("English"),
+            LanguageChoice::Fixed(Language::German) => f.write_str("Deutsch"),
+        }
+    }
+}
+
+type Bundle = FluentBundle<FluentResource>;
+
+static BUNDLES: LazyLock<Vec<Bundle>> = LazyLock::new(|| {
+    LANGUAGES
+        .into_iter()
+        .map(|language| {
+            let id: LanguageIdentifier = language.code().parse().expect("valid language code");
+            let resource = FluentResource::try_new(language.source().to_string()).unwrap_or_else(
+                |(_, errors)| panic!("{}.ftl does not parse: {errors:?}", language.code()),
+            );
+            let mut bundle = Bundle::new_concurrent(vec![id]);
+            // Bidi isolation marks render as boxes in the deck's fonts.
+            bundle.set_use_isolating(false);
+            bundle
+                .add_resource(resource)
+                .unwrap_or_else(|errors| panic!("{}.ftl: {errors:?}", language.code()));
+            bundle
+        })
+        .collect()
+});
+
+/// The catalog [`lookup`] reads; view code has no handle on the deck's
+/// state in every helper, so the choice lives here like the palette.
+static ACTIVE: AtomicU8 = AtomicU8::new(Language::English as u8);
+
+/// Switch every string over to `language`.
+pub(crate) fn activate(language: Language) {
+    ACTIVE.store(language as u8, Ordering::Relaxed);
+}
+
+fn format(bundle: &Bundle, id: &str, args: Option<&FluentArgs>) -> Option<String> {
+    let pattern = bundle.get_message(id)?.value()?;
+    let mut errors = Vec::new();
+    let text = bundle.format_pattern(pattern, args, &mut errors);
+    if !errors.is_empty() {
+        log::warn!("message {id}: {errors:?}");
+    }
+    Some(text.into_owned())
+}
+
+/// Message `id` in the active language, or `english` when that catalog has
+/// none; for text whose English lives outside the catalogs, such as the
+/// parameter schema shared with the CLI.
+pub(crate) fn lookup_or(id: &str, english: &str) -> String {
+    let active = usize::from(ACTIVE.load(Ordering::Relaxed));
+    format(&BUNDLES[active], id, None).unwrap_or_else(|| english.to_string())
+}
+
+/// Message `id` in the active language, falling back to English and then
+/// to the id itself.
+pub(crate) fn lookup(id: &str, args: Option<&FluentArgs>) -> String {
+    let active = usize::from(ACTIVE.load(Ordering::Relaxed));
+    format(&BUNDLES[active], id, args)
+        .or_else(|| format(&BUNDLES[Language::English as usize], id, args))
+        .unwrap_or_else(|| {
+            log::warn!("missing message {id}");
+            id.to_string()
+        })
+}
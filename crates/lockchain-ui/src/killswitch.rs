@@ -0,0 +1,94 @@
+//! Killswitch confirmation dialog and the emergency lockdown behind it.
+
+use std::path::PathBuf;
+
+use iced::widget::{button, column, container, row, text, toggler, Space};
+use iced::Length;
+use lockchain_core::config::LockchainConfig;
+use lockchain_core::workflow::{self, KillswitchOptions, WorkflowReport};
+use lockchain_zfs::SystemZfsProvider;
+
+use crate::i18n::t;
+use crate::theme::palette;
+use crate::{dialog_style, help_button, killswitch_button, text_color, LockchainUi, Message};
+
+/// Choices made in the open confirmation dialog.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct KillswitchPrompt {
+    pub(crate) stop_daemon: bool,
+}
+
+/// Lock every configured encryption root and shred the runtime key file.
+pub(crate) async fn engage(
+    config_path: PathBuf,
+    profile: Option<String>,
+    stop_daemon: bool,
+) -> Result<WorkflowReport, String> {
+    let config = LockchainConfig::load_profile(&config_path, profile.as_deref())
+        .map_err(|e| e.to_string())?;
+    let provider = SystemZfsProvider::from_config(&config).map_err(|e| e.to_string())?;
+    workflow::killswitch(&config, provider, KillswitchOptions { stop_daemon })
+        .map_err(|e| e.to_string())
+}
+
+impl LockchainUi {
+    /// Spell out what the killswitch does and wait for an explicit go.
+    pub(crate) fn view_killswitch_dialog(
+        &self,
+        prompt: KillswitchPrompt,
+    ) -> iced::Element<'_, Message> {
+        let mut roots: Vec<&str> = self
+            .datasets
+            .iter()
+            .map(|entry| entry.encryption_root.as_str())
+            .collect();
+        roots.sort_unstable();
+        roots.dedup();
+        let scope = if roots.is_empty() {
+            t!("killswitch-scope-all")
+        } else {
+            t!(
+                "killswitch-scope-roots",
+                count = roots.len(),
+                roots = roots.join(", ")
+            )
+        };
+
+        let detail = |line: String| text(line).size(14).style(text_color(palette().text));
+
+        let stop_daemon = toggler(prompt.stop_daemon)
+            .label(t!("killswitch-stop-daemon"))
+            .size(20)
+            .text_size(14)
+            .on_toggle(Message::KillswitchStopDaemon);
+
+        container(
+            column![
+                text(t!("killswitch-title"))
+                    .size(22)
+                    .style(text_color(palette().subheading)),
+                detail(scope),
+                detail(t!("killswitch-shred")),
+                detail(t!("killswitch-open-files")),
+                stop_daemon,
+                row![
+                    Space::with_width(Length::Fill),
+                    button(text(t!("dialog-cancel")))
+                        .padding([10, 16])
+                        .style(help_button())
+                        .on_press(Message::KillswitchCancel),
+                    button(text(t!("killswitch-confirm")))
+                        .padding([10, 16])
+                        .style(killswitch_button())
+                        .on_press(Message::KillswitchConfirm)
+                ]
+                .spacing(12)
+            ]
+            .spacing(14),
+        )
+        .padding(24)
+        .max_width(560)
+        .style(dialog_style())
+        .into()
+    }
+}
@@ -1,20 +1,65 @@
 //! Desktop control deck built with Iced to steer Lockchain workflows.
 
+mod activity_log;
+mod breakglass;
+mod confirm;
+mod dashboard;
+mod datasets;
+mod devices;
+mod doctor;
+mod forms;
+mod health;
+mod hosts;
+mod i18n;
+mod killswitch;
+mod passphrase;
+mod privilege;
+mod progress;
+mod settings;
+mod shortcuts;
+mod theme;
+mod tray;
+mod wizard;
+
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-use chrono::Local;
+use chrono::{DateTime, Local};
 use iced::alignment::Vertical;
 use iced::border::{Border, Radius};
 use iced::widget::button;
 use iced::widget::button::{Status as ButtonStatus, Style as ButtonStyle};
-use iced::widget::{column, container, row, scrollable, text, text_input, toggler, Space};
-use iced::{application, Font, Length, Size, Task, Theme};
-use lockchain_core::config::LockchainConfig;
+use iced::widget::{
+    center, column, container, mouse_area, opaque, pick_list, row, scrollable, stack, text,
+    toggler, Space,
+};
+use iced::{application, Font, Length, Size, Subscription, Task, Theme};
+use lockchain_core::config::{LockchainConfig, UiCfg};
+use lockchain_core::provider::DatasetKeyDescriptor;
 use lockchain_core::workflow::{
-    self, ForgeMode, ProvisionOptions, WorkflowEvent, WorkflowLevel, WorkflowReport,
+    self, DoctorMode, ForgeMode, ProvisionOptions, Remedy, RemovableDevice, SelfTestOptions,
+    WorkflowEvent, WorkflowLevel, WorkflowReport,
 };
 use lockchain_zfs::SystemZfsProvider;
+use serde::{Deserialize, Serialize};
+
+use activity_log::{ActivityLog, ExportFormat};
+use breakglass::{BreakGlass, BreakGlassMessage};
+use confirm::GuardedAction;
+use dashboard::{Dashboard, DashboardMessage};
+use doctor::DoctorCard;
+use forms::{FormMessage, ParamValues};
+use health::HealthReport;
+use hosts::Host;
+use i18n::{t, Language};
+use killswitch::KillswitchPrompt;
+use passphrase::{PassphrasePrompt, Secret};
+use privilege::{ElevatedAction, ElevationPrompt};
+use progress::DirectiveRun;
+use settings::{SettingsForm, SettingsMessage};
+use shortcuts::{Focus, Shortcut};
+use theme::palette;
+use wizard::{SetupWizard, WizardMessage};
 
 /// Launch the Iced application with the Lockchain-specific theme and state.
 pub fn main() -> iced::Result {
@@ -28,16 +73,17 @@ pub fn main() -> iced::Result {
     .antialiasing(true)
     .window_size(Size::new(1280.0, 768.0))
     .theme(LockchainUi::theme)
+    .subscription(LockchainUi::subscription)
+    .exit_on_close_request(false)
     .run_with(LockchainUi::init)
 }
 
 /// Actions the operator can trigger from the UI.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Directive {
     NewKey,
     NewKeySafe,
     SelfTest,
-    RecoverKey,
     SelfHeal,
     Doctor,
 }
@@ -45,7 +91,7 @@ enum Directive {
 /// Metadata used to render directive cards.
 struct DirectiveEntry {
     directive: Directive,
-    title: &'static str,
+    /// Catalog id of the one-line summary under the title.
     subtitle: &'static str,
 }
 
@@ -53,49 +99,41 @@ struct DirectiveEntry {
 const DIRECTIVES: &[DirectiveEntry] = &[
     DirectiveEntry {
         directive: Directive::NewKey,
-        title: "New Key",
-        subtitle: "Forge fresh USB key material",
+        subtitle: "directive-new-key-summary",
     },
     DirectiveEntry {
         directive: Directive::NewKeySafe,
-        title: "New Key (Safe mode)",
-        subtitle: "Guided forge with confirmations",
+        subtitle: "directive-new-key-safe-summary",
     },
     DirectiveEntry {
         directive: Directive::SelfTest,
-        title: "Self-test",
-        subtitle: "Drill unlock + keystatus verification",
-    },
-    DirectiveEntry {
-        directive: Directive::RecoverKey,
-        title: "Recover Key",
-        subtitle: "Derive fallback key from passphrase",
+        subtitle: "directive-self-test-summary",
     },
     DirectiveEntry {
         directive: Directive::SelfHeal,
-        title: "Self-heal Issues",
-        subtitle: "Diagnose key material and dataset state",
+        subtitle: "directive-self-heal-summary",
     },
     DirectiveEntry {
         directive: Directive::Doctor,
-        title: "Doctor",
-        subtitle: "Full system audit & remediation tips",
+        subtitle: "directive-doctor-summary",
     },
 ];
 
 /// Visual severity mapping for workflow events.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
 enum ActivityLevel {
     Info,
     Success,
     Warn,
     Error,
+    #[serde(rename = "SECURE")]
     Security,
 }
 
 impl ActivityLevel {
-    /// Short label used in status chips.
-    fn label(self) -> &'static str {
+    /// Untranslated name, as serialised, for exported logs.
+    fn tag(self) -> &'static str {
         match self {
             ActivityLevel::Info => "INFO",
             ActivityLevel::Success => "SUCCESS",
@@ -105,14 +143,25 @@ impl ActivityLevel {
         }
     }
 
+    /// Short label used in status chips.
+    fn label(self) -> String {
+        match self {
+            ActivityLevel::Info => t!("level-info"),
+            ActivityLevel::Success => t!("level-success"),
+            ActivityLevel::Warn => t!("level-warn"),
+            ActivityLevel::Error => t!("level-error"),
+            ActivityLevel::Security => t!("level-secure"),
+        }
+    }
+
     /// Theme color associated with each activity level.
     fn color(self) -> iced::Color {
         match self {
-            ActivityLevel::Info => iced::Color::from_rgb8(0x67, 0xd6, 0xff),
-            ActivityLevel::Success => iced::Color::from_rgb8(0x8a, 0xff, 0x70),
-            ActivityLevel::Warn => iced::Color::from_rgb8(0xff, 0xc1, 0x29),
-            ActivityLevel::Error => iced::Color::from_rgb8(0xff, 0x47, 0x80),
-            ActivityLevel::Security => iced::Color::from_rgb8(0xff, 0x73, 0xff),
+            ActivityLevel::Info => palette().info,
+            ActivityLevel::Success => palette().success,
+            ActivityLevel::Warn => palette().warning,
+            ActivityLevel::Error => palette().danger,
+            ActivityLevel::Security => palette().subheading,
         }
     }
 }
@@ -120,7 +169,7 @@ impl ActivityLevel {
 impl From<WorkflowLevel> for ActivityLevel {
     fn from(level: WorkflowLevel) -> Self {
         match level {
-            WorkflowLevel::Info => ActivityLevel::Info,
+            WorkflowLevel::Debug | WorkflowLevel::Info => ActivityLevel::Info,
             WorkflowLevel::Success => ActivityLevel::Success,
             WorkflowLevel::Warn => ActivityLevel::Warn,
             WorkflowLevel::Error => ActivityLevel::Error,
@@ -132,79 +181,255 @@ impl From<WorkflowLevel> for ActivityLevel {
 /// Normalised activity entry displayed in the timeline.
 #[derive(Debug, Clone)]
 struct ActivityItem {
-    timestamp: String,
+    timestamp: DateTime<Local>,
     level: ActivityLevel,
     message: String,
 }
 
+impl ActivityItem {
+    /// Time of day, prefixed with the date for entries replayed from an
+    /// earlier day.
+    fn clock(&self) -> String {
+        if self.timestamp.date_naive() == Local::now().date_naive() {
+            self.timestamp.format("%H:%M:%S").to_string()
+        } else {
+            self.timestamp.format("%b %d %H:%M:%S").to_string()
+        }
+    }
+}
+
 /// Application state backing the UI, including current directive and logs.
 #[derive(Debug)]
 struct LockchainUi {
     config_path: PathBuf,
+    profile: Option<String>,
     active_directive: Directive,
     secure_mode: bool,
-    terminal_input: String,
+    /// Parameter form values, kept per directive.
+    forms: HashMap<Directive, ParamValues>,
     activity: Vec<ActivityItem>,
+    /// Where the feed is persisted across sessions.
+    activity_log: ActivityLog,
+    export_format: ExportFormat,
+    exporting: bool,
     executing: bool,
-    pending_directive: Option<Directive>,
+    /// Directive currently streaming events, if any.
+    run: Option<DirectiveRun>,
+    next_run_id: u64,
     status_line: String,
     total_events: usize,
     key_present: bool,
+    /// Latest USB, daemon, and pool health shown in the header.
+    health: HealthReport,
+    health_checking: bool,
+    datasets: Vec<DatasetKeyDescriptor>,
+    /// Why the last keystatus query failed, until one succeeds.
+    datasets_error: Option<String>,
+    datasets_loading: bool,
+    /// Dataset with an unlock or lock in flight.
+    dataset_busy: Option<String>,
+    /// Removable disks offered as key tokens by the New Key directives.
+    devices: Vec<RemovableDevice>,
+    selected_device: Option<RemovableDevice>,
+    devices_scanning: bool,
+    devices_error: Option<String>,
+    /// Open killswitch confirmation, if any.
+    killswitch: Option<KillswitchPrompt>,
+    /// Destructive directive waiting for its typed confirmation.
+    guard: Option<GuardedAction>,
+    /// Open masked passphrase dialog, if any.
+    passphrase_prompt: Option<PassphrasePrompt>,
+    /// Fallback passphrase seeded by the next New Key run.
+    fallback_passphrase: Option<Secret>,
+    /// The settings screen replaces the deck body while open.
+    settings_open: bool,
+    /// Open history dashboard, if any.
+    dashboard: Option<Dashboard>,
+    /// Open break-glass recovery screen, if any.
+    breakglass: Option<BreakGlass>,
+    settings: Option<SettingsForm>,
+    settings_error: Option<String>,
+    /// First-run setup, open while no config exists yet.
+    wizard: Option<SetupWizard>,
+    /// Closing hides the deck to the tray and polling continues.
+    tray_mode: bool,
+    /// Why the tray icon could not be registered, if it failed.
+    tray_error: Option<String>,
+    /// Remedies from the last Doctor run.
+    doctor_cards: Vec<DoctorCard>,
+    /// Running as root; otherwise privileged work goes through pkexec.
+    privileged: bool,
+    /// Open "run as root?" dialog, if any.
+    elevation: Option<ElevationPrompt>,
+    /// `[ui]` preferences: theme and reduced motion.
+    appearance: UiCfg,
+    /// The desktop's light/dark preference, once the portal has answered.
+    prefers_light: Option<bool>,
+    /// `[ui] hosts` remotes offered besides this machine.
+    hosts: Vec<String>,
+    /// Host the dataset panel and ZFS chip act on.
+    host: Host,
+    /// Control the keyboard focus ring is on, outlined while set.
+    focus: Option<Focus>,
 }
 
 /// Messages produced by Iced interactions and background tasks.
 #[derive(Debug, Clone)]
 enum Message {
     DirectiveSelected(Directive),
-    TerminalChanged(String),
+    Form(FormMessage),
     Execute,
+    WorkflowEvent(WorkflowEvent),
     WorkflowFinished(Result<WorkflowReport, String>),
+    DoctorRemedies(Vec<Remedy>),
+    ApplyFix(usize),
+    FixApplied(usize, Result<WorkflowReport, String>),
+    DismissDoctor,
+    Tick,
     ToggleSecure(bool),
     HelpPressed,
     KillSwitchPressed,
+    KillswitchStopDaemon(bool),
+    KillswitchCancel,
+    KillswitchConfirm,
+    KillswitchFinished(Result<WorkflowReport, String>),
+    Settings(SettingsMessage),
+    Dashboard(DashboardMessage),
+    BreakGlass(BreakGlassMessage),
+    Wizard(WizardMessage),
+    ToggleTray(bool),
+    TrayActivated,
+    TrayUnavailable(String),
+    CloseRequested(iced::window::Id),
+    Poll,
+    CheckHealth,
+    HealthChecked(HealthReport),
+    DesktopScheme(Option<bool>),
+    HostSelected(Host),
+    ExportFormatSelected(ExportFormat),
+    ExportLog,
+    LogExported(Result<PathBuf, String>),
+    GuardInput(String),
+    GuardConfirm,
+    GuardCancel,
+    PassphraseInput(Secret),
+    PassphraseConfirmInput(Secret),
+    PassphraseSubmit,
+    PassphraseCancel,
+    SetFallbackPassphrase,
+    ClearFallbackPassphrase,
     Refresh,
+    DatasetsLoaded(Host, Result<Vec<DatasetKeyDescriptor>, String>),
+    UnlockDataset(String),
+    LockDataset(String),
+    DatasetActionFinished(Result<String, String>),
+    UnlockFinished(Result<String, String>),
+    ScanDevices,
+    DevicesScanned(Result<Vec<RemovableDevice>, String>),
+    DeviceSelected(RemovableDevice),
+    ElevationConfirm,
+    ElevationCancel,
+    Shortcut(Shortcut),
 }
 
 impl LockchainUi {
-    /// Construct initial UI state and schedule no async work.
+    /// Construct initial UI state and start loading the dataset list.
     fn init() -> (Self, Task<Message>) {
         let config_path = std::env::var("LOCKCHAIN_CONFIG")
             .map(PathBuf::from)
             .unwrap_or_else(|_| PathBuf::from("/etc/lockchain-zfs.toml"));
-
+        let profile = selected_profile(std::env::args().skip(1));
+        let preferences = LockchainConfig::load_profile(&config_path, profile.as_deref())
+            .map(|config| config.ui)
+            .unwrap_or_default();
+        // Before any of the strings below are looked up.
+        i18n::activate(Language::resolve(preferences.language.as_deref()));
+
+        let activity_log = ActivityLog::from_env();
         let mut ui = Self {
             config_path,
+            profile,
             active_directive: Directive::NewKey,
             secure_mode: false,
-            terminal_input: String::new(),
-            activity: Vec::new(),
+            forms: HashMap::new(),
+            activity: activity_log.tail(activity_log::TAIL_ENTRIES),
+            activity_log,
+            export_format: ExportFormat::Json,
+            exporting: false,
             executing: false,
-            pending_directive: None,
-            status_line: "Monitoring".into(),
+            run: None,
+            next_run_id: 0,
+            status_line: t!("status-monitoring"),
             total_events: 0,
             key_present: false,
+            health: HealthReport::pending(),
+            health_checking: false,
+            datasets: Vec::new(),
+            datasets_error: None,
+            datasets_loading: false,
+            dataset_busy: None,
+            devices: Vec::new(),
+            selected_device: None,
+            devices_scanning: false,
+            devices_error: None,
+            killswitch: None,
+            guard: None,
+            passphrase_prompt: None,
+            fallback_passphrase: None,
+            settings_open: false,
+            dashboard: None,
+            breakglass: None,
+            settings: None,
+            settings_error: None,
+            wizard: None,
+            tray_mode: std::env::args().skip(1).any(|arg| arg == "--tray"),
+            tray_error: None,
+            doctor_cards: Vec::new(),
+            privileged: privilege::is_root(),
+            elevation: None,
+            appearance: UiCfg::default(),
+            prefers_light: None,
+            hosts: Vec::new(),
+            host: Host::Local,
+            focus: None,
         };
+        ui.hosts = preferences.hosts.clone();
+        ui.set_appearance(preferences);
 
-        ui.push_activity(
-            ActivityLevel::Info,
-            "Control Deck online. Select a directive to begin.",
-        );
-        ui.key_present = ui.detect_key_presence();
-        (ui, Task::none())
+        ui.push_activity(ActivityLevel::Info, t!("activity-online"));
+        if !ui.privileged {
+            ui.push_activity(ActivityLevel::Warn, t!("activity-unprivileged"));
+        }
+        let first = if ui.config_path.exists() {
+            ui.reload_datasets()
+        } else {
+            ui.open_wizard()
+        };
+        let load = Task::batch([
+            first,
+            ui.scan_devices(),
+            ui.check_health(),
+            Task::perform(theme::desktop_prefers_light(), Message::DesktopScheme),
+        ]);
+        (ui, load)
     }
 
     /// React to UI events and kick off any background tasks.
     fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::DirectiveSelected(directive) => {
-                if !self.executing {
-                    self.active_directive = directive;
-                    self.status_line = directive_title(directive).into();
+                if self.executing {
+                    return Task::none();
+                }
+                self.active_directive = directive;
+                self.status_line = directive_title(directive);
+                if forges_token(directive) && self.devices.is_empty() {
+                    return self.scan_devices();
                 }
                 Task::none()
             }
-            Message::TerminalChanged(value) => {
-                self.terminal_input = value;
+            Message::Form(message) => {
+                self.update_form(message);
                 Task::none()
             }
             Message::ToggleSecure(state) => {
@@ -212,125 +437,616 @@ impl LockchainUi {
                 self.push_activity(
                     ActivityLevel::Info,
                     if state {
-                        "Strict USB mode engaged."
+                        t!("activity-strict-on")
                     } else {
-                        "Strict USB mode disengaged."
+                        t!("activity-strict-off")
                     },
                 );
                 Task::none()
             }
+            Message::ToggleTray(state) => {
+                self.tray_mode = state;
+                self.tray_error = None;
+                self.push_activity(
+                    ActivityLevel::Info,
+                    if state {
+                        t!("activity-tray-on")
+                    } else {
+                        t!("activity-tray-off")
+                    },
+                );
+                Task::none()
+            }
+            Message::TrayActivated => self.restore_window(),
+            Message::TrayUnavailable(err) => {
+                self.push_activity(
+                    ActivityLevel::Warn,
+                    t!("activity-tray-unavailable", error = err.as_str()),
+                );
+                self.tray_error = Some(err);
+                Task::none()
+            }
+            Message::CloseRequested(id) => self.close_requested(id),
+            Message::Poll => self.refresh_state(),
+            Message::CheckHealth => self.check_health(),
+            Message::HealthChecked(report) => {
+                self.health_checking = false;
+                if report.host != self.host {
+                    return self.check_health();
+                }
+                self.key_present = report.usb.level == health::HealthLevel::Good;
+                self.health = report;
+                Task::none()
+            }
             Message::Execute => {
                 if self.executing {
                     return Task::none();
                 }
                 if !self.directive_enabled(self.active_directive) {
+                    self.push_activity(ActivityLevel::Warn, t!("activity-needs-key"));
+                    return Task::none();
+                }
+                match self.guard_for(self.active_directive) {
+                    Ok(Some(guard)) => self.guard = Some(guard),
+                    Ok(None) => self.execute(self.active_directive, None),
+                    Err(err) => self.push_activity(ActivityLevel::Warn, err),
+                }
+                Task::none()
+            }
+            Message::GuardInput(value) => {
+                if let Some(guard) = &mut self.guard {
+                    guard.typed = value;
+                }
+                Task::none()
+            }
+            Message::GuardConfirm => {
+                if let Some(guard) = self.guard.take_if(|guard| guard.confirmed()) {
+                    if !self.executing {
+                        self.execute(guard.directive, None);
+                    }
+                }
+                Task::none()
+            }
+            Message::GuardCancel => {
+                if let Some(guard) = self.guard.take() {
                     self.push_activity(
-                        ActivityLevel::Warn,
-                        "Forge or insert a LockChain key before running this directive.",
+                        ActivityLevel::Info,
+                        t!(
+                            "activity-cancelled",
+                            action = directive_title(guard.directive)
+                        ),
                     );
-                    return Task::none();
                 }
-                self.executing = true;
-                self.pending_directive = Some(self.active_directive);
-                self.push_activity(
-                    ActivityLevel::Info,
-                    format!("Executing {}", directive_title(self.active_directive)),
-                );
-                Task::perform(
-                    run_directive(
-                        self.config_path.clone(),
-                        self.active_directive,
-                        self.secure_mode,
-                        self.terminal_input.clone(),
-                    ),
-                    Message::WorkflowFinished,
-                )
+                Task::none()
+            }
+            Message::PassphraseInput(value) => {
+                if let Some(prompt) = &mut self.passphrase_prompt {
+                    prompt.passphrase = value;
+                }
+                Task::none()
+            }
+            Message::PassphraseConfirmInput(value) => {
+                if let Some(prompt) = &mut self.passphrase_prompt {
+                    prompt.confirm = value;
+                }
+                Task::none()
+            }
+            Message::PassphraseSubmit => {
+                if let Some(prompt) = self.passphrase_prompt.take_if(|prompt| prompt.ready()) {
+                    self.fallback_passphrase = Some(prompt.passphrase);
+                    self.push_activity(ActivityLevel::Info, t!("activity-passphrase-set"));
+                }
+                Task::none()
+            }
+            Message::PassphraseCancel => {
+                self.passphrase_prompt = None;
+                Task::none()
+            }
+            Message::SetFallbackPassphrase => {
+                self.passphrase_prompt = Some(PassphrasePrompt::default());
+                Task::none()
+            }
+            Message::ClearFallbackPassphrase => {
+                if self.fallback_passphrase.take().is_some() {
+                    self.push_activity(ActivityLevel::Info, t!("activity-passphrase-cleared"));
+                }
+                Task::none()
+            }
+            Message::WorkflowEvent(event) => {
+                if let Some(run) = &mut self.run {
+                    run.events += 1;
+                }
+                self.push_activity(ActivityLevel::from(event.level), event.message);
+                Task::none()
+            }
+            Message::Tick => {
+                if let Some(run) = &mut self.run {
+                    run.frame += 1;
+                }
+                Task::none()
             }
             Message::WorkflowFinished(result) => {
                 self.executing = false;
                 let directive = self
-                    .pending_directive
+                    .run
                     .take()
+                    .map(|run| run.directive)
                     .unwrap_or(self.active_directive);
                 match result {
                     Ok(report) => {
                         self.push_activity(
                             ActivityLevel::Success,
-                            format!("{} complete", report.title),
+                            t!("activity-complete", title = report.title),
                         );
-                        self.ingest_events(report.events);
-                        if matches!(directive, Directive::NewKey | Directive::NewKeySafe) {
-                            self.status_line = "Forge complete".into();
+                        if forges_token(directive) {
+                            self.status_line = t!("status-forge-complete");
                             self.key_present = true;
+                            self.fallback_passphrase = None;
                         } else {
-                            self.status_line = "Monitoring".into();
+                            self.status_line = t!("status-monitoring");
                         }
                     }
                     Err(err) => {
                         self.push_activity(ActivityLevel::Error, err);
-                        self.status_line = "Check diagnostics".into();
+                        self.status_line = t!("status-check-diagnostics");
                     }
                 }
-                self.key_present = self.detect_key_presence();
-                Task::none()
+                self.refresh_state()
             }
             Message::HelpPressed => {
-                self.push_activity(
-                    ActivityLevel::Info,
-                    help_text(self.active_directive).to_string(),
-                );
+                self.push_activity(ActivityLevel::Info, help_text(self.active_directive));
                 Task::none()
             }
             Message::KillSwitchPressed => {
-                self.push_activity(
-                    ActivityLevel::Warn,
-                    "Killswitch placeholder: integrate ZFS unload-key workflow.",
-                );
+                if !self.executing {
+                    self.killswitch = Some(KillswitchPrompt::default());
+                }
                 Task::none()
             }
-            Message::Refresh => {
+            Message::KillswitchStopDaemon(state) => {
+                if let Some(prompt) = &mut self.killswitch {
+                    prompt.stop_daemon = state;
+                }
+                Task::none()
+            }
+            Message::KillswitchCancel => {
+                self.killswitch = None;
+                Task::none()
+            }
+            Message::KillswitchConfirm => {
+                let Some(prompt) = self.killswitch.take() else {
+                    return Task::none();
+                };
                 if self.executing {
                     return Task::none();
                 }
-                self.key_present = self.detect_key_presence();
+                if !self.privileged {
+                    let command = privilege::killswitch_command(
+                        &self.config_path,
+                        self.profile.as_deref(),
+                        prompt.stop_daemon,
+                    );
+                    self.request_elevation(ElevatedAction::Killswitch, command);
+                    return Task::none();
+                }
                 self.executing = true;
-                self.pending_directive = Some(Directive::SelfHeal);
-                self.push_activity(ActivityLevel::Info, "Running self-heal diagnostics…");
+                self.status_line = t!("status-killswitch");
+                self.push_activity(ActivityLevel::Security, t!("activity-killswitch"));
                 Task::perform(
-                    run_directive(
+                    killswitch::engage(
                         self.config_path.clone(),
-                        Directive::SelfHeal,
+                        self.profile.clone(),
+                        prompt.stop_daemon,
+                    ),
+                    Message::KillswitchFinished,
+                )
+            }
+            Message::KillswitchFinished(result) => {
+                self.executing = false;
+                match result {
+                    Ok(report) => self.ingest_events(report.events),
+                    Err(err) => {
+                        self.push_activity(
+                            ActivityLevel::Error,
+                            t!("activity-killswitch-failed", error = err),
+                        );
+                        self.status_line = t!("status-check-diagnostics");
+                    }
+                }
+                self.refresh_state()
+            }
+            Message::Refresh => {
+                if self.executing {
+                    return Task::none();
+                }
+                if self.privileged {
+                    self.push_activity(ActivityLevel::Info, t!("activity-self-heal"));
+                    self.start_run(Directive::SelfHeal, None);
+                } else {
+                    self.push_activity(ActivityLevel::Info, t!("activity-refresh-only"));
+                }
+                self.refresh_state()
+            }
+            Message::Settings(message) => self.update_settings(message),
+            Message::Dashboard(message) => self.update_dashboard(message),
+            Message::BreakGlass(message) => self.update_breakglass(message),
+            Message::HostSelected(host) => self.select_host(host),
+            Message::DesktopScheme(prefers_light) => {
+                self.prefers_light = prefers_light;
+                self.set_appearance(self.appearance.clone());
+                Task::none()
+            }
+            Message::Wizard(message) => self.update_wizard(message),
+            Message::Shortcut(shortcut) => self.update_shortcut(shortcut),
+            Message::ExportFormatSelected(format) => {
+                self.export_format = format;
+                Task::none()
+            }
+            Message::ExportLog => {
+                if self.exporting {
+                    return Task::none();
+                }
+                self.exporting = true;
+                Task::perform(
+                    activity_log::export(self.activity_log.clone(), self.export_format),
+                    Message::LogExported,
+                )
+            }
+            Message::DoctorRemedies(remedies) => {
+                self.doctor_cards = remedies.into_iter().map(DoctorCard::new).collect();
+                Task::none()
+            }
+            Message::ApplyFix(index) => self.apply_fix(index),
+            Message::FixApplied(index, result) => self.fix_applied(index, result),
+            Message::DismissDoctor => {
+                self.doctor_cards.clear();
+                Task::none()
+            }
+            Message::LogExported(result) => {
+                self.exporting = false;
+                match result {
+                    Ok(path) => self.push_activity(
+                        ActivityLevel::Success,
+                        t!("activity-exported", path = path.display().to_string()),
+                    ),
+                    Err(err) => self.push_activity(
+                        ActivityLevel::Error,
+                        t!("activity-export-failed", error = err),
+                    ),
+                }
+                Task::none()
+            }
+            Message::DatasetsLoaded(host, result) => {
+                self.datasets_loading = false;
+                if host != self.host {
+                    return self.reload_datasets();
+                }
+                match result {
+                    Ok(datasets) => {
+                        let locked: Vec<String> = tray::newly_locked(&self.datasets, &datasets)
+                            .into_iter()
+                            .map(str::to_string)
+                            .collect();
+                        self.datasets = datasets;
+                        self.datasets_error = None;
+                        if !locked.is_empty() {
+                            let summary = t!("activity-locked", datasets = locked.join(", "));
+                            self.push_activity(ActivityLevel::Security, summary.clone());
+                            return self.notify(t!("notify-dataset-locked"), summary);
+                        }
+                    }
+                    Err(err) => {
+                        if self.datasets_error.as_ref() != Some(&err) {
+                            self.push_activity(
+                                ActivityLevel::Warn,
+                                t!("activity-keystatus-failed", error = err.as_str()),
+                            );
+                        }
+                        self.datasets_error = Some(err);
+                    }
+                }
+                Task::none()
+            }
+            Message::UnlockDataset(dataset) => {
+                if self.dataset_busy.is_some() || self.executing {
+                    return Task::none();
+                }
+                if !self.privileged {
+                    let command = privilege::unlock_command(
+                        &self.config_path,
+                        self.profile.as_deref(),
+                        &dataset,
+                        self.secure_mode,
+                        self.host.destination(),
+                    );
+                    self.request_elevation(ElevatedAction::Unlock(dataset), Ok(command));
+                    return Task::none();
+                }
+                self.push_activity(
+                    ActivityLevel::Info,
+                    t!("activity-unlocking", dataset = dataset.as_str()),
+                );
+                self.dataset_busy = Some(dataset.clone());
+                Task::perform(
+                    datasets::unlock(
+                        self.config_path.clone(),
+                        self.profile.clone(),
+                        self.host.clone(),
+                        dataset,
                         self.secure_mode,
-                        self.terminal_input.clone(),
                     ),
-                    Message::WorkflowFinished,
+                    Message::UnlockFinished,
                 )
             }
+            Message::LockDataset(dataset) => {
+                if self.dataset_busy.is_some() || self.executing {
+                    return Task::none();
+                }
+                // Unloading a remote key needs no local root.
+                if !self.privileged && self.host == Host::Local {
+                    let command = privilege::lock_command(
+                        &self.config_path,
+                        self.profile.as_deref(),
+                        &dataset,
+                    );
+                    self.request_elevation(ElevatedAction::Lock(dataset), Ok(command));
+                    return Task::none();
+                }
+                self.push_activity(
+                    ActivityLevel::Info,
+                    t!("activity-locking", dataset = dataset.as_str()),
+                );
+                self.dataset_busy = Some(dataset.clone());
+                Task::perform(
+                    datasets::lock(
+                        self.config_path.clone(),
+                        self.profile.clone(),
+                        self.host.clone(),
+                        dataset,
+                    ),
+                    Message::DatasetActionFinished,
+                )
+            }
+            Message::ScanDevices => self.scan_devices(),
+            Message::DevicesScanned(result) => {
+                self.devices_scanning = false;
+                match result {
+                    Ok(devices) => {
+                        self.selected_device = self
+                            .selected_device
+                            .take()
+                            .and_then(|picked| devices.iter().find(|d| d.path == picked.path))
+                            .cloned();
+                        self.devices = devices;
+                        self.devices_error = None;
+                    }
+                    Err(err) => {
+                        self.devices.clear();
+                        self.selected_device = None;
+                        self.devices_error = Some(t!("devices-scan-failed", error = err));
+                    }
+                }
+                Task::none()
+            }
+            Message::DeviceSelected(device) => {
+                self.push_activity(
+                    ActivityLevel::Info,
+                    t!("activity-device-selected", device = device.to_string()),
+                );
+                self.selected_device = Some(device);
+                Task::none()
+            }
+            Message::UnlockFinished(result) => {
+                let alert = match &result {
+                    Err(err) => self.notify(t!("notify-unlock-failed"), err.clone()),
+                    Ok(_) => Task::none(),
+                };
+                Task::batch([alert, self.update(Message::DatasetActionFinished(result))])
+            }
+            Message::DatasetActionFinished(result) => {
+                self.dataset_busy = None;
+                match result {
+                    Ok(summary) => self.push_activity(ActivityLevel::Success, summary),
+                    Err(err) => self.push_activity(ActivityLevel::Error, err),
+                }
+                self.reload_datasets()
+            }
+            Message::ElevationCancel => {
+                if let Some(prompt) = self.elevation.take() {
+                    self.push_activity(
+                        ActivityLevel::Info,
+                        t!("activity-cancelled", action = prompt.command.summary),
+                    );
+                }
+                Task::none()
+            }
+            Message::ElevationConfirm => {
+                let Some(prompt) = self.elevation.take() else {
+                    return Task::none();
+                };
+                if self.executing || self.dataset_busy.is_some() {
+                    return Task::none();
+                }
+                self.push_activity(
+                    ActivityLevel::Security,
+                    t!(
+                        "activity-elevating",
+                        command = prompt.command.command_line()
+                    ),
+                );
+                match prompt.action {
+                    ElevatedAction::Directive(mut run) => {
+                        if run.directive == Directive::Doctor {
+                            self.push_activity(ActivityLevel::Info, t!("activity-elevated-doctor"));
+                        }
+                        run.elevated = Some(prompt.command);
+                        self.begin_run(*run);
+                        Task::none()
+                    }
+                    ElevatedAction::Unlock(dataset) => {
+                        self.dataset_busy = Some(dataset);
+                        Task::perform(
+                            privilege::run_action(prompt.command),
+                            Message::UnlockFinished,
+                        )
+                    }
+                    ElevatedAction::Lock(dataset) => {
+                        self.dataset_busy = Some(dataset);
+                        Task::perform(
+                            privilege::run_action(prompt.command),
+                            Message::DatasetActionFinished,
+                        )
+                    }
+                    ElevatedAction::BreakGlass(destination) => {
+                        self.breakglass_elevated(prompt.command, destination)
+                    }
+                    ElevatedAction::Killswitch => {
+                        self.executing = true;
+                        self.status_line = t!("status-killswitch");
+                        Task::perform(
+                            privilege::run_report(prompt.command),
+                            Message::KillswitchFinished,
+                        )
+                    }
+                }
+            }
+        }
+    }
+
+    /// Announce and start `directive`, with the passphrase from the dialog
+    /// when it needs one.
+    fn execute(&mut self, directive: Directive, passphrase: Option<Secret>) {
+        self.push_activity(
+            ActivityLevel::Info,
+            t!("activity-executing", directive = directive_title(directive)),
+        );
+        self.start_run(directive, passphrase);
+    }
+
+    /// Begin streaming `directive`, or ask to run it elevated when the deck
+    /// lacks root; its events arrive through [`Self::subscription`].
+    fn start_run(&mut self, directive: Directive, passphrase: Option<Secret>) {
+        self.next_run_id += 1;
+        let run = DirectiveRun {
+            id: self.next_run_id,
+            directive,
+            config_path: self.config_path.clone(),
+            profile: self.profile.clone(),
+            secure_mode: self.secure_mode,
+            params: self.params(),
+            device: self
+                .selected_device
+                .as_ref()
+                .filter(|_| forges_token(directive))
+                .map(|device| device.path.clone()),
+            passphrase: passphrase.or_else(|| {
+                self.fallback_passphrase
+                    .clone()
+                    .filter(|_| forges_token(directive))
+            }),
+            elevated: None,
+            events: 0,
+            frame: 0,
+        };
+        if self.privileged {
+            self.begin_run(run);
+        } else {
+            let command = privilege::directive_command(&run);
+            self.request_elevation(ElevatedAction::Directive(Box::new(run)), command);
         }
     }
 
+    fn begin_run(&mut self, run: DirectiveRun) {
+        self.executing = true;
+        if run.directive == Directive::Doctor {
+            self.doctor_cards.clear();
+        }
+        self.run = Some(run);
+    }
+
+    /// Feed the running directive's events in, animate its progress, and
+    /// listen for keyboard shortcuts.
+    fn subscription(&self) -> Subscription<Message> {
+        let run = match &self.run {
+            Some(run) if self.appearance.reduced_motion => run.subscription(),
+            Some(run) => Subscription::batch([
+                run.subscription(),
+                iced::time::every(std::time::Duration::from_millis(120)).map(|_| Message::Tick),
+            ]),
+            None => Subscription::none(),
+        };
+        Subscription::batch([
+            run,
+            self.tray_subscription(),
+            iced::keyboard::on_key_press(shortcuts::shortcut),
+            iced::time::every(health::HEALTH_INTERVAL).map(|_| Message::CheckHealth),
+        ])
+    }
+
+    /// Re-enumerate removable disks in the background.
+    fn scan_devices(&mut self) -> Task<Message> {
+        if self.devices_scanning {
+            return Task::none();
+        }
+        self.devices_scanning = true;
+        Task::perform(devices::scan(), Message::DevicesScanned)
+    }
+
+    /// Start a background keystatus query unless one is already running.
+    fn reload_datasets(&mut self) -> Task<Message> {
+        if self.datasets_loading {
+            return Task::none();
+        }
+        self.datasets_loading = true;
+        let host = self.host.clone();
+        Task::perform(
+            datasets::load(self.config_path.clone(), self.profile.clone(), host.clone()),
+            move |result| Message::DatasetsLoaded(host.clone(), result),
+        )
+    }
+
     /// Produce the full view tree for the current state.
     fn view(&self) -> iced::Element<'_, Message> {
         let header = self.view_header();
         let main = self.view_body();
         let footer = self.view_footer();
 
-        container(
+        let deck = container(
             column![header, main, footer]
                 .spacing(20)
                 .width(Length::Fill),
         )
         .padding(24)
-        .style(deck_background())
-        .into()
+        .style(deck_background());
+
+        if let Some(prompt) = self.killswitch {
+            modal(
+                deck,
+                self.view_killswitch_dialog(prompt),
+                Message::KillswitchCancel,
+            )
+        } else if let Some(guard) = &self.guard {
+            modal(deck, self.view_guard_dialog(guard), Message::GuardCancel)
+        } else if let Some(prompt) = &self.passphrase_prompt {
+            modal(
+                deck,
+                self.view_passphrase_dialog(prompt),
+                Message::PassphraseCancel,
+            )
+        } else if let Some(prompt) = &self.elevation {
+            modal(
+                deck,
+                self.view_elevation_dialog(prompt),
+                Message::ElevationCancel,
+            )
+        } else {
+            deck.into()
+        }
     }
 
-    /// Check whether the expected USB key location has raw material present.
-    fn detect_key_presence(&self) -> bool {
-        LockchainConfig::load(&self.config_path)
-            .ok()
-            .map(|cfg| cfg.key_hex_path().exists())
-            .unwrap_or(false)
+    /// Re-check health and reload keystatus after something may have changed.
+    fn refresh_state(&mut self) -> Task<Message> {
+        Task::batch([self.check_health(), self.reload_datasets()])
     }
 
     /// Determine if a directive should be interactable based on context.
@@ -343,73 +1059,134 @@ impl LockchainUi {
 
     /// Provide the application theme customisations for Iced.
     fn theme(&self) -> Theme {
-        Theme::TokyoNight
+        self.scheme().iced_theme()
     }
 
     /// Render the title bar and key state indicator.
     fn view_header(&self) -> iced::Element<'_, Message> {
-        let title = text("Control Deck")
+        let title = text(t!("header-title"))
             .size(32)
-            .style(text_color(iced::Color::from_rgb8(0x24, 0xd0, 0xff)));
-        let subtitle = text("Cryptographic ZFS key management — powered by LockChain")
-            .size(16)
-            .style(text_color(iced::Color::from_rgb8(0xff, 0x73, 0xff)));
+            .style(text_color(palette().accent));
+        let subtitle = text(match &self.profile {
+            Some(profile) => t!("header-subtitle-profile", profile = profile.as_str()),
+            None => t!("header-subtitle"),
+        })
+        .size(16)
+        .style(text_color(palette().subheading));
 
         let status_chip = container(
             text(if self.secure_mode {
-                "SECURE"
+                t!("mode-secure")
             } else {
-                "STANDARD"
+                t!("mode-standard")
             })
             .size(14)
             .style(text_color(if self.secure_mode {
-                iced::Color::from_rgb8(0x8a, 0xff, 0x70)
+                palette().success
             } else {
-                iced::Color::from_rgb8(0xff, 0xc1, 0x29)
+                palette().warning
             })),
         )
         .padding([6, 12])
         .style(chip_style(self.secure_mode));
 
         let secure_toggle = toggler(self.secure_mode)
-            .label("Secure")
+            .label(t!("header-secure"))
             .size(22)
             .text_size(16)
             .on_toggle(Message::ToggleSecure);
+        let tray_toggle = toggler(self.tray_mode)
+            .label(t!("header-tray"))
+            .size(22)
+            .text_size(16)
+            .on_toggle(Message::ToggleTray);
 
-        row![
-            column![title, subtitle].spacing(4),
-            Space::with_width(Length::Fill),
+        let controls = row![
+            self.view_health_chips(),
             status_chip,
             secure_toggle,
-            button("Refresh")
-                .padding([10, 18])
-                .style(primary_button())
-                .on_press(Message::Refresh)
+            tray_toggle,
+            self.focusable(
+                Focus::Dashboard,
+                button(text(t!("header-dashboard")))
+                    .padding([10, 18])
+                    .style(help_button())
+                    .on_press(Message::Dashboard(if self.dashboard.is_some() {
+                        DashboardMessage::Close
+                    } else {
+                        DashboardMessage::Open
+                    }))
+            ),
+            self.focusable(
+                Focus::Settings,
+                button(text(t!("header-settings")))
+                    .padding([10, 18])
+                    .style(help_button())
+                    .on_press(Message::Settings(if self.settings_open {
+                        SettingsMessage::Close
+                    } else {
+                        SettingsMessage::Open
+                    }))
+            ),
+            self.focusable(
+                Focus::Refresh,
+                button(text(t!("header-refresh")))
+                    .padding([10, 18])
+                    .style(primary_button())
+                    .on_press(Message::Refresh)
+            )
         ]
         .align_y(Vertical::Center)
+        .spacing(20);
+
+        row![
+            column![title, subtitle].spacing(4),
+            Space::with_width(Length::Fill)
+        ]
+        .push_maybe(self.view_host_picker())
+        .push(controls)
+        .align_y(Vertical::Center)
         .spacing(20)
         .into()
     }
 
-    /// Assemble the three-column layout containing directives, terminal, and activity log.
+    /// Assemble the two-column layout: directives and terminal beside datasets and the activity log.
     fn view_body(&self) -> iced::Element<'_, Message> {
+        if let Some(wizard) = &self.wizard {
+            return self.view_wizard(wizard);
+        }
+        if self.settings_open {
+            return self.view_settings();
+        }
+        if let Some(dashboard) = &self.dashboard {
+            return self.view_dashboard(dashboard);
+        }
+        if let Some(breakglass) = &self.breakglass {
+            return self.view_breakglass(breakglass);
+        }
         let directives: iced::Element<Message> =
             self.view_directive_panel().width(Length::Fill).into();
-        let terminal: iced::Element<Message> =
-            self.view_terminal_panel().width(Length::Fill).into();
+        let command: iced::Element<Message> = self.view_command_panel().width(Length::Fill).into();
 
-        let left_column: iced::Element<Message> = column![directives, terminal]
+        let left_column: iced::Element<Message> = column![directives, command]
             .spacing(16)
             .width(Length::FillPortion(5))
             .into();
 
-        let activity: iced::Element<Message> = self
-            .view_activity_panel()
+        let datasets: iced::Element<Message> = self.view_dataset_panel().width(Length::Fill).into();
+        let activity: iced::Element<Message> =
+            self.view_activity_panel().width(Length::Fill).into();
+
+        let mut right_column = column![datasets].spacing(16);
+        if !self.doctor_cards.is_empty() {
+            right_column = right_column.push(self.view_doctor_panel().width(Length::Fill));
+        }
+        let right_column: iced::Element<Message> = right_column
+            .push(activity)
             .width(Length::FillPortion(7))
             .into();
 
-        row![left_column, activity]
+        row![left_column, right_column]
             .spacing(24)
             .align_y(Vertical::Top)
             .into()
@@ -423,16 +1200,20 @@ impl LockchainUi {
             let enabled = self.directive_enabled(entry.directive);
             let mut button = button(
                 column![
-                    text(entry.title).size(20).style(text_color(if enabled {
-                        iced::Color::from_rgb8(0xe7, 0xff, 0xff)
-                    } else {
-                        iced::Color::from_rgb8(0x55, 0x66, 0x88)
-                    })),
-                    text(entry.subtitle).size(14).style(text_color(if enabled {
-                        iced::Color::from_rgb8(0x67, 0xd6, 0xff)
-                    } else {
-                        iced::Color::from_rgb8(0x44, 0x55, 0x8a)
-                    }))
+                    text(directive_title(entry.directive))
+                        .size(20)
+                        .style(text_color(if enabled {
+                            palette().text
+                        } else {
+                            palette().muted
+                        })),
+                    text(i18n::lookup(entry.subtitle, None))
+                        .size(14)
+                        .style(text_color(if enabled {
+                            palette().info
+                        } else {
+                            palette().muted
+                        }))
                 ]
                 .spacing(4),
             )
@@ -443,14 +1224,34 @@ impl LockchainUi {
             if enabled {
                 button = button.on_press(Message::DirectiveSelected(entry.directive));
             }
-            list = list.push(button);
+            list = list.push(self.focusable(Focus::Directive(entry.directive), button));
         }
+        list = list.push(
+            self.focusable(
+                Focus::BreakGlass,
+                button(
+                    column![
+                        text(t!("breakglass-card-title"))
+                            .size(20)
+                            .style(text_color(palette().text)),
+                        text(t!("breakglass-card-summary"))
+                            .size(14)
+                            .style(text_color(palette().info))
+                    ]
+                    .spacing(4),
+                )
+                .width(Length::Fill)
+                .padding([12, 18])
+                .style(directive_style(self.breakglass.is_some(), true))
+                .on_press(Message::BreakGlass(BreakGlassMessage::Open)),
+            ),
+        );
 
         container(
             column![
-                text("Select Module Directive")
+                text(t!("directives-heading"))
                     .size(18)
-                    .style(text_color(iced::Color::from_rgb8(0xff, 0x51, 0xff))),
+                    .style(text_color(palette().heading)),
                 list.spacing(10)
             ]
             .spacing(16),
@@ -459,52 +1260,65 @@ impl LockchainUi {
         .style(panel_style())
     }
 
-    /// Show terminal-like inputs, status chip, and action buttons for the active directive.
-    fn view_terminal_panel(&self) -> iced::widget::Container<'_, Message> {
-        let input = text_input("Enter command or parameters…", &self.terminal_input)
-            .on_input(Message::TerminalChanged)
-            .size(18)
-            .padding(12)
-            .style(text_input_style());
-
+    /// Show the active directive's parameter form, status chip, and action buttons.
+    fn view_command_panel(&self) -> iced::widget::Container<'_, Message> {
         let execute_enabled = self.directive_enabled(self.active_directive);
+        let passphrase: iced::Element<'_, Message> = if forges_token(self.active_directive) {
+            self.view_fallback_passphrase()
+        } else {
+            column![].into()
+        };
 
-        let mut execute = button(
-            text("Execute")
-                .size(18)
-                .style(text_color(iced::Color::from_rgb8(0x05, 0x08, 0x1f))),
-        )
-        .width(Length::Fill)
-        .padding([12, 18])
-        .style(execute_button(execute_enabled));
+        let label = match &self.run {
+            Some(run) => run.label(self.appearance.reduced_motion),
+            None => t!("command-execute"),
+        };
+        let mut execute = button(text(label).size(18).style(text_color(palette().on_accent)))
+            .width(Length::Fill)
+            .padding([12, 18])
+            .style(execute_button(execute_enabled));
 
-        if execute_enabled {
+        if execute_enabled && !self.executing {
             execute = execute.on_press(Message::Execute);
         }
 
         let status = column![
-            text(format!(
-                "System Status: {}",
-                self.status_line.to_uppercase()
+            text(t!(
+                "command-system-status",
+                status = self.status_line.to_uppercase()
             ))
             .size(14)
-            .style(text_color(iced::Color::from_rgb8(0x8a, 0xff, 0x70))),
-            text(format!(
-                "Active Module: {}",
-                directive_title(self.active_directive)
+            .style(text_color(palette().success)),
+            text(t!(
+                "command-active-module",
+                directive = directive_title(self.active_directive)
             ))
             .size(14)
-            .style(text_color(iced::Color::from_rgb8(0x67, 0xd6, 0xff)))
+            .style(text_color(palette().info)),
+            text(if self.privileged {
+                t!("command-privileges-root")
+            } else {
+                t!("command-privileges-user")
+            })
+            .size(14)
+            .style(text_color(if self.privileged {
+                palette().success
+            } else {
+                palette().warning
+            }))
         ]
+        .push_maybe(self.host.destination().map(|destination| {
+            text(t!("command-local-only", host = destination))
+                .size(14)
+                .style(text_color(palette().warning))
+        }))
         .spacing(4);
 
         let notes: iced::Element<'_, Message> =
             if matches!(self.active_directive, Directive::SelfTest) && !execute_enabled {
-                column![
-                    text("Self-test unavailable until a LockChain key is forged or inserted.")
-                        .size(14)
-                        .style(text_color(iced::Color::from_rgb8(0xff, 0xc1, 0x29)))
-                ]
+                column![text(t!("command-self-test-unavailable"))
+                    .size(14)
+                    .style(text_color(palette().warning))]
                 .spacing(4)
                 .into()
             } else {
@@ -513,28 +1327,38 @@ impl LockchainUi {
 
         container(
             column![
-                text("> User Input Terminal")
-                    .size(18)
-                    .style(text_color(iced::Color::from_rgb8(0xff, 0x51, 0xff))),
+                text(t!(
+                    "command-heading",
+                    directive = directive_title(self.active_directive)
+                ))
+                .size(18)
+                .style(text_color(palette().heading)),
                 column![
-                    text("Command Input:")
-                        .size(14)
-                        .style(text_color(iced::Color::from_rgb8(0x8a, 0xff, 0x70))),
-                    input,
-                    execute,
+                    self.view_directive_form(),
+                    passphrase,
+                    self.focusable(Focus::Execute, execute),
                     status,
                     notes,
                     row![
-                        button("Help")
-                            .padding([10, 16])
-                            .style(help_button())
-                            .on_press(Message::HelpPressed),
-                        button("Killswitch")
-                            .padding([10, 16])
-                            .style(killswitch_button())
-                            .on_press(Message::KillSwitchPressed)
+                        self.focusable(
+                            Focus::Help,
+                            button(text(t!("command-help")))
+                                .padding([10, 16])
+                                .style(help_button())
+                                .on_press(Message::HelpPressed)
+                        ),
+                        self.focusable(
+                            Focus::Killswitch,
+                            button(text(t!("command-killswitch")))
+                                .padding([10, 16])
+                                .style(killswitch_button())
+                                .on_press(Message::KillSwitchPressed)
+                        )
                     ]
-                    .spacing(12)
+                    .spacing(12),
+                    text(t!("command-shortcuts"))
+                        .size(12)
+                        .style(text_color(palette().muted))
                 ]
                 .spacing(12)
             ]
@@ -550,9 +1374,9 @@ impl LockchainUi {
         for item in self.activity.iter().rev() {
             let line = column![
                 row![
-                    text(format!("[{}]", item.timestamp))
+                    text(format!("[{}]", item.clock()))
                         .size(14)
-                        .style(text_color(iced::Color::from_rgb8(0x67, 0xd6, 0xff))),
+                        .style(text_color(palette().info)),
                     text(item.level.label())
                         .size(14)
                         .style(text_color(item.level.color()))
@@ -560,7 +1384,7 @@ impl LockchainUi {
                 .spacing(12),
                 text(&item.message)
                     .size(14)
-                    .style(text_color(iced::Color::from_rgb8(0xe7, 0xff, 0xff)))
+                    .style(text_color(palette().text))
             ]
             .spacing(6);
             column = column.push(container(line).padding([8, 12]).style(activity_entry()));
@@ -568,11 +1392,34 @@ impl LockchainUi {
 
         let scroll = scrollable(column.spacing(12)).height(Length::Fill);
 
+        let mut export = button(text(if self.exporting {
+            t!("activity-exporting")
+        } else {
+            t!("activity-export")
+        }))
+        .padding([8, 14])
+        .style(help_button());
+        if !self.exporting {
+            export = export.on_press(Message::ExportLog);
+        }
+
         container(
             column![
-                text("Runtime Activity Feed")
-                    .size(18)
-                    .style(text_color(iced::Color::from_rgb8(0xff, 0x51, 0xff))),
+                row![
+                    text(t!("activity-heading"))
+                        .size(18)
+                        .style(text_color(palette().heading)),
+                    Space::with_width(Length::Fill),
+                    pick_list(
+                        ExportFormat::ALL,
+                        Some(self.export_format),
+                        Message::ExportFormatSelected
+                    )
+                    .text_size(14),
+                    self.focusable(Focus::Export, export)
+                ]
+                .spacing(8)
+                .align_y(Vertical::Center),
                 scroll
             ]
             .spacing(16),
@@ -584,13 +1431,16 @@ impl LockchainUi {
     /// Render the footer with a simple status line and dataset summary.
     fn view_footer(&self) -> iced::Element<'_, Message> {
         row![
-            text(format!("Total Events: {}", self.total_events))
+            text(t!("footer-total-events", count = self.total_events))
                 .size(14)
-                .style(text_color(iced::Color::from_rgb8(0x67, 0xd6, 0xff))),
+                .style(text_color(palette().info)),
             Space::with_width(Length::Fill),
-            text(format!("Status: {}", self.status_line.to_uppercase()))
-                .size(14)
-                .style(text_color(iced::Color::from_rgb8(0x8a, 0xff, 0x70)))
+            text(t!(
+                "footer-status",
+                status = self.status_line.to_uppercase()
+            ))
+            .size(14)
+            .style(text_color(palette().success))
         ]
         .align_y(Vertical::Center)
         .into()
@@ -603,14 +1453,18 @@ impl LockchainUi {
         }
     }
 
-    /// Push a single activity entry and prune the backlog when needed.
+    /// Push a single activity entry, persist it, and prune the backlog when
+    /// needed.
     fn push_activity(&mut self, level: ActivityLevel, message: impl Into<String>) {
-        let ts = Local::now().format("%H:%M:%S").to_string();
-        self.activity.push(ActivityItem {
-            timestamp: ts,
+        let item = ActivityItem {
+            timestamp: Local::now(),
             level,
             message: message.into(),
-        });
+        };
+        if let Err(err) = self.activity_log.append(&item) {
+            log::warn!("failed to persist activity entry: {err}");
+        }
+        self.activity.push(item);
         self.total_events += 1;
         if self.activity.len() > 400 {
             let excess = self.activity.len() - 400;
@@ -619,157 +1473,127 @@ impl LockchainUi {
     }
 }
 
+/// Directives that write a key token and so need a device picked.
+fn forges_token(directive: Directive) -> bool {
+    matches!(directive, Directive::NewKey | Directive::NewKeySafe)
+}
+
 /// Human-friendly label for directives when status lines need text.
-fn directive_title(directive: Directive) -> &'static str {
+fn directive_title(directive: Directive) -> String {
     match directive {
-        Directive::NewKey => "New Key",
-        Directive::NewKeySafe => "New Key (Safe mode)",
-        Directive::SelfTest => "Self-test",
-        Directive::RecoverKey => "Recover Key",
-        Directive::SelfHeal => "Self-heal Issues",
-        Directive::Doctor => "Doctor",
+        Directive::NewKey => t!("directive-new-key"),
+        Directive::NewKeySafe => t!("directive-new-key-safe"),
+        Directive::SelfTest => t!("directive-self-test"),
+        Directive::SelfHeal => t!("directive-self-heal"),
+        Directive::Doctor => t!("directive-doctor"),
     }
 }
 
 /// Contextual help string shown in the terminal panel.
-fn help_text(directive: Directive) -> &'static str {
+fn help_text(directive: Directive) -> String {
     match directive {
-        Directive::NewKey => "Forge a new 32-byte USB key. Provide dataset=<name> to target a specific encryption root.",
-        Directive::NewKeySafe => "Safe forge prompts for review. Supply dataset=<name> as needed.",
-        Directive::SelfTest => "Provision a scratch encrypted pool, unlock it with the current key, then tear it down. Supports dataset=<name>, device=/dev/sdX, mount=/run/lockchain, filename=lockchain.key, rebuild=false, passphrase=<secret>.",
-        Directive::RecoverKey => "Derive fallback key using passphrase. Provide dataset=<name> passphrase=<secret> [output=/path].",
-        Directive::SelfHeal => "Runs diagnostics against key file, checksum, and dataset keystatus.",
-        Directive::Doctor => "Runs self-heal plus systemd/journal/initramfs audits. Provide no args; review warnings for remediation guidance.",
+        Directive::NewKey => t!("directive-new-key-help"),
+        Directive::NewKeySafe => t!("directive-new-key-safe-help"),
+        Directive::SelfTest => t!("directive-self-test-help"),
+        Directive::SelfHeal => t!("directive-self-heal-help"),
+        Directive::Doctor => t!("directive-doctor-help"),
     }
 }
 
-/// Parse key=value arguments from the terminal input field.
-fn parse_kv(input: &str) -> (HashMap<String, String>, Vec<String>) {
-    let mut map = HashMap::new();
-    let mut free = Vec::new();
-
-    for token in input.split_whitespace() {
-        if let Some((key, value)) = token.split_once('=') {
-            map.insert(key.to_lowercase(), value.to_string());
-        } else if let Some((key, value)) = token.split_once(':') {
-            map.insert(key.to_lowercase(), value.to_string());
-        } else {
-            free.push(token.to_string());
+/// Profile chosen with `--profile <name>` (or `LOCKCHAIN_PROFILE`), if any.
+fn selected_profile(mut args: impl Iterator<Item = String>) -> Option<String> {
+    while let Some(arg) = args.next() {
+        if arg == "--profile" {
+            return args.next();
+        }
+        if let Some(name) = arg.strip_prefix("--profile=") {
+            return Some(name.to_string());
         }
     }
-
-    (map, free)
+    std::env::var("LOCKCHAIN_PROFILE")
+        .ok()
+        .filter(|name| !name.is_empty())
 }
 
-/// Accept several truthy strings when toggling options via text.
-fn parse_bool(input: &str) -> bool {
-    matches!(
-        input.to_ascii_lowercase().as_str(),
-        "1" | "true" | "yes" | "on"
-    )
-}
-
-/// Kick off the selected workflow and return a `Message` when finished.
-async fn run_directive(
-    config_path: PathBuf,
-    directive: Directive,
-    secure_mode: bool,
-    raw_input: String,
+/// Run the selected workflow to completion, handing each event to `on_event`
+/// as it happens and Doctor's remedies to `on_remedies`.
+fn run_directive(
+    run: &DirectiveRun,
+    on_event: impl FnMut(&WorkflowEvent),
+    on_remedies: impl FnOnce(Vec<Remedy>),
 ) -> Result<WorkflowReport, String> {
-    let mut config = LockchainConfig::load(&config_path).map_err(|e| e.to_string())?;
+    let mut config = LockchainConfig::load_profile(&run.config_path, run.profile.as_deref())
+        .map_err(|e| e.to_string())?;
     let provider = SystemZfsProvider::from_config(&config).map_err(|err| format!("{err}"))?;
 
-    let (kv, free) = parse_kv(&raw_input);
+    let params = &run.params;
+    let directive = run.directive;
 
     match directive {
         Directive::NewKey | Directive::NewKeySafe => {
-            let dataset = resolve_dataset(&config, &kv, &free)?;
+            let dataset = resolve_dataset(&config, params)?;
             let mode = if matches!(directive, Directive::NewKeySafe) {
                 ForgeMode::Safe
             } else {
                 ForgeMode::Standard
             };
 
-            let mut options = ProvisionOptions::default();
-            if let Some(device) = kv.get("device").map(|s| s.to_string()) {
-                options.usb_device = Some(device);
-            }
-            if let Some(mount) = kv.get("mount").map(|s| PathBuf::from(s)) {
-                options.mountpoint = Some(mount);
-            }
-            if let Some(file) = kv
-                .get("filename")
-                .or_else(|| kv.get("file"))
-                .map(|s| s.to_string())
-            {
-                options.key_filename = Some(file);
-            }
-            if let Some(pass) = kv.get("passphrase").map(|s| s.to_string()) {
-                options.passphrase = Some(pass);
-            }
-            if let Some(force) = kv.get("force").map(|v| parse_bool(v)) {
-                options.force_wipe = force;
-            } else if matches!(mode, ForgeMode::Standard) {
-                options.force_wipe = true;
-            }
-            if let Some(rebuild) = kv.get("rebuild").map(|v| parse_bool(v)) {
-                options.rebuild_initramfs = rebuild;
-            }
+            let options = ProvisionOptions {
+                usb_device: run.device.clone(),
+                mountpoint: params.text("mount").map(PathBuf::from),
+                key_filename: params.text("filename").map(str::to_string),
+                passphrase: run
+                    .passphrase
+                    .as_ref()
+                    .map(|pass| pass.expose().to_string()),
+                force_wipe: mode == ForgeMode::Standard || params.flag("force_wipe"),
+                rebuild_initramfs: !params.flag("no_rebuild"),
+            };
 
-            workflow::forge_key(&mut config, &provider, &dataset, mode, options)
+            workflow::forge_key_observed(&mut config, &provider, &dataset, mode, options, on_event)
                 .map_err(|e| e.to_string())
         }
         Directive::SelfTest => {
-            let dataset = resolve_dataset(&config, &kv, &free)?;
-            workflow::self_test(&config, provider, &dataset, secure_mode).map_err(|e| e.to_string())
-        }
-        Directive::RecoverKey => {
-            let dataset = resolve_dataset(&config, &kv, &free)?;
-            let passphrase = kv
-                .get("passphrase")
-                .map(|s| s.to_string())
-                .or_else(|| {
-                    if !free.is_empty() {
-                        Some(free.join(" "))
-                    } else {
-                        None
-                    }
-                })
-                .ok_or_else(|| "passphrase=<secret> required for recovery".to_string())?;
-
-            let output = kv
-                .get("output")
-                .map(PathBuf::from)
-                .unwrap_or_else(|| default_recovery_path(&dataset));
-
-            workflow::recover_key(&config, provider, &dataset, passphrase.as_bytes(), &output)
+            let dataset = resolve_dataset(&config, params)?;
+            let mut options = SelfTestOptions {
+                strict_usb: run.secure_mode || params.flag("strict_usb"),
+                keep_on_failure: params.flag("keep_on_failure"),
+                ..SelfTestOptions::default()
+            };
+            if let Some(size) = params.size("pool_size")? {
+                options.pool_size = size;
+            }
+            workflow::self_test_observed(&config, provider, &dataset, options, on_event)
                 .map_err(|e| e.to_string())
         }
-        Directive::SelfHeal => workflow::self_heal(&config, provider).map_err(|e| e.to_string()),
-        Directive::Doctor => workflow::doctor(&config, provider).map_err(|e| e.to_string()),
+        Directive::SelfHeal => {
+            workflow::self_heal_observed(&config, provider, on_event).map_err(|e| e.to_string())
+        }
+        Directive::Doctor => {
+            let doctor = workflow::doctor_report_observed(
+                &config,
+                provider,
+                DoctorMode::CheckOnly,
+                on_event,
+            )
+            .map_err(|e| e.to_string())?;
+            on_remedies(doctor.actions);
+            Ok(doctor.report)
+        }
     }
 }
 
 /// Reuse CLI dataset resolution semantics inside the UI.
-fn resolve_dataset(
-    config: &LockchainConfig,
-    kv: &HashMap<String, String>,
-    free: &[String],
-) -> Result<String, String> {
-    if let Some(ds) = kv.get("dataset") {
-        return Ok(ds.clone());
-    }
-    if let Some(first) = free.first() {
-        if first.contains('/') {
-            return Ok(first.clone());
-        }
+fn resolve_dataset(config: &LockchainConfig, params: &ParamValues) -> Result<String, String> {
+    if let Some(dataset) = params.text("dataset") {
+        return Ok(dataset.to_string());
     }
     config
         .policy
         .datasets
         .first()
         .cloned()
-        .ok_or_else(|| "No dataset configured; add one to policy.datasets".to_string())
+        .ok_or_else(|| t!("dataset-none-configured"))
 }
 
 /// Derive a sensible filename for fallback key recovery output.
@@ -779,43 +1603,97 @@ fn default_recovery_path(dataset: &str) -> PathBuf {
     Path::new("/var/lib/lockchain").join(format!("{}_{}.key", sanitized, timestamp))
 }
 
+/// Lay `dialog` over a dimmed `base`; clicking outside the dialog sends `on_blur`.
+fn modal<'a>(
+    base: impl Into<iced::Element<'a, Message>>,
+    dialog: impl Into<iced::Element<'a, Message>>,
+    on_blur: Message,
+) -> iced::Element<'a, Message> {
+    stack![
+        base.into(),
+        opaque(
+            mouse_area(center(opaque(dialog)).style(|_| {
+                iced::widget::container::Style {
+                    background: Some(iced::Background::Color(iced::Color::from_rgba(
+                        0.0, 0.0, 0.0, 0.75,
+                    ))),
+                    ..Default::default()
+                }
+            }))
+            .on_press(on_blur)
+        )
+    ]
+    .into()
+}
+
 /// Base background styling for the entire control deck.
 fn deck_background() -> impl Fn(&Theme) -> iced::widget::container::Style + Copy {
     |_| iced::widget::container::Style {
-        background: Some(iced::Background::Color(iced::Color::from_rgb8(
-            0x05, 0x08, 0x1f,
-        ))),
+        background: Some(iced::Background::Color(palette().background)),
         ..Default::default()
     }
 }
 
 /// Shared styling for the directive/terminal/activity panels.
 fn panel_style() -> impl Fn(&Theme) -> iced::widget::container::Style + Copy {
-    |_| iced::widget::container::Style {
-        background: Some(iced::Background::Color(iced::Color::from_rgba(
-            0.05, 0.08, 0.2, 0.7,
-        ))),
-        border: Border {
-            radius: Radius::from(12.0),
-            width: 1.5,
-            color: iced::Color::from_rgb8(0x24, 0xd0, 0xff),
-        },
-        ..Default::default()
+    |_| {
+        let palette = palette();
+        iced::widget::container::Style {
+            background: Some(iced::Background::Color(palette.panel)),
+            border: Border {
+                radius: Radius::from(12.0),
+                width: 1.5 * palette.stroke,
+                color: palette.accent,
+            },
+            ..Default::default()
+        }
+    }
+}
+
+/// Security-level framing for confirmation dialogs.
+fn dialog_style() -> impl Fn(&Theme) -> iced::widget::container::Style + Copy {
+    |_| {
+        let palette = palette();
+        iced::widget::container::Style {
+            background: Some(iced::Background::Color(palette.dialog)),
+            border: Border {
+                radius: Radius::from(12.0),
+                width: 2.0 * palette.stroke,
+                color: palette.subheading,
+            },
+            ..Default::default()
+        }
     }
 }
 
 /// Container styling for individual activity log entries.
 fn activity_entry() -> impl Fn(&Theme) -> iced::widget::container::Style + Copy {
-    |_| iced::widget::container::Style {
-        background: Some(iced::Background::Color(iced::Color::from_rgba(
-            0.03, 0.05, 0.18, 0.8,
-        ))),
+    |_| {
+        let palette = palette();
+        iced::widget::container::Style {
+            background: Some(iced::Background::Color(palette.entry)),
+            border: Border {
+                radius: Radius::from(8.0),
+                width: palette.stroke,
+                color: palette.subheading,
+            },
+            ..Default::default()
+        }
+    }
+}
+
+/// Greyed-out look shared by disabled directive cards and the Execute button.
+fn disabled_button(radius: f32) -> ButtonStyle {
+    let palette = palette();
+    ButtonStyle {
+        background: Some(iced::Background::Color(palette.disabled)),
+        text_color: palette.muted,
         border: Border {
-            radius: Radius::from(8.0),
-            width: 1.0,
-            color: iced::Color::from_rgb8(0xff, 0x73, 0xff),
+            color: palette.outline,
+            width: palette.stroke,
+            radius: Radius::from(radius),
         },
-        ..Default::default()
+        ..ButtonStyle::default()
     }
 }
 
@@ -825,41 +1703,27 @@ fn directive_style(
     enabled: bool,
 ) -> impl Fn(&Theme, ButtonStatus) -> ButtonStyle + Copy {
     move |_theme, _status| {
+        let palette = palette();
         if !enabled {
-            ButtonStyle {
-                background: Some(iced::Background::Color(iced::Color::from_rgb8(
-                    0x12, 0x15, 0x29,
-                ))),
-                text_color: iced::Color::from_rgb8(0x55, 0x66, 0x88),
-                border: Border {
-                    color: iced::Color::from_rgb8(0x25, 0x28, 0x40),
-                    width: 1.0,
-                    radius: Radius::from(10.0),
-                },
-                ..ButtonStyle::default()
-            }
+            disabled_button(10.0)
         } else if active {
             ButtonStyle {
-                background: Some(iced::Background::Color(iced::Color::from_rgb8(
-                    0x1a, 0x2b, 0x66,
-                ))),
-                text_color: iced::Color::from_rgb8(0xe7, 0xff, 0xff),
+                background: Some(iced::Background::Color(palette.selected)),
+                text_color: palette.text,
                 border: Border {
-                    color: iced::Color::from_rgb8(0xff, 0x73, 0xff),
-                    width: 2.0,
+                    color: palette.subheading,
+                    width: 2.0 * palette.stroke,
                     radius: Radius::from(10.0),
                 },
                 ..ButtonStyle::default()
             }
         } else {
             ButtonStyle {
-                background: Some(iced::Background::Color(iced::Color::from_rgba(
-                    0.07, 0.10, 0.24, 0.8,
-                ))),
-                text_color: iced::Color::from_rgb8(0xe7, 0xff, 0xff),
+                background: Some(iced::Background::Color(palette.idle)),
+                text_color: palette.text,
                 border: Border {
-                    color: iced::Color::from_rgb8(0x24, 0xd0, 0xff),
-                    width: 1.0,
+                    color: palette.accent,
+                    width: palette.stroke,
                     radius: Radius::from(10.0),
                 },
                 ..ButtonStyle::default()
@@ -870,36 +1734,11 @@ fn directive_style(
 
 /// Style sheet for the main Execute button depending on availability.
 fn execute_button(enabled: bool) -> impl Fn(&Theme, ButtonStatus) -> ButtonStyle + Copy {
-    move |_theme, status| {
-        if !enabled {
-            ButtonStyle {
-                background: Some(iced::Background::Color(iced::Color::from_rgb8(
-                    0x12, 0x15, 0x29,
-                ))),
-                text_color: iced::Color::from_rgb8(0x55, 0x66, 0x88),
-                border: Border {
-                    color: iced::Color::from_rgb8(0x25, 0x28, 0x40),
-                    width: 1.0,
-                    radius: Radius::from(8.0),
-                },
-                ..ButtonStyle::default()
-            }
+    move |theme, status| {
+        if enabled {
+            primary_button()(theme, status)
         } else {
-            let base = iced::Color::from_rgb8(0x24, 0xd0, 0xff);
-            let background = match status {
-                ButtonStatus::Pressed => iced::Color::from_rgb8(0x1a, 0xa0, 0xc8),
-                _ => base,
-            };
-            ButtonStyle {
-                background: Some(iced::Background::Color(background)),
-                text_color: iced::Color::from_rgb8(0x05, 0x08, 0x1f),
-                border: Border {
-                    color: iced::Color::from_rgb8(0x24, 0xd0, 0xff),
-                    width: 1.0,
-                    radius: Radius::from(8.0),
-                },
-                ..ButtonStyle::default()
-            }
+            disabled_button(8.0)
         }
     }
 }
@@ -907,17 +1746,17 @@ fn execute_button(enabled: bool) -> impl Fn(&Theme, ButtonStatus) -> ButtonStyle
 /// Reusable primary button style for positive actions.
 fn primary_button() -> impl Fn(&Theme, ButtonStatus) -> ButtonStyle + Copy {
     move |_theme, status| {
-        let base = iced::Color::from_rgb8(0x24, 0xd0, 0xff);
+        let palette = palette();
         let background = match status {
-            ButtonStatus::Pressed => iced::Color::from_rgb8(0x1a, 0xa0, 0xc8),
-            _ => base,
+            ButtonStatus::Pressed => palette.accent_pressed,
+            _ => palette.accent,
         };
         ButtonStyle {
             background: Some(iced::Background::Color(background)),
-            text_color: iced::Color::from_rgb8(0x05, 0x08, 0x1f),
+            text_color: palette.on_accent,
             border: Border {
-                color: iced::Color::from_rgb8(0x24, 0xd0, 0xff),
-                width: 1.0,
+                color: palette.accent,
+                width: palette.stroke,
                 radius: Radius::from(8.0),
             },
             ..ButtonStyle::default()
@@ -927,83 +1766,86 @@ fn primary_button() -> impl Fn(&Theme, ButtonStatus) -> ButtonStyle + Copy {
 
 /// Button styling for the inline help toggle.
 fn help_button() -> impl Fn(&Theme, ButtonStatus) -> ButtonStyle + Copy {
-    move |_theme, _status| ButtonStyle {
-        background: Some(iced::Background::Color(iced::Color::from_rgb8(
-            0x12, 0x66, 0x4f,
-        ))),
-        text_color: iced::Color::from_rgb8(0xe7, 0xff, 0xff),
-        border: Border {
-            color: iced::Color::from_rgb8(0x12, 0x66, 0x4f),
-            width: 1.0,
-            radius: Radius::from(6.0),
-        },
-        ..ButtonStyle::default()
+    move |_theme, _status| {
+        let palette = palette();
+        ButtonStyle {
+            background: Some(iced::Background::Color(palette.help)),
+            text_color: palette.help_text,
+            border: Border {
+                color: palette.help,
+                width: palette.stroke,
+                radius: Radius::from(6.0),
+            },
+            ..ButtonStyle::default()
+        }
     }
 }
 
 /// Styling for the kill-switch button that stands out from primary actions.
 fn killswitch_button() -> impl Fn(&Theme, ButtonStatus) -> ButtonStyle + Copy {
-    move |_theme, _status| ButtonStyle {
-        background: Some(iced::Background::Color(iced::Color::from_rgb8(
-            0x70, 0x13, 0x39,
-        ))),
-        text_color: iced::Color::from_rgb8(0xff, 0x73, 0xff),
-        border: Border {
-            color: iced::Color::from_rgb8(0x70, 0x13, 0x39),
-            width: 1.0,
-            radius: Radius::from(6.0),
-        },
-        ..ButtonStyle::default()
+    move |_theme, _status| {
+        let palette = palette();
+        ButtonStyle {
+            background: Some(iced::Background::Color(palette.killswitch)),
+            text_color: palette.killswitch_text,
+            border: Border {
+                color: palette.killswitch,
+                width: palette.stroke,
+                radius: Radius::from(6.0),
+            },
+            ..ButtonStyle::default()
+        }
     }
 }
 
 /// The pill-style indicator that shows whether secure mode is enabled.
 fn chip_style(secure: bool) -> impl Fn(&Theme) -> iced::widget::container::Style + Copy {
-    move |_| iced::widget::container::Style {
-        background: Some(iced::Background::Color(if secure {
-            iced::Color::from_rgba(0.08, 0.20, 0.14, 0.9)
-        } else {
-            iced::Color::from_rgba(0.20, 0.12, 0.24, 0.9)
-        })),
-        border: Border {
-            radius: Radius::from(999.0),
-            width: 1.0,
-            color: if secure {
-                iced::Color::from_rgb8(0x8a, 0xff, 0x70)
+    move |_| {
+        let palette = palette();
+        iced::widget::container::Style {
+            background: Some(iced::Background::Color(if secure {
+                palette.secure_chip
             } else {
-                iced::Color::from_rgb8(0xff, 0xc1, 0x29)
+                palette.standard_chip
+            })),
+            border: Border {
+                radius: Radius::from(999.0),
+                width: palette.stroke,
+                color: if secure {
+                    palette.success
+                } else {
+                    palette.warning
+                },
             },
-        },
-        ..Default::default()
+            ..Default::default()
+        }
     }
 }
-/// Text input styling that keeps the neon look while typing.
+/// Text input styling in the active palette.
 fn text_input_style(
 ) -> impl Fn(&Theme, iced::widget::text_input::Status) -> iced::widget::text_input::Style + Copy {
     move |_theme, status| {
+        let palette = palette();
         let border = match status {
-            iced::widget::text_input::Status::Focused => iced::Color::from_rgb8(0x24, 0xd0, 0xff),
-            _ => iced::Color::from_rgb8(0x3a, 0x45, 0x7d),
+            iced::widget::text_input::Status::Focused => palette.accent,
+            _ => palette.input_border,
         };
         iced::widget::text_input::Style {
-            background: iced::Background::Color(iced::Color::from_rgba(0.04, 0.07, 0.20, 0.9)),
+            background: iced::Background::Color(palette.input),
             border: Border {
                 radius: Radius::from(8.0),
-                width: 1.0,
+                width: palette.stroke,
                 color: border,
             },
-            icon: iced::Color::WHITE,
-            placeholder: iced::Color::from_rgb8(0x67, 0xd6, 0xff),
-            value: iced::Color::from_rgb8(0xe7, 0xff, 0xff),
-            selection: iced::Color::from_rgb8(0x24, 0xd0, 0xff),
+            icon: palette.text,
+            placeholder: palette.info,
+            value: palette.text,
+            selection: palette.accent,
         }
     }
 }
 
 /// Helper to override text color based on the theme palette.
 fn text_color(color: iced::Color) -> impl Fn(&Theme) -> iced::widget::text::Style + Copy {
-    move |_| iced::widget::text::Style {
-        color: Some(color),
-        ..Default::default()
-    }
+    move |_| iced::widget::text::Style { color: Some(color) }
 }
@@ -0,0 +1,235 @@
+//! Masked entry for the fallback passphrase seeded when a new key is
+//! forged, and the redacted `Secret` the deck keeps passphrases in.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use iced::alignment::Vertical;
+use iced::border::{Border, Radius};
+use iced::widget::{button, column, container, progress_bar, row, text, text_input, Space};
+use iced::{Background, Length};
+use zeroize::Zeroizing;
+
+use crate::i18n::t;
+use crate::theme::palette;
+use crate::{
+    dialog_style, help_button, primary_button, text_color, text_input_style, LockchainUi, Message,
+};
+
+/// Passphrase text held by the deck; wiped on drop and redacted from `Debug`.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub(crate) struct Secret(Zeroizing<String>);
+
+impl Secret {
+    pub(crate) fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self(Zeroizing::new(value))
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(<redacted>)")
+    }
+}
+
+/// Contents of the open passphrase dialog.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PassphrasePrompt {
+    pub(crate) passphrase: Secret,
+    pub(crate) confirm: Secret,
+}
+
+impl PassphrasePrompt {
+    /// Both fields are filled in and agree.
+    pub(crate) fn ready(&self) -> bool {
+        !self.passphrase.is_empty() && self.passphrase == self.confirm
+    }
+
+    fn mismatch(&self) -> bool {
+        !self.confirm.is_empty() && self.passphrase != self.confirm
+    }
+}
+
+/// Rough entropy estimate in bits: the character pool a passphrase draws
+/// from, with repeated characters counted at a quarter.
+fn estimate_bits(passphrase: &str) -> f32 {
+    let mut pool = 0u32;
+    if passphrase.chars().any(|c| c.is_ascii_lowercase()) {
+        pool += 26;
+    }
+    if passphrase.chars().any(|c| c.is_ascii_uppercase()) {
+        pool += 26;
+    }
+    if passphrase.chars().any(|c| c.is_ascii_digit()) {
+        pool += 10;
+    }
+    if passphrase
+        .chars()
+        .any(|c| c.is_ascii_punctuation() || c == ' ')
+    {
+        pool += 33;
+    }
+    if !passphrase.is_ascii() {
+        pool += 100;
+    }
+    if pool == 0 {
+        return 0.0;
+    }
+
+    let length = passphrase.chars().count();
+    let distinct = passphrase.chars().collect::<HashSet<_>>().len();
+    let effective = distinct as f32 + (length - distinct) as f32 * 0.25;
+    effective * (pool as f32).log2()
+}
+
+/// Meter label and colour for an estimate from [`estimate_bits`].
+fn strength_band(bits: f32) -> (String, iced::Color) {
+    if bits < 40.0 {
+        (t!("strength-weak"), palette().danger)
+    } else if bits < 60.0 {
+        (t!("strength-fair"), palette().warning)
+    } else if bits < 80.0 {
+        (t!("strength-good"), palette().accent)
+    } else {
+        (t!("strength-strong"), palette().success)
+    }
+}
+
+impl LockchainUi {
+    /// Masked passphrase and confirmation fields with a strength meter.
+    pub(crate) fn view_passphrase_dialog<'a>(
+        &self,
+        prompt: &'a PassphrasePrompt,
+    ) -> iced::Element<'a, Message> {
+        let mut body = column![
+            text(t!("passphrase-title"))
+                .size(22)
+                .style(text_color(palette().subheading)),
+            text(t!("passphrase-explainer"))
+                .size(14)
+                .style(text_color(palette().text)),
+        ]
+        .spacing(14);
+
+        let mut passphrase = text_input(&t!("passphrase-placeholder"), prompt.passphrase.expose())
+            .secure(true)
+            .on_input(|value| Message::PassphraseInput(Secret::from(value)))
+            .size(16)
+            .padding(10)
+            .style(text_input_style());
+        let mut confirm = text_input(
+            &t!("passphrase-confirm-placeholder"),
+            prompt.confirm.expose(),
+        )
+        .secure(true)
+        .on_input(|value| Message::PassphraseConfirmInput(Secret::from(value)))
+        .size(16)
+        .padding(10)
+        .style(text_input_style());
+        let mut proceed = button(text(t!("passphrase-use")))
+            .padding([10, 16])
+            .style(primary_button());
+        if prompt.ready() {
+            passphrase = passphrase.on_submit(Message::PassphraseSubmit);
+            confirm = confirm.on_submit(Message::PassphraseSubmit);
+            proceed = proceed.on_press(Message::PassphraseSubmit);
+        }
+        body = body.push(passphrase).push(confirm);
+
+        if !prompt.passphrase.is_empty() {
+            let bits = estimate_bits(prompt.passphrase.expose());
+            let (label, color) = strength_band(bits);
+            body = body.push(
+                row![
+                    progress_bar(0.0..=100.0, bits.min(100.0))
+                        .height(6)
+                        .style(move |_theme| progress_bar::Style {
+                            background: Background::Color(palette().outline),
+                            bar: Background::Color(color),
+                            border: Border {
+                                radius: Radius::from(3.0),
+                                ..Border::default()
+                            },
+                        }),
+                    text(t!("passphrase-strength", band = label, bits = bits.round()))
+                        .size(13)
+                        .style(text_color(color))
+                ]
+                .spacing(10)
+                .align_y(Vertical::Center),
+            );
+        }
+        if prompt.mismatch() {
+            body = body.push(
+                text(t!("passphrase-mismatch"))
+                    .size(13)
+                    .style(text_color(palette().danger)),
+            );
+        }
+
+        container(
+            body.push(
+                row![
+                    Space::with_width(Length::Fill),
+                    button(text(t!("dialog-cancel")))
+                        .padding([10, 16])
+                        .style(help_button())
+                        .on_press(Message::PassphraseCancel),
+                    proceed
+                ]
+                .spacing(12),
+            ),
+        )
+        .padding(24)
+        .max_width(560)
+        .style(dialog_style())
+        .into()
+    }
+
+    /// Fallback passphrase status with buttons to set or clear it, shown
+    /// under the token picker.
+    pub(crate) fn view_fallback_passphrase(&self) -> iced::Element<'_, Message> {
+        let (status, color) = if self.fallback_passphrase.is_some() {
+            (t!("passphrase-status-set"), palette().success)
+        } else {
+            (t!("passphrase-status-unset"), palette().info)
+        };
+
+        let mut set = button(text(if self.fallback_passphrase.is_some() {
+            t!("passphrase-change")
+        } else {
+            t!("passphrase-set")
+        }))
+        .padding([8, 14])
+        .style(help_button());
+        let mut clear = button(text(t!("passphrase-clear")))
+            .padding([8, 14])
+            .style(help_button());
+        if !self.executing {
+            set = set.on_press(Message::SetFallbackPassphrase);
+            if self.fallback_passphrase.is_some() {
+                clear = clear.on_press(Message::ClearFallbackPassphrase);
+            }
+        }
+
+        row![
+            text(status).size(14).style(text_color(color)),
+            Space::with_width(Length::Fill),
+            set,
+            clear
+        ]
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .into()
+    }
+}
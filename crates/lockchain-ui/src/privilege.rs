@@ -0,0 +1,503 @@
+//! Privilege escalation: when the deck runs without root, directives and
+//! dataset actions are handed to `lockchain-cli` through pkexec, after the
+//! operator has seen the exact command that will run elevated.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread::{self, JoinHandle};
+
+use iced::widget::{button, column, container, row, text, Space};
+use iced::{Font, Length};
+use lockchain_core::workflow::{ParamKind, WorkflowEvent, WorkflowLevel, WorkflowReport};
+use zeroize::Zeroizing;
+
+use crate::breakglass::Destination;
+
+use crate::forms::directive_params;
+use crate::i18n::t;
+use crate::passphrase::Secret;
+use crate::progress::DirectiveRun;
+use crate::theme::palette;
+use crate::{
+    dialog_style, directive_title, help_button, killswitch_button, panel_style, text_color,
+    ActivityLevel, Directive, LockchainUi, Message,
+};
+
+/// Overrides which CLI binary is run elevated.
+const CLI_ENV: &str = "LOCKCHAIN_CLI";
+const DEFAULT_CLI: &str = "/usr/bin/lockchain-cli";
+/// pkexec exit codes for a dismissed dialog and a refused authorization.
+const PKEXEC_DISMISSED: i32 = 126;
+const PKEXEC_DENIED: i32 = 127;
+
+/// Whether the deck runs with an effective uid of root.
+pub(crate) fn is_root() -> bool {
+    // SAFETY: geteuid has no preconditions and cannot fail.
+    unsafe { libc::geteuid() == 0 }
+}
+
+/// `$LOCKCHAIN_CLI`, the `lockchain-cli` installed beside this binary, or
+/// the packaged path.
+fn cli_path() -> PathBuf {
+    if let Some(path) = std::env::var_os(CLI_ENV).filter(|value| !value.is_empty()) {
+        return PathBuf::from(path);
+    }
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join("lockchain-cli")))
+        .filter(|candidate| candidate.exists())
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_CLI))
+}
+
+fn pkexec_path() -> Option<PathBuf> {
+    std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths)
+            .map(|dir| dir.join("pkexec"))
+            .find(|candidate| candidate.exists())
+    })
+}
+
+/// A `lockchain-cli` invocation to run as root.
+#[derive(Debug, Clone)]
+pub(crate) struct ElevatedCommand {
+    /// What the command does, in the operator's terms.
+    pub(crate) summary: String,
+    cli: PathBuf,
+    args: Vec<String>,
+    /// Handed to the command on standard input rather than argv.
+    stdin: Option<Secret>,
+}
+
+impl ElevatedCommand {
+    fn new(summary: String, config_path: &Path, profile: Option<&str>) -> Self {
+        let mut args = vec![
+            "--config".to_string(),
+            config_path.display().to_string(),
+            "--yes".to_string(),
+        ];
+        if let Some(profile) = profile {
+            args.push("--profile".into());
+            args.push(profile.to_string());
+        }
+        Self {
+            summary,
+            cli: cli_path(),
+            args,
+            stdin: None,
+        }
+    }
+
+    fn arg(&mut self, arg: impl Into<String>) {
+        self.args.push(arg.into());
+    }
+
+    /// The command line as shown before it runs; the passphrase never
+    /// appears in it.
+    pub(crate) fn command_line(&self) -> String {
+        let mut line = format!("pkexec {}", self.cli.display());
+        for arg in &self.args {
+            if arg.contains(char::is_whitespace) {
+                line.push_str(&format!(" '{arg}'"));
+            } else {
+                line.push(' ');
+                line.push_str(arg);
+            }
+        }
+        line
+    }
+}
+
+/// The CLI equivalent of `run`: the same subcommand, with the form values
+/// spelled as the flags they were generated from.
+pub(crate) fn directive_command(run: &DirectiveRun) -> Result<ElevatedCommand, String> {
+    let title = directive_title(run.directive);
+    let mut command = ElevatedCommand::new(title.clone(), &run.config_path, run.profile.as_deref());
+    match run.directive {
+        Directive::NewKey | Directive::NewKeySafe => {
+            command.arg("init");
+            if run.directive == Directive::NewKeySafe {
+                command.arg("--safe");
+            }
+            if let Some(device) = &run.device {
+                command.arg("--device");
+                command.arg(device.clone());
+            }
+        }
+        Directive::SelfTest => {
+            command.arg("self-test");
+            if run.secure_mode && !run.params.flag("strict_usb") {
+                command.arg("--strict-usb");
+            }
+        }
+        Directive::Doctor => {
+            command.arg("doctor");
+            command.arg("--check-only");
+        }
+        Directive::SelfHeal => return Err(t!("elevate-no-cli", directive = title)),
+    }
+
+    for spec in directive_params(run.directive) {
+        match spec.kind {
+            ParamKind::Device => {}
+            ParamKind::Dataset => {
+                if let Some(dataset) = run.params.text(spec.name) {
+                    command.arg(dataset);
+                }
+            }
+            ParamKind::Flag => {
+                if run.params.flag(spec.name) {
+                    command.arg(format!("--{}", spec.name.replace('_', "-")));
+                }
+            }
+            ParamKind::Path | ParamKind::Text | ParamKind::Size => {
+                if let Some(value) = run.params.text(spec.name) {
+                    command.arg(format!("--{}", spec.name.replace('_', "-")));
+                    command.arg(value);
+                }
+            }
+        }
+    }
+
+    if let Some(passphrase) = &run.passphrase {
+        command.arg("--passphrase-stdin");
+        command.stdin = Some(passphrase.clone());
+    }
+    Ok(command)
+}
+
+/// `lockchain-cli breakglass` for `dataset`, writing the key to `output` or,
+/// without one, to standard output for [`capture`].
+pub(crate) fn breakglass_command(
+    config_path: &Path,
+    profile: Option<&str>,
+    dataset: &str,
+    output: Option<&Path>,
+    passphrase: Secret,
+) -> ElevatedCommand {
+    let mut command = ElevatedCommand::new(
+        t!("elevate-summary-breakglass", dataset = dataset),
+        config_path,
+        profile,
+    );
+    command.arg("breakglass");
+    command.arg(dataset);
+    match output {
+        Some(path) => {
+            command.arg("--output-file");
+            command.arg(path.display().to_string());
+        }
+        None => command.arg("--stdout"),
+    }
+    command.arg("--passphrase-stdin");
+    command.stdin = Some(passphrase);
+    command
+}
+
+/// `lockchain-cli unlock` for one dataset.
+pub(crate) fn unlock_command(
+    config_path: &Path,
+    profile: Option<&str>,
+    dataset: &str,
+    strict_usb: bool,
+    remote: Option<&str>,
+) -> ElevatedCommand {
+    let summary = match remote {
+        Some(destination) => t!(
+            "elevate-summary-unlock-remote",
+            dataset = dataset,
+            host = destination
+        ),
+        None => t!("elevate-summary-unlock", dataset = dataset),
+    };
+    let mut command = ElevatedCommand::new(summary, config_path, profile);
+    command.arg("unlock");
+    command.arg(dataset);
+    if strict_usb {
+        command.arg("--strict-usb");
+    }
+    if let Some(destination) = remote {
+        command.arg("--remote");
+        command.arg(destination);
+    }
+    command
+}
+
+/// `lockchain-cli lock` for one dataset.
+pub(crate) fn lock_command(
+    config_path: &Path,
+    profile: Option<&str>,
+    dataset: &str,
+) -> ElevatedCommand {
+    let mut command = ElevatedCommand::new(
+        t!("elevate-summary-lock", dataset = dataset),
+        config_path,
+        profile,
+    );
+    command.arg("lock");
+    command.arg(dataset);
+    command
+}
+
+/// The killswitch as `lockchain-cli lock --all`; stopping the daemon has
+/// no CLI equivalent.
+pub(crate) fn killswitch_command(
+    config_path: &Path,
+    profile: Option<&str>,
+    stop_daemon: bool,
+) -> Result<ElevatedCommand, String> {
+    if stop_daemon {
+        return Err(t!("elevate-stop-daemon"));
+    }
+    let mut command = ElevatedCommand::new(t!("elevate-summary-lock-all"), config_path, profile);
+    command.arg("lock");
+    command.arg("--all");
+    Ok(command)
+}
+
+/// The level behind one of the CLI's `[TAG]` event prefixes.
+fn level_for_tag(tag: &str) -> Option<WorkflowLevel> {
+    Some(match tag {
+        "DBG" => WorkflowLevel::Debug,
+        "INFO" => WorkflowLevel::Info,
+        "OK" => WorkflowLevel::Success,
+        "WARN" => WorkflowLevel::Warn,
+        "ERR" => WorkflowLevel::Error,
+        "SEC" => WorkflowLevel::Security,
+        _ => return None,
+    })
+}
+
+/// One line of CLI output as an event; untagged lines are informational.
+fn parse_line(line: &str) -> WorkflowEvent {
+    let trimmed = line.trim();
+    let tagged = trimmed
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once("] "))
+        .and_then(|(tag, message)| Some((level_for_tag(tag)?, message)));
+    match tagged {
+        Some((level, message)) => WorkflowEvent {
+            level,
+            message: message.to_string(),
+        },
+        None => WorkflowEvent {
+            level: WorkflowLevel::Info,
+            message: trimmed.to_string(),
+        },
+    }
+}
+
+/// Start `command` under pkexec with its secret written to standard input;
+/// standard error is collected on a thread.
+fn spawn(command: &ElevatedCommand) -> Result<(Child, Option<JoinHandle<String>>), String> {
+    let pkexec = pkexec_path().ok_or_else(|| t!("elevate-no-pkexec"))?;
+    let mut child = Command::new(pkexec)
+        .arg(&command.cli)
+        .args(&command.args)
+        .stdin(if command.stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| t!("elevate-start-failed", error = err.to_string()))?;
+
+    if let (Some(mut stdin), Some(secret)) = (child.stdin.take(), &command.stdin) {
+        stdin
+            .write_all(secret.expose().as_bytes())
+            .and_then(|()| stdin.write_all(b"\n"))
+            .map_err(|err| t!("elevate-stdin-failed", error = err.to_string()))?;
+    }
+    let stderr = child.stderr.take().map(|mut stderr| {
+        thread::spawn(move || {
+            let mut output = String::new();
+            let _ = stderr.read_to_string(&mut output);
+            output
+        })
+    });
+    Ok((child, stderr))
+}
+
+/// Wait for `child` and turn a failing exit into the CLI's last error line.
+fn finish(
+    command: &ElevatedCommand,
+    mut child: Child,
+    stderr: Option<JoinHandle<String>>,
+) -> Result<(), String> {
+    let status = child
+        .wait()
+        .map_err(|err| t!("elevate-wait-failed", error = err.to_string()))?;
+    let stderr = stderr
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+
+    match status.code() {
+        Some(0) => Ok(()),
+        Some(PKEXEC_DISMISSED) => Err(t!("elevate-dismissed")),
+        Some(PKEXEC_DENIED) => Err(t!(
+            "elevate-denied",
+            cli = command.cli.display().to_string()
+        )),
+        _ => Err(stderr
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .map(|line| line.trim().to_string())
+            .unwrap_or_else(|| {
+                t!(
+                    "elevate-failed",
+                    action = command.summary.as_str(),
+                    status = status.to_string()
+                )
+            })),
+    }
+}
+
+/// Run `command` through pkexec, handing each line it prints to `on_event`
+/// as it arrives.
+pub(crate) fn run(
+    command: &ElevatedCommand,
+    mut on_event: impl FnMut(&WorkflowEvent),
+) -> Result<WorkflowReport, String> {
+    let (mut child, stderr) = spawn(command)?;
+    let mut events = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event = parse_line(&line);
+            on_event(&event);
+            events.push(event);
+        }
+    }
+    finish(command, child, stderr)?;
+    Ok(WorkflowReport {
+        title: command.summary.clone(),
+        events,
+    })
+}
+
+/// Run `command` through pkexec and return the raw bytes it writes to
+/// standard output, such as key material from `breakglass --stdout`.
+pub(crate) fn capture(command: &ElevatedCommand) -> Result<Zeroizing<Vec<u8>>, String> {
+    let (mut child, stderr) = spawn(command)?;
+    let mut output = Zeroizing::new(Vec::new());
+    if let Some(mut stdout) = child.stdout.take() {
+        stdout
+            .read_to_end(&mut output)
+            .map_err(|err| t!("elevate-read-failed", error = err.to_string()))?;
+    }
+    finish(command, child, stderr)?;
+    Ok(output)
+}
+
+/// Run `command` elevated without streaming its events.
+pub(crate) async fn run_report(command: ElevatedCommand) -> Result<WorkflowReport, String> {
+    run(&command, |_| {})
+}
+
+/// Run a dataset action elevated, summarised by its last line of output.
+pub(crate) async fn run_action(command: ElevatedCommand) -> Result<String, String> {
+    let report = run(&command, |_| {})?;
+    Ok(report
+        .events
+        .last()
+        .map(|event| event.message.clone())
+        .unwrap_or(report.title))
+}
+
+/// What waits on the operator's go-ahead to run elevated.
+#[derive(Debug, Clone)]
+pub(crate) enum ElevatedAction {
+    Directive(Box<DirectiveRun>),
+    Unlock(String),
+    Lock(String),
+    Killswitch,
+    BreakGlass(Destination),
+}
+
+/// The open "run as root?" dialog.
+#[derive(Debug, Clone)]
+pub(crate) struct ElevationPrompt {
+    pub(crate) action: ElevatedAction,
+    pub(crate) command: ElevatedCommand,
+}
+
+impl LockchainUi {
+    /// Ask before running `command` for `action` through pkexec.
+    pub(crate) fn request_elevation(
+        &mut self,
+        action: ElevatedAction,
+        command: Result<ElevatedCommand, String>,
+    ) {
+        match command {
+            Ok(command) => self.elevation = Some(ElevationPrompt { action, command }),
+            Err(err) => self.push_activity(ActivityLevel::Warn, err),
+        }
+    }
+
+    /// The summary, the exact command, and what polkit will ask for.
+    pub(crate) fn view_elevation_dialog<'a>(
+        &self,
+        prompt: &'a ElevationPrompt,
+    ) -> iced::Element<'a, Message> {
+        let body_color = palette().text;
+        let mut body = column![
+            text(t!("elevate-title"))
+                .size(22)
+                .style(text_color(palette().subheading)),
+            text(t!(
+                "elevate-explainer",
+                action = prompt.command.summary.as_str()
+            ))
+            .size(14)
+            .style(text_color(body_color)),
+            container(
+                text(prompt.command.command_line())
+                    .size(13)
+                    .font(Font::MONOSPACE)
+                    .style(text_color(palette().success))
+            )
+            .padding(10)
+            .width(Length::Fill)
+            .style(panel_style()),
+        ]
+        .spacing(14);
+        if prompt.command.stdin.is_some() {
+            body = body.push(
+                text(t!("elevate-stdin-note"))
+                    .size(13)
+                    .style(text_color(palette().info)),
+            );
+        }
+        body = body.push(
+            text(t!("elevate-polkit-note"))
+                .size(13)
+                .style(text_color(palette().warning)),
+        );
+
+        container(
+            body.push(
+                row![
+                    Space::with_width(Length::Fill),
+                    button(text(t!("dialog-cancel")))
+                        .padding([10, 16])
+                        .style(help_button())
+                        .on_press(Message::ElevationCancel),
+                    button(text(t!("elevate-confirm")))
+                        .padding([10, 16])
+                        .style(killswitch_button())
+                        .on_press(Message::ElevationConfirm)
+                ]
+                .spacing(12),
+            ),
+        )
+        .padding(24)
+        .max_width(640)
+        .style(dialog_style())
+        .into()
+    }
+}
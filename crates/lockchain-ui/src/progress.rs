@@ -0,0 +1,91 @@
+//! Live directive runs: the workflow executes on a worker thread and its
+//! events reach the activity feed through a subscription as they happen.
+
+use std::path::PathBuf;
+use std::thread;
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::Subscription;
+use lockchain_core::workflow::WorkflowEvent;
+
+use crate::forms::ParamValues;
+use crate::i18n::t;
+use crate::passphrase::Secret;
+use crate::privilege::{self, ElevatedCommand};
+use crate::{run_directive, Directive, Message};
+
+/// Spinner frames shown on the Execute button while a directive runs.
+const SPINNER: [char; 8] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧'];
+
+/// A directive started from the deck, with everything it needs to run.
+#[derive(Debug, Clone)]
+pub(crate) struct DirectiveRun {
+    /// Distinguishes back-to-back runs of the same directive.
+    pub(crate) id: u64,
+    pub(crate) directive: Directive,
+    pub(crate) config_path: PathBuf,
+    pub(crate) profile: Option<String>,
+    pub(crate) secure_mode: bool,
+    /// Values from the directive's parameter form.
+    pub(crate) params: ParamValues,
+    /// Token picked for the New Key directives.
+    pub(crate) device: Option<String>,
+    /// Typed in the passphrase dialog; recovery needs it, forging seeds the
+    /// fallback with it.
+    pub(crate) passphrase: Option<Secret>,
+    /// Set when the operator agreed to run it as root through pkexec.
+    pub(crate) elevated: Option<ElevatedCommand>,
+    /// Events received so far.
+    pub(crate) events: usize,
+    /// Current spinner frame, advanced by the ticker.
+    pub(crate) frame: usize,
+}
+
+impl DirectiveRun {
+    /// Execute button label, e.g. `⠹ Running Doctor · 12 events`; `still`
+    /// drops the spinner for reduced motion.
+    pub(crate) fn label(&self, still: bool) -> String {
+        let label = t!(
+            "run-label",
+            directive = crate::directive_title(self.directive),
+            count = self.events
+        );
+        if still {
+            label
+        } else {
+            format!("{} {label}", SPINNER[self.frame % SPINNER.len()])
+        }
+    }
+
+    /// Stream `WorkflowEvent` messages while the run lasts, plus Doctor's
+    /// `DoctorRemedies`, then one `WorkflowFinished`.
+    pub(crate) fn subscription(&self) -> Subscription<Message> {
+        let run = self.clone();
+        Subscription::run_with_id(
+            run.id,
+            iced::stream::channel(64, move |mut output| async move {
+                let (sender, mut receiver) = mpsc::unbounded();
+                thread::spawn(move || {
+                    let events = sender.clone();
+                    let remedies = sender.clone();
+                    let on_event = |event: &WorkflowEvent| {
+                        let _ = events.unbounded_send(Message::WorkflowEvent(event.clone()));
+                    };
+                    let result = match &run.elevated {
+                        Some(command) => privilege::run(command, on_event),
+                        None => run_directive(&run, on_event, |found| {
+                            let _ = remedies.unbounded_send(Message::DoctorRemedies(found));
+                        }),
+                    };
+                    let _ = sender.unbounded_send(Message::WorkflowFinished(result));
+                });
+                while let Some(message) = receiver.next().await {
+                    if output.send(message).await.is_err() {
+                        break;
+                    }
+                }
+            }),
+        )
+    }
+}
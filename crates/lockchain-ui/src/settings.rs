@@ -0,0 +1,589 @@
+//! Settings screen: edit the common config sections as form fields and save
+//! them back without touching TOML by hand.
+
+use std::path::PathBuf;
+
+use iced::alignment::Vertical;
+use iced::widget::{
+    button, column, container, pick_list, row, scrollable, text, text_input, toggler, Space,
+};
+use iced::{Length, Task};
+use lockchain_core::config::{IssueSeverity, LockchainConfig, RetryStrategy, UiTheme};
+use lockchain_core::units::{format_duration, parse_duration};
+
+use crate::i18n::{t, LanguageChoice, LANGUAGE_CHOICES};
+use crate::theme::palette;
+use crate::{
+    help_button, panel_style, primary_button, text_color, text_input_style, LockchainUi, Message,
+};
+
+const STRATEGIES: [RetryStrategy; 3] = [
+    RetryStrategy::Fixed,
+    RetryStrategy::Exponential,
+    RetryStrategy::ExponentialFullJitter,
+];
+
+const THEMES: [UiTheme; 4] = [
+    UiTheme::System,
+    UiTheme::Neon,
+    UiTheme::Light,
+    UiTheme::HighContrast,
+];
+
+/// Free-text fields on the form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Field {
+    DeviceLabel,
+    DeviceUuid,
+    AskpassPath,
+    PassphraseIters,
+    MaxAttempts,
+    BaseDelay,
+    MaxDelay,
+    JitterRatio,
+    Hosts,
+}
+
+/// Edits coming from the settings screen.
+#[derive(Debug, Clone)]
+pub(crate) enum SettingsMessage {
+    Open,
+    Close,
+    Loaded(Result<Box<LockchainConfig>, String>),
+    Edit(Field, String),
+    Dataset(usize, String),
+    AddDataset,
+    RemoveDataset(usize),
+    FallbackEnabled(bool),
+    Askpass(bool),
+    Strategy(RetryStrategy),
+    Theme(UiTheme),
+    ReducedMotion(bool),
+    Language(LanguageChoice),
+    Save,
+    Saved(Result<(), String>),
+}
+
+/// The loaded config plus the form's current, possibly invalid, values.
+#[derive(Debug)]
+pub(crate) struct SettingsForm {
+    base: LockchainConfig,
+    device_label: String,
+    device_uuid: String,
+    datasets: Vec<String>,
+    fallback_enabled: bool,
+    askpass: bool,
+    askpass_path: String,
+    passphrase_iters: String,
+    max_attempts: String,
+    base_delay: String,
+    max_delay: String,
+    jitter_ratio: String,
+    strategy: RetryStrategy,
+    theme: UiTheme,
+    reduced_motion: bool,
+    language: LanguageChoice,
+    /// `ui.hosts`, comma separated.
+    hosts: String,
+    /// Parse and validation findings for the current values.
+    issues: Vec<(IssueSeverity, String)>,
+    saving: bool,
+    dirty: bool,
+}
+
+impl SettingsForm {
+    fn new(config: LockchainConfig) -> Self {
+        let mut form = Self {
+            device_label: config.usb.device_label.clone().unwrap_or_default(),
+            device_uuid: config.usb.device_uuid.clone().unwrap_or_default(),
+            datasets: config.policy.datasets.clone(),
+            fallback_enabled: config.fallback.enabled,
+            askpass: config.fallback.askpass,
+            askpass_path: config.fallback.askpass_path.clone().unwrap_or_default(),
+            passphrase_iters: config.fallback.passphrase_iters.to_string(),
+            max_attempts: config.retry.max_attempts.to_string(),
+            base_delay: format_duration(config.retry.base_delay),
+            max_delay: format_duration(config.retry.max_delay),
+            jitter_ratio: config.retry.jitter_ratio.to_string(),
+            strategy: config.retry.strategy,
+            theme: config.ui.theme,
+            reduced_motion: config.ui.reduced_motion,
+            language: LanguageChoice::from_config(config.ui.language.as_deref()),
+            hosts: config.ui.hosts.join(", "),
+            issues: Vec::new(),
+            saving: false,
+            dirty: false,
+            base: config,
+        };
+        form.revalidate();
+        form
+    }
+
+    /// Apply the form to a copy of the loaded config. Fields that do not
+    /// parse are reported against their config key.
+    fn build(&self) -> Result<LockchainConfig, Vec<(IssueSeverity, String)>> {
+        let mut config = self.base.clone();
+        let mut errors = Vec::new();
+        let mut fail = |field: &str, err: String| {
+            errors.push((IssueSeverity::Error, format!("{field}: {err}")));
+        };
+        let optional = |value: &str| {
+            let value = value.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        };
+
+        config.usb.device_label = optional(&self.device_label);
+        config.usb.device_uuid = optional(&self.device_uuid);
+        config.policy.datasets = self
+            .datasets
+            .iter()
+            .map(|dataset| dataset.trim().to_string())
+            .collect();
+        config.fallback.enabled = self.fallback_enabled;
+        config.fallback.askpass = self.askpass;
+        config.fallback.askpass_path = optional(&self.askpass_path);
+        match self.passphrase_iters.trim().parse() {
+            Ok(iters) => config.fallback.passphrase_iters = iters,
+            Err(err) => fail("fallback.passphrase_iters", format!("{err}")),
+        }
+        match self.max_attempts.trim().parse() {
+            Ok(attempts) => config.retry.max_attempts = attempts,
+            Err(err) => fail("retry.max_attempts", format!("{err}")),
+        }
+        match parse_duration(&self.base_delay) {
+            Ok(delay) => config.retry.base_delay = delay,
+            Err(err) => fail("retry.base_delay", err.to_string()),
+        }
+        match parse_duration(&self.max_delay) {
+            Ok(delay) => config.retry.max_delay = delay,
+            Err(err) => fail("retry.max_delay", err.to_string()),
+        }
+        match self.jitter_ratio.trim().parse() {
+            Ok(ratio) => config.retry.jitter_ratio = ratio,
+            Err(err) => fail("retry.jitter_ratio", format!("{err}")),
+        }
+        config.retry.strategy = self.strategy;
+        config.ui.theme = self.theme;
+        config.ui.reduced_motion = self.reduced_motion;
+        config.ui.language = self.language.to_config();
+        config.ui.hosts = self
+            .hosts
+            .split(',')
+            .map(str::trim)
+            .filter(|host| !host.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn revalidate(&mut self) {
+        self.issues = match self.build() {
+            Ok(config) => config
+                .validate()
+                .into_iter()
+                .map(|issue| (issue.severity, issue.to_string()))
+                .collect(),
+            Err(errors) => errors,
+        };
+    }
+
+    fn has_errors(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|(severity, _)| *severity == IssueSeverity::Error)
+    }
+}
+
+async fn load(
+    config_path: PathBuf,
+    profile: Option<String>,
+) -> Result<Box<LockchainConfig>, String> {
+    LockchainConfig::load_profile(&config_path, profile.as_deref())
+        .map(Box::new)
+        .map_err(|e| e.to_string())
+}
+
+async fn save(config: LockchainConfig) -> Result<(), String> {
+    config.save().map_err(|e| e.to_string())
+}
+
+impl LockchainUi {
+    /// Handle a settings-screen message.
+    pub(crate) fn update_settings(&mut self, message: SettingsMessage) -> Task<Message> {
+        let edited = |form: &mut SettingsForm| {
+            form.dirty = true;
+            form.revalidate();
+        };
+        match message {
+            SettingsMessage::Open => {
+                self.settings_open = true;
+                self.dashboard = None;
+                self.breakglass = None;
+                return Task::perform(
+                    load(self.config_path.clone(), self.profile.clone()),
+                    |result| Message::Settings(SettingsMessage::Loaded(result)),
+                );
+            }
+            SettingsMessage::Close => {
+                self.settings_open = false;
+                self.settings = None;
+                self.settings_error = None;
+            }
+            SettingsMessage::Loaded(Ok(config)) => {
+                self.settings = Some(SettingsForm::new(*config));
+                self.settings_error = None;
+            }
+            SettingsMessage::Loaded(Err(err)) => {
+                self.settings_error = Some(t!("settings-load-failed", error = err));
+            }
+            SettingsMessage::Save => {
+                let Some(form) = &mut self.settings else {
+                    return Task::none();
+                };
+                match form.build() {
+                    Ok(config) if !form.has_errors() && !form.saving => {
+                        form.saving = true;
+                        return Task::perform(save(config), |result| {
+                            Message::Settings(SettingsMessage::Saved(result))
+                        });
+                    }
+                    _ => {}
+                }
+            }
+            SettingsMessage::Saved(result) => {
+                if let Some(form) = &mut self.settings {
+                    form.saving = false;
+                }
+                match result {
+                    Ok(()) => {
+                        let mut hosts = Task::none();
+                        if let Some(Ok(config)) = self.settings.as_ref().map(SettingsForm::build) {
+                            hosts = self.set_hosts(config.ui.hosts.clone());
+                            self.set_appearance(config.ui);
+                        }
+                        self.push_activity(
+                            crate::ActivityLevel::Success,
+                            t!(
+                                "settings-saved",
+                                path = self.config_path.display().to_string()
+                            ),
+                        );
+                        self.settings_open = false;
+                        self.settings = None;
+                        return Task::batch([hosts, self.refresh_state()]);
+                    }
+                    Err(err) => self.push_activity(
+                        crate::ActivityLevel::Error,
+                        t!("settings-save-failed", error = err),
+                    ),
+                }
+            }
+            other => {
+                let Some(form) = &mut self.settings else {
+                    return Task::none();
+                };
+                match other {
+                    SettingsMessage::Edit(field, value) => {
+                        let slot = match field {
+                            Field::DeviceLabel => &mut form.device_label,
+                            Field::DeviceUuid => &mut form.device_uuid,
+                            Field::AskpassPath => &mut form.askpass_path,
+                            Field::PassphraseIters => &mut form.passphrase_iters,
+                            Field::MaxAttempts => &mut form.max_attempts,
+                            Field::BaseDelay => &mut form.base_delay,
+                            Field::MaxDelay => &mut form.max_delay,
+                            Field::JitterRatio => &mut form.jitter_ratio,
+                            Field::Hosts => &mut form.hosts,
+                        };
+                        *slot = value;
+                    }
+                    SettingsMessage::Dataset(index, value) => {
+                        if let Some(slot) = form.datasets.get_mut(index) {
+                            *slot = value;
+                        }
+                    }
+                    SettingsMessage::AddDataset => form.datasets.push(String::new()),
+                    SettingsMessage::RemoveDataset(index) => {
+                        if index < form.datasets.len() {
+                            form.datasets.remove(index);
+                        }
+                    }
+                    SettingsMessage::FallbackEnabled(state) => form.fallback_enabled = state,
+                    SettingsMessage::Askpass(state) => form.askpass = state,
+                    SettingsMessage::Strategy(strategy) => form.strategy = strategy,
+                    SettingsMessage::Theme(theme) => form.theme = theme,
+                    SettingsMessage::ReducedMotion(state) => form.reduced_motion = state,
+                    SettingsMessage::Language(language) => form.language = language,
+                    _ => return Task::none(),
+                }
+                edited(form);
+            }
+        }
+        Task::none()
+    }
+
+    /// The settings screen, shown in place of the deck body.
+    pub(crate) fn view_settings(&self) -> iced::Element<'_, Message> {
+        let heading = text(t!("settings-heading"))
+            .size(18)
+            .style(text_color(palette().heading));
+        let back = button(text(t!("nav-back")))
+            .padding([10, 16])
+            .style(help_button())
+            .on_press(Message::Settings(SettingsMessage::Close));
+
+        let Some(form) = &self.settings else {
+            let note = self
+                .settings_error
+                .clone()
+                .unwrap_or_else(|| t!("settings-loading"));
+            return container(
+                column![
+                    heading,
+                    text(note).size(14).style(text_color(palette().warning)),
+                    back
+                ]
+                .spacing(16),
+            )
+            .padding(20)
+            .width(Length::Fill)
+            .style(panel_style())
+            .into();
+        };
+
+        let mut datasets = column![].spacing(8);
+        for (index, dataset) in form.datasets.iter().enumerate() {
+            datasets = datasets.push(
+                row![
+                    text_input("pool/dataset", dataset)
+                        .on_input(move |value| {
+                            Message::Settings(SettingsMessage::Dataset(index, value))
+                        })
+                        .size(14)
+                        .padding(8)
+                        .style(text_input_style()),
+                    button(text(t!("settings-remove-dataset")))
+                        .padding([8, 12])
+                        .style(help_button())
+                        .on_press(Message::Settings(SettingsMessage::RemoveDataset(index)))
+                ]
+                .spacing(8)
+                .align_y(Vertical::Center),
+            );
+        }
+        datasets = datasets.push(
+            button(text(t!("settings-add-dataset")))
+                .padding([8, 12])
+                .style(primary_button())
+                .on_press(Message::Settings(SettingsMessage::AddDataset)),
+        );
+
+        let usb = section(
+            t!("settings-usb"),
+            column![
+                labelled(
+                    t!("settings-device-label"),
+                    input("LOCKCHAIN", &form.device_label, Field::DeviceLabel)
+                ),
+                labelled(
+                    t!("settings-device-uuid"),
+                    input(&t!("any-value"), &form.device_uuid, Field::DeviceUuid)
+                ),
+            ],
+        );
+        let policy = section(t!("settings-datasets"), datasets);
+        let fallback = section(
+            t!("settings-fallback"),
+            column![
+                toggler(form.fallback_enabled)
+                    .label(t!("settings-fallback-enabled"))
+                    .size(20)
+                    .text_size(14)
+                    .on_toggle(|state| Message::Settings(SettingsMessage::FallbackEnabled(state))),
+                toggler(form.askpass)
+                    .label(t!("settings-askpass"))
+                    .size(20)
+                    .text_size(14)
+                    .on_toggle(|state| Message::Settings(SettingsMessage::Askpass(state))),
+                labelled(
+                    t!("settings-askpass-path"),
+                    input(
+                        "/usr/bin/systemd-ask-password",
+                        &form.askpass_path,
+                        Field::AskpassPath
+                    )
+                ),
+                labelled(
+                    t!("settings-pbkdf2-iterations"),
+                    input("250000", &form.passphrase_iters, Field::PassphraseIters)
+                ),
+            ],
+        );
+        let retry = section(
+            t!("settings-retry"),
+            column![
+                labelled(
+                    t!("settings-max-attempts"),
+                    input("3", &form.max_attempts, Field::MaxAttempts)
+                ),
+                labelled(
+                    t!("settings-base-delay"),
+                    input("500ms", &form.base_delay, Field::BaseDelay)
+                ),
+                labelled(
+                    t!("settings-max-delay"),
+                    input("5s", &form.max_delay, Field::MaxDelay)
+                ),
+                labelled(
+                    t!("settings-jitter-ratio"),
+                    input("0.1", &form.jitter_ratio, Field::JitterRatio)
+                ),
+                labelled(
+                    t!("settings-strategy"),
+                    pick_list(STRATEGIES, Some(form.strategy), |strategy| {
+                        Message::Settings(SettingsMessage::Strategy(strategy))
+                    })
+                    .text_size(14)
+                    .into()
+                ),
+            ],
+        );
+
+        let appearance = section(
+            t!("settings-appearance"),
+            column![
+                labelled(
+                    t!("settings-theme"),
+                    pick_list(THEMES, Some(form.theme), |theme| {
+                        Message::Settings(SettingsMessage::Theme(theme))
+                    })
+                    .text_size(14)
+                    .into()
+                ),
+                labelled(
+                    t!("settings-language"),
+                    pick_list(LANGUAGE_CHOICES, Some(form.language), |language| {
+                        Message::Settings(SettingsMessage::Language(language))
+                    })
+                    .text_size(14)
+                    .into()
+                ),
+                toggler(form.reduced_motion)
+                    .label(t!("settings-reduced-motion"))
+                    .size(20)
+                    .text_size(14)
+                    .on_toggle(|state| Message::Settings(SettingsMessage::ReducedMotion(state))),
+            ],
+        );
+        let hosts = section(
+            t!("settings-hosts"),
+            column![labelled(
+                t!("settings-ssh-destinations"),
+                input("root@nas1, root@nas2", &form.hosts, Field::Hosts)
+            )],
+        );
+
+        let mut issues = column![].spacing(4);
+        for (severity, message) in &form.issues {
+            let color = match severity {
+                IssueSeverity::Error => palette().danger,
+                IssueSeverity::Warning => palette().warning,
+            };
+            issues = issues.push(text(message).size(13).style(text_color(color)));
+        }
+
+        let mut save = button(text(if form.saving {
+            t!("settings-saving")
+        } else {
+            t!("settings-save")
+        }))
+        .padding([10, 18])
+        .style(primary_button());
+        if form.dirty && !form.saving && !form.has_errors() {
+            save = save.on_press(Message::Settings(SettingsMessage::Save));
+        }
+        let target = match &self.profile {
+            Some(profile) => t!(
+                "settings-target-profile",
+                path = self.config_path.display().to_string(),
+                profile = profile.as_str()
+            ),
+            None => self.config_path.display().to_string(),
+        };
+
+        container(
+            column![
+                row![
+                    heading,
+                    Space::with_width(Length::Fill),
+                    text(target).size(14).style(text_color(palette().info))
+                ]
+                .align_y(Vertical::Center),
+                scrollable(
+                    row![
+                        column![usb, policy]
+                            .spacing(16)
+                            .width(Length::FillPortion(1)),
+                        column![fallback, retry, appearance, hosts]
+                            .spacing(16)
+                            .width(Length::FillPortion(1))
+                    ]
+                    .spacing(24)
+                )
+                .height(Length::Fill),
+                issues,
+                row![Space::with_width(Length::Fill), back, save].spacing(12)
+            ]
+            .spacing(16),
+        )
+        .padding(20)
+        .width(Length::Fill)
+        .style(panel_style())
+        .into()
+    }
+}
+
+/// One-line text field bound to `field`.
+fn input<'a>(placeholder: &str, value: &str, field: Field) -> iced::Element<'a, Message> {
+    text_input(placeholder, value)
+        .on_input(move |value| Message::Settings(SettingsMessage::Edit(field, value)))
+        .size(14)
+        .padding(8)
+        .style(text_input_style())
+        .into()
+}
+
+/// Caption on the left, control filling the rest of the row.
+pub(crate) fn labelled<'a>(
+    label: impl text::IntoFragment<'a>,
+    control: iced::Element<'a, Message>,
+) -> iced::Element<'a, Message> {
+    row![
+        text(label)
+            .size(14)
+            .width(Length::Fixed(150.0))
+            .style(text_color(palette().success)),
+        control
+    ]
+    .spacing(12)
+    .align_y(Vertical::Center)
+    .into()
+}
+
+/// Titled group of form rows.
+fn section<'a>(
+    title: impl text::IntoFragment<'a>,
+    body: iced::widget::Column<'a, Message>,
+) -> iced::Element<'a, Message> {
+    column![
+        text(title).size(16).style(text_color(palette().accent)),
+        body.spacing(10)
+    ]
+    .spacing(10)
+    .into()
+}
@@ -0,0 +1,283 @@
+//! Keyboard operation of the deck: arrows switch directives, Enter runs or
+//! confirms, Esc backs out, Ctrl+L clears the feed, and Tab walks a focus
+//! ring over the deck's controls. Iced 0.13 buttons take no focus, so the
+//! ring is tracked here and drawn as an outline by [`LockchainUi::focusable`].
+
+use iced::keyboard::{key::Named, Key, Modifiers};
+use iced::widget::{container, text_input};
+use iced::{Border, Task};
+use lockchain_core::workflow::ParamKind;
+
+use crate::breakglass::BreakGlassMessage;
+use crate::dashboard::DashboardMessage;
+use crate::forms::{self, directive_params};
+use crate::i18n::t;
+use crate::settings::SettingsMessage;
+use crate::theme::palette;
+use crate::{ActivityLevel, Directive, LockchainUi, Message, DIRECTIVES};
+
+/// What a key press asks for, before the deck's state decides its meaning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Shortcut {
+    PreviousDirective,
+    NextDirective,
+    Activate,
+    Cancel,
+    ClearFeed,
+    FocusNext,
+    FocusPrevious,
+}
+
+/// A control the focus ring can land on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Focus {
+    Dashboard,
+    Settings,
+    Refresh,
+    Directive(Directive),
+    BreakGlass,
+    /// A text field of the active directive's form.
+    Param(&'static str),
+    Execute,
+    Help,
+    Killswitch,
+    Unlock(String),
+    Lock(String),
+    Export,
+}
+
+/// Key bindings, for [`iced::keyboard::on_key_press`]. Keys a focused text
+/// field consumes never get here, apart from Tab and the arrows.
+pub(crate) fn shortcut(key: Key, modifiers: Modifiers) -> Option<Message> {
+    let shortcut = match key.as_ref() {
+        Key::Named(Named::ArrowUp) => Shortcut::PreviousDirective,
+        Key::Named(Named::ArrowDown) => Shortcut::NextDirective,
+        Key::Named(Named::Enter) => Shortcut::Activate,
+        Key::Named(Named::Escape) => Shortcut::Cancel,
+        Key::Named(Named::Tab) if modifiers.shift() => Shortcut::FocusPrevious,
+        Key::Named(Named::Tab) => Shortcut::FocusNext,
+        Key::Character(c) if modifiers.command() && c.eq_ignore_ascii_case("l") => {
+            Shortcut::ClearFeed
+        }
+        _ => return None,
+    };
+    Some(Message::Shortcut(shortcut))
+}
+
+impl LockchainUi {
+    pub(crate) fn update_shortcut(&mut self, shortcut: Shortcut) -> Task<Message> {
+        match shortcut {
+            Shortcut::PreviousDirective => self.step_directive(false),
+            Shortcut::NextDirective => self.step_directive(true),
+            Shortcut::Activate => match self.confirm_message() {
+                Some(message) => self.update(message),
+                None => Task::none(),
+            },
+            Shortcut::Cancel => match self.cancel_message() {
+                Some(message) => self.update(message),
+                None => {
+                    self.focus = None;
+                    Task::none()
+                }
+            },
+            Shortcut::ClearFeed => {
+                self.activity.clear();
+                self.push_activity(ActivityLevel::Info, t!("activity-feed-cleared"));
+                Task::none()
+            }
+            Shortcut::FocusNext => self.step_focus(true),
+            Shortcut::FocusPrevious => self.step_focus(false),
+        }
+    }
+
+    /// A dialog or full-screen view covers the deck's own controls.
+    fn deck_covered(&self) -> bool {
+        self.killswitch.is_some()
+            || self.guard.is_some()
+            || self.passphrase_prompt.is_some()
+            || self.elevation.is_some()
+            || self.wizard.is_some()
+            || self.settings_open
+            || self.dashboard.is_some()
+            || self.breakglass.is_some()
+    }
+
+    /// Select the neighbouring enabled directive, staying put at either end.
+    fn step_directive(&mut self, forward: bool) -> Task<Message> {
+        if self.deck_covered() || self.executing {
+            return Task::none();
+        }
+        let enabled: Vec<_> = DIRECTIVES
+            .iter()
+            .map(|entry| entry.directive)
+            .filter(|directive| self.directive_enabled(*directive))
+            .collect();
+        let current = enabled
+            .iter()
+            .position(|directive| *directive == self.active_directive);
+        let next = match (current, forward) {
+            (Some(index), true) => enabled.get(index + 1),
+            (Some(index), false) => index.checked_sub(1).and_then(|index| enabled.get(index)),
+            (None, _) => enabled.first(),
+        };
+        let Some(&directive) = next else {
+            return Task::none();
+        };
+        self.focus = Some(Focus::Directive(directive));
+        self.update(Message::DirectiveSelected(directive))
+    }
+
+    /// What Enter does: confirm the open dialog, press the focused control,
+    /// or run the active directive.
+    fn confirm_message(&self) -> Option<Message> {
+        if self.killswitch.is_some() {
+            return Some(Message::KillswitchConfirm);
+        }
+        if self.guard.is_some() {
+            return Some(Message::GuardConfirm);
+        }
+        if self.passphrase_prompt.is_some() {
+            return Some(Message::PassphraseSubmit);
+        }
+        if self.elevation.is_some() {
+            return Some(Message::ElevationConfirm);
+        }
+        if self.deck_covered() {
+            return None;
+        }
+        match &self.focus {
+            Some(focus) if self.focus_ring().contains(focus) => self.press(focus),
+            _ => Some(Message::Execute),
+        }
+    }
+
+    /// What Esc does: dismiss the topmost dialog, then close the open
+    /// screen. The first-run wizard stays until finished or skipped.
+    fn cancel_message(&self) -> Option<Message> {
+        if self.killswitch.is_some() {
+            Some(Message::KillswitchCancel)
+        } else if self.guard.is_some() {
+            Some(Message::GuardCancel)
+        } else if self.passphrase_prompt.is_some() {
+            Some(Message::PassphraseCancel)
+        } else if self.elevation.is_some() {
+            Some(Message::ElevationCancel)
+        } else if self.breakglass.is_some() {
+            Some(Message::BreakGlass(BreakGlassMessage::Close))
+        } else if self.dashboard.is_some() {
+            Some(Message::Dashboard(DashboardMessage::Close))
+        } else if self.settings_open {
+            Some(Message::Settings(SettingsMessage::Close))
+        } else {
+            None
+        }
+    }
+
+    /// The message the focused control sends when pressed; text fields
+    /// have none.
+    fn press(&self, focus: &Focus) -> Option<Message> {
+        match focus {
+            Focus::Dashboard => Some(Message::Dashboard(DashboardMessage::Open)),
+            Focus::Settings => Some(Message::Settings(SettingsMessage::Open)),
+            Focus::Refresh => Some(Message::Refresh),
+            Focus::Directive(directive) => Some(Message::DirectiveSelected(*directive)),
+            Focus::BreakGlass => Some(Message::BreakGlass(BreakGlassMessage::Open)),
+            Focus::Param(_) => None,
+            Focus::Execute => Some(Message::Execute),
+            Focus::Help => Some(Message::HelpPressed),
+            Focus::Killswitch => Some(Message::KillSwitchPressed),
+            Focus::Unlock(dataset) => Some(Message::UnlockDataset(dataset.clone())),
+            Focus::Lock(dataset) => Some(Message::LockDataset(dataset.clone())),
+            Focus::Export => Some(Message::ExportLog),
+        }
+    }
+
+    /// Controls Tab visits on the deck, in reading order: header, then the
+    /// left column, then the right.
+    fn focus_ring(&self) -> Vec<Focus> {
+        let mut ring = vec![Focus::Dashboard, Focus::Settings, Focus::Refresh];
+        ring.extend(
+            DIRECTIVES
+                .iter()
+                .map(|entry| entry.directive)
+                .filter(|directive| self.directive_enabled(*directive))
+                .map(Focus::Directive),
+        );
+        ring.push(Focus::BreakGlass);
+        ring.extend(
+            directive_params(self.active_directive)
+                .into_iter()
+                .filter(|spec| {
+                    matches!(
+                        spec.kind,
+                        ParamKind::Path | ParamKind::Text | ParamKind::Size
+                    )
+                })
+                .map(|spec| Focus::Param(spec.name)),
+        );
+        ring.extend([Focus::Execute, Focus::Help, Focus::Killswitch]);
+        for entry in &self.datasets {
+            ring.push(Focus::Unlock(entry.dataset.clone()));
+            ring.push(Focus::Lock(entry.dataset.clone()));
+        }
+        ring.push(Focus::Export);
+        ring
+    }
+
+    /// Move along the focus ring, wrapping at either end. Over dialogs and
+    /// full-screen views Tab cycles their text fields instead.
+    fn step_focus(&mut self, forward: bool) -> Task<Message> {
+        if self.deck_covered() {
+            return if forward {
+                iced::widget::focus_next()
+            } else {
+                iced::widget::focus_previous()
+            };
+        }
+        let ring = self.focus_ring();
+        let current = self
+            .focus
+            .as_ref()
+            .and_then(|focus| ring.iter().position(|entry| entry == focus));
+        let index = match (current, forward) {
+            (Some(index), true) => (index + 1) % ring.len(),
+            (Some(index), false) => (index + ring.len() - 1) % ring.len(),
+            (None, true) => 0,
+            (None, false) => ring.len() - 1,
+        };
+        let focus = ring[index].clone();
+        // Typing goes to the field the ring is on; focusing an id no field
+        // carries takes it away from all of them.
+        let task = match focus {
+            Focus::Param(name) => text_input::focus(forms::input_id(name)),
+            _ => text_input::focus(text_input::Id::unique()),
+        };
+        self.focus = Some(focus);
+        task
+    }
+
+    /// Wrap `content` in the focus outline, drawn only while the ring is on
+    /// `target`; the padding stays so focus never shifts the layout.
+    pub(crate) fn focusable<'a>(
+        &self,
+        target: Focus,
+        content: impl Into<iced::Element<'a, Message>>,
+    ) -> iced::Element<'a, Message> {
+        let focused = self.focus.as_ref() == Some(&target);
+        container(content)
+            .padding(3)
+            .style(move |_| container::Style {
+                border: Border {
+                    color: if focused {
+                        palette().focus
+                    } else {
+                        iced::Color::TRANSPARENT
+                    },
+                    width: 2.0 * palette().stroke,
+                    radius: 12.0.into(),
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+}
@@ -0,0 +1,253 @@
+//! Colour schemes for the Control Deck: the `[ui] theme` palette every style
+//! reads, and the desktop's dark/light preference behind `theme = "system"`.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use iced::Color;
+use lockchain_core::config::{UiCfg, UiTheme};
+use zbus::zvariant::{OwnedValue, Value};
+use zbus::Connection;
+
+use crate::i18n::{self, Language};
+use crate::LockchainUi;
+
+/// Colours for every element the deck draws.
+#[derive(Debug)]
+pub(crate) struct Palette {
+    pub(crate) background: Color,
+    pub(crate) panel: Color,
+    pub(crate) dialog: Color,
+    /// Doctor cards and health chips.
+    pub(crate) card: Color,
+    pub(crate) entry: Color,
+    pub(crate) input: Color,
+    pub(crate) input_border: Color,
+    pub(crate) text: Color,
+    pub(crate) heading: Color,
+    /// Subtitles, dialog frames, and the active directive.
+    pub(crate) subheading: Color,
+    pub(crate) accent: Color,
+    pub(crate) accent_pressed: Color,
+    /// Text on accent-filled buttons.
+    pub(crate) on_accent: Color,
+    pub(crate) info: Color,
+    pub(crate) success: Color,
+    pub(crate) warning: Color,
+    pub(crate) danger: Color,
+    /// Disabled text.
+    pub(crate) muted: Color,
+    pub(crate) disabled: Color,
+    pub(crate) outline: Color,
+    pub(crate) selected: Color,
+    pub(crate) idle: Color,
+    pub(crate) help: Color,
+    pub(crate) help_text: Color,
+    pub(crate) killswitch: Color,
+    pub(crate) killswitch_text: Color,
+    pub(crate) secure_chip: Color,
+    pub(crate) standard_chip: Color,
+    /// Keyboard focus outline.
+    pub(crate) focus: Color,
+    /// Multiplier for border widths.
+    pub(crate) stroke: f32,
+}
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color::from_rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
+}
+
+static NEON: Palette = Palette {
+    background: rgb(0x05, 0x08, 0x1f),
+    panel: Color::from_rgba(0.05, 0.08, 0.2, 0.7),
+    dialog: rgb(0x0b, 0x0e, 0x2a),
+    card: Color::from_rgba(0.08, 0.10, 0.20, 0.9),
+    entry: Color::from_rgba(0.03, 0.05, 0.18, 0.8),
+    input: Color::from_rgba(0.04, 0.07, 0.20, 0.9),
+    input_border: rgb(0x3a, 0x45, 0x7d),
+    text: rgb(0xe7, 0xff, 0xff),
+    heading: rgb(0xff, 0x51, 0xff),
+    subheading: rgb(0xff, 0x73, 0xff),
+    accent: rgb(0x24, 0xd0, 0xff),
+    accent_pressed: rgb(0x1a, 0xa0, 0xc8),
+    on_accent: rgb(0x05, 0x08, 0x1f),
+    info: rgb(0x67, 0xd6, 0xff),
+    success: rgb(0x8a, 0xff, 0x70),
+    warning: rgb(0xff, 0xc1, 0x29),
+    danger: rgb(0xff, 0x47, 0x80),
+    muted: rgb(0x55, 0x66, 0x88),
+    disabled: rgb(0x12, 0x15, 0x29),
+    outline: rgb(0x25, 0x28, 0x40),
+    selected: rgb(0x1a, 0x2b, 0x66),
+    idle: Color::from_rgba(0.07, 0.10, 0.24, 0.8),
+    help: rgb(0x12, 0x66, 0x4f),
+    help_text: rgb(0xe7, 0xff, 0xff),
+    killswitch: rgb(0x70, 0x13, 0x39),
+    killswitch_text: rgb(0xff, 0x73, 0xff),
+    secure_chip: Color::from_rgba(0.08, 0.20, 0.14, 0.9),
+    standard_chip: Color::from_rgba(0.20, 0.12, 0.24, 0.9),
+    focus: rgb(0xff, 0xe6, 0x4d),
+    stroke: 1.0,
+};
+
+static LIGHT: Palette = Palette {
+    background: rgb(0xf2, 0xf4, 0xfa),
+    panel: rgb(0xff, 0xff, 0xff),
+    dialog: rgb(0xff, 0xff, 0xff),
+    card: rgb(0xee, 0xf2, 0xfa),
+    entry: rgb(0xf7, 0xf9, 0xfd),
+    input: rgb(0xff, 0xff, 0xff),
+    input_border: rgb(0xa8, 0xb3, 0xcf),
+    text: rgb(0x1b, 0x20, 0x33),
+    heading: rgb(0x9c, 0x1f, 0x98),
+    subheading: rgb(0x7b, 0x2a, 0x9e),
+    accent: rgb(0x00, 0x6f, 0xb0),
+    accent_pressed: rgb(0x00, 0x55, 0x88),
+    on_accent: rgb(0xff, 0xff, 0xff),
+    info: rgb(0x1f, 0x5f, 0xa8),
+    success: rgb(0x2e, 0x7d, 0x32),
+    warning: rgb(0x94, 0x62, 0x00),
+    danger: rgb(0xc2, 0x18, 0x5b),
+    muted: rgb(0x7a, 0x83, 0x99),
+    disabled: rgb(0xe3, 0xe7, 0xf0),
+    outline: rgb(0xc9, 0xd0, 0xe0),
+    selected: rgb(0xdb, 0xe9, 0xff),
+    idle: rgb(0xf7, 0xf9, 0xfd),
+    help: rgb(0x1f, 0x7a, 0x5c),
+    help_text: rgb(0xff, 0xff, 0xff),
+    killswitch: rgb(0xb0, 0x1a, 0x4f),
+    killswitch_text: rgb(0xff, 0xff, 0xff),
+    secure_chip: rgb(0xe3, 0xf5, 0xe6),
+    standard_chip: rgb(0xfb, 0xef, 0xdb),
+    focus: rgb(0xd9, 0x4f, 0x00),
+    stroke: 1.0,
+};
+
+static HIGH_CONTRAST: Palette = Palette {
+    background: rgb(0x00, 0x00, 0x00),
+    panel: rgb(0x00, 0x00, 0x00),
+    dialog: rgb(0x00, 0x00, 0x00),
+    card: rgb(0x00, 0x00, 0x00),
+    entry: rgb(0x00, 0x00, 0x00),
+    input: rgb(0x00, 0x00, 0x00),
+    input_border: rgb(0xff, 0xff, 0xff),
+    text: rgb(0xff, 0xff, 0xff),
+    heading: rgb(0xff, 0xff, 0x00),
+    subheading: rgb(0xff, 0xff, 0xff),
+    accent: rgb(0x00, 0xff, 0xff),
+    accent_pressed: rgb(0x00, 0xbf, 0xbf),
+    on_accent: rgb(0x00, 0x00, 0x00),
+    info: rgb(0x00, 0xff, 0xff),
+    success: rgb(0x00, 0xff, 0x00),
+    warning: rgb(0xff, 0xff, 0x00),
+    danger: rgb(0xff, 0x40, 0x40),
+    muted: rgb(0xbf, 0xbf, 0xbf),
+    disabled: rgb(0x1a, 0x1a, 0x1a),
+    outline: rgb(0xff, 0xff, 0xff),
+    selected: rgb(0x00, 0x00, 0x80),
+    idle: rgb(0x00, 0x00, 0x00),
+    help: rgb(0x00, 0x4d, 0x00),
+    help_text: rgb(0xff, 0xff, 0xff),
+    killswitch: rgb(0x8b, 0x00, 0x00),
+    killswitch_text: rgb(0xff, 0xff, 0xff),
+    secure_chip: rgb(0x00, 0x00, 0x00),
+    standard_chip: rgb(0x00, 0x00, 0x00),
+    focus: rgb(0xff, 0xff, 0x00),
+    stroke: 2.0,
+};
+
+/// The palette a configured theme resolves to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Scheme {
+    Neon,
+    Light,
+    HighContrast,
+}
+
+impl Scheme {
+    /// `theme`, with `system` decided by the desktop preference (dark when
+    /// unknown).
+    pub(crate) fn resolve(theme: UiTheme, prefers_light: Option<bool>) -> Self {
+        match theme {
+            UiTheme::System if prefers_light == Some(true) => Self::Light,
+            UiTheme::System | UiTheme::Neon => Self::Neon,
+            UiTheme::Light => Self::Light,
+            UiTheme::HighContrast => Self::HighContrast,
+        }
+    }
+
+    /// The Iced theme behind widgets the deck does not style itself.
+    pub(crate) fn iced_theme(self) -> iced::Theme {
+        match self {
+            Self::Neon => iced::Theme::TokyoNight,
+            Self::Light => iced::Theme::Light,
+            Self::HighContrast => iced::Theme::custom(
+                "High contrast".into(),
+                iced::theme::Palette {
+                    background: HIGH_CONTRAST.background,
+                    text: HIGH_CONTRAST.text,
+                    primary: HIGH_CONTRAST.accent,
+                    success: HIGH_CONTRAST.success,
+                    danger: HIGH_CONTRAST.danger,
+                },
+            ),
+        }
+    }
+}
+
+/// The scheme [`palette`] reads; style closures have no access to the
+/// deck's state, so the choice lives here.
+static ACTIVE: AtomicU8 = AtomicU8::new(Scheme::Neon as u8);
+
+/// Switch every style over to `scheme`.
+pub(crate) fn activate(scheme: Scheme) {
+    ACTIVE.store(scheme as u8, Ordering::Relaxed);
+}
+
+/// The active palette.
+pub(crate) fn palette() -> &'static Palette {
+    match ACTIVE.load(Ordering::Relaxed) {
+        value if value == Scheme::Light as u8 => &LIGHT,
+        value if value == Scheme::HighContrast as u8 => &HIGH_CONTRAST,
+        _ => &NEON,
+    }
+}
+
+/// The desktop's colour-scheme preference from the settings portal:
+/// `Some(true)` for light, `Some(false)` for dark, `None` without one.
+pub(crate) async fn desktop_prefers_light() -> Option<bool> {
+    let connection = Connection::session().await.ok()?;
+    let reply = connection
+        .call_method(
+            Some("org.freedesktop.portal.Desktop"),
+            "/org/freedesktop/portal/desktop",
+            Some("org.freedesktop.portal.Settings"),
+            "Read",
+            &("org.freedesktop.appearance", "color-scheme"),
+        )
+        .await
+        .ok()?;
+    let mut value: Value = reply.body().deserialize::<OwnedValue>().ok()?.into();
+    // `Read` wraps the setting in a second variant.
+    while let Value::Value(inner) = value {
+        value = *inner;
+    }
+    match u32::try_from(&value).ok()? {
+        1 => Some(false),
+        2 => Some(true),
+        _ => None,
+    }
+}
+
+impl LockchainUi {
+    /// The scheme the current preferences resolve to.
+    pub(crate) fn scheme(&self) -> Scheme {
+        Scheme::resolve(self.appearance.theme, self.prefers_light)
+    }
+
+    /// Adopt `appearance` and repaint in its scheme and language.
+    pub(crate) fn set_appearance(&mut self, appearance: UiCfg) {
+        i18n::activate(Language::resolve(appearance.language.as_deref()));
+        self.appearance = appearance;
+        activate(self.scheme());
+    }
+}
@@ -0,0 +1,203 @@
+//! Tray mode: a StatusNotifierItem icon that brings the hidden deck back,
+//! periodic polling of key presence and keystatus, and desktop
+//! notifications for datasets that lock or fail to unlock.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::window;
+use iced::{Subscription, Task};
+use lockchain_core::provider::{DatasetKeyDescriptor, KeyState};
+use zbus::zvariant::Value;
+use zbus::{interface, Connection};
+
+use crate::{LockchainUi, Message};
+
+/// How often keystatus and the key token are re-checked in tray mode.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const WATCHER: &str = "org.kde.StatusNotifierWatcher";
+
+/// The tray icon's D-Bus object; clicks are forwarded to the deck.
+struct TrayItem {
+    clicks: mpsc::UnboundedSender<()>,
+}
+
+#[interface(name = "org.kde.StatusNotifierItem")]
+impl TrayItem {
+    fn activate(&self, _x: i32, _y: i32) {
+        let _ = self.clicks.unbounded_send(());
+    }
+
+    fn secondary_activate(&self, _x: i32, _y: i32) {
+        let _ = self.clicks.unbounded_send(());
+    }
+
+    #[zbus(property)]
+    fn category(&self) -> &str {
+        "SystemServices"
+    }
+
+    #[zbus(property)]
+    fn id(&self) -> &str {
+        "lockchain-ui"
+    }
+
+    #[zbus(property)]
+    fn title(&self) -> &str {
+        "LockChain Control Deck"
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> &str {
+        "Active"
+    }
+
+    #[zbus(property)]
+    fn icon_name(&self) -> &str {
+        "drive-removable-media"
+    }
+
+    #[zbus(property)]
+    fn item_is_menu(&self) -> bool {
+        false
+    }
+}
+
+/// Export the tray item on the session bus and register it with the
+/// desktop's StatusNotifierWatcher.
+async fn register(clicks: mpsc::UnboundedSender<()>) -> zbus::Result<Connection> {
+    let name = format!("org.kde.StatusNotifierItem-{}-1", std::process::id());
+    let connection = zbus::connection::Builder::session()?
+        .name(name.as_str())?
+        .serve_at(ITEM_PATH, TrayItem { clicks })?
+        .build()
+        .await?;
+    connection
+        .call_method(
+            Some(WATCHER),
+            "/StatusNotifierWatcher",
+            Some(WATCHER),
+            "RegisterStatusNotifierItem",
+            &name,
+        )
+        .await?;
+    Ok(connection)
+}
+
+/// Show a desktop notification through `org.freedesktop.Notifications`.
+async fn notify(summary: String, body: String) -> Result<(), String> {
+    let connection = Connection::session().await.map_err(|e| e.to_string())?;
+    connection
+        .call_method(
+            Some("org.freedesktop.Notifications"),
+            "/org/freedesktop/Notifications",
+            Some("org.freedesktop.Notifications"),
+            "Notify",
+            &(
+                "LockChain",
+                0u32,
+                "dialog-warning",
+                summary.as_str(),
+                body.as_str(),
+                Vec::<&str>::new(),
+                HashMap::<&str, Value>::new(),
+                -1i32,
+            ),
+        )
+        .await
+        .map(drop)
+        .map_err(|e| e.to_string())
+}
+
+/// Datasets whose key was loaded in `before` and is unloaded in `after`.
+pub(crate) fn newly_locked<'a>(
+    before: &[DatasetKeyDescriptor],
+    after: &'a [DatasetKeyDescriptor],
+) -> Vec<&'a str> {
+    after
+        .iter()
+        .filter(|entry| entry.state == KeyState::Unavailable)
+        .filter(|entry| {
+            before
+                .iter()
+                .any(|old| old.dataset == entry.dataset && old.state == KeyState::Available)
+        })
+        .map(|entry| entry.dataset.as_str())
+        .collect()
+}
+
+/// Tray icon clicks, or one `TrayUnavailable` when no tray can be shown.
+fn tray_icon() -> Subscription<Message> {
+    Subscription::run(|| {
+        iced::stream::channel(8, |mut output| async move {
+            let (clicks, mut received) = mpsc::unbounded();
+            match register(clicks).await {
+                Ok(_connection) => {
+                    while received.next().await.is_some() {
+                        if output.send(Message::TrayActivated).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+                Err(err) => {
+                    let _ = output.send(Message::TrayUnavailable(err.to_string())).await;
+                }
+            }
+        })
+    })
+}
+
+impl LockchainUi {
+    /// Icon, poll timer, and close-to-tray handling while tray mode is on.
+    pub(crate) fn tray_subscription(&self) -> Subscription<Message> {
+        let close = window::close_requests().map(Message::CloseRequested);
+        if !self.tray_mode {
+            return close;
+        }
+        Subscription::batch([
+            close,
+            tray_icon(),
+            iced::time::every(POLL_INTERVAL).map(|_| Message::Poll),
+        ])
+    }
+
+    /// Hide the window to the tray when tray mode is on, otherwise quit.
+    pub(crate) fn close_requested(&self, id: window::Id) -> Task<Message> {
+        if self.tray_mode && self.tray_error.is_none() {
+            window::change_mode(id, window::Mode::Hidden)
+        } else {
+            iced::exit()
+        }
+    }
+
+    /// Bring the hidden deck back in front.
+    pub(crate) fn restore_window(&self) -> Task<Message> {
+        window::get_latest().and_then(|id| {
+            Task::batch([
+                window::change_mode(id, window::Mode::Windowed),
+                window::gain_focus(id),
+            ])
+        })
+    }
+
+    /// Raise a desktop notification when tray mode is on; failures only
+    /// reach the log.
+    pub(crate) fn notify(
+        &self,
+        summary: impl Into<String>,
+        body: impl Into<String>,
+    ) -> Task<Message> {
+        if !self.tray_mode {
+            return Task::none();
+        }
+        Task::future(notify(summary.into(), body.into())).then(|result| {
+            if let Err(err) = result {
+                log::warn!("desktop notification failed: {err}");
+            }
+            Task::none()
+        })
+    }
+}
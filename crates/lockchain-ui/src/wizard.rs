@@ -0,0 +1,491 @@
+//! First-run setup: when no config exists yet, walk through datasets, the
+//! USB token, and the fallback policy, then write a starting config.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use iced::alignment::Vertical;
+use iced::widget::{
+    button, checkbox, column, container, row, scrollable, text, text_input, toggler, Space,
+};
+use iced::{Length, Task};
+use lockchain_core::config::{IssueSeverity, LockchainConfig};
+use lockchain_core::ZfsProvider;
+use lockchain_zfs::SystemZfsProvider;
+
+use crate::i18n::t;
+use crate::passphrase::PassphrasePrompt;
+use crate::theme::palette;
+use crate::{
+    help_button, panel_style, primary_button, text_color, text_input_style, ActivityLevel,
+    Directive, LockchainUi, Message,
+};
+
+/// Label the forge writes onto freshly formatted tokens.
+const DEFAULT_USB_LABEL: &str = "LOCKCHAINKEY";
+
+/// Pages of the wizard, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WizardStep {
+    Datasets,
+    Token,
+    Fallback,
+    Review,
+}
+
+impl WizardStep {
+    const ALL: [WizardStep; 4] = [
+        WizardStep::Datasets,
+        WizardStep::Token,
+        WizardStep::Fallback,
+        WizardStep::Review,
+    ];
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|step| *step == self).unwrap_or(0)
+    }
+
+    fn title(self) -> String {
+        match self {
+            WizardStep::Datasets => t!("wizard-step-datasets"),
+            WizardStep::Token => t!("wizard-step-token"),
+            WizardStep::Fallback => t!("wizard-step-fallback"),
+            WizardStep::Review => t!("wizard-step-review"),
+        }
+    }
+}
+
+/// Input coming from the setup wizard.
+#[derive(Debug, Clone)]
+pub(crate) enum WizardMessage {
+    Discover,
+    Discovered(Result<Vec<String>, String>),
+    ToggleRoot(usize, bool),
+    ExtraDatasets(String),
+    Label(String),
+    SeedFallback(bool),
+    Askpass(bool),
+    Back,
+    Next,
+    Write,
+    Written(Result<(), String>),
+    Skip,
+}
+
+/// Answers collected so far.
+#[derive(Debug)]
+pub(crate) struct SetupWizard {
+    step: WizardStep,
+    /// Encryption roots reported by `zfs`, with whether each is managed.
+    roots: Vec<(String, bool)>,
+    discovering: bool,
+    discovery_error: Option<String>,
+    /// Comma-separated datasets typed in by hand.
+    extra: String,
+    label: String,
+    seed_fallback: bool,
+    askpass: bool,
+    writing: bool,
+}
+
+impl SetupWizard {
+    pub(crate) fn new() -> Self {
+        Self {
+            step: WizardStep::Datasets,
+            roots: Vec::new(),
+            discovering: false,
+            discovery_error: None,
+            extra: String::new(),
+            label: DEFAULT_USB_LABEL.to_string(),
+            seed_fallback: true,
+            askpass: true,
+            writing: false,
+        }
+    }
+
+    fn datasets(&self) -> Vec<String> {
+        let mut datasets: Vec<String> = self
+            .roots
+            .iter()
+            .filter(|(_, managed)| *managed)
+            .map(|(root, _)| root.clone())
+            .collect();
+        for dataset in self.extra.split(',').map(str::trim) {
+            if !dataset.is_empty() && !datasets.iter().any(|known| known == dataset) {
+                datasets.push(dataset.to_string());
+            }
+        }
+        datasets
+    }
+
+    /// The config these answers produce. Fallback material only exists once
+    /// a key is forged, so the fallback stays disabled until then.
+    fn build(&self, path: PathBuf) -> LockchainConfig {
+        let mut config = LockchainConfig::starter(path, self.datasets());
+        let label = self.label.trim();
+        config.usb.device_label = (!label.is_empty()).then(|| label.to_string());
+        config.fallback.enabled = false;
+        config.fallback.askpass = self.askpass;
+        config
+    }
+
+    fn can_advance(&self) -> bool {
+        match self.step {
+            WizardStep::Datasets => !self.datasets().is_empty(),
+            _ => true,
+        }
+    }
+}
+
+/// Encryption roots visible to the local `zfs`.
+async fn discover() -> Result<Vec<String>, String> {
+    SystemZfsProvider::discover(Duration::from_secs(10))
+        .and_then(|provider| provider.encryption_roots())
+        .map_err(|e| e.to_string())
+}
+
+/// Save `config` to its path, refusing to replace a file that appeared
+/// meanwhile.
+async fn write(config: LockchainConfig) -> Result<(), String> {
+    if config.path.exists() {
+        return Err(t!(
+            "wizard-config-exists",
+            path = config.path.display().to_string()
+        ));
+    }
+    if let Some(parent) = config
+        .path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent).map_err(|err| {
+            t!(
+                "wizard-create-failed",
+                path = parent.display().to_string(),
+                error = err.to_string()
+            )
+        })?;
+    }
+    config.save().map_err(|e| e.to_string())
+}
+
+impl LockchainUi {
+    /// Open the wizard and start looking for encryption roots.
+    pub(crate) fn open_wizard(&mut self) -> Task<Message> {
+        self.wizard = Some(SetupWizard::new());
+        self.update_wizard(WizardMessage::Discover)
+    }
+
+    /// Handle a setup-wizard message.
+    pub(crate) fn update_wizard(&mut self, message: WizardMessage) -> Task<Message> {
+        let Some(wizard) = &mut self.wizard else {
+            return Task::none();
+        };
+        match message {
+            WizardMessage::Discover => {
+                if wizard.discovering {
+                    return Task::none();
+                }
+                wizard.discovering = true;
+                return Task::perform(discover(), |result| {
+                    Message::Wizard(WizardMessage::Discovered(result))
+                });
+            }
+            WizardMessage::Discovered(result) => {
+                wizard.discovering = false;
+                match result {
+                    Ok(roots) => {
+                        // Keep earlier choices; newly seen roots start selected.
+                        let previous = std::mem::take(&mut wizard.roots);
+                        wizard.roots = roots
+                            .into_iter()
+                            .map(|root| {
+                                let managed = previous
+                                    .iter()
+                                    .find(|(known, _)| *known == root)
+                                    .is_none_or(|(_, managed)| *managed);
+                                (root, managed)
+                            })
+                            .collect();
+                        wizard.discovery_error = None;
+                    }
+                    Err(err) => wizard.discovery_error = Some(err),
+                }
+            }
+            WizardMessage::ToggleRoot(index, managed) => {
+                if let Some(entry) = wizard.roots.get_mut(index) {
+                    entry.1 = managed;
+                }
+            }
+            WizardMessage::ExtraDatasets(value) => wizard.extra = value,
+            WizardMessage::Label(value) => wizard.label = value,
+            WizardMessage::SeedFallback(state) => wizard.seed_fallback = state,
+            WizardMessage::Askpass(state) => wizard.askpass = state,
+            WizardMessage::Back => {
+                let index = wizard.step.index();
+                if index > 0 {
+                    wizard.step = WizardStep::ALL[index - 1];
+                }
+            }
+            WizardMessage::Next => {
+                let index = wizard.step.index();
+                if wizard.can_advance() && index + 1 < WizardStep::ALL.len() {
+                    wizard.step = WizardStep::ALL[index + 1];
+                }
+            }
+            WizardMessage::Write => {
+                let config = wizard.build(self.config_path.clone());
+                if wizard.writing || config.validate().iter().any(|issue| issue.is_error()) {
+                    return Task::none();
+                }
+                wizard.writing = true;
+                return Task::perform(write(config), |result| {
+                    Message::Wizard(WizardMessage::Written(result))
+                });
+            }
+            WizardMessage::Written(Ok(())) => {
+                let seed_fallback = wizard.seed_fallback;
+                self.wizard = None;
+                self.push_activity(
+                    ActivityLevel::Success,
+                    t!(
+                        "wizard-wrote",
+                        path = self.config_path.display().to_string()
+                    ),
+                );
+                self.active_directive = Directive::NewKey;
+                self.status_line = crate::directive_title(Directive::NewKey);
+                self.push_activity(ActivityLevel::Info, t!("wizard-next-steps"));
+                if seed_fallback {
+                    self.passphrase_prompt = Some(PassphrasePrompt::default());
+                }
+                return self.refresh_state();
+            }
+            WizardMessage::Written(Err(err)) => {
+                wizard.writing = false;
+                self.push_activity(ActivityLevel::Error, t!("wizard-write-failed", error = err));
+            }
+            WizardMessage::Skip => {
+                self.wizard = None;
+                self.push_activity(
+                    ActivityLevel::Warn,
+                    t!(
+                        "wizard-skipped",
+                        path = self.config_path.display().to_string()
+                    ),
+                );
+            }
+        }
+        Task::none()
+    }
+
+    /// The wizard, shown in place of the deck body.
+    pub(crate) fn view_wizard<'a>(&'a self, wizard: &'a SetupWizard) -> iced::Element<'a, Message> {
+        let step = wizard.step;
+        let heading = row![
+            text(t!("wizard-heading"))
+                .size(18)
+                .style(text_color(palette().heading)),
+            Space::with_width(Length::Fill),
+            text(t!(
+                "wizard-step",
+                current = step.index() + 1,
+                total = WizardStep::ALL.len(),
+                title = step.title()
+            ))
+            .size(14)
+            .style(text_color(palette().info))
+        ]
+        .align_y(Vertical::Center);
+
+        let page = match step {
+            WizardStep::Datasets => self.view_wizard_datasets(wizard),
+            WizardStep::Token => self.view_wizard_token(wizard),
+            WizardStep::Fallback => view_wizard_fallback(wizard),
+            WizardStep::Review => self.view_wizard_review(wizard),
+        };
+
+        let skip = button(text(t!("wizard-skip")))
+            .padding([10, 16])
+            .style(help_button())
+            .on_press(Message::Wizard(WizardMessage::Skip));
+        let mut back = button(text(t!("nav-back")))
+            .padding([10, 16])
+            .style(help_button());
+        if step != WizardStep::Datasets && !wizard.writing {
+            back = back.on_press(Message::Wizard(WizardMessage::Back));
+        }
+        let forward = if step == WizardStep::Review {
+            let errors = wizard
+                .build(self.config_path.clone())
+                .validate()
+                .iter()
+                .any(|issue| issue.is_error());
+            let mut save = button(text(if wizard.writing {
+                t!("wizard-writing")
+            } else {
+                t!("wizard-write")
+            }))
+            .padding([10, 18])
+            .style(primary_button());
+            if !errors && !wizard.writing {
+                save = save.on_press(Message::Wizard(WizardMessage::Write));
+            }
+            save
+        } else {
+            let mut next = button(text(t!("wizard-next")))
+                .padding([10, 18])
+                .style(primary_button());
+            if wizard.can_advance() {
+                next = next.on_press(Message::Wizard(WizardMessage::Next));
+            }
+            next
+        };
+
+        container(
+            column![
+                heading,
+                scrollable(page).height(Length::Fill),
+                row![skip, Space::with_width(Length::Fill), back, forward].spacing(12)
+            ]
+            .spacing(16),
+        )
+        .padding(20)
+        .width(Length::Fill)
+        .style(panel_style())
+        .into()
+    }
+
+    fn view_wizard_datasets<'a>(&'a self, wizard: &'a SetupWizard) -> iced::Element<'a, Message> {
+        let mut body = column![note(t!(
+            "wizard-datasets-intro",
+            path = self.config_path.display().to_string()
+        ))]
+        .spacing(10);
+
+        if wizard.discovering {
+            body = body.push(note(t!("wizard-discovering")));
+        } else if let Some(err) = &wizard.discovery_error {
+            body = body.push(warning(t!("wizard-discovery-failed", error = err.as_str())));
+        } else if wizard.roots.is_empty() {
+            body = body.push(warning(t!("wizard-no-roots")));
+        }
+        for (index, (root, managed)) in wizard.roots.iter().enumerate() {
+            body = body.push(
+                checkbox(root.as_str(), *managed)
+                    .on_toggle(move |state| {
+                        Message::Wizard(WizardMessage::ToggleRoot(index, state))
+                    })
+                    .text_size(14),
+            );
+        }
+
+        let mut rescan = button(text(t!("devices-rescan")))
+            .padding([8, 14])
+            .style(help_button());
+        if !wizard.discovering {
+            rescan = rescan.on_press(Message::Wizard(WizardMessage::Discover));
+        }
+        body.push(
+            row![
+                text_input(&t!("wizard-extra-datasets"), &wizard.extra)
+                    .on_input(|value| Message::Wizard(WizardMessage::ExtraDatasets(value)))
+                    .size(14)
+                    .padding(8)
+                    .style(text_input_style()),
+                rescan
+            ]
+            .spacing(8)
+            .align_y(Vertical::Center),
+        )
+        .into()
+    }
+
+    fn view_wizard_token<'a>(&'a self, wizard: &'a SetupWizard) -> iced::Element<'a, Message> {
+        column![
+            note(t!("wizard-token-intro")),
+            self.view_device_picker(),
+            text(t!("wizard-token-label"))
+                .size(14)
+                .style(text_color(palette().success)),
+            text_input(DEFAULT_USB_LABEL, &wizard.label)
+                .on_input(|value| Message::Wizard(WizardMessage::Label(value)))
+                .size(14)
+                .padding(8)
+                .style(text_input_style()),
+            note(t!("wizard-token-label-note", label = DEFAULT_USB_LABEL)),
+        ]
+        .spacing(10)
+        .into()
+    }
+
+    fn view_wizard_review<'a>(&'a self, wizard: &'a SetupWizard) -> iced::Element<'a, Message> {
+        let config = wizard.build(self.config_path.clone());
+        let token = self
+            .selected_device
+            .as_ref()
+            .map(|device| device.path.clone())
+            .unwrap_or_else(|| t!("wizard-review-no-token"));
+        let mut body = column![
+            note(t!(
+                "wizard-review-config",
+                path = self.config_path.display().to_string()
+            )),
+            note(t!(
+                "wizard-review-datasets",
+                datasets = config.policy.datasets.join(", ")
+            )),
+            note(t!(
+                "wizard-review-label",
+                label = config
+                    .usb
+                    .device_label
+                    .clone()
+                    .unwrap_or_else(|| t!("any-value"))
+            )),
+            note(t!("wizard-review-token", token = token)),
+            note(if wizard.seed_fallback {
+                t!("wizard-review-fallback-seeded")
+            } else {
+                t!("wizard-review-fallback-disabled")
+            }),
+        ]
+        .spacing(8);
+
+        for issue in config.validate() {
+            let color = match issue.severity {
+                IssueSeverity::Error => palette().danger,
+                IssueSeverity::Warning => palette().warning,
+            };
+            body = body.push(text(issue.to_string()).size(13).style(text_color(color)));
+        }
+        body.into()
+    }
+}
+
+fn view_wizard_fallback(wizard: &SetupWizard) -> iced::Element<'_, Message> {
+    column![
+        note(t!("wizard-fallback-intro")),
+        toggler(wizard.seed_fallback)
+            .label(t!("wizard-seed-fallback"))
+            .size(20)
+            .text_size(14)
+            .on_toggle(|state| Message::Wizard(WizardMessage::SeedFallback(state))),
+        toggler(wizard.askpass)
+            .label(t!("wizard-askpass"))
+            .size(20)
+            .text_size(14)
+            .on_toggle(|state| Message::Wizard(WizardMessage::Askpass(state))),
+    ]
+    .spacing(12)
+    .into()
+}
+
+fn note<'a>(line: impl text::IntoFragment<'a>) -> iced::widget::Text<'a> {
+    text(line).size(14).style(text_color(palette().text))
+}
+
+fn warning<'a>(line: impl text::IntoFragment<'a>) -> iced::widget::Text<'a> {
+    text(line).size(14).style(text_color(palette().warning))
+}
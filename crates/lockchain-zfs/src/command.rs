@@ -14,6 +14,8 @@ use std::time::{Duration, Instant};
 pub struct CommandRunner {
     path: PathBuf,
     timeout: Duration,
+    /// `user@host` to run the binary on through `ssh`; local when `None`.
+    remote: Option<String>,
 }
 
 #[derive(Debug)]
@@ -27,7 +29,28 @@ pub struct Output {
 impl CommandRunner {
     /// Build a new runner targeting the supplied binary and timeout.
     pub fn new(path: PathBuf, timeout: Duration) -> Self {
-        Self { path, timeout }
+        Self {
+            path,
+            timeout,
+            remote: None,
+        }
+    }
+
+    /// Build a runner that executes `path` on `destination` (`user@host`) over `ssh`.
+    ///
+    /// `ssh` runs in batch mode, so keys or an agent must already grant access;
+    /// stdin payloads such as key material travel inside the SSH channel.
+    pub fn over_ssh(destination: &str, path: PathBuf, timeout: Duration) -> LockchainResult<Self> {
+        if destination.is_empty() || destination.starts_with('-') || destination.contains(' ') {
+            return Err(LockchainError::InvalidConfig(format!(
+                "`{destination}` is not an ssh destination such as root@host"
+            )));
+        }
+        Ok(Self {
+            path,
+            timeout,
+            remote: Some(destination.to_string()),
+        })
     }
 
     /// Return the binary path this runner will execute.
@@ -37,8 +60,23 @@ impl CommandRunner {
 
     /// Execute the binary with arguments, optional stdin payload, and capture the result.
     pub fn run(&self, args: &[&str], input: Option<&[u8]>) -> LockchainResult<Output> {
-        let mut command = Command::new(&self.path);
-        command.args(args);
+        let mut command = match &self.remote {
+            Some(destination) => {
+                let remote_command: Vec<String> = std::iter::once(self.path.to_string_lossy())
+                    .chain(args.iter().map(|arg| (*arg).into()))
+                    .map(|word| shell_quote(&word))
+                    .collect();
+                let mut ssh = Command::new("ssh");
+                ssh.args(["-T", "-o", "BatchMode=yes", destination, "--"]);
+                ssh.arg(remote_command.join(" "));
+                ssh
+            }
+            None => {
+                let mut local = Command::new(&self.path);
+                local.args(args);
+                local
+            }
+        };
         command.stdout(Stdio::piped());
         command.stderr(Stdio::piped());
 
@@ -46,7 +84,12 @@ impl CommandRunner {
             command.stdin(Stdio::piped());
         }
 
-        let mut child = command.spawn()?;
+        let mut child = command.spawn().map_err(|err| match &self.remote {
+            Some(destination) => {
+                LockchainError::Provider(format!("cannot start ssh to {destination}: {err}"))
+            }
+            None => err.into(),
+        })?;
 
         if let Some(bytes) = input {
             if let Some(mut stdin) = child.stdin.take() {
@@ -84,8 +127,12 @@ impl CommandRunner {
             let _ = child.kill();
             let _ = child.wait();
             return Err(LockchainError::Provider(format!(
-                "{} timed out after {:?}",
+                "{}{} timed out after {:?}",
                 self.path.display(),
+                self.remote
+                    .as_deref()
+                    .map(|destination| format!(" on {destination}"))
+                    .unwrap_or_default(),
                 self.timeout
             )));
         }
@@ -122,3 +169,29 @@ impl CommandRunner {
         })
     }
 }
+
+/// Quote `word` for the remote POSIX shell that `ssh` hands the command to.
+fn shell_quote(word: &str) -> String {
+    if !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/._-=,:@%+".contains(c))
+    {
+        return word.to_string();
+    }
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_words_are_quoted_for_the_shell() {
+        assert_eq!(shell_quote("tank/secure"), "tank/secure");
+        assert_eq!(shell_quote("name,value"), "name,value");
+        assert_eq!(shell_quote("it's; rm -rf /"), "'it'\\''s; rm -rf /'");
+        assert_eq!(shell_quote(""), "''");
+        assert!(CommandRunner::over_ssh("-oProxyCommand=x", "zfs".into(), Duration::ZERO).is_err());
+    }
+}
@@ -3,10 +3,7 @@
 
 /// Turn `-H -o name,value` style command output into name/value pairs.
 pub(crate) fn parse_tabular_pairs(output: &str) -> Vec<(String, String)> {
-    output
-        .lines()
-        .filter_map(|line| parse_pair_line(line))
-        .collect()
+    output.lines().filter_map(parse_pair_line).collect()
 }
 
 /// Normalize a single line from the CLI into a `(name, value)` pair if possible.
@@ -7,7 +7,7 @@ use crate::parse::{parse_tabular_pairs, pool_from_dataset};
 use lockchain_core::config::LockchainConfig;
 use lockchain_core::error::{LockchainError, LockchainResult};
 use lockchain_core::provider::{DatasetKeyDescriptor, KeyState, KeyStatusSnapshot, ZfsProvider};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
@@ -27,6 +27,9 @@ pub const DEFAULT_ZPOOL_PATHS: &[&str] = &[
     "/bin/zpool",
 ];
 
+/// Columns that say whether an unlocked dataset is actually reachable.
+const ACCESS_COLUMNS: &str = "mounted,keyformat,keylocation";
+
 /// System-oriented `ZfsProvider` that shells out to the native `zfs` and `zpool` CLIs.
 #[derive(Clone)]
 pub struct SystemZfsProvider {
@@ -56,6 +59,24 @@ impl SystemZfsProvider {
         })
     }
 
+    /// Build a provider that runs `zfs` and `zpool` on `destination` (`user@host`) over SSH.
+    ///
+    /// Binary paths come from the config when set and otherwise default to the
+    /// first entries of the search lists; they are not checked locally.
+    pub fn remote(config: &LockchainConfig, destination: &str) -> LockchainResult<Self> {
+        let timeout = config.zfs_timeout();
+        let zfs_path = config
+            .zfs_binary_path()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_ZFS_PATHS[0]));
+        let zpool_path = config
+            .zpool_binary_path()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_ZPOOL_PATHS[0]));
+        Ok(Self {
+            zfs_runner: CommandRunner::over_ssh(destination, zfs_path, timeout)?,
+            zpool_runner: CommandRunner::over_ssh(destination, zpool_path, timeout)?,
+        })
+    }
+
     /// Construct a provider with an explicit `zfs` path and an auto-discovered `zpool`.
     pub fn with_path(path: PathBuf, timeout: Duration) -> LockchainResult<Self> {
         let zfs_runner = Self::runner_with_path(path, timeout)?;
@@ -209,29 +230,13 @@ impl SystemZfsProvider {
 
     /// Confirm the pool exists and reports a healthy status.
     fn ensure_pool_ready(&self, pool: &str) -> LockchainResult<()> {
-        let args = ["list", "-H", "-o", "name,health", pool];
-        let out = self.run_checked_zpool(&args)?;
-
-        let mut seen = false;
-        for (name, health) in parse_tabular_pairs(&out.stdout) {
-            if name == pool {
-                seen = true;
-                if !health.eq_ignore_ascii_case("online") {
-                    return Err(LockchainError::Provider(format!(
-                        "pool {} is not healthy (reported state: {})",
-                        pool, health
-                    )));
-                }
-            }
-        }
-
-        if !seen {
+        let health = self.pool_health(pool)?;
+        if !health.eq_ignore_ascii_case("online") {
             return Err(LockchainError::Provider(format!(
-                "pool {} not reported by zpool list output",
-                pool
+                "pool {} is not healthy (reported state: {})",
+                pool, health
             )));
         }
-
         Ok(())
     }
 
@@ -274,12 +279,43 @@ impl SystemZfsProvider {
         Ok(())
     }
 
-    /// Ask `zfs` for the dataset's `keystatus` and translate it to `KeyState`.
+    /// Unmount `dataset`, treating "not currently mounted" as success.
+    fn unmount(&self, dataset: &str) -> LockchainResult<()> {
+        let args = ["unmount", dataset];
+        let out = self.run_zfs(&args, None)?;
+        if out.status != 0 {
+            let diagnostic = format!("{} {}", out.stderr, out.stdout).to_ascii_lowercase();
+            if diagnostic.contains("not currently mounted") || diagnostic.contains("not mounted") {
+                return Ok(());
+            }
+            return Err(Self::classify_cli_error(
+                self.zfs_runner.binary(),
+                &args,
+                &out,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Ask `zfs` for one dataset's encryption root, keystatus, and access columns.
     ///
     /// `parse_keystatus` stays separate so tests can validate the string mapping in isolation.
-    fn keystatus(&self, dataset: &str) -> LockchainResult<KeyState> {
-        let out = self.run_checked_zfs(&["get", "-H", "-o", "value", "keystatus", dataset])?;
-        Ok(Self::parse_keystatus(out.stdout.trim()))
+    fn describe_dataset(&self, dataset: &str) -> LockchainResult<DatasetKeyDescriptor> {
+        let columns = format!("encryptionroot,keystatus,{ACCESS_COLUMNS}");
+        let out = self.run_checked_zfs(&["list", "-H", "-o", &columns, dataset])?;
+        let line = out.stdout.lines().next().unwrap_or_default();
+        let mut fields = line.split('\t').map(str::trim);
+        let encryption_root = fields.next().unwrap_or_default().to_string();
+        let state = Self::parse_keystatus(fields.next().unwrap_or_default());
+        let (mounted, keyformat, keylocation) = Self::parse_access_columns(fields);
+        Ok(DatasetKeyDescriptor {
+            dataset: dataset.to_string(),
+            encryption_root,
+            state,
+            mounted,
+            keyformat,
+            keylocation,
+        })
     }
 
     /// Translate the raw `keystatus` field into Lockchain's enum.
@@ -293,6 +329,23 @@ impl SystemZfsProvider {
             other => KeyState::Unknown(other.to_string()),
         }
     }
+
+    /// Read the `mounted`, `keyformat`, and `keylocation` columns; `-` means
+    /// the property does not apply (volumes are never mounted).
+    fn parse_access_columns<'a>(
+        mut fields: impl Iterator<Item = &'a str>,
+    ) -> (Option<bool>, Option<String>, Option<String>) {
+        let mut next = || {
+            fields
+                .next()
+                .map(str::trim)
+                .filter(|value| !matches!(*value, "" | "-"))
+        };
+        let mounted = next().map(|value| value == "yes");
+        let keyformat = next().map(str::to_string);
+        let keylocation = next().map(str::to_string);
+        (mounted, keyformat, keylocation)
+    }
 }
 
 impl ZfsProvider for SystemZfsProvider {
@@ -303,29 +356,50 @@ impl ZfsProvider for SystemZfsProvider {
 
     /// List every descendant under `root` that still reports a locked key.
     fn locked_descendants(&self, root: &str) -> LockchainResult<Vec<String>> {
+        Ok(self
+            .key_tree_status(root)?
+            .into_iter()
+            .filter(|entry| !matches!(entry.state, KeyState::Available))
+            .map(|entry| entry.dataset)
+            .collect())
+    }
+
+    /// Keystatus and access columns for the whole tree under `root` from two
+    /// recursive `zfs` calls.
+    fn key_tree_status(&self, root: &str) -> LockchainResult<KeyStatusSnapshot> {
         self.ensure_dataset_pool_ready(root)?;
 
-        let list_output =
-            self.run_checked_zfs(&["list", "-H", "-r", "-o", "name,encryptionroot", root])?;
-        let same_root: HashSet<String> = parse_tabular_pairs(&list_output.stdout)
+        let columns = format!("name,encryptionroot,{ACCESS_COLUMNS}");
+        let list_output = self.run_checked_zfs(&["list", "-H", "-r", "-o", &columns, root])?;
+        let mut same_root: HashMap<String, String> = parse_tabular_pairs(&list_output.stdout)
             .into_iter()
-            .filter(|(_, enc_root)| enc_root == root)
-            .map(|(name, _)| name)
+            .filter_map(|(name, rest)| {
+                let (enc_root, access) = rest.split_once('\t').unwrap_or((&rest, ""));
+                (enc_root.trim() == root).then(|| (name, access.to_string()))
+            })
             .collect();
 
         let status_output =
             self.run_checked_zfs(&["get", "-H", "-r", "-o", "name,value", "keystatus", root])?;
-        let mut locked = Vec::new();
-        for (name, value) in parse_tabular_pairs(&status_output.stdout) {
-            if same_root.contains(&name) {
-                let state = Self::parse_keystatus(value.trim());
-                if !matches!(state, KeyState::Available) {
-                    locked.push(name);
-                }
-            }
-        }
-        locked.sort_unstable();
-        Ok(locked)
+        let mut snapshot: KeyStatusSnapshot = parse_tabular_pairs(&status_output.stdout)
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let access = same_root.remove(&name)?;
+                let (mounted, keyformat, keylocation) =
+                    Self::parse_access_columns(access.split('\t'));
+                Some(DatasetKeyDescriptor {
+                    dataset: name,
+                    encryption_root: root.to_string(),
+                    state: Self::parse_keystatus(value.trim()),
+                    mounted,
+                    keyformat,
+                    keylocation,
+                })
+            })
+            .collect();
+        // Descendant names extend the root's, so a plain sort keeps the root first.
+        snapshot.sort_unstable_by(|a, b| a.dataset.cmp(&b.dataset));
+        Ok(snapshot)
     }
 
     /// Load the key at `root`, retry locked descendants, and surface any stragglers.
@@ -363,6 +437,131 @@ impl ZfsProvider for SystemZfsProvider {
         Ok(unlocked)
     }
 
+    /// Unmount (optionally) and unload the key shared by `root` and its descendants.
+    fn unload_key_tree(&self, root: &str, unmount: bool) -> LockchainResult<Vec<String>> {
+        self.ensure_dataset_pool_ready(root)?;
+
+        let list_output =
+            self.run_checked_zfs(&["list", "-H", "-r", "-o", "name,encryptionroot", root])?;
+        let locked_before: HashSet<String> = self.locked_descendants(root)?.into_iter().collect();
+        let mut unlocked: Vec<String> = parse_tabular_pairs(&list_output.stdout)
+            .into_iter()
+            .filter(|(name, enc_root)| enc_root == root && !locked_before.contains(name))
+            .map(|(name, _)| name)
+            .collect();
+        unlocked.sort_unstable();
+
+        if unmount {
+            // Children sort after their parents, so walk backwards to unmount leaves first.
+            for ds in unlocked.iter().rev() {
+                self.unmount(ds)?;
+            }
+        }
+
+        self.run_checked_zfs(&["unload-key", root])?;
+
+        let locked_after: HashSet<String> = self.locked_descendants(root)?.into_iter().collect();
+        let stubborn: Vec<&str> = unlocked
+            .iter()
+            .filter(|ds| !locked_after.contains(*ds))
+            .map(String::as_str)
+            .collect();
+        if !stubborn.is_empty() {
+            return Err(LockchainError::Provider(format!(
+                "datasets still unlocked after unload-key: {}",
+                stubborn.join(", ")
+            )));
+        }
+
+        Ok(unlocked)
+    }
+
+    /// `zfs mount` one filesystem, treating "already mounted" as success.
+    fn mount_dataset(&self, dataset: &str) -> LockchainResult<()> {
+        let args = ["mount", dataset];
+        let out = self.run_zfs(&args, None)?;
+        if out.status != 0 {
+            let diagnostic = format!("{} {}", out.stderr, out.stdout).to_ascii_lowercase();
+            if diagnostic.contains("already mounted") {
+                return Ok(());
+            }
+            return Err(Self::classify_cli_error(
+                self.zfs_runner.binary(),
+                &args,
+                &out,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Dry-run `zfs load-key -n` so the key is checked but never loaded.
+    fn check_key(&self, root: &str, key: &[u8]) -> LockchainResult<()> {
+        self.ensure_dataset_pool_ready(root)?;
+        let args = ["load-key", "-n", "-L", "prompt", root];
+        let out = self.run_zfs(&args, Some(key))?;
+        if out.status != 0 {
+            return Err(Self::classify_cli_error(
+                self.zfs_runner.binary(),
+                &args,
+                &out,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Re-wrap `root` under `key`, restoring its `keylocation` afterwards.
+    fn change_key(&self, root: &str, key: &[u8]) -> LockchainResult<()> {
+        self.ensure_dataset_pool_ready(root)?;
+
+        // The new key is fed on stdin, which requires keylocation=prompt for the call.
+        let location = self.get_property(root, "keylocation")?;
+        let args = [
+            "change-key",
+            "-o",
+            "keyformat=raw",
+            "-o",
+            "keylocation=prompt",
+            root,
+        ];
+        let out = self.run_zfs(&args, Some(key))?;
+        if out.status != 0 {
+            return Err(Self::classify_cli_error(
+                self.zfs_runner.binary(),
+                &args,
+                &out,
+            ));
+        }
+        if !matches!(location.as_str(), "" | "prompt" | "none" | "-") {
+            self.run_checked_zfs(&["set", &format!("keylocation={location}"), root])?;
+        }
+        Ok(())
+    }
+
+    /// List datasets that are their own encryption root across all imported pools.
+    fn encryption_roots(&self) -> LockchainResult<Vec<String>> {
+        let out = self.run_checked_zfs(&["list", "-H", "-o", "name,encryptionroot"])?;
+        let mut roots: Vec<String> = parse_tabular_pairs(&out.stdout)
+            .into_iter()
+            .filter(|(name, enc_root)| name == enc_root)
+            .map(|(name, _)| name)
+            .collect();
+        roots.sort_unstable();
+        Ok(roots)
+    }
+
+    /// Ask `zpool list` for the pool's `health` column.
+    fn pool_health(&self, pool: &str) -> LockchainResult<String> {
+        let args = ["list", "-H", "-o", "name,health", pool];
+        let out = self.run_checked_zpool(&args)?;
+        parse_tabular_pairs(&out.stdout)
+            .into_iter()
+            .find(|(name, _)| name == pool)
+            .map(|(_, health)| health)
+            .ok_or_else(|| {
+                LockchainError::Provider(format!("pool {} not reported by zpool list output", pool))
+            })
+    }
+
     /// Describe the current key status for each dataset listed by the caller.
     fn describe_datasets(&self, datasets: &[String]) -> LockchainResult<KeyStatusSnapshot> {
         let mut snapshot = Vec::with_capacity(datasets.len());
@@ -380,13 +579,7 @@ impl ZfsProvider for SystemZfsProvider {
                 self.ensure_pool_ready(pool)?;
             }
 
-            let encryption_root = self.encryption_root(ds)?;
-            let state = self.keystatus(ds)?;
-            snapshot.push(DatasetKeyDescriptor {
-                dataset: ds.clone(),
-                encryption_root,
-                state,
-            });
+            snapshot.push(self.describe_dataset(ds)?);
         }
         Ok(snapshot)
     }
@@ -467,6 +660,29 @@ if args[0] == "list" and len(args) >= 6 and args[1] == "-H" and args[2] == "-r"
     print("tank/secure/home\ttank/secure")
     sys.exit(0)
 
+def access(name):
+    mounted = "yes" if state.get(name) == "available" else "no"
+    return f"{mounted}\traw\t{state.get('keylocation:' + name, 'prompt')}"
+
+if args[0] == "list" and len(args) >= 6 and args[1] == "-H" and args[2] == "-r" and args[3] == "-o" and args[4] == "name,encryptionroot,mounted,keyformat,keylocation":
+    root = args[5]
+    ensure_dataset_known(root)
+    for name in ("tank/secure", "tank/secure/home"):
+        print(f"{name}\ttank/secure\t{access(name)}")
+    sys.exit(0)
+
+if args[0] == "list" and len(args) >= 5 and args[1] == "-H" and args[2] == "-o" and args[3] == "encryptionroot,keystatus,mounted,keyformat,keylocation":
+    dataset = args[4]
+    ensure_dataset_known(dataset)
+    print(f"tank/secure\t{state.get(dataset, 'unavailable')}\t{access(dataset)}")
+    sys.exit(0)
+
+if args == ["list", "-H", "-o", "name,encryptionroot"]:
+    print("tank\t-")
+    print("tank/secure\ttank/secure")
+    print("tank/secure/home\ttank/secure")
+    sys.exit(0)
+
 if args[0] == "get" and len(args) >= 7 and args[1] == "-H" and args[2] == "-r" and args[3] == "-o" and args[4] == "name,value" and args[5] == "keystatus":
     root = args[6]
     ensure_dataset_known(root)
@@ -487,6 +703,17 @@ if args[0] == "get" and len(args) >= 6 and args[1] == "-H" and args[2] == "-o" a
     print("tank/secure")
     sys.exit(0)
 
+if args[0] == "load-key" and len(args) >= 5 and args[1] == "-n":
+    dataset = args[4]
+    ensure_dataset_known(dataset)
+    if state.get(dataset) == "available":
+        print(f"Key load error: Key already loaded for '{dataset}'.", file=sys.stderr)
+        sys.exit(1)
+    if sys.stdin.buffer.read() != bytes(32):
+        print(f"Key load error: Incorrect key provided for '{dataset}'.", file=sys.stderr)
+        sys.exit(1)
+    sys.exit(0)
+
 if args[0] == "load-key" and len(args) >= 4:
     dataset = args[3]
     ensure_dataset_known(dataset)
@@ -494,6 +721,42 @@ if args[0] == "load-key" and len(args) >= 4:
     save()
     sys.exit(0)
 
+if args[0] == "get" and len(args) >= 6 and args[1] == "-H" and args[2] == "-o" and args[3] == "value" and args[4] == "keylocation":
+    dataset = args[5]
+    ensure_dataset_known(dataset)
+    print(state.get("keylocation:" + dataset, "prompt"))
+    sys.exit(0)
+
+if args[0] == "change-key" and len(args) >= 2:
+    root = args[-1]
+    ensure_dataset_known(root)
+    if state.get(root) != "available":
+        print(f"Key change error: Key must be loaded for '{root}'.", file=sys.stderr)
+        sys.exit(1)
+    state["key:" + root] = sys.stdin.buffer.read().hex()
+    state["keylocation:" + root] = "prompt"
+    save()
+    sys.exit(0)
+
+if args[0] == "set" and len(args) >= 3 and args[1].startswith("keylocation="):
+    ensure_dataset_known(args[2])
+    state["keylocation:" + args[2]] = args[1].split("=", 1)[1]
+    save()
+    sys.exit(0)
+
+if args[0] == "unmount" and len(args) >= 2:
+    ensure_dataset_known(args[1])
+    sys.exit(0)
+
+if args[0] == "unload-key" and len(args) >= 2:
+    root = args[1]
+    ensure_dataset_known(root)
+    for name in ("tank/secure", "tank/secure/home"):
+        if name == root or name.startswith(root + "/"):
+            state[name] = "unavailable"
+    save()
+    sys.exit(0)
+
 print("unexpected args: " + " ".join(args), file=sys.stderr)
 sys.exit(2)
 "#;
@@ -609,6 +872,88 @@ sys.exit(2)
             assert!(after.is_empty());
         }
 
+        #[test]
+        fn unload_key_tree_locks_datasets() {
+            let _guard = test_lock();
+            let fixture = ProviderFixture::new("ONLINE", AVAILABLE_STATE).unwrap();
+            let provider = fixture.provider();
+
+            let locked = provider.unload_key_tree("tank/secure", true).unwrap();
+            assert_eq!(
+                locked,
+                vec!["tank/secure".to_string(), "tank/secure/home".to_string()]
+            );
+            assert_eq!(provider.locked_descendants("tank/secure").unwrap().len(), 2);
+        }
+
+        #[test]
+        fn change_key_feeds_key_and_restores_keylocation() {
+            let _guard = test_lock();
+            let fixture = ProviderFixture::new(
+                "ONLINE",
+                r#"{"tank/secure":"available","keylocation:tank/secure":"file:///run/lockchain/key"}"#,
+            )
+            .unwrap();
+            fixture
+                .provider()
+                .change_key("tank/secure", &[0xab; 32])
+                .unwrap();
+
+            let state: serde_json::Value = serde_json::from_str(
+                &fs::read_to_string(env::var("FAKE_ZFS_STATE").unwrap()).unwrap(),
+            )
+            .unwrap();
+            assert_eq!(state["key:tank/secure"], "ab".repeat(32));
+            assert_eq!(
+                state["keylocation:tank/secure"],
+                "file:///run/lockchain/key"
+            );
+        }
+
+        #[test]
+        fn check_key_dry_runs_without_loading() {
+            let _guard = test_lock();
+            let fixture = ProviderFixture::new("ONLINE", DEFAULT_STATE).unwrap();
+            let provider = fixture.provider();
+
+            provider.check_key("tank/secure", &[0u8; 32]).unwrap();
+            assert!(provider.check_key("tank/secure", &[1u8; 32]).is_err());
+            assert_eq!(provider.locked_descendants("tank/secure").unwrap().len(), 2);
+        }
+
+        #[test]
+        fn key_tree_status_reports_whole_tree() {
+            let _guard = test_lock();
+            let fixture = ProviderFixture::new("ONLINE", DEFAULT_STATE).unwrap();
+            let tree = fixture.provider().key_tree_status("tank/secure").unwrap();
+            let names: Vec<&str> = tree.iter().map(|e| e.dataset.as_str()).collect();
+            assert_eq!(names, vec!["tank/secure", "tank/secure/home"]);
+            assert!(tree.iter().all(|e| e.encryption_root == "tank/secure"));
+            assert!(tree
+                .iter()
+                .all(|e| matches!(e.state, KeyState::Unavailable)));
+            assert!(tree.iter().all(|e| e.mounted == Some(false)));
+            assert_eq!(tree[0].keyformat.as_deref(), Some("raw"));
+        }
+
+        #[test]
+        fn encryption_roots_lists_only_roots() {
+            let _guard = test_lock();
+            let fixture = ProviderFixture::new("ONLINE", DEFAULT_STATE).unwrap();
+            assert_eq!(
+                fixture.provider().encryption_roots().unwrap(),
+                vec!["tank/secure".to_string()]
+            );
+        }
+
+        #[test]
+        fn pool_health_reports_zpool_state() {
+            let _guard = test_lock();
+            let fixture = ProviderFixture::new("DEGRADED", DEFAULT_STATE).unwrap();
+            assert_eq!(fixture.provider().pool_health("tank").unwrap(), "DEGRADED");
+            assert!(fixture.provider().pool_health("other").is_err());
+        }
+
         #[test]
         fn locked_descendants_missing_dataset_returns_invalid_config() {
             let _guard = test_lock();
@@ -647,12 +992,14 @@ sys.exit(2)
             let fixture = ProviderFixture::new("ONLINE", AVAILABLE_STATE).unwrap();
             let snapshot = fixture
                 .provider()
-                .describe_datasets(&vec!["tank/secure".to_string()])
+                .describe_datasets(&["tank/secure".to_string()])
                 .unwrap();
             assert_eq!(snapshot.len(), 1);
             assert_eq!(snapshot[0].dataset, "tank/secure");
             assert_eq!(snapshot[0].encryption_root, "tank/secure");
             assert!(matches!(snapshot[0].state, KeyState::Available));
+            assert_eq!(snapshot[0].mounted, Some(true));
+            assert_eq!(snapshot[0].keylocation.as_deref(), Some("prompt"));
         }
     }
 }
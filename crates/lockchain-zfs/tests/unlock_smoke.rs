@@ -1,15 +1,18 @@
 use lockchain_core::config::{
-    ConfigFormat, CryptoCfg, Fallback, LockchainConfig, Policy, RetryCfg, Usb,
+    AuditCfg, ConfigFormat, ControlCfg, CryptoCfg, DaemonCfg, Fallback, HealthCfg, HooksCfg,
+    LockchainConfig, Policy, RetryCfg, TuiCfg, UiCfg, Usb,
 };
 use lockchain_core::service::{LockchainService, UnlockOptions};
 use lockchain_core::LockchainResult;
 use lockchain_zfs::SystemZfsProvider;
 use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use tempfile::tempdir;
 
 const DEFAULT_STATE: &str = r#"{"tank/secure":"unavailable","tank/secure/home":"unavailable"}"#;
@@ -45,11 +48,12 @@ args = sys.argv[1:]
 if not args:
     sys.exit(2)
 
-if args[0] == "list" and len(args) >= 6 and args[1] == "-H" and args[2] == "-r" and args[3] == "-o" and args[4] == "name,encryptionroot":
+if args[0] == "list" and len(args) >= 6 and args[1] == "-H" and args[2] == "-r" and args[3] == "-o" and args[4].startswith("name,encryptionroot"):
     root = args[5]
     ensure_dataset_known(root)
-    print("tank/secure\ttank/secure")
-    print("tank/secure/home\ttank/secure")
+    extra = "\tno\traw\tprompt" if args[4] != "name,encryptionroot" else ""
+    print(f"tank/secure\ttank/secure{extra}")
+    print(f"tank/secure/home\ttank/secure{extra}")
     sys.exit(0)
 
 if args[0] == "get" and len(args) >= 7 and args[1] == "-H" and args[2] == "-r" and args[3] == "-o" and args[4] == "name,value" and args[5] == "keystatus":
@@ -133,8 +137,12 @@ fn unlock_smoke_unlocks_dev_pool() -> LockchainResult<()> {
             zpool_path: Some(zpool_path.to_string_lossy().into_owned()),
             binary_path: None,
             allow_root: false,
+            unlock_window: None,
+            require_strict_usb: false,
+        },
+        crypto: CryptoCfg {
+            timeout: Duration::from_secs(5),
         },
-        crypto: CryptoCfg { timeout_secs: 5 },
         usb: Usb {
             key_hex_path: key_path.to_string_lossy().into_owned(),
             expected_sha256: Some(expected_sha),
@@ -147,10 +155,30 @@ fn unlock_smoke_unlocks_dev_pool() -> LockchainResult<()> {
             passphrase_salt: None,
             passphrase_xor: None,
             passphrase_iters: 1,
+            sealed: false,
         },
         retry: RetryCfg::default(),
+        hooks: HooksCfg::default(),
+        audit: AuditCfg {
+            enabled: false,
+            ..AuditCfg::default()
+        },
+        control: ControlCfg::default(),
+        daemon: DaemonCfg::default(),
+        health: HealthCfg::default(),
+        webhooks: Vec::new(),
+        tui: TuiCfg::default(),
+        ui: UiCfg::default(),
+        vault: None,
+        remote: None,
+        remote_unlock: None,
         path: PathBuf::from("/etc/lockchain-zfs.toml"),
         format: ConfigFormat::Toml,
+        fragments: Vec::new(),
+        profiles: BTreeMap::new(),
+        profile: None,
+        load_issues: Vec::new(),
+        source: None,
     });
 
     let provider = SystemZfsProvider::from_config(&config)?;